@@ -24,12 +24,18 @@ Example usage:
     >>> import numpy as np
     >>> arr = np.array([[0, 100], [150, 200]], dtype=np.int64)
     >>> intervals = pygrit.from_numpy("chr1", arr)
+    >>>
+    >>> # Repeated lookups against a sorted file
+    >>> idx = pygrit.GritIndex.load("reads.sorted.bed")
+    >>> hits = idx.query("chr1", 1000, 2000)
 """
 
 from pygrit.pygrit import (
     # Core types
     Interval,
     IntervalSet,
+    GritIndex,
+    GlobalCoords,
     # File-based streaming functions
     intersect,
     merge,
@@ -44,6 +50,10 @@ from pygrit.pygrit import (
     jaccard,
     multiinter,
     generate,
+    # NumPy result arrays
+    intersect_counts,
+    intersect_pairs,
+    genomecov_arrays,
     # I/O utilities
     read_bed,
     parse_bed,
@@ -56,6 +66,8 @@ __all__ = [
     # Core types
     "Interval",
     "IntervalSet",
+    "GritIndex",
+    "GlobalCoords",
     # File-based streaming functions
     "intersect",
     "merge",
@@ -70,6 +82,10 @@ __all__ = [
     "jaccard",
     "multiinter",
     "generate",
+    # NumPy result arrays
+    "intersect_counts",
+    "intersect_pairs",
+    "genomecov_arrays",
     # I/O utilities
     "read_bed",
     "parse_bed",
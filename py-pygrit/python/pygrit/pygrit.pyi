@@ -1,6 +1,6 @@
 """Type stubs for pygrit - Python bindings for GRIT."""
 
-from typing import overload
+from typing import Literal, overload
 import numpy as np
 import numpy.typing as npt
 
@@ -107,6 +107,65 @@ class Interval:
         """Convert to a tuple (chrom, start, end)."""
         ...
 
+    def to_global(self, coords: "GlobalCoords") -> tuple[int, int] | None:
+        """Start and end in genome-wide coordinates.
+
+        Returns None if the chromosome is not in coords or the interval runs
+        past its end.
+        """
+        ...
+
+    @staticmethod
+    def from_global(start: int, end: int, coords: "GlobalCoords") -> "Interval | None":
+        """Interval at genome-wide coordinates [start, end).
+
+        Returns None if the range is outside the genome or crosses a
+        chromosome boundary.
+        """
+        ...
+
+
+class GlobalCoords:
+    """Genome-wide linear coordinates from a genome file.
+
+    Chromosomes are laid end to end in genome file order, so a single
+    integer locates any base.
+
+    Example:
+        >>> coords = GlobalCoords.from_genome("hg38.genome")
+        >>> coords.to_global("chr2", 100)
+        248956522
+        >>> coords.to_local(248956522)
+        ('chr2', 0)
+    """
+
+    @staticmethod
+    def from_genome(path: str) -> "GlobalCoords":
+        """Load chromosome sizes from a genome file (chrom<TAB>size)."""
+        ...
+
+    @property
+    def total_len(self) -> int:
+        """Length of the whole genome."""
+        ...
+
+    @property
+    def chroms(self) -> list[str]:
+        """Chromosomes in genome file order."""
+        ...
+
+    def offset(self, chrom: str) -> int | None:
+        """Genome-wide offset of a chromosome's first base."""
+        ...
+
+    def to_global(self, chrom: str, pos: int) -> int | None:
+        """Genome-wide position of chrom:pos, or None if outside the genome."""
+        ...
+
+    def to_local(self, pos: int) -> tuple[str, int] | None:
+        """(chrom, pos) of a genome-wide position, or None if past the genome."""
+        ...
+
 
 class IntervalSet:
     """A collection of genomic intervals with bulk operations.
@@ -155,6 +214,14 @@ class IntervalSet:
         """
         ...
 
+    @property
+    def is_sorted(self) -> bool:
+        """Whether intervals are sorted by chromosome and start position.
+
+        Sorted sets are intersected with a linear sweep instead of an index.
+        """
+        ...
+
     def add(self, interval: Interval) -> None:
         """Add an interval to the set.
 
@@ -211,6 +278,36 @@ class IntervalSet:
         """Sort intervals in place by chromosome and start position."""
         ...
 
+    def query(self, chrom: str, start: int, end: int | None = None) -> list[int]:
+        """Positions of the intervals overlapping a region.
+
+        The interval index is built on the first query and kept until the
+        set is modified.
+
+        Args:
+            chrom: Chromosome name.
+            start: Region start (0-based).
+            end: Region end (exclusive); defaults to start + 1.
+
+        Returns:
+            Indices into this set, ordered by interval start.
+        """
+        ...
+
+    def overlaps_with(
+        self, other: "IntervalSet"
+    ) -> tuple[npt.NDArray[np.int64], npt.NDArray[np.int64]]:
+        """Every overlapping pair between this set and another.
+
+        Args:
+            other: IntervalSet to compare against.
+
+        Returns:
+            Tuple (self_idx, other_idx) with one element per overlapping
+            pair, ordered by self_idx.
+        """
+        ...
+
     def to_numpy(self) -> npt.NDArray[np.int64]:
         """Convert to a NumPy array.
 
@@ -219,6 +316,79 @@ class IntervalSet:
         """
         ...
 
+    def to_numpy_full(
+        self,
+    ) -> tuple[npt.NDArray[np.int64], list[str], npt.NDArray[np.int64], npt.NDArray[np.int64]]:
+        """Convert to NumPy arrays, including chromosomes.
+
+        Returns:
+            Tuple (chrom_codes, categories, starts, ends). Codes index into
+            categories, which are in order of first appearance.
+        """
+        ...
+
+
+class GritIndex:
+    """A sorted BED file opened for repeated region queries.
+
+    The file is memory-mapped and queries binary-search their chromosome,
+    so lookups never re-read the file. Uses the `.gri` sidecar written by
+    `grit stab --build-index` when present.
+
+    Example:
+        >>> idx = GritIndex.load("reads.sorted.bed")
+        >>> idx.query("chr1", 1000, 2000)
+        [Interval(chr1:950-1050)]
+    """
+
+    @staticmethod
+    def load(path: str, build_index: bool = False) -> "GritIndex":
+        """Open a sorted BED file.
+
+        Args:
+            path: Path to a BED file sorted by chromosome, then start.
+            build_index: Rebuild the `.gri` sidecar and write it next to the file.
+
+        Raises:
+            ValueError: If the file is not sorted or its sidecar is stale.
+        """
+        ...
+
+    @property
+    def chroms(self) -> list[str]:
+        """Chromosomes in file order."""
+        ...
+
+    def __len__(self) -> int:
+        """Number of records in the file."""
+        ...
+
+    def query(self, chrom: str, start: int, end: int | None = None) -> list[Interval]:
+        """Records overlapping [start, end), in file order.
+
+        end defaults to start + 1 (a point lookup).
+        """
+        ...
+
+    def query_batch(
+        self,
+        chroms: str | list[str] | npt.NDArray[np.str_],
+        starts: npt.NDArray[np.int64],
+        ends: npt.NDArray[np.int64] | None = None,
+    ) -> tuple[npt.NDArray[np.int64], npt.NDArray[np.int64], npt.NDArray[np.int64]]:
+        """Run many queries in parallel.
+
+        Args:
+            chroms: One chromosome for all queries, or one per query.
+            starts: Query starts.
+            ends: Query ends; defaults to starts + 1.
+
+        Returns:
+            Tuple (query_idx, start, end) with one element per hit, ordered
+            by query.
+        """
+        ...
+
 
 # File-based streaming functions
 
@@ -864,6 +1034,91 @@ def generate(
     ...
 
 
+# NumPy result arrays
+
+def intersect_counts(a: str, b: str) -> npt.NDArray[np.int64]:
+    """Count B overlaps for every A record.
+
+    Inputs do not need to be sorted.
+
+    Args:
+        a: Path to file A.
+        b: Path to file B.
+
+    Returns:
+        Array of length len(A); element i is the overlap count of A record i.
+    """
+    ...
+
+
+@overload
+def intersect_pairs(
+    a: str, b: str, fraction: Literal[False] = False
+) -> tuple[npt.NDArray[np.int64], npt.NDArray[np.int64], npt.NDArray[np.int64]]:
+    ...
+
+
+@overload
+def intersect_pairs(
+    a: str, b: str, fraction: Literal[True]
+) -> tuple[
+    npt.NDArray[np.int64], npt.NDArray[np.int64], npt.NDArray[np.int64], npt.NDArray[np.float64]
+]:
+    ...
+
+
+def intersect_pairs(a: str, b: str, fraction: bool = False) -> tuple[npt.NDArray, ...]:
+    """Report every overlapping (A, B) pair with its overlap length.
+
+    Inputs do not need to be sorted. Pairs are ordered by A record.
+
+    Args:
+        a: Path to file A.
+        b: Path to file B.
+        fraction: Also return the fraction of each A record covered.
+
+    Returns:
+        Tuple (a_idx, b_idx, overlap_len) of 0-based record indices and
+        overlap lengths in base pairs, plus a float64 overlap_fraction
+        array when fraction=True.
+    """
+    ...
+
+
+@overload
+def genomecov_arrays(
+    input: str, genome: str, expand: Literal[False] = False
+) -> dict[str, tuple[npt.NDArray[np.int64], npt.NDArray[np.int64], npt.NDArray[np.int64]]]:
+    ...
+
+
+@overload
+def genomecov_arrays(
+    input: str, genome: str, expand: Literal[True]
+) -> dict[str, npt.NDArray[np.int64]]:
+    ...
+
+
+def genomecov_arrays(
+    input: str, genome: str, expand: bool = False
+) -> dict[str, object]:
+    """Compute genome-wide coverage as NumPy arrays.
+
+    Input does not need to be sorted.
+
+    Args:
+        input: Path to input BED file.
+        genome: Path to genome file.
+        expand: Return per-base depth arrays instead of runs.
+
+    Returns:
+        Dict keyed by chromosome in genome order. Values are
+        (starts, ends, depth) run arrays tiling the chromosome, or a
+        per-base depth array of length chrom_size when expand is True.
+    """
+    ...
+
+
 # I/O functions
 
 def read_bed(path: str) -> IntervalSet:
@@ -893,17 +1148,29 @@ def parse_bed(content: str) -> IntervalSet:
     ...
 
 
-def from_numpy(chrom: str, arr: npt.NDArray[np.int64]) -> IntervalSet:
-    """Create an IntervalSet from a NumPy array.
+def from_numpy(
+    chrom: str | list[str] | npt.NDArray[np.str_] | npt.NDArray[np.integer],
+    arr: npt.NDArray[np.int64] | None = None,
+    *,
+    starts: npt.NDArray[np.int64] | None = None,
+    ends: npt.NDArray[np.int64] | None = None,
+    categories: list[str] | None = None,
+) -> IntervalSet:
+    """Create an IntervalSet from NumPy arrays.
 
     Args:
-        chrom: Chromosome name for all intervals.
+        chrom: Chromosome name for all intervals, an array of n names, or
+            an array of n integer codes into categories.
         arr: Array with shape (n, 2) containing [start, end] pairs.
+        starts: Array of n starts, instead of arr.
+        ends: Array of n ends, instead of arr.
+        categories: Chromosome names the codes in chrom refer to.
 
     Returns:
-        IntervalSet with n intervals, all on the specified chromosome.
+        IntervalSet with n intervals.
 
     Raises:
-        ValueError: If array shape is not (n, 2).
+        ValueError: If array shape is not (n, 2), lengths differ, a
+            coordinate is negative or a code has no category.
     """
     ...
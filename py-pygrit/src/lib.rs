@@ -7,10 +7,14 @@
 mod errors;
 
 use errors::to_py_err;
-use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2};
+use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2};
 use pyo3::exceptions::{PyIOError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 // Re-export from main crate
 use grit_genomics::bed::{
@@ -18,15 +22,15 @@ use grit_genomics::bed::{
 };
 use grit_genomics::commands::{
     ComplementCommand, FastSortCommand, GenerateCommand, GenerateConfig, GenerateMode,
-    IntersectCommand as RsIntersectCommand, JaccardCommand, MergeCommand as RsMergeCommand,
-    SizeSpec, SlopCommand, SortMode, StreamingClosestCommand, StreamingCoverageCommand,
-    StreamingGenomecovCommand, StreamingGenomecovMode, StreamingIntersectCommand,
-    StreamingMergeCommand, StreamingMultiinterCommand, StreamingSubtractCommand,
-    StreamingWindowCommand,
+    GenomecovCommand, IndexedBed, IntersectCommand as RsIntersectCommand, JaccardCommand,
+    MergeCommand as RsMergeCommand, SizeSpec, SlopCommand, SortMode, StreamingClosestCommand,
+    StreamingCoverageCommand, StreamingGenomecovCommand, StreamingGenomecovMode,
+    StreamingIntersectCommand, StreamingMergeCommand, StreamingMultiinterCommand,
+    StreamingSubtractCommand, StreamingWindowCommand,
 };
 use grit_genomics::genome::Genome;
 use grit_genomics::index::IntervalIndex as RsIntervalIndex;
-use grit_genomics::interval::Interval as RsInterval;
+use grit_genomics::interval::{GlobalCoords as RsGlobalCoords, Interval as RsInterval};
 
 // ============================================================================
 // Core Types
@@ -126,6 +130,19 @@ impl Interval {
     fn to_tuple(&self) -> (String, u64, u64) {
         (self.chrom.clone(), self.start, self.end)
     }
+
+    /// Start and end in genome-wide coordinates, or None if the chromosome
+    /// is not in `coords` or the interval runs past its end.
+    fn to_global(&self, coords: &GlobalCoords) -> Option<(u64, u64)> {
+        RsInterval::from(self).to_global(&coords.inner)
+    }
+
+    /// Interval at genome-wide coordinates [start, end), or None if the
+    /// range is outside the genome or crosses a chromosome boundary.
+    #[staticmethod]
+    fn from_global(start: u64, end: u64, coords: &GlobalCoords) -> Option<Interval> {
+        RsInterval::from_global(start, end, &coords.inner).map(Interval::from)
+    }
 }
 
 impl From<RsInterval> for Interval {
@@ -144,29 +161,126 @@ impl From<&Interval> for RsInterval {
     }
 }
 
+/// Genome-wide linear coordinates from a genome file.
+///
+/// Chromosomes are laid end to end in genome file order, so a single integer
+/// locates any base; useful for plotting and genome-wide binning.
+///
+/// Example:
+///     >>> coords = pygrit.GlobalCoords.from_genome("hg38.genome")
+///     >>> coords.to_global("chr2", 100)
+///     248956522
+///     >>> coords.to_local(248956522)
+///     ('chr2', 0)
+#[pyclass]
+#[derive(Clone)]
+pub struct GlobalCoords {
+    inner: RsGlobalCoords,
+}
+
+#[pymethods]
+impl GlobalCoords {
+    /// Load chromosome sizes from a genome file (chrom<TAB>size).
+    #[staticmethod]
+    fn from_genome(path: PathBuf) -> PyResult<Self> {
+        let genome = Genome::from_file(path).map_err(to_py_err)?;
+        Ok(Self {
+            inner: RsGlobalCoords::from_genome(&genome),
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GlobalCoords(chroms={}, total_len={})",
+            self.inner.chroms().len(),
+            self.inner.total_len()
+        )
+    }
+
+    /// Length of the whole genome.
+    #[getter]
+    fn total_len(&self) -> u64 {
+        self.inner.total_len()
+    }
+
+    /// Chromosomes in genome file order.
+    #[getter]
+    fn chroms(&self) -> Vec<String> {
+        self.inner.chroms().to_vec()
+    }
+
+    /// Genome-wide offset of a chromosome's first base.
+    fn offset(&self, chrom: &str) -> Option<u64> {
+        self.inner.offset(chrom)
+    }
+
+    /// Genome-wide position of chrom:pos, or None if outside the genome.
+    fn to_global(&self, chrom: &str, pos: u64) -> Option<u64> {
+        self.inner.to_global(chrom, pos)
+    }
+
+    /// (chrom, pos) of a genome-wide position, or None if past the genome.
+    fn to_local(&self, pos: u64) -> Option<(String, u64)> {
+        self.inner
+            .to_local(pos)
+            .map(|(chrom, pos)| (chrom.to_string(), pos))
+    }
+}
+
 /// A collection of genomic intervals.
 ///
 /// Provides methods for bulk operations like merge, intersect, and sorting.
+/// Sortedness is tracked as intervals are added, so operations on sorted sets
+/// can use a sweep instead of building an index.
+///
+/// Point queries use a per-chromosome interval index built on first use and
+/// kept until the set is modified.
 #[pyclass]
 pub struct IntervalSet {
     intervals: Vec<RsInterval>,
+    sorted: bool,
+    /// Index over a copy of `intervals`, in the same order
+    index: OnceLock<RsIntervalIndex<'static>>,
+}
+
+impl IntervalSet {
+    /// Wrap intervals whose order is not known in advance.
+    fn from_vec(intervals: Vec<RsInterval>) -> Self {
+        let sorted = intervals.windows(2).all(|w| w[0] <= w[1]);
+        Self {
+            intervals,
+            sorted,
+            index: OnceLock::new(),
+        }
+    }
+
+    /// Wrap intervals that are known to be sorted.
+    fn from_sorted_vec(intervals: Vec<RsInterval>) -> Self {
+        Self {
+            intervals,
+            sorted: true,
+            index: OnceLock::new(),
+        }
+    }
+
+    /// The interval index, built on first use.
+    fn index(&self) -> &RsIntervalIndex<'static> {
+        self.index
+            .get_or_init(|| RsIntervalIndex::from_intervals(self.intervals.clone()))
+    }
 }
 
 #[pymethods]
 impl IntervalSet {
     #[new]
     fn new() -> Self {
-        Self {
-            intervals: Vec::new(),
-        }
+        Self::from_sorted_vec(Vec::new())
     }
 
     /// Create an IntervalSet from a list of Interval objects.
     #[staticmethod]
     fn from_intervals(intervals: Vec<Interval>) -> Self {
-        Self {
-            intervals: intervals.iter().map(RsInterval::from).collect(),
-        }
+        Self::from_vec(intervals.iter().map(RsInterval::from).collect())
     }
 
     fn __len__(&self) -> usize {
@@ -184,9 +298,82 @@ impl IntervalSet {
             .ok_or_else(|| PyValueError::new_err("Index out of bounds"))
     }
 
+    /// Whether the intervals are sorted by chromosome and start position.
+    #[getter]
+    fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
     /// Add an interval.
     fn add(&mut self, interval: Interval) {
-        self.intervals.push(RsInterval::from(&interval));
+        let interval = RsInterval::from(&interval);
+        if let Some(last) = self.intervals.last() {
+            self.sorted &= *last <= interval;
+        }
+        self.intervals.push(interval);
+        self.index.take();
+    }
+
+    /// Positions of the intervals overlapping a region.
+    ///
+    /// Args:
+    ///     chrom: Chromosome name
+    ///     start: Region start (0-based)
+    ///     end: Region end (exclusive); defaults to start + 1
+    ///
+    /// Returns:
+    ///     Indices into this set, ordered by interval start.
+    #[pyo3(signature = (chrom, start, end = None))]
+    fn query(&self, py: Python<'_>, chrom: &str, start: u64, end: Option<u64>) -> Vec<usize> {
+        let query = RsInterval::new(chrom, start, end.unwrap_or(start + 1));
+        py.allow_threads(|| self.index().find_overlap_indices(&query))
+    }
+
+    /// Every overlapping pair between this set and another.
+    ///
+    /// The other set's index is built once and queried in parallel with the
+    /// GIL released.
+    ///
+    /// Args:
+    ///     other: IntervalSet to compare against
+    ///
+    /// Returns:
+    ///     Tuple of two int64 NumPy arrays (self_idx, other_idx), ordered by
+    ///     self_idx, then by the start of the other interval.
+    ///
+    /// Example:
+    ///     >>> i, j = peaks.overlaps_with(genes)
+    ///     >>> np.bincount(i, minlength=len(peaks))  # genes per peak
+    fn overlaps_with<'py>(
+        &self,
+        py: Python<'py>,
+        other: &IntervalSet,
+    ) -> PyResult<Bound<'py, PyTuple>> {
+        let (self_idx, other_idx) = py.allow_threads(|| {
+            let index = other.index();
+            let per_self: Vec<Vec<usize>> = self
+                .intervals
+                .par_iter()
+                .map(|iv| index.find_overlap_indices(iv))
+                .collect();
+
+            let total: usize = per_self.iter().map(Vec::len).sum();
+            let mut self_idx = Vec::with_capacity(total);
+            let mut other_idx = Vec::with_capacity(total);
+            for (i, hits) in per_self.into_iter().enumerate() {
+                for j in hits {
+                    self_idx.push(i as i64);
+                    other_idx.push(j as i64);
+                }
+            }
+            (self_idx, other_idx)
+        });
+
+        (
+            PyArray1::from_vec(py, self_idx),
+            PyArray1::from_vec(py, other_idx),
+        )
+            .into_pyobject(py)
     }
 
     /// Convert to a list of Interval objects.
@@ -201,19 +388,28 @@ impl IntervalSet {
     #[pyo3(signature = (distance = 0))]
     fn merge(&self, distance: u64) -> Self {
         let cmd = RsMergeCommand::new().with_distance(distance);
-        let merged = cmd.merge(self.intervals.clone());
-        Self { intervals: merged }
+        let merged = if self.sorted {
+            cmd.merge_sorted(&self.intervals)
+        } else {
+            cmd.merge(&self.intervals)
+        };
+        Self::from_sorted_vec(merged)
     }
 
     /// Find intersections with another IntervalSet.
+    ///
+    /// Uses a sweep when both sets are sorted, otherwise an interval index.
     #[pyo3(signature = (other, fraction = None, reciprocal = false))]
     fn intersect(&self, other: &IntervalSet, fraction: Option<f64>, reciprocal: bool) -> Self {
         let mut cmd = RsIntersectCommand::new();
         cmd.fraction_a = fraction;
         cmd.reciprocal = reciprocal;
 
-        let results =
-            cmd.find_intersections_parallel(self.intervals.clone(), other.intervals.clone());
+        let results = if self.sorted && other.sorted {
+            cmd.find_intersections_sorted(&self.intervals, &other.intervals)
+        } else {
+            cmd.find_intersections_parallel(&self.intervals, &other.intervals)
+        };
 
         let mut seen = std::collections::HashSet::new();
         let intervals: Vec<RsInterval> = results
@@ -222,7 +418,7 @@ impl IntervalSet {
             .map(|r| r.a_interval)
             .collect();
 
-        Self { intervals }
+        Self::from_vec(intervals)
     }
 
     /// Find intervals with no overlap.
@@ -230,17 +426,30 @@ impl IntervalSet {
         let mut cmd = RsIntersectCommand::new();
         cmd.no_overlap = true;
 
-        let b_index = RsIntervalIndex::from_intervals(other.intervals.clone());
-        let results = cmd.find_intersections(&self.intervals, &b_index);
+        let results = if self.sorted && other.sorted {
+            cmd.find_intersections_sorted(&self.intervals, &other.intervals)
+        } else {
+            let b_index = RsIntervalIndex::from_intervals(&other.intervals);
+            cmd.find_intersections(&self.intervals, &b_index)
+        };
 
         let intervals: Vec<RsInterval> = results.into_iter().map(|r| r.a_interval).collect();
 
-        Self { intervals }
+        // A order is preserved, so a sorted input stays sorted
+        if self.sorted {
+            Self::from_sorted_vec(intervals)
+        } else {
+            Self::from_vec(intervals)
+        }
     }
 
     /// Sort intervals by chromosome and start position.
     fn sort(&mut self) {
-        self.intervals.sort();
+        if !self.sorted {
+            self.intervals.sort();
+            self.sorted = true;
+            self.index.take();
+        }
     }
 
     /// Convert to NumPy array (start, end only).
@@ -257,6 +466,46 @@ impl IntervalSet {
             .reshape([n, 2])
             .map_err(|e| PyValueError::new_err(format!("Failed to reshape: {}", e)))?)
     }
+
+    /// Convert to NumPy arrays, including chromosomes.
+    ///
+    /// Chromosomes are returned as codes into a list of categories (in order
+    /// of first appearance), like a pandas Categorical, so the result can be
+    /// passed back to `from_numpy`.
+    ///
+    /// Returns:
+    ///     Tuple (chrom_codes, categories, starts, ends): three int64 NumPy
+    ///     arrays of length n and a list of chromosome names.
+    ///
+    /// Example:
+    ///     >>> codes, cats, starts, ends = intervals.to_numpy_full()
+    ///     >>> same = pygrit.from_numpy(codes, starts=starts, ends=ends, categories=cats)
+    fn to_numpy_full<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        let n = self.intervals.len();
+        let mut codes = Vec::with_capacity(n);
+        let mut starts = Vec::with_capacity(n);
+        let mut ends = Vec::with_capacity(n);
+        let mut categories: Vec<String> = Vec::new();
+        let mut ids: HashMap<&str, i64> = HashMap::new();
+
+        for interval in &self.intervals {
+            let code = *ids.entry(interval.chrom.as_str()).or_insert_with(|| {
+                categories.push(interval.chrom.clone());
+                categories.len() as i64 - 1
+            });
+            codes.push(code);
+            starts.push(interval.start as i64);
+            ends.push(interval.end as i64);
+        }
+
+        (
+            PyArray1::from_vec(py, codes),
+            categories,
+            PyArray1::from_vec(py, starts),
+            PyArray1::from_vec(py, ends),
+        )
+            .into_pyobject(py)
+    }
 }
 
 // ============================================================================
@@ -985,6 +1234,377 @@ pub fn generate(
     Ok(dict.into())
 }
 
+// ============================================================================
+// NumPy Result Arrays
+// ============================================================================
+
+/// Read A and B interval files for the array-returning functions.
+fn read_ab_intervals(a: &str, b: &str) -> Result<(Vec<RsInterval>, Vec<RsInterval>), BedError> {
+    Ok((rs_read_intervals(a)?, rs_read_intervals(b)?))
+}
+
+/// Count B overlaps for every A record.
+///
+/// Unlike the streaming functions, inputs do not need to be sorted. Both
+/// files are loaded into memory and B is indexed.
+///
+/// Args:
+///     a: Path to BED file A
+///     b: Path to BED file B
+///
+/// Returns:
+///     int64 NumPy array of length len(A); element i is the number of B
+///     intervals overlapping the i-th record of A.
+///
+/// Example:
+///     >>> counts = pygrit.intersect_counts("peaks.bed", "reads.bed")
+///     >>> counts.sum()
+#[pyfunction]
+pub fn intersect_counts<'py>(
+    py: Python<'py>,
+    a: &str,
+    b: &str,
+) -> PyResult<Bound<'py, PyArray1<i64>>> {
+    let counts = py
+        .allow_threads(|| -> Result<Vec<i64>, BedError> {
+            let (a_intervals, b_intervals) = read_ab_intervals(a, b)?;
+            let b_index = RsIntervalIndex::from_intervals(b_intervals);
+            Ok(a_intervals
+                .par_iter()
+                .map(|iv| b_index.count_overlaps(iv) as i64)
+                .collect())
+        })
+        .map_err(to_py_err)?;
+
+    Ok(PyArray1::from_vec(py, counts))
+}
+
+/// Report every overlapping (A, B) pair with its overlap length.
+///
+/// Inputs do not need to be sorted. Pairs are ordered by A record, then by
+/// B start position.
+///
+/// Args:
+///     a: Path to BED file A
+///     b: Path to BED file B
+///     fraction: Also return the fraction of each A record covered by its B
+///
+/// Returns:
+///     Tuple of three int64 NumPy arrays (a_idx, b_idx, overlap_len), where
+///     the indices are 0-based record positions in each file. With
+///     fraction=True a fourth float64 array holds overlap_len divided by the
+///     A record's length (0 for zero-length A records).
+///
+/// Example:
+///     >>> a_idx, b_idx, overlap = pygrit.intersect_pairs("a.bed", "b.bed")
+///     >>> np.bincount(a_idx, weights=overlap)  # bases covered per A record
+///     >>> *_, frac = pygrit.intersect_pairs("a.bed", "b.bed", fraction=True)
+#[pyfunction]
+#[pyo3(signature = (a, b, fraction = false))]
+pub fn intersect_pairs<'py>(
+    py: Python<'py>,
+    a: &str,
+    b: &str,
+    fraction: bool,
+) -> PyResult<Bound<'py, PyTuple>> {
+    let (a_idx, b_idx, overlap, fractions) = py
+        .allow_threads(|| -> Result<_, BedError> {
+            let (a_intervals, b_intervals) = read_ab_intervals(a, b)?;
+            let b_index = RsIntervalIndex::from_intervals(b_intervals);
+            let per_a: Vec<Vec<(i64, i64)>> = a_intervals
+                .par_iter()
+                .map(|iv| {
+                    b_index
+                        .find_overlap_indices(iv)
+                        .into_iter()
+                        .map(|j| (j as i64, iv.overlap_length(&b_index.intervals()[j]) as i64))
+                        .collect()
+                })
+                .collect();
+
+            let total: usize = per_a.iter().map(Vec::len).sum();
+            let mut a_idx = Vec::with_capacity(total);
+            let mut b_idx = Vec::with_capacity(total);
+            let mut overlap = Vec::with_capacity(total);
+            let mut fractions = Vec::with_capacity(if fraction { total } else { 0 });
+            for (i, hits) in per_a.into_iter().enumerate() {
+                let a_len = a_intervals[i].len();
+                for (j, len) in hits {
+                    a_idx.push(i as i64);
+                    b_idx.push(j);
+                    overlap.push(len);
+                    if fraction {
+                        fractions.push(if a_len > 0 {
+                            len as f64 / a_len as f64
+                        } else {
+                            0.0
+                        });
+                    }
+                }
+            }
+            Ok((a_idx, b_idx, overlap, fractions))
+        })
+        .map_err(to_py_err)?;
+
+    let a_idx = PyArray1::from_vec(py, a_idx);
+    let b_idx = PyArray1::from_vec(py, b_idx);
+    let overlap = PyArray1::from_vec(py, overlap);
+    if fraction {
+        (a_idx, b_idx, overlap, PyArray1::from_vec(py, fractions)).into_pyobject(py)
+    } else {
+        (a_idx, b_idx, overlap).into_pyobject(py)
+    }
+}
+
+/// Compute genome-wide coverage as NumPy arrays.
+///
+/// Input does not need to be sorted. Chromosomes missing from the genome
+/// file are ignored.
+///
+/// Args:
+///     input: Path to input BED file
+///     genome: Path to genome file (chromosome sizes)
+///     expand: Return one depth value per base instead of runs
+///
+/// Returns:
+///     Dict keyed by chromosome in genome order. By default each value is a
+///     tuple (starts, ends, depth) of int64 arrays whose runs tile the whole
+///     chromosome, zero-depth runs included. With expand=True each value is
+///     an int64 array of length chrom_size.
+///
+/// Example:
+///     >>> cov = pygrit.genomecov_arrays("reads.bed", "genome.txt")
+///     >>> starts, ends, depth = cov["chr1"]
+///     >>> np.average(depth, weights=ends - starts)  # mean depth
+#[pyfunction]
+#[pyo3(signature = (input, genome, expand = false))]
+pub fn genomecov_arrays<'py>(
+    py: Python<'py>,
+    input: &str,
+    genome: &str,
+    expand: bool,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let runs = py
+        .allow_threads(|| -> Result<_, BedError> {
+            let genome_data = Genome::from_file(genome)?;
+            let file = std::fs::File::open(input)?;
+            let reader = grit_genomics::bed::BedReader::new(file);
+            GenomecovCommand::new().depth_runs(reader, &genome_data)
+        })
+        .map_err(to_py_err)?;
+
+    let dict = pyo3::types::PyDict::new(py);
+    for (chrom, chrom_runs) in runs {
+        if expand {
+            let size = chrom_runs.last().map_or(0, |r| r.1) as usize;
+            let depth = py.allow_threads(|| {
+                let mut depth = vec![0i64; size];
+                for &(start, end, d) in &chrom_runs {
+                    depth[start as usize..end as usize].fill(d as i64);
+                }
+                depth
+            });
+            dict.set_item(chrom, PyArray1::from_vec(py, depth))?;
+        } else {
+            let starts: Vec<i64> = chrom_runs.iter().map(|r| r.0 as i64).collect();
+            let ends: Vec<i64> = chrom_runs.iter().map(|r| r.1 as i64).collect();
+            let depth: Vec<i64> = chrom_runs.iter().map(|r| r.2 as i64).collect();
+            dict.set_item(
+                chrom,
+                (
+                    PyArray1::from_vec(py, starts),
+                    PyArray1::from_vec(py, ends),
+                    PyArray1::from_vec(py, depth),
+                ),
+            )?;
+        }
+    }
+
+    Ok(dict)
+}
+
+// ============================================================================
+// Indexed Queries
+// ============================================================================
+
+/// A sorted BED file opened for repeated region queries.
+///
+/// The file is memory-mapped and each query binary-searches its chromosome,
+/// so lookups cost microseconds regardless of file size and the file is
+/// never parsed as a whole. Chromosome ranges come from the `.gri` sidecar
+/// written by `grit stab --build-index` when present.
+///
+/// Example:
+///     >>> idx = pygrit.GritIndex.load("reads.sorted.bed")
+///     >>> idx.query("chr1", 1000, 2000)
+///     [Interval(chr1:950-1050), ...]
+///     >>> q, starts, ends = idx.query_batch("chr1", positions)
+#[pyclass]
+pub struct GritIndex {
+    bed: IndexedBed,
+    path: String,
+}
+
+#[pymethods]
+impl GritIndex {
+    /// Open a sorted BED file for querying.
+    ///
+    /// Args:
+    ///     path: Path to a BED file sorted by chromosome, then start
+    ///     build_index: Rebuild the `.gri` sidecar and write it next to the file
+    ///
+    /// Raises:
+    ///     ValueError: If the file is not sorted or its sidecar is stale
+    #[staticmethod]
+    #[pyo3(signature = (path, build_index = false))]
+    fn load(py: Python<'_>, path: &str, build_index: bool) -> PyResult<Self> {
+        let bed = py
+            .allow_threads(|| IndexedBed::open(path, build_index))
+            .map_err(to_py_err)?;
+        Ok(Self {
+            bed,
+            path: path.to_string(),
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GritIndex('{}', chroms={}, records={})",
+            self.path,
+            self.bed.index().blocks.len(),
+            self.__len__()
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        self.bed
+            .index()
+            .blocks
+            .iter()
+            .map(|b| b.records as usize)
+            .sum()
+    }
+
+    /// Chromosomes in file order.
+    #[getter]
+    fn chroms(&self) -> Vec<String> {
+        self.bed
+            .index()
+            .blocks
+            .iter()
+            .map(|b| b.chrom.clone())
+            .collect()
+    }
+
+    /// Records overlapping a region, in file order.
+    ///
+    /// Args:
+    ///     chrom: Chromosome name
+    ///     start: Region start (0-based)
+    ///     end: Region end (exclusive); defaults to start + 1
+    #[pyo3(signature = (chrom, start, end = None))]
+    fn query(&self, chrom: &str, start: u64, end: Option<u64>) -> Vec<Interval> {
+        let end = end.unwrap_or(start + 1);
+        self.bed
+            .query(chrom, start, end)
+            .map(|(_, s, e)| Interval {
+                chrom: chrom.to_string(),
+                start: s,
+                end: e,
+            })
+            .collect()
+    }
+
+    /// Run many queries at once, in parallel with the GIL released.
+    ///
+    /// Args:
+    ///     chroms: One chromosome for every query, or a sequence of
+    ///         chromosomes the same length as starts
+    ///     starts: int64 array of query starts (0-based)
+    ///     ends: int64 array of query ends; defaults to starts + 1 (point
+    ///         lookups)
+    ///
+    /// Returns:
+    ///     Tuple of three int64 NumPy arrays (query_idx, start, end) with one
+    ///     element per hit, ordered by query.
+    ///
+    /// Example:
+    ///     >>> q, s, e = idx.query_batch("chr1", np.array([100, 5000]))
+    ///     >>> np.bincount(q, minlength=2)  # hits per query
+    #[pyo3(signature = (chroms, starts, ends = None))]
+    fn query_batch<'py>(
+        &self,
+        py: Python<'py>,
+        chroms: &Bound<'py, PyAny>,
+        starts: PyReadonlyArray1<'py, i64>,
+        ends: Option<PyReadonlyArray1<'py, i64>>,
+    ) -> PyResult<Bound<'py, PyTuple>> {
+        let starts = starts.as_slice()?;
+        let ends = match &ends {
+            Some(ends) => {
+                let ends = ends.as_slice()?;
+                if ends.len() != starts.len() {
+                    return Err(PyValueError::new_err(
+                        "starts and ends must have the same length",
+                    ));
+                }
+                Some(ends)
+            }
+            None => None,
+        };
+        let chroms: Vec<String> = match chroms.extract::<String>() {
+            Ok(chrom) => vec![chrom],
+            Err(_) => {
+                let chroms: Vec<String> = chroms.extract()?;
+                if chroms.len() != starts.len() {
+                    return Err(PyValueError::new_err(
+                        "chroms must be a string or have the same length as starts",
+                    ));
+                }
+                chroms
+            }
+        };
+        if starts.iter().chain(ends.unwrap_or(&[])).any(|&v| v < 0) {
+            return Err(PyValueError::new_err("coordinates must be non-negative"));
+        }
+
+        let (query_idx, hit_starts, hit_ends) = py.allow_threads(|| {
+            let per_query: Vec<Vec<(i64, i64)>> = (0..starts.len())
+                .into_par_iter()
+                .map(|i| {
+                    let chrom = &chroms[if chroms.len() == 1 { 0 } else { i }];
+                    let start = starts[i] as u64;
+                    let end = ends.map_or(start + 1, |e| e[i] as u64);
+                    self.bed
+                        .query(chrom, start, end)
+                        .map(|(_, s, e)| (s as i64, e as i64))
+                        .collect()
+                })
+                .collect();
+
+            let total: usize = per_query.iter().map(Vec::len).sum();
+            let mut query_idx = Vec::with_capacity(total);
+            let mut hit_starts = Vec::with_capacity(total);
+            let mut hit_ends = Vec::with_capacity(total);
+            for (i, hits) in per_query.into_iter().enumerate() {
+                for (s, e) in hits {
+                    query_idx.push(i as i64);
+                    hit_starts.push(s);
+                    hit_ends.push(e);
+                }
+            }
+            (query_idx, hit_starts, hit_ends)
+        });
+
+        (
+            PyArray1::from_vec(py, query_idx),
+            PyArray1::from_vec(py, hit_starts),
+            PyArray1::from_vec(py, hit_ends),
+        )
+            .into_pyobject(py)
+    }
+}
+
 // ============================================================================
 // I/O Utilities
 // ============================================================================
@@ -1000,7 +1620,7 @@ pub fn generate(
 fn read_bed(path: &str) -> PyResult<IntervalSet> {
     let intervals = rs_read_intervals(path)
         .map_err(|e| PyIOError::new_err(format!("Failed to read BED file: {}", e)))?;
-    Ok(IntervalSet { intervals })
+    Ok(IntervalSet::from_vec(intervals))
 }
 
 /// Parse intervals from a string.
@@ -1014,36 +1634,115 @@ fn read_bed(path: &str) -> PyResult<IntervalSet> {
 fn parse_bed(content: &str) -> PyResult<IntervalSet> {
     let intervals = rs_parse_intervals(content)
         .map_err(|e| PyValueError::new_err(format!("Failed to parse BED content: {}", e)))?;
-    Ok(IntervalSet { intervals })
+    Ok(IntervalSet::from_vec(intervals))
 }
 
-/// Create an IntervalSet from a NumPy array.
+/// Create an IntervalSet from NumPy arrays.
+///
+/// Coordinates come either as one (n, 2) array or as separate starts and
+/// ends arrays. The chromosome is one name for all intervals, a sequence of
+/// n names, or n integer codes into `categories` (e.g. the codes and
+/// categories of a pandas Categorical).
 ///
 /// Args:
-///     chrom: Chromosome name for all intervals
+///     chrom: Chromosome name, array of n names, or array of n codes
 ///     arr: NumPy array with shape (n, 2) containing start and end coordinates
+///     starts: int64 array of n starts (instead of arr)
+///     ends: int64 array of n ends (instead of arr)
+///     categories: Chromosome names that the codes in chrom refer to
 ///
 /// Returns:
-///     IntervalSet with intervals from the array.
+///     IntervalSet with intervals from the arrays.
+///
+/// Example:
+///     >>> pygrit.from_numpy("chr1", np.array([[100, 200], [300, 400]]))
+///     >>> pygrit.from_numpy(np.array(["chr1", "chr2"]), starts=s, ends=e)
+///     >>> pygrit.from_numpy(codes, starts=s, ends=e, categories=["chr1", "chr2"])
 #[pyfunction]
-fn from_numpy(_py: Python<'_>, chrom: &str, arr: PyReadonlyArray2<i64>) -> PyResult<IntervalSet> {
-    let arr = arr.as_array();
-    let shape = arr.shape();
-
-    if shape.len() != 2 || shape[1] != 2 {
-        return Err(PyValueError::new_err(
-            "Array must have shape (n, 2) with start and end columns",
-        ));
-    }
-
-    let mut intervals = Vec::with_capacity(shape[0]);
-    for i in 0..shape[0] {
-        let start = arr[[i, 0]] as u64;
-        let end = arr[[i, 1]] as u64;
-        intervals.push(RsInterval::new(chrom, start, end));
+#[pyo3(signature = (chrom, arr = None, *, starts = None, ends = None, categories = None))]
+fn from_numpy<'py>(
+    chrom: &Bound<'py, PyAny>,
+    arr: Option<PyReadonlyArray2<'py, i64>>,
+    starts: Option<PyReadonlyArray1<'py, i64>>,
+    ends: Option<PyReadonlyArray1<'py, i64>>,
+    categories: Option<Vec<String>>,
+) -> PyResult<IntervalSet> {
+    let coords: Vec<(i64, i64)> = match (arr, starts, ends) {
+        (Some(arr), None, None) => {
+            let arr = arr.as_array();
+            let shape = arr.shape();
+            if shape.len() != 2 || shape[1] != 2 {
+                return Err(PyValueError::new_err(
+                    "Array must have shape (n, 2) with start and end columns",
+                ));
+            }
+            (0..shape[0]).map(|i| (arr[[i, 0]], arr[[i, 1]])).collect()
+        }
+        (None, Some(starts), Some(ends)) => {
+            let (starts, ends) = (starts.as_slice()?, ends.as_slice()?);
+            if starts.len() != ends.len() {
+                return Err(PyValueError::new_err(
+                    "starts and ends must have the same length",
+                ));
+            }
+            starts.iter().copied().zip(ends.iter().copied()).collect()
+        }
+        _ => {
+            return Err(PyValueError::new_err(
+                "pass either arr or both starts and ends",
+            ))
+        }
+    };
+    if coords.iter().any(|&(start, end)| start < 0 || end < 0) {
+        return Err(PyValueError::new_err("coordinates must be non-negative"));
     }
 
-    Ok(IntervalSet { intervals })
+    let n = coords.len();
+    let chroms: Vec<String> = if let Some(categories) = categories {
+        let codes: Vec<i64> = match chrom.extract::<PyReadonlyArray1<i64>>() {
+            Ok(codes) => codes.as_slice()?.to_vec(),
+            // Other integer dtypes, e.g. the int8 codes of a pandas Categorical
+            Err(_) => chrom.extract()?,
+        };
+        if codes.len() != n {
+            return Err(PyValueError::new_err(
+                "chrom codes must have the same length as the coordinates",
+            ));
+        }
+        codes
+            .iter()
+            .map(|&code| {
+                usize::try_from(code)
+                    .ok()
+                    .and_then(|i| categories.get(i))
+                    .cloned()
+                    .ok_or_else(|| {
+                        PyValueError::new_err(format!(
+                            "chrom code {} is out of range for {} categories",
+                            code,
+                            categories.len()
+                        ))
+                    })
+            })
+            .collect::<PyResult<_>>()?
+    } else if let Ok(name) = chrom.extract::<String>() {
+        vec![name; n]
+    } else {
+        let names: Vec<String> = chrom.extract()?;
+        if names.len() != n {
+            return Err(PyValueError::new_err(
+                "chrom must be a string or have the same length as the coordinates",
+            ));
+        }
+        names
+    };
+
+    let intervals = chroms
+        .into_iter()
+        .zip(coords)
+        .map(|(chrom, (start, end))| RsInterval::new(chrom, start as u64, end as u64))
+        .collect();
+    Ok(IntervalSet::from_vec(intervals))
 }
 
 // ============================================================================
@@ -1068,7 +1767,9 @@ fn from_numpy(_py: Python<'_>, chrom: &str, arr: PyReadonlyArray2<i64>) -> PyRes
 fn pygrit(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Core types
     m.add_class::<Interval>()?;
+    m.add_class::<GlobalCoords>()?;
     m.add_class::<IntervalSet>()?;
+    m.add_class::<GritIndex>()?;
 
     // File-based streaming functions
     m.add_function(wrap_pyfunction!(intersect, m)?)?;
@@ -1085,6 +1786,11 @@ fn pygrit(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(multiinter, m)?)?;
     m.add_function(wrap_pyfunction!(generate, m)?)?;
 
+    // NumPy result arrays
+    m.add_function(wrap_pyfunction!(intersect_counts, m)?)?;
+    m.add_function(wrap_pyfunction!(intersect_pairs, m)?)?;
+    m.add_function(wrap_pyfunction!(genomecov_arrays, m)?)?;
+
     // I/O utilities
     m.add_function(wrap_pyfunction!(read_bed, m)?)?;
     m.add_function(wrap_pyfunction!(parse_bed, m)?)?;
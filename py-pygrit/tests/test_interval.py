@@ -182,3 +182,21 @@ class TestIntervalConversion:
         """Test conversion to tuple."""
         iv = Interval("chr1", 100, 200)
         assert iv.to_tuple() == ("chr1", 100, 200)
+
+    def test_global_coords(self, temp_dir):
+        """Test conversion to and from genome-wide coordinates."""
+        genome = temp_dir / "test.genome"
+        genome.write_text("chr1\t1000\nchr2\t500\n")
+        coords = pygrit.GlobalCoords.from_genome(genome)
+
+        assert coords.total_len == 1500
+        assert coords.chroms == ["chr1", "chr2"]
+        assert coords.to_global("chr2", 10) == 1010
+        assert coords.to_local(1010) == ("chr2", 10)
+        assert coords.to_local(1500) is None
+
+        iv = Interval("chr2", 100, 200)
+        assert iv.to_global(coords) == (1100, 1200)
+        assert Interval.from_global(1100, 1200, coords) == iv
+        assert Interval.from_global(900, 1100, coords) is None
+        assert Interval("chrX", 0, 10).to_global(coords) is None
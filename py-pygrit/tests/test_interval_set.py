@@ -201,6 +201,85 @@ class TestIntervalSetSort:
         assert result[1].start == 300
         assert result[2].chrom == "chr2"
 
+    def test_is_sorted_tracking(self):
+        """Test that sortedness is tracked across add and sort."""
+        iset = IntervalSet()
+        assert iset.is_sorted
+        iset.add(Interval("chr1", 100, 200))
+        iset.add(Interval("chr1", 300, 400))
+        assert iset.is_sorted
+        iset.add(Interval("chr1", 50, 80))
+        assert not iset.is_sorted
+        iset.sort()
+        assert iset.is_sorted
+
+    def test_sorted_and_unsorted_intersect_agree(self):
+        """Test that the sweep and index strategies give the same result."""
+        a = [
+            Interval("chr1", 100, 200),
+            Interval("chr1", 300, 400),
+            Interval("chr2", 100, 200),
+        ]
+        b = [Interval("chr1", 150, 350), Interval("chr2", 500, 600)]
+        unsorted_a = IntervalSet.from_intervals(list(reversed(a)))
+        sorted_a = IntervalSet.from_intervals(a)
+        b_set = IntervalSet.from_intervals(b)
+        assert sorted_a.is_sorted
+        assert not unsorted_a.is_sorted
+
+        expected = sorted((i.chrom, i.start) for i in unsorted_a.intersect(b_set).to_list())
+        actual = [(i.chrom, i.start) for i in sorted_a.intersect(b_set).to_list()]
+        assert actual == expected
+        assert len(sorted_a.non_overlapping(b_set)) == 1
+
+
+class TestIntervalSetQuery:
+    """Tests for IntervalSet.query and overlaps_with."""
+
+    def test_query(self):
+        """Test region queries return indices in start order."""
+        iset = IntervalSet.from_intervals([
+            Interval("chr1", 300, 400),
+            Interval("chr2", 100, 200),
+            Interval("chr1", 100, 350),
+        ])
+        assert iset.query("chr1", 320, 330) == [2, 0]
+        assert iset.query("chr1", 150) == [2]
+        assert iset.query("chr2", 200, 300) == []
+        assert iset.query("chrX", 0, 1000) == []
+
+    def test_query_after_add(self):
+        """Test that the cached index follows changes to the set."""
+        iset = IntervalSet.from_intervals([Interval("chr1", 100, 200)])
+        assert iset.query("chr1", 500, 600) == []
+        iset.add(Interval("chr1", 550, 650))
+        assert iset.query("chr1", 500, 600) == [1]
+        iset.add(Interval("chr1", 0, 10))
+        iset.sort()
+        assert iset.query("chr1", 500, 600) == [2]
+
+    def test_overlaps_with(self):
+        """Test overlapping pairs match a brute-force comparison."""
+        a = [
+            Interval("chr1", 100, 200),
+            Interval("chr1", 300, 400),
+            Interval("chr2", 100, 200),
+        ]
+        b = [
+            Interval("chr1", 150, 350),
+            Interval("chr2", 500, 600),
+            Interval("chr1", 0, 1000),
+        ]
+        i, j = IntervalSet.from_intervals(a).overlaps_with(IntervalSet.from_intervals(b))
+        assert i.dtype == np.int64
+        expected = [(x, y) for x in range(3) for y in (2, 0) if a[x].overlaps(b[y])]
+        assert list(zip(i.tolist(), j.tolist())) == expected
+
+    def test_overlaps_with_empty(self):
+        """Test pairs with an empty set."""
+        i, j = IntervalSet().overlaps_with(IntervalSet.from_intervals([Interval("chr1", 0, 10)]))
+        assert len(i) == 0 and len(j) == 0
+
 
 class TestIntervalSetNumpy:
     """Tests for IntervalSet NumPy integration."""
@@ -224,3 +303,22 @@ class TestIntervalSetNumpy:
         iset = IntervalSet()
         arr = iset.to_numpy()
         assert arr.shape == (0, 2)
+
+    def test_to_numpy_full_round_trip(self):
+        """Test that chromosomes survive a NumPy round trip."""
+        iset = IntervalSet.from_intervals([
+            Interval("chr2", 10, 20),
+            Interval("chr1", 100, 200),
+            Interval("chr2", 30, 40),
+        ])
+        codes, categories, starts, ends = iset.to_numpy_full()
+        assert categories == ["chr2", "chr1"]
+        assert codes.tolist() == [0, 1, 0]
+        assert starts.tolist() == [10, 100, 30]
+        assert ends.tolist() == [20, 200, 40]
+
+        back = pygrit.from_numpy(codes, starts=starts, ends=ends, categories=categories)
+        assert [str(iv) for iv in back.to_list()] == [str(iv) for iv in iset.to_list()]
+
+        codes, categories, starts, _ = IntervalSet().to_numpy_full()
+        assert len(codes) == 0 and categories == [] and len(starts) == 0
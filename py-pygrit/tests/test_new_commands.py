@@ -201,6 +201,32 @@ class TestGenomecov:
         assert result is None
         assert output.exists()
 
+    def test_genomecov_arrays_runs(self, temp_dir, genome_file):
+        """Runs tile every genome chromosome."""
+        bed_file = temp_dir / "test.bed"
+        bed_file.write_text("chr1\t100\t200\nchr1\t150\t250\n")
+
+        cov = pygrit.genomecov_arrays(str(bed_file), str(genome_file))
+
+        assert list(cov) == ["chr1", "chr2", "chr3"]
+        starts, ends, depth = cov["chr1"]
+        assert starts.tolist() == [0, 100, 150, 200, 250]
+        assert ends.tolist() == [100, 150, 200, 250, 1000]
+        assert depth.tolist() == [0, 1, 2, 1, 0]
+        assert depth.dtype.name == "int64"
+        assert cov["chr2"][2].tolist() == [0]
+
+    def test_genomecov_arrays_expand(self, temp_dir, genome_file):
+        """Expanded arrays hold one depth per base."""
+        bed_file = temp_dir / "test.bed"
+        bed_file.write_text("chr2\t10\t20\n")
+
+        cov = pygrit.genomecov_arrays(str(bed_file), str(genome_file), expand=True)
+
+        assert len(cov["chr2"]) == 800
+        assert cov["chr2"][10:20].tolist() == [1] * 10
+        assert cov["chr2"].sum() == 10
+
 
 class TestJaccard:
     """Tests for pygrit.jaccard function."""
@@ -359,6 +385,94 @@ class TestGenerate:
             pygrit.generate(str(temp_dir), mode="invalid_mode")
 
 
+class TestIntersectArrays:
+    """Tests for intersect_counts and intersect_pairs."""
+
+    def test_intersect_counts(self, sample_bed_a, sample_bed_b):
+        """Counts are aligned with A records."""
+        counts = pygrit.intersect_counts(str(sample_bed_a), str(sample_bed_b))
+
+        assert counts.dtype.name == "int64"
+        assert counts.tolist() == [1, 0, 1, 1, 1]
+
+    def test_intersect_pairs(self, sample_bed_a, sample_bed_b):
+        """Pairs carry record indices and overlap lengths."""
+        a_idx, b_idx, overlap = pygrit.intersect_pairs(str(sample_bed_a), str(sample_bed_b))
+
+        assert a_idx.tolist() == [0, 2, 3, 4]
+        assert b_idx.tolist() == [0, 1, 2, 3]
+        assert overlap.tolist() == [50, 50, 50, 50]
+
+    def test_intersect_pairs_fraction(self, sample_bed_a, sample_bed_b):
+        """fraction=True adds the covered fraction of each A record."""
+        a_idx, _, overlap, frac = pygrit.intersect_pairs(
+            str(sample_bed_a), str(sample_bed_b), fraction=True
+        )
+
+        assert frac.dtype.name == "float64"
+        assert frac.tolist() == (overlap / 100).tolist() == [0.5] * len(a_idx)
+
+    def test_intersect_counts_unsorted(self, temp_dir):
+        """Unsorted input keeps A order."""
+        a = temp_dir / "a.bed"
+        b = temp_dir / "b.bed"
+        a.write_text("chr2\t0\t10\nchr1\t0\t10\n")
+        b.write_text("chr1\t5\t6\nchr1\t1\t2\n")
+
+        counts = pygrit.intersect_counts(str(a), str(b))
+
+        assert counts.tolist() == [0, 2]
+
+
+class TestGritIndex:
+    """Tests for GritIndex."""
+
+    def test_query(self, sample_bed_a):
+        """Single queries return overlapping records."""
+        idx = pygrit.GritIndex.load(str(sample_bed_a))
+
+        assert len(idx) == 5
+        assert idx.chroms == ["chr1", "chr2"]
+        assert [iv.to_tuple() for iv in idx.query("chr1", 150, 350)] == [
+            ("chr1", 100, 200),
+            ("chr1", 300, 400),
+        ]
+        assert [iv.to_tuple() for iv in idx.query("chr2", 399)] == [("chr2", 300, 400)]
+        assert idx.query("chr1", 200, 300) == []
+        assert idx.query("chrX", 0, 1000) == []
+
+    def test_query_batch(self, sample_bed_a):
+        """Batch queries report hits by query index."""
+        import numpy as np
+
+        idx = pygrit.GritIndex.load(str(sample_bed_a))
+        q, starts, ends = idx.query_batch(
+            ["chr1", "chr1", "chr2"],
+            np.array([150, 250, 0], dtype=np.int64),
+            np.array([350, 260, 1000], dtype=np.int64),
+        )
+
+        assert q.tolist() == [0, 0, 2, 2]
+        assert starts.tolist() == [100, 300, 100, 300]
+        assert ends.tolist() == [200, 400, 200, 400]
+
+        q, starts, _ = idx.query_batch("chr1", np.array([100, 450, 599], dtype=np.int64))
+        assert q.tolist() == [0, 2]
+        assert starts.tolist() == [100, 500]
+
+    def test_build_index(self, sample_bed_a):
+        """build_index writes the .gri sidecar."""
+        pygrit.GritIndex.load(str(sample_bed_a), build_index=True)
+
+        assert os.path.exists(str(sample_bed_a) + ".gri")
+        assert len(pygrit.GritIndex.load(str(sample_bed_a))) == 5
+
+    def test_unsorted_raises(self, unsorted_bed):
+        """Unsorted input is rejected."""
+        with pytest.raises(ValueError):
+            pygrit.GritIndex.load(str(unsorted_bed))
+
+
 class TestNewCommandsIntegration:
     """Integration tests for new commands."""
 
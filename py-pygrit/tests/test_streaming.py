@@ -350,3 +350,31 @@ class TestFromNumpy:
         assert result[0].chrom == "chr1"
         assert result[0].start == 100
         assert result[0].end == 200
+
+    def test_from_numpy_chrom_array(self):
+        """Test one chromosome per interval, as names or as codes."""
+        import numpy as np
+        starts = np.array([100, 50, 300], dtype=np.int64)
+        ends = np.array([200, 80, 400], dtype=np.int64)
+        names = np.array(["chr1", "chr2", "chr1"])
+        result = pygrit.from_numpy(names, starts=starts, ends=ends)
+        assert [iv.chrom for iv in result.to_list()] == ["chr1", "chr2", "chr1"]
+        assert result[1].start == 50
+
+        codes = np.array([0, 1, 0], dtype=np.int8)
+        result = pygrit.from_numpy(codes, starts=starts, ends=ends, categories=["chr1", "chr2"])
+        assert [iv.chrom for iv in result.to_list()] == ["chr1", "chr2", "chr1"]
+
+    def test_from_numpy_invalid(self):
+        """Test that mismatched or out-of-range inputs raise ValueError."""
+        import numpy as np
+        starts = np.array([100, 300], dtype=np.int64)
+        ends = np.array([200, 400], dtype=np.int64)
+        with pytest.raises(ValueError):
+            pygrit.from_numpy(["chr1"], starts=starts, ends=ends)
+        with pytest.raises(ValueError):
+            pygrit.from_numpy(np.array([0, 2]), starts=starts, ends=ends, categories=["chr1"])
+        with pytest.raises(ValueError):
+            pygrit.from_numpy("chr1", starts=starts)
+        with pytest.raises(ValueError):
+            pygrit.from_numpy("chr1", starts=-starts, ends=ends)
@@ -1,6 +1,9 @@
 //! Streaming BED file parser.
 
-use crate::config::normalize_end;
+use crate::config::{
+    chrom_allowed, chrom_filter_active, delimiter, normalize_end, record_irregularity, Delimiter,
+    Irregularity,
+};
 use crate::interval::{BedRecord, Interval, Strand};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
@@ -27,6 +30,7 @@ pub struct BedReader<R: Read> {
     reader: BufReader<R>,
     line_number: usize,
     buffer: String,
+    seen_record: bool,
 }
 
 impl BedReader<File> {
@@ -44,6 +48,7 @@ impl<R: Read> BedReader<R> {
             reader: BufReader::new(reader),
             line_number: 0,
             buffer: String::with_capacity(1024),
+            seen_record: false,
         }
     }
 
@@ -53,6 +58,7 @@ impl<R: Read> BedReader<R> {
             reader: BufReader::with_capacity(capacity, reader),
             line_number: 0,
             buffer: String::with_capacity(1024),
+            seen_record: false,
         }
     }
 
@@ -66,23 +72,48 @@ impl<R: Read> BedReader<R> {
             }
             self.line_number += 1;
 
+            if self.buffer.ends_with("\r\n") {
+                record_irregularity(Irregularity::Crlf);
+            }
+
             // Skip empty lines and comments
             let line = self.buffer.trim();
-            if line.is_empty()
-                || line.starts_with('#')
-                || line.starts_with("track")
-                || line.starts_with("browser")
-            {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with("track") || line.starts_with("browser") {
+                // Concatenated UCSC tracks repeat these between records
+                if self.seen_record {
+                    record_irregularity(Irregularity::MidFileHeader);
+                }
                 continue;
             }
 
+            self.seen_record = true;
+            if chrom_filter_active() {
+                let chrom = line.split(|c: char| c.is_ascii_whitespace()).next();
+                if !chrom_allowed(chrom.unwrap_or_default().as_bytes()) {
+                    continue;
+                }
+            }
             return self.parse_line(line).map(Some);
         }
     }
 
     /// Parse a single BED line.
     fn parse_line(&self, line: &str) -> Result<BedRecord> {
-        let fields: Vec<&str> = line.split('\t').collect();
+        let fields: Vec<&str> = match delimiter() {
+            Delimiter::Tab => line.split('\t').collect(),
+            Delimiter::Space => line.split_whitespace().collect(),
+            Delimiter::Auto if line.contains('\t') => line.split('\t').collect(),
+            Delimiter::Auto => {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() >= 3 {
+                    record_irregularity(Irregularity::WhitespaceDelimited);
+                }
+                fields
+            }
+        };
 
         if fields.len() < 3 {
             return Err(BedError::Parse {
@@ -199,6 +230,43 @@ pub fn read_records<P: AsRef<Path>>(path: P) -> Result<Vec<BedRecord>> {
     reader.records().collect()
 }
 
+/// Whether a file looks like BedGraph: a `track type=bedGraph` line, or a
+/// first record of exactly four columns ending in a number.
+///
+/// Only the lines up to the first record are read. Stdin (`-`) and files
+/// that cannot be read are reported as not BedGraph.
+pub fn is_bedgraph<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    if path.as_os_str() == "-" {
+        return false;
+    }
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            return false;
+        };
+        let line = line.trim_end();
+        if line.starts_with("track") {
+            if line.contains("type=bedGraph") {
+                return true;
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line.starts_with("browser") {
+            continue;
+        }
+        let fields: Vec<&str> = if line.contains('\t') {
+            line.split('\t').collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        return fields.len() == 4 && fields[3].parse::<f64>().is_ok_and(f64::is_finite);
+    }
+    false
+}
+
 /// Parse intervals from a string (useful for testing).
 pub fn parse_intervals(content: &str) -> Result<Vec<Interval>> {
     let reader = BedReader::new(content.as_bytes());
@@ -297,6 +365,26 @@ mod tests {
         assert_eq!(intervals.len(), 1);
     }
 
+    #[test]
+    fn test_parse_crlf_and_whitespace() {
+        let content =
+            "chr1\t100\t200\r\nchr1 300 400 name 5 -\r\ntrack name=second\nchr2\t10\t20\n";
+        let reader = BedReader::new(content.as_bytes());
+        let records: Vec<_> = reader.records().collect::<Result<_>>().unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].end(), 200);
+        assert_eq!(records[1].start(), 300);
+        assert_eq!(records[1].name, Some("name".to_string()));
+        assert_eq!(records[1].strand, Some(Strand::Minus));
+        assert_eq!(records[2].chrom(), "chr2");
+
+        let seen = crate::config::parse_irregularities();
+        assert!(seen.crlf_lines >= 2);
+        assert!(seen.whitespace_delimited_lines >= 1);
+        assert!(seen.mid_file_headers >= 1);
+    }
+
     #[test]
     fn test_invalid_bed() {
         let content = "chr1\t100\n"; // Only 2 fields
@@ -314,4 +402,24 @@ mod tests {
         assert_eq!(interval.start, 100);
         assert_eq!(interval.end, 200);
     }
+
+    #[test]
+    fn test_is_bedgraph() {
+        use std::io::Write;
+        let detect = |content: &str| {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(content.as_bytes()).unwrap();
+            file.flush().unwrap();
+            is_bedgraph(file.path())
+        };
+        assert!(detect("chr1\t0\t100\t1.5\n"));
+        assert!(detect(
+            "#c\ntrack type=bedGraph name=x\nchr1\t0\t100\tnan\n"
+        ));
+        assert!(detect("chr1 0 100 -2e-3\n"));
+        assert!(!detect("chr1\t0\t100\tpeak1\n"));
+        assert!(!detect("chr1\t0\t100\t5\t0\t+\n"));
+        assert!(!detect(""));
+        assert!(!is_bedgraph("-"));
+    }
 }
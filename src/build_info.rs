@@ -0,0 +1,94 @@
+//! Build metadata reported by `grit --version`.
+//!
+//! Commit, date, target and feature values are embedded by `build.rs`; CPU
+//! features are detected at runtime on the machine running the binary.
+
+use std::fmt::Write;
+
+/// Short git commit the binary was built from (`unknown` outside a checkout).
+pub const GIT_COMMIT: &str = env!("GRIT_GIT_COMMIT");
+
+/// UTC build date (YYYY-MM-DD).
+pub const BUILD_DATE: &str = env!("GRIT_BUILD_DATE");
+
+/// Target triple the binary was compiled for.
+pub const TARGET: &str = env!("GRIT_TARGET");
+
+/// Enabled cargo features, comma-separated, or `none`.
+pub const FEATURES: &str = env!("GRIT_FEATURES");
+
+/// SIMD-relevant CPU features available on this machine.
+///
+/// These are the instruction sets the byte-search routines used by the BED
+/// parsers (via `memchr`) can dispatch to at runtime.
+pub fn cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut found = Vec::new();
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("sse2") {
+            found.push("sse2");
+        }
+        if std::arch::is_x86_feature_detected!("sse4.2") {
+            found.push("sse4.2");
+        }
+        if std::arch::is_x86_feature_detected!("popcnt") {
+            found.push("popcnt");
+        }
+        if std::arch::is_x86_feature_detected!("avx2") {
+            found.push("avx2");
+        }
+        if std::arch::is_x86_feature_detected!("bmi2") {
+            found.push("bmi2");
+        }
+        if std::arch::is_x86_feature_detected!("avx512f") {
+            found.push("avx512f");
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            found.push("neon");
+        }
+    }
+
+    found
+}
+
+/// Multi-line version report: version, commit, build date, target, features.
+pub fn long_version() -> String {
+    let cpu = cpu_features();
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", crate::VERSION);
+    let _ = writeln!(out, "commit:       {}", GIT_COMMIT);
+    let _ = writeln!(out, "build date:   {}", BUILD_DATE);
+    let _ = writeln!(out, "target:       {}", TARGET);
+    let _ = writeln!(out, "features:     {}", FEATURES);
+    let _ = write!(
+        out,
+        "cpu features: {}",
+        if cpu.is_empty() {
+            "none".to_string()
+        } else {
+            cpu.join(",")
+        }
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_version() {
+        let version = long_version();
+        assert!(version.starts_with(crate::VERSION));
+        assert!(version.contains("commit:"));
+        assert_eq!(BUILD_DATE.len(), 10);
+        #[cfg(target_arch = "x86_64")]
+        assert!(cpu_features().contains(&"sse2"));
+    }
+}
@@ -3,11 +3,12 @@
 //! Uses O(n log m) algorithm per chromosome with binary search and limited scans.
 
 use crate::bed::{read_records, BedError};
+use crate::config::display_score;
 use crate::interval::{BedRecord, Interval};
-use crate::parallel::{group_by_chromosome, PARALLEL_THRESHOLD};
+use crate::parallel::{group_by_chromosome, PoolHandle, PARALLEL_THRESHOLD};
 use rayon::prelude::*;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 use std::path::Path;
 
@@ -41,8 +42,13 @@ pub struct ClosestCommand {
     pub opposite_strand: bool,
     /// Maximum distance to report
     pub max_distance: Option<u64>,
+    /// Report one tied B per value of this 1-based B column (e.g. 4 for the
+    /// name); only BED6 columns are kept in memory
+    pub dedup_b_col: Option<usize>,
     /// Process in parallel by chromosome
     pub parallel: bool,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
 }
 
 impl Default for ClosestCommand {
@@ -62,10 +68,18 @@ impl ClosestCommand {
             same_strand: false,
             opposite_strand: false,
             max_distance: None,
+            dedup_b_col: None,
             parallel: true,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Find the closest B intervals for each A interval.
     pub fn find_closest(
         &self,
@@ -166,13 +180,15 @@ impl ClosestCommand {
         let a_groups = group_by_chromosome(a_intervals);
         let b_groups = group_by_chromosome(b_intervals);
 
-        let results: Vec<Vec<ClosestResult>> = a_groups
-            .into_par_iter()
-            .map(|(chrom, a_ints)| {
-                let b_ints = b_groups.get(&chrom).map(|v| v.as_slice()).unwrap_or(&[]);
-                self.find_closest(&a_ints, b_ints)
-            })
-            .collect();
+        let results: Vec<Vec<ClosestResult>> = self.pool.install(|| {
+            a_groups
+                .into_par_iter()
+                .map(|(chrom, a_ints)| {
+                    let b_ints = b_groups.get(&chrom).map(|v| v.as_slice()).unwrap_or(&[]);
+                    self.find_closest(&a_ints, b_ints)
+                })
+                .collect()
+        });
 
         results.into_iter().flatten().collect()
     }
@@ -193,8 +209,12 @@ impl ClosestCommand {
         let b_records = read_records(b_path)?;
 
         // Group by chromosome
-        let a_by_chrom = Self::group_records_by_chrom_owned(a_records);
-        let b_by_chrom = Self::group_records_by_chrom_owned(b_records);
+        let a_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(a_records));
+        let b_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(b_records));
 
         // Get sorted list of chromosomes
         let mut chroms: Vec<_> = a_by_chrom.keys().cloned().collect();
@@ -210,23 +230,29 @@ impl ClosestCommand {
                 let mut buf = Vec::with_capacity(64 * 1024);
                 if let Some(a_list) = a_by_chrom.get(chrom) {
                     let b_list = b_by_chrom.get(chrom);
-                    self.closest_chromosome_optimized(a_list, b_list, &mut buf);
+                    self.closest_chromosome_optimized(a_list, b_list, |a, c| {
+                        self.output_closest_to_buf(&mut buf, a, c)
+                    });
                 }
                 output.write_all(&buf).map_err(BedError::Io)?;
             }
         } else {
             // Parallel processing for large datasets
-            let results: Vec<Vec<u8>> = chroms
-                .par_iter()
-                .map(|chrom| {
-                    let mut buf = Vec::with_capacity(64 * 1024);
-                    if let Some(a_list) = a_by_chrom.get(chrom) {
-                        let b_list = b_by_chrom.get(chrom);
-                        self.closest_chromosome_optimized(a_list, b_list, &mut buf);
-                    }
-                    buf
-                })
-                .collect();
+            let results: Vec<Vec<u8>> = self.pool.install(|| {
+                chroms
+                    .par_iter()
+                    .map(|chrom| {
+                        let mut buf = Vec::with_capacity(64 * 1024);
+                        if let Some(a_list) = a_by_chrom.get(chrom) {
+                            let b_list = b_by_chrom.get(chrom);
+                            self.closest_chromosome_optimized(a_list, b_list, |a, c| {
+                                self.output_closest_to_buf(&mut buf, a, c)
+                            });
+                        }
+                        buf
+                    })
+                    .collect()
+            });
 
             // Write results in chromosome order
             for buf in results {
@@ -237,6 +263,45 @@ impl ClosestCommand {
         Ok(())
     }
 
+    /// Compute the distribution of A-to-nearest-B distances instead of pairs.
+    ///
+    /// Distances are the same values reported by `-d`; A records with no
+    /// eligible B are counted separately.
+    pub fn distance_histogram<P: AsRef<Path>>(
+        &self,
+        a_path: P,
+        b_path: P,
+        bin_size: u64,
+    ) -> Result<DistanceHistogram, BedError> {
+        let a_records = read_records(a_path)?;
+        let b_records = read_records(b_path)?;
+        let a_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(a_records));
+        let b_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(b_records));
+
+        let histograms: Vec<DistanceHistogram> = self.pool.install(|| {
+            a_by_chrom
+                .par_iter()
+                .map(|(chrom, a_list)| {
+                    let mut hist = DistanceHistogram::new(bin_size);
+                    self.closest_chromosome_optimized(a_list, b_by_chrom.get(chrom), |_, c| {
+                        hist.add(c.first().map(|(_, d)| d.unsigned_abs()))
+                    });
+                    hist
+                })
+                .collect()
+        });
+
+        let mut result = DistanceHistogram::new(bin_size);
+        for hist in histograms {
+            result.merge(hist);
+        }
+        Ok(result)
+    }
+
     /// Optimized closest search for a single chromosome.
     ///
     /// Algorithm:
@@ -245,18 +310,22 @@ impl ClosestCommand {
     ///    a. Binary search for downstream B: O(log m)
     ///    b. Binary search for upstream B using secondary end-sorted index: O(log m)
     ///    c. Scan limited window for overlaps: O(k) where k = overlapping count
-    fn closest_chromosome_optimized(
+    ///
+    /// `emit` is called once per A record with its closest B records (empty if none).
+    fn closest_chromosome_optimized<F>(
         &self,
         a_sorted: &[BedRecord],
         b_sorted: Option<&Vec<BedRecord>>,
-        output: &mut Vec<u8>,
-    ) {
+        mut emit: F,
+    ) where
+        F: FnMut(&BedRecord, &[(&BedRecord, i64)]),
+    {
         let b_sorted = match b_sorted {
             Some(b) if !b.is_empty() => b,
             _ => {
-                // No B intervals - output "no closest" for all A
+                // No B intervals - report "no closest" for all A
                 for a_rec in a_sorted {
-                    self.write_no_closest_to_buf(output, a_rec);
+                    emit(a_rec, &[]);
                 }
                 return;
             }
@@ -413,17 +482,32 @@ impl ClosestCommand {
             self.apply_tie_handling(&mut candidates);
 
             // ========== Output results ==========
-            self.output_closest_to_buf(output, a_rec, &candidates);
+            emit(a_rec, &candidates);
         }
     }
 
-    /// Apply tie handling policy to candidates.
+    /// Apply the dedup column and tie handling policy to candidates.
     #[inline]
     fn apply_tie_handling(&self, candidates: &mut Vec<(&BedRecord, i64)>) {
         if candidates.is_empty() {
             return;
         }
 
+        if let Some(col) = self.dedup_b_col {
+            let mut seen: Vec<String> = Vec::new();
+            candidates.retain(|(b_rec, _)| {
+                let line = b_rec.to_string();
+                match line.split('\t').nth(col.saturating_sub(1)) {
+                    Some(key) if seen.iter().any(|k| k == key) => false,
+                    Some(key) if key != "." => {
+                        seen.push(key.to_string());
+                        true
+                    }
+                    _ => true,
+                }
+            });
+        }
+
         match self.tie_handling {
             TieHandling::All => {} // Keep all
             TieHandling::First => {
@@ -463,7 +547,7 @@ impl ClosestCommand {
         if let Some(ref name) = a_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = a_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = a_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -493,7 +577,7 @@ impl ClosestCommand {
         if let Some(ref name) = a_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = a_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = a_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -511,7 +595,7 @@ impl ClosestCommand {
         if let Some(ref name) = b_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = b_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = b_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -560,6 +644,113 @@ impl ClosestResult {
     }
 }
 
+/// Distribution of A-to-nearest-B distances, binned at a fixed width.
+#[derive(Debug, Clone)]
+pub struct DistanceHistogram {
+    /// Width of each bin in bp
+    pub bin_size: u64,
+    /// Count per bin, keyed by bin index
+    pub bins: BTreeMap<u64, u64>,
+    /// Number of A records with no eligible B
+    pub no_closest: u64,
+    distances: Vec<u64>,
+}
+
+impl DistanceHistogram {
+    pub fn new(bin_size: u64) -> Self {
+        Self {
+            bin_size: bin_size.max(1),
+            bins: BTreeMap::new(),
+            no_closest: 0,
+            distances: Vec::new(),
+        }
+    }
+
+    /// Record the nearest distance for one A record.
+    pub fn add(&mut self, distance: Option<u64>) {
+        match distance {
+            Some(d) => {
+                *self.bins.entry(d / self.bin_size).or_insert(0) += 1;
+                self.distances.push(d);
+            }
+            None => self.no_closest += 1,
+        }
+    }
+
+    /// Fold another histogram with the same bin size into this one.
+    pub fn merge(&mut self, other: DistanceHistogram) {
+        for (bin, count) in other.bins {
+            *self.bins.entry(bin).or_insert(0) += count;
+        }
+        self.no_closest += other.no_closest;
+        self.distances.extend(other.distances);
+    }
+
+    /// Number of A records with a nearest B.
+    pub fn count(&self) -> u64 {
+        self.distances.len() as u64
+    }
+
+    /// Nearest-rank quantile of the recorded distances (`q` in 0.0-1.0).
+    pub fn quantile(&mut self, q: f64) -> Option<u64> {
+        if self.distances.is_empty() {
+            return None;
+        }
+        self.distances.sort_unstable();
+        let rank = (q.clamp(0.0, 1.0) * (self.distances.len() - 1) as f64).round() as usize;
+        Some(self.distances[rank])
+    }
+
+    /// Mean of the recorded distances.
+    pub fn mean(&self) -> Option<f64> {
+        if self.distances.is_empty() {
+            return None;
+        }
+        Some(self.distances.iter().sum::<u64>() as f64 / self.distances.len() as f64)
+    }
+
+    /// Write the non-empty bins followed by a summary line.
+    pub fn write<W: Write>(&mut self, output: &mut W) -> Result<(), BedError> {
+        let total = self.count();
+        writeln!(output, "bin_start\tbin_end\tcount\tfraction")?;
+        for (&bin, &count) in &self.bins {
+            writeln!(
+                output,
+                "{}\t{}\t{}\t{:.7}",
+                bin * self.bin_size,
+                (bin + 1) * self.bin_size,
+                count,
+                count as f64 / total as f64
+            )?;
+        }
+
+        let fmt = |v: Option<u64>| v.map_or_else(|| ".".to_string(), |v| v.to_string());
+        let (min, q25, median, q75, q90, max) = (
+            self.quantile(0.0),
+            self.quantile(0.25),
+            self.quantile(0.5),
+            self.quantile(0.75),
+            self.quantile(0.9),
+            self.quantile(1.0),
+        );
+        writeln!(
+            output,
+            "# n={} no_closest={} min={} q25={} median={} q75={} q90={} max={} mean={}",
+            total,
+            self.no_closest,
+            fmt(min),
+            fmt(q25),
+            fmt(median),
+            fmt(q75),
+            fmt(q90),
+            fmt(max),
+            self.mean()
+                .map_or_else(|| ".".to_string(), |m| format!("{:.2}", m))
+        )?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -686,4 +877,60 @@ mod tests {
 
         assert_eq!(results.len(), 2);
     }
+
+    #[test]
+    fn test_distance_histogram() {
+        let mut hist = DistanceHistogram::new(100);
+        for d in [0, 50, 101, 150, 900] {
+            hist.add(Some(d));
+        }
+        hist.add(None);
+
+        assert_eq!(hist.count(), 5);
+        assert_eq!(hist.no_closest, 1);
+        assert_eq!(hist.bins.get(&0), Some(&2));
+        assert_eq!(hist.bins.get(&1), Some(&2));
+        assert_eq!(hist.bins.get(&9), Some(&1));
+        assert_eq!(hist.quantile(0.5), Some(101));
+        assert_eq!(hist.quantile(1.0), Some(900));
+
+        let mut out = Vec::new();
+        hist.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("bin_start\tbin_end\tcount\tfraction\n0\t100\t2\t0.4000000\n"));
+        assert!(text.ends_with(
+            "# n=5 no_closest=1 min=0 q25=50 median=101 q75=150 q90=900 max=900 mean=240.20\n"
+        ));
+    }
+
+    #[test]
+    fn test_dedup_b_col() {
+        let named = |start, name: &str| {
+            let mut rec = BedRecord::new("chr1", start, start + 100);
+            rec.name = Some(name.to_string());
+            rec
+        };
+        let b = [
+            named(300, "g1"),
+            named(300, "g1"),
+            named(300, "."),
+            named(300, "."),
+            named(300, "g2"),
+        ];
+
+        let mut cmd = ClosestCommand::new();
+        cmd.dedup_b_col = Some(4);
+        let mut candidates: Vec<(&BedRecord, i64)> = b.iter().map(|r| (r, 101)).collect();
+        cmd.apply_tie_handling(&mut candidates);
+        let names: Vec<&str> = candidates
+            .iter()
+            .map(|(r, _)| r.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["g1", ".", ".", "g2"]);
+
+        cmd.tie_handling = TieHandling::Last;
+        let mut candidates: Vec<(&BedRecord, i64)> = b.iter().map(|r| (r, 101)).collect();
+        cmd.apply_tie_handling(&mut candidates);
+        assert_eq!(candidates[0].0.name.as_deref(), Some("g2"));
+    }
 }
@@ -6,7 +6,7 @@
 #![allow(clippy::needless_range_loop)]
 
 use crate::bed::{BedError, BedReader};
-use crate::genome::Genome;
+use crate::genome::{Genome, GenomeMask};
 use crate::interval::Interval;
 use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
 use std::fs::File;
@@ -20,6 +20,8 @@ pub struct ComplementCommand {
     pub genome_only: bool,
     /// Assume input is sorted in genome order (enables O(1) memory streaming)
     pub assume_sorted: bool,
+    /// Only report complement regions inside this mask
+    pub mask: Option<GenomeMask>,
 }
 
 impl Default for ComplementCommand {
@@ -33,6 +35,7 @@ impl ComplementCommand {
         Self {
             genome_only: true,
             assume_sorted: false,
+            mask: None,
         }
     }
 
@@ -42,6 +45,12 @@ impl ComplementCommand {
         self
     }
 
+    /// Restrict output to regions allowed by a mask (builder pattern).
+    pub fn with_mask(mut self, mask: GenomeMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
     /// Streaming complement - assumes input is sorted by chrom, start, end.
     /// O(n) single pass through input, outputs in genome file order.
     pub fn complement_streaming<R: Read, W: Write>(
@@ -88,28 +97,25 @@ impl ComplementCommand {
 
             if let Some(chrom_gaps) = gaps.get(chrom) {
                 // Output accumulated gaps
-                for (start, end) in chrom_gaps {
-                    writeln!(buf_output, "{}\t{}\t{}", chrom, start, end).map_err(BedError::Io)?;
+                for &(start, end) in chrom_gaps {
+                    self.write_gap(&mut buf_output, chrom, start, end)?;
                 }
                 // Output trailing gap
                 if let Some(end_pos) = last_end {
                     if end_pos < chrom_size {
-                        writeln!(buf_output, "{}\t{}\t{}", chrom, end_pos, chrom_size)
-                            .map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, chrom, end_pos, chrom_size)?;
                     }
                 }
             } else if let Some(end_pos) = last_end {
                 // We saw intervals on this chromosome but no gaps (e.g., interval starts at 0)
                 // Just output trailing gap if any
                 if end_pos < chrom_size {
-                    writeln!(buf_output, "{}\t{}\t{}", chrom, end_pos, chrom_size)
-                        .map_err(BedError::Io)?;
+                    self.write_gap(&mut buf_output, chrom, end_pos, chrom_size)?;
                 }
             } else {
                 // No intervals on this chromosome - entire chromosome is complement
                 if chrom_size > 0 {
-                    writeln!(buf_output, "{}\t{}\t{}", chrom, 0, chrom_size)
-                        .map_err(BedError::Io)?;
+                    self.write_gap(&mut buf_output, chrom, 0, chrom_size)?;
                 }
             }
         }
@@ -166,13 +172,12 @@ impl ComplementCommand {
                         let c = chroms[i];
                         let size = genome.chrom_size(c).unwrap();
                         if size > 0 {
-                            writeln!(buf_output, "{}\t0\t{}", c, size).map_err(BedError::Io)?;
+                            self.write_gap(&mut buf_output, c, 0, size)?;
                         }
                     }
                     // Output leading gap on current chromosome
                     if record.start() > 0 {
-                        writeln!(buf_output, "{}\t0\t{}", chrom, record.start())
-                            .map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, chrom, 0, record.start())?;
                     }
                     current_chrom_idx = Some(chrom_idx);
                     last_end = record.end().min(chrom_size);
@@ -183,8 +188,7 @@ impl ComplementCommand {
                     let prev_chrom = chroms[prev_idx];
                     let prev_size = genome.chrom_size(prev_chrom).unwrap();
                     if last_end < prev_size {
-                        writeln!(buf_output, "{}\t{}\t{}", prev_chrom, last_end, prev_size)
-                            .map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, prev_chrom, last_end, prev_size)?;
                     }
 
                     // 2. Output full chromosomes between prev and current
@@ -192,14 +196,13 @@ impl ComplementCommand {
                         let c = chroms[i];
                         let size = genome.chrom_size(c).unwrap();
                         if size > 0 {
-                            writeln!(buf_output, "{}\t0\t{}", c, size).map_err(BedError::Io)?;
+                            self.write_gap(&mut buf_output, c, 0, size)?;
                         }
                     }
 
                     // 3. Output leading gap on current chromosome
                     if record.start() > 0 {
-                        writeln!(buf_output, "{}\t0\t{}", chrom, record.start())
-                            .map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, chrom, 0, record.start())?;
                     }
 
                     current_chrom_idx = Some(chrom_idx);
@@ -208,8 +211,7 @@ impl ComplementCommand {
                 Some(_) => {
                     // Same chromosome - output gap if there's space
                     if record.start() > last_end {
-                        writeln!(buf_output, "{}\t{}\t{}", chrom, last_end, record.start())
-                            .map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, chrom, last_end, record.start())?;
                     }
                     last_end = last_end.max(record.end().min(chrom_size));
                 }
@@ -223,8 +225,7 @@ impl ComplementCommand {
                 let last_chrom = chroms[last_idx];
                 let last_size = genome.chrom_size(last_chrom).unwrap();
                 if last_end < last_size {
-                    writeln!(buf_output, "{}\t{}\t{}", last_chrom, last_end, last_size)
-                        .map_err(BedError::Io)?;
+                    self.write_gap(&mut buf_output, last_chrom, last_end, last_size)?;
                 }
 
                 // Full chromosomes after the last one
@@ -232,7 +233,7 @@ impl ComplementCommand {
                     let c = chroms[i];
                     let size = genome.chrom_size(c).unwrap();
                     if size > 0 {
-                        writeln!(buf_output, "{}\t0\t{}", c, size).map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, c, 0, size)?;
                     }
                 }
             }
@@ -241,7 +242,7 @@ impl ComplementCommand {
                 for c in &chroms {
                     let size = genome.chrom_size(c).unwrap();
                     if size > 0 {
-                        writeln!(buf_output, "{}\t0\t{}", c, size).map_err(BedError::Io)?;
+                        self.write_gap(&mut buf_output, c, 0, size)?;
                     }
                 }
             }
@@ -323,7 +324,7 @@ impl ComplementCommand {
                         let c = chroms[i];
                         let size = genome.chrom_size(c).unwrap();
                         if size > 0 {
-                            Self::write_interval_fast(
+                            self.write_interval_fast(
                                 &mut buf_output,
                                 c.as_bytes(),
                                 0,
@@ -334,7 +335,7 @@ impl ComplementCommand {
                     }
                     // Output leading gap on current chromosome
                     if start > 0 {
-                        Self::write_interval_fast(&mut buf_output, chrom, 0, start, &mut itoa_buf)?;
+                        self.write_interval_fast(&mut buf_output, chrom, 0, start, &mut itoa_buf)?;
                     }
                     current_chrom_idx = Some(chrom_idx);
                     last_end = end.min(chrom_size);
@@ -344,7 +345,7 @@ impl ComplementCommand {
                     let prev_chrom = chroms[prev_idx];
                     let prev_size = genome.chrom_size(prev_chrom).unwrap();
                     if last_end < prev_size {
-                        Self::write_interval_fast(
+                        self.write_interval_fast(
                             &mut buf_output,
                             prev_chrom.as_bytes(),
                             last_end,
@@ -358,7 +359,7 @@ impl ComplementCommand {
                         let c = chroms[i];
                         let size = genome.chrom_size(c).unwrap();
                         if size > 0 {
-                            Self::write_interval_fast(
+                            self.write_interval_fast(
                                 &mut buf_output,
                                 c.as_bytes(),
                                 0,
@@ -370,7 +371,7 @@ impl ComplementCommand {
 
                     // Output leading gap on current chromosome
                     if start > 0 {
-                        Self::write_interval_fast(&mut buf_output, chrom, 0, start, &mut itoa_buf)?;
+                        self.write_interval_fast(&mut buf_output, chrom, 0, start, &mut itoa_buf)?;
                     }
 
                     current_chrom_idx = Some(chrom_idx);
@@ -379,7 +380,7 @@ impl ComplementCommand {
                 Some(_) => {
                     // Same chromosome - output gap if there's space
                     if start > last_end {
-                        Self::write_interval_fast(
+                        self.write_interval_fast(
                             &mut buf_output,
                             chrom,
                             last_end,
@@ -398,7 +399,7 @@ impl ComplementCommand {
                 let last_chrom = chroms[last_idx];
                 let last_size = genome.chrom_size(last_chrom).unwrap();
                 if last_end < last_size {
-                    Self::write_interval_fast(
+                    self.write_interval_fast(
                         &mut buf_output,
                         last_chrom.as_bytes(),
                         last_end,
@@ -411,7 +412,7 @@ impl ComplementCommand {
                     let c = chroms[i];
                     let size = genome.chrom_size(c).unwrap();
                     if size > 0 {
-                        Self::write_interval_fast(
+                        self.write_interval_fast(
                             &mut buf_output,
                             c.as_bytes(),
                             0,
@@ -426,7 +427,7 @@ impl ComplementCommand {
                 for c in &chroms {
                     let size = genome.chrom_size(c).unwrap();
                     if size > 0 {
-                        Self::write_interval_fast(
+                        self.write_interval_fast(
                             &mut buf_output,
                             c.as_bytes(),
                             0,
@@ -442,9 +443,48 @@ impl ComplementCommand {
         Ok(())
     }
 
-    /// Fast interval output using itoa.
+    /// Write a complement interval, clipped to the mask if one is set.
+    #[inline]
+    fn write_gap<W: Write>(
+        &self,
+        output: &mut W,
+        chrom: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<(), BedError> {
+        match &self.mask {
+            Some(mask) => {
+                for (s, e) in mask.clip(chrom, start, end) {
+                    writeln!(output, "{}\t{}\t{}", chrom, s, e).map_err(BedError::Io)?;
+                }
+            }
+            None => writeln!(output, "{}\t{}\t{}", chrom, start, end).map_err(BedError::Io)?,
+        }
+        Ok(())
+    }
+
+    /// Fast interval output using itoa, clipped to the mask if one is set.
     #[inline]
     fn write_interval_fast<W: Write>(
+        &self,
+        output: &mut W,
+        chrom: &[u8],
+        start: u64,
+        end: u64,
+        itoa_buf: &mut itoa::Buffer,
+    ) -> Result<(), BedError> {
+        if let Some(mask) = &self.mask {
+            let chrom_str = std::str::from_utf8(chrom).unwrap_or_default();
+            for (s, e) in mask.clip(chrom_str, start, end) {
+                Self::write_bed3_fast(output, chrom, s, e, itoa_buf)?;
+            }
+            return Ok(());
+        }
+        Self::write_bed3_fast(output, chrom, start, end, itoa_buf)
+    }
+
+    #[inline]
+    fn write_bed3_fast<W: Write>(
         output: &mut W,
         chrom: &[u8],
         start: u64,
@@ -501,7 +541,16 @@ impl ComplementCommand {
             }
         }
 
-        result
+        match &self.mask {
+            Some(mask) => result
+                .iter()
+                .flat_map(|gap| {
+                    mask.clip(&gap.chrom, gap.start, gap.end)
+                        .map(|(s, e)| Interval::new(gap.chrom.clone(), s, e))
+                })
+                .collect(),
+            None => result,
+        }
     }
 
     /// Find gaps in a sorted list of intervals on a single chromosome.
@@ -755,4 +804,33 @@ mod tests {
             String::from_utf8(output_sorted).unwrap()
         );
     }
+
+    #[test]
+    fn test_complement_with_mask() {
+        let genome = make_genome();
+        let intervals = vec![make_interval("chr1", 100, 200)];
+        let mask = GenomeMask::from_intervals(&[
+            make_interval("chr1", 50, 150),
+            make_interval("chr1", 900, 2000),
+        ]);
+        let cmd = ComplementCommand::new().with_mask(mask.clone());
+
+        let result = cmd.complement(&intervals, &genome);
+        assert_eq!(
+            result,
+            vec![
+                make_interval("chr1", 50, 100),
+                make_interval("chr1", 900, 1000)
+            ]
+        );
+
+        let input = "chr1\t100\t200\n";
+        let mut output = Vec::new();
+        cmd.complement_fast(input.as_bytes(), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t50\t100\nchr1\t900\t1000\n"
+        );
+    }
 }
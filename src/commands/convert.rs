@@ -0,0 +1,510 @@
+//! Convert command implementation.
+//!
+//! Translates interval files between BED and the formats that quantifiers
+//! and viewers read, so grit output can be handed to them without one-off
+//! scripts:
+//!
+//! - GFF3, with the BED name as the `Name` attribute and the score and
+//!   strand in their own columns.
+//! - SAF, the simplified annotation format of featureCounts
+//!   (`GeneID, Chr, Start, End, Strand`); records sharing a name become one
+//!   meta-feature there. SAF has no score column.
+//! - IGV locus lists (`chr1:101-200`, one per line), as pasted into the IGV
+//!   search box or loaded as a gene list. Names and scores are not kept.
+//!
+//! Every format converts to every other through the columns they share:
+//! chromosome, coordinates, name, score and strand. GFF, SAF and IGV count
+//! from 1 with inclusive ends, BED from 0 with exclusive ends; the
+//! conversion shifts the start so the same bases are covered. BED columns
+//! after the strand, and GFF attributes other than the name, are dropped.
+//! Converting to BED and back is lossless for what the other format holds.
+
+use crate::bed::BedError;
+use crate::interchange::stdin_reader;
+use crate::output_limit::limited_stdout;
+use crate::streaming::parsing::should_skip_line;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Header line of SAF files.
+const SAF_HEADER: &str = "GeneID\tChr\tStart\tEnd\tStrand";
+
+/// Interval file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConvertFormat {
+    #[default]
+    Bed,
+    Gff,
+    Saf,
+    Igv,
+}
+
+impl ConvertFormat {
+    /// Parse a format name as accepted by `--from` and `--to`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bed" => Some(Self::Bed),
+            "gff" | "gff3" => Some(Self::Gff),
+            "saf" => Some(Self::Saf),
+            "igv" => Some(Self::Igv),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of a file from its extension; anything unknown is
+    /// BED.
+    pub fn detect<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("gff" | "gff3") => Self::Gff,
+            Some("saf") => Self::Saf,
+            Some("igv" | "loci") => Self::Igv,
+            _ => Self::Bed,
+        }
+    }
+}
+
+/// The columns the formats share, in BED coordinates.
+#[derive(Debug, Clone, PartialEq)]
+struct Feature<'a> {
+    chrom: &'a str,
+    start: u64,
+    end: u64,
+    name: Option<String>,
+    score: Option<&'a str>,
+    strand: Option<&'a str>,
+}
+
+/// Convert command configuration.
+#[derive(Debug, Clone)]
+pub struct ConvertCommand {
+    pub from: ConvertFormat,
+    pub to: ConvertFormat,
+    /// GFF source column (column 2) of written features
+    pub source: String,
+    /// GFF type (column 3) of written features; when reading GFF, only
+    /// features of this type are converted
+    pub feature_type: Option<String>,
+}
+
+impl Default for ConvertCommand {
+    fn default() -> Self {
+        Self::new(ConvertFormat::Bed, ConvertFormat::Bed)
+    }
+}
+
+impl ConvertCommand {
+    pub fn new(from: ConvertFormat, to: ConvertFormat) -> Self {
+        Self {
+            from,
+            to,
+            source: "grit".to_string(),
+            feature_type: None,
+        }
+    }
+
+    /// Set the GFF source column (builder pattern).
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// Set the GFF feature type to write, and to select when reading GFF
+    /// (builder pattern).
+    pub fn with_feature_type(mut self, feature_type: Option<String>) -> Self {
+        self.feature_type = feature_type;
+        self
+    }
+
+    /// Convert a file.
+    ///
+    /// Returns the number of records written.
+    pub fn run<P: AsRef<Path>, W: Write>(&self, input: P, output: &mut W) -> Result<u64, BedError> {
+        let file = File::open(input)?;
+        self.convert_reader(file, output)
+    }
+
+    /// Convert stdin to stdout.
+    pub fn run_stdio(&self) -> Result<u64, BedError> {
+        let mut handle = limited_stdout();
+        self.convert_reader(stdin_reader(), &mut handle)
+    }
+
+    /// Convert a stream.
+    ///
+    /// Comment and header lines of the input are skipped; the output gets
+    /// the header its format requires (`##gff-version 3` for GFF, the
+    /// column names for SAF).
+    pub fn convert_reader<R: Read, W: Write>(
+        &self,
+        input: R,
+        output: &mut W,
+    ) -> Result<u64, BedError> {
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        let mut reader = BufReader::with_capacity(256 * 1024, input);
+        let mut line = String::with_capacity(1024);
+        let mut line_num = 0;
+        let mut written = 0;
+
+        match self.to {
+            ConvertFormat::Gff => writeln!(out, "##gff-version 3")?,
+            ConvertFormat::Saf => writeln!(out, "{}", SAF_HEADER)?,
+            ConvertFormat::Bed | ConvertFormat::Igv => {}
+        }
+
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            let text = line.trim_end_matches(['\n', '\r']);
+            let parse_error = |message: String| BedError::Parse {
+                line: line_num,
+                message,
+            };
+
+            let feature = match self.from {
+                ConvertFormat::Bed => read_bed(text),
+                ConvertFormat::Gff => read_gff(text, self.feature_type.as_deref()),
+                ConvertFormat::Saf => read_saf(text),
+                ConvertFormat::Igv => read_igv(text),
+            }
+            .map_err(parse_error)?;
+            let Some(feature) = feature else {
+                continue;
+            };
+            if feature.start > feature.end {
+                return Err(parse_error(format!(
+                    "start {} is after end {}",
+                    feature.start, feature.end
+                )));
+            }
+            if feature.start == feature.end && self.to != ConvertFormat::Bed {
+                return Err(parse_error(format!(
+                    "zero-length interval at {}:{} cannot be written with 1-based inclusive coordinates",
+                    feature.chrom, feature.start
+                )));
+            }
+
+            match self.to {
+                ConvertFormat::Bed => write_bed(&mut out, &feature)?,
+                ConvertFormat::Gff => self.write_gff(&mut out, &feature)?,
+                ConvertFormat::Saf => write_saf(&mut out, &feature)?,
+                ConvertFormat::Igv => write_igv(&mut out, &feature)?,
+            }
+            written += 1;
+        }
+
+        out.flush()?;
+        Ok(written)
+    }
+
+    fn write_gff<W: Write>(&self, out: &mut W, f: &Feature) -> std::io::Result<()> {
+        let attributes = match &f.name {
+            Some(name) => format!("Name={}", gff_escape(name)),
+            None => ".".to_string(),
+        };
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t.\t{}",
+            f.chrom,
+            self.source,
+            self.feature_type.as_deref().unwrap_or("region"),
+            f.start + 1,
+            f.end,
+            f.score.unwrap_or("."),
+            f.strand.unwrap_or("."),
+            attributes
+        )
+    }
+}
+
+/// Split a line on tabs, or on whitespace if it has no tab.
+fn split_fields(text: &str) -> Vec<&str> {
+    if text.contains('\t') {
+        text.split('\t').collect()
+    } else {
+        text.split_whitespace().collect()
+    }
+}
+
+fn parse_position(field: &str, what: &str) -> Result<u64, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid {}: '{}'", what, field))
+}
+
+/// Parse a 1-based start, which must be at least 1.
+fn parse_one_based(field: &str) -> Result<u64, String> {
+    match parse_position(field, "start")? {
+        0 => Err("start 0 in a 1-based format".to_string()),
+        start => Ok(start - 1),
+    }
+}
+
+fn parse_strand(field: &str) -> Result<Option<&str>, String> {
+    match field {
+        "+" | "-" => Ok(Some(field)),
+        "." | "" => Ok(None),
+        _ => Err(format!("invalid strand '{}'", field)),
+    }
+}
+
+fn read_bed(text: &str) -> Result<Option<Feature<'_>>, String> {
+    if should_skip_line(text.as_bytes()) {
+        return Ok(None);
+    }
+    let fields = split_fields(text);
+    if fields.len() < 3 {
+        return Err("expected chrom, start and end columns".to_string());
+    }
+    let field = |i: usize| fields.get(i).copied().filter(|f| *f != ".");
+    Ok(Some(Feature {
+        chrom: fields[0],
+        start: parse_position(fields[1], "start")?,
+        end: parse_position(fields[2], "end")?,
+        name: field(3).map(String::from),
+        score: field(4),
+        strand: fields.get(5).map_or(Ok(None), |s| parse_strand(s))?,
+    }))
+}
+
+fn read_gff<'a>(text: &'a str, feature_type: Option<&str>) -> Result<Option<Feature<'a>>, String> {
+    if text.trim().is_empty() || text.starts_with('#') {
+        return Ok(None);
+    }
+    let fields: Vec<&str> = text.split('\t').collect();
+    if fields.len() < 9 {
+        return Err(format!("expected 9 GFF columns, got {}", fields.len()));
+    }
+    if feature_type.is_some_and(|t| t != fields[2]) {
+        return Ok(None);
+    }
+    // Name, falling back to ID, as GFF tools label features
+    let attribute = |key: &str| {
+        fields[8].split(';').find_map(|pair| {
+            let (k, v) = pair.trim().split_once('=')?;
+            (k == key).then(|| gff_unescape(v))
+        })
+    };
+    Ok(Some(Feature {
+        chrom: fields[0],
+        start: parse_one_based(fields[3])?,
+        end: parse_position(fields[4], "end")?,
+        name: attribute("Name").or_else(|| attribute("ID")),
+        score: Some(fields[5]).filter(|s| *s != "."),
+        strand: match fields[6] {
+            "?" => None,
+            strand => parse_strand(strand)?,
+        },
+    }))
+}
+
+fn read_saf(text: &str) -> Result<Option<Feature<'_>>, String> {
+    if text.trim().is_empty() || text.starts_with('#') || text.starts_with("GeneID\t") {
+        return Ok(None);
+    }
+    let fields = split_fields(text);
+    if fields.len() < 5 {
+        return Err(format!("expected 5 SAF columns, got {}", fields.len()));
+    }
+    Ok(Some(Feature {
+        chrom: fields[1],
+        start: parse_one_based(fields[2])?,
+        end: parse_position(fields[3], "end")?,
+        name: Some(fields[0].to_string()),
+        score: None,
+        strand: parse_strand(fields[4])?,
+    }))
+}
+
+fn read_igv(text: &str) -> Result<Option<Feature<'_>>, String> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+        return Ok(None);
+    }
+    let invalid = || format!("invalid locus '{}': expected chrom:start-end", text);
+    let (chrom, range) = text.rsplit_once(':').ok_or_else(invalid)?;
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let number = |v: &str| v.replace(',', "").parse::<u64>().map_err(|_| invalid());
+    let (start, end) = (number(start)?, number(end)?);
+    if chrom.is_empty() || start == 0 {
+        return Err(invalid());
+    }
+    Ok(Some(Feature {
+        chrom,
+        start: start - 1,
+        end,
+        name: None,
+        score: None,
+        strand: None,
+    }))
+}
+
+/// Write BED3, or BED4/BED6 when there is a name, score or strand to keep.
+/// Missing names and scores in BED6 are written as `.`, so converting back
+/// does not invent a score.
+fn write_bed<W: Write>(out: &mut W, f: &Feature) -> std::io::Result<()> {
+    write!(out, "{}\t{}\t{}", f.chrom, f.start, f.end)?;
+    let name = f.name.as_deref().unwrap_or(".");
+    if f.score.is_some() || f.strand.is_some() {
+        write!(
+            out,
+            "\t{}\t{}\t{}",
+            name,
+            f.score.unwrap_or("."),
+            f.strand.unwrap_or(".")
+        )?;
+    } else if f.name.is_some() {
+        write!(out, "\t{}", name)?;
+    }
+    writeln!(out)
+}
+
+fn write_saf<W: Write>(out: &mut W, f: &Feature) -> std::io::Result<()> {
+    // featureCounts needs an id; unnamed records get their own locus
+    let id = match &f.name {
+        Some(name) => name.clone(),
+        None => format!("{}:{}-{}", f.chrom, f.start + 1, f.end),
+    };
+    writeln!(
+        out,
+        "{}\t{}\t{}\t{}\t{}",
+        id,
+        f.chrom,
+        f.start + 1,
+        f.end,
+        f.strand.unwrap_or(".")
+    )
+}
+
+fn write_igv<W: Write>(out: &mut W, f: &Feature) -> std::io::Result<()> {
+    writeln!(out, "{}:{}-{}", f.chrom, f.start + 1, f.end)
+}
+
+/// Percent-encode the characters GFF3 reserves in attribute values.
+fn gff_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ';' | '=' | '&' | ',' | '%' | '\t' | '\n' | '\r' => {
+                escaped.push_str(&format!("%{:02X}", c as u32))
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Decode `%XX` escapes of a GFF3 attribute value.
+fn gff_unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(from: ConvertFormat, to: ConvertFormat, input: &str) -> Result<String, BedError> {
+        let mut output = Vec::new();
+        ConvertCommand::new(from, to).convert_reader(input.as_bytes(), &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    const BED: &str = "track name=peaks\n\
+                       chr1\t99\t200\tpeak;1\t7.5\t+\n\
+                       chr2\t0\t50\n\
+                       chr2\t60\t80\tp3\n";
+
+    #[test]
+    fn test_bed_to_each_format() {
+        use ConvertFormat::*;
+        assert_eq!(
+            convert(Bed, Gff, BED).unwrap(),
+            "##gff-version 3\n\
+             chr1\tgrit\tregion\t100\t200\t7.5\t+\t.\tName=peak%3B1\n\
+             chr2\tgrit\tregion\t1\t50\t.\t.\t.\t.\n\
+             chr2\tgrit\tregion\t61\t80\t.\t.\t.\tName=p3\n"
+        );
+        assert_eq!(
+            convert(Bed, Saf, BED).unwrap(),
+            "GeneID\tChr\tStart\tEnd\tStrand\n\
+             peak;1\tchr1\t100\t200\t+\n\
+             chr2:1-50\tchr2\t1\t50\t.\n\
+             p3\tchr2\t61\t80\t.\n"
+        );
+        assert_eq!(
+            convert(Bed, Igv, BED).unwrap(),
+            "chr1:100-200\nchr2:1-50\nchr2:61-80\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trips() {
+        use ConvertFormat::*;
+        let records = "chr1\t99\t200\tpeak;1\t7.5\t+\nchr2\t0\t50\nchr2\t60\t80\tp3\n";
+        let gff = convert(Bed, Gff, records).unwrap();
+        assert_eq!(convert(Gff, Bed, &gff).unwrap(), records);
+
+        let saf = convert(Bed, Saf, "chr1\t99\t200\tg1\t.\t-\n").unwrap();
+        assert_eq!(
+            convert(Saf, Bed, &saf).unwrap(),
+            "chr1\t99\t200\tg1\t.\t-\n"
+        );
+        let bed = convert(Saf, Bed, &saf).unwrap();
+        assert_eq!(convert(Bed, Saf, &bed).unwrap(), saf);
+
+        let igv = convert(Bed, Igv, records).unwrap();
+        assert_eq!(
+            convert(Igv, Bed, &igv).unwrap(),
+            "chr1\t99\t200\nchr2\t0\t50\nchr2\t60\t80\n"
+        );
+    }
+
+    #[test]
+    fn test_gff_type_filter_and_errors() {
+        let gff = "##gff-version 3\n\
+                   chr1\tens\tgene\t11\t100\t.\t-\t.\tID=g1;Name=ABC\n\
+                   chr1\tens\texon\t11\t50\t.\t-\t.\tParent=g1\n";
+        let mut output = Vec::new();
+        ConvertCommand::new(ConvertFormat::Gff, ConvertFormat::Bed)
+            .with_feature_type(Some("gene".to_string()))
+            .convert_reader(gff.as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(output, b"chr1\t10\t100\tABC\t.\t-\n");
+
+        assert!(convert(ConvertFormat::Bed, ConvertFormat::Gff, "chr1\t5\t5\n").is_err());
+        assert!(convert(
+            ConvertFormat::Bed,
+            ConvertFormat::Saf,
+            "chr1\t5\t9\tx\t0\tF\n"
+        )
+        .is_err());
+        assert!(convert(ConvertFormat::Igv, ConvertFormat::Bed, "chr1:0-10\n").is_err());
+    }
+}
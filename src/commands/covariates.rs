@@ -0,0 +1,278 @@
+//! Covariates command implementation.
+//!
+//! Appends per-interval covariates used to normalize read counts in CNV
+//! workflows: interval length, GC fraction (from a FASTA reference) and mean
+//! mappability (from a BedGraph track). Each reference is read once; only one
+//! FASTA sequence is held in memory at a time.
+
+use crate::bed::BedError;
+use crate::fasta::FastaReader;
+use crate::streaming::parsing::{parse_bed3_bytes_with_rest, should_skip_line};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// One input interval with its original line.
+struct Region {
+    line: String,
+    start: u64,
+    end: u64,
+}
+
+/// Covariates command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct CovariatesCommand {
+    /// Reference FASTA for the GC column.
+    pub fasta: Option<PathBuf>,
+    /// BedGraph track for the mappability column.
+    pub mappability: Option<PathBuf>,
+}
+
+impl CovariatesCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a GC fraction column computed from `fasta`.
+    pub fn with_fasta(mut self, fasta: Option<PathBuf>) -> Self {
+        self.fasta = fasta;
+        self
+    }
+
+    /// Add a mean mappability column computed from a BedGraph track.
+    pub fn with_mappability(mut self, bedgraph: Option<PathBuf>) -> Self {
+        self.mappability = bedgraph;
+        self
+    }
+
+    /// Write each input line followed by length, GC and mappability columns.
+    ///
+    /// GC is the fraction of G/C among unambiguous (A/C/G/T) bases and is `.`
+    /// when the chromosome is missing from the FASTA or the interval has no
+    /// unambiguous bases. Mappability is the base-weighted mean of BedGraph
+    /// values over the interval, with bases outside the track counted as 0.
+    /// Output keeps the input order.
+    pub fn run<P: AsRef<Path>, W: Write>(&self, input: P, output: &mut W) -> Result<(), BedError> {
+        let (regions, by_chrom) = read_regions(input.as_ref())?;
+
+        let gc = match &self.fasta {
+            Some(path) => Some(gc_fractions(path, &regions, &by_chrom)?),
+            None => None,
+        };
+        let mappability = match &self.mappability {
+            Some(path) => Some(mean_track_values(path, &regions, &by_chrom)?),
+            None => None,
+        };
+
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        for (i, region) in regions.iter().enumerate() {
+            write!(out, "{}\t{}", region.line, region.end - region.start)?;
+            if let Some(gc) = &gc {
+                match gc[i] {
+                    Some(f) => write!(out, "\t{:.6}", f)?,
+                    None => out.write_all(b"\t.")?,
+                }
+            }
+            if let Some(map) = &mappability {
+                write!(out, "\t{:.6}", map[i])?;
+            }
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Read input intervals, grouping their indices by chromosome.
+fn read_regions(path: &Path) -> Result<(Vec<Region>, HashMap<String, Vec<usize>>), BedError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut regions = Vec::new();
+    let mut by_chrom: HashMap<String, Vec<usize>> = HashMap::new();
+
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if should_skip_line(line.as_bytes()) {
+            continue;
+        }
+        let (chrom, start, end, _) =
+            parse_bed3_bytes_with_rest(line.as_bytes()).ok_or_else(|| BedError::Parse {
+                line: line_num + 1,
+                message: "expected chrom, start and end columns".to_string(),
+            })?;
+        let chrom = String::from_utf8_lossy(chrom).into_owned();
+        by_chrom.entry(chrom).or_default().push(regions.len());
+        regions.push(Region {
+            line: line.to_string(),
+            start,
+            end: end.max(start),
+        });
+    }
+
+    Ok((regions, by_chrom))
+}
+
+/// GC fraction per region, reading the FASTA one sequence at a time.
+fn gc_fractions(
+    fasta: &Path,
+    regions: &[Region],
+    by_chrom: &HashMap<String, Vec<usize>>,
+) -> Result<Vec<Option<f64>>, BedError> {
+    let mut gc = vec![None; regions.len()];
+    let mut reader = FastaReader::from_path(fasta)?;
+
+    while let Some(record) = reader.next_record()? {
+        let Some(indices) = by_chrom.get(&record.name) else {
+            continue;
+        };
+        for &i in indices {
+            let seq_len = record.seq.len() as u64;
+            let start = regions[i].start.min(seq_len) as usize;
+            let end = regions[i].end.min(seq_len) as usize;
+            gc[i] = gc_fraction(&record.seq[start..end]);
+        }
+    }
+
+    Ok(gc)
+}
+
+/// Fraction of G/C among A/C/G/T bases, ignoring N and other ambiguity codes.
+fn gc_fraction(seq: &[u8]) -> Option<f64> {
+    let (mut gc, mut acgt) = (0u64, 0u64);
+    for &base in seq {
+        match base {
+            b'G' | b'C' | b'g' | b'c' => {
+                gc += 1;
+                acgt += 1;
+            }
+            b'A' | b'T' | b'a' | b't' => acgt += 1,
+            _ => {}
+        }
+    }
+    (acgt > 0).then(|| gc as f64 / acgt as f64)
+}
+
+/// Base-weighted mean of BedGraph values over each region.
+fn mean_track_values(
+    bedgraph: &Path,
+    regions: &[Region],
+    by_chrom: &HashMap<String, Vec<usize>>,
+) -> Result<Vec<f64>, BedError> {
+    let file = File::open(bedgraph).map_err(|e| {
+        BedError::InvalidFormat(format!(
+            "Failed to open BedGraph '{}': {}",
+            bedgraph.display(),
+            e
+        ))
+    })?;
+
+    // Track segments for chromosomes that have regions
+    let mut segments: HashMap<&str, Vec<(u64, u64, f64)>> = HashMap::new();
+    for (line_num, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let bytes = line.trim_end().as_bytes();
+        if should_skip_line(bytes) {
+            continue;
+        }
+        let parse_error = || BedError::Parse {
+            line: line_num + 1,
+            message: "expected chrom, start, end and value columns in BedGraph".to_string(),
+        };
+        let (chrom, start, end, rest) =
+            parse_bed3_bytes_with_rest(bytes).ok_or_else(parse_error)?;
+        let Some((name, _)) = by_chrom.get_key_value(std::str::from_utf8(chrom).unwrap_or(""))
+        else {
+            continue;
+        };
+        let value: f64 = std::str::from_utf8(&bytes[rest..])
+            .ok()
+            .and_then(|r| r.split_ascii_whitespace().next())
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(parse_error)?;
+        segments
+            .entry(name.as_str())
+            .or_default()
+            .push((start, end, value));
+    }
+
+    let mut means = vec![0.0; regions.len()];
+    for (chrom, segs) in segments.iter_mut() {
+        segs.sort_unstable_by_key(|&(start, end, _)| (start, end));
+        // Segments may overlap in a malformed track; a running max of ends
+        // keeps the search bound monotonic.
+        let mut max_end = Vec::with_capacity(segs.len());
+        let mut running = 0;
+        for &(_, end, _) in segs.iter() {
+            running = running.max(end);
+            max_end.push(running);
+        }
+
+        for &i in &by_chrom[*chrom] {
+            let Region { start, end, .. } = regions[i];
+            if start == end {
+                continue;
+            }
+            let first = max_end.partition_point(|&e| e <= start);
+            let mut weighted = 0.0;
+            for &(s, e, v) in segs[first..].iter().take_while(|(s, _, _)| *s < end) {
+                let overlap = e.min(end).saturating_sub(s.max(start));
+                weighted += overlap as f64 * v;
+            }
+            means[i] = weighted / (end - start) as f64;
+        }
+    }
+
+    Ok(means)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as IoWrite;
+    use tempfile::NamedTempFile;
+
+    fn temp_file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_gc_fraction() {
+        assert_eq!(gc_fraction(b"GCAT"), Some(0.5));
+        assert_eq!(gc_fraction(b"ggNNNa"), Some(2.0 / 3.0));
+        assert_eq!(gc_fraction(b"NNNN"), None);
+    }
+
+    #[test]
+    fn test_covariates_all_columns() {
+        let bed = temp_file("chr2\t0\t4\tbinB\nchr1\t2\t6\tbinA\nchrUn\t0\t10\tbinC\n");
+        let fasta = temp_file(">chr1\nAAGG\nCCNN\n>chr2 second\nACGT\n");
+        let track = temp_file("chr1\t0\t4\t1.0\nchr1\t4\t5\t0.5\nchr2\t2\t4\t1\n");
+
+        let cmd = CovariatesCommand::new()
+            .with_fasta(Some(fasta.path().to_path_buf()))
+            .with_mappability(Some(track.path().to_path_buf()));
+        let mut output = Vec::new();
+        cmd.run(bed.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr2\t0\t4\tbinB\t4\t0.500000\t0.500000\n\
+             chr1\t2\t6\tbinA\t4\t1.000000\t0.625000\n\
+             chrUn\t0\t10\tbinC\t10\t.\t0.000000\n"
+        );
+    }
+
+    #[test]
+    fn test_covariates_length_only() {
+        let bed = temp_file("chr1\t100\t250\n");
+        let mut output = Vec::new();
+        CovariatesCommand::new()
+            .run(bed.path(), &mut output)
+            .unwrap();
+        assert_eq!(output, b"chr1\t100\t250\t150\n");
+    }
+}
@@ -4,8 +4,9 @@
 //! Event-based depth computation avoids per-base iteration for basic/mean modes.
 
 use crate::bed::{read_records, BedError};
+use crate::config::display_score;
 use crate::interval::BedRecord;
-use crate::parallel::PARALLEL_THRESHOLD;
+use crate::parallel::{PoolHandle, PARALLEL_THRESHOLD};
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::io::Write;
@@ -26,6 +27,8 @@ pub struct CoverageCommand {
     pub opposite_strand: bool,
     /// Process in parallel by chromosome
     pub parallel: bool,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
 }
 
 impl Default for CoverageCommand {
@@ -43,9 +46,16 @@ impl CoverageCommand {
             same_strand: false,
             opposite_strand: false,
             parallel: true,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Execute coverage command on files using O(n+m) sweep-line algorithm.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
@@ -61,8 +71,12 @@ impl CoverageCommand {
         }
 
         // Group by chromosome
-        let a_by_chrom = Self::group_records_by_chrom(a_records);
-        let b_by_chrom = Self::group_records_by_chrom(b_records);
+        let a_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom(a_records));
+        let b_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom(b_records));
 
         // Get sorted chromosome list for deterministic output
         let mut chroms: Vec<_> = a_by_chrom.keys().cloned().collect();
@@ -84,17 +98,19 @@ impl CoverageCommand {
             }
         } else {
             // Parallel processing for large datasets
-            let results: Vec<Vec<u8>> = chroms
-                .par_iter()
-                .map(|chrom| {
-                    let mut buf = Vec::with_capacity(64 * 1024);
-                    if let Some(a_list) = a_by_chrom.get(chrom) {
-                        let b_list = b_by_chrom.get(chrom);
-                        self.coverage_chromosome_sweepline(a_list, b_list, &mut buf);
-                    }
-                    buf
-                })
-                .collect();
+            let results: Vec<Vec<u8>> = self.pool.install(|| {
+                chroms
+                    .par_iter()
+                    .map(|chrom| {
+                        let mut buf = Vec::with_capacity(64 * 1024);
+                        if let Some(a_list) = a_by_chrom.get(chrom) {
+                            let b_list = b_by_chrom.get(chrom);
+                            self.coverage_chromosome_sweepline(a_list, b_list, &mut buf);
+                        }
+                        buf
+                    })
+                    .collect()
+            });
 
             // Write results in chromosome order
             for buf in results {
@@ -223,7 +239,7 @@ impl CoverageCommand {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -0,0 +1,430 @@
+//! Cut command implementation.
+//!
+//! Selects and reorders the columns of a BED-like file. Unlike `cut -f`, the
+//! result is checked to still be BED: every output line must start with a
+//! chromosome and two integer coordinates with `start <= end`, so a typo in
+//! the field list fails loudly instead of producing a file that downstream
+//! tools misread. Columns can be named (`name`, `strand`, `signalValue`) for
+//! the formats in [`CutFormat`].
+//!
+//! Moving other columns into the coordinate positions (e.g. `thickStart` and
+//! `thickEnd` to get coding regions) can break sort order; `--sort` re-sorts
+//! the output in memory.
+
+use crate::bed::{is_bedgraph, BedError};
+use crate::genome::Genome;
+use crate::interchange::stdin_reader;
+use crate::output_format::BED_COLUMNS;
+use crate::output_limit::limited_stdout;
+use crate::streaming::parsing::{is_header_line, should_skip_line, trim_newline};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Column names of BedGraph files.
+const BEDGRAPH_COLUMNS: [&str; 4] = ["chrom", "start", "end", "value"];
+
+/// Column names of ENCODE narrowPeak files.
+const NARROWPEAK_COLUMNS: [&str; 10] = [
+    "chrom",
+    "start",
+    "end",
+    "name",
+    "score",
+    "strand",
+    "signalValue",
+    "pValue",
+    "qValue",
+    "peak",
+];
+
+/// File format whose column names `--fields` may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutFormat {
+    /// BED3-BED12.
+    #[default]
+    Bed,
+    /// BedGraph (`value` is column 4).
+    BedGraph,
+    /// ENCODE narrowPeak (BED6+4).
+    NarrowPeak,
+}
+
+impl CutFormat {
+    /// Parse a format name as accepted by `--format`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bed" => Some(Self::Bed),
+            "bedgraph" => Some(Self::BedGraph),
+            "narrowpeak" => Some(Self::NarrowPeak),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of a file from its extension and track line.
+    pub fn detect<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("narrowpeak") => Self::NarrowPeak,
+            Some("bedgraph" | "bdg") => Self::BedGraph,
+            _ if is_bedgraph(path) => Self::BedGraph,
+            _ => Self::Bed,
+        }
+    }
+
+    /// Names of the leading columns.
+    pub fn names(&self) -> &'static [&'static str] {
+        match self {
+            Self::Bed => &BED_COLUMNS,
+            Self::BedGraph => &BEDGRAPH_COLUMNS,
+            Self::NarrowPeak => &NARROWPEAK_COLUMNS,
+        }
+    }
+}
+
+/// One item of a field list; columns are 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutColumn {
+    /// A single column, which every line must have.
+    Single(usize),
+    /// Columns `from..=to`, or `from` to the last column of each line.
+    Range(usize, Option<usize>),
+}
+
+impl CutColumn {
+    /// Parse a comma-separated field list such as `1-3,name,7-`.
+    ///
+    /// Columns are 1-based numbers, ranges `N-M` or `N-` (to the end of each
+    /// line), or column names of `format`, matched ignoring case.
+    pub fn parse_list(spec: &str, format: CutFormat) -> Result<Vec<Self>, BedError> {
+        let invalid = |item: &str, why: &str| {
+            BedError::InvalidFormat(format!("invalid field '{}': {}", item, why))
+        };
+        let number = |item: &str, text: &str| match text.parse::<usize>() {
+            Ok(0) => Err(invalid(item, "columns are 1-based")),
+            Ok(n) => Ok(n - 1),
+            Err(_) => Err(invalid(item, "expected a column number")),
+        };
+
+        spec.split(',')
+            .map(|item| {
+                let item = item.trim();
+                if let Some(i) = format
+                    .names()
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(item))
+                {
+                    return Ok(Self::Single(i));
+                }
+                if !item.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Err(invalid(
+                        item,
+                        &format!(
+                            "not a column of {:?} ({})",
+                            format,
+                            format.names().join(", ")
+                        ),
+                    ));
+                }
+                match item.split_once('-') {
+                    None => Ok(Self::Single(number(item, item)?)),
+                    Some((from, "")) => Ok(Self::Range(number(item, from)?, None)),
+                    Some((from, to)) => {
+                        let (from, to) = (number(item, from)?, number(item, to)?);
+                        if from > to {
+                            return Err(invalid(item, "range ends before it starts"));
+                        }
+                        Ok(Self::Range(from, Some(to)))
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Cut command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct CutCommand {
+    /// Output columns, in order.
+    pub columns: Vec<CutColumn>,
+    /// Sort the output by chromosome, start and end.
+    pub sort: bool,
+}
+
+impl CutCommand {
+    pub fn new(columns: Vec<CutColumn>) -> Self {
+        Self {
+            columns,
+            sort: false,
+        }
+    }
+
+    /// Sort the output (needed when the coordinate columns change).
+    pub fn with_sort(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Whether the first three output columns are the input's chrom, start
+    /// and end, so records keep their coordinates and sort order.
+    pub fn keeps_coordinates(&self) -> bool {
+        let leading = self.columns.iter().flat_map(|c| match *c {
+            CutColumn::Single(i) => i..i + 1,
+            CutColumn::Range(from, to) => from..to.map_or(from + 3, |to| to + 1),
+        });
+        leading.take(3).eq(0..3)
+    }
+
+    /// Cut a file.
+    ///
+    /// With `sort`, chromosomes follow `genome` if given, otherwise
+    /// lexicographic order.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        genome: Option<&Genome>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let file = File::open(input)?;
+        self.cut_reader(file, genome, output)
+    }
+
+    /// Cut stdin to stdout.
+    pub fn run_stdio(&self, genome: Option<&Genome>) -> Result<(), BedError> {
+        let mut handle = limited_stdout();
+        self.cut_reader(stdin_reader(), genome, &mut handle)
+    }
+
+    /// Cut a BED stream.
+    ///
+    /// Comment, `track` and `browser` lines are copied unchanged (ahead of
+    /// the records when sorting).
+    pub fn cut_reader<R: Read, W: Write>(
+        &self,
+        input: R,
+        genome: Option<&Genome>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        if self.columns.is_empty() {
+            return Err(BedError::InvalidFormat("no fields selected".to_string()));
+        }
+
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        let mut reader = BufReader::with_capacity(256 * 1024, input);
+        let mut line = Vec::with_capacity(1024);
+        let mut record = Vec::with_capacity(1024);
+        let mut sorted: Vec<SortedLine> = Vec::new();
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            let text = trim_newline(&line);
+            if is_header_line(text) {
+                out.write_all(text)?;
+                out.write_all(b"\n")?;
+                continue;
+            }
+            if should_skip_line(text) {
+                continue;
+            }
+
+            let fields: Vec<&[u8]> = text.split(|&b| b == b'\t').collect();
+            record.clear();
+            let mut width = 0;
+            let mut coords = [0u64; 2];
+            for column in &self.columns {
+                let (from, to) = match *column {
+                    CutColumn::Single(i) if i >= fields.len() => {
+                        return Err(BedError::Parse {
+                            line: line_num,
+                            message: format!(
+                                "column {} requested but the line has {} columns",
+                                i + 1,
+                                fields.len()
+                            ),
+                        });
+                    }
+                    CutColumn::Single(i) => (i, i + 1),
+                    CutColumn::Range(from, to) => {
+                        let end = to.map_or(fields.len(), |to| (to + 1).min(fields.len()));
+                        (from, end.max(from))
+                    }
+                };
+                for field in fields.iter().take(to).skip(from) {
+                    if width > 0 {
+                        record.push(b'\t');
+                    }
+                    if width == 1 || width == 2 {
+                        coords[width - 1] = parse_coordinate(field, width, line_num)?;
+                    }
+                    record.extend_from_slice(field);
+                    width += 1;
+                }
+            }
+
+            if width < 3 {
+                return Err(BedError::Parse {
+                    line: line_num,
+                    message: format!(
+                        "output has {} columns; BED needs chrom, start and end",
+                        width
+                    ),
+                });
+            }
+            let [start, end] = coords;
+            if start > end {
+                return Err(BedError::Parse {
+                    line: line_num,
+                    message: format!("output start {} is after end {}", start, end),
+                });
+            }
+
+            if self.sort {
+                let chrom_len = memchr::memchr(b'\t', &record).unwrap_or(record.len());
+                sorted.push(SortedLine {
+                    chrom_len,
+                    start,
+                    end,
+                    line: record.clone(),
+                });
+            } else {
+                out.write_all(&record)?;
+                out.write_all(b"\n")?;
+            }
+        }
+
+        if self.sort {
+            let ranks: HashMap<&str, usize> = genome
+                .map(|g| {
+                    g.chromosomes()
+                        .enumerate()
+                        .map(|(i, c)| (c.as_str(), i))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let rank = |l: &SortedLine| {
+                std::str::from_utf8(l.chrom())
+                    .ok()
+                    .and_then(|c| ranks.get(c))
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            };
+            sorted.sort_by(|a, b| {
+                rank(a)
+                    .cmp(&rank(b))
+                    .then_with(|| a.chrom().cmp(b.chrom()))
+                    .then(a.start.cmp(&b.start))
+                    .then(a.end.cmp(&b.end))
+            });
+            for l in &sorted {
+                out.write_all(&l.line)?;
+                out.write_all(b"\n")?;
+            }
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// A cut record held for sorting.
+struct SortedLine {
+    chrom_len: usize,
+    start: u64,
+    end: u64,
+    line: Vec<u8>,
+}
+
+impl SortedLine {
+    fn chrom(&self) -> &[u8] {
+        &self.line[..self.chrom_len]
+    }
+}
+
+/// Parse output column 2 or 3 (`width` 1 or 2) as a coordinate.
+fn parse_coordinate(field: &[u8], width: usize, line: usize) -> Result<u64, BedError> {
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| BedError::Parse {
+            line,
+            message: format!(
+                "output column {} ({}) is not a coordinate: '{}'",
+                width + 1,
+                if width == 1 { "start" } else { "end" },
+                String::from_utf8_lossy(field)
+            ),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENES: &str = "#genes\n\
+                         chr2\t100\t900\tB\t0\t-\t150\t800\n\
+                         chr1\t500\t1500\tA\t0\t+\t700\t1200\n";
+
+    fn cut(spec: &str, format: CutFormat, sort: bool, input: &str) -> Result<String, BedError> {
+        let cmd = CutCommand::new(CutColumn::parse_list(spec, format)?).with_sort(sort);
+        let mut output = Vec::new();
+        cmd.cut_reader(input.as_bytes(), None, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        use CutColumn::*;
+        assert_eq!(
+            CutColumn::parse_list("1-3,Strand,name,8-", CutFormat::Bed).unwrap(),
+            [Range(0, Some(2)), Single(5), Single(3), Range(7, None)]
+        );
+        assert_eq!(
+            CutColumn::parse_list("chrom,start,end,signalValue", CutFormat::NarrowPeak).unwrap(),
+            [Single(0), Single(1), Single(2), Single(6)]
+        );
+        assert!(CutColumn::parse_list("0", CutFormat::Bed).is_err());
+        assert!(CutColumn::parse_list("4-2", CutFormat::Bed).is_err());
+        assert!(CutColumn::parse_list("signalValue", CutFormat::Bed).is_err());
+    }
+
+    #[test]
+    fn test_cut_reorders_columns() {
+        let out = cut("1-3,strand,name", CutFormat::Bed, false, GENES).unwrap();
+        assert_eq!(out, "#genes\nchr2\t100\t900\t-\tB\nchr1\t500\t1500\t+\tA\n");
+        assert!(
+            CutCommand::new(CutColumn::parse_list("1-3,6", CutFormat::Bed).unwrap())
+                .keeps_coordinates()
+        );
+    }
+
+    #[test]
+    fn test_cut_new_coordinates_sorted() {
+        let spec = "chrom,thickStart,thickEnd,name";
+        let cmd = CutCommand::new(CutColumn::parse_list(spec, CutFormat::Bed).unwrap());
+        assert!(!cmd.keeps_coordinates());
+        let out = cut(spec, CutFormat::Bed, true, GENES).unwrap();
+        assert_eq!(out, "#genes\nchr1\t700\t1200\tA\nchr2\t150\t800\tB\n");
+    }
+
+    #[test]
+    fn test_cut_rejects_broken_bed() {
+        // Name in the start column
+        let err = cut("1,4,3", CutFormat::Bed, false, GENES).unwrap_err();
+        assert!(matches!(err, BedError::Parse { line: 2, .. }));
+        // Too few columns
+        assert!(cut("1,2", CutFormat::Bed, false, GENES).is_err());
+        // Column past the end of the line
+        assert!(cut("1-3,9", CutFormat::Bed, false, GENES).is_err());
+        // End before start
+        assert!(cut("1,3,2", CutFormat::Bed, false, GENES).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! Delta command: differential signal between two BedGraph tracks.
+//!
+//! Both tracks (typically treatment and control coverage) are swept together
+//! base by base, with positions missing from a track counting as 0. Stretches
+//! where the treatment/control difference passes the thresholds form runs;
+//! runs shorter than `min_length` are dropped and runs in the same direction
+//! at most `distance` bp apart are merged. Each region is reported as
+//!
+//! ```text
+//! chrom  start  end  up|down  mean_treatment  mean_control  log2_fold
+//! ```
+//!
+//! where the means are per base over the whole region (merged gaps
+//! included) and `log2_fold = log2((mean_t + pseudocount) / (mean_c + pseudocount))`.
+//!
+//! REQUIREMENT: Both tracks must be sorted by (chrom, start), chromosomes in
+//! lexicographic order (or the genome file's order with `with_genome`), and
+//! records within a track must not overlap.
+
+use crate::bed::BedError;
+use crate::genome::Genome;
+use crate::streaming::parsing::{column_bytes, parse_bed3_bytes, should_skip_line};
+use crate::streaming::{ChromId, ChromTable};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Delta command configuration.
+#[derive(Debug, Clone)]
+pub struct DeltaCommand {
+    /// Minimum absolute difference `|t - c|` for a base to count
+    pub min_diff: Option<f64>,
+    /// Minimum fold change, in either direction, for a base to count
+    pub min_fold: Option<f64>,
+    /// Added to both values before taking ratios
+    pub pseudocount: f64,
+    /// Runs shorter than this are dropped before merging
+    pub min_length: u64,
+    /// Merge same-direction runs separated by at most this many bases
+    pub distance: u64,
+    /// Chromosome ranks from a genome file; lexicographic order when unset
+    chrom_order: Option<ChromTable>,
+}
+
+impl Default for DeltaCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A value-annotated interval of one track.
+#[derive(Debug, Clone)]
+struct Step {
+    chrom: Vec<u8>,
+    start: u64,
+    end: u64,
+    value: f64,
+}
+
+/// Sorted BedGraph reader that rejects overlapping records.
+struct Track<R: BufRead> {
+    reader: R,
+    line: String,
+    line_num: usize,
+    next: Option<Step>,
+}
+
+impl<R: BufRead> Track<R> {
+    fn new(reader: R) -> Result<Self, BedError> {
+        let mut track = Self {
+            reader,
+            line: String::new(),
+            line_num: 0,
+            next: None,
+        };
+        track.advance()?;
+        Ok(track)
+    }
+
+    /// Replace `next` with the following record.
+    fn advance(&mut self) -> Result<(), BedError> {
+        let prev = self.next.take();
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(());
+            }
+            self.line_num += 1;
+            let line = self.line.trim_end().as_bytes();
+            if should_skip_line(line) {
+                continue;
+            }
+            let parse_error = |message: String| BedError::Parse {
+                line: self.line_num,
+                message,
+            };
+            let (chrom, start, end) = parse_bed3_bytes(line)
+                .ok_or_else(|| parse_error("expected chrom, start, end".to_string()))?;
+            let value = column_bytes(line, 4)
+                .and_then(|v| std::str::from_utf8(v).ok())
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| parse_error("expected a numeric BedGraph value".to_string()))?;
+            if let Some(prev) = &prev {
+                if prev.chrom == chrom && start < prev.end {
+                    return Err(parse_error(format!(
+                        "BedGraph record {}:{}-{} overlaps or precedes the previous record",
+                        String::from_utf8_lossy(chrom),
+                        start,
+                        end
+                    )));
+                }
+            }
+            if start < end {
+                self.next = Some(Step {
+                    chrom: chrom.to_vec(),
+                    start,
+                    end,
+                    value,
+                });
+                return Ok(());
+            }
+        }
+    }
+
+    /// Value at `pos` on `chrom`, advancing past records that end before it.
+    fn value_at(&mut self, chrom: &[u8], pos: u64) -> Result<f64, BedError> {
+        while matches!(&self.next, Some(s) if s.chrom == chrom && s.end <= pos) {
+            self.advance()?;
+        }
+        Ok(match &self.next {
+            Some(s) if s.chrom == chrom && s.start <= pos => s.value,
+            _ => 0.0,
+        })
+    }
+
+    /// Next position after `pos` on `chrom` where this track's value changes.
+    fn next_boundary(&self, chrom: &[u8], pos: u64) -> Option<u64> {
+        match &self.next {
+            Some(s) if s.chrom == chrom => Some(if s.start > pos { s.start } else { s.end }),
+            _ => None,
+        }
+    }
+}
+
+/// Direction of a differential region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+/// A run or merged region; `t`/`c` are the base-weighted value sums.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    direction: Direction,
+    start: u64,
+    end: u64,
+    /// Cumulative sums along the chromosome at `start`
+    t_at_start: f64,
+    c_at_start: f64,
+    /// Cumulative sums along the chromosome at `end`
+    t_at_end: f64,
+    c_at_end: f64,
+}
+
+impl DeltaCommand {
+    pub fn new() -> Self {
+        Self {
+            min_diff: None,
+            min_fold: None,
+            pseudocount: 1.0,
+            min_length: 1,
+            distance: 0,
+            chrom_order: None,
+        }
+    }
+
+    /// Require `|t - c| >= min_diff`.
+    pub fn with_min_diff(mut self, min_diff: f64) -> Self {
+        self.min_diff = Some(min_diff);
+        self
+    }
+
+    /// Require a fold change of at least `min_fold` (e.g. 2 for twofold).
+    pub fn with_min_fold(mut self, min_fold: f64) -> Self {
+        self.min_fold = Some(min_fold);
+        self
+    }
+
+    pub fn with_pseudocount(mut self, pseudocount: f64) -> Self {
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    pub fn with_min_length(mut self, min_length: u64) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    pub fn with_distance(mut self, distance: u64) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Expect chromosomes in the order of `genome` instead of lexicographic.
+    pub fn with_genome(mut self, genome: &Genome) -> Self {
+        self.chrom_order = Some(ChromTable::from_genome(genome));
+        self
+    }
+
+    /// Compare a treatment BedGraph with a control BedGraph.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        treatment: P,
+        control: P,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let open = |p: &Path| -> Result<_, BedError> {
+            Ok(BufReader::with_capacity(256 * 1024, File::open(p)?))
+        };
+        self.run_readers(open(treatment.as_ref())?, open(control.as_ref())?, output)
+    }
+
+    /// Compare two BedGraph streams.
+    pub fn run_readers<R1: BufRead, R2: BufRead, W: Write>(
+        &self,
+        treatment: R1,
+        control: R2,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        if let Some(fold) = self.min_fold {
+            if fold.is_nan() || fold < 1.0 {
+                return Err(BedError::InvalidFormat(format!(
+                    "--min-fold must be at least 1, got {}",
+                    fold
+                )));
+            }
+        }
+        if self.pseudocount.is_nan() || self.pseudocount < 0.0 {
+            return Err(BedError::InvalidFormat(format!(
+                "--pseudocount must be non-negative, got {}",
+                self.pseudocount
+            )));
+        }
+
+        let mut t = Track::new(treatment)?;
+        let mut c = Track::new(control)?;
+
+        loop {
+            let chrom = match (&t.next, &c.next) {
+                (None, None) => break,
+                (Some(a), None) => a.chrom.clone(),
+                (None, Some(b)) => b.chrom.clone(),
+                (Some(a), Some(b)) => match self.chrom_cmp(&a.chrom, &b.chrom) {
+                    Ordering::Greater => b.chrom.clone(),
+                    _ => a.chrom.clone(),
+                },
+            };
+            self.sweep_chrom(&chrom, &mut t, &mut c, output)?;
+        }
+        Ok(())
+    }
+
+    fn chrom_cmp(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match &self.chrom_order {
+            Some(order) => {
+                let rank = |c: &[u8]| order.get(c).unwrap_or(ChromId::MAX);
+                rank(a).cmp(&rank(b)).then_with(|| a.cmp(b))
+            }
+            None => a.cmp(b),
+        }
+    }
+
+    /// Direction of a base with treatment `t` and control `c`, if it counts.
+    fn classify(&self, t: f64, c: f64) -> Option<Direction> {
+        if t == c {
+            return None;
+        }
+        if self.min_diff.is_some_and(|d| (t - c).abs() < d) {
+            return None;
+        }
+        if let Some(fold) = self.min_fold {
+            let ratio = (t + self.pseudocount) / (c + self.pseudocount);
+            if ratio.max(1.0 / ratio) < fold {
+                return None;
+            }
+        }
+        Some(if t > c {
+            Direction::Up
+        } else {
+            Direction::Down
+        })
+    }
+
+    /// Emit the regions of one chromosome, consuming its records.
+    fn sweep_chrom<R1: BufRead, R2: BufRead, W: Write>(
+        &self,
+        chrom: &[u8],
+        t: &mut Track<R1>,
+        c: &mut Track<R2>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let mut pos = 0u64;
+        let (mut t_sum, mut c_sum) = (0.0f64, 0.0f64);
+        let mut run: Option<Region> = None;
+        let mut pending: Option<Region> = None;
+
+        loop {
+            let tv = t.value_at(chrom, pos)?;
+            let cv = c.value_at(chrom, pos)?;
+            let next = match (t.next_boundary(chrom, pos), c.next_boundary(chrom, pos)) {
+                (None, None) => break,
+                (Some(a), None) | (None, Some(a)) => a,
+                (Some(a), Some(b)) => a.min(b),
+            };
+
+            let direction = self.classify(tv, cv);
+            if run.is_some_and(|r| Some(r.direction) != direction) {
+                self.finish_run(chrom, run.take(), &mut pending, output)?;
+            }
+            let width = (next - pos) as f64;
+            if let Some(direction) = direction {
+                let r = run.get_or_insert(Region {
+                    direction,
+                    start: pos,
+                    end: pos,
+                    t_at_start: t_sum,
+                    c_at_start: c_sum,
+                    t_at_end: t_sum,
+                    c_at_end: c_sum,
+                });
+                r.end = next;
+                r.t_at_end = t_sum + tv * width;
+                r.c_at_end = c_sum + cv * width;
+            }
+            t_sum += tv * width;
+            c_sum += cv * width;
+            pos = next;
+        }
+
+        self.finish_run(chrom, run, &mut pending, output)?;
+        if let Some(region) = pending {
+            self.write_region(chrom, &region, output)?;
+        }
+        Ok(())
+    }
+
+    /// Merge a finished run into `pending`, writing whatever it displaces.
+    fn finish_run<W: Write>(
+        &self,
+        chrom: &[u8],
+        run: Option<Region>,
+        pending: &mut Option<Region>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let Some(run) = run.filter(|r| r.end - r.start >= self.min_length) else {
+            return Ok(());
+        };
+        if let Some(p) = pending.as_mut() {
+            if p.direction == run.direction && run.start - p.end <= self.distance {
+                p.end = run.end;
+                p.t_at_end = run.t_at_end;
+                p.c_at_end = run.c_at_end;
+                return Ok(());
+            }
+        }
+        if let Some(prev) = pending.replace(run) {
+            self.write_region(chrom, &prev, output)?;
+        }
+        Ok(())
+    }
+
+    fn write_region<W: Write>(
+        &self,
+        chrom: &[u8],
+        region: &Region,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let len = (region.end - region.start) as f64;
+        let mean_t = (region.t_at_end - region.t_at_start) / len;
+        let mean_c = (region.c_at_end - region.c_at_start) / len;
+        let log2_fold = ((mean_t + self.pseudocount) / (mean_c + self.pseudocount)).log2();
+        output.write_all(chrom)?;
+        writeln!(
+            output,
+            "\t{}\t{}\t{}\t{:.6}\t{:.6}\t{:.6}",
+            region.start,
+            region.end,
+            match region.direction {
+                Direction::Up => "up",
+                Direction::Down => "down",
+            },
+            mean_t,
+            mean_c,
+            log2_fold
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(cmd: &DeltaCommand, t: &str, c: &str) -> Result<String, BedError> {
+        let mut out = Vec::new();
+        cmd.run_readers(t.as_bytes(), c.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_delta_difference_runs() {
+        let t = "chr1\t0\t100\t10\nchr1\t100\t150\t2\nchr1\t150\t200\t10\nchr2\t0\t50\t1\n";
+        let c = "track type=bedGraph\nchr1\t0\t200\t2\nchr2\t0\t50\t6\n";
+
+        let cmd = DeltaCommand::new().with_min_diff(5.0);
+        assert_eq!(
+            run(&cmd, t, c).unwrap(),
+            "chr1\t0\t100\tup\t10.000000\t2.000000\t1.874469\n\
+             chr1\t150\t200\tup\t10.000000\t2.000000\t1.874469\n\
+             chr2\t0\t50\tdown\t1.000000\t6.000000\t-1.807355\n"
+        );
+
+        // Merging across the 50 bp dip averages it into the region
+        let merged = run(&cmd.clone().with_distance(50), t, c).unwrap();
+        assert_eq!(
+            merged.lines().next().unwrap(),
+            "chr1\t0\t200\tup\t8.000000\t2.000000\t1.584963"
+        );
+
+        // Runs shorter than min_length are dropped before merging
+        let long = run(&cmd.with_min_length(60), t, c).unwrap();
+        assert_eq!(long.lines().count(), 1);
+        assert!(long.starts_with("chr1\t0\t100\tup"));
+    }
+
+    #[test]
+    fn test_delta_fold_and_missing_bases() {
+        // Control has no record for chr1:50-100, so it counts as 0 there
+        let t = "chr1\t0\t100\t3\n";
+        let c = "chr1\t0\t50\t3\nchr3\t0\t10\t4\n";
+        let cmd = DeltaCommand::new().with_min_fold(4.0);
+        assert_eq!(
+            run(&cmd, t, c).unwrap(),
+            "chr1\t50\t100\tup\t3.000000\t0.000000\t2.000000\n\
+             chr3\t0\t10\tdown\t0.000000\t4.000000\t-2.321928\n"
+        );
+    }
+
+    #[test]
+    fn test_delta_rejects_overlaps() {
+        let t = "chr1\t0\t100\t3\nchr1\t50\t150\t1\n";
+        let err = run(&DeltaCommand::new(), t, "").unwrap_err();
+        assert!(err.to_string().contains("overlaps"), "{}", err);
+        assert!(run(&DeltaCommand::new().with_min_fold(0.5), t, "").is_err());
+    }
+}
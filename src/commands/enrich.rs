@@ -0,0 +1,469 @@
+//! Enrich command implementation.
+//!
+//! Tests whether A overlaps B more (or less) than expected by chance. The
+//! observed overlap is either the number of A records touching B or the
+//! bases of A covered by B, and the expectation comes from one of two null
+//! models:
+//!
+//! - Genome coverage fractions (the default). Each A record is assumed to
+//!   land anywhere in the genome with equal probability: a record of `L`
+//!   bases touches B with the fraction of start positions at which an `L` bp
+//!   window overlaps B, and each base of A is covered with B's genome
+//!   fraction. Fast, but blind to the chromosome mix and clustering of A.
+//! - `N` shuffles of A with the [`shuffle`](super::shuffle) engine, which
+//!   honours the background model, a placement mask and genome file
+//!   metadata. Shuffle `k` draws from [`SeededRng::stream`]`(seed, k)`, so
+//!   the result does not depend on the thread count, and shuffle 0 places
+//!   A exactly as `grit shuffle --seed <seed>` does.
+//!
+//! The report gives the fold enrichment (observed / expected), a z-score and,
+//! with shuffles, the empirical p-value of enrichment,
+//! `(1 + #{shuffles >= observed}) / (N + 1)`.
+
+use super::jaccard::format_g;
+use super::shuffle::ShuffleCommand;
+use crate::bed::{read_intervals, BedError};
+use crate::genome::Genome;
+use crate::interval::Interval;
+use crate::rng::SeededRng;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// What counts as overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnrichMetric {
+    /// A records overlapping at least one B record.
+    #[default]
+    Records,
+    /// Bases of A (merged) covered by B.
+    Bases,
+}
+
+impl EnrichMetric {
+    /// Parse a metric name as accepted by `--metric`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "records" => Some(Self::Records),
+            "bases" => Some(Self::Bases),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Records => "records",
+            Self::Bases => "bases",
+        }
+    }
+}
+
+/// Observed and expected overlap of A with B.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnrichResult {
+    pub metric: EnrichMetric,
+    pub observed: u64,
+    pub expected: f64,
+    /// Observed minus expected in standard deviations of the null, when the
+    /// null has a spread.
+    pub z_score: Option<f64>,
+    /// Empirical p-value of enrichment; only with shuffles.
+    pub p_value: Option<f64>,
+    /// Shuffles behind the null; 0 for genome coverage fractions.
+    pub shuffles: usize,
+}
+
+impl EnrichResult {
+    /// Observed over expected, if anything is expected.
+    pub fn fold_enrichment(&self) -> Option<f64> {
+        (self.expected > 0.0).then(|| self.observed as f64 / self.expected)
+    }
+}
+
+/// Enrich command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct EnrichCommand {
+    pub metric: EnrichMetric,
+    /// Shuffles of A for the null distribution; 0 uses genome coverage
+    /// fractions instead.
+    pub shuffles: usize,
+    /// Background, seed and mask of the shuffles.
+    pub shuffler: ShuffleCommand,
+}
+
+impl EnrichCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set what counts as overlap.
+    pub fn with_metric(mut self, metric: EnrichMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Build the null from `n` shuffles of A (0 for coverage fractions).
+    pub fn with_shuffles(mut self, n: usize) -> Self {
+        self.shuffles = n;
+        self
+    }
+
+    /// Shuffle A with this configuration; its `per_record` is not used.
+    pub fn with_shuffler(mut self, shuffler: ShuffleCommand) -> Self {
+        self.shuffler = shuffler;
+        self
+    }
+
+    /// Compare two BED files and write the report.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input_a: P,
+        input_b: P,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let a = read_intervals(input_a)?;
+        let b = read_intervals(input_b)?;
+        let result = self.enrich(&a, &b, genome)?;
+        write_report(&result, output)
+    }
+
+    /// Observed and expected overlap of `a` with `b`.
+    ///
+    /// Neither input needs to be sorted.
+    pub fn enrich(
+        &self,
+        a: &[Interval],
+        b: &[Interval],
+        genome: &Genome,
+    ) -> Result<EnrichResult, BedError> {
+        let targets = Targets::new(b);
+        let mut tally = Tally::new(&targets, self.metric);
+        for iv in a {
+            tally.add(&iv.chrom, iv.start, iv.end);
+        }
+        let observed = tally.finish();
+
+        let (expected, z_score, p_value) = if self.shuffles == 0 {
+            let (expected, variance) = self.coverage_null(a, &targets, genome)?;
+            let z = variance
+                .filter(|&v| v > 0.0)
+                .map(|v| (observed as f64 - expected) / v.sqrt());
+            (expected, z, None)
+        } else {
+            let null = self.shuffle_null(a, &targets, genome)?;
+            let n = null.len() as f64;
+            let mean = null.iter().sum::<u64>() as f64 / n;
+            let z = if null.len() > 1 {
+                let var = null.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                (var > 0.0).then(|| (observed as f64 - mean) / var.sqrt())
+            } else {
+                None
+            };
+            let at_least = null.iter().filter(|&&x| x >= observed).count();
+            let p = (1 + at_least) as f64 / (n + 1.0);
+            (mean, z, Some(p))
+        };
+
+        Ok(EnrichResult {
+            metric: self.metric,
+            observed,
+            expected,
+            z_score,
+            p_value,
+            shuffles: self.shuffles,
+        })
+    }
+
+    /// Expected overlap, and its variance when known, if A were placed
+    /// uniformly over the genome.
+    fn coverage_null(
+        &self,
+        a: &[Interval],
+        targets: &Targets,
+        genome: &Genome,
+    ) -> Result<(f64, Option<f64>), BedError> {
+        let genome_size = genome.effective_total_size();
+        if genome_size == 0 {
+            return Err(BedError::InvalidFormat(
+                "genome file has no usable bases".to_string(),
+            ));
+        }
+        let genome_size = genome_size as f64;
+
+        match self.metric {
+            EnrichMetric::Records => {
+                // Records land independently: a sum of Bernoulli trials
+                let (mut mean, mut var) = (0.0, 0.0);
+                for iv in a {
+                    let len = iv.end.saturating_sub(iv.start);
+                    let p = (targets.hit_starts(len) as f64 / genome_size).min(1.0);
+                    mean += p;
+                    var += p * (1.0 - p);
+                }
+                Ok((mean, Some(var)))
+            }
+            EnrichMetric::Bases => {
+                // Neighbouring bases are not independent, so no variance
+                let a_bases = Targets::new(a).bases as f64;
+                Ok((a_bases * targets.bases as f64 / genome_size, None))
+            }
+        }
+    }
+
+    /// Overlap of each shuffle of A, in shuffle order.
+    fn shuffle_null(
+        &self,
+        a: &[Interval],
+        targets: &Targets,
+        genome: &Genome,
+    ) -> Result<Vec<u64>, BedError> {
+        (0..self.shuffles)
+            .into_par_iter()
+            .map(|k| {
+                let mut rng = SeededRng::stream(self.shuffler.seed, k as u64);
+                let mut tally = Tally::new(targets, self.metric);
+                self.shuffler
+                    .shuffle_each(a, genome, &mut rng, |chrom, start, end| {
+                        tally.add(chrom, start, end)
+                    })?;
+                Ok(tally.finish())
+            })
+            .collect()
+    }
+}
+
+/// Write the one-row report with a header line.
+pub fn write_report<W: Write>(result: &EnrichResult, output: &mut W) -> Result<(), BedError> {
+    let opt = |v: Option<f64>| v.map_or_else(|| "NA".to_string(), format_g);
+    writeln!(
+        output,
+        "metric\tobserved\texpected\tfold_enrichment\tz_score\tp_value\tshuffles"
+    )?;
+    writeln!(
+        output,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        result.metric.name(),
+        result.observed,
+        format_g(result.expected),
+        opt(result.fold_enrichment()),
+        opt(result.z_score),
+        opt(result.p_value),
+        result.shuffles
+    )?;
+    Ok(())
+}
+
+/// B merged per chromosome, with the gaps between its intervals.
+struct Targets {
+    index: HashMap<String, usize>,
+    merged: Vec<Vec<(u64, u64)>>,
+    /// Bases covered by B.
+    bases: u64,
+    /// Sorted distances from each merged interval back to the previous one
+    /// (or the chromosome start).
+    gaps: Vec<u64>,
+    /// Prefix sums of `gaps`, one longer than it.
+    gap_sums: Vec<u64>,
+}
+
+impl Targets {
+    fn new(intervals: &[Interval]) -> Self {
+        let mut index = HashMap::new();
+        let mut merged: Vec<Vec<(u64, u64)>> = Vec::new();
+        for iv in intervals {
+            let idx = *index.entry(iv.chrom.clone()).or_insert_with(|| {
+                merged.push(Vec::new());
+                merged.len() - 1
+            });
+            merged[idx].push((iv.start, iv.end));
+        }
+
+        let mut bases = 0;
+        let mut gaps = Vec::new();
+        for runs in &mut merged {
+            *runs = merge_runs(std::mem::take(runs));
+            let mut prev_end = 0;
+            for &(start, end) in runs.iter() {
+                bases += end - start;
+                gaps.push(start - prev_end);
+                prev_end = end;
+            }
+        }
+        gaps.sort_unstable();
+        let gap_sums = std::iter::once(0)
+            .chain(gaps.iter().scan(0u64, |sum, &g| {
+                *sum += g;
+                Some(*sum)
+            }))
+            .collect();
+
+        Self {
+            index,
+            merged,
+            bases,
+            gaps,
+            gap_sums,
+        }
+    }
+
+    /// Whether `[start, end)` overlaps B; an empty interval counts as its
+    /// first base.
+    fn overlaps(&self, idx: usize, start: u64, end: u64) -> bool {
+        let runs = &self.merged[idx];
+        let end = end.max(start + 1);
+        let j = runs.partition_point(|&(_, e)| e <= start);
+        runs.get(j).is_some_and(|&(s, _)| s < end)
+    }
+
+    /// Bases of the sorted, disjoint `runs` covered by B.
+    fn covered(&self, idx: usize, runs: &[(u64, u64)]) -> u64 {
+        let targets = &self.merged[idx];
+        let mut total = 0;
+        for &(start, end) in runs {
+            let mut j = targets.partition_point(|&(_, e)| e <= start);
+            while let Some(&(s, e)) = targets.get(j) {
+                if s >= end {
+                    break;
+                }
+                total += e.min(end) - s.max(start);
+                j += 1;
+            }
+        }
+        total
+    }
+
+    /// Start positions at which a window of `len` bases overlaps B: each
+    /// merged interval plus up to `len - 1` bases before it.
+    fn hit_starts(&self, len: u64) -> u64 {
+        let pad = len.max(1) - 1;
+        let k = self.gaps.partition_point(|&g| g < pad);
+        self.bases + self.gap_sums[k] + (self.gaps.len() - k) as u64 * pad
+    }
+}
+
+/// Overlap of one set of A placements with B.
+struct Tally<'t> {
+    targets: &'t Targets,
+    metric: EnrichMetric,
+    hits: u64,
+    /// Placements on B's chromosomes, for the bases metric.
+    placed: Vec<(usize, u64, u64)>,
+}
+
+impl<'t> Tally<'t> {
+    fn new(targets: &'t Targets, metric: EnrichMetric) -> Self {
+        Self {
+            targets,
+            metric,
+            hits: 0,
+            placed: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, chrom: &str, start: u64, end: u64) {
+        let Some(&idx) = self.targets.index.get(chrom) else {
+            return;
+        };
+        match self.metric {
+            EnrichMetric::Records => self.hits += self.targets.overlaps(idx, start, end) as u64,
+            EnrichMetric::Bases => self.placed.push((idx, start, end)),
+        }
+    }
+
+    fn finish(mut self) -> u64 {
+        if self.metric == EnrichMetric::Records {
+            return self.hits;
+        }
+        self.placed.sort_unstable();
+        let mut total = 0;
+        for chunk in self.placed.chunk_by(|x, y| x.0 == y.0) {
+            let runs = merge_runs(chunk.iter().map(|&(_, s, e)| (s, e)).collect());
+            total += self.targets.covered(chunk[0].0, &runs);
+        }
+        total
+    }
+}
+
+/// Sort and merge overlapping or touching intervals.
+fn merge_runs(mut runs: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    runs.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(runs.len());
+    for (start, end) in runs {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bed::parse_intervals;
+
+    fn genome() -> Genome {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 10_000);
+        genome.insert("chr2".to_string(), 10_000);
+        genome
+    }
+
+    #[test]
+    fn test_coverage_fraction_null() {
+        let a = parse_intervals("chr1\t100\t110\nchr1\t105\t120\nchr2\t0\t10\n").unwrap();
+        let b = parse_intervals("chr1\t100\t200\nchr1\t150\t300\n").unwrap();
+
+        let result = EnrichCommand::new().enrich(&a, &b, &genome()).unwrap();
+        assert_eq!(result.observed, 2);
+        // A 10 bp window touches [100, 300) from 209 starts, a 15 bp one from 214
+        let expected = (2.0 * 209.0 + 214.0) / 20_000.0;
+        assert!((result.expected - expected).abs() < 1e-12);
+        assert!(result.z_score.unwrap() > 10.0);
+        assert_eq!(result.p_value, None);
+
+        let result = EnrichCommand::new()
+            .with_metric(EnrichMetric::Bases)
+            .enrich(&a, &b, &genome())
+            .unwrap();
+        assert_eq!(result.observed, 20);
+        assert!((result.expected - 30.0 * 200.0 / 20_000.0).abs() < 1e-12);
+        assert_eq!(result.z_score, None);
+    }
+
+    #[test]
+    fn test_shuffle_null_is_seeded() {
+        let a = parse_intervals("chr1\t100\t200\nchr1\t300\t400\nchr2\t5\t50\n").unwrap();
+        let b = parse_intervals("chr1\t0\t5000\n").unwrap();
+        let cmd = EnrichCommand::new().with_shuffles(50);
+
+        let result = cmd.enrich(&a, &b, &genome()).unwrap();
+        assert_eq!(result.observed, 2);
+        // A quarter of the genome is B, so under one record hits by chance
+        assert!(result.expected > 0.3 && result.expected < 1.5);
+        let p = result.p_value.unwrap();
+        assert!(p > 1.0 / 51.0 && p <= 1.0);
+        assert_eq!(result, cmd.enrich(&a, &b, &genome()).unwrap());
+
+        let mut output = Vec::new();
+        write_report(&result, &mut output).unwrap();
+        let report = String::from_utf8(output).unwrap();
+        assert!(report.starts_with("metric\tobserved\texpected\t"));
+        assert!(report.lines().nth(1).unwrap().starts_with("records\t2\t"));
+    }
+
+    #[test]
+    fn test_targets_helpers() {
+        let b = parse_intervals("chr1\t10\t20\nchr1\t15\t30\nchr1\t40\t50\n").unwrap();
+        let targets = Targets::new(&b);
+        assert_eq!(targets.bases, 30);
+        // Gaps 10 and 10; a 6 bp window hits from 5 extra starts per interval
+        assert_eq!(targets.hit_starts(6), 40);
+        assert_eq!(targets.hit_starts(50), 50);
+        assert!(targets.overlaps(0, 29, 29));
+        assert!(!targets.overlaps(0, 30, 40));
+        assert_eq!(targets.covered(0, &[(0, 12), (25, 45)]), 12);
+    }
+}
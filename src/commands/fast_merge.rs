@@ -11,6 +11,9 @@
 //! Memory: O(1) - only tracks current merge span
 
 use crate::bed::BedError;
+use crate::commands::merge::{allowed_gap, within_reach, RelativeDistance};
+use crate::interchange::stdin_reader;
+use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
 use memchr::memchr;
 use std::fs::File;
 use std::io::{self, BufWriter, Read, Write};
@@ -26,6 +29,12 @@ pub struct FastMergeCommand {
     pub distance: u64,
     /// Report count of merged intervals
     pub count: bool,
+    /// Only output merged intervals formed from at least this many records
+    pub min_count: usize,
+    /// Merge distance relative to interval length; overrides `distance`
+    pub relative_distance: Option<RelativeDistance>,
+    /// Merge intervals exactly `distance` apart (book-ended at distance 0)
+    pub book_ended: bool,
 }
 
 impl Default for FastMergeCommand {
@@ -39,6 +48,9 @@ impl FastMergeCommand {
         Self {
             distance: 0,
             count: false,
+            min_count: 1,
+            relative_distance: None,
+            book_ended: true,
         }
     }
 
@@ -47,6 +59,37 @@ impl FastMergeCommand {
         self
     }
 
+    pub fn with_min_count(mut self, n: usize) -> Self {
+        self.min_count = n;
+        self
+    }
+
+    /// Measure the merge distance relative to interval length.
+    pub fn with_relative_distance(mut self, relative: Option<RelativeDistance>) -> Self {
+        self.relative_distance = relative;
+        self
+    }
+
+    /// Set whether intervals exactly the merge distance apart are merged.
+    pub fn with_book_ended(mut self, book_ended: bool) -> Self {
+        self.book_ended = book_ended;
+        self
+    }
+
+    /// Whether the next interval joins the current span.
+    #[inline(always)]
+    fn reaches(&self, span_start: u64, span_end: u64, next_start: u64, next_end: u64) -> bool {
+        let gap = allowed_gap(
+            self.distance,
+            self.relative_distance,
+            span_start,
+            span_end,
+            next_start,
+            next_end,
+        );
+        within_reach(next_start, span_end, gap, self.book_ended)
+    }
+
     /// Run merge on a file.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
@@ -59,8 +102,7 @@ impl FastMergeCommand {
 
     /// Run merge from stdin.
     pub fn run_stdin<W: Write>(&self, output: &mut W) -> Result<FastMergeStats, BedError> {
-        let stdin = io::stdin();
-        self.run_reader(stdin.lock(), output)
+        self.run_reader(stdin_reader(), output)
     }
 
     /// Core merge implementation with zero-allocation parsing.
@@ -110,11 +152,7 @@ impl FastMergeCommand {
                 pos = line_end + 1;
 
                 // Skip empty lines and headers
-                if line.is_empty()
-                    || line[0] == b'#'
-                    || line.starts_with(b"track")
-                    || line.starts_with(b"browser")
-                {
+                if should_skip_line(line) {
                     continue;
                 }
 
@@ -125,7 +163,7 @@ impl FastMergeCommand {
                     if has_current {
                         // Check if should merge
                         let should_merge = chrom == current_chrom.as_slice()
-                            && start <= current_end + self.distance;
+                            && self.reaches(current_start, current_end, start, end);
 
                         if should_merge {
                             // Extend current span
@@ -135,19 +173,15 @@ impl FastMergeCommand {
                             current_count += 1;
                         } else {
                             // Output current span
-                            write_bed3_fast(
+                            self.write_span(
                                 &mut writer,
                                 &current_chrom,
                                 current_start,
                                 current_end,
-                                if self.count {
-                                    Some(current_count)
-                                } else {
-                                    None
-                                },
+                                current_count,
                                 &mut itoa_buf,
+                                &mut stats,
                             )?;
-                            stats.intervals_written += 1;
 
                             // Start new span
                             current_chrom.clear();
@@ -176,17 +210,13 @@ impl FastMergeCommand {
         // Handle any remaining data (file without final newline)
         if !leftover.is_empty() {
             let line = leftover.as_slice();
-            if !line.is_empty()
-                && line[0] != b'#'
-                && !line.starts_with(b"track")
-                && !line.starts_with(b"browser")
-            {
+            if !should_skip_line(line) {
                 if let Some((chrom, start, end)) = parse_bed3_fast(line) {
                     stats.intervals_read += 1;
 
                     if has_current {
                         let should_merge = chrom == current_chrom.as_slice()
-                            && start <= current_end + self.distance;
+                            && self.reaches(current_start, current_end, start, end);
 
                         if should_merge {
                             if end > current_end {
@@ -194,19 +224,15 @@ impl FastMergeCommand {
                             }
                             current_count += 1;
                         } else {
-                            write_bed3_fast(
+                            self.write_span(
                                 &mut writer,
                                 &current_chrom,
                                 current_start,
                                 current_end,
-                                if self.count {
-                                    Some(current_count)
-                                } else {
-                                    None
-                                },
+                                current_count,
                                 &mut itoa_buf,
+                                &mut stats,
                             )?;
-                            stats.intervals_written += 1;
 
                             current_chrom.clear();
                             current_chrom.extend_from_slice(chrom);
@@ -227,76 +253,54 @@ impl FastMergeCommand {
 
         // Output final span
         if has_current {
-            write_bed3_fast(
+            self.write_span(
                 &mut writer,
                 &current_chrom,
                 current_start,
                 current_end,
-                if self.count {
-                    Some(current_count)
-                } else {
-                    None
-                },
+                current_count,
                 &mut itoa_buf,
+                &mut stats,
             )?;
-            stats.intervals_written += 1;
         }
 
         writer.flush().map_err(BedError::Io)?;
         Ok(stats)
     }
+
+    /// Write a finished span unless it has fewer than `min_count` records.
+    #[inline(always)]
+    fn write_span<W: Write>(
+        &self,
+        writer: &mut W,
+        chrom: &[u8],
+        start: u64,
+        end: u64,
+        count: usize,
+        itoa_buf: &mut itoa::Buffer,
+        stats: &mut FastMergeStats,
+    ) -> io::Result<()> {
+        if count < self.min_count {
+            return Ok(());
+        }
+        write_bed3_fast(
+            writer,
+            chrom,
+            start,
+            end,
+            if self.count { Some(count) } else { None },
+            itoa_buf,
+        )?;
+        stats.intervals_written += 1;
+        Ok(())
+    }
 }
 
 /// Parse BED3 fields from a byte slice with zero allocation.
 /// Returns (chrom, start, end) as byte slice and parsed integers.
 #[inline(always)]
 fn parse_bed3_fast(line: &[u8]) -> Option<(&[u8], u64, u64)> {
-    // Find first tab (end of chrom)
-    let tab1 = memchr(b'\t', line)?;
-    let chrom = &line[..tab1];
-
-    // Find second tab (end of start)
-    let rest1 = &line[tab1 + 1..];
-    let tab2 = memchr(b'\t', rest1)?;
-    let start_bytes = &rest1[..tab2];
-
-    // Find third tab or end of line (end of end field)
-    let rest2 = &rest1[tab2 + 1..];
-    let end_bytes = if let Some(tab3) = memchr(b'\t', rest2) {
-        &rest2[..tab3]
-    } else {
-        // Trim potential \r from end
-        let mut end = rest2;
-        if end.last() == Some(&b'\r') {
-            end = &end[..end.len() - 1];
-        }
-        end
-    };
-
-    // Parse integers with fast path
-    let start = parse_u64_fast(start_bytes)?;
-    let end = parse_u64_fast(end_bytes)?;
-
-    Some((chrom, start, end))
-}
-
-/// Ultra-fast u64 parsing - no bounds checking, assumes valid input.
-/// ~3-5x faster than str::parse for typical BED coordinates.
-#[inline(always)]
-fn parse_u64_fast(bytes: &[u8]) -> Option<u64> {
-    if bytes.is_empty() {
-        return None;
-    }
-
-    let mut result: u64 = 0;
-    for &b in bytes {
-        let digit = b.wrapping_sub(b'0');
-        if digit > 9 {
-            return None;
-        }
-        result = result.wrapping_mul(10).wrapping_add(digit as u64);
-    }
-    Some(result)
+    parse_bed3_bytes(line)
 }
 
 /// Write BED3 output using itoa for fast integer formatting.
@@ -354,15 +358,7 @@ impl std::fmt::Display for FastMergeStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_parse_u64_fast() {
-        assert_eq!(parse_u64_fast(b"0"), Some(0));
-        assert_eq!(parse_u64_fast(b"123"), Some(123));
-        assert_eq!(parse_u64_fast(b"100000000"), Some(100_000_000));
-        assert_eq!(parse_u64_fast(b""), None);
-        assert_eq!(parse_u64_fast(b"abc"), None);
-    }
+    use crate::commands::merge::DistanceBasis;
 
     #[test]
     fn test_parse_bed3_fast() {
@@ -400,6 +396,31 @@ mod tests {
         assert_eq!(stats.intervals_written, 2);
     }
 
+    #[test]
+    fn test_fast_merge_book_ended() {
+        let input = b"chr1\t100\t200\nchr1\t200\t300\nchr1\t310\t400\n";
+        let run = |cmd: FastMergeCommand| {
+            let mut output = Vec::new();
+            cmd.run_reader(&input[..], &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(
+            run(FastMergeCommand::new()),
+            "chr1\t100\t300\nchr1\t310\t400\n"
+        );
+        assert_eq!(
+            run(FastMergeCommand::new().with_book_ended(false)),
+            "chr1\t100\t200\nchr1\t200\t300\nchr1\t310\t400\n"
+        );
+        assert_eq!(
+            run(FastMergeCommand::new()
+                .with_distance(10)
+                .with_book_ended(false)),
+            "chr1\t100\t300\nchr1\t310\t400\n"
+        );
+    }
+
     #[test]
     fn test_fast_merge_with_distance() {
         let input = b"chr1\t100\t200\nchr1\t250\t350\n";
@@ -443,4 +464,33 @@ mod tests {
         assert!(lines[0].starts_with("chr1\t100\t250"));
         assert!(lines[1].starts_with("chr2\t100\t250"));
     }
+
+    #[test]
+    fn test_fast_merge_min_count() {
+        // Last span has no trailing newline to exercise the leftover path
+        let input = b"chr1\t100\t200\nchr1\t300\t400\nchr1\t350\t450\nchr1\t380\t390\nchr2\t0\t10\nchr2\t5\t20";
+        let mut cmd = FastMergeCommand::new().with_min_count(2);
+        cmd.count = true;
+        let mut output = Vec::new();
+
+        let stats = cmd.run_reader(&input[..], &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "chr1\t300\t450\t3\nchr2\t0\t20\t2\n");
+        assert_eq!(stats.intervals_read, 6);
+        assert_eq!(stats.intervals_written, 2);
+    }
+
+    #[test]
+    fn test_fast_merge_relative_distance() {
+        let input = b"chr1\t0\t10\nchr1\t12\t22\nchr1\t100\t1100\nchr1\t1150\t2150";
+        let cmd = FastMergeCommand::new()
+            .with_relative_distance(Some(RelativeDistance::new(10.0, DistanceBasis::Shorter)));
+        let mut output = Vec::new();
+
+        cmd.run_reader(&input[..], &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "chr1\t0\t10\nchr1\t12\t22\nchr1\t100\t2150\n");
+    }
 }
@@ -4,6 +4,7 @@
 //! - Memory-mapped file I/O for zero-copy access
 //! - Zero-allocation parsing (byte slices, no String allocation)
 //! - LSD Radix Sort for (chrom_index, start, end) - O(n)
+//! - With a genome file, a single genome-wide key replaces (chrom_index, start)
 //! - Parallel parsing with Rayon
 //! - Fast integer parsing and output
 //!
@@ -14,12 +15,17 @@
 //! 4. Input order preserved for ties (stable sort)
 
 use crate::bed::BedError;
+use crate::config::{delimiter, Delimiter};
+use crate::interchange::stdin_reader;
+use crate::interval::GlobalCoords;
+use crate::parallel::{first_touch_vec, PoolHandle};
+use crate::streaming::parsing::{parse_bed3_whitespace, should_skip_line};
 use memchr::memchr;
 use memmap2::Mmap;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
 
 /// Buffer size for I/O operations (256KB for better throughput)
@@ -34,15 +40,18 @@ const PARALLEL_THRESHOLD: usize = 10_000;
 /// Minimum records to use radix sort (smaller uses comparison sort)
 const RADIX_THRESHOLD: usize = 256;
 
+/// Chromosome index in sort order. 32 bits cover pan-genome and metagenome
+/// assemblies with millions of contigs, and take the space of what used to
+/// be a u16 index plus padding, so the entry size is unchanged.
+type ChromIndex = u32;
+
 /// Entry for sorting - designed for cache-efficient radix sort.
 /// Layout optimized for sequential memory access during radix passes.
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
 struct SortEntry {
     /// Chromosome index (from lexicographic ordering)
-    chrom_index: u16,
-    /// Padding for alignment
-    _padding: u16,
+    chrom_index: ChromIndex,
     /// Start coordinate (u32 is sufficient for genomic coordinates)
     start: u32,
     /// End coordinate
@@ -59,6 +68,8 @@ pub struct FastSortStats {
     pub records_read: usize,
     pub unique_chroms: usize,
     pub used_radix_sort: bool,
+    /// Radix sort keyed on genome-wide positions (see [`GlobalCoords`])
+    pub used_global_key: bool,
     pub used_mmap: bool,
 }
 
@@ -66,10 +77,11 @@ impl std::fmt::Display for FastSortStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Records: {}, Chroms: {}, Radix: {}, Mmap: {}",
+            "Records: {}, Chroms: {}, Radix: {}, Global key: {}, Mmap: {}",
             self.records_read,
             self.unique_chroms,
             if self.used_radix_sort { "yes" } else { "no" },
+            if self.used_global_key { "yes" } else { "no" },
             if self.used_mmap { "yes" } else { "no" }
         )
     }
@@ -83,7 +95,12 @@ pub struct FastSortCommand {
     /// Reverse sort order
     pub reverse: bool,
     /// Genome-based chromosome ordering (chrom bytes -> index)
-    genome_order: Option<HashMap<Vec<u8>, u16>>,
+    genome_order: Option<HashMap<Vec<u8>, ChromIndex>>,
+    /// Genome-wide offsets of the genome's chromosomes, indexed like
+    /// `genome_order`
+    global: Option<GlobalCoords>,
+    /// Thread pool for parallel parsing
+    pub pool: PoolHandle,
 }
 
 impl Default for FastSortCommand {
@@ -98,19 +115,31 @@ impl FastSortCommand {
             use_radix: true,
             reverse: false,
             genome_order: None,
+            global: None,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Set genome-based chromosome ordering.
     /// Chromosomes will be sorted in the order they appear in the genome file.
     /// Unknown chromosomes are placed after all known chromosomes.
+    ///
+    /// When every record lies on a known chromosome, radix sort orders on a
+    /// single genome-wide position instead of chromosome and start.
     pub fn with_genome(mut self, genome: &crate::genome::Genome) -> Self {
-        let order: HashMap<Vec<u8>, u16> = genome
+        let order: HashMap<Vec<u8>, ChromIndex> = genome
             .chromosomes()
             .enumerate()
-            .map(|(i, chrom)| (chrom.as_bytes().to_vec(), i as u16))
+            .map(|(i, chrom)| (chrom.as_bytes().to_vec(), i as ChromIndex))
             .collect();
         self.genome_order = Some(order);
+        self.global = Some(GlobalCoords::from_genome(genome));
         self
     }
 
@@ -137,8 +166,7 @@ impl FastSortCommand {
 
     /// Run fast sort from stdin.
     pub fn run_stdin<W: Write>(&self, output: &mut W) -> Result<FastSortStats, BedError> {
-        let stdin = io::stdin();
-        self.sort_buffered(stdin.lock(), output)
+        self.sort_buffered(stdin_reader(), output)
     }
 
     /// Sort using memory-mapped file (zero-copy).
@@ -162,7 +190,8 @@ impl FastSortCommand {
 
         // Phase 3: Parse all records into sort entries (parallel for large files)
         let entries = if num_records >= PARALLEL_THRESHOLD {
-            parse_entries_parallel(data, &line_offsets, &chrom_index)
+            self.pool
+                .install(|| parse_entries_parallel(data, &line_offsets, &chrom_index))
         } else {
             parse_entries_sequential(data, &line_offsets, &chrom_index)
         };
@@ -170,12 +199,7 @@ impl FastSortCommand {
         stats.records_read = entries.len();
 
         // Phase 4: Sort using LSD radix sort or comparison sort
-        let sorted_entries = if self.use_radix && entries.len() >= RADIX_THRESHOLD {
-            stats.used_radix_sort = true;
-            radix_sort_lsd(entries)
-        } else {
-            comparison_sort_entries(entries)
-        };
+        let sorted_entries = self.sort_entries(entries, &mut stats);
 
         // Phase 5: Output sorted records
         let mut writer = BufWriter::with_capacity(BUF_SIZE, output);
@@ -227,19 +251,15 @@ impl FastSortCommand {
         stats.unique_chroms = chrom_index.len();
 
         let entries = if num_records >= PARALLEL_THRESHOLD {
-            parse_entries_parallel(&data, &line_offsets, &chrom_index)
+            self.pool
+                .install(|| parse_entries_parallel(&data, &line_offsets, &chrom_index))
         } else {
             parse_entries_sequential(&data, &line_offsets, &chrom_index)
         };
 
         stats.records_read = entries.len();
 
-        let sorted_entries = if self.use_radix && entries.len() >= RADIX_THRESHOLD {
-            stats.used_radix_sort = true;
-            radix_sort_lsd(entries)
-        } else {
-            comparison_sort_entries(entries)
-        };
+        let sorted_entries = self.sort_entries(entries, &mut stats);
 
         let mut writer = BufWriter::with_capacity(BUF_SIZE, output);
         if self.reverse {
@@ -261,6 +281,30 @@ impl FastSortCommand {
 
         Ok(stats)
     }
+
+    /// Sort parsed entries, picking the fastest applicable algorithm.
+    fn sort_entries(&self, entries: Vec<SortEntry>, stats: &mut FastSortStats) -> Vec<SortEntry> {
+        if !self.use_radix || entries.len() < RADIX_THRESHOLD {
+            return comparison_sort_entries(entries);
+        }
+        stats.used_radix_sort = true;
+
+        // The global key only orders correctly if each record starts inside
+        // a chromosome of the genome; otherwise keys could collide across
+        // chromosomes
+        if let Some(offsets) = self.global.as_ref().map(GlobalCoords::offsets) {
+            let known = offsets.len().saturating_sub(1);
+            let in_genome = entries.iter().all(|e| {
+                let i = e.chrom_index as usize;
+                i < known && (e.start as u64) < offsets[i + 1] - offsets[i]
+            });
+            if in_genome {
+                stats.used_global_key = true;
+                return radix_sort_global(entries, offsets);
+            }
+        }
+        radix_sort_lsd(entries)
+    }
 }
 
 /// Find all line start/end offsets in the data.
@@ -290,11 +334,7 @@ fn find_line_offsets(data: &[u8]) -> Vec<(usize, usize)> {
             }
 
             // Skip headers and empty lines
-            if line_end > line_start
-                && data[line_start] != b'#'
-                && !data[line_start..].starts_with(b"track")
-                && !data[line_start..].starts_with(b"browser")
-            {
+            if !should_skip_line(&data[line_start..line_end]) {
                 offsets.push((line_start, line_end));
             }
 
@@ -302,11 +342,7 @@ fn find_line_offsets(data: &[u8]) -> Vec<(usize, usize)> {
         } else {
             // Last line without newline
             let line_end = data.len();
-            if line_end > line_start
-                && data[line_start] != b'#'
-                && !data[line_start..].starts_with(b"track")
-                && !data[line_start..].starts_with(b"browser")
-            {
+            if !should_skip_line(&data[line_start..line_end]) {
                 offsets.push((line_start, line_end));
             }
             break;
@@ -322,20 +358,23 @@ fn find_line_offsets(data: &[u8]) -> Vec<(usize, usize)> {
 fn build_chrom_index(
     data: &[u8],
     line_offsets: &[(usize, usize)],
-    genome_order: Option<&HashMap<Vec<u8>, u16>>,
-) -> HashMap<Vec<u8>, u16> {
-    let mut chroms: Vec<Vec<u8>> = Vec::new();
-
+    genome_order: Option<&HashMap<Vec<u8>, ChromIndex>>,
+) -> HashMap<Vec<u8>, ChromIndex> {
+    // Records of a chromosome are usually adjacent, so checking the last
+    // name first skips most hash lookups; the set keeps files with millions
+    // of contigs linear.
+    let mut seen: HashSet<&[u8]> = HashSet::new();
+    let mut last: &[u8] = &[];
     for &(start, end) in line_offsets {
         let line = &data[start..end];
-        if let Some(tab_pos) = memchr(b'\t', line) {
-            let chrom = &line[..tab_pos];
-            // Use a simple linear search for small number of chromosomes
-            if !chroms.iter().any(|c| c.as_slice() == chrom) {
-                chroms.push(chrom.to_vec());
+        if let Some((chrom, _, _)) = parse_bed3(line) {
+            if chrom != last || seen.is_empty() {
+                seen.insert(chrom);
+                last = chrom;
             }
         }
     }
+    let mut chroms: Vec<Vec<u8>> = seen.into_iter().map(<[u8]>::to_vec).collect();
 
     if let Some(genome_order) = genome_order {
         // Use genome file order: known chroms first, then unknown chroms lexicographically
@@ -368,7 +407,7 @@ fn build_chrom_index(
             }
         }
         for (i, chrom) in unknown.into_iter().enumerate() {
-            result.insert(chrom, (max_known + 1 + i) as u16);
+            result.insert(chrom, (max_known + 1 + i) as ChromIndex);
         }
         result
     } else {
@@ -378,15 +417,31 @@ fn build_chrom_index(
         chroms
             .into_iter()
             .enumerate()
-            .map(|(i, c)| (c, i as u16))
+            .map(|(i, c)| (c, i as ChromIndex))
             .collect()
     }
 }
 
 /// Parse BED3 fields from a line slice.
 /// Returns (chrom, start, end) or None if invalid.
+///
+/// CRLF endings are tolerated, and lines without a tab fall back to
+/// whitespace-delimited parsing unless `--delim tab` is set.
 #[inline(always)]
 fn parse_bed3(line: &[u8]) -> Option<(&[u8], u32, u32)> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    match parse_bed3_tab(line) {
+        Some(fields) => Some(fields),
+        None if delimiter() != Delimiter::Tab && memchr(b'\t', line).is_none() => {
+            let (chrom, start, end, _) = parse_bed3_whitespace(line)?;
+            Some((chrom, start as u32, end as u32))
+        }
+        None => None,
+    }
+}
+
+#[inline(always)]
+fn parse_bed3_tab(line: &[u8]) -> Option<(&[u8], u32, u32)> {
     let tab1 = memchr(b'\t', line)?;
     let chrom = &line[..tab1];
 
@@ -429,7 +484,7 @@ fn parse_u32_fast(bytes: &[u8]) -> Option<u32> {
 fn parse_entries_sequential(
     data: &[u8],
     line_offsets: &[(usize, usize)],
-    chrom_index: &HashMap<Vec<u8>, u16>,
+    chrom_index: &HashMap<Vec<u8>, ChromIndex>,
 ) -> Vec<SortEntry> {
     let mut entries = Vec::with_capacity(line_offsets.len());
 
@@ -439,7 +494,6 @@ fn parse_entries_sequential(
             if let Some(&chrom_idx) = chrom_index.get(chrom) {
                 entries.push(SortEntry {
                     chrom_index: chrom_idx,
-                    _padding: 0,
                     start,
                     end,
                     line_start: line_start as u32,
@@ -456,7 +510,7 @@ fn parse_entries_sequential(
 fn parse_entries_parallel(
     data: &[u8],
     line_offsets: &[(usize, usize)],
-    chrom_index: &HashMap<Vec<u8>, u16>,
+    chrom_index: &HashMap<Vec<u8>, ChromIndex>,
 ) -> Vec<SortEntry> {
     // Determine chunk size for parallel processing
     let num_threads = rayon::current_num_threads();
@@ -472,7 +526,6 @@ fn parse_entries_parallel(
                     if let Some(&chrom_idx) = chrom_index.get(chrom) {
                         entries.push(SortEntry {
                             chrom_index: chrom_idx,
-                            _padding: 0,
                             start,
                             end,
                             line_start: line_start as u32,
@@ -512,9 +565,9 @@ fn comparison_sort_entries(mut entries: Vec<SortEntry>) -> Vec<SortEntry> {
 /// - Passes 1-4: line_start bytes 0-3 (least significant, for stability)
 /// - Passes 5-8: end bytes 0-3
 /// - Passes 9-12: start bytes 0-3
-/// - Passes 13-14: chrom_index bytes 0-1 (most significant)
+/// - Passes 13-16: chrom_index bytes 0-3 (most significant)
 ///
-/// Total: 14 passes max, optimized by skipping passes where all values have same byte.
+/// Total: 16 passes max, optimized by skipping passes where all values have same byte.
 fn radix_sort_lsd(entries: Vec<SortEntry>) -> Vec<SortEntry> {
     if entries.len() < RADIX_THRESHOLD {
         return comparison_sort_entries(entries);
@@ -522,17 +575,16 @@ fn radix_sort_lsd(entries: Vec<SortEntry>) -> Vec<SortEntry> {
 
     let n = entries.len();
     let mut src = entries;
-    let mut dst = vec![
+    let mut dst = first_touch_vec(
+        n,
         SortEntry {
             chrom_index: 0,
-            _padding: 0,
             start: 0,
             end: 0,
             line_start: 0,
             line_len: 0,
-        };
-        n
-    ];
+        },
+    );
 
     // LSD radix sort: process from least significant to most significant
     // Order: line_start -> end -> start -> chrom_index
@@ -562,8 +614,8 @@ fn radix_sort_lsd(entries: Vec<SortEntry>) -> Vec<SortEntry> {
         std::mem::swap(&mut src, &mut dst);
     }
 
-    // Pass 13-14: Sort by chrom_index (most significant)
-    for shift in (0u32..16).step_by(8) {
+    // Pass 13-16: Sort by chrom_index (most significant)
+    for shift in (0u32..ChromIndex::BITS).step_by(8) {
         if !radix_pass_chrom(&mut src, &mut dst, shift) {
             continue;
         }
@@ -573,6 +625,86 @@ fn radix_sort_lsd(entries: Vec<SortEntry>) -> Vec<SortEntry> {
     src
 }
 
+/// LSD radix sort on (global position, end, line_start).
+///
+/// The global position `offsets[chrom_index] + start` orders chromosomes by
+/// genome order and starts within them in one key, so a chromosome and a
+/// start pass collapse into the bytes of a single u64. Callers must ensure
+/// every start lies inside its chromosome.
+fn radix_sort_global(entries: Vec<SortEntry>, offsets: &[u64]) -> Vec<SortEntry> {
+    let n = entries.len();
+    let mut src = entries;
+    let mut dst = first_touch_vec(
+        n,
+        SortEntry {
+            chrom_index: 0,
+            start: 0,
+            end: 0,
+            line_start: 0,
+            line_len: 0,
+        },
+    );
+
+    for shift in (0u32..32).step_by(8) {
+        if radix_pass_line_start(&mut src, &mut dst, shift) {
+            std::mem::swap(&mut src, &mut dst);
+        }
+    }
+    for shift in (0u32..32).step_by(8) {
+        if radix_pass_end(&mut src, &mut dst, shift) {
+            std::mem::swap(&mut src, &mut dst);
+        }
+    }
+    // Bytes above the genome length are zero for every key
+    let key_bits = u64::BITS - offsets.last().copied().unwrap_or(0).leading_zeros();
+    for shift in (0u32..key_bits).step_by(8) {
+        if radix_pass_global(&mut src, &mut dst, offsets, shift) {
+            std::mem::swap(&mut src, &mut dst);
+        }
+    }
+
+    src
+}
+
+/// Single radix pass by global position. Returns false if all bytes are same (can skip).
+#[inline]
+fn radix_pass_global(
+    src: &mut [SortEntry],
+    dst: &mut [SortEntry],
+    offsets: &[u64],
+    shift: u32,
+) -> bool {
+    let key = |entry: &SortEntry| offsets[entry.chrom_index as usize] + entry.start as u64;
+    let mut count = [0usize; 257];
+
+    for entry in src.iter() {
+        let byte = ((key(entry) >> shift) & 0xFF) as usize;
+        count[byte + 1] += 1;
+    }
+
+    let mut non_zero_buckets = 0;
+    for &c in &count[1..] {
+        if c > 0 {
+            non_zero_buckets += 1;
+        }
+    }
+    if non_zero_buckets <= 1 {
+        return false;
+    }
+
+    for i in 1..257 {
+        count[i] += count[i - 1];
+    }
+
+    for entry in src.iter() {
+        let byte = ((key(entry) >> shift) & 0xFF) as usize;
+        dst[count[byte]] = *entry;
+        count[byte] += 1;
+    }
+
+    true
+}
+
 /// Single radix pass by line_start field. Returns false if all bytes are same (can skip).
 #[inline]
 fn radix_pass_line_start(src: &mut [SortEntry], dst: &mut [SortEntry], shift: u32) -> bool {
@@ -917,12 +1049,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_more_chroms_than_u16() {
+        // One record per contig, in reverse order, with long names
+        let n = 70_000;
+        let prefix = "contig_".repeat(40);
+        let mut input = Vec::new();
+        for i in (0..n).rev() {
+            input.extend_from_slice(format!("{}{:06}\t{}\t{}\n", prefix, i, i, i + 1).as_bytes());
+        }
+
+        let cmd = FastSortCommand::new();
+        let mut output = Vec::new();
+        let stats = cmd.sort_buffered(&input[..], &mut output).unwrap();
+        assert_eq!(stats.unique_chroms, n);
+        assert!(stats.used_radix_sort);
+
+        let result = String::from_utf8(output).unwrap();
+        let starts: Vec<usize> = result
+            .lines()
+            .map(|l| l.split('\t').nth(1).unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(starts, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sort_entry_size() {
+        assert_eq!(std::mem::size_of::<SortEntry>(), 20);
+    }
+
     #[test]
     fn test_radix_sort_entries_basic() {
         let entries = vec![
             SortEntry {
                 chrom_index: 2,
-                _padding: 0,
                 start: 100,
                 end: 200,
                 line_start: 0,
@@ -930,7 +1090,6 @@ mod tests {
             },
             SortEntry {
                 chrom_index: 1,
-                _padding: 0,
                 start: 200,
                 end: 300,
                 line_start: 10,
@@ -938,7 +1097,6 @@ mod tests {
             },
             SortEntry {
                 chrom_index: 1,
-                _padding: 0,
                 start: 100,
                 end: 200,
                 line_start: 20,
@@ -1000,18 +1158,18 @@ mod tests {
     fn test_build_chrom_index_with_genome() {
         // Create a genome order: chr2, chr1, chr10 (non-lexicographic)
         let mut genome_order = HashMap::new();
-        genome_order.insert(b"chr2".to_vec(), 0u16);
-        genome_order.insert(b"chr1".to_vec(), 1u16);
-        genome_order.insert(b"chr10".to_vec(), 2u16);
+        genome_order.insert(b"chr2".to_vec(), 0u32);
+        genome_order.insert(b"chr1".to_vec(), 1u32);
+        genome_order.insert(b"chr10".to_vec(), 2u32);
 
         let data = b"chr1\t100\t200\nchr10\t50\t100\nchr2\t100\t200\n";
         let line_offsets = find_line_offsets(data);
         let chrom_index = build_chrom_index(data, &line_offsets, Some(&genome_order));
 
         // Verify genome ordering is used
-        assert_eq!(chrom_index.get(b"chr2".as_slice()), Some(&0u16));
-        assert_eq!(chrom_index.get(b"chr1".as_slice()), Some(&1u16));
-        assert_eq!(chrom_index.get(b"chr10".as_slice()), Some(&2u16));
+        assert_eq!(chrom_index.get(b"chr2".as_slice()), Some(&0u32));
+        assert_eq!(chrom_index.get(b"chr1".as_slice()), Some(&1u32));
+        assert_eq!(chrom_index.get(b"chr10".as_slice()), Some(&2u32));
     }
 
     #[test]
@@ -1065,4 +1223,41 @@ mod tests {
         assert!(lines[2].starts_with("chr10\t"));
         assert!(lines[3].starts_with("chrX\t"));
     }
+
+    #[test]
+    fn test_fast_sort_global_key() {
+        use crate::genome::Genome;
+
+        let mut genome = Genome::new();
+        genome.insert("chr2".to_string(), 70_000);
+        genome.insert("chr1".to_string(), 300);
+        let mut input = Vec::new();
+        for i in (0..300u64).rev() {
+            input.extend_from_slice(format!("chr1\t{}\t{}\n", i, i + 10).as_bytes());
+            input.extend_from_slice(format!("chr2\t{}\t{}\n", i * 200, i * 200 + 5).as_bytes());
+            input.extend_from_slice(format!("chr2\t{}\t{}\n", i * 200, i * 200 + 1).as_bytes());
+        }
+
+        let cmd = FastSortCommand::new().with_genome(&genome);
+        let mut output = Vec::new();
+        let stats = cmd.sort_buffered(&input[..], &mut output).unwrap();
+        assert!(stats.used_global_key);
+
+        // Same order as sorting on chromosome index and start
+        let mut expected = Vec::new();
+        let cmd = FastSortCommand {
+            global: None,
+            ..cmd
+        };
+        let stats = cmd.sort_buffered(&input[..], &mut expected).unwrap();
+        assert!(stats.used_radix_sort && !stats.used_global_key);
+        assert_eq!(output, expected);
+        assert!(output.starts_with(b"chr2\t0\t1\nchr2\t0\t5\nchr2\t200\t201\n"));
+
+        // A start past its chromosome's end falls back to the per-field sort
+        input.extend_from_slice(b"chr1\t300\t310\n");
+        let cmd = FastSortCommand::new().with_genome(&genome);
+        let stats = cmd.sort_buffered(&input[..], &mut Vec::new()).unwrap();
+        assert!(stats.used_radix_sort && !stats.used_global_key);
+    }
 }
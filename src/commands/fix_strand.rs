@@ -0,0 +1,330 @@
+//! Fix-strand command implementation.
+//!
+//! Strand-aware options (`-s`, `-S`, slop `-s`) only recognize `+`, `-` and
+//! `.` in column 6; anything else reads as an unknown strand, so records with
+//! `1`/`-1` or `F`/`R` strands silently drop out of strand-aware results.
+//! This command rewrites the strand column to `+`, `-` or `.`, and with a
+//! gene model fills unknown strands from the genes each record overlaps.
+//! All other columns are copied unchanged.
+
+use crate::bed::{read_records, BedError};
+use crate::index::IntervalIndex;
+use crate::interchange::stdin_reader;
+use crate::interval::{BedRecord, Interval, Strand};
+use crate::output_limit::limited_stdout;
+use crate::streaming::parsing::{is_header_line, should_skip_line, trim_newline};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// 0-based index of the strand column.
+const STRAND_COLUMN: usize = 5;
+
+/// Canonical strand for a strand field, or `None` if it is not recognized.
+///
+/// Accepts `+`/`-`/`.`, `1`/`-1`, `F`/`R`, `fwd`/`rev`, `forward`/`reverse`
+/// and `plus`/`minus` (ignoring case); empty fields, `0`, `?`, `*`, `NA` and
+/// `none` map to `.`.
+pub fn normalize_strand(field: &str) -> Option<u8> {
+    match field.to_ascii_lowercase().as_str() {
+        "+" | "1" | "+1" | "f" | "fwd" | "forward" | "plus" => Some(b'+'),
+        "-" | "-1" | "r" | "rev" | "reverse" | "minus" => Some(b'-'),
+        "." | "" | "0" | "?" | "*" | "na" | "none" => Some(b'.'),
+        _ => None,
+    }
+}
+
+/// Counts of what was changed, reported on stderr.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixStrandStats {
+    /// Records read
+    pub records: usize,
+    /// Records whose strand column was changed or added
+    pub repaired: usize,
+    /// Strand fields rewritten to `+`, `-` or `.`
+    pub normalized: usize,
+    /// Unrecognized strand fields replaced with `.`
+    pub invalid: usize,
+    /// Unknown strands filled in from the gene model
+    pub inferred: usize,
+    /// Unknown strands overlapping genes on both strands, left as `.`
+    pub ambiguous: usize,
+}
+
+impl fmt::Display for FixStrandStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Records: {}, Repaired: {} (normalized {}, invalid {}, inferred {}), Ambiguous: {}",
+            self.records,
+            self.repaired,
+            self.normalized,
+            self.invalid,
+            self.inferred,
+            self.ambiguous
+        )
+    }
+}
+
+/// Fix-strand command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct FixStrandCommand {
+    /// Fail on unrecognized strand fields instead of replacing them with `.`
+    pub strict: bool,
+    /// Genes (BED6) to infer unknown strands from, by overlap
+    pub genes: Option<Vec<BedRecord>>,
+}
+
+impl FixStrandCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail on unrecognized strand fields.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Infer unknown strands from the genes in a BED file.
+    pub fn with_genes<P: AsRef<Path>>(mut self, path: P) -> Result<Self, BedError> {
+        self.genes = Some(read_records(path)?);
+        Ok(self)
+    }
+
+    /// Fix the strands of a file.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        output: &mut W,
+    ) -> Result<FixStrandStats, BedError> {
+        let file = File::open(input)?;
+        self.fix_reader(file, output)
+    }
+
+    /// Fix the strands of stdin, writing to stdout.
+    pub fn run_stdio(&self) -> Result<FixStrandStats, BedError> {
+        let mut handle = limited_stdout();
+        self.fix_reader(stdin_reader(), &mut handle)
+    }
+
+    /// Fix the strands of a BED stream.
+    ///
+    /// Comment, `track` and `browser` lines are copied unchanged. Records
+    /// with fewer than six columns keep their columns, unless a strand is
+    /// inferred for them: they are then padded to BED6 with `.` names and
+    /// `0` scores.
+    pub fn fix_reader<R: Read, W: Write>(
+        &self,
+        input: R,
+        output: &mut W,
+    ) -> Result<FixStrandStats, BedError> {
+        let index = self.genes.as_deref().map(IntervalIndex::from_records);
+        let mut stats = FixStrandStats::default();
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        let mut reader = BufReader::with_capacity(256 * 1024, input);
+        let mut line = Vec::with_capacity(1024);
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            let text = String::from_utf8_lossy(trim_newline(&line));
+            if is_header_line(text.as_bytes()) {
+                writeln!(out, "{}", text)?;
+                continue;
+            }
+            if should_skip_line(text.as_bytes()) {
+                continue;
+            }
+
+            let mut fields: Vec<&str> = text.split('\t').collect();
+            if fields.len() < 3 {
+                return Err(BedError::Parse {
+                    line: line_num,
+                    message: format!("Expected at least 3 fields, got {}", fields.len()),
+                });
+            }
+            stats.records += 1;
+
+            let original = fields.get(STRAND_COLUMN).copied();
+            let mut strand = match original.map(normalize_strand) {
+                None => b'.',
+                Some(Some(strand)) => {
+                    if original != Some(strand_str(strand)) {
+                        stats.normalized += 1;
+                    }
+                    strand
+                }
+                Some(None) if self.strict => {
+                    return Err(BedError::Parse {
+                        line: line_num,
+                        message: format!("unrecognized strand '{}'", original.unwrap_or_default()),
+                    });
+                }
+                Some(None) => {
+                    stats.invalid += 1;
+                    b'.'
+                }
+            };
+
+            if let (b'.', Some(index)) = (strand, &index) {
+                let interval = parse_interval(&fields, line_num)?;
+                match self.gene_strands(index, &interval) {
+                    (true, false) => strand = b'+',
+                    (false, true) => strand = b'-',
+                    (true, true) => stats.ambiguous += 1,
+                    (false, false) => {}
+                }
+                if strand != b'.' {
+                    stats.inferred += 1;
+                }
+            }
+
+            if fields.len() > STRAND_COLUMN {
+                fields[STRAND_COLUMN] = strand_str(strand);
+            } else if strand != b'.' {
+                if fields.len() == 3 {
+                    fields.push(".");
+                }
+                if fields.len() == 4 {
+                    fields.push("0");
+                }
+                fields.push(strand_str(strand));
+            }
+            if fields.get(STRAND_COLUMN).copied() != original {
+                stats.repaired += 1;
+            }
+            writeln!(out, "{}", fields.join("\t"))?;
+        }
+
+        out.flush()?;
+        Ok(stats)
+    }
+
+    /// Whether `interval` overlaps genes on the plus and on the minus strand.
+    fn gene_strands(&self, index: &IntervalIndex, interval: &Interval) -> (bool, bool) {
+        let genes = self.genes.as_deref().unwrap_or_default();
+        let (mut plus, mut minus) = (false, false);
+        for i in index.find_overlap_indices(interval) {
+            match genes[i].strand {
+                Some(Strand::Plus) => plus = true,
+                Some(Strand::Minus) => minus = true,
+                _ => {}
+            }
+        }
+        (plus, minus)
+    }
+}
+
+fn strand_str(strand: u8) -> &'static str {
+    match strand {
+        b'+' => "+",
+        b'-' => "-",
+        _ => ".",
+    }
+}
+
+fn parse_interval(fields: &[&str], line: usize) -> Result<Interval, BedError> {
+    let coordinate = |s: &str, what: &str| {
+        s.parse::<u64>().map_err(|_| BedError::Parse {
+            line,
+            message: format!("Invalid {} position: {}", what, s),
+        })
+    };
+    Ok(Interval::new(
+        fields[0],
+        coordinate(fields[1], "start")?,
+        coordinate(fields[2], "end")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(cmd: &FixStrandCommand, input: &str) -> Result<(String, FixStrandStats), BedError> {
+        let mut output = Vec::new();
+        let stats = cmd.fix_reader(input.as_bytes(), &mut output)?;
+        Ok((String::from_utf8(output).unwrap(), stats))
+    }
+
+    #[test]
+    fn test_normalize_strand() {
+        assert_eq!(normalize_strand("1"), Some(b'+'));
+        assert_eq!(normalize_strand("F"), Some(b'+'));
+        assert_eq!(normalize_strand("-1"), Some(b'-'));
+        assert_eq!(normalize_strand("Reverse"), Some(b'-'));
+        assert_eq!(normalize_strand(""), Some(b'.'));
+        assert_eq!(normalize_strand("NA"), Some(b'.'));
+        assert_eq!(normalize_strand("x"), None);
+    }
+
+    #[test]
+    fn test_fix_strand_normalizes() {
+        let input = "#reads\n\
+                     chr1\t10\t20\ta\t0\t1\textra\n\
+                     chr1\t30\t40\tb\t0\tR\n\
+                     chr1\t50\t60\tc\t0\t+\n\
+                     chr1\t70\t80\td\t0\tsense\n\
+                     chr1\t90\t95\n";
+        let (output, stats) = fix(&FixStrandCommand::new(), input).unwrap();
+        assert_eq!(
+            output,
+            "#reads\n\
+             chr1\t10\t20\ta\t0\t+\textra\n\
+             chr1\t30\t40\tb\t0\t-\n\
+             chr1\t50\t60\tc\t0\t+\n\
+             chr1\t70\t80\td\t0\t.\n\
+             chr1\t90\t95\n"
+        );
+        assert_eq!(
+            stats,
+            FixStrandStats {
+                records: 5,
+                repaired: 3,
+                normalized: 2,
+                invalid: 1,
+                ..Default::default()
+            }
+        );
+
+        let strict = FixStrandCommand::new().with_strict(true);
+        let err = fix(&strict, input).unwrap_err();
+        assert!(err.to_string().contains("unrecognized strand 'sense'"));
+    }
+
+    #[test]
+    fn test_fix_strand_infers_from_genes() {
+        let mut plus = BedRecord::new("chr1", 0, 100);
+        plus.strand = Some(Strand::Plus);
+        let mut minus = BedRecord::new("chr1", 80, 200);
+        minus.strand = Some(Strand::Minus);
+        let cmd = FixStrandCommand {
+            genes: Some(vec![plus, minus]),
+            ..FixStrandCommand::new()
+        };
+
+        let input = "chr1\t10\t20\n\
+                     chr1\t90\t95\tboth\t0\t.\n\
+                     chr1\t150\t160\tx\t0\t0\n\
+                     chr1\t10\t20\ty\t0\t-\n\
+                     chr1\t500\t600\n";
+        let (output, stats) = fix(&cmd, input).unwrap();
+        assert_eq!(
+            output,
+            "chr1\t10\t20\t.\t0\t+\n\
+             chr1\t90\t95\tboth\t0\t.\n\
+             chr1\t150\t160\tx\t0\t-\n\
+             chr1\t10\t20\ty\t0\t-\n\
+             chr1\t500\t600\n"
+        );
+        assert_eq!(stats.inferred, 2);
+        assert_eq!(stats.ambiguous, 1);
+        assert_eq!(stats.repaired, 2);
+    }
+}
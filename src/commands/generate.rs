@@ -14,8 +14,8 @@
 //! - Deterministic reproducibility via seed
 
 use crate::bed::BedError;
-use rand::rngs::SmallRng;
-use rand::{Rng, SeedableRng};
+use crate::rng::{SeededRng, DEFAULT_SEED};
+use crate::scratch::ScratchDir;
 use rayon::prelude::*;
 use std::collections::BinaryHeap;
 use std::fs::{self, File};
@@ -176,7 +176,7 @@ impl Default for GenerateConfig {
                 SizeSpec { count: 25_000_000 },
                 SizeSpec { count: 50_000_000 },
             ],
-            seed: 42,
+            seed: DEFAULT_SEED,
             mode: GenerateMode::All,
             sorted: SortMode::Auto,
             custom_a: None,
@@ -267,8 +267,8 @@ impl HumanGenome {
     /// Sample a chromosome weighted by size.
     /// Returns (chromosome index, chromosome size).
     #[inline]
-    fn sample_chromosome(&self, rng: &mut SmallRng) -> (usize, u64) {
-        let target = rng.gen_range(0..self.total_size);
+    fn sample_chromosome(&self, rng: &mut SeededRng) -> (usize, u64) {
+        let target = rng.below(self.total_size);
         let idx = self.cumulative.partition_point(|&x| x <= target);
         (idx, self.chromosomes[idx].1)
     }
@@ -506,15 +506,15 @@ impl GenerateCommand {
         }
 
         // Generate A with seed
-        let mut rng_a = SmallRng::seed_from_u64(self.config.seed);
+        let mut rng_a = SeededRng::new(self.config.seed);
         eprint!("  Generating A.bed... ");
         let start_a = Instant::now();
         self.generate_file(&a_path, a_count, clustered, &mut rng_a)?;
         eprintln!("done ({:.1}s)", start_a.elapsed().as_secs_f64());
         eprintln!("  Saved: {}", a_path.display());
 
-        // Generate B with different seed
-        let mut rng_b = SmallRng::seed_from_u64(self.config.seed.wrapping_add(1));
+        // Generate B from a second stream of the same seed
+        let mut rng_b = SeededRng::stream(self.config.seed, 1);
         eprint!("  Generating B.bed... ");
         let start_b = Instant::now();
         self.generate_file(&b_path, b_count, clustered, &mut rng_b)?;
@@ -544,7 +544,7 @@ impl GenerateCommand {
         }
 
         // Generate A
-        let mut rng = SmallRng::seed_from_u64(self.config.seed);
+        let mut rng = SeededRng::new(self.config.seed);
         eprint!("  Generating A.bed... ");
         let start = Instant::now();
         self.generate_file(&a_path, count, false, &mut rng)?;
@@ -569,7 +569,7 @@ impl GenerateCommand {
         path: &Path,
         count: u64,
         clustered: bool,
-        rng: &mut SmallRng,
+        rng: &mut SeededRng,
     ) -> Result<(), BedError> {
         let should_sort = self.config.sorted.should_sort(count);
 
@@ -590,7 +590,7 @@ impl GenerateCommand {
         &self,
         count: u64,
         clustered: bool,
-        rng: &mut SmallRng,
+        rng: &mut SeededRng,
     ) -> Vec<RawInterval> {
         let mut intervals = Vec::with_capacity(count as usize);
 
@@ -604,11 +604,13 @@ impl GenerateCommand {
 
             // Generate hotspot intervals
             for _ in 0..in_hotspot {
-                let hotspot = &hotspots[rng.gen_range(0..hotspots.len())];
-                let len = rng.gen_range(self.config.len_min..=self.config.len_max);
+                let hotspot = &hotspots[rng.below(hotspots.len() as u64) as usize];
+                let len = rng
+                    .range_inclusive(self.config.len_min as u64..=self.config.len_max as u64)
+                    as u32;
 
                 // Sample position within hotspot
-                let pos = rng.gen_range(
+                let pos = rng.range(
                     hotspot.center.saturating_sub(hotspot.radius)
                         ..hotspot.center.saturating_add(hotspot.radius),
                 );
@@ -639,13 +641,14 @@ impl GenerateCommand {
 
     /// Generate a single uniform interval.
     #[inline]
-    fn generate_uniform_interval(&self, rng: &mut SmallRng) -> RawInterval {
+    fn generate_uniform_interval(&self, rng: &mut SeededRng) -> RawInterval {
         let (chrom_idx, chrom_size) = self.genome.sample_chromosome(rng);
-        let len = rng.gen_range(self.config.len_min..=self.config.len_max);
+        let len =
+            rng.range_inclusive(self.config.len_min as u64..=self.config.len_max as u64) as u32;
 
         let max_start = chrom_size.saturating_sub(len as u64);
         let start = if max_start > 0 {
-            rng.gen_range(0..max_start) as u32
+            rng.below(max_start) as u32
         } else {
             0
         };
@@ -658,7 +661,7 @@ impl GenerateCommand {
     }
 
     /// Create hotspots covering hotspot_frac of the genome.
-    fn create_hotspots(&self, rng: &mut SmallRng) -> Vec<Hotspot> {
+    fn create_hotspots(&self, rng: &mut SeededRng) -> Vec<Hotspot> {
         let target_coverage = (self.genome.total_size as f64 * self.config.hotspot_frac) as u64;
         let mut hotspots = Vec::new();
         let mut total_coverage = 0u64;
@@ -668,8 +671,8 @@ impl GenerateCommand {
 
         while total_coverage < target_coverage {
             let (chrom_idx, chrom_size) = self.genome.sample_chromosome(rng);
-            let center = rng.gen_range(0..chrom_size);
-            let radius = rng.gen_range(avg_hotspot_size / 2..avg_hotspot_size * 2);
+            let center = rng.below(chrom_size);
+            let radius = rng.range(avg_hotspot_size / 2..avg_hotspot_size * 2);
 
             hotspots.push(Hotspot {
                 chrom_idx: chrom_idx as u16,
@@ -689,7 +692,7 @@ impl GenerateCommand {
         path: &Path,
         count: u64,
         clustered: bool,
-        rng: &mut SmallRng,
+        rng: &mut SeededRng,
     ) -> Result<(), BedError> {
         let file = File::create(path)?;
         let mut writer = BufWriter::with_capacity(BUF_SIZE, file);
@@ -707,7 +710,7 @@ impl GenerateCommand {
         path: &Path,
         count: u64,
         clustered: bool,
-        rng: &mut SmallRng,
+        rng: &mut SeededRng,
     ) -> Result<(), BedError> {
         let mut intervals = self.generate_intervals(count, clustered, rng);
 
@@ -728,10 +731,10 @@ impl GenerateCommand {
         path: &Path,
         count: u64,
         clustered: bool,
-        rng: &mut SmallRng,
+        rng: &mut SeededRng,
     ) -> Result<(), BedError> {
-        // Create temp directory
-        let temp_dir = tempfile::tempdir()?;
+        // Chunks go to a scratch directory that is removed even on failure
+        let scratch = ScratchDir::new()?;
 
         // Phase 1: Generate and sort chunks
         let mut chunk_paths = Vec::new();
@@ -746,8 +749,8 @@ impl GenerateCommand {
             chunk.par_sort_by_key(|i| i.sort_key());
 
             // Write chunk to temp file
-            let chunk_path = temp_dir.path().join(format!("chunk_{}.bed", chunk_idx));
-            let file = File::create(&chunk_path)?;
+            let file = scratch.create(&format!("chunk_{}.bed", chunk_idx))?;
+            let chunk_path = file.path().to_path_buf();
             let mut writer = BufWriter::with_capacity(BUF_SIZE, file);
             self.write_intervals(&chunk, &mut writer)?;
             writer.flush()?;
@@ -767,7 +770,10 @@ impl GenerateCommand {
         // Phase 2: K-way merge
         eprint!("  Merging... ");
         self.k_way_merge(&chunk_paths, path)?;
-        eprintln!("done");
+        eprintln!(
+            "done ({:.1} MB scratch)",
+            scratch.bytes_written() as f64 / 1e6
+        );
 
         Ok(())
     }
@@ -958,7 +964,7 @@ mod tests {
     fn test_generate_uniform_interval() {
         let config = GenerateConfig::default();
         let cmd = GenerateCommand::new(config);
-        let mut rng = SmallRng::seed_from_u64(42);
+        let mut rng = SeededRng::new(42);
 
         let interval = cmd.generate_uniform_interval(&mut rng);
         assert!(interval.chrom_idx < 23);
@@ -974,7 +980,7 @@ mod tests {
             ..Default::default()
         };
         let cmd1 = GenerateCommand::new(config1);
-        let mut rng1 = SmallRng::seed_from_u64(12345);
+        let mut rng1 = SeededRng::new(12345);
         let intervals1 = cmd1.generate_intervals(100, false, &mut rng1);
 
         let config2 = GenerateConfig {
@@ -982,7 +988,7 @@ mod tests {
             ..Default::default()
         };
         let cmd2 = GenerateCommand::new(config2);
-        let mut rng2 = SmallRng::seed_from_u64(12345);
+        let mut rng2 = SeededRng::new(12345);
         let intervals2 = cmd2.generate_intervals(100, false, &mut rng2);
 
         // Same seed should produce identical intervals
@@ -4,7 +4,10 @@
 //! O(n log n) for sorting events, O(n) for sweep.
 
 use crate::bed::{BedError, BedReader};
-use crate::genome::Genome;
+use crate::config::ChromClasses;
+use crate::genome::{Genome, GenomeMask};
+use crate::progress::GenomeProgress;
+use crate::streaming::union_by_name;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Read, Write};
@@ -36,6 +39,398 @@ fn format_fraction(f: f64) -> String {
     }
 }
 
+/// Decimal places kept for non-integer scaled depths.
+const SCALED_DEPTH_DECIMALS: i32 = 6;
+
+/// Scaling applied to depths in BedGraph and per-base output.
+///
+/// Scaled values are rounded to six decimal places, or to the nearest
+/// integer with `round`, so output is deterministic and short enough for
+/// tools like `bedGraphToBigWig`. An unscaled depth is written as an integer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthScale {
+    pub factor: f64,
+    pub round: bool,
+}
+
+impl DepthScale {
+    pub fn new(factor: f64, round: bool) -> Self {
+        Self { factor, round }
+    }
+
+    /// Scaled value of a raw depth, rounded to the output precision.
+    #[inline]
+    pub fn apply(&self, depth: u32) -> f64 {
+        let value = depth as f64 * self.factor;
+        if self.round {
+            value.round()
+        } else {
+            let unit = 10f64.powi(SCALED_DEPTH_DECIMALS);
+            (value * unit).round() / unit
+        }
+    }
+
+    /// Formatter for a value returned by [`apply`](Self::apply).
+    #[inline]
+    pub fn display(value: f64) -> ScaledDepth {
+        ScaledDepth(value)
+    }
+}
+
+/// Display wrapper for a scaled depth: integers without a decimal point,
+/// other values with trailing zeros trimmed.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaledDepth(f64);
+
+impl std::fmt::Display for ScaledDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = self.0;
+        if v.fract() == 0.0 {
+            // -0.0 prints as 0
+            write!(f, "{:.0}", v + 0.0)
+        } else {
+            let s = format!("{:.*}", SCALED_DEPTH_DECIMALS as usize, v);
+            f.write_str(s.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Genome-wide normalisation of BedGraph depths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthNormalization {
+    /// Percentage of genome bases at or below the depth
+    Percentile,
+    /// Standard deviations from the genome-wide mean depth
+    ZScore,
+}
+
+impl DepthNormalization {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "percentile" => Some(Self::Percentile),
+            "zscore" => Some(Self::ZScore),
+            _ => None,
+        }
+    }
+}
+
+/// Depth-to-value map fitted to a genome-wide depth histogram.
+///
+/// Percentiles and z-scores depend only on where a depth sits in the
+/// sample's own distribution, so tracks from samples sequenced to different
+/// depths become directly comparable. Values are rounded like scaled depths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthTransform {
+    kind: DepthNormalization,
+    /// Sorted depths with the number of bases at or below each
+    cumulative: Vec<(u32, u64)>,
+    total: u64,
+    mean: f64,
+    sd: f64,
+}
+
+impl DepthTransform {
+    /// Fit to a histogram of bases per depth.
+    pub fn new(kind: DepthNormalization, hist: &HashMap<u32, u64>) -> Self {
+        let mut depths: Vec<(u32, u64)> = hist.iter().map(|(&d, &b)| (d, b)).collect();
+        depths.sort_unstable();
+
+        let total: u64 = depths.iter().map(|&(_, b)| b).sum();
+        let n = total.max(1) as f64;
+        let mean = depths
+            .iter()
+            .map(|&(d, b)| d as f64 * b as f64)
+            .sum::<f64>()
+            / n;
+        let var = depths
+            .iter()
+            .map(|&(d, b)| (d as f64 - mean).powi(2) * b as f64)
+            .sum::<f64>()
+            / n;
+
+        let mut running = 0;
+        let cumulative = depths
+            .into_iter()
+            .map(|(d, b)| {
+                running += b;
+                (d, running)
+            })
+            .collect();
+
+        Self {
+            kind,
+            cumulative,
+            total,
+            mean,
+            sd: var.sqrt(),
+        }
+    }
+
+    /// Normalised value of a raw depth.
+    pub fn apply(&self, depth: u32) -> f64 {
+        let value = match self.kind {
+            DepthNormalization::Percentile => {
+                if self.total == 0 {
+                    return 0.0;
+                }
+                let at_or_below = match self.cumulative.partition_point(|&(d, _)| d <= depth) {
+                    0 => 0,
+                    i => self.cumulative[i - 1].1,
+                };
+                100.0 * at_or_below as f64 / self.total as f64
+            }
+            DepthNormalization::ZScore => {
+                if self.sd == 0.0 {
+                    return 0.0;
+                }
+                (depth as f64 - self.mean) / self.sd
+            }
+        };
+        let unit = 10f64.powi(SCALED_DEPTH_DECIMALS);
+        (value * unit).round() / unit
+    }
+}
+
+/// Convert depth runs to maximal BedGraph segments of scaled values.
+///
+/// Runs that become equal after scaling and rounding are joined when they are
+/// adjacent, so every output row differs in value from its neighbours. With
+/// `skip_zero`, raw depth-0 runs are dropped (and break adjacency).
+pub(crate) fn coalesce_scaled(
+    regions: &[(u64, u64, u32)],
+    scale: DepthScale,
+    skip_zero: bool,
+) -> Vec<(u64, u64, f64)> {
+    coalesce_values(regions, skip_zero, |depth| scale.apply(depth))
+}
+
+/// [`coalesce_scaled`] with an arbitrary depth-to-value map.
+pub(crate) fn coalesce_values(
+    regions: &[(u64, u64, u32)],
+    skip_zero: bool,
+    value_of: impl Fn(u32) -> f64,
+) -> Vec<(u64, u64, f64)> {
+    let mut out: Vec<(u64, u64, f64)> = Vec::with_capacity(regions.len());
+    for &(start, end, depth) in regions {
+        if start >= end || (skip_zero && depth == 0) {
+            continue;
+        }
+        let value = value_of(depth);
+        match out.last_mut() {
+            Some(last) if last.1 == start && last.2 == value => last.1 = end,
+            _ => out.push((start, end, value)),
+        }
+    }
+    out
+}
+
+/// Parts of depth runs that fall inside sorted, disjoint target ranges.
+pub(crate) fn clip_runs(runs: &[(u64, u64, u32)], targets: &[(u64, u64)]) -> Vec<(u64, u64, u32)> {
+    let mut out = Vec::new();
+    let mut t = 0;
+    for &(start, end, depth) in runs {
+        while t < targets.len() && targets[t].1 <= start {
+            t += 1;
+        }
+        let mut k = t;
+        while k < targets.len() && targets[k].0 < end {
+            let (s, e) = (start.max(targets[k].0), end.min(targets[k].1));
+            if s < e {
+                out.push((s, e, depth));
+            }
+            k += 1;
+        }
+    }
+    out
+}
+
+/// Bases of the targets that count toward genome-wide totals.
+pub(crate) fn target_total(genome: &Genome, targets: &GenomeMask) -> u64 {
+    genome
+        .chromosomes()
+        .filter(|c| genome.in_totals(c))
+        .map(|c| targets.regions(c).iter().map(|(s, e)| e - s).sum::<u64>())
+        .sum()
+}
+
+/// Depth over the target bases of `genomecov --regions`, per chromosome.
+///
+/// Chromosomes are kept in the order they were added (genome order) and
+/// only if they hold target bases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetSummary {
+    chroms: Vec<(String, HashMap<u32, u64>)>,
+}
+
+impl TargetSummary {
+    /// Add a chromosome's depth runs, already clipped to the targets.
+    pub fn add(&mut self, chrom: &str, runs: &[(u64, u64, u32)]) {
+        if runs.is_empty() {
+            return;
+        }
+        let mut hist: HashMap<u32, u64> = HashMap::new();
+        for &(start, end, depth) in runs {
+            *hist.entry(depth).or_insert(0) += end - start;
+        }
+        self.chroms.push((chrom.to_string(), hist));
+    }
+
+    /// Target bases seen.
+    pub fn bases(&self) -> u64 {
+        self.chroms
+            .iter()
+            .map(|(_, h)| h.values().sum::<u64>())
+            .sum()
+    }
+
+    /// Mean depth over all target bases (0 without targets).
+    pub fn mean_depth(&self) -> f64 {
+        mean_depth(self.chroms.iter().flat_map(|(_, h)| h.iter()))
+    }
+
+    /// Fraction of target bases with depth of at least `min_depth`.
+    pub fn fraction_at_least(&self, min_depth: u32) -> f64 {
+        fraction_at_least(self.chroms.iter().flat_map(|(_, h)| h.iter()), min_depth)
+    }
+
+    /// Write a header and one row per chromosome plus a `total` row: target
+    /// bases, mean depth and the percentage of bases at each threshold.
+    pub fn write<W: Write>(&self, output: &mut W, thresholds: &[u32]) -> Result<(), BedError> {
+        write!(output, "#chrom\ttarget_bases\tmean_depth")?;
+        for t in thresholds {
+            write!(output, "\tpct_ge_{}x", t)?;
+        }
+        writeln!(output)?;
+
+        let total = self.chroms.iter().flat_map(|(_, h)| h.iter());
+        let rows = self
+            .chroms
+            .iter()
+            .map(|(chrom, h)| (chrom.as_str(), h.iter().collect::<Vec<_>>()))
+            .chain(std::iter::once(("total", total.collect())));
+        for (label, hist) in rows {
+            let bases: u64 = hist.iter().map(|&(_, &b)| b).sum();
+            write!(
+                output,
+                "{}\t{}\t{:.2}",
+                label,
+                bases,
+                mean_depth(hist.iter().copied())
+            )?;
+            for &t in thresholds {
+                write!(
+                    output,
+                    "\t{:.2}",
+                    100.0 * fraction_at_least(hist.iter().copied(), t)
+                )?;
+            }
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+}
+
+fn mean_depth<'a>(hist: impl Iterator<Item = (&'a u32, &'a u64)>) -> f64 {
+    let (mut bases, mut depth_sum) = (0u64, 0f64);
+    for (&depth, &b) in hist {
+        bases += b;
+        depth_sum += depth as f64 * b as f64;
+    }
+    if bases == 0 {
+        0.0
+    } else {
+        depth_sum / bases as f64
+    }
+}
+
+fn fraction_at_least<'a>(hist: impl Iterator<Item = (&'a u32, &'a u64)>, min_depth: u32) -> f64 {
+    let (mut bases, mut above) = (0u64, 0u64);
+    for (&depth, &b) in hist {
+        bases += b;
+        if depth >= min_depth {
+            above += b;
+        }
+    }
+    if bases == 0 {
+        0.0
+    } else {
+        above as f64 / bases as f64
+    }
+}
+
+/// Depth histograms of chromosome classes (`--chrom-classes`), which take
+/// the place of per-chromosome histogram rows.
+///
+/// Inactive (adds nothing, writes nothing) without classes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClassRollup {
+    classes: Option<ChromClasses>,
+    /// Bases per depth and bases measured against, in class order
+    rows: Vec<(HashMap<u32, u64>, u64)>,
+}
+
+impl ClassRollup {
+    pub(crate) fn new(classes: Option<&ChromClasses>) -> Self {
+        let rows = classes.map_or(0, |c| c.names().count());
+        Self {
+            classes: classes.cloned(),
+            rows: vec![Default::default(); rows],
+        }
+    }
+
+    /// True if chromosomes are rolled up into classes.
+    pub(crate) fn is_active(&self) -> bool {
+        self.classes.is_some()
+    }
+
+    /// Add a chromosome's histogram, measured against `size` bases.
+    pub(crate) fn add(&mut self, chrom: &str, hist: &HashMap<u32, u64>, size: u64) {
+        let Some(classes) = &self.classes else {
+            return;
+        };
+        let (class_hist, class_size) = &mut self.rows[classes.class_of(chrom.as_bytes())];
+        for (&depth, &bases) in hist {
+            *class_hist.entry(depth).or_insert(0) += bases;
+        }
+        *class_size += size;
+    }
+
+    /// Write `class depth bases class_size fraction` rows in class order,
+    /// skipping classes without bases.
+    pub(crate) fn write<W: Write>(
+        &self,
+        output: &mut W,
+        report_zero: bool,
+    ) -> Result<(), BedError> {
+        let Some(classes) = &self.classes else {
+            return Ok(());
+        };
+        for (name, (hist, size)) in classes.names().zip(&self.rows) {
+            if *size == 0 {
+                continue;
+            }
+            let mut depths: Vec<_> = hist.keys().copied().collect();
+            depths.sort_unstable();
+            for depth in depths {
+                if depth == 0 && !report_zero {
+                    continue;
+                }
+                let bases = hist[&depth];
+                writeln!(
+                    output,
+                    "{}\t{}\t{}\t{}\t{}",
+                    name,
+                    depth,
+                    bases,
+                    size,
+                    format_fraction(bases as f64 / *size as f64)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Genomecov output mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputMode {
@@ -56,6 +451,8 @@ pub struct GenomecovCommand {
     pub mode: OutputMode,
     /// Scale factor for depth
     pub scale: f64,
+    /// Round scaled depths to integers
+    pub round_scaled: bool,
     /// Maximum depth to report (for histogram)
     pub max_depth: Option<u32>,
     /// Report depth >= 1 (not zero coverage)
@@ -66,6 +463,18 @@ pub struct GenomecovCommand {
     pub five_prime: bool,
     /// 3' end only
     pub three_prime: bool,
+    /// Report BedGraph values as genome-wide percentiles or z-scores
+    pub normalize: Option<DepthNormalization>,
+    /// Report progress through the genome on stderr
+    pub progress: bool,
+    /// With `progress`, also report each finished chromosome
+    pub verbose: bool,
+    /// Count records sharing a name (column 4) once toward depth
+    pub collapse_by_name: bool,
+    /// Report only bases inside these regions (`--regions`)
+    pub targets: Option<GenomeMask>,
+    /// Roll histogram rows up into these chromosome classes
+    pub chrom_classes: Option<ChromClasses>,
 }
 
 impl Default for GenomecovCommand {
@@ -79,14 +488,35 @@ impl GenomecovCommand {
         Self {
             mode: OutputMode::Histogram,
             scale: 1.0,
+            round_scaled: false,
             max_depth: None,
             report_zero: true,
             strand: false,
             five_prime: false,
             three_prime: false,
+            normalize: None,
+            progress: false,
+            verbose: false,
+            collapse_by_name: false,
+            targets: None,
+            chrom_classes: None,
         }
     }
 
+    /// Normalise BedGraph values genome-wide (builder pattern).
+    pub fn with_normalize(mut self, normalize: Option<DepthNormalization>) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Report progress on stderr, per chromosome if `verbose` (builder
+    /// pattern). Progress is measured once the input is loaded.
+    pub fn with_progress(mut self, progress: bool, verbose: bool) -> Self {
+        self.progress = progress;
+        self.verbose = verbose;
+        self
+    }
+
     /// Process a chromosome's intervals using sweep-line algorithm.
     /// Returns sorted (start, end, depth) tuples with adjacent same-depth regions merged.
     fn sweep_chromosome(&self, intervals: &[(u64, u64)], chrom_size: u64) -> Vec<(u64, u64, u32)> {
@@ -157,6 +587,136 @@ impl GenomecovCommand {
         hist
     }
 
+    /// Count records sharing a name once toward depth (builder pattern).
+    pub fn with_collapse_by_name(mut self, collapse_by_name: bool) -> Self {
+        self.collapse_by_name = collapse_by_name;
+        self
+    }
+
+    /// Report only bases inside `targets` (builder pattern).
+    ///
+    /// Output is clipped to the targets, and histogram rows count target
+    /// bases, with the chromosome's target bases in place of its size.
+    pub fn with_targets(mut self, targets: Option<GenomeMask>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Report histogram rows per chromosome class rather than per
+    /// chromosome (builder pattern). Genome-wide rows are unchanged.
+    pub fn with_chrom_classes(mut self, chrom_classes: Option<ChromClasses>) -> Self {
+        self.chrom_classes = chrom_classes;
+        self
+    }
+
+    /// Depth runs of a chromosome as reported: clipped to the targets if any.
+    fn reported_runs(&self, chrom: &str, runs: Vec<(u64, u64, u32)>) -> Vec<(u64, u64, u32)> {
+        match &self.targets {
+            Some(targets) => clip_runs(&runs, targets.regions(chrom)),
+            None => runs,
+        }
+    }
+
+    /// Bases a chromosome's histogram rows are measured against.
+    fn reported_size(&self, genome: &Genome, chrom: &str) -> u64 {
+        match &self.targets {
+            Some(targets) => targets.regions(chrom).iter().map(|(s, e)| e - s).sum(),
+            None => genome.chrom_size(chrom).unwrap_or(0),
+        }
+    }
+
+    /// Bases a chromosome contributes to the genome-wide histogram.
+    fn genome_wide_bases(&self, genome: &Genome, chrom: &str, depth: u32, bases: u64) -> u64 {
+        match &self.targets {
+            // Effective sizes describe whole chromosomes, not targets
+            Some(_) if genome.in_totals(chrom) => bases,
+            Some(_) => 0,
+            None => genome.genome_wide_bases(chrom, depth, bases),
+        }
+    }
+
+    /// Group input intervals by chromosome, dropping chromosomes not in the genome.
+    ///
+    /// With `collapse_by_name`, the intervals of each name are merged first.
+    fn group_by_chrom<R: Read>(
+        &self,
+        reader: BedReader<R>,
+        genome: &Genome,
+    ) -> Result<HashMap<String, Vec<(u64, u64)>>, BedError> {
+        let mut by_chrom: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+        // With `collapse_by_name`: (name id, start, end) per chromosome
+        let mut named: HashMap<String, Vec<(u64, u64, u64)>> = HashMap::new();
+        let mut name_ids: HashMap<String, u64> = HashMap::new();
+        let mut next_id = 0u64;
+        for result in reader.records() {
+            let record = result?;
+            let chrom = record.chrom().to_string();
+
+            // Skip chromosomes not in genome
+            if !genome.has_chrom(&chrom) {
+                continue;
+            }
+
+            if self.collapse_by_name {
+                // Unnamed records get an id of their own
+                let id = match record.name.as_deref() {
+                    Some(name) if name != "." => {
+                        *name_ids.entry(name.to_string()).or_insert_with(|| {
+                            next_id += 1;
+                            next_id
+                        })
+                    }
+                    _ => {
+                        next_id += 1;
+                        next_id
+                    }
+                };
+                named
+                    .entry(chrom)
+                    .or_default()
+                    .push((id, record.start(), record.end()));
+                continue;
+            }
+
+            by_chrom
+                .entry(chrom)
+                .or_default()
+                .push((record.start(), record.end()));
+        }
+        for (chrom, mut pieces) in named {
+            union_by_name(&mut pieces);
+            by_chrom.insert(
+                chrom,
+                pieces
+                    .into_iter()
+                    .map(|(_, start, end)| (start, end))
+                    .collect(),
+            );
+        }
+        Ok(by_chrom)
+    }
+
+    /// Compute depth runs for every genome chromosome, in genome order.
+    ///
+    /// Each chromosome maps to `(start, end, depth)` runs that tile it
+    /// completely, including zero-depth runs. Scale and output mode are
+    /// ignored.
+    pub fn depth_runs<R: Read>(
+        &self,
+        reader: BedReader<R>,
+        genome: &Genome,
+    ) -> Result<Vec<(String, Vec<(u64, u64, u32)>)>, BedError> {
+        let by_chrom = self.group_by_chrom(reader, genome)?;
+        Ok(genome
+            .chromosomes()
+            .map(|chrom| {
+                let chrom_size = genome.chrom_size(chrom).unwrap();
+                let intervals = by_chrom.get(chrom).map(|v| v.as_slice()).unwrap_or(&[]);
+                (chrom.clone(), self.sweep_chromosome(intervals, chrom_size))
+            })
+            .collect())
+    }
+
     /// Run genomecov with streaming output.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
@@ -164,9 +724,21 @@ impl GenomecovCommand {
         genome: &Genome,
         output: &mut W,
     ) -> Result<(), BedError> {
+        self.run_with_summary(input, genome, output)?;
+        Ok(())
+    }
+
+    /// Run genomecov, also returning the depth over the targets (empty
+    /// without targets).
+    pub fn run_with_summary<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<TargetSummary, BedError> {
         let file = File::open(input)?;
         let reader = BedReader::new(file);
-        self.genomecov_streaming(reader, genome, output)
+        self.genomecov_with_summary(reader, genome, output)
     }
 
     /// Streaming genomecov implementation.
@@ -176,39 +748,70 @@ impl GenomecovCommand {
         genome: &Genome,
         output: &mut W,
     ) -> Result<(), BedError> {
-        let mut buf_output = BufWriter::with_capacity(256 * 1024, output);
+        self.genomecov_with_summary(reader, genome, output)?;
+        Ok(())
+    }
 
-        // Group intervals by chromosome
-        let mut by_chrom: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
-        for result in reader.records() {
-            let record = result?;
-            let chrom = record.chrom().to_string();
+    fn genomecov_with_summary<R: Read, W: Write>(
+        &self,
+        reader: BedReader<R>,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<TargetSummary, BedError> {
+        let mut buf_output = BufWriter::with_capacity(256 * 1024, output);
+        let by_chrom = self.group_by_chrom(reader, genome)?;
+        let scale = DepthScale::new(self.scale, self.round_scaled);
 
-            // Skip chromosomes not in genome
-            if !genome.has_chrom(&chrom) {
-                continue;
+        // Normalised BedGraph needs the genome-wide distribution first
+        let transform = match self.normalize {
+            Some(kind) if matches!(self.mode, OutputMode::BedGraph | OutputMode::BedGraphAll) => {
+                let mut hist: HashMap<u32, u64> = HashMap::new();
+                for chrom in genome.chromosomes() {
+                    let chrom_size = genome.chrom_size(chrom).unwrap();
+                    let intervals = by_chrom.get(chrom).map(|v| v.as_slice()).unwrap_or(&[]);
+                    let regions =
+                        self.reported_runs(chrom, self.sweep_chromosome(intervals, chrom_size));
+                    for (depth, bases) in self.build_histogram(&regions) {
+                        let counted = self.genome_wide_bases(genome, chrom, depth, bases);
+                        if counted > 0 {
+                            *hist.entry(depth).or_insert(0) += counted;
+                        }
+                    }
+                }
+                Some(DepthTransform::new(kind, &hist))
             }
-
-            by_chrom
-                .entry(chrom)
-                .or_default()
-                .push((record.start(), record.end()));
-        }
+            _ => None,
+        };
 
         // Process chromosomes in genome order
         let mut genome_hist: HashMap<u32, u64> = HashMap::new();
-        let mut total_bases: u64 = 0;
+        let mut summary = TargetSummary::default();
+        let mut class_hist = ClassRollup::new(self.chrom_classes.as_ref());
+        let total_bases = match &self.targets {
+            Some(targets) => target_total(genome, targets),
+            None => genome.effective_total_size(),
+        };
+        let mut progress = self
+            .progress
+            .then(|| GenomeProgress::stderr("genomecov", genome, self.verbose));
 
-        for chrom in genome.chromosomes() {
+        for (chrom_idx, chrom) in genome.chromosomes().enumerate() {
             let chrom_size = genome.chrom_size(chrom).unwrap();
-            total_bases += chrom_size;
+            if let Some(progress) = progress.as_mut() {
+                progress.update(chrom_idx, 0);
+            }
 
             let intervals = by_chrom.get(chrom).map(|v| v.as_slice()).unwrap_or(&[]);
-            let regions = self.sweep_chromosome(intervals, chrom_size);
+            let regions = self.reported_runs(chrom, self.sweep_chromosome(intervals, chrom_size));
+            if self.targets.is_some() {
+                summary.add(chrom, &regions);
+            }
+            let chrom_size = self.reported_size(genome, chrom);
 
             match self.mode {
                 OutputMode::Histogram => {
                     let chrom_hist = self.build_histogram(&regions);
+                    class_hist.add(chrom, &chrom_hist, chrom_size);
 
                     // Output per-chromosome histogram
                     let mut depths: Vec<_> = chrom_hist.keys().copied().collect();
@@ -219,33 +822,42 @@ impl GenomecovCommand {
                         if depth == 0 && !self.report_zero {
                             continue;
                         }
-                        let fraction = bases as f64 / chrom_size as f64;
-                        writeln!(
-                            buf_output,
-                            "{}\t{}\t{}\t{}\t{}",
-                            chrom,
-                            depth,
-                            bases,
-                            chrom_size,
-                            format_fraction(fraction)
-                        )
-                        .map_err(BedError::Io)?;
+                        if !class_hist.is_active() {
+                            let fraction = bases as f64 / chrom_size as f64;
+                            writeln!(
+                                buf_output,
+                                "{}\t{}\t{}\t{}\t{}",
+                                chrom,
+                                depth,
+                                bases,
+                                chrom_size,
+                                format_fraction(fraction)
+                            )
+                            .map_err(BedError::Io)?;
+                        }
 
                         // Accumulate for genome-wide
-                        *genome_hist.entry(depth).or_insert(0) += bases;
+                        let counted = self.genome_wide_bases(genome, chrom, depth, bases);
+                        if counted > 0 {
+                            *genome_hist.entry(depth).or_insert(0) += counted;
+                        }
                     }
                 }
 
                 OutputMode::BedGraph | OutputMode::BedGraphAll => {
-                    for (start, end, depth) in &regions {
-                        if self.mode == OutputMode::BedGraph && *depth == 0 {
-                            continue;
-                        }
-                        let scaled_depth = (*depth as f64 * self.scale) as u32;
+                    let skip_zero = self.mode == OutputMode::BedGraph;
+                    let values = match &transform {
+                        Some(t) => coalesce_values(&regions, skip_zero, |d| t.apply(d)),
+                        None => coalesce_scaled(&regions, scale, skip_zero),
+                    };
+                    for (start, end, value) in values {
                         writeln!(
                             buf_output,
                             "{}\t{}\t{}\t{}",
-                            chrom, start, end, scaled_depth
+                            chrom,
+                            start,
+                            end,
+                            DepthScale::display(value)
                         )
                         .map_err(BedError::Io)?;
                     }
@@ -253,7 +865,7 @@ impl GenomecovCommand {
 
                 OutputMode::PerBase => {
                     for (start, end, depth) in &regions {
-                        let scaled_depth = (*depth as f64 * self.scale) as u32;
+                        let scaled_depth = DepthScale::display(scale.apply(*depth));
                         for pos in *start..*end {
                             // 1-based position for output
                             writeln!(buf_output, "{}\t{}\t{}", chrom, pos + 1, scaled_depth)
@@ -264,8 +876,13 @@ impl GenomecovCommand {
             }
         }
 
-        // Output genome-wide histogram
+        if let Some(progress) = progress.as_mut() {
+            progress.finish();
+        }
+
+        // Output class and genome-wide histograms
         if self.mode == OutputMode::Histogram {
+            class_hist.write(&mut buf_output, self.report_zero)?;
             let mut depths: Vec<_> = genome_hist.keys().copied().collect();
             depths.sort_unstable();
 
@@ -288,7 +905,7 @@ impl GenomecovCommand {
         }
 
         buf_output.flush().map_err(BedError::Io)?;
-        Ok(())
+        Ok(summary)
     }
 }
 
@@ -347,6 +964,218 @@ mod tests {
         assert_eq!(hist.get(&2), Some(&50));
     }
 
+    #[test]
+    fn test_depth_runs() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 100);
+        genome.insert("chr2".to_string(), 50);
+        let content = "chr1\t10\t30\nchr1\t20\t40\nchrUn\t0\t10\n";
+
+        let runs = GenomecovCommand::new()
+            .depth_runs(BedReader::new(content.as_bytes()), &genome)
+            .unwrap();
+
+        assert_eq!(
+            runs,
+            vec![
+                (
+                    "chr1".to_string(),
+                    vec![
+                        (0, 10, 0),
+                        (10, 20, 1),
+                        (20, 30, 2),
+                        (30, 40, 1),
+                        (40, 100, 0)
+                    ]
+                ),
+                ("chr2".to_string(), vec![(0, 50, 0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scaled_depth_format() {
+        let scale = DepthScale::new(1.0 / 3.0, false);
+        assert_eq!(DepthScale::display(scale.apply(1)).to_string(), "0.333333");
+        assert_eq!(DepthScale::display(scale.apply(3)).to_string(), "1");
+        assert_eq!(DepthScale::display(scale.apply(0)).to_string(), "0");
+
+        let scale = DepthScale::new(0.25, false);
+        assert_eq!(DepthScale::display(scale.apply(1)).to_string(), "0.25");
+
+        let scale = DepthScale::new(0.4, true);
+        assert_eq!(DepthScale::display(scale.apply(4)).to_string(), "2");
+    }
+
+    #[test]
+    fn test_coalesce_scaled() {
+        let regions = vec![(0, 10, 0), (10, 20, 4), (20, 30, 5), (40, 50, 5)];
+
+        // 4 * 0.25 and 5 * 0.25 stay distinct
+        let exact = coalesce_scaled(&regions, DepthScale::new(0.25, false), true);
+        assert_eq!(exact, vec![(10, 20, 1.0), (20, 30, 1.25), (40, 50, 1.25)]);
+
+        // Rounded values merge, but not across the gap
+        let rounded = coalesce_scaled(&regions, DepthScale::new(0.25, true), true);
+        assert_eq!(rounded, vec![(10, 30, 1.0), (40, 50, 1.0)]);
+
+        // Depth 0 becomes equal to depth 1 after rounding but is kept with -bga
+        let regions = vec![(0, 10, 0), (10, 20, 1)];
+        let all = coalesce_scaled(&regions, DepthScale::new(0.1, true), false);
+        assert_eq!(all, vec![(0, 20, 0.0)]);
+    }
+
+    #[test]
+    fn test_bedgraph_scaled_output() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 100);
+        let content = "chr1\t10\t20\nchr1\t20\t30\nchr1\t25\t30\n";
+
+        let mut cmd = GenomecovCommand::new();
+        cmd.mode = OutputMode::BedGraph;
+        cmd.scale = 0.6;
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t10\t25\t0.6\nchr1\t25\t30\t1.2\n"
+        );
+
+        cmd.round_scaled = true;
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "chr1\t10\t30\t1\n");
+    }
+
+    #[test]
+    fn test_depth_transform() {
+        let hist: HashMap<u32, u64> = [(0, 80), (1, 15), (2, 5)].into_iter().collect();
+        let pct = DepthTransform::new(DepthNormalization::Percentile, &hist);
+        assert_eq!(
+            (pct.apply(0), pct.apply(1), pct.apply(2), pct.apply(9)),
+            (80.0, 95.0, 100.0, 100.0)
+        );
+
+        let z = DepthTransform::new(DepthNormalization::ZScore, &hist);
+        let sd = 0.2875f64.sqrt();
+        assert!((z.apply(0) + 0.25 / sd).abs() < 1e-6);
+        assert!((z.apply(2) - 1.75 / sd).abs() < 1e-6);
+
+        let flat = DepthTransform::new(DepthNormalization::ZScore, &[(3, 10)].into());
+        assert_eq!(flat.apply(3), 0.0);
+    }
+
+    #[test]
+    fn test_bedgraph_normalized_output() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 100);
+        let content = "chr1\t10\t20\nchr1\t20\t30\nchr1\t25\t30\n";
+
+        let mut cmd = GenomecovCommand::new().with_normalize(Some(DepthNormalization::Percentile));
+        cmd.mode = OutputMode::BedGraphAll;
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t0\t10\t80\nchr1\t10\t25\t95\nchr1\t25\t30\t100\nchr1\t30\t100\t80\n"
+        );
+    }
+
+    #[test]
+    fn test_clip_runs() {
+        let runs = vec![(0, 100, 0), (100, 200, 1), (200, 1000, 0)];
+        let targets = [(50, 150), (190, 210), (900, 2000)];
+        assert_eq!(
+            clip_runs(&runs, &targets),
+            vec![
+                (50, 100, 0),
+                (100, 150, 1),
+                (190, 200, 1),
+                (200, 210, 0),
+                (900, 1000, 0)
+            ]
+        );
+        assert!(clip_runs(&runs, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_target_summary() {
+        let mut summary = TargetSummary::default();
+        summary.add("chr1", &[(0, 10, 0), (10, 30, 25), (30, 40, 10)]);
+        summary.add("chr2", &[]);
+        summary.add("chr3", &[(0, 60, 30)]);
+
+        assert_eq!(summary.bases(), 100);
+        assert!((summary.mean_depth() - 24.0).abs() < 1e-9);
+        assert!((summary.fraction_at_least(20) - 0.8).abs() < 1e-9);
+
+        let mut out = Vec::new();
+        summary.write(&mut out, &[1, 20]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "#chrom\ttarget_bases\tmean_depth\tpct_ge_1x\tpct_ge_20x\n\
+             chr1\t40\t15.00\t75.00\t50.00\n\
+             chr3\t60\t30.00\t100.00\t100.00\n\
+             total\t100\t24.00\t90.00\t80.00\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_over_targets() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        genome.insert("chr2".to_string(), 500);
+        let content = "chr1\t100\t200\nchr2\t0\t50\n";
+        let targets = GenomeMask::from_intervals(&[
+            crate::interval::Interval::new("chr1", 150, 250),
+            crate::interval::Interval::new("chrX", 0, 10),
+        ]);
+
+        let cmd = GenomecovCommand::new().with_targets(Some(targets));
+        let mut output = Vec::new();
+        let summary = cmd
+            .genomecov_with_summary(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+
+        // chr2 has no targets and is left out
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t0\t50\t100\t0.5\nchr1\t1\t50\t100\t0.5\n\
+             genome\t0\t50\t100\t0.5\ngenome\t1\t50\t100\t0.5\n"
+        );
+        assert_eq!(summary.bases(), 100);
+    }
+
+    #[test]
+    fn test_histogram_by_chrom_class() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        genome.insert("chr2".to_string(), 500);
+        genome.insert("chrM".to_string(), 100);
+        genome.insert("chrUn_1".to_string(), 50);
+        let content = "chr1\t0\t100\nchrM\t0\t100\n";
+        let classes = ChromClasses::from_reader(
+            "autosomes\tchr1-22\nsex\tchrX,chrY\nmito\tchrM\n".as_bytes(),
+        )
+        .unwrap();
+
+        let cmd = GenomecovCommand::new().with_chrom_classes(Some(classes));
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+
+        // `sex` has no chromosomes and is left out; chrUn_1 is `other`
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "autosomes\t0\t1400\t1500\t0.933333\nautosomes\t1\t100\t1500\t0.0666667\n\
+             mito\t1\t100\t100\t1\nother\t0\t50\t50\t1\n\
+             genome\t0\t1450\t1650\t0.878788\ngenome\t1\t200\t1650\t0.121212\n"
+        );
+    }
+
     #[test]
     fn test_empty_chromosome() {
         let cmd = GenomecovCommand::new();
@@ -0,0 +1,240 @@
+//! Coverage of an indexed A by a streamed B.
+//!
+//! The regular coverage sweep streams both files and needs them sorted in the
+//! same order. When A is small (a gene set, a capture panel) and B is huge
+//! (aligned reads), it is cheaper to turn the loop around: A is loaded into
+//! an [`IntervalIndex`] and every B record is looked up in it once, adding to
+//! counters kept per A interval. B is read in a single pass and does not have
+//! to be sorted; A is reported in input order when B is exhausted.
+//!
+//! Memory: O(A) plus, per A interval, the disjoint covered segments seen so
+//! far (at most one per gap in coverage).
+
+use crate::bed::BedError;
+use crate::index::IntervalIndex;
+use crate::interval::Interval;
+use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
+use crate::streaming::parsing::{
+    parse_bed3_bytes, parse_bed3_bytes_with_rest, should_skip_line, trim_newline,
+};
+use crate::streaming::ScoreFilter;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Indexed-A coverage command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedCoverageCommand {
+    /// Report mean depth instead of count and breadth
+    pub mean: bool,
+    /// Drop B records whose score is outside these bounds.
+    pub b_score: ScoreFilter,
+}
+
+/// Counters of one A interval.
+#[derive(Debug, Default)]
+struct Tally {
+    /// B records overlapping the interval.
+    count: u64,
+    /// Sum of the clipped B lengths (depth × bases).
+    depth_bases: u64,
+    /// Covered parts of the interval: sorted, disjoint, non-adjacent.
+    covered: Vec<(u64, u64)>,
+}
+
+impl Tally {
+    /// Add the clipped B range `[start, end)`.
+    fn add(&mut self, start: u64, end: u64) {
+        self.count += 1;
+        self.depth_bases += end - start;
+
+        // Sorted B only ever touches the last segment
+        match self.covered.last_mut() {
+            None => return self.covered.push((start, end)),
+            Some(last) if start > last.1 => return self.covered.push((start, end)),
+            Some(last) if start >= last.0 => {
+                last.1 = last.1.max(end);
+                return;
+            }
+            Some(_) => {}
+        }
+
+        // Out of order: merge with every segment it touches
+        let first = self.covered.partition_point(|&(_, e)| e < start);
+        let last = self.covered.partition_point(|&(s, _)| s <= end);
+        if first == last {
+            self.covered.insert(first, (start, end));
+        } else {
+            let merged = (
+                start.min(self.covered[first].0),
+                end.max(self.covered[last - 1].1),
+            );
+            self.covered.splice(first..last, [merged]);
+        }
+    }
+
+    fn covered_bases(&self) -> u64 {
+        self.covered.iter().map(|(s, e)| e - s).sum()
+    }
+}
+
+impl IndexedCoverageCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report mean depth (builder pattern).
+    pub fn with_mean(mut self, mean: bool) -> Self {
+        self.mean = mean;
+        self
+    }
+
+    /// Index A, stream B once and write one coverage row per A record.
+    ///
+    /// Rows have the same columns as the default sweep: count, covered
+    /// bases, length and covered fraction, or the mean depth with `mean`.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b_path: P,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let (a_lines, a_intervals) = read_a(a_path.as_ref())?;
+        let index = IntervalIndex::from_intervals(&a_intervals[..]);
+        let mut tallies: Vec<Tally> = (0..a_intervals.len()).map(|_| Tally::default()).collect();
+
+        let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, File::open(b_path)?);
+        let mut line = Vec::with_capacity(1024);
+        let mut query = Interval::new("", 0, 0);
+        loop {
+            line.clear();
+            if b_reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let bytes = trim_newline(&line);
+            if should_skip_line(bytes) {
+                continue;
+            }
+            let Some((chrom, start, end, rest)) = parse_bed3_bytes_with_rest(bytes) else {
+                continue;
+            };
+            if end < start || !self.b_score.accepts_rest(&bytes[rest..]) {
+                continue;
+            }
+
+            if query.chrom.as_bytes() != chrom {
+                query.chrom = String::from_utf8_lossy(chrom).into_owned();
+            }
+            query.start = start;
+            query.end = end;
+            for idx in index.find_overlap_indices(&query) {
+                let a = &a_intervals[idx];
+                tallies[idx].add(start.max(a.start), end.min(a.end));
+            }
+        }
+
+        let mut out = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
+        for ((line, a), tally) in a_lines.iter().zip(&a_intervals).zip(&tallies) {
+            // Use f32 to match the sweep (and bedtools) precision
+            let len = a.len();
+            let ratio = |bases: u64| {
+                if len > 0 {
+                    bases as f32 / len as f32
+                } else {
+                    0.0
+                }
+            };
+            if self.mean {
+                writeln!(out, "{}\t{:.7}", line, ratio(tally.depth_bases))?;
+            } else {
+                let covered = tally.covered_bases();
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{:.7}",
+                    line,
+                    tally.count,
+                    covered,
+                    len,
+                    ratio(covered)
+                )?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Read A records and their original lines.
+fn read_a(path: &Path) -> Result<(Vec<String>, Vec<Interval>), BedError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut lines = Vec::new();
+    let mut intervals = Vec::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let bytes = trim_newline(&line);
+        if should_skip_line(bytes) {
+            continue;
+        }
+        let Some((chrom, start, end)) = parse_bed3_bytes(bytes) else {
+            continue;
+        };
+        intervals.push(Interval::new(String::from_utf8_lossy(chrom), start, end));
+        lines.push(String::from_utf8_lossy(bytes).into_owned());
+    }
+    Ok((lines, intervals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as IoWrite;
+    use tempfile::NamedTempFile;
+
+    fn bed(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_tally_out_of_order() {
+        let mut tally = Tally::default();
+        for (s, e) in [(50, 60), (10, 20), (30, 40), (15, 35), (70, 80), (0, 5)] {
+            tally.add(s, e);
+        }
+        assert_eq!(tally.covered, vec![(0, 5), (10, 40), (50, 60), (70, 80)]);
+        assert_eq!(tally.covered_bases(), 55);
+        assert_eq!(tally.count, 6);
+    }
+
+    #[test]
+    fn test_unsorted_b_matches_sweep() {
+        // A in arbitrary order with a nested interval, B unsorted
+        let a = bed("chr2\t0\t100\tg3\nchr1\t100\t200\tg1\nchr1\t120\t130\tg2\n");
+        let b = bed("chr1\t150\t250\nchr2\t90\t95\nchr1\t90\t125\nchr3\t0\t10\nchr1\t125\t160\n");
+
+        let mut output = Vec::new();
+        IndexedCoverageCommand::new()
+            .run(a.path(), b.path(), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr2\t0\t100\tg3\t1\t5\t100\t0.0500000\n\
+             chr1\t100\t200\tg1\t3\t100\t100\t1.0000000\n\
+             chr1\t120\t130\tg2\t2\t10\t10\t1.0000000\n"
+        );
+
+        let mut output = Vec::new();
+        IndexedCoverageCommand::new()
+            .with_mean(true)
+            .run(a.path(), b.path(), &mut output)
+            .unwrap();
+        let means: Vec<&str> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|l| l.rsplit('\t').next().unwrap())
+            .collect();
+        assert_eq!(means, ["0.0500000", "1.1000000", "1.0000000"]);
+    }
+}
@@ -2,11 +2,15 @@
 //!
 //! Uses O(n+m) sweep-line algorithm per chromosome for optimal performance.
 
-use crate::bed::{read_records, BedError};
+use crate::bed::{read_records, BedError, BedReader};
+use crate::commands::stab::IndexedBed;
+use crate::config::display_score;
 use crate::index::IntervalIndex;
 use crate::interval::{BedRecord, Interval};
-use crate::parallel::{group_by_chromosome, PARALLEL_THRESHOLD};
+use crate::parallel::{PoolHandle, PARALLEL_THRESHOLD};
+use crate::streaming::ScoreFilter;
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
@@ -41,6 +45,8 @@ pub struct IntersectCommand {
     pub fraction_b: Option<f64>,
     /// Require reciprocal fraction overlap
     pub reciprocal: bool,
+    /// Minimum overlap in base pairs
+    pub min_overlap_bp: Option<u64>,
     /// Report the number of overlaps
     pub count: bool,
     /// Require same strand
@@ -51,6 +57,19 @@ pub struct IntersectCommand {
     pub report_once: bool,
     /// Split by chromosome for parallel processing
     pub parallel: bool,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
+    /// Drop B records whose score is outside these bounds
+    pub b_score: ScoreFilter,
+    /// Stop each A after this many reported overlaps
+    pub max_hits: Option<usize>,
+    /// Write A and B with the overlap in bp and as fractions of A and B
+    pub overlap_fractions: bool,
+    /// Write A and B followed by the overlap in bp (-wo)
+    pub write_overlap: bool,
+    /// Also report A records without overlaps, against a null B record
+    /// (-loj; -wao together with `write_overlap`)
+    pub left_outer: bool,
 }
 
 impl Default for IntersectCommand {
@@ -69,14 +88,33 @@ impl IntersectCommand {
             fraction_a: None,
             fraction_b: None,
             reciprocal: false,
+            min_overlap_bp: None,
             count: false,
             same_strand: false,
             opposite_strand: false,
             report_once: false,
             parallel: true,
+            pool: PoolHandle::global(),
+            b_score: ScoreFilter::default(),
+            max_hits: None,
+            overlap_fractions: false,
+            write_overlap: false,
+            left_outer: false,
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Only intersect with B records whose score lies within `filter`.
+    pub fn with_b_score(mut self, filter: ScoreFilter) -> Self {
+        self.b_score = filter;
+        self
+    }
+
     /// Find all intersecting pairs.
     pub fn find_intersections(
         &self,
@@ -87,65 +125,138 @@ impl IntersectCommand {
 
         for (a_idx, a) in a_intervals.iter().enumerate() {
             let overlaps = b_index.find_overlaps(a);
+            self.collect_results(&mut results, a, a_idx, overlaps);
+        }
+
+        results
+    }
+
+    /// Find all intersecting pairs with a sweep over pre-sorted inputs.
+    ///
+    /// Both slices must be sorted by chromosome, then start (the `Ord` order
+    /// of `Interval`). No index is built and neither input is copied, so this
+    /// is the preferred path when the caller already knows its data is sorted.
+    pub fn find_intersections_sorted(
+        &self,
+        a_intervals: &[Interval],
+        b_intervals: &[Interval],
+    ) -> Vec<IntersectResult> {
+        let mut results = Vec::new();
+        let b_len = b_intervals.len();
 
-            if self.no_overlap {
-                // -v flag: report A intervals with NO overlap
-                if overlaps.is_empty() {
-                    results.push(IntersectResult {
-                        a_interval: a.clone(),
-                        a_index: a_idx,
-                        b_intervals: Vec::new(),
-                        overlap_count: 0,
-                    });
+        // First B that could still overlap the current or a later A
+        let mut b_head: usize = 0;
+        let mut overlaps: Vec<&Interval> = Vec::with_capacity(64);
+
+        for (a_idx, a) in a_intervals.iter().enumerate() {
+            // Skip B on earlier chromosomes, or ending before A starts
+            while b_head < b_len {
+                let b = &b_intervals[b_head];
+                match b.chrom.cmp(&a.chrom) {
+                    std::cmp::Ordering::Less => b_head += 1,
+                    std::cmp::Ordering::Equal if b.end <= a.start => b_head += 1,
+                    _ => break,
                 }
-            } else {
-                let filtered: Vec<&Interval> = overlaps
-                    .into_iter()
-                    .filter(|b| self.passes_filters(a, b))
-                    .collect();
-
-                if !filtered.is_empty() {
-                    if self.report_once || self.unique {
-                        results.push(IntersectResult {
-                            a_interval: a.clone(),
-                            a_index: a_idx,
-                            b_intervals: if self.unique {
-                                Vec::new()
-                            } else {
-                                vec![filtered[0].clone()]
-                            },
-                            overlap_count: filtered.len(),
-                        });
-                    } else {
-                        for b in filtered {
-                            results.push(IntersectResult {
-                                a_interval: a.clone(),
-                                a_index: a_idx,
-                                b_intervals: vec![b.clone()],
-                                overlap_count: 1,
-                            });
-                        }
-                    }
+            }
+
+            overlaps.clear();
+            for b in &b_intervals[b_head..] {
+                if b.chrom != a.chrom || b.start >= a.end {
+                    break;
+                }
+                if b.end > a.start {
+                    overlaps.push(b);
                 }
             }
+
+            self.collect_results(&mut results, a, a_idx, overlaps.drain(..));
         }
 
         results
     }
 
-    /// Find intersections in parallel by chromosome.
-    pub fn find_intersections_parallel(
+    /// Turn the raw overlaps of one A interval into results per the command flags.
+    #[inline]
+    fn collect_results<'b>(
         &self,
-        a_intervals: Vec<Interval>,
-        b_intervals: Vec<Interval>,
+        results: &mut Vec<IntersectResult>,
+        a: &Interval,
+        a_idx: usize,
+        overlaps: impl IntoIterator<Item = &'b Interval>,
+    ) {
+        let mut overlaps = overlaps.into_iter().peekable();
+
+        if self.no_overlap {
+            // -v flag: report A intervals with NO overlap
+            if overlaps.peek().is_none() {
+                results.push(IntersectResult {
+                    a_interval: a.clone(),
+                    a_index: a_idx,
+                    b_intervals: Vec::new(),
+                    overlap_count: 0,
+                });
+            }
+            return;
+        }
+
+        let filtered: Vec<&Interval> = overlaps.filter(|b| self.passes_filters(a, b)).collect();
+
+        if filtered.is_empty() {
+            return;
+        }
+
+        if self.report_once || self.unique {
+            results.push(IntersectResult {
+                a_interval: a.clone(),
+                a_index: a_idx,
+                b_intervals: if self.unique {
+                    Vec::new()
+                } else {
+                    vec![filtered[0].clone()]
+                },
+                overlap_count: filtered.len(),
+            });
+        } else {
+            let limit = self.max_hits.unwrap_or(usize::MAX);
+            for b in filtered.into_iter().take(limit) {
+                results.push(IntersectResult {
+                    a_interval: a.clone(),
+                    a_index: a_idx,
+                    b_intervals: vec![b.clone()],
+                    overlap_count: 1,
+                });
+            }
+        }
+    }
+
+    /// Find intersections in parallel over chunks of A.
+    ///
+    /// Accepts owned `Vec`s or borrowed slices; borrowed input is not copied.
+    /// Results are in A order and `a_index` refers to the position in A.
+    pub fn find_intersections_parallel<'a, 'b>(
+        &self,
+        a_intervals: impl Into<Cow<'a, [Interval]>>,
+        b_intervals: impl Into<Cow<'b, [Interval]>>,
     ) -> Vec<IntersectResult> {
+        const CHUNK_SIZE: usize = 4096;
+
+        let a_intervals = a_intervals.into();
         let b_index = IntervalIndex::from_intervals(b_intervals);
-        let a_groups = group_by_chromosome(a_intervals);
 
-        let results: Vec<Vec<IntersectResult>> = a_groups
-            .into_par_iter()
-            .map(|(_, intervals)| self.find_intersections(&intervals, &b_index))
-            .collect();
+        let results: Vec<Vec<IntersectResult>> = self.pool.install(|| {
+            a_intervals
+                .par_chunks(CHUNK_SIZE)
+                .enumerate()
+                .map(|(chunk_idx, chunk)| {
+                    let mut results = self.find_intersections(chunk, &b_index);
+                    let offset = chunk_idx * CHUNK_SIZE;
+                    for result in &mut results {
+                        result.a_index += offset;
+                    }
+                    results
+                })
+                .collect()
+        });
 
         results.into_iter().flatten().collect()
     }
@@ -153,6 +264,12 @@ impl IntersectCommand {
     /// Check if an overlap passes all filters.
     #[inline(always)]
     fn passes_filters(&self, a: &Interval, b: &Interval) -> bool {
+        if let Some(bp) = self.min_overlap_bp {
+            if a.overlap_length(b) < bp {
+                return false;
+            }
+        }
+
         // Check fraction filters
         if let Some(frac) = self.fraction_a {
             if !a.overlaps_by_fraction(b, frac) {
@@ -208,7 +325,11 @@ impl IntersectCommand {
         output: &mut W,
     ) -> Result<(), BedError> {
         let a_records = read_records(a_path)?;
-        let b_records = read_records(b_path)?;
+        let mut b_records = read_records(b_path)?;
+        let b_columns = b_records.first().map_or(3, written_columns);
+        if self.b_score.is_active() {
+            b_records.retain(|b| self.b_score.accepts_record(b));
+        }
 
         // Group by chromosome
         let a_by_chrom = Self::group_records_by_chrom_owned(a_records);
@@ -228,23 +349,27 @@ impl IntersectCommand {
                 let mut buf = Vec::with_capacity(64 * 1024);
                 if let Some(a_list) = a_by_chrom.get(chrom) {
                     let b_list = b_by_chrom.get(chrom);
-                    self.intersect_chromosome_sweepline(a_list, b_list, &mut buf);
+                    self.intersect_chromosome_sweepline(a_list, b_list, b_columns, &mut buf);
                 }
                 output.write_all(&buf).map_err(BedError::Io)?;
             }
         } else {
             // Parallel processing for large datasets
-            let results: Vec<Vec<u8>> = chroms
-                .par_iter()
-                .map(|chrom| {
-                    let mut buf = Vec::with_capacity(64 * 1024);
-                    if let Some(a_list) = a_by_chrom.get(chrom) {
-                        let b_list = b_by_chrom.get(chrom);
-                        self.intersect_chromosome_sweepline(a_list, b_list, &mut buf);
-                    }
-                    buf
-                })
-                .collect();
+            let results: Vec<Vec<u8>> = self.pool.install(|| {
+                chroms
+                    .par_iter()
+                    .map(|chrom| {
+                        let mut buf = Vec::with_capacity(64 * 1024);
+                        if let Some(a_list) = a_by_chrom.get(chrom) {
+                            let b_list = b_by_chrom.get(chrom);
+                            self.intersect_chromosome_sweepline(
+                                a_list, b_list, b_columns, &mut buf,
+                            );
+                        }
+                        buf
+                    })
+                    .collect()
+            });
 
             // Write results in chromosome order
             for buf in results {
@@ -255,6 +380,68 @@ impl IntersectCommand {
         Ok(())
     }
 
+    /// Look up each A record in an indexed B instead of reading all of B.
+    ///
+    /// Meant for a few A records against a large sorted B with a `.gri`
+    /// index (see [`IndexedBed`]): only the B records near each A record
+    /// are parsed, so the cost does not grow with the size of B. A need not
+    /// be sorted; its records are reported in input order, with the same
+    /// output as [`run`] gives for sorted A.
+    ///
+    /// [`run`]: IntersectCommand::run
+    pub fn run_indexed<P: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b: &IndexedBed,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let a_records = read_records(a_path)?;
+        let mut buf = Vec::with_capacity(64 * 1024);
+        let mut b_lines = Vec::new();
+        let b_columns = match b.first_record() {
+            Some(line) => BedReader::new(line)
+                .read_record()?
+                .as_ref()
+                .map_or(3, written_columns),
+            None => 3,
+        };
+
+        for a_rec in &a_records {
+            let (a_start, a_end) = (a_rec.start(), a_rec.end());
+
+            // Parse the B lines near A together. One base of slack on the
+            // left catches zero-length B records that --bedtools-compatible
+            // widens to 1 bp; coordinates are checked again after parsing
+            b_lines.clear();
+            for (line, _, _) in b.query(a_rec.chrom(), a_start.saturating_sub(1), a_end) {
+                b_lines.extend_from_slice(line);
+                b_lines.push(b'\n');
+            }
+            let b_records = BedReader::new(b_lines.as_slice())
+                .records()
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let overlaps: Vec<&BedRecord> = b_records
+                .iter()
+                .filter(|b_rec| {
+                    b_rec.start() < a_end
+                        && a_start < b_rec.end()
+                        && self.b_score.accepts_record(b_rec)
+                        && self.passes_record_filters(a_rec, b_rec)
+                })
+                .collect();
+            self.output_overlaps(&mut buf, a_rec, &overlaps, b_columns);
+
+            if buf.len() >= 64 * 1024 {
+                output.write_all(&buf).map_err(BedError::Io)?;
+                buf.clear();
+            }
+        }
+
+        output.write_all(&buf).map_err(BedError::Io)?;
+        Ok(())
+    }
+
     /// O(n+m) sweep-line intersection for a single chromosome.
     ///
     /// Algorithm:
@@ -268,20 +455,15 @@ impl IntersectCommand {
         &self,
         a_sorted: &[BedRecord],
         b_sorted: Option<&Vec<BedRecord>>,
+        b_columns: usize,
         output: &mut Vec<u8>,
     ) {
         let b_sorted = match b_sorted {
             Some(b) if !b.is_empty() => b,
             _ => {
-                // No B intervals - handle -v flag or output nothing
-                if self.no_overlap {
-                    for a_rec in a_sorted {
-                        self.write_record_to_buf(output, a_rec);
-                    }
-                } else if self.count {
-                    for a_rec in a_sorted {
-                        self.write_record_with_count_to_buf(output, a_rec, 0);
-                    }
+                // No B intervals - only the modes reporting A without overlaps write
+                for a_rec in a_sorted {
+                    self.output_overlaps(output, a_rec, &[], b_columns);
                 }
                 return;
             }
@@ -329,13 +511,28 @@ impl IntersectCommand {
             }
 
             // Output based on flags
-            self.output_overlaps(output, a_rec, &overlaps);
+            self.output_overlaps(output, a_rec, &overlaps, b_columns);
         }
     }
 
-    /// Output overlaps for a single A record based on command flags
+    /// Output overlaps for a single A record based on command flags.
+    ///
+    /// `b_columns` is the width of the null B record written for A records
+    /// without overlaps in `left_outer` mode.
     #[inline]
-    fn output_overlaps(&self, output: &mut Vec<u8>, a_rec: &BedRecord, overlaps: &[&BedRecord]) {
+    fn output_overlaps(
+        &self,
+        output: &mut Vec<u8>,
+        a_rec: &BedRecord,
+        overlaps: &[&BedRecord],
+        b_columns: usize,
+    ) {
+        // --max-hits caps the modes that print one line per overlap
+        let hits = match self.max_hits {
+            Some(limit) if overlaps.len() > limit => &overlaps[..limit],
+            _ => overlaps,
+        };
+
         if self.no_overlap {
             // -v flag: report A if NO overlap
             if overlaps.is_empty() {
@@ -349,24 +546,56 @@ impl IntersectCommand {
             if !overlaps.is_empty() {
                 self.write_record_to_buf(output, a_rec);
             }
+        } else if self.overlap_fractions {
+            // --overlap-fractions: report A and B, then the overlap columns
+            for b_rec in hits {
+                self.write_both_records_to_buf(output, a_rec, b_rec);
+                // The overlap columns go before the newline
+                output.pop();
+                let _ = write_overlap_fractions(
+                    output,
+                    (a_rec.start(), a_rec.end()),
+                    (b_rec.start(), b_rec.end()),
+                );
+                output.push(b'\n');
+            }
+        } else if self.write_overlap || self.left_outer {
+            // -wo / -loj / -wao: report A and B, then the overlap in bp
+            for b_rec in hits {
+                self.write_both_records_to_buf(output, a_rec, b_rec);
+                if self.write_overlap {
+                    output.pop();
+                    let bp = a_rec.interval.overlap_length(&b_rec.interval);
+                    let _ = writeln!(output, "\t{}", bp);
+                }
+            }
+            if overlaps.is_empty() && self.left_outer {
+                self.write_record_to_buf(output, a_rec);
+                output.pop();
+                let _ = write_null_b(output, b_columns);
+                if self.write_overlap {
+                    output.extend_from_slice(b"\t0");
+                }
+                output.push(b'\n');
+            }
         } else if self.write_a && self.write_b {
             // -wa -wb: report both A and B
-            for b_rec in overlaps {
+            for b_rec in hits {
                 self.write_both_records_to_buf(output, a_rec, b_rec);
             }
         } else if self.write_a {
             // -wa: report original A for each overlap
-            for _ in overlaps {
+            for _ in hits {
                 self.write_record_to_buf(output, a_rec);
             }
         } else if self.write_b {
             // -wb: report overlap portion + B entry
-            for b_rec in overlaps {
+            for b_rec in hits {
                 self.write_overlap_with_b_to_buf(output, a_rec, b_rec);
             }
         } else {
             // Default: report overlapping portion with A's fields
-            for b_rec in overlaps {
+            for b_rec in hits {
                 self.write_overlap_to_buf(output, a_rec, b_rec);
             }
         }
@@ -394,7 +623,7 @@ impl IntersectCommand {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -410,7 +639,7 @@ impl IntersectCommand {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -428,7 +657,7 @@ impl IntersectCommand {
         if let Some(ref name) = a_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = a_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = a_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -445,7 +674,7 @@ impl IntersectCommand {
         if let Some(ref name) = b_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = b_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = b_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -466,7 +695,7 @@ impl IntersectCommand {
         if let Some(ref name) = a_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = a_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = a_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -487,7 +716,7 @@ impl IntersectCommand {
         if let Some(ref name) = a_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = a_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = a_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -504,7 +733,7 @@ impl IntersectCommand {
         if let Some(ref name) = b_rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = b_rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = b_rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -547,6 +776,48 @@ pub fn filter_overlapping(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
         .collect()
 }
 
+/// Write the `--overlap-fractions` columns for an A/B pair, each after a
+/// tab: the overlap in bp, then the fractions of A and of B it covers.
+/// A zero-length record has no fraction and gets `.`.
+pub(crate) fn write_overlap_fractions<W: Write>(
+    out: &mut W,
+    (a_start, a_end): (u64, u64),
+    (b_start, b_end): (u64, u64),
+) -> std::io::Result<()> {
+    let bp = a_end.min(b_end).saturating_sub(a_start.max(b_start));
+    write!(out, "\t{}", bp)?;
+    for len in [a_end - a_start, b_end - b_start] {
+        if len == 0 {
+            out.write_all(b"\t.")?;
+        } else {
+            write!(out, "\t{:.7}", bp as f64 / len as f64)?;
+        }
+    }
+    Ok(())
+}
+
+/// Columns of `rec` as intersect writes it: BED3, then name, score and
+/// strand for as long as they are present.
+pub(crate) fn written_columns(rec: &BedRecord) -> usize {
+    match (&rec.name, rec.score, rec.strand) {
+        (None, _, _) => 3,
+        (Some(_), None, _) => 4,
+        (Some(_), Some(_), None) => 5,
+        (Some(_), Some(_), Some(_)) => 6,
+    }
+}
+
+/// Write the null B record that `-loj` and `-wao` pair with an A record
+/// without overlaps, after a tab: `.`, `-1`, `-1`, then `.` for each
+/// further B column.
+pub(crate) fn write_null_b<W: Write>(out: &mut W, b_columns: usize) -> std::io::Result<()> {
+    out.write_all(b"\t.\t-1\t-1")?;
+    for _ in 3..b_columns {
+        out.write_all(b"\t.")?;
+    }
+    Ok(())
+}
+
 /// Find intervals in A that have NO overlap with B.
 pub fn filter_non_overlapping(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
     let b_index = IntervalIndex::from_intervals(b.to_vec());
@@ -617,6 +888,109 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_min_overlap_bp() {
+        let a = vec![Interval::new("chr1", 100, 200)];
+        let b = vec![
+            Interval::new("chr1", 190, 300), // 10bp
+            Interval::new("chr1", 50, 130),  // 30bp
+        ];
+        let b_index = IntervalIndex::from_intervals(b);
+
+        let mut cmd = IntersectCommand::new();
+        cmd.min_overlap_bp = Some(30);
+        let results = cmd.find_intersections(&a, &b_index);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].b_intervals, vec![Interval::new("chr1", 50, 130)]);
+
+        // Both thresholds must hold: 30bp is only 30% of A
+        cmd.fraction_a = Some(0.5);
+        assert!(cmd.find_intersections(&a, &b_index).is_empty());
+    }
+
+    #[test]
+    fn test_max_hits() {
+        let a = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 300, 400),
+        ];
+        let b = vec![
+            Interval::new("chr1", 110, 120),
+            Interval::new("chr1", 130, 140),
+            Interval::new("chr1", 150, 160),
+            Interval::new("chr1", 350, 360),
+        ];
+
+        let mut cmd = IntersectCommand::new();
+        cmd.max_hits = Some(2);
+        let results = cmd.find_intersections_sorted(&a, &b);
+        let starts: Vec<u64> = results.iter().map(|r| r.b_intervals[0].start).collect();
+        assert_eq!(starts, [110, 130, 350]);
+
+        // -u still reports the full count
+        cmd.unique = true;
+        let results = cmd.find_intersections_sorted(&a, &b);
+        assert_eq!(results[0].overlap_count, 3);
+    }
+
+    #[test]
+    fn test_write_overlap_fractions() {
+        let mut out = Vec::new();
+        write_overlap_fractions(&mut out, (100, 200), (150, 350)).unwrap();
+        assert_eq!(out, b"\t50\t0.5000000\t0.2500000");
+
+        // A zero-length B has no fraction
+        out.clear();
+        write_overlap_fractions(&mut out, (100, 200), (150, 150)).unwrap();
+        assert_eq!(out, b"\t0\t0.0000000\t.");
+    }
+
+    #[test]
+    fn test_write_null_b() {
+        let mut out = Vec::new();
+        write_null_b(&mut out, 3).unwrap();
+        assert_eq!(out, b"\t.\t-1\t-1");
+
+        out.clear();
+        write_null_b(&mut out, 5).unwrap();
+        assert_eq!(out, b"\t.\t-1\t-1\t.\t.");
+    }
+
+    #[test]
+    fn test_join_modes() {
+        let mut a = tempfile::NamedTempFile::new().unwrap();
+        a.write_all(b"chr1\t100\t200\ta1\nchr1\t300\t400\ta2\nchr2\t0\t10\ta3\n")
+            .unwrap();
+        let mut b = tempfile::NamedTempFile::new().unwrap();
+        b.write_all(b"chr1\t150\t250\tb1\n").unwrap();
+
+        let run = |write_overlap: bool, left_outer: bool| {
+            let mut cmd = IntersectCommand::new();
+            cmd.write_overlap = write_overlap;
+            cmd.left_outer = left_outer;
+            let mut out = Vec::new();
+            cmd.run(a.path(), b.path(), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let hit = "chr1\t100\t200\ta1\tchr1\t150\t250\tb1";
+        assert_eq!(run(true, false), format!("{}\t50\n", hit));
+        assert_eq!(
+            run(false, true),
+            format!(
+                "{}\nchr1\t300\t400\ta2\t.\t-1\t-1\t.\nchr2\t0\t10\ta3\t.\t-1\t-1\t.\n",
+                hit
+            )
+        );
+        assert_eq!(
+            run(true, true),
+            format!(
+                "{}\t50\nchr1\t300\t400\ta2\t.\t-1\t-1\t.\t0\nchr2\t0\t10\ta3\t.\t-1\t-1\t.\t0\n",
+                hit
+            )
+        );
+    }
+
     #[test]
     fn test_compute_overlap() {
         let cmd = IntersectCommand::new();
@@ -645,6 +1019,38 @@ mod tests {
         assert_eq!(filtered[0].start, 500);
     }
 
+    #[test]
+    fn test_sorted_intersect() {
+        let a = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 300, 400),
+            Interval::new("chr2", 100, 200),
+            Interval::new("chr3", 50, 60),
+        ];
+        let b = vec![
+            Interval::new("chr1", 50, 1000),
+            Interval::new("chr1", 150, 160),
+            Interval::new("chr2", 10, 20),
+            Interval::new("chr2", 150, 250),
+        ];
+
+        let cmd = IntersectCommand::new();
+        let results = cmd.find_intersections_sorted(&a, &b);
+
+        let pairs: Vec<(usize, u64)> = results
+            .iter()
+            .map(|r| (r.a_index, r.b_intervals[0].start))
+            .collect();
+        // The long chr1 B interval must still be seen by the second A
+        assert_eq!(pairs, vec![(0, 50), (0, 150), (1, 50), (2, 150)]);
+
+        let mut cmd = IntersectCommand::new();
+        cmd.no_overlap = true;
+        let results = cmd.find_intersections_sorted(&a, &b);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].a_interval.chrom, "chr3");
+    }
+
     #[test]
     fn test_parallel_intersect() {
         let (a, b) = make_intervals();
@@ -654,4 +1060,47 @@ mod tests {
 
         assert_eq!(results.len(), 2);
     }
+
+    #[test]
+    fn test_parallel_intersect_global_index() {
+        let a: Vec<Interval> = (0..10_000u64)
+            .map(|i| Interval::new("chr1", i * 10, i * 10 + 5))
+            .collect();
+        let b = vec![Interval::new("chr1", 99_990, 99_995)];
+        let cmd = IntersectCommand::new();
+
+        let results = cmd.find_intersections_parallel(&a, &b);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].a_index, 9_999);
+    }
+
+    #[test]
+    fn test_parallel_intersect_dedicated_pools() {
+        let a: Vec<Interval> = (0..20_000u64)
+            .map(|i| Interval::new("chr1", i * 10, i * 10 + 5))
+            .collect();
+        let b: Vec<Interval> = (0..2_000u64)
+            .map(|i| Interval::new("chr1", i * 100, i * 100 + 1))
+            .collect();
+
+        // Two commands with separate thread budgets running concurrently
+        let (one, four) = std::thread::scope(|scope| {
+            let one = scope.spawn(|| {
+                IntersectCommand::new()
+                    .with_pool(PoolHandle::with_threads(1).unwrap())
+                    .find_intersections_parallel(&a, &b)
+            });
+            let four = scope.spawn(|| {
+                IntersectCommand::new()
+                    .with_pool(PoolHandle::with_threads(4).unwrap())
+                    .find_intersections_parallel(&a, &b)
+            });
+            (one.join().unwrap(), four.join().unwrap())
+        });
+
+        assert_eq!(one.len(), 2_000);
+        let index = |r: &[IntersectResult]| r.iter().map(|x| x.a_index).collect::<Vec<_>>();
+        assert_eq!(index(&one), index(&four));
+    }
 }
@@ -18,8 +18,9 @@
 //!    vectorization of overlap detection.
 
 use crate::bed::{read_records, BedError, BedReader};
+use crate::config::display_score;
 use crate::interval::BedRecord;
-use crate::parallel::PARALLEL_THRESHOLD;
+use crate::parallel::{PoolHandle, PARALLEL_THRESHOLD};
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs::File;
@@ -165,10 +166,14 @@ pub struct IntersectConfig {
     pub fraction_a: Option<f64>,
     /// Require reciprocal fraction overlap (-r)
     pub reciprocal: bool,
+    /// Minimum overlap in base pairs
+    pub min_overlap_bp: Option<u64>,
     /// Report the number of overlaps (-c)
     pub count: bool,
     /// Force specific execution mode
     pub forced_mode: Option<ForcedMode>,
+    /// Thread pool for parallel execution
+    pub pool: PoolHandle,
 }
 
 /// Statistics from intersect operation.
@@ -228,7 +233,7 @@ impl IntersectEngine {
             num_chromosomes: 0, // Will be computed during grouping
             is_sorted: true,    // Assume sorted for now
             available_memory_mb: 1024,
-            available_cores: rayon::current_num_threads(),
+            available_cores: self.config.pool.current_num_threads(),
         };
 
         // Select execution mode
@@ -311,19 +316,21 @@ impl IntersectEngine {
         chroms.sort();
 
         // Process chromosomes in parallel
-        let results: Vec<(Vec<u8>, usize)> = chroms
-            .par_iter()
-            .map(|chrom| {
-                let mut buf = Vec::with_capacity(64 * 1024);
-                let overlaps = if let Some(a_list) = a_by_chrom.get(chrom) {
-                    let b_list = b_by_chrom.get(chrom);
-                    self.sweep_line_intersect(a_list, b_list, &mut buf)
-                } else {
-                    0
-                };
-                (buf, overlaps)
-            })
-            .collect();
+        let results: Vec<(Vec<u8>, usize)> = self.config.pool.install(|| {
+            chroms
+                .par_iter()
+                .map(|chrom| {
+                    let mut buf = Vec::with_capacity(64 * 1024);
+                    let overlaps = if let Some(a_list) = a_by_chrom.get(chrom) {
+                        let b_list = b_by_chrom.get(chrom);
+                        self.sweep_line_intersect(a_list, b_list, &mut buf)
+                    } else {
+                        0
+                    };
+                    (buf, overlaps)
+                })
+                .collect()
+        });
 
         // Aggregate results in chromosome order (deterministic)
         for (buf, overlaps) in results {
@@ -588,6 +595,11 @@ impl IntersectEngine {
 
     #[inline]
     fn passes_filters(&self, a: &BedRecord, b: &BedRecord) -> bool {
+        if let Some(bp) = self.config.min_overlap_bp {
+            if a.interval.overlap_length(&b.interval) < bp {
+                return false;
+            }
+        }
         if let Some(frac) = self.config.fraction_a {
             if !a.interval.overlaps_by_fraction(&b.interval, frac) {
                 return false;
@@ -642,7 +654,7 @@ impl IntersectEngine {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -2,15 +2,22 @@
 //!
 //! Computes Jaccard similarity coefficient between two BED files.
 //! Uses true streaming merge-sweep algorithm with O(k) memory.
+//!
+//! [`JaccardCommand::run_batch`] compares many labelled file pairs in
+//! parallel, e.g. every pair of replicates from a manifest, and writes the
+//! results as a long-format table.
 
 use crate::bed::BedError;
+use crate::genome::Genome;
+use crate::manifest::{Manifest, ManifestEntry};
 use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
+use rayon::prelude::*;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Format a float like C's %g: 6 significant figures, trailing zeros trimmed
-fn format_g(val: f64) -> String {
+pub(crate) fn format_g(val: f64) -> String {
     if val == 0.0 {
         return "0".to_string();
     }
@@ -42,6 +49,10 @@ pub struct JaccardCommand {
     pub fraction_a: Option<f64>,
     pub fraction_b: Option<f64>,
     pub reciprocal: bool,
+    /// Also report per-file bases and overlap/Dice coefficients
+    pub extended: bool,
+    /// Genome size for fraction-of-genome columns (implies `extended`)
+    pub genome_size: Option<u64>,
 }
 
 impl Default for JaccardCommand {
@@ -57,9 +68,23 @@ impl JaccardCommand {
             fraction_a: None,
             fraction_b: None,
             reciprocal: false,
+            extended: false,
+            genome_size: None,
         }
     }
 
+    /// Report per-file bases and overlap/Dice coefficients.
+    pub fn with_extended(mut self, extended: bool) -> Self {
+        self.extended = extended;
+        self
+    }
+
+    /// Report fractions of `genome` covered by A, B and their union.
+    pub fn with_genome(mut self, genome: &Genome) -> Self {
+        self.genome_size = Some(genome.total_size());
+        self
+    }
+
     /// Run jaccard analysis between two files.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
@@ -105,6 +130,17 @@ impl JaccardCommand {
         b_path: &Path,
         output: &mut W,
     ) -> Result<(), BedError> {
+        let stats = self.compute(a_path, b_path)?;
+        let columns = self.columns(&stats);
+        let header: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        let values: Vec<&str> = columns.iter().map(|(_, value)| value.as_str()).collect();
+        writeln!(output, "{}", header.join("\t"))?;
+        writeln!(output, "{}", values.join("\t"))?;
+        Ok(())
+    }
+
+    /// Sweep two sorted files and total their overlap.
+    pub fn compute(&self, a_path: &Path, b_path: &Path) -> Result<JaccardStats, BedError> {
         let file_a = File::open(a_path)?;
         let file_b = File::open(b_path)?;
 
@@ -128,6 +164,8 @@ impl JaccardCommand {
         let mut total_intersection: u64 = 0;
         let mut total_union: u64 = 0;
         let mut total_n_intersections: u64 = 0;
+        let mut total_a: u64 = 0;
+        let mut total_b: u64 = 0;
 
         // Current chromosome being processed
         let mut current_chrom: Vec<u8> = Vec::new();
@@ -260,6 +298,12 @@ impl JaccardCommand {
                 if depth_a > 0 || depth_b > 0 {
                     total_union += span;
                 }
+                if depth_a > 0 {
+                    total_a += span;
+                }
+                if depth_b > 0 {
+                    total_b += span;
+                }
             }
 
             // Process the event
@@ -308,26 +352,191 @@ impl JaccardCommand {
             total_n_intersections += 1;
         }
 
-        // Compute Jaccard coefficient
-        let jaccard = if total_union > 0 {
-            total_intersection as f64 / total_union as f64
-        } else {
-            0.0
+        Ok(JaccardStats {
+            intersection: total_intersection,
+            union: total_union,
+            n_intersections: total_n_intersections,
+            bases_a: total_a,
+            bases_b: total_b,
+        })
+    }
+
+    /// Named output columns for `stats`, in output order.
+    pub fn columns(&self, stats: &JaccardStats) -> Vec<(&'static str, String)> {
+        let ratio = |num: u64, den: u64| {
+            if den > 0 {
+                format_g(num as f64 / den as f64)
+            } else {
+                "0".to_string()
+            }
         };
 
-        let jaccard_str = format_g(jaccard);
+        let mut columns = vec![
+            ("intersection", stats.intersection.to_string()),
+            ("union", stats.union.to_string()),
+            ("jaccard", ratio(stats.intersection, stats.union)),
+            ("n_intersections", stats.n_intersections.to_string()),
+        ];
+        if !self.extended && self.genome_size.is_none() {
+            return columns;
+        }
 
-        writeln!(output, "intersection\tunion\tjaccard\tn_intersections")?;
-        writeln!(
-            output,
-            "{}\t{}\t{}\t{}",
-            total_intersection, total_union, jaccard_str, total_n_intersections
-        )?;
+        // Szymkiewicz-Simpson overlap coefficient and Sorensen-Dice coefficient
+        columns.extend([
+            ("bases_a", stats.bases_a.to_string()),
+            ("bases_b", stats.bases_b.to_string()),
+            (
+                "overlap_coef",
+                ratio(stats.intersection, stats.bases_a.min(stats.bases_b)),
+            ),
+            (
+                "dice",
+                ratio(2 * stats.intersection, stats.bases_a + stats.bases_b),
+            ),
+        ]);
+        if let Some(genome_size) = self.genome_size {
+            columns.extend([
+                ("genome_frac_a", ratio(stats.bases_a, genome_size)),
+                ("genome_frac_b", ratio(stats.bases_b, genome_size)),
+                ("genome_frac_union", ratio(stats.union, genome_size)),
+            ]);
+        }
+        columns
+    }
 
+    /// Compare many file pairs in parallel and write one `label_a`,
+    /// `label_b`, `statistic`, `value` line per output column of each pair.
+    ///
+    /// Pairs are compared on the global Rayon pool and written in input
+    /// order once all have finished, so the output does not depend on the
+    /// thread count.
+    pub fn run_batch<W: Write>(
+        &self,
+        pairs: &[JaccardPair],
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let results: Vec<JaccardStats> = pairs
+            .par_iter()
+            .map(|pair| {
+                self.compute(&pair.path_a, &pair.path_b).map_err(|e| {
+                    BedError::InvalidFormat(format!("{} vs {}: {}", pair.label_a, pair.label_b, e))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        writeln!(output, "label_a\tlabel_b\tstatistic\tvalue")?;
+        for (pair, stats) in pairs.iter().zip(&results) {
+            for (name, value) in self.columns(stats) {
+                writeln!(
+                    output,
+                    "{}\t{}\t{}\t{}",
+                    pair.label_a, pair.label_b, name, value
+                )?;
+            }
+        }
         Ok(())
     }
 }
 
+/// Base-pair totals of one jaccard comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JaccardStats {
+    /// Bases covered by both files
+    pub intersection: u64,
+    /// Bases covered by either file
+    pub union: u64,
+    /// Number of contiguous intersecting regions
+    pub n_intersections: u64,
+    /// Bases covered by A
+    pub bases_a: u64,
+    /// Bases covered by B
+    pub bases_b: u64,
+}
+
+/// Two labelled files to compare in a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JaccardPair {
+    pub label_a: String,
+    pub path_a: PathBuf,
+    pub label_b: String,
+    pub path_b: PathBuf,
+}
+
+impl JaccardPair {
+    fn new(a: &ManifestEntry, b: &ManifestEntry) -> Self {
+        Self {
+            label_a: a.label.clone(),
+            path_a: a.path.clone(),
+            label_b: b.label.clone(),
+            path_b: b.path.clone(),
+        }
+    }
+
+    /// Every unordered pair of manifest inputs, in manifest order.
+    pub fn all_pairs(manifest: &Manifest) -> Vec<Self> {
+        let entries = &manifest.entries;
+        entries
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| entries[i + 1..].iter().map(move |b| Self::new(a, b)))
+            .collect()
+    }
+
+    /// Every other manifest input against the one labelled `label`.
+    pub fn against(manifest: &Manifest, label: &str) -> Result<Vec<Self>, BedError> {
+        let reference = manifest
+            .entries
+            .iter()
+            .find(|e| e.label == label)
+            .ok_or_else(|| {
+                BedError::InvalidFormat(format!("label '{}' is not in the manifest", label))
+            })?;
+        Ok(manifest
+            .entries
+            .iter()
+            .filter(|e| e.label != label)
+            .map(|e| Self::new(e, reference))
+            .collect())
+    }
+
+    /// Pairs listed as `label_a<TAB>label_b` lines of manifest labels.
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn from_reader<R: BufRead>(reader: R, manifest: &Manifest) -> Result<Vec<Self>, BedError> {
+        let find = |label: &str, line: usize| {
+            manifest
+                .entries
+                .iter()
+                .find(|e| e.label == label)
+                .ok_or_else(|| BedError::Parse {
+                    line,
+                    message: format!("label '{}' is not in the manifest", label),
+                })
+        };
+
+        let mut pairs = Vec::new();
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [a, b] = fields.as_slice() else {
+                return Err(BedError::Parse {
+                    line: line_num + 1,
+                    message: format!(
+                        "expected 'label_a<TAB>label_b', found {} columns",
+                        fields.len()
+                    ),
+                });
+            };
+            pairs.push(Self::new(find(a, line_num + 1)?, find(b, line_num + 1)?));
+        }
+        Ok(pairs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -522,4 +731,87 @@ mod tests {
         assert_eq!(parts[1], "200"); // union = 100 + 100
         assert_eq!(parts[3], "0"); // n_intersections
     }
+
+    #[test]
+    fn test_jaccard_extended() {
+        // A covers 200bp (overlapping A intervals merge), B covers 100bp
+        let a_file = create_temp_bed("chr1\t100\t200\nchr1\t150\t300\n");
+        let b_file = create_temp_bed("chr1\t250\t350\n");
+
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+
+        let cmd = JaccardCommand::new()
+            .with_extended(true)
+            .with_genome(&genome);
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(
+            lines[0],
+            "intersection\tunion\tjaccard\tn_intersections\tbases_a\tbases_b\toverlap_coef\tdice\tgenome_frac_a\tgenome_frac_b\tgenome_frac_union"
+        );
+
+        let parts: Vec<&str> = lines[1].split('\t').collect();
+        assert_eq!(parts[0], "50"); // intersection
+        assert_eq!(parts[1], "250"); // union
+        assert_eq!(parts[4], "200"); // bases_a
+        assert_eq!(parts[5], "100"); // bases_b
+        assert_eq!(parts[6], "0.5"); // 50 / min(200, 100)
+        assert_eq!(parts[7], "0.333333"); // 2 * 50 / 300
+        assert_eq!(parts[8], "0.2");
+        assert_eq!(parts[9], "0.1");
+        assert_eq!(parts[10], "0.25");
+    }
+
+    #[test]
+    fn test_jaccard_batch() {
+        let r1 = create_temp_bed("chr1\t100\t200\nchr1\t300\t400\n");
+        let r2 = create_temp_bed("chr1\t150\t350\n");
+        let r3 = create_temp_bed("chr1\t0\t1000\n");
+        let manifest = format!(
+            "rep1\t{}\nrep2\t{}\nrep3\t{}\n",
+            r1.path().display(),
+            r2.path().display(),
+            r3.path().display()
+        );
+        let manifest = Manifest::from_reader(manifest.as_bytes(), None).unwrap();
+
+        let labels = |pairs: &[JaccardPair]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|p| (p.label_a.clone(), p.label_b.clone()))
+                .collect()
+        };
+        let all = JaccardPair::all_pairs(&manifest);
+        assert_eq!(labels(&all).len(), 3);
+        let against = JaccardPair::against(&manifest, "rep1").unwrap();
+        assert_eq!(
+            labels(&against),
+            [
+                ("rep2".into(), "rep1".into()),
+                ("rep3".into(), "rep1".into())
+            ]
+        );
+        assert!(JaccardPair::against(&manifest, "rep9").is_err());
+        let listed = JaccardPair::from_reader("# a\tb\nrep3\trep2\n".as_bytes(), &manifest);
+        assert_eq!(labels(&listed.unwrap()), [("rep3".into(), "rep2".into())]);
+        let unknown = JaccardPair::from_reader("rep1\trep9\n".as_bytes(), &manifest);
+        assert!(matches!(unknown, Err(BedError::Parse { line: 1, .. })));
+
+        let mut output = Vec::new();
+        JaccardCommand::new()
+            .run_batch(&all[..1], &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "label_a\tlabel_b\tstatistic\tvalue\n\
+             rep1\trep2\tintersection\t100\n\
+             rep1\trep2\tunion\t300\n\
+             rep1\trep2\tjaccard\t0.333333\n\
+             rep1\trep2\tn_intersections\t2\n"
+        );
+    }
 }
@@ -0,0 +1,289 @@
+//! Join command implementation.
+//!
+//! Joins a BED file (left) with a tab-delimited table (right) on equal key
+//! columns, e.g. a gene ID in the BED name column against the first column of
+//! an expression table. The right table is hashed in memory and the left file
+//! is streamed, so neither side has to be sorted by the key and the output
+//! keeps the left file's order and coordinates.
+
+use crate::bed::BedError;
+use crate::interchange::stdin_reader;
+use crate::output_limit::limited_stdout;
+use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line, trim_newline};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Which left records are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinType {
+    /// Only left records with at least one matching right row.
+    #[default]
+    Inner,
+    /// Every left record; unmatched ones get fill values.
+    Left,
+}
+
+impl JoinType {
+    /// Parse a join type as accepted by `--type`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "inner" => Some(Self::Inner),
+            "left" => Some(Self::Left),
+            _ => None,
+        }
+    }
+}
+
+/// Join command configuration.
+#[derive(Debug, Clone)]
+pub struct JoinCommand {
+    /// 1-based key column in the left BED file.
+    pub key_a: usize,
+    /// 1-based key column in the right table.
+    pub key_b: usize,
+    /// Inner or left join.
+    pub join_type: JoinType,
+    /// Value written for each right column when a left record has no match.
+    pub fill: String,
+}
+
+impl Default for JoinCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JoinCommand {
+    pub fn new() -> Self {
+        Self {
+            key_a: 4,
+            key_b: 1,
+            join_type: JoinType::Inner,
+            fill: ".".to_string(),
+        }
+    }
+
+    /// Set the left and right key columns (1-based).
+    pub fn with_keys(mut self, key_a: usize, key_b: usize) -> Self {
+        self.key_a = key_a;
+        self.key_b = key_b;
+        self
+    }
+
+    /// Set the join type.
+    pub fn with_join_type(mut self, join_type: JoinType) -> Self {
+        self.join_type = join_type;
+        self
+    }
+
+    /// Set the fill value for unmatched left records.
+    pub fn with_fill(mut self, fill: impl Into<String>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    /// Join two files.
+    pub fn run<P: AsRef<Path>, Q: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b_path: Q,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let left = File::open(a_path)?;
+        let right = File::open(b_path)?;
+        self.join_readers(left, right, output)
+    }
+
+    /// Join stdin (left) with a right table file.
+    pub fn run_stdio<Q: AsRef<Path>>(&self, b_path: Q) -> Result<(), BedError> {
+        let right = File::open(b_path)?;
+        let mut handle = limited_stdout();
+        self.join_readers(stdin_reader(), right, &mut handle)
+    }
+
+    /// Join a left BED stream with a right table.
+    ///
+    /// Each output line is the left line followed by the right row without its
+    /// key column. A left record matching several right rows is written once
+    /// per row, in right-file order.
+    pub fn join_readers<R: Read, S: Read, W: Write>(
+        &self,
+        left: R,
+        right: S,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        if self.key_a == 0 || self.key_b == 0 {
+            return Err(BedError::InvalidFormat(
+                "join key columns are 1-based".to_string(),
+            ));
+        }
+
+        let table = RightTable::read(right, self.key_b - 1)?;
+        let fill_row = vec![self.fill.as_str(); table.width].join("\t");
+
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        let mut reader = BufReader::with_capacity(256 * 1024, left);
+        let mut line = Vec::with_capacity(1024);
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            let record = trim_newline(&line);
+            if should_skip_line(record) {
+                continue;
+            }
+
+            if parse_bed3_bytes(record).is_none() {
+                return Err(BedError::Parse {
+                    line: line_num,
+                    message: "expected chrom, start and end columns".to_string(),
+                });
+            }
+            let key = field(record, self.key_a - 1).ok_or_else(|| BedError::Parse {
+                line: line_num,
+                message: format!("missing join key column {}", self.key_a),
+            })?;
+
+            match table.rows.get(key) {
+                Some(rows) => {
+                    for row in rows {
+                        out.write_all(record)?;
+                        if !row.is_empty() {
+                            out.write_all(b"\t")?;
+                            out.write_all(row)?;
+                        }
+                        out.write_all(b"\n")?;
+                    }
+                }
+                None if self.join_type == JoinType::Left => {
+                    out.write_all(record)?;
+                    if table.width > 0 {
+                        out.write_all(b"\t")?;
+                        out.write_all(fill_row.as_bytes())?;
+                    }
+                    out.write_all(b"\n")?;
+                }
+                None => {}
+            }
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Right-hand rows keyed by the join column, with the key column removed.
+struct RightTable {
+    rows: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    /// Number of non-key columns, used for fill values.
+    width: usize,
+}
+
+impl RightTable {
+    fn read<S: Read>(right: S, key_col: usize) -> Result<Self, BedError> {
+        let mut rows: HashMap<Vec<u8>, Vec<Vec<u8>>> = HashMap::new();
+        let mut width = 0;
+        let mut reader = BufReader::new(right);
+        let mut line = Vec::new();
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            let row = trim_newline(&line);
+            if row.is_empty() || row[0] == b'#' {
+                continue;
+            }
+
+            let fields: Vec<&[u8]> = row.split(|&b| b == b'\t').collect();
+            if key_col >= fields.len() {
+                return Err(BedError::Parse {
+                    line: line_num,
+                    message: format!("missing join key column {} in right file", key_col + 1),
+                });
+            }
+            let rest: Vec<&[u8]> = fields
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != key_col)
+                .map(|(_, f)| *f)
+                .collect();
+            width = width.max(rest.len());
+            rows.entry(fields[key_col].to_vec())
+                .or_default()
+                .push(rest.join(&b'\t'));
+        }
+
+        Ok(Self { rows, width })
+    }
+}
+
+/// 0-based tab-delimited field of a line.
+#[inline]
+fn field(line: &[u8], index: usize) -> Option<&[u8]> {
+    line.split(|&b| b == b'\t').nth(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENES: &str = "chr1\t100\t200\tGENE1\t0\t+\n\
+                         chr1\t300\t400\tGENE2\t0\t-\n\
+                         chr2\t50\t80\tGENE3\t0\t+\n";
+    const TABLE: &str = "#id\ttpm\tbiotype\n\
+                         GENE2\t7.5\tlncRNA\n\
+                         GENE1\t12.0\tprotein_coding\n\
+                         GENE1\t3.1\tprotein_coding\n";
+
+    fn join(cmd: &JoinCommand, left: &str, right: &str) -> String {
+        let mut output = Vec::new();
+        cmd.join_readers(left.as_bytes(), right.as_bytes(), &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_inner_join_keeps_left_order() {
+        let out = join(&JoinCommand::new(), GENES, TABLE);
+        assert_eq!(
+            out,
+            "chr1\t100\t200\tGENE1\t0\t+\t12.0\tprotein_coding\n\
+             chr1\t100\t200\tGENE1\t0\t+\t3.1\tprotein_coding\n\
+             chr1\t300\t400\tGENE2\t0\t-\t7.5\tlncRNA\n"
+        );
+    }
+
+    #[test]
+    fn test_left_join_fill() {
+        let cmd = JoinCommand::new()
+            .with_join_type(JoinType::Left)
+            .with_fill("NA");
+        let out = join(&cmd, "chr2\t50\t80\tGENE3\n", TABLE);
+        assert_eq!(out, "chr2\t50\t80\tGENE3\tNA\tNA\n");
+    }
+
+    #[test]
+    fn test_join_key_columns() {
+        let cmd = JoinCommand::new().with_keys(1, 2);
+        let out = join(&cmd, "chrM\t0\t10\n", "x\tchrM\n");
+        assert_eq!(out, "chrM\t0\t10\tx\n");
+
+        let mut output = Vec::new();
+        let err = JoinCommand::new().with_keys(5, 1).join_readers(
+            "chr1\t0\t10\tname\n".as_bytes(),
+            TABLE.as_bytes(),
+            &mut output,
+        );
+        assert!(matches!(err, Err(BedError::Parse { line: 1, .. })));
+    }
+}
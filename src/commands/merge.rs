@@ -3,9 +3,13 @@
 //! Uses O(n log n) sort + O(n) single-pass sweep-line merge.
 
 use crate::bed::{read_records, BedError, BedReader};
-use crate::interval::{BedRecord, Interval};
-use crate::parallel::{group_by_chromosome, parallel_sort_records, PARALLEL_THRESHOLD};
+use crate::config::display_score;
+use crate::interval::{BedRecord, Interval, Strand};
+use crate::parallel::{
+    group_refs_by_chromosome, parallel_sort_records, PoolHandle, PARALLEL_THRESHOLD,
+};
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{self, Write};
 use std::path::Path;
@@ -45,6 +49,269 @@ impl MergeOperation {
     }
 }
 
+/// Which interval length a relative merge distance is measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceBasis {
+    /// The shorter of the current merged span and the next interval
+    #[default]
+    Shorter,
+    /// The longer of the two
+    Longer,
+}
+
+impl DistanceBasis {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "shorter" => Some(Self::Shorter),
+            "longer" => Some(Self::Longer),
+            _ => None,
+        }
+    }
+}
+
+/// Merge distance given as a percentage of interval length.
+///
+/// Useful when features span orders of magnitude in size: a 10% gap
+/// tolerance joins exons a few bases apart but genes kilobases apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelativeDistance {
+    /// Allowed gap, in percent of the basis length
+    pub percent: f64,
+    pub basis: DistanceBasis,
+}
+
+impl RelativeDistance {
+    pub fn new(percent: f64, basis: DistanceBasis) -> Self {
+        Self { percent, basis }
+    }
+
+    /// Largest gap allowed between a span of `span_len` and a next interval of `next_len`.
+    #[inline]
+    pub fn max_gap(&self, span_len: u64, next_len: u64) -> u64 {
+        let len = match self.basis {
+            DistanceBasis::Shorter => span_len.min(next_len),
+            DistanceBasis::Longer => span_len.max(next_len),
+        };
+        (len as f64 * self.percent / 100.0) as u64
+    }
+}
+
+/// Gap allowed between the current span `[span_start, span_end)` and the
+/// next interval, using the relative distance when set.
+#[inline]
+pub(crate) fn allowed_gap(
+    distance: u64,
+    relative: Option<RelativeDistance>,
+    span_start: u64,
+    span_end: u64,
+    next_start: u64,
+    next_end: u64,
+) -> u64 {
+    match relative {
+        Some(rel) => rel.max_gap(
+            span_end.saturating_sub(span_start),
+            next_end.saturating_sub(next_start),
+        ),
+        None => distance,
+    }
+}
+
+/// Whether an interval starting at `next_start` joins a span ending at
+/// `span_end`, given the allowed `gap`.
+///
+/// An interval exactly `gap` bases away (book-ended at a gap of 0) joins,
+/// as in `bedtools merge`, unless `book_ended` is false.
+#[inline]
+pub(crate) fn within_reach(next_start: u64, span_end: u64, gap: u64, book_ended: bool) -> bool {
+    if book_ended {
+        next_start <= span_end + gap
+    } else {
+        next_start < span_end + gap
+    }
+}
+
+/// Identifier emitted for each merged interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeId {
+    /// `chrom_start_end`
+    Coords,
+    /// 16 hex digits of a 64-bit FNV-1a hash of the coordinates
+    Hash,
+}
+
+impl MergeId {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "coords" => Some(Self::Coords),
+            "hash" => Some(Self::Hash),
+            _ => None,
+        }
+    }
+
+    /// Identifier of a merged span.
+    ///
+    /// For strand-specific merges `strand` is `Some`, and the strand becomes
+    /// part of the identifier so that coinciding spans on opposite strands
+    /// stay distinct. Both forms depend only on the span, so they are stable
+    /// across runs and input orderings.
+    pub fn format(&self, chrom: &str, start: u64, end: u64, strand: Option<Strand>) -> String {
+        let coords = match strand {
+            Some(strand) => format!("{}_{}_{}_{}", chrom, start, end, strand),
+            None => format!("{}_{}_{}", chrom, start, end),
+        };
+        match self {
+            Self::Coords => coords,
+            Self::Hash => {
+                let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+                for &b in coords.as_bytes() {
+                    hash ^= b as u64;
+                    hash = hash.wrapping_mul(0x0100_0000_01b3);
+                }
+                format!("{:016x}", hash)
+            }
+        }
+    }
+}
+
+/// Append the optional ID and constituent-name columns of a merged span.
+///
+/// Names are joined with commas in input order; a span whose records have
+/// no name column gets `.`.
+pub(crate) fn write_merge_labels<W: Write, S: AsRef<str>>(
+    out: &mut W,
+    id: Option<MergeId>,
+    names: Option<&[S]>,
+    chrom: &str,
+    start: u64,
+    end: u64,
+    strand: Option<Strand>,
+) -> io::Result<()> {
+    if let Some(id) = id {
+        write!(out, "\t{}", id.format(chrom, start, end, strand))?;
+    }
+    if let Some(names) = names {
+        if names.is_empty() {
+            out.write_all(b"\t.")?;
+        }
+        for (i, name) in names.iter().enumerate() {
+            out.write_all(if i == 0 { b"\t" } else { b"," })?;
+            out.write_all(name.as_ref().as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Highest-scoring record of a merged span, for `--best-by`.
+///
+/// Records without a numeric score lose to any scored record; among equal
+/// scores the first record in input order is kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Representative {
+    best: Option<(u64, u64, f64)>,
+}
+
+impl Representative {
+    #[inline]
+    pub(crate) fn offer(&mut self, start: u64, end: u64, score: Option<f64>) {
+        let score = score.filter(|s| !s.is_nan()).unwrap_or(f64::NEG_INFINITY);
+        if self.best.is_none_or(|(_, _, best)| score > best) {
+            self.best = Some((start, end, score));
+        }
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.best = None;
+    }
+
+    /// Coordinates of the best record so far.
+    #[inline]
+    pub(crate) fn coords(&self) -> Option<(u64, u64)> {
+        self.best.map(|(start, end, _)| (start, end))
+    }
+}
+
+/// How `--scores` combines the scores of merged records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreOp {
+    Sum,
+    Mean,
+    Max,
+}
+
+impl ScoreOp {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sum" => Some(Self::Sum),
+            "mean" => Some(Self::Mean),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+}
+
+/// Running combination of the scores of a merged span, for `--scores`.
+///
+/// A record's score is its score column, or the value column of a 4-column
+/// BedGraph record. Records without a numeric score are left out; a span
+/// with none gets `.`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ScoreSummary {
+    sum: f64,
+    max: f64,
+    n: usize,
+}
+
+impl ScoreSummary {
+    /// Score of a record, if it has a numeric one.
+    #[inline]
+    pub(crate) fn score_of(record: &BedRecord) -> Option<f64> {
+        let bedgraph_value = || {
+            (record.strand.is_none() && record.extra_fields.is_empty())
+                .then(|| record.numeric_column(4))
+                .flatten()
+        };
+        record.score.or_else(bedgraph_value).filter(|s| !s.is_nan())
+    }
+
+    #[inline]
+    pub(crate) fn offer(&mut self, record: &BedRecord) {
+        self.add(Self::score_of(record));
+    }
+
+    #[inline]
+    pub(crate) fn add(&mut self, score: Option<f64>) {
+        let Some(score) = score else {
+            return;
+        };
+        self.max = if self.n == 0 {
+            score
+        } else {
+            self.max.max(score)
+        };
+        self.sum += score;
+        self.n += 1;
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Write the combined score as an extra column.
+    pub(crate) fn write<W: Write>(&self, out: &mut W, op: ScoreOp) -> io::Result<()> {
+        if self.n == 0 {
+            return out.write_all(b"\t.");
+        }
+        let value = match op {
+            ScoreOp::Sum => self.sum,
+            ScoreOp::Mean => self.sum / self.n as f64,
+            ScoreOp::Max => self.max,
+        };
+        write!(out, "\t{}", display_score(value))
+    }
+}
+
 /// Merge command configuration.
 #[derive(Debug, Clone)]
 pub struct MergeCommand {
@@ -58,6 +325,25 @@ pub struct MergeCommand {
     pub operations: Vec<MergeOperation>,
     /// Delimiter for collapsed values
     pub delimiter: String,
+    /// Report count of merged intervals
+    pub count: bool,
+    /// Only output merged intervals formed from at least this many records
+    pub min_count: usize,
+    /// Merge distance relative to interval length; overrides `distance`
+    pub relative_distance: Option<RelativeDistance>,
+    /// Merge intervals exactly `distance` apart (book-ended at distance 0)
+    pub book_ended: bool,
+    /// Append an identifier column to each merged interval
+    pub id: Option<MergeId>,
+    /// Append the comma-joined names of the merged records
+    pub report_names: bool,
+    /// Report the coordinates of the record with the highest value in this
+    /// 1-based column, followed by the merged span
+    pub best_by: Option<usize>,
+    /// Append the scores of the merged records combined with this operation
+    pub scores: Option<ScoreOp>,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
 }
 
 impl Default for MergeCommand {
@@ -74,6 +360,15 @@ impl MergeCommand {
             columns: Vec::new(),
             operations: Vec::new(),
             delimiter: ",".to_string(),
+            count: false,
+            min_count: 1,
+            relative_distance: None,
+            book_ended: true,
+            id: None,
+            report_names: false,
+            best_by: None,
+            scores: None,
+            pool: PoolHandle::global(),
         }
     }
 
@@ -89,58 +384,157 @@ impl MergeCommand {
         self
     }
 
+    /// Report the number of records in each merged interval.
+    pub fn with_count(mut self, count: bool) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Drop merged intervals formed from fewer than `n` records.
+    pub fn with_min_count(mut self, n: usize) -> Self {
+        self.min_count = n;
+        self
+    }
+
+    /// Measure the merge distance relative to interval length.
+    pub fn with_relative_distance(mut self, relative: Option<RelativeDistance>) -> Self {
+        self.relative_distance = relative;
+        self
+    }
+
+    /// Set whether intervals exactly the merge distance apart are merged.
+    pub fn with_book_ended(mut self, book_ended: bool) -> Self {
+        self.book_ended = book_ended;
+        self
+    }
+
+    /// Append an identifier column to each merged interval.
+    pub fn with_id(mut self, id: Option<MergeId>) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Append the names of the records making up each merged interval.
+    pub fn with_names(mut self, report_names: bool) -> Self {
+        self.report_names = report_names;
+        self
+    }
+
+    /// Represent each merged interval by its highest-scoring record.
+    ///
+    /// The record with the largest value in 1-based column `col` (5 for the
+    /// score) gives the first three columns; the merged span follows as two
+    /// extra columns after the other optional columns.
+    pub fn with_best_by(mut self, col: Option<usize>) -> Self {
+        self.best_by = col;
+        self
+    }
+
+    /// Append the scores of each merged interval's records, combined with
+    /// `op`, after the count column.
+    ///
+    /// On 4-column BedGraph input with no other optional columns the output
+    /// is again a BedGraph.
+    pub fn with_scores(mut self, op: Option<ScoreOp>) -> Self {
+        self.scores = op;
+        self
+    }
+
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Whether the next interval joins the current span.
+    #[inline]
+    fn reaches(&self, span_start: u64, span_end: u64, next_start: u64, next_end: u64) -> bool {
+        let gap = allowed_gap(
+            self.distance,
+            self.relative_distance,
+            span_start,
+            span_end,
+            next_start,
+            next_end,
+        );
+        within_reach(next_start, span_end, gap, self.book_ended)
+    }
+
     /// Merge intervals, returning merged intervals.
-    pub fn merge(&self, intervals: Vec<Interval>) -> Vec<Interval> {
+    ///
+    /// Accepts an owned `Vec` or a borrowed slice; the input is never copied,
+    /// only references to it are grouped and sorted.
+    pub fn merge<'a>(&self, intervals: impl Into<Cow<'a, [Interval]>>) -> Vec<Interval> {
+        let intervals = intervals.into();
         if intervals.is_empty() {
             return Vec::new();
         }
 
-        // Group by chromosome
-        let groups = group_by_chromosome(intervals);
+        // Group by chromosome, in output order
+        let mut groups: Vec<(&str, Vec<&Interval>)> =
+            group_refs_by_chromosome(&intervals).into_iter().collect();
+        groups.sort_unstable_by_key(|(chrom, _)| *chrom);
 
         // Merge each chromosome in parallel
-        let merged: Vec<Vec<Interval>> = groups
-            .into_par_iter()
-            .map(|(_, mut intervals)| {
-                intervals.sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
-                self.merge_sorted(&intervals)
-            })
-            .collect();
-
-        // Flatten and sort results
-        let mut result: Vec<Interval> = merged.into_iter().flatten().collect();
-        result.sort();
-        result
+        let merged: Vec<Vec<Interval>> = self.pool.install(|| {
+            groups
+                .into_par_iter()
+                .map(|(_, mut intervals)| {
+                    intervals.sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
+                    self.merge_sorted_iter(intervals)
+                })
+                .collect()
+        });
+
+        merged.into_iter().flatten().collect()
     }
 
-    /// Merge already-sorted intervals for a single chromosome.
-    fn merge_sorted(&self, intervals: &[Interval]) -> Vec<Interval> {
-        if intervals.is_empty() {
+    /// Merge already-sorted intervals without copying the input.
+    ///
+    /// Intervals must be sorted by chromosome, then start (the `Ord` order of
+    /// `Interval`); the output is sorted the same way.
+    pub fn merge_sorted(&self, intervals: &[Interval]) -> Vec<Interval> {
+        self.merge_sorted_iter(intervals)
+    }
+
+    fn merge_sorted_iter<'a>(
+        &self,
+        intervals: impl IntoIterator<Item = &'a Interval>,
+    ) -> Vec<Interval> {
+        let mut intervals = intervals.into_iter();
+        let Some(first) = intervals.next() else {
             return Vec::new();
-        }
+        };
 
         let mut result = Vec::new();
-        let mut current = intervals[0].clone();
+        let mut current = first.clone();
+        let mut count = 1;
 
-        for interval in intervals.iter().skip(1) {
+        for interval in intervals {
             // Check if intervals should be merged
             if self.should_merge(&current, interval) {
                 // Extend current interval
                 current.end = current.end.max(interval.end);
+                count += 1;
             } else {
-                result.push(current);
+                if count >= self.min_count {
+                    result.push(current);
+                }
                 current = interval.clone();
+                count = 1;
             }
         }
 
-        result.push(current);
+        if count >= self.min_count {
+            result.push(current);
+        }
         result
     }
 
     /// Check if two intervals should be merged.
     #[inline]
     fn should_merge(&self, a: &Interval, b: &Interval) -> bool {
-        a.chrom == b.chrom && b.start <= a.end + self.distance
+        a.chrom == b.chrom && self.reaches(a.start, a.end, b.start, b.end)
     }
 
     /// Merge BED records, preserving and combining extra fields.
@@ -165,7 +559,7 @@ impl MergeCommand {
             });
             recs
         } else {
-            parallel_sort_records(records)
+            self.pool.install(|| parallel_sort_records(records))
         };
 
         // Group and merge
@@ -173,6 +567,7 @@ impl MergeCommand {
         let first_record = records.remove(0);
         let mut current_chrom = first_record.chrom().to_string();
         let mut current_strand = first_record.strand;
+        let mut current_start = first_record.start();
         let mut current_end = first_record.end();
         let mut current_group: Vec<BedRecord> = vec![first_record];
 
@@ -185,7 +580,7 @@ impl MergeCommand {
             // Check if this record should be merged with current group
             let same_chrom = rec_chrom == current_chrom;
             let same_strand = !self.strand_specific || rec_strand == current_strand;
-            let overlaps = rec_start <= current_end + self.distance;
+            let overlaps = self.reaches(current_start, current_end, rec_start, rec_end);
 
             if same_chrom && same_strand && overlaps {
                 // Extend the current group's end position
@@ -196,6 +591,7 @@ impl MergeCommand {
                 result.push(self.emit_merged_record(&current_group));
                 current_chrom = rec_chrom.to_string();
                 current_strand = rec_strand;
+                current_start = rec_start;
                 current_end = rec_end;
                 current_group = vec![record];
             }
@@ -205,6 +601,9 @@ impl MergeCommand {
             result.push(self.emit_merged_record(&current_group));
         }
 
+        if self.min_count > 1 {
+            result.retain(|m| m.count >= self.min_count);
+        }
         result
     }
 
@@ -238,7 +637,9 @@ impl MergeCommand {
         }
 
         // Group by chromosome
-        let grouped = Self::group_records_by_chrom_owned(records, self.strand_specific);
+        let grouped = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(records, self.strand_specific));
 
         // Get sorted chromosome list for deterministic output
         let mut chroms: Vec<_> = grouped.keys().cloned().collect();
@@ -258,16 +659,18 @@ impl MergeCommand {
             }
         } else {
             // Parallel processing for large datasets
-            let results: Vec<Vec<u8>> = chroms
-                .par_iter()
-                .map(|chrom| {
-                    let mut buf = Vec::with_capacity(32 * 1024);
-                    if let Some(records) = grouped.get(chrom) {
-                        self.merge_chromosome_sweepline(records, &mut buf);
-                    }
-                    buf
-                })
-                .collect();
+            let results: Vec<Vec<u8>> = self.pool.install(|| {
+                chroms
+                    .par_iter()
+                    .map(|chrom| {
+                        let mut buf = Vec::with_capacity(32 * 1024);
+                        if let Some(records) = grouped.get(chrom) {
+                            self.merge_chromosome_sweepline(records, &mut buf);
+                        }
+                        buf
+                    })
+                    .collect()
+            });
 
             // Write results in chromosome order
             for buf in results {
@@ -293,42 +696,133 @@ impl MergeCommand {
         let mut current_start: u64 = records[0].start();
         let mut current_end: u64 = records[0].end();
         let mut current_strand = records[0].strand;
+        let mut current_count: usize = 1;
+        let mut current_names: Vec<&str> = Vec::new();
+        let mut best = Representative::default();
+        let mut scores = ScoreSummary::default();
+        self.push_name(&mut current_names, &records[0]);
+        self.offer(&mut best, &mut scores, &records[0]);
 
         for rec in &records[1..] {
             let rec_start = rec.start();
             let rec_end = rec.end();
 
             // Check if should merge with current span
+            let reaches = self.reaches(current_start, current_end, rec_start, rec_end);
             let should_merge = if self.strand_specific {
-                rec.strand == current_strand && rec_start <= current_end + self.distance
+                rec.strand == current_strand && reaches
             } else {
-                rec_start <= current_end + self.distance
+                reaches
             };
 
             if should_merge {
                 // Extend current span
                 current_end = current_end.max(rec_end);
+                current_count += 1;
             } else {
                 // Output current span
-                self.write_interval_to_buf(output, current_chrom, current_start, current_end);
+                self.write_interval_to_buf(
+                    output,
+                    current_chrom,
+                    current_start,
+                    current_end,
+                    current_count,
+                    current_strand,
+                    &current_names,
+                    best.coords(),
+                    &scores,
+                );
 
                 // Start new span
                 current_chrom = rec.chrom();
                 current_start = rec_start;
                 current_end = rec_end;
                 current_strand = rec.strand;
+                current_count = 1;
+                current_names.clear();
+                best.clear();
+                scores.clear();
             }
+            self.push_name(&mut current_names, rec);
+            self.offer(&mut best, &mut scores, rec);
         }
 
         // Output final span
-        self.write_interval_to_buf(output, current_chrom, current_start, current_end);
+        self.write_interval_to_buf(
+            output,
+            current_chrom,
+            current_start,
+            current_end,
+            current_count,
+            current_strand,
+            &current_names,
+            best.coords(),
+            &scores,
+        );
+    }
+
+    /// Collect a record's name for `--names` output.
+    #[inline]
+    fn push_name<'a>(&self, names: &mut Vec<&'a str>, record: &'a BedRecord) {
+        if self.report_names {
+            if let Some(name) = &record.name {
+                names.push(name);
+            }
+        }
+    }
+
+    /// Offer a record as the representative for `--best-by` and its score
+    /// for `--scores`.
+    #[inline]
+    fn offer(&self, best: &mut Representative, scores: &mut ScoreSummary, record: &BedRecord) {
+        if let Some(col) = self.best_by {
+            best.offer(record.start(), record.end(), record.numeric_column(col));
+        }
+        if self.scores.is_some() {
+            scores.offer(record);
+        }
     }
 
     /// Write interval directly to buffer (zero allocation).
+    ///
+    /// Spans with fewer than `min_count` records are skipped. With
+    /// `representative`, its coordinates replace the span's, which follows
+    /// in two extra columns.
     #[inline]
-    fn write_interval_to_buf(&self, buf: &mut Vec<u8>, chrom: &str, start: u64, end: u64) {
+    fn write_interval_to_buf<S: AsRef<str>>(
+        &self,
+        buf: &mut Vec<u8>,
+        chrom: &str,
+        start: u64,
+        end: u64,
+        count: usize,
+        strand: Option<Strand>,
+        names: &[S],
+        representative: Option<(u64, u64)>,
+        scores: &ScoreSummary,
+    ) {
         use std::io::Write as IoWrite;
-        let _ = writeln!(buf, "{}\t{}\t{}", chrom, start, end);
+        if count < self.min_count {
+            return;
+        }
+        let (rep_start, rep_end) = representative.unwrap_or((start, end));
+        if self.count {
+            let _ = write!(buf, "{}\t{}\t{}\t{}", chrom, rep_start, rep_end, count);
+        } else {
+            let _ = write!(buf, "{}\t{}\t{}", chrom, rep_start, rep_end);
+        }
+        if let Some(op) = self.scores {
+            let _ = scores.write(buf, op);
+        }
+        let strand = self
+            .strand_specific
+            .then(|| strand.unwrap_or(Strand::Unknown));
+        let names = self.report_names.then_some(names);
+        let _ = write_merge_labels(buf, self.id, names, chrom, start, end, strand);
+        if representative.is_some() {
+            let _ = write!(buf, "\t{}\t{}", start, end);
+        }
+        buf.push(b'\n');
     }
 
     /// Group records by chromosome (and strand if strand-specific), returning sorted records.
@@ -367,25 +861,69 @@ impl MergeCommand {
         output: &mut W,
     ) -> Result<(), BedError> {
         let mut current: Option<Interval> = None;
+        let mut count: usize = 0;
+        let mut names: Vec<String> = Vec::new();
+        let mut best = Representative::default();
+        let mut scores = ScoreSummary::default();
+        let mut buf = Vec::with_capacity(256);
 
         for result in reader.records() {
             let record = result?;
+            let score = self.best_by.and_then(|col| record.numeric_column(col));
+            let record_score = self.scores.and_then(|_| ScoreSummary::score_of(&record));
+            let name = record.name.filter(|_| self.report_names);
             let interval = record.interval;
+            let (start, end) = (interval.start, interval.end);
 
             if let Some(ref mut curr) = current {
-                if curr.chrom == interval.chrom && interval.start <= curr.end + self.distance {
+                if self.should_merge(curr, &interval) {
                     curr.end = curr.end.max(interval.end);
+                    count += 1;
                 } else {
-                    writeln!(output, "{}", curr).map_err(BedError::Io)?;
+                    buf.clear();
+                    self.write_interval_to_buf(
+                        &mut buf,
+                        &curr.chrom,
+                        curr.start,
+                        curr.end,
+                        count,
+                        None,
+                        &names,
+                        best.coords(),
+                        &scores,
+                    );
+                    output.write_all(&buf).map_err(BedError::Io)?;
                     *curr = interval;
+                    count = 1;
+                    names.clear();
+                    best.clear();
+                    scores.clear();
                 }
             } else {
                 current = Some(interval);
+                count = 1;
+            }
+            names.extend(name);
+            if self.best_by.is_some() {
+                best.offer(start, end, score);
             }
+            scores.add(record_score);
         }
 
         if let Some(curr) = current {
-            writeln!(output, "{}", curr).map_err(BedError::Io)?;
+            buf.clear();
+            self.write_interval_to_buf(
+                &mut buf,
+                &curr.chrom,
+                curr.start,
+                curr.end,
+                count,
+                None,
+                &names,
+                best.coords(),
+                &scores,
+            );
+            output.write_all(&buf).map_err(BedError::Io)?;
         }
 
         Ok(())
@@ -432,6 +970,41 @@ mod tests {
         assert_eq!(merged[1].end, 400);
     }
 
+    #[test]
+    fn test_merge_book_ended() {
+        let intervals = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 200, 300), // Book-ended
+            Interval::new("chr1", 350, 400), // Gap of 50
+        ];
+
+        let merged = MergeCommand::new().merge(intervals.clone());
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].start, merged[0].end), (100, 300));
+
+        let cmd = MergeCommand::new().with_book_ended(false);
+        assert_eq!(cmd.merge(intervals.clone()).len(), 3);
+
+        // With a distance, only gaps strictly below it merge
+        let cmd = MergeCommand::new().with_distance(50).with_book_ended(false);
+        let merged = cmd.merge(intervals.clone());
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].start, merged[0].end), (100, 300));
+        let cmd = MergeCommand::new().with_distance(51).with_book_ended(false);
+        assert_eq!(cmd.merge(intervals).len(), 1);
+
+        let content = "chr1\t100\t200\nchr1\t200\t300\n";
+        let mut output = Vec::new();
+        MergeCommand::new()
+            .with_book_ended(false)
+            .merge_streaming(BedReader::new(content.as_bytes()), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\nchr1\t200\t300\n"
+        );
+    }
+
     #[test]
     fn test_merge_with_distance() {
         let cmd = MergeCommand::new().with_distance(50);
@@ -490,6 +1063,40 @@ mod tests {
         assert_eq!(merged[0].end, 400);
     }
 
+    #[test]
+    fn test_merge_sorted_across_chroms() {
+        let cmd = MergeCommand::new();
+        let intervals = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 150, 250),
+            Interval::new("chr2", 200, 300),
+            Interval::new("chr2", 300, 400),
+        ];
+
+        let merged = cmd.merge_sorted(&intervals);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0], Interval::new("chr1", 100, 250));
+        assert_eq!(merged[1], Interval::new("chr2", 200, 400));
+    }
+
+    #[test]
+    fn test_merge_borrowed() {
+        let cmd = MergeCommand::new();
+        let intervals = vec![
+            Interval::new("chr2", 100, 200),
+            Interval::new("chr1", 150, 250),
+            Interval::new("chr1", 100, 200),
+        ];
+
+        let merged = cmd.merge(intervals.as_slice());
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0], Interval::new("chr1", 100, 250));
+        assert_eq!(merged[1], Interval::new("chr2", 100, 200));
+        assert_eq!(intervals.len(), 3);
+    }
+
     #[test]
     fn test_merge_unsorted_input() {
         let cmd = MergeCommand::new();
@@ -505,4 +1112,148 @@ mod tests {
         assert_eq!(merged[0].start, 100);
         assert_eq!(merged[0].end, 250);
     }
+
+    #[test]
+    fn test_merge_min_count() {
+        let cmd = MergeCommand::new().with_min_count(2);
+        let intervals = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 150, 250),
+            Interval::new("chr1", 400, 500),
+            Interval::new("chr2", 100, 200),
+        ];
+
+        let merged = cmd.merge(intervals);
+
+        assert_eq!(merged, vec![Interval::new("chr1", 100, 250)]);
+    }
+
+    #[test]
+    fn test_merge_relative_distance() {
+        let rel = RelativeDistance::new(10.0, DistanceBasis::Shorter);
+        assert_eq!(rel.max_gap(1000, 50), 5);
+        assert_eq!(
+            RelativeDistance::new(10.0, DistanceBasis::Longer).max_gap(1000, 50),
+            100
+        );
+
+        // Gap of 20 between 100bp exons exceeds 10bp; gap of 2000 between
+        // 40kb genes is within 4kb
+        let cmd = MergeCommand::new().with_relative_distance(Some(rel));
+        let intervals = vec![
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 220, 320),
+            Interval::new("chr1", 10_000, 50_000),
+            Interval::new("chr1", 52_000, 92_000),
+        ];
+
+        let merged = cmd.merge(intervals);
+
+        assert_eq!(
+            merged,
+            vec![
+                Interval::new("chr1", 100, 200),
+                Interval::new("chr1", 220, 320),
+                Interval::new("chr1", 10_000, 92_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_id_format() {
+        assert_eq!(MergeId::Coords.format("chr1", 5, 9, None), "chr1_5_9");
+        assert_eq!(
+            MergeId::Coords.format("chr1", 5, 9, Some(Strand::Minus)),
+            "chr1_5_9_-"
+        );
+        let hash = MergeId::Hash.format("chr1", 5, 9, None);
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, MergeId::Hash.format("chr1", 5, 9, None));
+        assert_ne!(hash, MergeId::Hash.format("chr1", 5, 9, Some(Strand::Plus)));
+    }
+
+    #[test]
+    fn test_merge_streaming_id_and_names() {
+        let content = "chr1\t100\t200\ta\nchr1\t150\t250\tb\nchr1\t300\t400\nchr2\t0\t10\tc\n";
+        let cmd = MergeCommand::new()
+            .with_id(Some(MergeId::Coords))
+            .with_names(true);
+        let mut output = Vec::new();
+        cmd.merge_streaming(BedReader::new(content.as_bytes()), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t250\tchr1_100_250\ta,b\nchr1\t300\t400\tchr1_300_400\t.\nchr2\t0\t10\tchr2_0_10\tc\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_best_by() {
+        let records = "chr1\t100\t200\ta\t5\t+\t0\t0\t0\t1\t100,\t0,\t0.5\n\
+                       chr1\t150\t300\tb\t9\t+\t0\t0\t0\t1\t150,\t0,\t0.9\n\
+                       chr1\t250\t400\tc\t2\t+\t0\t0\t0\t1\t150,\t0,\t2.5\n\
+                       chr2\t0\t10\td\t.\n\
+                       chr2\t5\t20\te\t.\n";
+        let run = |cmd: &MergeCommand| {
+            let mut output = Vec::new();
+            cmd.merge_streaming(BedReader::new(records.as_bytes()), &mut output)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let cmd = MergeCommand::new().with_count(true).with_best_by(Some(5));
+        assert_eq!(
+            run(&cmd),
+            "chr1\t150\t300\t3\t100\t400\nchr2\t0\t10\t2\t0\t20\n"
+        );
+        // Extra column, and the in-memory sweep agrees
+        let cmd = MergeCommand::new().with_best_by(Some(13));
+        assert_eq!(run(&cmd), "chr1\t250\t400\t100\t400\nchr2\t0\t10\t0\t20\n");
+        let records = BedReader::new(records.as_bytes())
+            .records()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut buf = Vec::new();
+        cmd.merge_chromosome_sweepline(&records[..3], &mut buf);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "chr1\t250\t400\t100\t400\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_scores() {
+        let records =
+            "chr1\t0\t10\t2\nchr1\t10\t20\t4.5\nchr1\t30\t40\tx\t.\nchr2\t0\t5\tp\t7\t+\n";
+        let expected = "chr1\t0\t20\t2\t6.5\nchr1\t30\t40\t1\t.\nchr2\t0\t5\t1\t7\n";
+        let cmd = MergeCommand::new()
+            .with_count(true)
+            .with_scores(Some(ScoreOp::Sum));
+
+        let mut output = Vec::new();
+        cmd.merge_streaming(BedReader::new(records.as_bytes()), &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        let parsed = BedReader::new(records.as_bytes())
+            .records()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut buf = Vec::new();
+        cmd.merge_chromosome_sweepline(&parsed[..3], &mut buf);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "chr1\t0\t20\t2\t6.5\nchr1\t30\t40\t1\t.\n"
+        );
+
+        let mut output = Vec::new();
+        crate::commands::StreamingMergeCommand::new()
+            .with_scores(Some(ScoreOp::Max))
+            .run_streaming(BedReader::new(records.as_bytes()), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t0\t20\t4.5\nchr1\t30\t40\t.\nchr2\t0\t5\t7\n"
+        );
+    }
 }
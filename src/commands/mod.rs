@@ -2,18 +2,29 @@
 
 pub mod closest;
 pub mod complement;
+pub mod convert;
+pub mod covariates;
 pub mod coverage;
+pub mod cut;
+pub mod delta;
+pub mod enrich;
 pub mod fast_merge;
 pub mod fast_sort;
+pub mod fix_strand;
 pub mod generate;
 pub mod genomecov;
+pub mod indexed_coverage;
 pub mod intersect;
 pub mod intersect_engine;
 pub mod jaccard;
+pub mod join;
 pub mod merge;
+pub mod modify;
 pub mod multiinter;
+pub mod shuffle;
 pub mod slop;
 pub mod sort;
+pub mod stab;
 pub mod streaming_closest;
 pub mod streaming_coverage;
 pub mod streaming_genomecov;
@@ -26,26 +37,40 @@ pub mod subtract;
 pub mod window;
 
 pub use crate::streaming::{
-    verify_sorted, verify_sorted_reader, verify_sorted_with_genome, GenomeOrderValidator,
+    verify_sorted, verify_sorted_bytes, verify_sorted_reader, verify_sorted_with_genome,
+    GenomeOrderValidator,
 };
 pub use closest::ClosestCommand;
 pub use complement::ComplementCommand;
+pub use convert::{ConvertCommand, ConvertFormat};
+pub use covariates::CovariatesCommand;
 pub use coverage::CoverageCommand;
+pub use cut::{CutColumn, CutCommand, CutFormat};
+pub use delta::DeltaCommand;
+pub use enrich::{EnrichCommand, EnrichMetric, EnrichResult};
 pub use fast_merge::{FastMergeCommand, FastMergeStats};
 pub use fast_sort::{FastSortCommand, FastSortStats};
+pub use fix_strand::{normalize_strand, FixStrandCommand, FixStrandStats};
 pub use generate::{
     GenerateCommand, GenerateConfig, GenerateMode, GenerateStats, SizeSpec, SortMode,
 };
-pub use genomecov::{GenomecovCommand, OutputMode as GenomecovOutputMode};
+pub use genomecov::{
+    DepthNormalization, DepthTransform, GenomecovCommand, OutputMode as GenomecovOutputMode,
+};
+pub use indexed_coverage::IndexedCoverageCommand;
 pub use intersect::IntersectCommand;
 pub use intersect_engine::{ExecutionMode, IntersectConfig, IntersectEngine, IntersectStats};
-pub use jaccard::JaccardCommand;
-pub use merge::MergeCommand;
-pub use multiinter::MultiinterCommand;
+pub use jaccard::{JaccardCommand, JaccardPair, JaccardStats};
+pub use join::{JoinCommand, JoinType};
+pub use merge::{DistanceBasis, MergeCommand, MergeId, RelativeDistance, ScoreOp};
+pub use modify::{Anchor, ModifyCommand};
+pub use multiinter::{MultiinterCommand, SparseMultiinterReader, SparseRegion, SPARSE_HEADER};
+pub use shuffle::{Background as ShuffleBackground, ShuffleCommand};
 pub use slop::SlopCommand;
 pub use sort::SortCommand;
+pub use stab::{read_stab_regions, IndexedBed, Overlaps, StabCommand, StabQuery};
 pub use streaming_closest::{StreamingClosestCommand, StreamingClosestStats};
-pub use streaming_coverage::StreamingCoverageCommand;
+pub use streaming_coverage::{DepthClasses, ReciprocalSummary, StreamingCoverageCommand};
 pub use streaming_genomecov::{StreamingGenomecovCommand, StreamingGenomecovMode};
 pub use streaming_intersect::{StreamingIntersectCommand, StreamingStats};
 pub use streaming_merge::{StreamingMergeCommand, StreamingMergeStats};
@@ -0,0 +1,188 @@
+//! Modify command implementation.
+//!
+//! Reduces each record to an anchor base (midpoint, 5' or 3' end) and
+//! optionally builds a fixed-width window centered on it. The geometry comes
+//! from [`BedRecord::centered`] and the anchor helpers, so library callers and
+//! `grit modify` produce identical windows.
+
+use crate::bed::{BedError, BedReader};
+use crate::genome::Genome;
+use crate::interchange::stdin_reader;
+use crate::interval::BedRecord;
+use crate::output_limit::limited_stdout;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+/// Base of each record that the output is anchored on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    /// Midpoint of the interval.
+    #[default]
+    Midpoint,
+    /// 5' end, honoring the record's strand.
+    FivePrime,
+    /// 3' end, honoring the record's strand.
+    ThreePrime,
+}
+
+impl Anchor {
+    /// Parse an anchor name as accepted by `--anchor`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "mid" | "midpoint" => Some(Self::Midpoint),
+            "5p" | "5prime" => Some(Self::FivePrime),
+            "3p" | "3prime" => Some(Self::ThreePrime),
+            _ => None,
+        }
+    }
+}
+
+/// Modify command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct ModifyCommand {
+    /// Anchor base for each record.
+    pub anchor: Anchor,
+    /// Width of the window centered on the anchor; `None` emits the 1 bp anchor.
+    pub center: Option<u64>,
+}
+
+impl ModifyCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the anchor base.
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Center a window of `width` bases on the anchor.
+    pub fn with_center(mut self, width: Option<u64>) -> Self {
+        self.center = width;
+        self
+    }
+
+    /// Apply the modification to a single record.
+    ///
+    /// When `chrom_size` is given the result is clipped to the chromosome.
+    pub fn modify_record(&self, record: &BedRecord, chrom_size: Option<u64>) -> BedRecord {
+        let anchored = match self.anchor {
+            Anchor::Midpoint => record.centered(1),
+            Anchor::FivePrime => record.anchor_5prime(),
+            Anchor::ThreePrime => record.anchor_3prime(),
+        };
+
+        let mut result = match self.center {
+            Some(width) => anchored.centered(width),
+            None => anchored,
+        };
+
+        if let Some(size) = chrom_size {
+            result.interval.end = result.interval.end.min(size);
+        }
+        result
+    }
+
+    /// Run modify on a file with streaming output.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        genome: Option<&Genome>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let file = std::fs::File::open(input)?;
+        let reader = BedReader::new(file);
+        self.modify_streaming(reader, genome, output)
+    }
+
+    /// Streaming modify processing.
+    ///
+    /// With a genome, records on unknown chromosomes are skipped as in `slop`.
+    pub fn modify_streaming<R: Read, W: Write>(
+        &self,
+        reader: BedReader<R>,
+        genome: Option<&Genome>,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let mut buf_output = BufWriter::with_capacity(256 * 1024, output);
+
+        for result in reader.records() {
+            let record = result?;
+
+            let chrom_size = match genome {
+                Some(g) => match g.chrom_size(record.chrom()) {
+                    Some(size) => Some(size),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            let record = self.modify_record(&record, chrom_size);
+            if record.start() < record.end() {
+                writeln!(buf_output, "{}", record).map_err(BedError::Io)?;
+            }
+        }
+
+        buf_output.flush().map_err(BedError::Io)?;
+        Ok(())
+    }
+
+    /// Run modify from stdin to stdout.
+    pub fn run_stdio(&self, genome: Option<&Genome>) -> Result<(), BedError> {
+        let reader = BedReader::new(stdin_reader());
+
+        let handle = limited_stdout();
+
+        self.modify_streaming(reader, genome, &mut BufWriter::new(handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::Strand;
+
+    fn run_on(cmd: &ModifyCommand, content: &str, genome: Option<&Genome>) -> String {
+        let reader = BedReader::new(content.as_bytes());
+        let mut output = Vec::new();
+        cmd.modify_streaming(reader, genome, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_modify_center_on_midpoint() {
+        let cmd = ModifyCommand::new().with_center(Some(100));
+        let out = run_on(&cmd, "chr1\t1000\t1201\tpeak1\n", None);
+        assert_eq!(out, "chr1\t1050\t1150\tpeak1\n");
+
+        // Same window as the library helper
+        let rec = BedRecord::new("chr1", 1000, 1201);
+        assert_eq!(rec.centered(100).start(), 1050);
+    }
+
+    #[test]
+    fn test_modify_stranded_anchor() {
+        let cmd = ModifyCommand::new()
+            .with_anchor(Anchor::FivePrime)
+            .with_center(Some(20));
+        let mut rec = BedRecord::new("chr1", 100, 200);
+        rec.strand = Some(Strand::Minus);
+
+        let out = cmd.modify_record(&rec, None);
+        assert_eq!((out.start(), out.end()), (189, 209));
+
+        let out = cmd.modify_record(&rec, Some(205));
+        assert_eq!((out.start(), out.end()), (189, 205));
+    }
+
+    #[test]
+    fn test_modify_skips_unknown_chrom() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+
+        let cmd = ModifyCommand::new();
+        let out = run_on(&cmd, "chr1\t10\t20\nchrUn\t10\t20\n", Some(&genome));
+        assert_eq!(out, "chr1\t15\t16\n");
+    }
+}
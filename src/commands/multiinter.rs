@@ -2,12 +2,198 @@
 //!
 //! Identifies common intervals across multiple BED files using sweep-line algorithm.
 //! O(n log n) for sorting events, O(n) for sweep.
+//!
+//! # Sparse output
+//!
+//! With many inputs the presence flags (one `0`/`1` column per file) make up
+//! most of the output. The sparse format drops them and lists only the
+//! 1-based indices of the files present; a header line names the files:
+//!
+//! ```text
+//! #grit-sparse  tumor  normal  pon
+//! chr1  100  150  1  2
+//! chr1  150  200  2  1,2
+//! ```
+//!
+//! [`SparseMultiinterReader`] reads it back, and can expand it to the dense
+//! layout.
 
 use crate::bed::{BedError, BedReader};
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
+/// First column of the header line of sparse output.
+pub const SPARSE_HEADER: &str = "#grit-sparse";
+
+/// Write the sparse header: one label per input, `names` or 1-based indices.
+pub(crate) fn write_sparse_header<W: Write>(
+    output: &mut W,
+    names: Option<&[String]>,
+    n_files: usize,
+) -> Result<(), BedError> {
+    output.write_all(SPARSE_HEADER.as_bytes())?;
+    for i in 0..n_files {
+        match names {
+            Some(names) => write!(output, "\t{}", names[i])?,
+            None => write!(output, "\t{}", i + 1)?,
+        }
+    }
+    output.write_all(b"\n")?;
+    Ok(())
+}
+
+/// One region of sparse multiinter output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseRegion {
+    pub chrom: String,
+    pub start: u64,
+    pub end: u64,
+    /// 0-based indices of the files covering the region, ascending.
+    pub samples: Vec<usize>,
+}
+
+/// Reader for sparse multiinter output.
+pub struct SparseMultiinterReader<R: BufRead> {
+    reader: R,
+    labels: Vec<String>,
+    line: String,
+    line_number: usize,
+}
+
+impl SparseMultiinterReader<BufReader<File>> {
+    /// Open a sparse multiinter file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> SparseMultiinterReader<R> {
+    /// Read the header; the first line must be a `#grit-sparse` line.
+    pub fn new(mut reader: R) -> Result<Self, BedError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut fields = line.trim_end_matches(['\n', '\r']).split('\t');
+        if fields.next() != Some(SPARSE_HEADER) {
+            return Err(BedError::InvalidFormat(format!(
+                "not sparse multiinter output: the first line must start with {}",
+                SPARSE_HEADER
+            )));
+        }
+        let labels = fields.map(str::to_string).collect();
+        Ok(Self {
+            reader,
+            labels,
+            line,
+            line_number: 1,
+        })
+    }
+
+    /// Labels of the input files, in index order.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Read the next region, or `None` at the end of the input.
+    pub fn read_region(&mut self) -> Result<Option<SparseRegion>, BedError> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = self.line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return self.parse_region(line).map(Some);
+        }
+    }
+
+    fn parse_region(&self, line: &str) -> Result<SparseRegion, BedError> {
+        let error = |message: String| BedError::Parse {
+            line: self.line_number,
+            message,
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 5 {
+            return Err(error(format!(
+                "expected 5 columns (chrom, start, end, count, indices), found {}",
+                fields.len()
+            )));
+        }
+        let number = |text: &str, what: &str| -> Result<u64, BedError> {
+            text.parse()
+                .map_err(|_| error(format!("invalid {} '{}'", what, text)))
+        };
+        let samples = fields[4]
+            .split(',')
+            .map(|idx| match number(idx, "file index")? as usize {
+                i if i >= 1 && i <= self.labels.len() => Ok(i - 1),
+                i => Err(error(format!(
+                    "file index {} is outside 1..={}",
+                    i,
+                    self.labels.len()
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if number(fields[3], "count")? as usize != samples.len() {
+            return Err(error(format!(
+                "count {} does not match {} file indices",
+                fields[3],
+                samples.len()
+            )));
+        }
+        Ok(SparseRegion {
+            chrom: fields[0].to_string(),
+            start: number(fields[1], "start")?,
+            end: number(fields[2], "end")?,
+            samples,
+        })
+    }
+
+    /// Write the remaining regions in the dense layout: labels of the files
+    /// present, then a `0`/`1` column per file.
+    pub fn write_dense<W: Write>(mut self, output: &mut W) -> Result<(), BedError> {
+        let mut output = BufWriter::with_capacity(256 * 1024, output);
+        let mut flags = vec![b'0'; self.labels.len()];
+        while let Some(region) = self.read_region()? {
+            let present: Vec<&str> = region
+                .samples
+                .iter()
+                .map(|&i| self.labels[i].as_str())
+                .collect();
+            write!(
+                output,
+                "{}\t{}\t{}\t{}\t{}",
+                region.chrom,
+                region.start,
+                region.end,
+                present.len(),
+                present.join(",")
+            )?;
+            flags.fill(b'0');
+            for &i in &region.samples {
+                flags[i] = b'1';
+            }
+            for &flag in &flags {
+                output.write_all(&[b'\t', flag])?;
+            }
+            output.write_all(b"\n")?;
+        }
+        output.flush()?;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for SparseMultiinterReader<R> {
+    type Item = Result<SparseRegion, BedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_region().transpose()
+    }
+}
+
 /// Multiinter command configuration.
 #[derive(Debug, Clone)]
 pub struct MultiinterCommand {
@@ -17,6 +203,10 @@ pub struct MultiinterCommand {
     pub cluster: bool,
     /// Empty placeholder for missing files
     pub empty: bool,
+    /// Labels reported in the file list column instead of 1-based indices
+    pub names: Option<Vec<String>>,
+    /// Write the sparse format: file indices instead of presence flags
+    pub sparse: bool,
 }
 
 impl Default for MultiinterCommand {
@@ -31,15 +221,39 @@ impl MultiinterCommand {
             header: false,
             cluster: false,
             empty: false,
+            names: None,
+            sparse: false,
         }
     }
 
+    /// Write the sparse format (builder pattern).
+    pub fn with_sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Report `names` instead of file indices (one per input, in order).
+    pub fn with_names(mut self, names: Vec<String>) -> Self {
+        self.names = Some(names);
+        self
+    }
+
     /// Run multiinter on multiple files.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
         inputs: &[P],
         output: &mut W,
     ) -> Result<(), BedError> {
+        if let Some(ref names) = self.names {
+            if names.len() != inputs.len() {
+                return Err(BedError::InvalidFormat(format!(
+                    "{} names given for {} input files",
+                    names.len(),
+                    inputs.len()
+                )));
+            }
+        }
+
         // Read all intervals from all files
         let mut all_intervals: Vec<Vec<(String, u64, u64)>> = Vec::with_capacity(inputs.len());
 
@@ -67,6 +281,9 @@ impl MultiinterCommand {
     ) -> Result<(), BedError> {
         let mut buf_output = BufWriter::with_capacity(256 * 1024, output);
         let n_files = all_intervals.len();
+        if self.sparse {
+            write_sparse_header(&mut buf_output, self.names.as_deref(), n_files)?;
+        }
 
         // Group by chromosome
         let mut by_chrom: std::collections::HashMap<String, Vec<(u64, u64, usize)>> =
@@ -158,14 +375,31 @@ impl MultiinterCommand {
             return Ok(());
         }
 
-        // Build list of file indices (1-based)
+        // Build list of file labels (1-based indices unless names are set or
+        // the output is sparse)
         let file_list: Vec<String> = file_depths
             .iter()
             .enumerate()
             .filter(|(_, &d)| d > 0)
-            .map(|(i, _)| (i + 1).to_string())
+            .map(|(i, _)| match &self.names {
+                Some(names) if !self.sparse => names[i].clone(),
+                _ => (i + 1).to_string(),
+            })
             .collect();
 
+        if self.sparse {
+            writeln!(
+                output,
+                "{}\t{}\t{}\t{}\t{}",
+                chrom,
+                start,
+                end,
+                count,
+                file_list.join(",")
+            )?;
+            return Ok(());
+        }
+
         // Build presence flags
         let flags: Vec<&str> = file_depths
             .iter()
@@ -225,6 +459,26 @@ mod tests {
         assert_eq!(first[4], "1"); // file list
     }
 
+    #[test]
+    fn test_multiinter_names() {
+        let cmd = MultiinterCommand::new().with_names(vec!["wt".to_string(), "ko".to_string()]);
+
+        let all = vec![
+            vec![("chr1".to_string(), 100u64, 200u64)],
+            vec![("chr1".to_string(), 150, 250)],
+        ];
+
+        let mut output = Vec::new();
+        cmd.multiinter_from_intervals(&all, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let lists: Vec<&str> = result
+            .lines()
+            .map(|l| l.split('\t').nth(4).unwrap())
+            .collect();
+        assert_eq!(lists, vec!["wt", "wt,ko", "ko"]);
+    }
+
     #[test]
     fn test_multiinter_three_files() {
         let cmd = MultiinterCommand::new();
@@ -276,4 +530,58 @@ mod tests {
             assert_eq!(parts[3], "1"); // count == 1
         }
     }
+
+    #[test]
+    fn test_sparse_roundtrip() {
+        let all = vec![
+            vec![("chr1".to_string(), 100u64, 200u64)],
+            vec![("chr1".to_string(), 150, 250)],
+        ];
+        let names = vec!["t".to_string(), "n".to_string()];
+
+        let mut sparse = Vec::new();
+        MultiinterCommand::new()
+            .with_names(names.clone())
+            .with_sparse(true)
+            .multiinter_from_intervals(&all, &mut sparse)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(sparse.clone()).unwrap(),
+            "#grit-sparse\tt\tn\n\
+             chr1\t100\t150\t1\t1\n\
+             chr1\t150\t200\t2\t1,2\n\
+             chr1\t200\t250\t1\t2\n"
+        );
+
+        let reader = SparseMultiinterReader::new(&sparse[..]).unwrap();
+        assert_eq!(reader.labels(), names);
+        let regions: Vec<SparseRegion> = reader.map(Result::unwrap).collect();
+        assert_eq!(regions[1].samples, vec![0, 1]);
+        assert_eq!((regions[2].start, regions[2].end), (200, 250));
+
+        let mut dense = Vec::new();
+        SparseMultiinterReader::new(&sparse[..])
+            .unwrap()
+            .write_dense(&mut dense)
+            .unwrap();
+        let mut expected = Vec::new();
+        MultiinterCommand::new()
+            .with_names(names)
+            .multiinter_from_intervals(&all, &mut expected)
+            .unwrap();
+        assert_eq!(dense, expected);
+    }
+
+    #[test]
+    fn test_sparse_reader_errors() {
+        assert!(SparseMultiinterReader::new(&b"chr1\t0\t1\t1\t1\n"[..]).is_err());
+
+        let mut reader =
+            SparseMultiinterReader::new(&b"#grit-sparse\ta\tb\nchr1\t0\t1\t1\t3\n"[..]).unwrap();
+        assert!(reader.read_region().is_err());
+
+        let mut reader =
+            SparseMultiinterReader::new(&b"#grit-sparse\ta\tb\nchr1\t0\t1\t2\t1\n"[..]).unwrap();
+        assert!(reader.read_region().is_err());
+    }
 }
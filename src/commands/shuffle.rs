@@ -0,0 +1,433 @@
+//! Shuffle command implementation.
+//!
+//! Moves every record to a random location of the same length to build null
+//! backgrounds for enrichment tests. The uniform background draws chromosomes
+//! in proportion to their size, like `bedtools shuffle`. The matched
+//! background keeps each record on its own chromosome, so the controls
+//! reproduce both the length distribution and the chromosome proportions of
+//! the input. Placements can be restricted with a [`GenomeMask`], e.g. to keep
+//! controls out of assembly gaps.
+//!
+//! The uniform background honours genome file metadata: excluded and
+//! zero-ploidy chromosomes are never drawn, and the others are weighted by
+//! their effective size and, when any ploidy is given, by their copy number.
+
+use crate::bed::BedError;
+use crate::genome::{Genome, GenomeMask, DEFAULT_PLOIDY};
+use crate::interchange::stdin_reader;
+use crate::interval::Interval;
+use crate::output_limit::limited_stdout;
+use crate::rng::{SeededRng, DEFAULT_SEED};
+use crate::streaming::parsing::{parse_bed3_bytes_with_rest, should_skip_line, trim_newline};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// How control locations are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Any chromosome, weighted by chromosome size (see the module docs for
+    /// genome file metadata).
+    #[default]
+    Uniform,
+    /// The record's own chromosome, matching the input's chromosome mix.
+    Matched,
+}
+
+impl Background {
+    /// Parse a background name as accepted by `--background`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "uniform" => Some(Self::Uniform),
+            "matched" => Some(Self::Matched),
+            _ => None,
+        }
+    }
+}
+
+/// Shuffle command configuration.
+#[derive(Debug, Clone)]
+pub struct ShuffleCommand {
+    /// Where controls may be placed.
+    pub background: Background,
+    /// Seed for the random number generator; equal seeds give equal output.
+    pub seed: u64,
+    /// Number of controls written per input record.
+    pub per_record: usize,
+    /// Only place controls entirely inside this mask.
+    pub mask: Option<GenomeMask>,
+    /// Placement attempts per control before giving up.
+    pub max_tries: usize,
+}
+
+impl Default for ShuffleCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShuffleCommand {
+    pub fn new() -> Self {
+        Self {
+            background: Background::Uniform,
+            seed: DEFAULT_SEED,
+            per_record: 1,
+            mask: None,
+            max_tries: 1000,
+        }
+    }
+
+    /// Set the background model.
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Set the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Write `n` controls per input record.
+    pub fn with_per_record(mut self, n: usize) -> Self {
+        self.per_record = n;
+        self
+    }
+
+    /// Restrict placements to regions allowed by a mask.
+    pub fn with_mask(mut self, mask: GenomeMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Shuffle a BED file.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let file = File::open(input)?;
+        self.shuffle_reader(file, genome, output)
+    }
+
+    /// Shuffle stdin to stdout.
+    pub fn run_stdio(&self, genome: &Genome) -> Result<(), BedError> {
+        let mut handle = limited_stdout();
+        self.shuffle_reader(stdin_reader(), genome, &mut handle)
+    }
+
+    /// Shuffle records from a reader.
+    ///
+    /// Each control keeps the record's length and any columns after the
+    /// coordinates. Controls are written in input order, `per_record` lines
+    /// per record, so the output is unsorted.
+    pub fn shuffle_reader<R: Read, W: Write>(
+        &self,
+        input: R,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<(), BedError> {
+        let sampler = ChromSampler::new(genome);
+        let mut rng = SeededRng::new(self.seed);
+
+        let mut out = BufWriter::with_capacity(256 * 1024, output);
+        let mut reader = BufReader::with_capacity(256 * 1024, input);
+        let mut line = Vec::with_capacity(1024);
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_num += 1;
+            if should_skip_line(&line) {
+                continue;
+            }
+
+            let (chrom, start, end, rest) =
+                parse_bed3_bytes_with_rest(&line).ok_or_else(|| BedError::Parse {
+                    line: line_num,
+                    message: "expected chrom, start and end columns".to_string(),
+                })?;
+            let chrom = std::str::from_utf8(chrom).unwrap_or("");
+            let len = end.saturating_sub(start);
+            let rest = trim_newline(&line[rest..]);
+
+            let own_size = match self.background {
+                Background::Matched => {
+                    Some(genome.chrom_size(chrom).ok_or_else(|| BedError::Parse {
+                        line: line_num,
+                        message: format!("chromosome '{}' not found in genome file", chrom),
+                    })?)
+                }
+                Background::Uniform => None,
+            };
+
+            for _ in 0..self.per_record {
+                let (target, target_start) = self
+                    .place(&sampler, &mut rng, chrom, own_size, len)
+                    .ok_or_else(|| BedError::Parse {
+                    line: line_num,
+                    message: format!(
+                        "could not place a {} bp interval after {} tries",
+                        len, self.max_tries
+                    ),
+                })?;
+                write!(out, "{}\t{}\t{}", target, target_start, target_start + len)?;
+                out.write_all(rest)?;
+                out.write_all(b"\n")?;
+            }
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Draw one control for each interval held in memory, passing each
+    /// placement to `emit` in input order; `per_record` is not used.
+    ///
+    /// The draws come from `rng`, so callers running several shuffles can give
+    /// each its own [`SeededRng::stream`]. Given `SeededRng::new(self.seed)`,
+    /// the placements are those [`shuffle_reader`](Self::shuffle_reader)
+    /// writes for the same records with one control per record.
+    pub fn shuffle_each<F: FnMut(&str, u64, u64)>(
+        &self,
+        intervals: &[Interval],
+        genome: &Genome,
+        rng: &mut SeededRng,
+        mut emit: F,
+    ) -> Result<(), BedError> {
+        let sampler = ChromSampler::new(genome);
+        for iv in intervals {
+            let len = iv.end.saturating_sub(iv.start);
+            let own_size = match self.background {
+                Background::Matched => Some(genome.chrom_size(&iv.chrom).ok_or_else(|| {
+                    BedError::InvalidFormat(format!(
+                        "chromosome '{}' not found in genome file",
+                        iv.chrom
+                    ))
+                })?),
+                Background::Uniform => None,
+            };
+            let (target, start) = self
+                .place(&sampler, rng, &iv.chrom, own_size, len)
+                .ok_or_else(|| {
+                    BedError::InvalidFormat(format!(
+                        "could not place a {} bp interval after {} tries",
+                        len, self.max_tries
+                    ))
+                })?;
+            emit(target, start, start + len);
+        }
+        Ok(())
+    }
+
+    /// Draw a location for an interval of `len` bases.
+    fn place<'g>(
+        &self,
+        sampler: &ChromSampler<'g>,
+        rng: &mut SeededRng,
+        chrom: &'g str,
+        own_size: Option<u64>,
+        len: u64,
+    ) -> Option<(&'g str, u64)> {
+        for _ in 0..self.max_tries {
+            let (target, size) = match own_size {
+                Some(size) => (chrom, size),
+                None => sampler.draw(rng)?,
+            };
+            if size < len {
+                continue;
+            }
+            let start = rng.range_inclusive(0..=size - len);
+            let allowed = self
+                .mask
+                .as_ref()
+                .is_none_or(|mask| mask.contains(target, start, start + len));
+            if allowed {
+                return Some((target, start));
+            }
+        }
+        None
+    }
+}
+
+/// Size-weighted chromosome sampling.
+struct ChromSampler<'g> {
+    chroms: Vec<(&'g str, u64)>,
+    /// Running total of chromosome weights, parallel to `chroms`.
+    cumulative: Vec<u64>,
+}
+
+impl<'g> ChromSampler<'g> {
+    fn new(genome: &'g Genome) -> Self {
+        // Only scale by ploidy when some is given, so plain genome files keep
+        // the same draws for a given seed
+        let by_ploidy = genome
+            .chromosomes()
+            .any(|c| genome.ploidy(c) != DEFAULT_PLOIDY);
+        let weighted: Vec<(&str, u64, u64)> = genome
+            .chromosomes()
+            .filter(|c| genome.in_totals(c))
+            .filter_map(|c| {
+                let size = genome.chrom_size(c)?;
+                let mut weight = genome.effective_size(c)?;
+                if by_ploidy {
+                    weight *= genome.ploidy(c) as u64;
+                }
+                Some((c.as_str(), size, weight))
+            })
+            .filter(|&(_, size, weight)| size > 0 && weight > 0)
+            .collect();
+        let chroms = weighted.iter().map(|&(c, size, _)| (c, size)).collect();
+        let cumulative = weighted
+            .iter()
+            .scan(0u64, |total, &(_, _, weight)| {
+                *total += weight;
+                Some(*total)
+            })
+            .collect();
+        Self { chroms, cumulative }
+    }
+
+    fn draw(&self, rng: &mut SeededRng) -> Option<(&'g str, u64)> {
+        let total = *self.cumulative.last()?;
+        let pos = rng.below(total);
+        let idx = self.cumulative.partition_point(|&c| c <= pos);
+        Some(self.chroms[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::ChromInfo;
+    use crate::interval::Interval;
+
+    fn genome() -> Genome {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 10_000);
+        genome.insert("chr2".to_string(), 5_000);
+        genome.insert("chrM".to_string(), 50);
+        genome
+    }
+
+    fn shuffle(cmd: &ShuffleCommand, input: &str) -> Vec<(String, u64, u64, String)> {
+        let mut output = Vec::new();
+        cmd.shuffle_reader(input.as_bytes(), &genome(), &mut output)
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| {
+                let f: Vec<&str> = l.splitn(4, '\t').collect();
+                (
+                    f[0].to_string(),
+                    f[1].parse().unwrap(),
+                    f[2].parse().unwrap(),
+                    f.get(3).unwrap_or(&"").to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matched_keeps_chrom_and_length() {
+        let cmd = ShuffleCommand::new()
+            .with_background(Background::Matched)
+            .with_per_record(20);
+        let input = "chr2\t100\t400\tpeak1\t7\nchrM\t0\t50\tpeak2\t1\n";
+        let out = shuffle(&cmd, input);
+
+        assert_eq!(out.len(), 40);
+        for (chrom, start, end, rest) in &out[..20] {
+            assert_eq!(chrom, "chr2");
+            assert_eq!(end - start, 300);
+            assert!(*end <= 5_000);
+            assert_eq!(rest, "peak1\t7");
+        }
+        // A record spanning its whole chromosome has one possible placement
+        assert!(out[20..]
+            .iter()
+            .all(|(c, s, e, _)| c == "chrM" && *s == 0 && *e == 50));
+    }
+
+    #[test]
+    fn test_uniform_is_seeded_and_fits() {
+        let cmd = ShuffleCommand::new().with_per_record(200);
+        let input = "chr1\t0\t1000\n";
+        let out = shuffle(&cmd, input);
+
+        assert_eq!(out, shuffle(&cmd, input));
+        assert_ne!(out, shuffle(&cmd.clone().with_seed(7), input));
+        // chrM is too short for a 1 kb interval; the other two both get draws
+        assert!(out.iter().all(|(c, s, e, _)| c != "chrM" && e - s == 1000));
+        assert!(out.iter().any(|(c, _, _, _)| c == "chr1"));
+        assert!(out.iter().any(|(c, _, _, _)| c == "chr2"));
+    }
+
+    #[test]
+    fn test_uniform_honours_genome_metadata() {
+        let mut genome = genome();
+        let excluded = ChromInfo {
+            excluded: true,
+            ..ChromInfo::default()
+        };
+        genome.set_chrom_info("chr1", excluded);
+        let haploid = ChromInfo {
+            ploidy: 1,
+            ..ChromInfo::default()
+        };
+        genome.set_chrom_info("chrM", haploid);
+
+        let mut output = Vec::new();
+        ShuffleCommand::new()
+            .with_per_record(500)
+            .shuffle_reader("chr1\t0\t10\n".as_bytes(), &genome, &mut output)
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.lines().all(|l| !l.starts_with("chr1\t")));
+        assert!(out.lines().any(|l| l.starts_with("chrM\t")));
+    }
+
+    #[test]
+    fn test_shuffle_each_matches_reader() {
+        let cmd = ShuffleCommand::new().with_seed(9);
+        let input = "chr1\t0\t100\nchr2\t50\t80\n";
+        let intervals = crate::bed::parse_intervals(input).unwrap();
+        let mut placed = Vec::new();
+        cmd.shuffle_each(&intervals, &genome(), &mut SeededRng::new(9), |c, s, e| {
+            placed.push((c.to_string(), s, e, String::new()))
+        })
+        .unwrap();
+        assert_eq!(placed, shuffle(&cmd, input));
+    }
+
+    #[test]
+    fn test_mask_and_unplaceable() {
+        let mask = GenomeMask::from_intervals(&[Interval::new("chr1", 2_000, 2_600)]);
+        let cmd = ShuffleCommand::new().with_mask(mask).with_per_record(50);
+        let out = shuffle(&cmd, "chr2\t0\t500\n");
+        assert!(out
+            .iter()
+            .all(|(c, s, e, _)| c == "chr1" && *s >= 2_000 && *e <= 2_600));
+
+        let mut output = Vec::new();
+        let err = ShuffleCommand::new()
+            .with_background(Background::Matched)
+            .shuffle_reader(
+                "chrM\t0\t10\nchrM\t0\t60\n".as_bytes(),
+                &genome(),
+                &mut output,
+            );
+        assert!(matches!(err, Err(BedError::Parse { line: 2, .. })));
+
+        let err = ShuffleCommand::new()
+            .with_background(Background::Matched)
+            .shuffle_reader("chrUn\t0\t10\n".as_bytes(), &genome(), &mut output);
+        assert!(matches!(err, Err(BedError::Parse { line: 1, .. })));
+    }
+}
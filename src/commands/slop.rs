@@ -1,11 +1,15 @@
 //! Slop command implementation.
 //!
 //! Extends intervals by a fixed number of bases upstream and/or downstream,
-//! respecting chromosome boundaries.
+//! respecting chromosome boundaries. With `amount_col` the number of bases
+//! comes from a column of each record instead, e.g. the fragment length of a
+//! ChIP-seq read, and the fixed values scale it.
 
 use crate::bed::{BedError, BedReader};
 use crate::genome::Genome;
+use crate::interchange::stdin_reader;
 use crate::interval::BedRecord;
+use crate::output_limit::limited_stdout;
 use std::io::{self, BufWriter, Read, Write};
 use std::path::Path;
 
@@ -25,6 +29,9 @@ pub struct SlopCommand {
     pub pct: bool,
     /// Treat the slop values as header lines to skip
     pub header: bool,
+    /// Read the extension from this 1-based column of each record; `both`,
+    /// `left` and `right` then multiply it
+    pub amount_col: Option<usize>,
 }
 
 impl Default for SlopCommand {
@@ -42,47 +49,68 @@ impl SlopCommand {
             strand: false,
             pct: false,
             header: false,
+            amount_col: None,
         }
     }
 
     /// Get effective left extension.
     #[inline]
-    fn get_left(&self, interval_len: u64) -> u64 {
+    fn get_left(&self, interval_len: u64, amount: Option<f64>) -> u64 {
         let base = self.left.unwrap_or(self.both);
-        if self.pct {
-            ((interval_len as f64) * base).round() as u64
-        } else {
-            base as u64
-        }
+        Self::scale(base, interval_len, amount, self.pct)
     }
 
     /// Get effective right extension.
     #[inline]
-    fn get_right(&self, interval_len: u64) -> u64 {
+    fn get_right(&self, interval_len: u64, amount: Option<f64>) -> u64 {
         let base = self.right.unwrap_or(self.both);
-        if self.pct {
+        Self::scale(base, interval_len, amount, self.pct)
+    }
+
+    #[inline]
+    fn scale(base: f64, interval_len: u64, amount: Option<f64>, pct: bool) -> u64 {
+        if let Some(amount) = amount {
+            (amount * base).round() as u64
+        } else if pct {
             ((interval_len as f64) * base).round() as u64
         } else {
             base as u64
         }
     }
 
-    /// Apply slop to a single record.
+    /// Extension amount stored in `amount_col` of `record`, if it holds a
+    /// non-negative number.
+    pub fn record_amount(&self, record: &BedRecord) -> Option<f64> {
+        let value = record.numeric_column(self.amount_col?)?;
+        (value >= 0.0 && value.is_finite()).then_some(value)
+    }
+
+    /// Bases to add before the start and after the end of a record.
     #[inline]
-    pub fn slop_record(&self, record: &mut BedRecord, chrom_size: u64) {
+    fn extensions(&self, record: &BedRecord) -> (u64, u64) {
         let interval_len = record.end() - record.start();
-        let left_ext = self.get_left(interval_len);
-        let right_ext = self.get_right(interval_len);
+        // A record without an amount is left as is
+        let amount = self
+            .amount_col
+            .map(|_| self.record_amount(record).unwrap_or(0.0));
+        let left_ext = self.get_left(interval_len, amount);
+        let right_ext = self.get_right(interval_len, amount);
 
         // Handle strand-aware slop
-        let (upstream, downstream) = if self.strand {
+        if self.strand {
             match record.strand {
                 Some(crate::interval::Strand::Minus) => (right_ext, left_ext),
                 _ => (left_ext, right_ext),
             }
         } else {
             (left_ext, right_ext)
-        };
+        }
+    }
+
+    /// Apply slop to a single record.
+    #[inline]
+    pub fn slop_record(&self, record: &mut BedRecord, chrom_size: u64) {
+        let (upstream, downstream) = self.extensions(record);
 
         // Apply slop with boundary enforcement
         let new_start = record.start().saturating_sub(upstream);
@@ -92,16 +120,47 @@ impl SlopCommand {
         record.interval.end = new_end;
     }
 
+    /// Why slopping `record` would not produce the full requested extension,
+    /// or `None` when it fits inside the chromosome.
+    fn clamp_reason(&self, record: &BedRecord, chrom_size: u64) -> Option<&'static str> {
+        let (upstream, downstream) = self.extensions(record);
+        let new_start = record.start().saturating_sub(upstream);
+        let new_end = (record.end() + downstream).min(chrom_size);
+        if new_start >= new_end {
+            return Some("outside_chrom");
+        }
+
+        let clamped_start = record.start() < upstream;
+        let clamped_end = record.end() + downstream > chrom_size;
+        match (clamped_start, clamped_end) {
+            (true, true) => Some("clamped_both"),
+            (true, false) => Some("clamped_start"),
+            (false, true) => Some("clamped_end"),
+            (false, false) => None,
+        }
+    }
+
     /// Run slop on a file with streaming output.
     pub fn run<P: AsRef<Path>, W: Write>(
         &self,
         input: P,
         genome: &Genome,
         output: &mut W,
+    ) -> Result<(), BedError> {
+        self.run_with_rejects(input, genome, output, None::<&mut io::Sink>)
+    }
+
+    /// Run slop on a file, also writing records affected by chromosome bounds.
+    pub fn run_with_rejects<P: AsRef<Path>, W: Write, J: Write>(
+        &self,
+        input: P,
+        genome: &Genome,
+        output: &mut W,
+        rejects: Option<&mut J>,
     ) -> Result<(), BedError> {
         let file = std::fs::File::open(input)?;
         let reader = BedReader::new(file);
-        self.slop_streaming(reader, genome, output)
+        self.slop_streaming_with_rejects(reader, genome, output, rejects)
     }
 
     /// Streaming slop processing.
@@ -110,21 +169,61 @@ impl SlopCommand {
         reader: BedReader<R>,
         genome: &Genome,
         output: &mut W,
+    ) -> Result<(), BedError> {
+        self.slop_streaming_with_rejects(reader, genome, output, None::<&mut io::Sink>)
+    }
+
+    /// Streaming slop processing with an optional rejects sink.
+    ///
+    /// Each input record that is clamped at a chromosome edge, dropped for
+    /// lying outside the chromosome, or skipped because its chromosome is
+    /// missing from the genome is written unchanged to `rejects`, followed by
+    /// a reason column: `clamped_start`, `clamped_end`, `clamped_both`,
+    /// `outside_chrom` or `unknown_chrom`. Clamped records are still written
+    /// to `output`.
+    ///
+    /// With `amount_col`, a record whose column does not hold a non-negative
+    /// number is an error.
+    pub fn slop_streaming_with_rejects<R: Read, W: Write, J: Write>(
+        &self,
+        reader: BedReader<R>,
+        genome: &Genome,
+        output: &mut W,
+        rejects: Option<&mut J>,
     ) -> Result<(), BedError> {
         let mut buf_output = BufWriter::with_capacity(256 * 1024, output);
+        let mut rejects = rejects.map(|r| BufWriter::with_capacity(64 * 1024, r));
 
         for result in reader.records() {
             let mut record = result?;
 
+            if let Some(col) = self.amount_col {
+                if self.record_amount(&record).is_none() {
+                    return Err(BedError::InvalidFormat(format!(
+                        "slop: column {} of '{}' is not a non-negative extension amount",
+                        col, record
+                    )));
+                }
+            }
+
             // Get chromosome size, skip if not in genome
             let chrom_size = match genome.chrom_size(record.chrom()) {
                 Some(size) => size,
                 None => {
                     // bedtools skips intervals on unknown chromosomes
+                    if let Some(ref mut r) = rejects {
+                        writeln!(r, "{}\tunknown_chrom", record).map_err(BedError::Io)?;
+                    }
                     continue;
                 }
             };
 
+            if let Some(ref mut r) = rejects {
+                if let Some(reason) = self.clamp_reason(&record, chrom_size) {
+                    writeln!(r, "{}\t{}", record, reason).map_err(BedError::Io)?;
+                }
+            }
+
             self.slop_record(&mut record, chrom_size);
 
             // Only output if interval is valid (start < end)
@@ -134,16 +233,17 @@ impl SlopCommand {
         }
 
         buf_output.flush().map_err(BedError::Io)?;
+        if let Some(ref mut r) = rejects {
+            r.flush().map_err(BedError::Io)?;
+        }
         Ok(())
     }
 
     /// Run slop from stdin to stdout.
     pub fn run_stdio(&self, genome: &Genome) -> Result<(), BedError> {
-        let stdin = io::stdin();
-        let reader = BedReader::new(stdin.lock());
+        let reader = BedReader::new(stdin_reader());
 
-        let stdout = io::stdout();
-        let handle = stdout.lock();
+        let handle = limited_stdout();
 
         self.slop_streaming(reader, genome, &mut BufWriter::new(handle))
     }
@@ -255,6 +355,43 @@ mod tests {
         assert_eq!(rec.end(), 210); // +10 (left becomes downstream)
     }
 
+    #[test]
+    fn test_slop_rejects() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        let cmd = SlopCommand {
+            both: 100.0,
+            ..SlopCommand::new()
+        };
+        let content = "chr1\t50\t150\tnearStart\n\
+                       chr1\t400\t500\tinside\n\
+                       chr1\t950\t990\tnearEnd\n\
+                       chr1\t1200\t1300\tpastEnd\n\
+                       chrUn\t10\t20\tunknown\n";
+
+        let mut output = Vec::new();
+        let mut rejects = Vec::new();
+        cmd.slop_streaming_with_rejects(
+            BedReader::new(content.as_bytes()),
+            &genome,
+            &mut output,
+            Some(&mut rejects),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t0\t250\tnearStart\nchr1\t300\t600\tinside\nchr1\t850\t1000\tnearEnd\n"
+        );
+        assert_eq!(
+            String::from_utf8(rejects).unwrap(),
+            "chr1\t50\t150\tnearStart\tclamped_start\n\
+             chr1\t950\t990\tnearEnd\tclamped_end\n\
+             chr1\t1200\t1300\tpastEnd\toutside_chrom\n\
+             chrUn\t10\t20\tunknown\tunknown_chrom\n"
+        );
+    }
+
     #[test]
     fn test_slop_percentage() {
         let cmd = SlopCommand {
@@ -271,4 +408,52 @@ mod tests {
         assert_eq!(rec.start(), 0); // 100 - 100 = 0
         assert_eq!(rec.end(), 300); // 200 + 100 = 300
     }
+
+    #[test]
+    fn test_slop_amount_column() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        // Extend reads downstream to their fragment length (score column)
+        let cmd = SlopCommand {
+            right: Some(1.0),
+            strand: true,
+            amount_col: Some(5),
+            ..SlopCommand::new()
+        };
+        let content = "chr1\t100\t150\tr1\t200\t+\n\
+                       chr1\t500\t550\tr2\t150\t-\n";
+
+        let mut output = Vec::new();
+        cmd.slop_streaming(BedReader::new(content.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t350\tr1\t200\t+\nchr1\t350\t550\tr2\t150\t-\n"
+        );
+
+        // Half the amount on each side, from an extra column
+        let cmd = SlopCommand {
+            both: 0.5,
+            amount_col: Some(13),
+            ..SlopCommand::new()
+        };
+        let line = "chr1\t100\t200\tr\t0\t+\t100\t200\t0\t1\t100,\t0,\t60\n";
+        let mut output = Vec::new();
+        cmd.slop_streaming(BedReader::new(line.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("chr1\t70\t230\t"));
+
+        let bad = "chr1\t100\t150\tr1\t.\t+\n";
+        let cmd = SlopCommand {
+            amount_col: Some(5),
+            both: 1.0,
+            ..SlopCommand::new()
+        };
+        let err = cmd
+            .slop_streaming(BedReader::new(bad.as_bytes()), &genome, &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("column 5"));
+    }
 }
@@ -10,11 +10,14 @@
 //! O(n log n) string allocations during comparison.
 
 use crate::bed::{read_records, BedError, BedReader};
+use crate::interchange::stdin_reader;
 use crate::interval::BedRecord;
+use crate::output_limit::limited_stdout;
+use crate::parallel::PoolHandle;
 use rayon::prelude::*;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::io::{self, BufWriter, Write};
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 /// Sort key specification.
@@ -47,6 +50,8 @@ pub struct SortCommand {
     pub natural_sort: bool,
     /// Genome-based chromosome ordering (chrom name -> index)
     genome_order: Option<HashMap<String, u32>>,
+    /// Thread pool for parallel sorting
+    pub pool: PoolHandle,
 }
 
 impl Default for SortCommand {
@@ -64,9 +69,16 @@ impl SortCommand {
             chrom_only: false,
             natural_sort: false, // Lexicographic by default (matches GNU sort -k1,1)
             genome_order: None,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Set genome-based chromosome ordering.
     /// Chromosomes will be sorted in the order they appear in the genome file.
     /// Unknown chromosomes are placed after all known chromosomes.
@@ -126,7 +138,11 @@ impl SortCommand {
     }
 
     /// Parallel sort using Rayon with stable sort to preserve input order for ties.
-    pub fn sort_parallel(&self, mut records: Vec<BedRecord>) -> Vec<BedRecord> {
+    pub fn sort_parallel(&self, records: Vec<BedRecord>) -> Vec<BedRecord> {
+        self.pool.install(|| self.sort_parallel_in_pool(records))
+    }
+
+    fn sort_parallel_in_pool(&self, mut records: Vec<BedRecord>) -> Vec<BedRecord> {
         if records.len() < 2 {
             return records;
         }
@@ -367,15 +383,13 @@ impl SortCommand {
 
     /// Execute sort from stdin to stdout.
     pub fn run_stdio(&self) -> Result<(), BedError> {
-        let stdin = io::stdin();
-        let reader = BedReader::new(stdin.lock());
+        let reader = BedReader::new(stdin_reader());
         let records: Result<Vec<_>, _> = reader.records().collect();
         let records = records?;
 
         let sorted = self.sort_parallel(records);
 
-        let stdout = io::stdout();
-        let handle = stdout.lock();
+        let handle = limited_stdout();
         let mut buf_output = BufWriter::with_capacity(256 * 1024, handle);
         for record in sorted {
             writeln!(buf_output, "{}", record).map_err(BedError::Io)?;
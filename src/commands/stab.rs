@@ -0,0 +1,387 @@
+//! Stab command implementation.
+//!
+//! Reports the records of a sorted BED file that cover given positions or
+//! regions. The file is memory-mapped and each query binary-searches the
+//! byte range of its chromosome, so only the records near the query are
+//! read. Chromosome ranges come from a `.gri` sidecar index when one exists
+//! (see [`FileIndex`]) and are otherwise computed with one scan of the file.
+
+use crate::bed::BedError;
+use crate::file_index::{is_header, ChromBlock, FileIndex, SourceStamp};
+use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// A query region in 0-based, half-open coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StabQuery {
+    pub chrom: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl StabQuery {
+    /// Parse `chrom:pos` (a 1-based position) or `chrom:start-end` (a 1-based,
+    /// inclusive region, as genome browsers display it). Commas in numbers
+    /// are ignored.
+    pub fn parse(s: &str) -> Result<Self, BedError> {
+        let invalid = || {
+            BedError::InvalidFormat(format!(
+                "invalid query '{}': expected chrom:pos or chrom:start-end",
+                s
+            ))
+        };
+        let (chrom, range) = s.rsplit_once(':').ok_or_else(invalid)?;
+        let number = |v: &str| -> Result<u64, BedError> {
+            v.replace(',', "").parse::<u64>().map_err(|_| invalid())
+        };
+        let (first, last) = match range.split_once('-') {
+            Some((a, b)) => (number(a)?, number(b)?),
+            None => {
+                let pos = number(range)?;
+                (pos, pos)
+            }
+        };
+        if chrom.is_empty() || first == 0 || last < first {
+            return Err(invalid());
+        }
+        Ok(Self {
+            chrom: chrom.to_string(),
+            start: first - 1,
+            end: last,
+        })
+    }
+}
+
+/// Read query regions from a BED file.
+pub fn read_stab_regions<P: AsRef<Path>>(path: P) -> Result<Vec<StabQuery>, BedError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut queries = Vec::new();
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        if is_header(line) {
+            continue;
+        }
+        let (chrom, start, end) = parse_bed3_bytes(line).ok_or_else(|| BedError::Parse {
+            line: i + 1,
+            message: "expected chrom, start and end columns".to_string(),
+        })?;
+        queries.push(StabQuery {
+            chrom: String::from_utf8_lossy(chrom).into_owned(),
+            start,
+            end,
+        });
+    }
+    Ok(queries)
+}
+
+/// Stab command configuration.
+#[derive(Debug, Clone, Default)]
+pub struct StabCommand {
+    /// Write a `.gri` index next to the input before querying
+    pub build_index: bool,
+}
+
+impl StabCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the sidecar index (builder pattern).
+    pub fn with_build_index(mut self, build_index: bool) -> Self {
+        self.build_index = build_index;
+        self
+    }
+
+    /// Print the records of `input` overlapping each query, in query order.
+    ///
+    /// Returns the number of records written.
+    pub fn run<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        queries: &[StabQuery],
+        output: &mut W,
+    ) -> Result<u64, BedError> {
+        let input = input.as_ref();
+        if input.as_os_str() == "-" {
+            return Err(BedError::InvalidFormat(
+                "stab needs a seekable file; it cannot read from stdin".to_string(),
+            ));
+        }
+        let bed = IndexedBed::open(input, self.build_index)?;
+
+        let mut out = BufWriter::new(output);
+        let mut written = 0;
+        for query in queries {
+            for (line, _, _) in bed.query(&query.chrom, query.start, query.end) {
+                out.write_all(line)?;
+                out.write_all(b"\n")?;
+                written += 1;
+            }
+        }
+        out.flush()?;
+        Ok(written)
+    }
+}
+
+/// A memory-mapped sorted BED file with its chromosome index, for repeated
+/// region queries.
+pub struct IndexedBed {
+    mmap: Option<Mmap>,
+    index: FileIndex,
+}
+
+impl IndexedBed {
+    /// Map `path` and load its `.gri` sidecar, or index the file in memory
+    /// when there is none. With `build_index` the index is rebuilt and
+    /// written next to the file first.
+    pub fn open<P: AsRef<Path>>(path: P, build_index: bool) -> Result<Self, BedError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let mmap = if file.metadata()?.len() == 0 {
+            None
+        } else {
+            // SAFETY: the file is opened read-only and not modified while mapped.
+            Some(unsafe { Mmap::map(&file)? })
+        };
+        let data = mmap.as_deref().unwrap_or(&[]);
+
+        let index = if build_index {
+            let index = FileIndex::build(data, SourceStamp::of(path)?)?;
+            index.write_sidecar(path)?;
+            index
+        } else {
+            match FileIndex::load_sidecar(path)? {
+                Some(index) => index,
+                None => FileIndex::build(data, SourceStamp::of(path)?)?,
+            }
+        };
+        Ok(Self { mmap, index })
+    }
+
+    /// The chromosome index.
+    pub fn index(&self) -> &FileIndex {
+        &self.index
+    }
+
+    /// The first record line of the file, with the line ending stripped.
+    pub fn first_record(&self) -> Option<&[u8]> {
+        let data = self.mmap.as_deref().unwrap_or(&[]);
+        data.split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .find(|line| !should_skip_line(line))
+    }
+
+    /// Records overlapping `[start, end)` on `chrom`, in file order, as
+    /// `(line, start, end)` with the line ending stripped.
+    pub fn query(&self, chrom: &str, start: u64, end: u64) -> Overlaps<'_> {
+        let data = self.mmap.as_deref().unwrap_or(&[]);
+        match self.index.block(chrom) {
+            Some(block) => Overlaps::new(data, block, start, end),
+            None => Overlaps {
+                data: &[],
+                pos: 0,
+                start,
+                end,
+            },
+        }
+    }
+}
+
+/// Iterator over the records of one chromosome block that overlap a region.
+pub struct Overlaps<'a> {
+    data: &'a [u8],
+    pos: usize,
+    start: u64,
+    end: u64,
+}
+
+impl<'a> Overlaps<'a> {
+    fn new(data: &'a [u8], block: &ChromBlock, start: u64, end: u64) -> Self {
+        let block_end = (block.end as usize).min(data.len());
+        let block_start = (block.offset as usize).min(block_end);
+        let data = &data[..block_end];
+
+        // No record longer than max_len, so nothing starting before this can
+        // reach the query.
+        let pos = first_start_at_least(data, block_start, start.saturating_sub(block.max_len));
+        Self {
+            data,
+            pos,
+            start,
+            end,
+        }
+    }
+}
+
+impl<'a> Iterator for Overlaps<'a> {
+    type Item = (&'a [u8], u64, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.data.len() {
+            let line_end = line_end(self.data, self.pos);
+            let line = &self.data[self.pos..line_end];
+            self.pos = line_end + 1;
+            if should_skip_line(line) {
+                continue;
+            }
+            let Some((_, s, e)) = parse_bed3_bytes(line) else {
+                continue;
+            };
+            if s >= self.end {
+                self.pos = self.data.len();
+                break;
+            }
+            if e > self.start {
+                return Some((line.strip_suffix(b"\r").unwrap_or(line), s, e));
+            }
+        }
+        None
+    }
+}
+
+/// Byte offset of the first record in `data[lo..]` whose start is at least
+/// `target`, or `data.len()` if there is none.
+fn first_start_at_least(data: &[u8], mut lo: usize, target: u64) -> usize {
+    let mut hi = data.len();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let line_start = memchr::memrchr(b'\n', &data[lo..mid]).map_or(lo, |i| lo + i + 1);
+
+        // Comment lines carry no position; compare the next record instead.
+        let mut probe = line_start;
+        let mut key = None;
+        while probe < hi {
+            let probe_end = line_end(data, probe);
+            let line = &data[probe..probe_end];
+            if !is_header(line) {
+                key = parse_bed3_bytes(line).map(|(_, s, _)| (s, probe_end));
+                break;
+            }
+            probe = probe_end + 1;
+        }
+
+        match key {
+            Some((s, probe_end)) if s < target => lo = probe_end + 1,
+            _ => hi = line_start,
+        }
+    }
+    lo.min(data.len())
+}
+
+#[inline]
+fn line_end(data: &[u8], pos: usize) -> usize {
+    memchr::memchr(b'\n', &data[pos..]).map_or(data.len(), |i| pos + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stab(data: &[u8], start: u64, end: u64) -> String {
+        let index = FileIndex::build(
+            data,
+            SourceStamp {
+                len: 0,
+                mtime: 0,
+                nanos: None,
+            },
+        )
+        .unwrap();
+        let mut out = String::new();
+        for block in &index.blocks {
+            for (line, _, _) in Overlaps::new(data, block, start, end) {
+                out.push_str(std::str::from_utf8(line).unwrap());
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let q = StabQuery::parse("chr1:12,345").unwrap();
+        assert_eq!((q.chrom.as_str(), q.start, q.end), ("chr1", 12344, 12345));
+        let q = StabQuery::parse("HLA-A:1-100").unwrap();
+        assert_eq!((q.chrom.as_str(), q.start, q.end), ("HLA-A", 0, 100));
+        assert!(StabQuery::parse("chr1").is_err());
+        assert!(StabQuery::parse("chr1:0").is_err());
+        assert!(StabQuery::parse("chr1:10-5").is_err());
+    }
+
+    #[test]
+    fn test_stab_uses_longest_record() {
+        let data =
+            b"chr1\t0\t1000\tlong\nchr1\t100\t110\ta\nchr1\t200\t210\tb\nchr1\t500\t600\tc\n";
+        assert_eq!(
+            stab(data, 204, 205),
+            "chr1\t0\t1000\tlong\nchr1\t200\t210\tb\n"
+        );
+        assert_eq!(stab(data, 1000, 1001), "");
+        assert_eq!(
+            stab(data, 99, 201),
+            "chr1\t0\t1000\tlong\nchr1\t100\t110\ta\nchr1\t200\t210\tb\n"
+        );
+    }
+
+    #[test]
+    fn test_stab_skips_comments_inside_block() {
+        let mut data = Vec::new();
+        for i in 0..50u64 {
+            data.extend_from_slice(format!("chr1\t{}\t{}\n", i * 10, i * 10 + 5).as_bytes());
+            if i % 7 == 0 {
+                data.extend_from_slice(b"# note\n");
+            }
+        }
+        assert_eq!(stab(&data, 302, 303), "chr1\t300\t305\n");
+        assert_eq!(stab(&data, 0, 1), "chr1\t0\t5\n");
+        assert_eq!(stab(&data, 491, 492), "chr1\t490\t495\n");
+        assert_eq!(stab(&data, 306, 309), "");
+    }
+
+    #[test]
+    fn test_first_start_matches_linear_scan() {
+        let data = b"c\t1\t2\nc\t3\t4\nc\t3\t9\nc\t8\t9\nc\t20\t21";
+        for target in 0..25 {
+            let linear = data
+                .split(|&b| b == b'\n')
+                .scan(0, |pos, line| {
+                    let here = *pos;
+                    *pos += line.len() + 1;
+                    Some((here, parse_bed3_bytes(line).unwrap().1))
+                })
+                .find(|&(_, s)| s >= target)
+                .map_or(data.len(), |(p, _)| p);
+            assert_eq!(
+                first_start_at_least(data, 0, target),
+                linear,
+                "target {}",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn test_indexed_bed_query() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"#header\nchr1\t10\t20\ta\nchr1\t15\t30\tb\r\nchr2\t5\t6\tc\n")
+            .unwrap();
+        file.flush().unwrap();
+        let bed = IndexedBed::open(file.path(), false).unwrap();
+
+        let hits: Vec<_> = bed.query("chr1", 18, 19).collect();
+        assert_eq!(
+            hits,
+            vec![
+                (&b"chr1\t10\t20\ta"[..], 10, 20),
+                (&b"chr1\t15\t30\tb"[..], 15, 30)
+            ]
+        );
+        assert_eq!(bed.query("chr1", 20, 25).count(), 1);
+        assert_eq!(bed.query("chr2", 0, 5).count(), 0);
+        assert_eq!(bed.query("chr3", 0, 100).count(), 0);
+    }
+}
@@ -7,7 +7,7 @@
 //! - No per-record String allocation (raw byte parsing with memchr)
 //! - Vec + head index for active set (no VecDeque)
 //! - itoa for integer formatting
-//! - Large buffered I/O (256KB input, 8MB output)
+//! - Large buffered input (256KB) and batched output (1MB coalesced writes)
 //!
 //! # Memory Complexity
 //!
@@ -25,11 +25,11 @@
 //! Both input files MUST be sorted by chromosome (lexicographic), then by start position.
 
 use crate::bed::BedError;
-use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use crate::streaming::buffers::DEFAULT_INPUT_BUFFER;
+use crate::streaming::parsing::{column_bytes, parse_bed3_bytes, should_skip_line};
+use crate::streaming::read_ahead::open_input;
+use crate::streaming::{BatchWriter, ChromTable, DedupReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
 /// Active B interval - stores coordinates and original line for output.
@@ -52,6 +52,14 @@ pub struct StreamingClosestCommand {
     pub ignore_downstream: bool,
     /// Report all ties (bedtools -t all, default true)
     pub report_all_ties: bool,
+    /// Append the signed distance to B (-d flag)
+    pub report_distance: bool,
+    /// Report one tied B per value of this 1-based B column (e.g. 4 for the name)
+    pub dedup_b_col: Option<usize>,
+    /// Collapse exact duplicate B records before searching
+    pub dedup_b: bool,
+    /// With `dedup_b`, append each B record's multiplicity as a last column
+    pub dedup_b_counts: bool,
 }
 
 impl Default for StreamingClosestCommand {
@@ -67,6 +75,10 @@ impl StreamingClosestCommand {
             ignore_upstream: false,
             ignore_downstream: false,
             report_all_ties: true,
+            report_distance: false,
+            dedup_b_col: None,
+            dedup_b: false,
+            dedup_b_counts: false,
         }
     }
 
@@ -79,16 +91,28 @@ impl StreamingClosestCommand {
         b_path: P,
         output: &mut W,
     ) -> Result<StreamingClosestStats, BedError> {
-        // Output buffer (2MB default, reduced from 8MB for memory efficiency)
-        let mut output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
+        let b_file = open_input(b_path.as_ref())?;
+        let b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
+        if self.dedup_b {
+            let b_reader = DedupReader::new(b_reader).with_counts(self.dedup_b_counts);
+            return self.run_with_b(a_path, b_reader, output);
+        }
+        self.run_with_b(a_path, b_reader, output)
+    }
+
+    fn run_with_b<P: AsRef<Path>, R: BufRead, W: Write>(
+        &self,
+        a_path: P,
+        mut b_reader: R,
+        output: &mut W,
+    ) -> Result<StreamingClosestStats, BedError> {
+        // Coalesce output records into large writes
+        let mut output = BatchWriter::new(output);
 
         // Stream files
-        let a_file = File::open(a_path.as_ref())?;
+        let a_file = open_input(a_path.as_ref())?;
         let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
 
-        let b_file = File::open(b_path.as_ref())?;
-        let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
-
         // Reusable line buffers
         let mut a_line_buf = String::with_capacity(1024);
         let mut b_line_buf = String::with_capacity(1024);
@@ -102,9 +126,9 @@ impl StreamingClosestCommand {
         let mut b_exhausted = pending_b.is_none();
 
         // Track seen B chromosomes to handle any sort order
-        let mut seen_b_chroms: HashSet<Vec<u8>> = HashSet::new();
+        let mut seen_b_chroms = ChromTable::new();
         if !b_exhausted {
-            seen_b_chroms.insert(b_chrom.clone());
+            seen_b_chroms.intern(&b_chrom);
         }
 
         // Active set: B intervals that might overlap current or future A
@@ -122,6 +146,8 @@ impl StreamingClosestCommand {
 
         // Stats
         let mut stats = StreamingClosestStats::default();
+        // Dedup column values already reported for the current A
+        let mut seen_keys: Vec<Vec<u8>> = Vec::new();
 
         // Main loop
         loop {
@@ -167,7 +193,7 @@ impl StreamingClosestCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -251,7 +277,7 @@ impl StreamingClosestCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                         continue;
                     } else {
                         // B is on the same chromosome as A
@@ -276,7 +302,7 @@ impl StreamingClosestCommand {
                                 )?;
                                 if let Some(nb) = next_b {
                                     stats.b_intervals += 1;
-                                    seen_b_chroms.insert(b_chrom.clone());
+                                    seen_b_chroms.intern(&b_chrom);
                                     if b_chrom.as_slice() != chrom {
                                         pending_b = Some(nb);
                                         break;
@@ -315,7 +341,7 @@ impl StreamingClosestCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -327,6 +353,7 @@ impl StreamingClosestCommand {
             // Find closest
             let active_slice = &active[head_idx..];
             let mut min_dist: u64 = u64::MAX;
+            seen_keys.clear();
 
             // Check overlaps in active set
             let mut overlaps: Vec<&ActiveB> = Vec::new();
@@ -344,12 +371,24 @@ impl StreamingClosestCommand {
             if !overlaps.is_empty() {
                 if self.report_all_ties {
                     for b in &overlaps {
-                        Self::write_pair(&mut output, line_bytes, &b.line)?;
-                        stats.pairs_written += 1;
+                        self.write_hit(
+                            &mut output,
+                            &mut stats,
+                            &mut seen_keys,
+                            line_bytes,
+                            &b.line,
+                            0,
+                        )?;
                     }
                 } else {
-                    Self::write_pair(&mut output, line_bytes, &overlaps[0].line)?;
-                    stats.pairs_written += 1;
+                    self.write_hit(
+                        &mut output,
+                        &mut stats,
+                        &mut seen_keys,
+                        line_bytes,
+                        &overlaps[0].line,
+                        0,
+                    )?;
                 }
                 continue;
             }
@@ -413,63 +452,123 @@ impl StreamingClosestCommand {
             // Output results
             if min_dist == u64::MAX {
                 // No closest found
-                Self::write_no_closest(&mut output, line_bytes)?;
+                self.write_no_closest(&mut output, line_bytes)?;
             } else if upstream_dist == downstream_dist && upstream_dist == min_dist {
                 // Tie between upstream and downstream
                 if self.report_all_ties {
                     for lc in &left_candidates {
-                        Self::write_pair(&mut output, line_bytes, &lc.line)?;
-                        stats.pairs_written += 1;
+                        self.write_hit(
+                            &mut output,
+                            &mut stats,
+                            &mut seen_keys,
+                            line_bytes,
+                            &lc.line,
+                            upstream_dist as i64,
+                        )?;
                     }
                     if use_active_downstream {
                         for b in &active_downstream {
-                            Self::write_pair(&mut output, line_bytes, &b.line)?;
-                            stats.pairs_written += 1;
+                            self.write_hit(
+                                &mut output,
+                                &mut stats,
+                                &mut seen_keys,
+                                line_bytes,
+                                &b.line,
+                                downstream_dist as i64,
+                            )?;
                         }
                     }
                     if use_right_candidates {
                         for rc in &right_candidates {
-                            Self::write_pair(&mut output, line_bytes, &rc.line)?;
-                            stats.pairs_written += 1;
+                            self.write_hit(
+                                &mut output,
+                                &mut stats,
+                                &mut seen_keys,
+                                line_bytes,
+                                &rc.line,
+                                downstream_dist as i64,
+                            )?;
                         }
                     }
                 } else if !left_candidates.is_empty() {
-                    Self::write_pair(&mut output, line_bytes, &left_candidates[0].line)?;
-                    stats.pairs_written += 1;
+                    self.write_hit(
+                        &mut output,
+                        &mut stats,
+                        &mut seen_keys,
+                        line_bytes,
+                        &left_candidates[0].line,
+                        upstream_dist as i64,
+                    )?;
                 }
             } else if upstream_dist == min_dist {
                 if self.report_all_ties {
                     for lc in &left_candidates {
-                        Self::write_pair(&mut output, line_bytes, &lc.line)?;
-                        stats.pairs_written += 1;
+                        self.write_hit(
+                            &mut output,
+                            &mut stats,
+                            &mut seen_keys,
+                            line_bytes,
+                            &lc.line,
+                            upstream_dist as i64,
+                        )?;
                     }
                 } else if !left_candidates.is_empty() {
-                    Self::write_pair(&mut output, line_bytes, &left_candidates[0].line)?;
-                    stats.pairs_written += 1;
+                    self.write_hit(
+                        &mut output,
+                        &mut stats,
+                        &mut seen_keys,
+                        line_bytes,
+                        &left_candidates[0].line,
+                        upstream_dist as i64,
+                    )?;
                 }
             } else if downstream_dist == min_dist {
                 if self.report_all_ties {
                     if use_active_downstream {
                         for b in &active_downstream {
-                            Self::write_pair(&mut output, line_bytes, &b.line)?;
-                            stats.pairs_written += 1;
+                            self.write_hit(
+                                &mut output,
+                                &mut stats,
+                                &mut seen_keys,
+                                line_bytes,
+                                &b.line,
+                                downstream_dist as i64,
+                            )?;
                         }
                     }
                     if use_right_candidates {
                         for rc in &right_candidates {
-                            Self::write_pair(&mut output, line_bytes, &rc.line)?;
-                            stats.pairs_written += 1;
+                            self.write_hit(
+                                &mut output,
+                                &mut stats,
+                                &mut seen_keys,
+                                line_bytes,
+                                &rc.line,
+                                downstream_dist as i64,
+                            )?;
                         }
                     }
                 } else if use_active_downstream && !active_downstream.is_empty() {
-                    Self::write_pair(&mut output, line_bytes, &active_downstream[0].line)?;
-                    stats.pairs_written += 1;
+                    self.write_hit(
+                        &mut output,
+                        &mut stats,
+                        &mut seen_keys,
+                        line_bytes,
+                        &active_downstream[0].line,
+                        downstream_dist as i64,
+                    )?;
                 } else if use_right_candidates && !right_candidates.is_empty() {
-                    Self::write_pair(&mut output, line_bytes, &right_candidates[0].line)?;
-                    stats.pairs_written += 1;
+                    self.write_hit(
+                        &mut output,
+                        &mut stats,
+                        &mut seen_keys,
+                        line_bytes,
+                        &right_candidates[0].line,
+                        downstream_dist as i64,
+                    )?;
                 }
             } else {
-                Self::write_no_closest(&mut output, line_bytes)?;
+                self.write_no_closest(&mut output, line_bytes)?;
             }
         }
 
@@ -486,8 +585,8 @@ impl StreamingClosestCommand {
     /// Read next B interval.
     /// Returns Err on IO error, Ok(None) on EOF, Ok(Some) on success.
     #[inline]
-    fn read_next_b(
-        reader: &mut BufReader<File>,
+    fn read_next_b<R: BufRead>(
+        reader: &mut R,
         line_buf: &mut String,
         chrom_buf: &mut Vec<u8>,
     ) -> Result<Option<ActiveB>, BedError> {
@@ -522,22 +621,58 @@ impl StreamingClosestCommand {
         }
     }
 
+    /// Write an A/B pair, followed by the distance when `-d` is set.
     #[inline]
-    fn write_pair<W: Write>(output: &mut W, a_line: &[u8], b_line: &[u8]) -> Result<(), BedError> {
-        output.write_all(a_line).map_err(BedError::Io)?;
-        output.write_all(b"\t").map_err(BedError::Io)?;
-        output.write_all(b_line).map_err(BedError::Io)?;
-        output.write_all(b"\n").map_err(BedError::Io)?;
-        Ok(())
+    fn write_pair<W: Write>(
+        &self,
+        output: &mut BatchWriter<W>,
+        a_line: &[u8],
+        b_line: &[u8],
+        distance: i64,
+    ) -> Result<(), BedError> {
+        if self.report_distance {
+            output.write_pair_with_int(a_line, b_line, distance)
+        } else {
+            output.write_pair(a_line, b_line)
+        }
+        .map_err(BedError::Io)
     }
 
+    /// Write a closest pair unless a tie with the same dedup key was written.
     #[inline]
-    fn write_no_closest<W: Write>(output: &mut W, a_line: &[u8]) -> Result<(), BedError> {
-        output.write_all(a_line).map_err(BedError::Io)?;
-        output.write_all(b"\t.\t-1\t-1").map_err(BedError::Io)?;
-        output.write_all(b"\n").map_err(BedError::Io)?;
+    fn write_hit<W: Write>(
+        &self,
+        output: &mut BatchWriter<W>,
+        stats: &mut StreamingClosestStats,
+        seen_keys: &mut Vec<Vec<u8>>,
+        a_line: &[u8],
+        b_line: &[u8],
+        distance: i64,
+    ) -> Result<(), BedError> {
+        let key = self
+            .dedup_b_col
+            .and_then(|col| column_bytes(b_line, col))
+            .filter(|k| *k != b".");
+        if let Some(key) = key {
+            if seen_keys.iter().any(|k| k == key) {
+                return Ok(());
+            }
+            seen_keys.push(key.to_vec());
+        }
+        self.write_pair(output, a_line, b_line, distance)?;
+        stats.pairs_written += 1;
         Ok(())
     }
+
+    /// Write the placeholder row for an A record with no eligible B.
+    #[inline]
+    fn write_no_closest<W: Write>(
+        &self,
+        output: &mut BatchWriter<W>,
+        a_line: &[u8],
+    ) -> Result<(), BedError> {
+        self.write_pair(output, a_line, b".\t-1\t-1", -1)
+    }
 }
 
 /// Statistics from streaming closest operation.
@@ -935,4 +1070,60 @@ mod tests {
         let lines: Vec<_> = result.lines().collect();
         assert_eq!(lines.len(), 1, "Should report only first tie: {}", result);
     }
+
+    #[test]
+    fn test_report_distance() {
+        let a_file = create_temp_bed("chr1\t100\t200\nchr1\t500\t600\nchr2\t10\t20\n");
+        let b_file = create_temp_bed("chr1\t150\t160\nchr1\t300\t350");
+
+        let mut cmd = StreamingClosestCommand::new();
+        cmd.report_distance = true;
+        cmd.ignore_overlaps = true;
+
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\tchr1\t300\t350\t101\n\
+             chr1\t500\t600\tchr1\t300\t350\t151\n\
+             chr2\t10\t20\t.\t-1\t-1\t-1\n"
+        );
+    }
+
+    #[test]
+    fn test_header_only_b() {
+        let a_file = create_temp_bed("chr1\t100\t200");
+        let b_file = create_temp_bed("#header\ntrack name=empty\n\n");
+
+        let mut cmd = StreamingClosestCommand::new();
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(output, b"chr1\t100\t200\t.\t-1\t-1\n");
+
+        cmd.report_distance = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(output, b"chr1\t100\t200\t.\t-1\t-1\t-1\n");
+    }
+
+    #[test]
+    fn test_dedup_b_name() {
+        let a_file = create_temp_bed("chr1\t100\t200\nchr1\t1000\t1100\n");
+        let b_file = create_temp_bed(
+            "chr1\t300\t400\tg1\nchr1\t300\t400\tg1\nchr1\t300\t400\tg2\n\
+             chr1\t1050\t1060\tg3\nchr1\t1050\t1070\tg3\n",
+        );
+
+        let mut cmd = StreamingClosestCommand::new();
+        cmd.dedup_b_col = Some(4);
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\tchr1\t300\t400\tg1\n\
+             chr1\t100\t200\tchr1\t300\t400\tg2\n\
+             chr1\t1000\t1100\tchr1\t1050\t1060\tg3\n"
+        );
+    }
 }
@@ -11,12 +11,22 @@
 //!
 //! REQUIREMENT: Both A and B must be sorted by (chrom, start) in same order.
 //! Use `--assume-sorted` flag or pre-sort with `grit sort`.
+//!
+//! Several A files can share one pass over B: they are merged into a single
+//! sorted stream with a loser tree, and each A record's result goes to the
+//! output of the file it came from. Reading a huge B once is then amortized
+//! over many small region sets.
 
 use crate::bed::BedError;
+use crate::genome::Genome;
 use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
-use crate::streaming::ActiveInterval;
-use std::fs::File;
+use crate::streaming::parsing::{
+    column_bytes, parse_bed3_bytes, parse_bed3_bytes_with_rest, should_skip_line,
+};
+use crate::streaming::read_ahead::{open_input, InputFile};
+use crate::streaming::{union_by_name, ActiveInterval, LoserTree, NameWindow, ScoreFilter};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
@@ -26,6 +36,21 @@ pub struct StreamingCoverageCommand {
     pub histogram: bool,
     pub per_base: bool,
     pub mean: bool,
+    /// With `mean`, also report the standard deviation of per-base depth.
+    pub stddev: bool,
+    /// With `mean`, also report these quantiles (0.0-1.0) of per-base depth.
+    pub quantiles: Vec<f64>,
+    /// Drop B records whose score is outside these bounds.
+    pub b_score: ScoreFilter,
+    /// Split each A interval where B depth changes class, instead of
+    /// reporting coverage.
+    pub segment: Option<DepthClasses>,
+    /// Report the diversity of B classes over each A interval instead of
+    /// coverage, reading the class from this 1-based B column.
+    pub diversity: Option<usize>,
+    /// Count B records sharing a name (column 4) once toward depth, so mates
+    /// and split alignments count as one fragment. Ignored with `diversity`.
+    pub collapse_by_name: bool,
 }
 
 impl Default for StreamingCoverageCommand {
@@ -34,6 +59,256 @@ impl Default for StreamingCoverageCommand {
     }
 }
 
+/// Depth classes for `grit segment`.
+///
+/// Breakpoints `b1 < b2 < ... < bk` divide depth into `[0, b1)`, `[b1, b2)`,
+/// ..., `[bk, inf)`. The default breakpoints `1,2` give the classes `0`, `1`
+/// and `2+`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthClasses {
+    breaks: Vec<u32>,
+    labels: Vec<String>,
+}
+
+impl Default for DepthClasses {
+    fn default() -> Self {
+        Self::new(vec![1, 2]).expect("default breakpoints are valid")
+    }
+}
+
+impl DepthClasses {
+    /// Classes from strictly increasing, positive breakpoints.
+    pub fn new(breaks: Vec<u32>) -> Result<Self, BedError> {
+        if breaks.first() == Some(&0) || breaks.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(BedError::InvalidFormat(format!(
+                "depth breakpoints must be positive and strictly increasing, got {:?}",
+                breaks
+            )));
+        }
+        let labels = (0..=breaks.len())
+            .map(|i| {
+                let low = if i == 0 { 0 } else { breaks[i - 1] };
+                match breaks.get(i) {
+                    None => format!("{}+", low),
+                    Some(&high) if high == low + 1 => low.to_string(),
+                    Some(&high) => format!("{}-{}", low, high - 1),
+                }
+            })
+            .collect();
+        Ok(Self { breaks, labels })
+    }
+
+    /// Class index of a depth.
+    #[inline]
+    pub fn class_of(&self, depth: u32) -> usize {
+        self.breaks.partition_point(|&b| b <= depth)
+    }
+
+    /// Label of a class, e.g. `0`, `1-4` or `5+`.
+    pub fn label(&self, class: usize) -> &str {
+        &self.labels[class]
+    }
+}
+
+/// How much of B falls within A, gathered alongside coverage.
+///
+/// A B record counts as within A if at least one of its bases is covered by
+/// an A interval; bases covered by several A intervals count once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReciprocalSummary {
+    /// B records read (after score filtering).
+    pub records: u64,
+    /// B records overlapping A.
+    pub records_in_a: u64,
+    /// Total length of the B records.
+    pub bases: u64,
+    /// Bases of B records covered by A.
+    pub bases_in_a: u64,
+}
+
+impl ReciprocalSummary {
+    /// Fraction of B records overlapping A (0 for an empty B).
+    pub fn record_fraction(&self) -> f64 {
+        fraction(self.records_in_a, self.records)
+    }
+
+    /// Fraction of B bases covered by A (0 for an empty B).
+    pub fn base_fraction(&self) -> f64 {
+        fraction(self.bases_in_a, self.bases)
+    }
+
+    /// Write the header line for [`write_row`](Self::write_row).
+    pub fn write_header<W: Write>(output: &mut W) -> Result<(), BedError> {
+        output.write_all(
+            b"#b_file\trecords\trecords_in_a\trecord_fraction\tbases\tbases_in_a\tbase_fraction\n",
+        )?;
+        Ok(())
+    }
+
+    /// Write one tab-separated row labelled with the B file name.
+    pub fn write_row<W: Write>(&self, output: &mut W, b_name: &str) -> Result<(), BedError> {
+        writeln!(
+            output,
+            "{}\t{}\t{}\t{:.6}\t{}\t{}\t{:.6}",
+            b_name,
+            self.records,
+            self.records_in_a,
+            self.record_fraction(),
+            self.bases,
+            self.bases_in_a,
+            self.base_fraction()
+        )?;
+        Ok(())
+    }
+
+    /// Count a B record as read.
+    #[inline]
+    fn add_record(&mut self, b: PendingB) {
+        self.records += 1;
+        self.bases += b.end.saturating_sub(b.start) as u64;
+    }
+}
+
+#[inline]
+fn fraction(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// A source of A lines, each tagged with the output it belongs to.
+trait ASource {
+    /// Read the next line into `buf` and return its output index, or `None`
+    /// at the end of the input.
+    fn next_line(&mut self, buf: &mut String) -> Result<Option<usize>, BedError>;
+}
+
+/// A single A file; every line goes to output 0.
+impl<R: BufRead> ASource for R {
+    #[inline]
+    fn next_line(&mut self, buf: &mut String) -> Result<Option<usize>, BedError> {
+        Ok((self.read_line(buf)? > 0).then_some(0))
+    }
+}
+
+/// Head record of one A file in a merge.
+#[derive(Debug)]
+struct AHead {
+    /// Position of the chromosome in the genome file (`u32::MAX` if absent)
+    rank: u32,
+    chrom: Vec<u8>,
+    start: u64,
+    line: String,
+}
+
+impl AHead {
+    #[inline]
+    fn key(&self) -> (u32, &[u8], u64) {
+        (self.rank, &self.chrom, self.start)
+    }
+}
+
+impl PartialEq for AHead {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for AHead {}
+
+impl PartialOrd for AHead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AHead {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Several sorted A files merged by (chrom, start). Chromosomes follow the
+/// genome file order when one is given, otherwise lexicographic order.
+struct MergedA {
+    readers: Vec<BufReader<InputFile>>,
+    tree: LoserTree<AHead>,
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+impl MergedA {
+    fn open<P: AsRef<Path>>(paths: &[P], genome: Option<&Genome>) -> Result<Self, BedError> {
+        let ranks = genome
+            .map(|g| {
+                g.chromosomes()
+                    .enumerate()
+                    .map(|(i, c)| (c.as_bytes().to_vec(), i as u32))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut readers = paths
+            .iter()
+            .map(|p| {
+                Ok(BufReader::with_capacity(
+                    DEFAULT_INPUT_BUFFER,
+                    open_input(p)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, BedError>>()?;
+        let heads = readers
+            .iter_mut()
+            .map(|r| Self::read_head(r, &ranks))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            readers,
+            tree: LoserTree::new(heads),
+            ranks,
+        })
+    }
+
+    /// Next record of one file; lines without coordinates are dropped, as
+    /// the single-file sweep skips them too.
+    fn read_head(
+        reader: &mut BufReader<InputFile>,
+        ranks: &HashMap<Vec<u8>, u32>,
+    ) -> Result<Option<AHead>, BedError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let bytes = line.trim_end().as_bytes();
+            if should_skip_line(bytes) {
+                continue;
+            }
+            if let Some((chrom, start, _)) = parse_bed3_bytes(bytes) {
+                return Ok(Some(AHead {
+                    rank: ranks.get(chrom).copied().unwrap_or(u32::MAX),
+                    chrom: chrom.to_vec(),
+                    start,
+                    line,
+                }));
+            }
+        }
+    }
+}
+
+impl ASource for MergedA {
+    fn next_line(&mut self, buf: &mut String) -> Result<Option<usize>, BedError> {
+        if self.tree.peek().is_none() {
+            return Ok(None);
+        }
+        let file = self.tree.winner();
+        let next = Self::read_head(&mut self.readers[file], &self.ranks)?;
+        let head = self.tree.replace_winner(next).expect("tree has a winner");
+        buf.push_str(&head.line);
+        Ok(Some(file))
+    }
+}
+
 /// Pending B interval - stores coordinates only, chrom tracked separately.
 #[derive(Debug, Clone, Copy)]
 struct PendingB {
@@ -47,9 +322,34 @@ impl StreamingCoverageCommand {
             histogram: false,
             per_base: false,
             mean: false,
+            stddev: false,
+            quantiles: Vec::new(),
+            b_score: ScoreFilter::default(),
+            segment: None,
+            diversity: None,
+            collapse_by_name: false,
         }
     }
 
+    /// Whether B records are grouped by name.
+    #[inline]
+    fn collapses(&self) -> bool {
+        self.collapse_by_name && self.diversity.is_none()
+    }
+
+    /// B column read along with each record: the diversity class, or the
+    /// name when collapsing.
+    #[inline]
+    fn b_key_column(&self) -> Option<usize> {
+        self.diversity.or(self.collapses().then_some(4))
+    }
+
+    /// Check if mean mode reports more than the mean itself.
+    #[inline]
+    fn has_depth_stats(&self) -> bool {
+        self.stddev || !self.quantiles.is_empty()
+    }
+
     /// Execute TRUE O(k) streaming coverage.
     ///
     /// Memory: O(k) where k = max overlapping B intervals.
@@ -62,15 +362,76 @@ impl StreamingCoverageCommand {
         b_path: P,
         output: &mut W,
     ) -> Result<(), BedError> {
-        // Output buffer (2MB default, reduced from 8MB for memory efficiency)
-        let mut output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
+        let a_file = open_input(&a_path)?;
+        let a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
+        self.run_inner(a_reader, b_path, std::slice::from_mut(output), None)
+    }
 
-        // Stream A file
-        let a_file = File::open(&a_path)?;
-        let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
+    /// Compute the coverage of several A files in one pass over B.
+    ///
+    /// The results for `a_paths[i]` are written to `outputs[i]`, in the same
+    /// form as [`run`](Self::run) would write them. All A files must be
+    /// sorted like B: by the chromosome order of `genome` if given, otherwise
+    /// lexicographically.
+    pub fn run_multi<P: AsRef<Path>, Q: AsRef<Path>, W: Write>(
+        &self,
+        a_paths: &[P],
+        b_path: Q,
+        outputs: &mut [W],
+        genome: Option<&Genome>,
+    ) -> Result<(), BedError> {
+        if a_paths.len() != outputs.len() {
+            return Err(BedError::InvalidFormat(format!(
+                "{} A files but {} outputs",
+                a_paths.len(),
+                outputs.len()
+            )));
+        }
+        let a_source = MergedA::open(a_paths, genome)?;
+        self.run_inner(a_source, b_path, outputs, None)
+    }
+
+    /// Like [`run`](Self::run), but also measure how much of B lies within A.
+    ///
+    /// The summary is collected in the same pass, so it costs one extra
+    /// counter per active B interval instead of a second run with A and B
+    /// swapped. B records past the last A interval are still read to count
+    /// them.
+    pub fn run_reciprocal<P: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b_path: P,
+        output: &mut W,
+    ) -> Result<ReciprocalSummary, BedError> {
+        let mut summary = ReciprocalSummary::default();
+        let a_file = open_input(&a_path)?;
+        let a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
+        self.run_inner(
+            a_reader,
+            b_path,
+            std::slice::from_mut(output),
+            Some(&mut summary),
+        )?;
+        Ok(summary)
+    }
+
+    fn run_inner<A: ASource, Q: AsRef<Path>, W: Write>(
+        &self,
+        mut a_source: A,
+        b_path: Q,
+        outputs: &mut [W],
+        mut summary: Option<&mut ReciprocalSummary>,
+    ) -> Result<(), BedError> {
+        // Output buffers (2MB default, reduced from 8MB for memory
+        // efficiency), shared out between the outputs of a multi-A run
+        let capacity = (DEFAULT_OUTPUT_BUFFER / outputs.len().max(1)).max(64 * 1024);
+        let mut outputs: Vec<BufWriter<&mut W>> = outputs
+            .iter_mut()
+            .map(|output| BufWriter::with_capacity(capacity, output))
+            .collect();
 
         // Stream B file
-        let b_file = File::open(&b_path)?;
+        let b_file = open_input(&b_path)?;
         let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
 
         // Reusable line buffers (no per-line allocation)
@@ -82,26 +443,46 @@ impl StreamingCoverageCommand {
 
         // Pending B record: chrom stored separately, only (start, end) in struct
         let mut b_chrom: Vec<u8> = Vec::with_capacity(64);
-        let mut pending_b = Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+        // Class column of the pending B (with `diversity`), or its name
+        // (with `collapse_by_name`)
+        let mut b_class: Vec<u8> = Vec::new();
+        let mut pending_b =
+            self.read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom, &mut b_class)?;
         let mut b_exhausted = pending_b.is_none();
 
         // Active set: Vec with head index (no VecDeque, no make_contiguous)
         let mut active: Vec<ActiveInterval> = Vec::with_capacity(1024);
         let mut head_idx: usize = 0;
+        // With a summary: end of the part of each active B already covered by
+        // A, parallel to `active`. A is start-sorted, so that part is a prefix
+        // of what any later A can cover.
+        let mut covered_to: Vec<u64> = Vec::new();
+        // With `diversity`: class id of each active B, parallel to `active`
+        let mut active_class: Vec<u32> = Vec::new();
+        let mut class_ids: HashMap<Vec<u8>, u32> = HashMap::new();
+        let mut class_bases: HashMap<u32, u64> = HashMap::new();
+        // With `collapse_by_name`: name id of each active B, parallel to
+        // `active`, and the per-A union of each name's intervals
+        let mut active_name: Vec<u64> = Vec::new();
+        let mut names = NameWindow::new();
+        let mut name_pieces: Vec<(u64, u64, u64)> = Vec::new();
+        let mut collapsed: Vec<ActiveInterval> = Vec::new();
 
         // itoa buffer for fast integer formatting
         let mut itoa_buf = itoa::Buffer::new();
 
         // Reusable event buffer for mean/histogram modes
         let mut events_buf: Vec<(u64, i32)> = Vec::with_capacity(2048);
+        // Reusable (depth, bases) runs for mean-mode stddev/quantiles
+        let mut runs_buf: Vec<(u32, u64)> = Vec::with_capacity(256);
 
         // Main loop: stream A records
         loop {
             a_line_buf.clear();
-            let bytes_read = a_reader.read_line(&mut a_line_buf)?;
-            if bytes_read == 0 {
+            let Some(output_idx) = a_source.next_line(&mut a_line_buf)? else {
                 break;
-            }
+            };
+            let output = &mut outputs[output_idx];
 
             let line = a_line_buf.trim_end();
             let line_bytes = line.as_bytes();
@@ -128,6 +509,10 @@ impl StreamingCoverageCommand {
 
                 // Clear active set on chromosome change
                 active.clear();
+                covered_to.clear();
+                active_class.clear();
+                active_name.clear();
+                names.clear();
                 head_idx = 0;
 
                 // Skip B records until we reach this chromosome or exhaust B.
@@ -135,8 +520,15 @@ impl StreamingCoverageCommand {
                 // Both A and B must be sorted in the SAME order, but that order can be either.
                 if !b_exhausted {
                     while b_chrom.as_slice() != chrom {
-                        pending_b =
-                            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        if let (Some(summary), Some(b)) = (summary.as_deref_mut(), pending_b) {
+                            summary.add_record(b);
+                        }
+                        pending_b = self.read_next_b(
+                            &mut b_reader,
+                            &mut b_line_buf,
+                            &mut b_chrom,
+                            &mut b_class,
+                        )?;
                         if pending_b.is_none() {
                             b_exhausted = true;
                             break;
@@ -147,7 +539,15 @@ impl StreamingCoverageCommand {
 
             // Handle zero-length intervals
             if a_len == 0 {
-                self.write_zero_coverage(&mut output, line, 0, &mut itoa_buf)?;
+                if self.segment.is_some() {
+                    continue;
+                }
+                if self.diversity.is_some() {
+                    class_bases.clear();
+                    Self::write_diversity(output, line_bytes, &class_bases)?;
+                    continue;
+                }
+                self.write_zero_coverage(output, line, 0, &mut itoa_buf)?;
                 continue;
             }
 
@@ -159,6 +559,15 @@ impl StreamingCoverageCommand {
             // Periodic compaction: avoid unbounded head_idx growth
             if head_idx > 4096 && head_idx * 2 > active.len() {
                 active.drain(0..head_idx);
+                if summary.is_some() {
+                    covered_to.drain(0..head_idx);
+                }
+                if self.diversity.is_some() {
+                    active_class.drain(0..head_idx);
+                }
+                if self.collapses() {
+                    active_name.drain(0..head_idx);
+                }
                 head_idx = 0;
             }
 
@@ -180,9 +589,25 @@ impl StreamingCoverageCommand {
                             start: b.start,
                             end: b.end,
                         });
+                        if let Some(summary) = summary.as_deref_mut() {
+                            summary.add_record(b);
+                            covered_to.push(b.start as u64);
+                        }
+                        if self.diversity.is_some() {
+                            let next_id = class_ids.len() as u32;
+                            let id = *class_ids.entry(b_class.clone()).or_insert(next_id);
+                            active_class.push(id);
+                        }
+                        if self.collapses() {
+                            active_name.push(names.add(&b_class, b.end as u64).id);
+                        }
                         // Read next B
-                        pending_b =
-                            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        pending_b = self.read_next_b(
+                            &mut b_reader,
+                            &mut b_line_buf,
+                            &mut b_chrom,
+                            &mut b_class,
+                        )?;
                         if pending_b.is_none() {
                             b_exhausted = true;
                             break;
@@ -198,10 +623,65 @@ impl StreamingCoverageCommand {
             // Step 3: Compute coverage from active slice (ZERO ALLOCATION)
             let active_slice = &active[head_idx..];
 
+            if let Some(summary) = summary.as_deref_mut() {
+                Self::cover_b(
+                    summary,
+                    active_slice,
+                    &mut covered_to[head_idx..],
+                    a_start,
+                    a_end,
+                );
+            }
+
+            // Same-name B intervals are merged, so each name adds at most 1
+            // to the depth of any base
+            let mut name_count = 0;
+            let active_slice = if self.collapses() {
+                names.prune(a_start);
+                name_pieces.clear();
+                for (b, &id) in active_slice.iter().zip(&active_name[head_idx..]) {
+                    let (b_start, b_end) = (b.start as u64, b.end as u64);
+                    if b_end > a_start && b_start < a_end {
+                        name_pieces.push((id, b_start.max(a_start), b_end.min(a_end)));
+                    }
+                }
+                name_count = union_by_name(&mut name_pieces);
+                collapsed.clear();
+                collapsed.extend(
+                    name_pieces
+                        .iter()
+                        .map(|&(_, start, end)| ActiveInterval::new(start, end)),
+                );
+                &collapsed[..]
+            } else {
+                active_slice
+            };
+
             // Step 4: Output based on mode
-            if self.per_base {
+            if self.diversity.is_some() {
+                class_bases.clear();
+                for (b, &class) in active_slice.iter().zip(&active_class[head_idx..]) {
+                    let from = (b.start as u64).max(a_start);
+                    let to = (b.end as u64).min(a_end);
+                    if to > from {
+                        *class_bases.entry(class).or_insert(0) += to - from;
+                    }
+                }
+                Self::write_diversity(output, line_bytes, &class_bases)?;
+            } else if let Some(classes) = &self.segment {
+                Self::write_segments(
+                    output,
+                    line_bytes,
+                    a_start,
+                    a_end,
+                    active_slice,
+                    classes,
+                    &mut events_buf,
+                    &mut runs_buf,
+                )?;
+            } else if self.per_base {
                 self.write_per_base_coverage(
-                    &mut output,
+                    output,
                     line,
                     a_start,
                     a_end,
@@ -210,7 +690,7 @@ impl StreamingCoverageCommand {
                 )?;
             } else if self.histogram {
                 self.write_histogram_coverage(
-                    &mut output,
+                    output,
                     line,
                     a_start,
                     a_end,
@@ -220,21 +700,25 @@ impl StreamingCoverageCommand {
                 )?;
             } else if self.mean {
                 self.write_mean_coverage(
-                    &mut output,
+                    output,
                     line,
                     a_start,
                     a_end,
                     a_len,
                     active_slice,
                     &mut events_buf,
+                    &mut runs_buf,
                 )?;
             } else {
                 // Basic coverage - most common path, ZERO ALLOCATION
-                let (num_overlaps, bases_covered) =
+                let (mut num_overlaps, bases_covered) =
                     Self::compute_coverage_inline(active_slice, a_start, a_end);
+                if self.collapses() {
+                    num_overlaps = name_count;
+                }
 
                 self.write_basic_coverage_fast(
-                    &mut output,
+                    output,
                     line,
                     num_overlaps,
                     bases_covered,
@@ -244,18 +728,54 @@ impl StreamingCoverageCommand {
             }
         }
 
-        output.flush()?;
+        // B records no A interval reached still count towards the totals
+        if let Some(summary) = summary {
+            while let Some(b) = pending_b {
+                summary.add_record(b);
+                pending_b =
+                    self.read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom, &mut b_class)?;
+            }
+        }
+
+        for output in &mut outputs {
+            output.flush()?;
+        }
         Ok(())
     }
 
+    /// Add the bases of active B intervals that A newly covers.
+    #[inline]
+    fn cover_b(
+        summary: &mut ReciprocalSummary,
+        active: &[ActiveInterval],
+        covered_to: &mut [u64],
+        a_start: u64,
+        a_end: u64,
+    ) {
+        for (b, covered) in active.iter().zip(covered_to) {
+            let (b_start, b_end) = (b.start as u64, b.end as u64);
+            let from = b_start.max(a_start).max(*covered);
+            let to = b_end.min(a_end);
+            if to > from {
+                if *covered == b_start {
+                    summary.records_in_a += 1;
+                }
+                summary.bases_in_a += to - from;
+                *covered = to;
+            }
+        }
+    }
+
     /// Read next B interval. Updates b_chrom buffer in place.
     /// Returns Err on IO error, Ok(None) on EOF, Ok(Some) on success.
     /// ZERO ALLOCATION per call (reuses buffers).
     #[inline]
     fn read_next_b(
-        reader: &mut BufReader<File>,
+        &self,
+        reader: &mut BufReader<InputFile>,
         line_buf: &mut String,
         chrom_buf: &mut Vec<u8>,
+        class_buf: &mut Vec<u8>,
     ) -> Result<Option<PendingB>, BedError> {
         loop {
             line_buf.clear();
@@ -272,14 +792,21 @@ impl StreamingCoverageCommand {
             }
 
             // Parse BED3 - skip malformed lines
-            let (chrom, start, end) = match parse_bed3_bytes(line) {
+            let (chrom, start, end, rest) = match parse_bed3_bytes_with_rest(line) {
                 Some(v) => v,
                 None => continue,
             };
+            if !self.b_score.accepts_rest(&line[rest..]) {
+                continue;
+            }
 
             // Update chromosome buffer (reuses allocation)
             chrom_buf.clear();
             chrom_buf.extend_from_slice(chrom);
+            if let Some(col) = self.b_key_column() {
+                class_buf.clear();
+                class_buf.extend_from_slice(column_bytes(line, col).unwrap_or(b"."));
+            }
 
             return Ok(Some(PendingB {
                 start: start as u32,
@@ -288,6 +815,33 @@ impl StreamingCoverageCommand {
         }
     }
 
+    /// Write A followed by the number of B classes over it, their Shannon
+    /// diversity (natural log, weighted by overlapping bases) and Pielou's
+    /// evenness (`.` with fewer than two classes).
+    fn write_diversity<W: Write>(
+        output: &mut W,
+        line: &[u8],
+        class_bases: &HashMap<u32, u64>,
+    ) -> Result<(), BedError> {
+        let total: u64 = class_bases.values().sum();
+        let shannon: f64 = class_bases
+            .values()
+            .map(|&bases| {
+                let p = bases as f64 / total as f64;
+                -p * p.ln()
+            })
+            .sum();
+        let n = class_bases.len();
+        output.write_all(line)?;
+        write!(output, "\t{}\t{:.6}", n, shannon.abs())?;
+        if n < 2 {
+            output.write_all(b"\t.\n")?;
+        } else {
+            writeln!(output, "\t{:.6}", shannon / (n as f64).ln())?;
+        }
+        Ok(())
+    }
+
     /// Compute coverage INLINE without any allocation.
     ///
     /// INVARIANT: Active intervals are sorted by START because:
@@ -423,7 +977,14 @@ impl StreamingCoverageCommand {
             output
                 .write_all(original_line.as_bytes())
                 .map_err(BedError::Io)?;
-            output.write_all(b"\t0.0000000\n").map_err(BedError::Io)?;
+            output.write_all(b"\t0.0000000").map_err(BedError::Io)?;
+            if self.stddev {
+                output.write_all(b"\t0.0000000").map_err(BedError::Io)?;
+            }
+            for _ in &self.quantiles {
+                output.write_all(b"\t0").map_err(BedError::Io)?;
+            }
+            output.write_all(b"\n").map_err(BedError::Io)?;
         } else {
             output
                 .write_all(original_line.as_bytes())
@@ -438,6 +999,10 @@ impl StreamingCoverageCommand {
     }
 
     /// Mean coverage using reusable event buffer.
+    ///
+    /// With `stddev`/`quantiles`, the per-base depth distribution is taken
+    /// from the depth runs inside A, so the cost is O(k log k) per A rather
+    /// than O(A length).
     #[inline]
     fn write_mean_coverage<W: Write>(
         &self,
@@ -448,6 +1013,7 @@ impl StreamingCoverageCommand {
         a_len: u64,
         active: &[ActiveInterval],
         events: &mut Vec<(u64, i32)>,
+        runs: &mut Vec<(u32, u64)>,
     ) -> Result<(), BedError> {
         let total_depth = Self::compute_total_depth(active, a_start, a_end, events);
         // Use f32 to match bedtools precision (bedtools uses float internally)
@@ -462,8 +1028,106 @@ impl StreamingCoverageCommand {
             .map_err(BedError::Io)?;
 
         // Format mean with {:.7} to match bedtools (uses C printf rounding)
-        writeln!(output, "\t{:.7}", mean).map_err(BedError::Io)?;
+        write!(output, "\t{:.7}", mean).map_err(BedError::Io)?;
+
+        if self.has_depth_stats() {
+            Self::compute_depth_runs(active, a_start, a_end, events, runs);
+            if self.stddev {
+                write!(output, "\t{:.7}", depth_stddev(runs, a_len)).map_err(BedError::Io)?;
+            }
+            if !self.quantiles.is_empty() {
+                runs.sort_unstable_by_key(|&(depth, _)| depth);
+                for &q in &self.quantiles {
+                    write!(output, "\t{}", depth_quantile(runs, a_len, q)).map_err(BedError::Io)?;
+                }
+            }
+        }
 
+        output.write_all(b"\n").map_err(BedError::Io)?;
+        Ok(())
+    }
+
+    /// Collect (depth, bases) runs covering all of `[a_start, a_end)`.
+    ///
+    /// Uncovered stretches appear as depth-0 runs, so the run lengths sum to
+    /// the A length.
+    fn compute_depth_runs(
+        active: &[ActiveInterval],
+        a_start: u64,
+        a_end: u64,
+        events: &mut Vec<(u64, i32)>,
+        runs: &mut Vec<(u32, u64)>,
+    ) {
+        events.clear();
+        runs.clear();
+        events.push((a_end, 0));
+
+        for b in active {
+            let b_start = b.start as u64;
+            let b_end = b.end as u64;
+
+            if b_end > a_start && b_start < a_end {
+                events.push((b_start.max(a_start), 1));
+                events.push((b_end.min(a_end), -1));
+            }
+        }
+
+        events.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut depth: i32 = 0;
+        let mut prev_pos = a_start;
+        for &(pos, delta) in events.iter() {
+            if pos > prev_pos {
+                runs.push((depth as u32, pos - prev_pos));
+            }
+            depth += delta;
+            prev_pos = pos;
+        }
+    }
+
+    /// Write the pieces of A between depth class changes, each followed by
+    /// A's own columns and the class label.
+    #[allow(clippy::too_many_arguments)]
+    fn write_segments<W: Write>(
+        output: &mut W,
+        original_line: &[u8],
+        a_start: u64,
+        a_end: u64,
+        active: &[ActiveInterval],
+        classes: &DepthClasses,
+        events: &mut Vec<(u64, i32)>,
+        runs: &mut Vec<(u32, u64)>,
+    ) -> Result<(), BedError> {
+        let Some((chrom, _, _, rest)) = parse_bed3_bytes_with_rest(original_line) else {
+            return Ok(());
+        };
+        let rest = &original_line[rest..];
+        Self::compute_depth_runs(active, a_start, a_end, events, runs);
+
+        let mut itoa_buf = itoa::Buffer::new();
+        let mut seg_start = a_start;
+        let mut pos = a_start;
+        let mut runs = runs.iter().peekable();
+        while let Some(&(depth, bases)) = runs.next() {
+            let class = classes.class_of(depth);
+            pos += bases;
+            if runs
+                .peek()
+                .is_some_and(|&&(next, _)| classes.class_of(next) == class)
+            {
+                continue;
+            }
+            output.write_all(chrom)?;
+            output.write_all(b"\t")?;
+            output.write_all(itoa_buf.format(seg_start).as_bytes())?;
+            output.write_all(b"\t")?;
+            output.write_all(itoa_buf.format(pos).as_bytes())?;
+            output.write_all(rest)?;
+            output.write_all(b"\t")?;
+            output.write_all(classes.label(class).as_bytes())?;
+            output.write_all(b"\n")?;
+            seg_start = pos;
+        }
         Ok(())
     }
 
@@ -611,6 +1275,41 @@ impl StreamingCoverageCommand {
     }
 }
 
+/// Population standard deviation of per-base depth from (depth, bases) runs.
+fn depth_stddev(runs: &[(u32, u64)], len: u64) -> f64 {
+    if len == 0 {
+        return 0.0;
+    }
+    let n = len as f64;
+    let (sum, sum_sq) = runs.iter().fold((0.0, 0.0), |(s, sq), &(depth, bases)| {
+        let d = depth as f64;
+        let b = bases as f64;
+        (s + d * b, sq + d * d * b)
+    });
+    let mean = sum / n;
+    (sum_sq / n - mean * mean).max(0.0).sqrt()
+}
+
+/// Nearest-rank quantile of per-base depth from runs sorted by depth.
+///
+/// Returns the smallest depth `d` such that at least `q` of the bases have
+/// depth <= `d`, so the result is always an observed depth (the median of an
+/// even-length interval is the lower middle value).
+fn depth_quantile(sorted_runs: &[(u32, u64)], len: u64, q: f64) -> u32 {
+    if len == 0 {
+        return 0;
+    }
+    let rank = ((q * len as f64).ceil() as u64).clamp(1, len);
+    let mut seen = 0;
+    for &(depth, bases) in sorted_runs {
+        seen += bases;
+        if seen >= rank {
+            return depth;
+        }
+    }
+    sorted_runs.last().map_or(0, |&(depth, _)| depth)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -658,6 +1357,186 @@ mod tests {
         assert_eq!(bases, 50);
     }
 
+    #[test]
+    fn test_depth_runs_stats() {
+        // Depth over [100, 200): 0 x 20, 1 x 30, 2 x 20, 1 x 10, 0 x 20
+        let active = vec![
+            ActiveInterval {
+                start: 120,
+                end: 170,
+            },
+            ActiveInterval {
+                start: 150,
+                end: 180,
+            },
+        ];
+        let mut events = Vec::new();
+        let mut runs = Vec::new();
+        StreamingCoverageCommand::compute_depth_runs(&active, 100, 200, &mut events, &mut runs);
+        assert_eq!(runs, vec![(0, 20), (1, 30), (2, 20), (1, 10), (0, 20)]);
+
+        // mean 0.8, E[d^2] = (40 + 80) / 100 = 1.2, var = 0.56
+        assert!((depth_stddev(&runs, 100) - 0.56f64.sqrt()).abs() < 1e-12);
+
+        runs.sort_unstable_by_key(|&(d, _)| d);
+        assert_eq!(depth_quantile(&runs, 100, 0.0), 0);
+        assert_eq!(depth_quantile(&runs, 100, 0.4), 0);
+        assert_eq!(depth_quantile(&runs, 100, 0.5), 1);
+        assert_eq!(depth_quantile(&runs, 100, 0.8), 1);
+        assert_eq!(depth_quantile(&runs, 100, 0.81), 2);
+        assert_eq!(depth_quantile(&runs, 100, 1.0), 2);
+    }
+
+    #[test]
+    fn test_streaming_mean_with_stats() {
+        use std::io::Write as IoWrite;
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+
+        // Bimodal: half the interval at depth 4, half at 0
+        writeln!(a_file, "chr1\t100\t200").unwrap();
+        writeln!(a_file, "chr1\t300\t300").unwrap();
+        for _ in 0..4 {
+            writeln!(b_file, "chr1\t100\t150").unwrap();
+        }
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let mut cmd = StreamingCoverageCommand::new();
+        cmd.mean = true;
+        cmd.stddev = true;
+        cmd.quantiles = vec![0.5, 0.9];
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\t2.0000000\t2.0000000\t0\t4\n\
+             chr1\t300\t300\t0.0000000\t0.0000000\t0\t0\n"
+        );
+    }
+
+    #[test]
+    fn test_depth_classes() {
+        let classes = DepthClasses::default();
+        let labels: Vec<&str> = (0..4).map(|d| classes.label(classes.class_of(d))).collect();
+        assert_eq!(labels, ["0", "1", "2+", "2+"]);
+
+        let classes = DepthClasses::new(vec![5, 10]).unwrap();
+        assert_eq!(classes.label(classes.class_of(4)), "0-4");
+        assert_eq!(classes.label(classes.class_of(9)), "5-9");
+        assert_eq!(classes.label(classes.class_of(10)), "10+");
+
+        assert!(DepthClasses::new(vec![0, 1]).is_err());
+        assert!(DepthClasses::new(vec![3, 2]).is_err());
+    }
+
+    #[test]
+    fn test_streaming_segment() {
+        use std::io::Write as IoWrite;
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        writeln!(a_file, "chr1\t100\t200\tgene").unwrap();
+        writeln!(a_file, "chr1\t300\t300\tempty").unwrap();
+        writeln!(a_file, "chr2\t0\t10\tnone").unwrap();
+        // Depth over [100, 200): 0 x 20, 1 x 30, 2 x 10, 3 x 10, 1 x 10, 0 x 20
+        writeln!(b_file, "chr1\t120\t170").unwrap();
+        writeln!(b_file, "chr1\t150\t170").unwrap();
+        writeln!(b_file, "chr1\t160\t180").unwrap();
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let mut cmd = StreamingCoverageCommand::new();
+        cmd.segment = Some(DepthClasses::default());
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t120\tgene\t0\n\
+             chr1\t120\t150\tgene\t1\n\
+             chr1\t150\t170\tgene\t2+\n\
+             chr1\t170\t180\tgene\t1\n\
+             chr1\t180\t200\tgene\t0\n\
+             chr2\t0\t10\tnone\t0\n"
+        );
+    }
+
+    #[test]
+    fn test_streaming_diversity() {
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        writeln!(a_file, "chr1\t100\t200\tpeak1").unwrap();
+        writeln!(a_file, "chr1\t300\t400\tpeak2").unwrap();
+        writeln!(a_file, "chr1\t500\t500\tempty").unwrap();
+        writeln!(a_file, "chr2\t0\t10\tnone").unwrap();
+        // peak1: 50 bp promoter, 80 bp enhancer; peak2: enhancer only
+        writeln!(b_file, "chr1\t100\t150\tpromoter").unwrap();
+        writeln!(b_file, "chr1\t120\t200\tenhancer").unwrap();
+        writeln!(b_file, "chr1\t300\t350\tenhancer").unwrap();
+        writeln!(b_file, "chr1\t320\t340\tenhancer").unwrap();
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let mut cmd = StreamingCoverageCommand::new();
+        cmd.diversity = Some(4);
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\tpeak1\t2\t0.666278\t0.961237\n\
+             chr1\t300\t400\tpeak2\t1\t0.000000\t.\n\
+             chr1\t500\t500\tempty\t0\t0.000000\t.\n\
+             chr2\t0\t10\tnone\t0\t0.000000\t.\n"
+        );
+    }
+
+    #[test]
+    fn test_streaming_collapse_by_name() {
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        writeln!(a_file, "chr1\t100\t200").unwrap();
+        writeln!(a_file, "chr1\t300\t400").unwrap();
+        // Overlapping mates of frag1, and a mate of frag2 reaching the
+        // second A; unnamed reads are never grouped
+        writeln!(b_file, "chr1\t100\t150\tfrag1").unwrap();
+        writeln!(b_file, "chr1\t120\t170\tfrag1").unwrap();
+        writeln!(b_file, "chr1\t130\t140\t.").unwrap();
+        writeln!(b_file, "chr1\t130\t140\t.").unwrap();
+        writeln!(b_file, "chr1\t190\t210\tfrag2").unwrap();
+        writeln!(b_file, "chr1\t350\t380\tfrag2").unwrap();
+        writeln!(b_file, "chr1\t390\t420\tfrag2").unwrap();
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let mut cmd = StreamingCoverageCommand::new();
+        cmd.collapse_by_name = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\t4\t80\t100\t0.8000000\n\
+             chr1\t300\t400\t1\t40\t100\t0.4000000\n"
+        );
+
+        // Depth: frag1 adds 70 bases once, the unnamed reads 10 each
+        cmd.mean = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("chr1\t100\t200\t1.0000000\n"));
+    }
+
     #[test]
     fn test_active_interval_size() {
         assert_eq!(std::mem::size_of::<ActiveInterval>(), 8);
@@ -746,4 +1625,107 @@ mod tests {
         // chr2 should have coverage from B's chr2 interval
         assert!(result.contains("1\t50\t100"));
     }
+
+    #[test]
+    fn test_run_reciprocal() {
+        use std::io::Write as IoWrite;
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        // Overlapping A intervals must not count B bases twice
+        writeln!(a_file, "chr1\t100\t200").unwrap();
+        writeln!(a_file, "chr1\t150\t300").unwrap();
+        writeln!(a_file, "chr2\t0\t10").unwrap();
+        writeln!(b_file, "chr1\t0\t50").unwrap();
+        writeln!(b_file, "chr1\t90\t260").unwrap();
+        writeln!(b_file, "chr1\t280\t400").unwrap();
+        writeln!(b_file, "chr3\t0\t30").unwrap();
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let cmd = StreamingCoverageCommand::new();
+        let mut output = Vec::new();
+        let summary = cmd
+            .run_reciprocal(a_file.path(), b_file.path(), &mut output)
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            ReciprocalSummary {
+                records: 4,
+                records_in_a: 2,
+                bases: 370,
+                bases_in_a: 180,
+            }
+        );
+        assert_eq!(summary.record_fraction(), 0.5);
+
+        let mut plain = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut plain).unwrap();
+        assert_eq!(output, plain);
+    }
+
+    #[test]
+    fn test_streaming_b_score_filter() {
+        use std::io::Write as IoWrite;
+        use tempfile::NamedTempFile;
+
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        writeln!(a_file, "chr1\t100\t200").unwrap();
+        writeln!(b_file, "chr1\t100\t150\tweak\t2").unwrap();
+        writeln!(b_file, "chr1\t150\t170\tstrong\t80").unwrap();
+        a_file.flush().unwrap();
+        b_file.flush().unwrap();
+
+        let mut cmd = StreamingCoverageCommand::new();
+        cmd.b_score = ScoreFilter::new(Some(50.0), None);
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\t1\t20\t100\t0.2000000\n"
+        );
+    }
+
+    #[test]
+    fn test_run_multi_matches_separate_runs() {
+        use std::io::Write as IoWrite;
+        use tempfile::NamedTempFile;
+
+        let mut a1 = NamedTempFile::new().unwrap();
+        let mut a2 = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        writeln!(a1, "chr1\t100\t200\tp1").unwrap();
+        writeln!(a1, "chr2\t0\t50\tp2").unwrap();
+        writeln!(a2, "#header").unwrap();
+        writeln!(a2, "chr1\t50\t150\te1").unwrap();
+        writeln!(a2, "chr1\t150\t150\te2").unwrap();
+        writeln!(a2, "chr3\t0\t10\te3").unwrap();
+        writeln!(b_file, "chr1\t90\t160").unwrap();
+        writeln!(b_file, "chr1\t120\t130").unwrap();
+        writeln!(b_file, "chr2\t10\t20").unwrap();
+        for f in [&mut a1, &mut a2, &mut b_file] {
+            f.flush().unwrap();
+        }
+
+        let cmd = StreamingCoverageCommand::new();
+        let mut outputs = vec![Vec::new(), Vec::new()];
+        cmd.run_multi(&[a1.path(), a2.path()], b_file.path(), &mut outputs, None)
+            .unwrap();
+
+        for (a, output) in [a1.path(), a2.path()].iter().zip(&outputs) {
+            let mut single = Vec::new();
+            cmd.run(a, &b_file.path(), &mut single).unwrap();
+            assert_eq!(output, &single);
+        }
+        assert_eq!(
+            String::from_utf8(outputs[1].clone()).unwrap(),
+            "chr1\t50\t150\te1\t2\t60\t100\t0.6000000\n\
+             chr1\t150\t150\te2\t0\t0\t0\t0.0000000\n\
+             chr3\t0\t10\te3\t0\t0\t10\t0.0000000\n"
+        );
+    }
 }
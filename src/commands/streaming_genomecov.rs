@@ -17,12 +17,19 @@
 //! but still stream through the input efficiently.
 
 use crate::bed::BedError;
-use crate::genome::Genome;
+use crate::commands::genomecov::{
+    clip_runs, coalesce_scaled, coalesce_values, target_total, ClassRollup, DepthNormalization,
+    DepthScale, DepthTransform, TargetSummary,
+};
+use crate::config::ChromClasses;
+use crate::genome::{Genome, GenomeMask};
+use crate::progress::GenomeProgress;
 use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
+use crate::streaming::parsing::{column_bytes, parse_bed3_bytes, should_skip_line};
+use crate::streaming::read_ahead::open_input;
+use crate::streaming::NameWindow;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
 /// Output mode for streaming genomecov.
@@ -45,8 +52,24 @@ pub struct StreamingGenomecovCommand {
     pub mode: StreamingGenomecovMode,
     /// Scale factor for depth
     pub scale: f64,
+    /// Round scaled depths to integers
+    pub round_scaled: bool,
     /// Skip sorted validation (faster for pre-sorted input)
     pub assume_sorted: bool,
+    /// Report BedGraph values as genome-wide percentiles or z-scores
+    pub normalize: Option<DepthNormalization>,
+    /// Report progress through the genome on stderr
+    pub progress: bool,
+    /// With `progress`, also report each finished chromosome
+    pub verbose: bool,
+    /// Count records sharing a name (column 4) once toward depth
+    pub collapse_by_name: bool,
+    /// Report only bases inside these regions (`--regions`)
+    pub targets: Option<GenomeMask>,
+    /// Roll histogram rows up into these chromosome classes
+    pub chrom_classes: Option<ChromClasses>,
+    /// Fitted normalisation, set for the output pass
+    transform: Option<DepthTransform>,
 }
 
 impl Default for StreamingGenomecovCommand {
@@ -60,7 +83,15 @@ impl StreamingGenomecovCommand {
         Self {
             mode: StreamingGenomecovMode::Histogram,
             scale: 1.0,
+            round_scaled: false,
             assume_sorted: false,
+            normalize: None,
+            progress: false,
+            verbose: false,
+            collapse_by_name: false,
+            targets: None,
+            chrom_classes: None,
+            transform: None,
         }
     }
 
@@ -82,6 +113,47 @@ impl StreamingGenomecovCommand {
         self
     }
 
+    /// Round scaled depths to integers (builder pattern).
+    pub fn with_round_scaled(mut self, round_scaled: bool) -> Self {
+        self.round_scaled = round_scaled;
+        self
+    }
+
+    /// Normalise BedGraph values genome-wide (builder pattern).
+    ///
+    /// The input is read twice: once for the depth histogram, once to write
+    /// the track.
+    pub fn with_normalize(mut self, normalize: Option<DepthNormalization>) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Report progress on stderr, per chromosome if `verbose` (builder
+    /// pattern). Progress follows the position reached in the input.
+    pub fn with_progress(mut self, progress: bool, verbose: bool) -> Self {
+        self.progress = progress;
+        self.verbose = verbose;
+        self
+    }
+
+    /// Count records sharing a name once toward depth (builder pattern).
+    pub fn with_collapse_by_name(mut self, collapse_by_name: bool) -> Self {
+        self.collapse_by_name = collapse_by_name;
+        self
+    }
+
+    /// Report only bases inside `targets` (builder pattern).
+    pub fn with_targets(mut self, targets: Option<GenomeMask>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Report histogram rows per chromosome class (builder pattern).
+    pub fn with_chrom_classes(mut self, chrom_classes: Option<ChromClasses>) -> Self {
+        self.chrom_classes = chrom_classes;
+        self
+    }
+
     /// Execute streaming genomecov.
     ///
     /// Memory: O(k) where k = max overlapping intervals on any chromosome.
@@ -94,9 +166,45 @@ impl StreamingGenomecovCommand {
         genome: &Genome,
         output: &mut W,
     ) -> Result<(), BedError> {
-        let file = File::open(input)?;
-        let reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, file);
-        self.genomecov_streaming(reader, genome, output)
+        self.run_with_summary(input, genome, output)?;
+        Ok(())
+    }
+
+    /// Execute streaming genomecov, also returning the depth over the
+    /// targets (empty without targets).
+    pub fn run_with_summary<P: AsRef<Path>, W: Write>(
+        &self,
+        input: P,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<TargetSummary, BedError> {
+        let open = || -> Result<_, BedError> {
+            let file = open_input(&input)?;
+            Ok(BufReader::with_capacity(DEFAULT_INPUT_BUFFER, file))
+        };
+
+        let bedgraph = matches!(
+            self.mode,
+            StreamingGenomecovMode::BedGraph | StreamingGenomecovMode::BedGraphAll
+        );
+        match self.normalize {
+            Some(kind) if bedgraph => {
+                let histogram_pass = Self {
+                    mode: StreamingGenomecovMode::Histogram,
+                    normalize: None,
+                    progress: false,
+                    ..self.clone()
+                };
+                let (hist, _) =
+                    histogram_pass.genomecov_streaming(open()?, genome, &mut io::sink())?;
+                let output_pass = Self {
+                    transform: Some(DepthTransform::new(kind, &hist)),
+                    ..self.clone()
+                };
+                Ok(output_pass.genomecov_streaming(open()?, genome, output)?.1)
+            }
+            _ => Ok(self.genomecov_streaming(open()?, genome, output)?.1),
+        }
     }
 
     /// Streaming genomecov implementation.
@@ -111,12 +219,15 @@ impl StreamingGenomecovCommand {
     ///
     /// Memory is O(k) where k = max intervals on any single chromosome.
     /// For typical genomic data, this is much smaller than total file size.
+    ///
+    /// Returns the genome-wide depth histogram (filled in histogram mode)
+    /// and the depth over the targets.
     fn genomecov_streaming<R: BufRead, W: Write>(
         &self,
         mut reader: R,
         genome: &Genome,
         output: &mut W,
-    ) -> Result<(), BedError> {
+    ) -> Result<(HashMap<u32, u64>, TargetSummary), BedError> {
         // Large output buffer (8MB)
         let mut buf_output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
 
@@ -137,7 +248,8 @@ impl StreamingGenomecovCommand {
 
         // For histogram mode: genome-wide accumulator
         let mut genome_hist: HashMap<u32, u64> = HashMap::new();
-        let mut total_bases: u64 = 0;
+        let mut summary = TargetSummary::default();
+        let mut class_hist = ClassRollup::new(self.chrom_classes.as_ref());
 
         // Track which chromosomes we've seen (for outputting empty chromosomes)
         let mut seen_chroms: Vec<bool> = vec![false; chroms.len()];
@@ -145,6 +257,13 @@ impl StreamingGenomecovCommand {
         // itoa buffer for fast integer formatting
         let mut itoa_buf = itoa::Buffer::new();
 
+        let mut progress = self
+            .progress
+            .then(|| GenomeProgress::stderr("genomecov", genome, self.verbose));
+
+        // With `collapse_by_name`: how far each recent name already reaches
+        let mut names = NameWindow::new();
+
         loop {
             line_buf.clear();
             let bytes_read = reader.read_line(&mut line_buf)?;
@@ -179,6 +298,8 @@ impl StreamingGenomecovCommand {
                         genome,
                         &mut buf_output,
                         &mut genome_hist,
+                        &mut class_hist,
+                        &mut summary,
                         &mut itoa_buf,
                     )?;
 
@@ -190,6 +311,8 @@ impl StreamingGenomecovCommand {
                             genome,
                             &mut buf_output,
                             &mut genome_hist,
+                            &mut class_hist,
+                            &mut summary,
                             &mut itoa_buf,
                         )?;
                         seen_chroms[skip_idx] = true;
@@ -206,14 +329,34 @@ impl StreamingGenomecovCommand {
                         genome,
                         &mut buf_output,
                         &mut genome_hist,
+                        &mut class_hist,
+                        &mut summary,
                         &mut itoa_buf,
                     )?;
                     seen_chroms[skip_idx] = true;
                 }
             }
 
+            if current_chrom_idx != Some(chrom_idx) {
+                names.clear();
+            }
             current_chrom_idx = Some(chrom_idx);
             seen_chroms[chrom_idx] = true;
+            if let Some(progress) = progress.as_mut() {
+                progress.update(chrom_idx, start);
+            }
+
+            // Only the part not yet covered by an earlier record of the same
+            // name adds depth
+            let mut start = start;
+            if self.collapse_by_name {
+                names.prune(start);
+                let name = column_bytes(line_bytes, 4).unwrap_or(b".");
+                start = start.max(names.add(name, end).covered_to);
+                if start >= end {
+                    continue;
+                }
+            }
 
             // Add events for this interval
             events.push((start, 1));
@@ -229,6 +372,8 @@ impl StreamingGenomecovCommand {
                 genome,
                 &mut buf_output,
                 &mut genome_hist,
+                &mut class_hist,
+                &mut summary,
                 &mut itoa_buf,
             )?;
 
@@ -240,6 +385,8 @@ impl StreamingGenomecovCommand {
                     genome,
                     &mut buf_output,
                     &mut genome_hist,
+                    &mut class_hist,
+                    &mut summary,
                     &mut itoa_buf,
                 )?;
             }
@@ -252,23 +399,29 @@ impl StreamingGenomecovCommand {
                     genome,
                     &mut buf_output,
                     &mut genome_hist,
+                    &mut class_hist,
+                    &mut summary,
                     &mut itoa_buf,
                 )?;
             }
         }
 
-        // Calculate total bases for genome histogram
-        for chrom in &chroms {
-            total_bases += genome.chrom_size(chrom).unwrap_or(0);
+        if let Some(progress) = progress.as_mut() {
+            progress.finish();
         }
 
         // Output genome-wide histogram if in histogram mode
         if self.mode == StreamingGenomecovMode::Histogram {
+            let total_bases = match &self.targets {
+                Some(targets) => target_total(genome, targets),
+                None => genome.effective_total_size(),
+            };
+            class_hist.write(&mut buf_output, true)?;
             self.output_genome_histogram(&genome_hist, total_bases, &mut buf_output)?;
         }
 
         buf_output.flush().map_err(BedError::Io)?;
-        Ok(())
+        Ok((genome_hist, summary))
     }
 
     /// Process a chromosome with events using sweep-line algorithm.
@@ -280,6 +433,8 @@ impl StreamingGenomecovCommand {
         genome: &Genome,
         output: &mut W,
         genome_hist: &mut HashMap<u32, u64>,
+        class_hist: &mut ClassRollup,
+        summary: &mut TargetSummary,
         itoa_buf: &mut itoa::Buffer,
     ) -> Result<(), BedError> {
         let chrom = chroms[chrom_idx];
@@ -292,6 +447,8 @@ impl StreamingGenomecovCommand {
                 genome,
                 output,
                 genome_hist,
+                class_hist,
+                summary,
                 itoa_buf,
             );
         }
@@ -302,6 +459,39 @@ impl StreamingGenomecovCommand {
 
         // Sweep and collect depth regions
         let regions = self.sweep_events(&sorted_events, chrom_size);
+        self.output_regions(
+            chrom,
+            regions,
+            genome,
+            output,
+            genome_hist,
+            class_hist,
+            summary,
+            itoa_buf,
+        )
+    }
+
+    /// Output a chromosome's depth regions, clipped to the targets if any.
+    fn output_regions<W: Write>(
+        &self,
+        chrom: &str,
+        regions: Vec<(u64, u64, u32)>,
+        genome: &Genome,
+        output: &mut W,
+        genome_hist: &mut HashMap<u32, u64>,
+        class_hist: &mut ClassRollup,
+        summary: &mut TargetSummary,
+        itoa_buf: &mut itoa::Buffer,
+    ) -> Result<(), BedError> {
+        let (regions, size) = match &self.targets {
+            Some(targets) => {
+                let ranges = targets.regions(chrom);
+                let regions = clip_runs(&regions, ranges);
+                summary.add(chrom, &regions);
+                (regions, ranges.iter().map(|(s, e)| e - s).sum())
+            }
+            None => (regions, genome.chrom_size(chrom).unwrap_or(0)),
+        };
 
         // Output based on mode
         match self.mode {
@@ -310,9 +500,11 @@ impl StreamingGenomecovCommand {
                 self.output_chromosome_histogram(
                     chrom,
                     &chrom_hist,
-                    chrom_size,
+                    size,
+                    genome,
                     output,
                     genome_hist,
+                    class_hist,
                 )?;
             }
             StreamingGenomecovMode::BedGraph | StreamingGenomecovMode::BedGraphAll => {
@@ -334,6 +526,8 @@ impl StreamingGenomecovCommand {
         genome: &Genome,
         output: &mut W,
         genome_hist: &mut HashMap<u32, u64>,
+        class_hist: &mut ClassRollup,
+        summary: &mut TargetSummary,
         itoa_buf: &mut itoa::Buffer,
     ) -> Result<(), BedError> {
         let chrom = chroms[chrom_idx];
@@ -342,6 +536,19 @@ impl StreamingGenomecovCommand {
         if chrom_size == 0 {
             return Ok(());
         }
+        if self.targets.is_some() {
+            // Only the targets are reported, all at depth 0
+            return self.output_regions(
+                chrom,
+                vec![(0, chrom_size, 0)],
+                genome,
+                output,
+                genome_hist,
+                class_hist,
+                summary,
+                itoa_buf,
+            );
+        }
 
         match self.mode {
             StreamingGenomecovMode::Histogram => {
@@ -352,18 +559,15 @@ impl StreamingGenomecovCommand {
                     chrom,
                     &chrom_hist,
                     chrom_size,
+                    genome,
                     output,
                     genome_hist,
+                    class_hist,
                 )?;
             }
             StreamingGenomecovMode::BedGraphAll => {
                 // Output single region at depth 0
-                output.write_all(chrom.as_bytes()).map_err(BedError::Io)?;
-                output.write_all(b"\t0\t").map_err(BedError::Io)?;
-                output
-                    .write_all(itoa_buf.format(chrom_size).as_bytes())
-                    .map_err(BedError::Io)?;
-                output.write_all(b"\t0\n").map_err(BedError::Io)?;
+                self.output_bedgraph(chrom.as_bytes(), &[(0, chrom_size, 0)], output, itoa_buf)?;
             }
             StreamingGenomecovMode::BedGraph => {
                 // No output for BedGraph (only non-zero)
@@ -437,34 +641,48 @@ impl StreamingGenomecovCommand {
         hist
     }
 
-    /// Output per-chromosome histogram and accumulate genome-wide stats.
+    /// Output per-chromosome histogram (or add it to its class) and
+    /// accumulate genome-wide stats.
     fn output_chromosome_histogram<W: Write>(
         &self,
         chrom: &str,
         chrom_hist: &HashMap<u32, u64>,
         chrom_size: u64,
+        genome: &Genome,
         output: &mut W,
         genome_hist: &mut HashMap<u32, u64>,
+        class_hist: &mut ClassRollup,
     ) -> Result<(), BedError> {
+        class_hist.add(chrom, chrom_hist, chrom_size);
         let mut depths: Vec<_> = chrom_hist.keys().copied().collect();
         depths.sort_unstable();
 
         for depth in depths {
             let bases = chrom_hist[&depth];
-            let fraction = bases as f64 / chrom_size as f64;
-            writeln!(
-                output,
-                "{}\t{}\t{}\t{}\t{}",
-                chrom,
-                depth,
-                bases,
-                chrom_size,
-                format_fraction(fraction)
-            )
-            .map_err(BedError::Io)?;
+            if !class_hist.is_active() {
+                let fraction = bases as f64 / chrom_size as f64;
+                writeln!(
+                    output,
+                    "{}\t{}\t{}\t{}\t{}",
+                    chrom,
+                    depth,
+                    bases,
+                    chrom_size,
+                    format_fraction(fraction)
+                )
+                .map_err(BedError::Io)?;
+            }
 
-            // Accumulate for genome-wide
-            *genome_hist.entry(depth).or_insert(0) += bases;
+            // Accumulate for genome-wide; effective sizes describe whole
+            // chromosomes, not targets
+            let counted = match self.targets {
+                Some(_) if genome.in_totals(chrom) => bases,
+                Some(_) => 0,
+                None => genome.genome_wide_bases(chrom, depth, bases),
+            };
+            if counted > 0 {
+                *genome_hist.entry(depth).or_insert(0) += counted;
+            }
         }
 
         Ok(())
@@ -505,11 +723,13 @@ impl StreamingGenomecovCommand {
         output: &mut W,
         itoa_buf: &mut itoa::Buffer,
     ) -> Result<(), BedError> {
-        for &(start, end, depth) in regions {
-            if self.mode == StreamingGenomecovMode::BedGraph && depth == 0 {
-                continue;
-            }
-            let scaled_depth = (depth as f64 * self.scale) as u32;
+        let scale = DepthScale::new(self.scale, self.round_scaled);
+        let skip_zero = self.mode == StreamingGenomecovMode::BedGraph;
+        let values = match &self.transform {
+            Some(t) => coalesce_values(regions, skip_zero, |d| t.apply(d)),
+            None => coalesce_scaled(regions, scale, skip_zero),
+        };
+        for (start, end, value) in values {
             output.write_all(chrom).map_err(BedError::Io)?;
             output.write_all(b"\t").map_err(BedError::Io)?;
             output
@@ -519,11 +739,7 @@ impl StreamingGenomecovCommand {
             output
                 .write_all(itoa_buf.format(end).as_bytes())
                 .map_err(BedError::Io)?;
-            output.write_all(b"\t").map_err(BedError::Io)?;
-            output
-                .write_all(itoa_buf.format(scaled_depth).as_bytes())
-                .map_err(BedError::Io)?;
-            output.write_all(b"\n").map_err(BedError::Io)?;
+            writeln!(output, "\t{}", DepthScale::display(value)).map_err(BedError::Io)?;
         }
         Ok(())
     }
@@ -536,8 +752,9 @@ impl StreamingGenomecovCommand {
         output: &mut W,
         itoa_buf: &mut itoa::Buffer,
     ) -> Result<(), BedError> {
+        let scale = DepthScale::new(self.scale, self.round_scaled);
         for &(start, end, depth) in regions {
-            let scaled_depth = (depth as f64 * self.scale) as u32;
+            let scaled_depth = DepthScale::display(scale.apply(depth)).to_string();
             for pos in start..end {
                 output.write_all(chrom).map_err(BedError::Io)?;
                 output.write_all(b"\t").map_err(BedError::Io)?;
@@ -547,7 +764,7 @@ impl StreamingGenomecovCommand {
                     .map_err(BedError::Io)?;
                 output.write_all(b"\t").map_err(BedError::Io)?;
                 output
-                    .write_all(itoa_buf.format(scaled_depth).as_bytes())
+                    .write_all(scaled_depth.as_bytes())
                     .map_err(BedError::Io)?;
                 output.write_all(b"\n").map_err(BedError::Io)?;
             }
@@ -681,6 +898,165 @@ mod tests {
         assert!(result.contains("chr2\t0\t500\t0"));
     }
 
+    #[test]
+    fn test_streaming_genomecov_coalesces_adjacent() {
+        let genome = make_genome();
+        let bed_data = "chr1\t100\t200\nchr1\t200\t300\nchr1\t300\t350\nchr1\t300\t350\n";
+
+        let cmd = StreamingGenomecovCommand::new()
+            .with_mode(StreamingGenomecovMode::BedGraph)
+            .with_scale(0.5)
+            .with_assume_sorted(true);
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BufReader::new(bed_data.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t300\t0.5\nchr1\t300\t350\t1\n"
+        );
+
+        let cmd = cmd.with_scale(0.75).with_round_scaled(true);
+        let mut output = Vec::new();
+        cmd.genomecov_streaming(BufReader::new(bed_data.as_bytes()), &genome, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t300\t1\nchr1\t300\t350\t2\n"
+        );
+    }
+
+    #[test]
+    fn test_streaming_genomecov_collapse_by_name() {
+        use crate::bed::BedReader;
+        use crate::commands::genomecov::{GenomecovCommand, OutputMode};
+
+        let genome = make_genome();
+        // Overlapping mates count once; the unnamed reads twice
+        let bed_data = "chr1\t100\t200\tfrag\n\
+                        chr1\t150\t250\tfrag\n\
+                        chr1\t160\t170\t.\n\
+                        chr1\t160\t170\t.\n\
+                        chr1\t300\t320\tfrag\n\
+                        chr2\t0\t10\tfrag\n";
+        let cmd = StreamingGenomecovCommand::new()
+            .with_mode(StreamingGenomecovMode::BedGraph)
+            .with_collapse_by_name(true);
+        let mut streamed = Vec::new();
+        cmd.genomecov_streaming(BufReader::new(bed_data.as_bytes()), &genome, &mut streamed)
+            .unwrap();
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert_eq!(
+            streamed,
+            "chr1\t100\t160\t1\nchr1\t160\t170\t3\nchr1\t170\t250\t1\n\
+             chr1\t300\t320\t1\nchr2\t0\t10\t1\n"
+        );
+
+        let mut in_memory = GenomecovCommand::new().with_collapse_by_name(true);
+        in_memory.mode = OutputMode::BedGraph;
+        let mut expected = Vec::new();
+        in_memory
+            .genomecov_streaming(BedReader::new(bed_data.as_bytes()), &genome, &mut expected)
+            .unwrap();
+        assert_eq!(streamed, String::from_utf8(expected).unwrap());
+    }
+
+    #[test]
+    fn test_streaming_genomecov_normalized_matches_in_memory() {
+        use crate::bed::BedReader;
+        use crate::commands::genomecov::{GenomecovCommand, OutputMode};
+
+        let genome = make_genome();
+        let bed_data = "chr1\t100\t200\nchr1\t150\t250\nchr2\t0\t400\n";
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(bed_data.as_bytes()).unwrap();
+
+        for kind in [DepthNormalization::Percentile, DepthNormalization::ZScore] {
+            let cmd = StreamingGenomecovCommand::new()
+                .with_mode(StreamingGenomecovMode::BedGraphAll)
+                .with_normalize(Some(kind));
+            let mut streamed = Vec::new();
+            cmd.run(file.path(), &genome, &mut streamed).unwrap();
+
+            let mut in_memory = GenomecovCommand::new().with_normalize(Some(kind));
+            in_memory.mode = OutputMode::BedGraphAll;
+            let mut expected = Vec::new();
+            in_memory
+                .genomecov_streaming(BedReader::new(bed_data.as_bytes()), &genome, &mut expected)
+                .unwrap();
+
+            assert_eq!(
+                String::from_utf8(streamed).unwrap(),
+                String::from_utf8(expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_streaming_genomecov_targets_match_in_memory() {
+        use crate::bed::BedReader;
+        use crate::commands::genomecov::{GenomecovCommand, OutputMode};
+        use crate::interval::Interval;
+
+        let genome = make_genome();
+        let bed_data = "chr1\t100\t200\nchr1\t150\t250\n";
+        let targets = GenomeMask::from_intervals(&[
+            Interval::new("chr1", 180, 300),
+            Interval::new("chr1", 400, 420),
+            Interval::new("chr2", 10, 20),
+        ]);
+
+        for (mode, in_memory_mode) in [
+            (StreamingGenomecovMode::Histogram, OutputMode::Histogram),
+            (StreamingGenomecovMode::BedGraphAll, OutputMode::BedGraphAll),
+            (StreamingGenomecovMode::PerBase, OutputMode::PerBase),
+        ] {
+            let cmd = StreamingGenomecovCommand::new()
+                .with_mode(mode)
+                .with_targets(Some(targets.clone()));
+            let mut streamed = Vec::new();
+            let (_, summary) = cmd
+                .genomecov_streaming(BufReader::new(bed_data.as_bytes()), &genome, &mut streamed)
+                .unwrap();
+            assert_eq!(summary.bases(), 150);
+
+            let mut in_memory = GenomecovCommand::new().with_targets(Some(targets.clone()));
+            in_memory.mode = in_memory_mode;
+            let mut expected = Vec::new();
+            in_memory
+                .genomecov_streaming(BedReader::new(bed_data.as_bytes()), &genome, &mut expected)
+                .unwrap();
+            assert_eq!(
+                String::from_utf8(streamed).unwrap(),
+                String::from_utf8(expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_streaming_genomecov_chrom_classes_match_in_memory() {
+        use crate::bed::BedReader;
+        use crate::commands::genomecov::GenomecovCommand;
+
+        let genome = make_genome();
+        let bed_data = "chr1\t100\t200\nchr2\t150\t250\n";
+        let classes = ChromClasses::from_reader("first\tchr1\n".as_bytes()).unwrap();
+
+        let cmd = StreamingGenomecovCommand::new().with_chrom_classes(Some(classes.clone()));
+        let mut streamed = Vec::new();
+        cmd.genomecov_streaming(BufReader::new(bed_data.as_bytes()), &genome, &mut streamed)
+            .unwrap();
+
+        let mut expected = Vec::new();
+        GenomecovCommand::new()
+            .with_chrom_classes(Some(classes))
+            .genomecov_streaming(BedReader::new(bed_data.as_bytes()), &genome, &mut expected)
+            .unwrap();
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert_eq!(streamed, String::from_utf8(expected).unwrap());
+        assert!(streamed
+            .starts_with("first\t0\t900\t1000\t0.9\nfirst\t1\t100\t1000\t0.1\nother\t0\t400\t500"));
+    }
+
     #[test]
     fn test_format_fraction() {
         assert_eq!(format_fraction(0.0), "0");
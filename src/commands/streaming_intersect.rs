@@ -12,6 +12,9 @@
 //! 4. Remove from active set when B.end <= A.start (can't overlap anymore)
 //! 5. Report overlaps between current A and active B intervals
 //!
+//! Consecutive A records with identical coordinates share one active window,
+//! so their overlaps are computed once and replayed (see `HitCache`).
+//!
 //! # Memory Complexity
 //!
 //! - O(k) where k = maximum number of B intervals overlapping any single A interval
@@ -43,17 +46,27 @@
 //! | -wa       | A record (once per overlapping B)         |
 //! | -wb       | Overlap region + B record                 |
 //! | -wa -wb   | A record + B record (tab-separated)       |
+//! | -wo       | A record + B record + overlap bp          |
+//! | -loj      | A + B, or A + null B if no overlaps       |
+//! | -wao      | -loj with overlap bp (0 for null B)       |
 //! | -c        | A record + overlap count                  |
 //! | -u        | A record (once if ANY overlap)            |
 //! | -v        | A record (only if NO overlaps)            |
 
 use crate::bed::{BedError, BedReader};
+use crate::commands::intersect::{write_null_b, write_overlap_fractions, written_columns};
+use crate::config::display_score;
+use crate::genome::Genome;
 use crate::interval::BedRecord;
-use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, parse_bed3_bytes_with_rest, should_skip_line};
-use std::collections::{HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use crate::streaming::buffers::DEFAULT_INPUT_BUFFER;
+use crate::streaming::parsing::{column_count, parse_bed3_bytes_with_rest, should_skip_line};
+use crate::streaming::read_ahead::open_input;
+use crate::streaming::{
+    ActiveSet, BatchWriter, ChromId, ChromTable, ComplementStream, DedupReader,
+    GenomeOrderValidator, ScoreFilter,
+};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
 
 /// Warning threshold for active window size (potential pathological case)
@@ -72,6 +85,48 @@ struct ActiveB {
     line: Vec<u8>,
 }
 
+/// Overlap hits of the previous A record.
+///
+/// Annotation-derived A files often repeat the same coordinates many times
+/// (e.g. one exon per transcript). Consecutive A records with identical
+/// coordinates see the same active B window, so the overlap and filter checks
+/// are done once and the hit list is replayed for the duplicates.
+#[derive(Debug, Default)]
+struct HitCache {
+    /// (start, end, active window length) the hits were computed for.
+    key: Option<(u64, u64, usize)>,
+    /// Indices into the active window of B intervals that passed all checks.
+    hits: Vec<u32>,
+    /// More hits qualified than the per-A cap allowed.
+    capped: bool,
+}
+
+impl HitCache {
+    /// Recompute hits unless this A matches the previous one.
+    ///
+    /// Returns `true` when the cached hits were reused.
+    #[inline]
+    fn refresh<F>(&mut self, a_start: u64, a_end: u64, active_len: usize, compute: F) -> bool
+    where
+        F: FnOnce(&mut Vec<u32>) -> bool,
+    {
+        let key = (a_start, a_end, active_len);
+        if self.key == Some(key) {
+            return true;
+        }
+        self.key = Some(key);
+        self.hits.clear();
+        self.capped = compute(&mut self.hits);
+        false
+    }
+
+    /// Forget the cached hits (the active window was rebuilt).
+    #[inline]
+    fn clear(&mut self) {
+        self.key = None;
+    }
+}
+
 /// Output mode computed once before processing to reduce branch entropy.
 /// This replaces repeated flag checks in the hot loop.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -84,6 +139,11 @@ enum OutputMode {
     WriteB,
     /// -wa -wb: print A + B records
     WriteBoth,
+    /// --overlap-fractions: print A + B records + overlap bp and fractions
+    Fractions,
+    /// -wo / -loj / -wao: print A + B records (+ overlap bp), and with
+    /// `outer` A against a null B record when it has no overlaps
+    Join { overlap_bp: bool, outer: bool },
     /// -u: print A once if any overlap
     Unique,
     /// -c: print A + overlap count
@@ -109,6 +169,8 @@ pub struct StreamingIntersectCommand {
     pub fraction_b: Option<f64>,
     /// Require reciprocal fraction overlap (-r)
     pub reciprocal: bool,
+    /// Minimum overlap in base pairs
+    pub min_overlap_bp: Option<u64>,
     /// Report the number of overlaps (-c)
     pub count: bool,
     /// Require same strand (-s)
@@ -119,6 +181,21 @@ pub struct StreamingIntersectCommand {
     pub assume_sorted: bool,
     /// Warn if active window exceeds threshold
     pub warn_large_window: bool,
+    /// Drop B records whose score is outside these bounds
+    pub b_score: ScoreFilter,
+    /// Stop each A after this many reported overlaps
+    pub max_hits: Option<usize>,
+    /// Collapse exact duplicate B records before intersecting
+    pub dedup_b: bool,
+    /// With `dedup_b`, append each B record's multiplicity as a last column
+    pub dedup_b_counts: bool,
+    /// Write A and B with the overlap in bp and as fractions of A and B
+    pub overlap_fractions: bool,
+    /// Write A and B followed by the overlap in bp (-wo)
+    pub write_overlap: bool,
+    /// Also report A records without overlaps, against a null B record
+    /// (-loj; -wao together with `write_overlap`)
+    pub left_outer: bool,
 }
 
 impl Default for StreamingIntersectCommand {
@@ -136,12 +213,20 @@ impl StreamingIntersectCommand {
             no_overlap: false,
             fraction_a: None,
             fraction_b: None,
+            min_overlap_bp: None,
             reciprocal: false,
             count: false,
             same_strand: false,
             opposite_strand: false,
             assume_sorted: false,
             warn_large_window: true,
+            b_score: ScoreFilter::default(),
+            max_hits: None,
+            dedup_b: false,
+            dedup_b_counts: false,
+            overlap_fractions: false,
+            write_overlap: false,
+            left_outer: false,
         }
     }
 
@@ -155,6 +240,13 @@ impl StreamingIntersectCommand {
             OutputMode::Count
         } else if self.unique {
             OutputMode::Unique
+        } else if self.overlap_fractions {
+            OutputMode::Fractions
+        } else if self.write_overlap || self.left_outer {
+            OutputMode::Join {
+                overlap_bp: self.write_overlap,
+                outer: self.left_outer,
+            }
         } else if self.write_a && self.write_b {
             OutputMode::WriteBoth
         } else if self.write_b {
@@ -167,10 +259,11 @@ impl StreamingIntersectCommand {
         }
     }
 
-    /// Check if any filters are active (fraction or strand)
+    /// Check if any filters are active (overlap size or strand)
     #[inline]
     fn has_filters(&self) -> bool {
         self.fraction_a.is_some()
+            || self.min_overlap_bp.is_some()
             || self.fraction_b.is_some()
             || self.reciprocal
             || self.same_strand
@@ -190,16 +283,171 @@ impl StreamingIntersectCommand {
         output: &mut W,
     ) -> Result<StreamingStats, BedError> {
         // Fall back to original path for strand filtering (not supported in optimized path)
+        let b_file = open_input(b_path.as_ref())?;
+        let b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
         if self.same_strand || self.opposite_strand {
-            let a_file = File::open(a_path.as_ref())?;
-            let b_file = File::open(b_path.as_ref())?;
+            let a_file = open_input(a_path.as_ref())?;
             let a_reader = BedReader::new(BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file));
-            let b_reader = BedReader::new(BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file));
-            return self.run_streaming(a_reader, b_reader, output);
+            if self.dedup_b {
+                let b_reader = DedupReader::new(b_reader).with_counts(self.dedup_b_counts);
+                return self.run_streaming(a_reader, BedReader::new(b_reader), output);
+            }
+            return self.run_streaming(a_reader, BedReader::new(b_reader), output);
         }
 
         // Use optimized path with raw line parsing
-        self.run_optimized(a_path, b_path, output)
+        if self.dedup_b {
+            let b_reader = DedupReader::new(b_reader).with_counts(self.dedup_b_counts);
+            return self.run_optimized(a_path, b_reader, output);
+        }
+        self.run_optimized(a_path, b_reader, output)
+    }
+
+    /// Execute streaming intersect of A against the complement of B.
+    ///
+    /// The complement is computed on the fly from B and the genome, so
+    /// "regions of A not covered by B" queries take a single pass without
+    /// writing the complement to disk. Both inputs must be sorted in genome
+    /// order. With `-wb`, the complement interval is written as a BED3 record.
+    pub fn run_invert_b<P: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b_path: P,
+        genome: &Genome,
+        output: &mut W,
+    ) -> Result<StreamingStats, BedError> {
+        if self.same_strand || self.opposite_strand {
+            return Err(BedError::InvalidFormat(
+                "Strand filters cannot be used with --invert-b: complement intervals have no strand"
+                    .to_string(),
+            ));
+        }
+        if self.b_score.is_active() {
+            return Err(BedError::InvalidFormat(
+                "B score filters cannot be used with --invert-b".to_string(),
+            ));
+        }
+
+        let mut stats = StreamingStats::default();
+        let mut writer = BatchWriter::new(output);
+
+        let a_file = open_input(a_path.as_ref())?;
+        let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
+        let b_file = open_input(b_path.as_ref())?;
+        let mut gaps = ComplementStream::new(
+            BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file),
+            genome,
+        );
+        let mut pending_gap = gaps.next_gap()?;
+
+        let mut validator = (!self.assume_sorted).then(|| GenomeOrderValidator::new(genome));
+        // Complement intervals are BED3
+        let b_columns = 3;
+        let mut active: ActiveSet<ActiveB> = ActiveSet::new();
+        let mut a_chrom_idx: Option<usize> = None;
+        let mut a_line_buf = String::with_capacity(1024);
+        let mut hit_cache = HitCache::default();
+
+        let output_mode = self.compute_output_mode();
+        let has_filters = self.has_filters();
+        let mut itoa_buf = itoa::Buffer::new();
+
+        loop {
+            a_line_buf.clear();
+            if a_reader.read_line(&mut a_line_buf)? == 0 {
+                break;
+            }
+
+            let line = a_line_buf.trim_end();
+            let line_bytes = line.as_bytes();
+            if should_skip_line(line_bytes) {
+                continue;
+            }
+
+            let (chrom, a_start, a_end, rest_start) = match parse_bed3_bytes_with_rest(line_bytes) {
+                Some(v) => v,
+                None => continue,
+            };
+            stats.a_intervals += 1;
+
+            if let Some(validator) = validator.as_mut() {
+                validator.validate(&String::from_utf8_lossy(chrom), a_start)?;
+            }
+
+            let chrom_idx = gaps.chrom_index(chrom).ok_or_else(|| {
+                BedError::InvalidFormat(format!(
+                    "Chromosome '{}' in A not found in genome file",
+                    String::from_utf8_lossy(chrom)
+                ))
+            })?;
+
+            if a_chrom_idx != Some(chrom_idx) {
+                a_chrom_idx = Some(chrom_idx);
+                active.clear();
+                hit_cache.clear();
+            }
+
+            // Expire gaps that end before this A
+            active.advance_while(|g| (g.end as u64) <= a_start);
+            active.compact_if_needed();
+
+            // Pull gaps that start before this A ends
+            while let Some(gap) = pending_gap {
+                if gap.chrom_idx > chrom_idx || (gap.chrom_idx == chrom_idx && gap.start >= a_end) {
+                    break;
+                }
+                stats.b_intervals += 1;
+                if gap.chrom_idx == chrom_idx && gap.end > a_start {
+                    let mut line = Vec::with_capacity(chrom.len() + 24);
+                    line.extend_from_slice(gaps.chrom_name(gap.chrom_idx).as_bytes());
+                    line.push(b'\t');
+                    line.extend_from_slice(itoa_buf.format(gap.start).as_bytes());
+                    line.push(b'\t');
+                    line.extend_from_slice(itoa_buf.format(gap.end).as_bytes());
+                    active.push(ActiveB {
+                        start: gap.start as u32,
+                        end: gap.end as u32,
+                        line,
+                    });
+                }
+                pending_gap = gaps.next_gap()?;
+            }
+
+            stats.max_active_b = stats.max_active_b.max(active.len());
+
+            let active_slice = active.as_slice();
+            if hit_cache.refresh(a_start, a_end, active_slice.len(), |hits| {
+                self.collect_hits(
+                    output_mode,
+                    has_filters,
+                    (a_start, a_end),
+                    active_slice,
+                    hits,
+                )
+            }) {
+                stats.a_reused += 1;
+            }
+            if hit_cache.capped {
+                stats.a_capped += 1;
+            }
+
+            self.write_overlaps(
+                &mut writer,
+                output_mode,
+                &mut stats,
+                &mut itoa_buf,
+                line_bytes,
+                chrom,
+                (a_start, a_end),
+                rest_start,
+                active_slice,
+                &hit_cache.hits,
+                b_columns,
+            )?;
+        }
+
+        writer.flush().map_err(BedError::Io)?;
+        Ok(stats)
     }
 
     /// Optimized streaming intersect with zero-allocation parsing.
@@ -208,25 +456,21 @@ impl StreamingIntersectCommand {
     /// - Raw line parsing with memchr (no String allocation per record)
     /// - Vec + head_idx instead of VecDeque (better cache locality)
     /// - Stores raw line bytes for output (avoids formatting overhead)
-    fn run_optimized<P: AsRef<Path>, W: Write>(
+    fn run_optimized<P: AsRef<Path>, R: BufRead, W: Write>(
         &self,
         a_path: P,
-        b_path: P,
+        mut b_reader: R,
         output: &mut W,
     ) -> Result<StreamingStats, BedError> {
         let mut stats = StreamingStats::default();
 
-        // Output buffer (2MB default, reduced from 8MB for memory efficiency)
-        let mut writer = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
+        // Coalesce output records into large writes
+        let mut writer = BatchWriter::new(output);
 
         // Stream A file
-        let a_file = File::open(a_path.as_ref())?;
+        let a_file = open_input(a_path.as_ref())?;
         let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
 
-        // Stream B file
-        let b_file = File::open(b_path.as_ref())?;
-        let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
-
         // Reusable line buffers
         let mut a_line_buf = String::with_capacity(1024);
         let mut b_line_buf = String::with_capacity(1024);
@@ -237,19 +481,22 @@ impl StreamingIntersectCommand {
         // Pending B: chrom stored separately
         let mut b_chrom: Vec<u8> = Vec::with_capacity(64);
         let mut pending_b =
-            Self::read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+            self.read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
         let mut b_exhausted = pending_b.is_none();
+        // Width of the null B record, from the first B record
+        let b_columns = pending_b.as_ref().map_or(3, |b| column_count(&b.line));
 
         // Track seen chromosomes for sort validation
-        let mut seen_a_chroms: HashSet<Vec<u8>> = HashSet::new();
-        let mut seen_b_chroms: HashSet<Vec<u8>> = HashSet::new();
+        let mut seen_a_chroms = ChromTable::new();
+        let mut seen_b_chroms = ChromTable::new();
         if !b_exhausted {
-            seen_b_chroms.insert(b_chrom.clone());
+            seen_b_chroms.intern(&b_chrom);
         }
 
         // Active set: Vec with head index (better cache locality than VecDeque)
         let mut active: Vec<ActiveB> = Vec::with_capacity(1024);
         let mut head_idx: usize = 0;
+        let mut hit_cache = HitCache::default();
 
         // Sorted validation state
         let mut prev_a_start: u64 = 0;
@@ -314,7 +561,7 @@ impl StreamingIntersectCommand {
             let chrom_changed = chrom != a_chrom.as_slice();
             if chrom_changed {
                 // Update A chromosome (reuses buffer)
-                seen_a_chroms.insert(a_chrom.clone());
+                seen_a_chroms.intern(&a_chrom);
                 a_chrom.clear();
                 a_chrom.extend_from_slice(chrom);
 
@@ -322,11 +569,12 @@ impl StreamingIntersectCommand {
                 active.clear();
                 head_idx = 0;
                 prev_b_start = 0;
+                hit_cache.clear();
 
                 // Skip B records until we reach this chromosome (or B has already passed it)
                 if !b_exhausted && !seen_b_chroms.contains(chrom) {
                     while b_chrom.as_slice() != chrom {
-                        pending_b = Self::read_next_b_optimized(
+                        pending_b = self.read_next_b_optimized(
                             &mut b_reader,
                             &mut b_line_buf,
                             &mut b_chrom,
@@ -336,7 +584,7 @@ impl StreamingIntersectCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -377,7 +625,7 @@ impl StreamingIntersectCommand {
                         }
                         // B hasn't reached A's chromosome yet, skip it
                         stats.b_intervals += 1;
-                        pending_b = Self::read_next_b_optimized(
+                        pending_b = self.read_next_b_optimized(
                             &mut b_reader,
                             &mut b_line_buf,
                             &mut b_chrom,
@@ -393,7 +641,7 @@ impl StreamingIntersectCommand {
                                 String::from_utf8_lossy(&b_chrom)
                             )));
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                         continue;
                     }
 
@@ -412,7 +660,7 @@ impl StreamingIntersectCommand {
                     // Read next B
                     stats.b_intervals += 1;
                     pending_b =
-                        Self::read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        self.read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
                     if pending_b.is_none() {
                         b_exhausted = true;
                         break;
@@ -426,7 +674,7 @@ impl StreamingIntersectCommand {
                             String::from_utf8_lossy(&b_chrom)
                         )));
                     }
-                    seen_b_chroms.insert(b_chrom.clone());
+                    seen_b_chroms.intern(&b_chrom);
                 }
             }
 
@@ -446,192 +694,250 @@ impl StreamingIntersectCommand {
                 warned_large_window = true;
             }
 
-            // Step 3: Process overlaps based on output mode
+            // Step 3: Find overlaps, reusing the previous A's hits for duplicates
             let active_slice = &active[head_idx..];
+            if hit_cache.refresh(a_start, a_end, active_slice.len(), |hits| {
+                self.collect_hits(
+                    output_mode,
+                    has_filters,
+                    (a_start, a_end),
+                    active_slice,
+                    hits,
+                )
+            }) {
+                stats.a_reused += 1;
+            }
+            if hit_cache.capped {
+                stats.a_capped += 1;
+            }
 
-            match output_mode {
-                OutputMode::NoOverlap => {
-                    // -v mode: output A if no overlaps found
-                    let has_overlap = active_slice.iter().any(|b| {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-                        b_end > a_start
-                            && b_start < a_end
-                            && (!has_filters
-                                || self.passes_filters_raw(a_start, a_end, b_start, b_end))
-                    });
+            // Step 4: Write output based on output mode
+            self.write_overlaps(
+                &mut writer,
+                output_mode,
+                &mut stats,
+                &mut itoa_buf,
+                line_bytes,
+                chrom,
+                (a_start, a_end),
+                rest_start,
+                active_slice,
+                &hit_cache.hits,
+                b_columns,
+            )?;
+        }
 
-                    if !has_overlap {
-                        writer.write_all(line_bytes)?;
-                        writer.write_all(b"\n")?;
-                    }
-                }
+        // Count remaining B intervals for stats
+        while pending_b.is_some() {
+            stats.b_intervals += 1;
+            pending_b = self.read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+        }
 
-                OutputMode::Count => {
-                    // -c mode: output A with overlap count
-                    let count = active_slice
-                        .iter()
-                        .filter(|b| {
-                            let b_start = b.start as u64;
-                            let b_end = b.end as u64;
-                            b_end > a_start
-                                && b_start < a_end
-                                && (!has_filters
-                                    || self.passes_filters_raw(a_start, a_end, b_start, b_end))
-                        })
-                        .count();
+        writer.flush().map_err(BedError::Io)?;
+        Ok(stats)
+    }
+
+    /// Collect indices of active B intervals that overlap A and pass the filters.
+    ///
+    /// For `-u`/`-v` only the existence of a hit matters, so collection stops
+    /// at the first one. Modes that print one line per overlap stop at
+    /// `max_hits`; the return value tells whether a further hit was cut off.
+    #[inline]
+    fn collect_hits(
+        &self,
+        output_mode: OutputMode,
+        has_filters: bool,
+        (a_start, a_end): (u64, u64),
+        active_slice: &[ActiveB],
+        hits: &mut Vec<u32>,
+    ) -> bool {
+        let first_only = matches!(output_mode, OutputMode::Unique | OutputMode::NoOverlap);
+        let limit = match output_mode {
+            OutputMode::Unique | OutputMode::NoOverlap | OutputMode::Count => usize::MAX,
+            _ => self.max_hits.unwrap_or(usize::MAX),
+        };
+        for (i, b) in active_slice.iter().enumerate() {
+            let b_start = b.start as u64;
+            let b_end = b.end as u64;
+
+            if b_end <= a_start || b_start >= a_end {
+                continue;
+            }
+
+            if has_filters && !self.passes_filters_raw(a_start, a_end, b_start, b_end) {
+                continue;
+            }
 
+            if hits.len() == limit {
+                return true;
+            }
+            hits.push(i as u32);
+            if first_only {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Write the output for one A record given its overlapping B intervals.
+    ///
+    /// `hits` indexes into `active_slice` (see [`Self::collect_hits`]).
+    #[inline]
+    fn write_overlaps<W: Write>(
+        &self,
+        writer: &mut BatchWriter<W>,
+        output_mode: OutputMode,
+        stats: &mut StreamingStats,
+        itoa_buf: &mut itoa::Buffer,
+        line_bytes: &[u8],
+        chrom: &[u8],
+        (a_start, a_end): (u64, u64),
+        rest_start: usize,
+        active_slice: &[ActiveB],
+        hits: &[u32],
+        b_columns: usize,
+    ) -> io::Result<()> {
+        match output_mode {
+            OutputMode::NoOverlap => {
+                // -v mode: output A if no overlaps found
+                if hits.is_empty() {
                     writer.write_all(line_bytes)?;
-                    writer.write_all(b"\t")?;
-                    writer.write_all(itoa_buf.format(count).as_bytes())?;
                     writer.write_all(b"\n")?;
                 }
+            }
 
-                OutputMode::Unique => {
-                    // -u mode: output A once if any overlap exists
-                    let has_overlap = active_slice.iter().any(|b| {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-                        b_end > a_start
-                            && b_start < a_end
-                            && (!has_filters
-                                || self.passes_filters_raw(a_start, a_end, b_start, b_end))
-                    });
+            OutputMode::Count => {
+                // -c mode: output A with overlap count
+                writer.write_all(line_bytes)?;
+                writer.write_all(b"\t")?;
+                writer.write_all(itoa_buf.format(hits.len()).as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
 
-                    if has_overlap {
-                        writer.write_all(line_bytes)?;
-                        writer.write_all(b"\n")?;
-                        stats.overlaps_found += 1;
-                    }
+            OutputMode::Unique => {
+                // -u mode: output A once if any overlap exists
+                if !hits.is_empty() {
+                    writer.write_all(line_bytes)?;
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
                 }
+            }
 
-                OutputMode::Default => {
-                    // Default: output overlap region with A's extra fields
-                    for b in active_slice {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-
-                        if b_end <= a_start || b_start >= a_end {
-                            continue;
-                        }
-
-                        if has_filters && !self.passes_filters_raw(a_start, a_end, b_start, b_end) {
-                            continue;
-                        }
-
-                        let overlap_start = a_start.max(b_start);
-                        let overlap_end = a_end.min(b_end);
+            OutputMode::Default => {
+                // Default: output overlap region with A's extra fields
+                for &i in hits {
+                    let b = &active_slice[i as usize];
+                    let overlap_start = a_start.max(b.start as u64);
+                    let overlap_end = a_end.min(b.end as u64);
 
-                        // Write overlap region with A's extra fields
-                        writer.write_all(chrom)?;
-                        writer.write_all(b"\t")?;
-                        writer.write_all(itoa_buf.format(overlap_start).as_bytes())?;
-                        writer.write_all(b"\t")?;
-                        writer.write_all(itoa_buf.format(overlap_end).as_bytes())?;
-                        // Write A's extra fields if present
-                        if rest_start < line_bytes.len() {
-                            writer.write_all(&line_bytes[rest_start..])?;
-                        }
-                        writer.write_all(b"\n")?;
-                        stats.overlaps_found += 1;
+                    // Write overlap region with A's extra fields
+                    writer.write_all(chrom)?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(itoa_buf.format(overlap_start).as_bytes())?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(itoa_buf.format(overlap_end).as_bytes())?;
+                    // Write A's extra fields if present
+                    if rest_start < line_bytes.len() {
+                        writer.write_all(&line_bytes[rest_start..])?;
                     }
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
                 }
+            }
 
-                OutputMode::WriteA => {
-                    // -wa: output A record once per overlap
-                    for b in active_slice {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-
-                        if b_end <= a_start || b_start >= a_end {
-                            continue;
-                        }
+            OutputMode::WriteA => {
+                // -wa: output A record once per overlap
+                for _ in hits {
+                    writer.write_all(line_bytes)?;
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
+                }
+            }
 
-                        if has_filters && !self.passes_filters_raw(a_start, a_end, b_start, b_end) {
-                            continue;
-                        }
+            OutputMode::WriteB => {
+                // -wb: output overlap region + B record
+                for &i in hits {
+                    let b = &active_slice[i as usize];
+                    let overlap_start = a_start.max(b.start as u64);
+                    let overlap_end = a_end.min(b.end as u64);
 
-                        writer.write_all(line_bytes)?;
-                        writer.write_all(b"\n")?;
-                        stats.overlaps_found += 1;
+                    // Write overlap region with A's extra fields
+                    writer.write_all(chrom)?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(itoa_buf.format(overlap_start).as_bytes())?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(itoa_buf.format(overlap_end).as_bytes())?;
+                    if rest_start < line_bytes.len() {
+                        writer.write_all(&line_bytes[rest_start..])?;
                     }
+                    // Tab separator + B record
+                    writer.write_all(b"\t")?;
+                    // Write B's raw line (already trimmed)
+                    writer.write_all(&b.line)?;
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
                 }
+            }
 
-                OutputMode::WriteB => {
-                    // -wb: output overlap region + B record
-                    for b in active_slice {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-
-                        if b_end <= a_start || b_start >= a_end {
-                            continue;
-                        }
-
-                        if has_filters && !self.passes_filters_raw(a_start, a_end, b_start, b_end) {
-                            continue;
-                        }
+            OutputMode::WriteBoth => {
+                // -wa -wb: output A + B for each overlap
+                for &i in hits {
+                    writer.write_pair(line_bytes, &active_slice[i as usize].line)?;
+                    stats.overlaps_found += 1;
+                }
+            }
 
-                        let overlap_start = a_start.max(b_start);
-                        let overlap_end = a_end.min(b_end);
+            OutputMode::Fractions => {
+                // --overlap-fractions: output A + B + overlap columns
+                for &i in hits {
+                    let b = &active_slice[i as usize];
+                    writer.write_all(line_bytes)?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(&b.line)?;
+                    write_overlap_fractions(
+                        writer,
+                        (a_start, a_end),
+                        (b.start as u64, b.end as u64),
+                    )?;
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
+                }
+            }
 
-                        // Write overlap region with A's extra fields
-                        writer.write_all(chrom)?;
-                        writer.write_all(b"\t")?;
-                        writer.write_all(itoa_buf.format(overlap_start).as_bytes())?;
-                        writer.write_all(b"\t")?;
-                        writer.write_all(itoa_buf.format(overlap_end).as_bytes())?;
-                        if rest_start < line_bytes.len() {
-                            writer.write_all(&line_bytes[rest_start..])?;
-                        }
-                        // Tab separator + B record
+            OutputMode::Join { overlap_bp, outer } => {
+                // -wo / -loj / -wao: output A + B (+ overlap bp)
+                for &i in hits {
+                    let b = &active_slice[i as usize];
+                    writer.write_all(line_bytes)?;
+                    writer.write_all(b"\t")?;
+                    writer.write_all(&b.line)?;
+                    if overlap_bp {
+                        let bp = a_end.min(b.end as u64) - a_start.max(b.start as u64);
                         writer.write_all(b"\t")?;
-                        // Write B's raw line (already trimmed)
-                        writer.write_all(&b.line)?;
-                        writer.write_all(b"\n")?;
-                        stats.overlaps_found += 1;
+                        writer.write_all(itoa_buf.format(bp).as_bytes())?;
                     }
+                    writer.write_all(b"\n")?;
+                    stats.overlaps_found += 1;
                 }
-
-                OutputMode::WriteBoth => {
-                    // -wa -wb: output A + B for each overlap
-                    for b in active_slice {
-                        let b_start = b.start as u64;
-                        let b_end = b.end as u64;
-
-                        if b_end <= a_start || b_start >= a_end {
-                            continue;
-                        }
-
-                        if has_filters && !self.passes_filters_raw(a_start, a_end, b_start, b_end) {
-                            continue;
-                        }
-
-                        // Write A record
-                        writer.write_all(line_bytes)?;
-                        // Tab separator + B record
-                        writer.write_all(b"\t")?;
-                        writer.write_all(&b.line)?;
-                        writer.write_all(b"\n")?;
-                        stats.overlaps_found += 1;
+                if outer && hits.is_empty() {
+                    writer.write_all(line_bytes)?;
+                    write_null_b(writer, b_columns)?;
+                    if overlap_bp {
+                        writer.write_all(b"\t0")?;
                     }
+                    writer.write_all(b"\n")?;
                 }
             }
         }
 
-        // Count remaining B intervals for stats
-        while pending_b.is_some() {
-            stats.b_intervals += 1;
-            pending_b = Self::read_next_b_optimized(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
-        }
-
-        writer.flush().map_err(BedError::Io)?;
-        Ok(stats)
+        Ok(())
     }
 
     /// Read next B interval with zero-allocation parsing.
     #[inline]
-    fn read_next_b_optimized(
-        reader: &mut BufReader<File>,
+    fn read_next_b_optimized<R: BufRead>(
+        &self,
+        reader: &mut R,
         line_buf: &mut String,
         chrom_buf: &mut Vec<u8>,
     ) -> Result<Option<ActiveB>, BedError> {
@@ -650,10 +956,13 @@ impl StreamingIntersectCommand {
             }
 
             // Parse BED3 - skip malformed lines
-            let (chrom, start, end) = match parse_bed3_bytes(line) {
+            let (chrom, start, end, rest) = match parse_bed3_bytes_with_rest(line) {
                 Some(v) => v,
                 None => continue,
             };
+            if !self.b_score.accepts_rest(&line[rest..]) {
+                continue;
+            }
 
             // Update chromosome buffer (reuses allocation)
             chrom_buf.clear();
@@ -667,12 +976,32 @@ impl StreamingIntersectCommand {
         }
     }
 
+    /// Read the next B record that passes the score filter.
+    #[inline]
+    fn next_b_record<R: io::Read>(
+        &self,
+        reader: &mut BedReader<R>,
+    ) -> Result<Option<BedRecord>, BedError> {
+        loop {
+            match reader.read_record()? {
+                Some(b) if !self.b_score.accepts_record(&b) => continue,
+                next => return Ok(next),
+            }
+        }
+    }
+
     /// Check fraction filters without BedRecord (raw coordinates).
     #[inline]
     fn passes_filters_raw(&self, a_start: u64, a_end: u64, b_start: u64, b_end: u64) -> bool {
         // Note: strand filtering is not supported in optimized path (no strand info stored)
         // For -s/-S flags, the old path should be used
 
+        if let Some(bp) = self.min_overlap_bp {
+            if a_end.min(b_end).saturating_sub(a_start.max(b_start)) < bp {
+                return false;
+            }
+        }
+
         if let Some(frac) = self.fraction_a {
             let overlap_start = a_start.max(b_start);
             let overlap_end = a_end.min(b_end);
@@ -731,18 +1060,21 @@ impl StreamingIntersectCommand {
         output: &mut W,
     ) -> Result<StreamingStats, BedError> {
         let mut stats = StreamingStats::default();
-        let mut writer = BufWriter::with_capacity(256 * 1024, output);
+        let mut writer = BatchWriter::new(output);
 
         // Compute output mode once to avoid repeated flag checks
         let output_mode = self.compute_output_mode();
         let has_filters = self.has_filters();
+        let max_hits = self.max_hits.unwrap_or(usize::MAX);
 
         // Active B intervals that could still overlap current/future A intervals
         // Zero-clone: records are MOVED into this deque, never cloned
         let mut active_b: VecDeque<BedRecord> = VecDeque::with_capacity(256);
 
         // Next B record to potentially add to active set
-        let mut pending_b: Option<BedRecord> = b_reader.read_record()?;
+        let mut pending_b: Option<BedRecord> = self.next_b_record(&mut b_reader)?;
+        // Width of the null B record, from the first B record
+        let b_columns = pending_b.as_ref().map_or(3, written_columns);
 
         // Current chromosome we're processing (stored as bytes to avoid allocation)
         let mut current_chrom: Option<String> = None;
@@ -751,15 +1083,15 @@ impl StreamingIntersectCommand {
         let mut b_exhausted_for_chrom = false;
 
         // Sorted validation state
-        let mut prev_a_chrom: Option<String> = None;
+        let mut prev_a_chrom: Option<ChromId> = None;
         let mut prev_a_start: u64 = 0;
-        let mut prev_b_chrom: Option<String> = None;
+        let mut prev_b_chrom: Option<ChromId> = None;
         let mut prev_b_start: u64 = 0;
         let mut warned_large_window = false;
 
         // Track seen chromosomes to handle any sort order (genome or lexicographic)
-        let mut seen_a_chroms: std::collections::HashSet<String> = std::collections::HashSet::new();
-        let mut seen_b_chroms: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut seen_a_chroms = ChromTable::new();
+        let mut seen_b_chroms = ChromTable::new();
         // Note: Don't add pending_b's chrom to seen_b_chroms here
         // It should only be added after validation when we process the record
 
@@ -779,23 +1111,30 @@ impl StreamingIntersectCommand {
 
             // Inline sorted validation for A
             if !self.assume_sorted {
-                if let Some(ref pc) = prev_a_chrom {
-                    // Detect unsorted: chromosome changed but we've seen this one before
-                    if a_chrom != pc.as_str() && seen_a_chroms.contains(a_chrom) {
-                        return Err(BedError::InvalidFormat(format!(
-                            "File A not sorted: chromosome '{}' at record {} comes after '{}'",
-                            a_chrom, stats.a_intervals, pc
-                        )));
+                match prev_a_chrom {
+                    Some(pc) if seen_a_chroms.name(pc) == a_chrom.as_bytes() => {
+                        if a_start < prev_a_start {
+                            return Err(BedError::InvalidFormat(format!(
+                                "File A not sorted: position {} at record {} comes after {} on {}",
+                                a_start, stats.a_intervals, prev_a_start, a_chrom
+                            )));
+                        }
                     }
-                    if a_chrom == pc && a_start < prev_a_start {
-                        return Err(BedError::InvalidFormat(format!(
-                            "File A not sorted: position {} at record {} comes after {} on {}",
-                            a_start, stats.a_intervals, prev_a_start, a_chrom
-                        )));
+                    _ => {
+                        // Detect unsorted: chromosome changed but we've seen this one before
+                        if let Some(pc) = prev_a_chrom {
+                            if seen_a_chroms.contains(a_chrom.as_bytes()) {
+                                return Err(BedError::InvalidFormat(format!(
+                                    "File A not sorted: chromosome '{}' at record {} comes after '{}'",
+                                    a_chrom,
+                                    stats.a_intervals,
+                                    seen_a_chroms.name_lossy(pc)
+                                )));
+                            }
+                        }
+                        prev_a_chrom = Some(seen_a_chroms.intern(a_chrom.as_bytes()));
                     }
                 }
-                seen_a_chroms.insert(a_chrom.to_string());
-                prev_a_chrom = Some(a_chrom.to_string());
                 prev_a_start = a_start;
             }
 
@@ -813,28 +1152,31 @@ impl StreamingIntersectCommand {
                     if b_rec.chrom() == a_chrom {
                         break; // Found matching chromosome
                     }
-                    if seen_b_chroms.contains(a_chrom) {
+                    if seen_b_chroms.contains(a_chrom.as_bytes()) {
                         // B has already passed a_chrom
                         break;
                     }
                     // Inline sorted validation for B
+                    let b_id = seen_b_chroms.get(b_rec.chrom().as_bytes());
                     if !self.assume_sorted {
-                        if let Some(ref pc) = prev_b_chrom {
-                            if b_rec.chrom() != pc.as_str() && seen_b_chroms.contains(b_rec.chrom())
-                            {
+                        if let Some(pc) = prev_b_chrom {
+                            if b_id.is_some_and(|id| id != pc) {
                                 return Err(BedError::InvalidFormat(format!(
                                     "File B not sorted: chromosome '{}' comes after '{}'",
                                     b_rec.chrom(),
-                                    pc
+                                    seen_b_chroms.name_lossy(pc)
                                 )));
                             }
                         }
-                        prev_b_chrom = Some(b_rec.chrom().to_string());
+                    }
+                    let b_id =
+                        b_id.unwrap_or_else(|| seen_b_chroms.intern(b_rec.chrom().as_bytes()));
+                    if !self.assume_sorted {
+                        prev_b_chrom = Some(b_id);
                         prev_b_start = b_rec.start();
                     }
-                    seen_b_chroms.insert(b_rec.chrom().to_string());
                     stats.b_intervals += 1;
-                    pending_b = b_reader.read_record()?;
+                    pending_b = self.next_b_record(&mut b_reader)?;
                     // Note: Don't add pending_b's chrom to seen_b_chroms here
                     // It should only be added after validation in Step 2
                 }
@@ -849,21 +1191,20 @@ impl StreamingIntersectCommand {
                     }
                     // Inline sorted validation for B
                     if !self.assume_sorted {
-                        if let Some(ref pc) = prev_b_chrom {
-                            if b_rec.chrom() == pc && b_rec.start() < prev_b_start {
-                                return Err(BedError::InvalidFormat(format!(
-                                    "File B not sorted: position {} comes after {} on {}",
-                                    b_rec.start(),
-                                    prev_b_start,
-                                    b_rec.chrom()
-                                )));
-                            }
+                        let b_id = seen_b_chroms.intern(b_rec.chrom().as_bytes());
+                        if prev_b_chrom == Some(b_id) && b_rec.start() < prev_b_start {
+                            return Err(BedError::InvalidFormat(format!(
+                                "File B not sorted: position {} comes after {} on {}",
+                                b_rec.start(),
+                                prev_b_start,
+                                b_rec.chrom()
+                            )));
                         }
-                        prev_b_chrom = Some(b_rec.chrom().to_string());
+                        prev_b_chrom = Some(b_id);
                         prev_b_start = b_rec.start();
                     }
                     stats.b_intervals += 1;
-                    pending_b = b_reader.read_record()?;
+                    pending_b = self.next_b_record(&mut b_reader)?;
                 }
             }
 
@@ -882,18 +1223,18 @@ impl StreamingIntersectCommand {
             if !b_exhausted_for_chrom {
                 while let Some(b_rec) = pending_b.take() {
                     // Inline sorted validation for B
+                    let b_id = seen_b_chroms.get(b_rec.chrom().as_bytes());
                     if !self.assume_sorted {
-                        if let Some(ref pc) = prev_b_chrom {
+                        if let Some(pc) = prev_b_chrom {
                             // Detect unsorted: chromosome changed but we've seen this one before
-                            if b_rec.chrom() != pc.as_str() && seen_b_chroms.contains(b_rec.chrom())
-                            {
+                            if b_id.is_some_and(|id| id != pc) {
                                 return Err(BedError::InvalidFormat(format!(
                                     "File B not sorted: chromosome '{}' comes after '{}'",
                                     b_rec.chrom(),
-                                    pc
+                                    seen_b_chroms.name_lossy(pc)
                                 )));
                             }
-                            if b_rec.chrom() == pc.as_str() && b_rec.start() < prev_b_start {
+                            if b_id == Some(pc) && b_rec.start() < prev_b_start {
                                 return Err(BedError::InvalidFormat(format!(
                                     "File B not sorted: position {} comes after {} on {}",
                                     b_rec.start(),
@@ -902,10 +1243,13 @@ impl StreamingIntersectCommand {
                                 )));
                             }
                         }
-                        prev_b_chrom = Some(b_rec.chrom().to_string());
+                    }
+                    let b_id =
+                        b_id.unwrap_or_else(|| seen_b_chroms.intern(b_rec.chrom().as_bytes()));
+                    if !self.assume_sorted {
+                        prev_b_chrom = Some(b_id);
                         prev_b_start = b_rec.start();
                     }
-                    seen_b_chroms.insert(b_rec.chrom().to_string());
 
                     // Different chromosome - stop adding, put record back
                     if b_rec.chrom() != a_chrom {
@@ -934,7 +1278,7 @@ impl StreamingIntersectCommand {
                     // If B.end <= a_start, the record is simply dropped (no overlap)
 
                     // Read next B record
-                    pending_b = b_reader.read_record()?;
+                    pending_b = self.next_b_record(&mut b_reader)?;
                 }
             }
 
@@ -959,6 +1303,7 @@ impl StreamingIntersectCommand {
             // Step 3: Process overlaps based on output mode
             // Helper closure to check if B overlaps A
             let overlaps = |b: &BedRecord| b.end() > a_start && b.start() < a_end;
+            let mut reported = 0;
 
             match output_mode {
                 OutputMode::NoOverlap => {
@@ -1023,6 +1368,11 @@ impl StreamingIntersectCommand {
                         if has_filters && !self.passes_filters(&a_rec, b_rec) {
                             continue;
                         }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
 
                         output_buf.clear();
                         self.write_overlap_region(&mut output_buf, &a_rec, b_rec, &mut itoa_buf);
@@ -1042,6 +1392,11 @@ impl StreamingIntersectCommand {
                         if has_filters && !self.passes_filters(&a_rec, b_rec) {
                             continue;
                         }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
 
                         output_buf.clear();
                         self.write_record(&mut output_buf, &a_rec, &mut itoa_buf);
@@ -1061,6 +1416,11 @@ impl StreamingIntersectCommand {
                         if has_filters && !self.passes_filters(&a_rec, b_rec) {
                             continue;
                         }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
 
                         output_buf.clear();
                         self.write_overlap_with_b(&mut output_buf, &a_rec, b_rec, &mut itoa_buf);
@@ -1080,12 +1440,88 @@ impl StreamingIntersectCommand {
                         if has_filters && !self.passes_filters(&a_rec, b_rec) {
                             continue;
                         }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
+
+                        output_buf.clear();
+                        self.write_both_records(&mut output_buf, &a_rec, b_rec, &mut itoa_buf);
+                        writer.write_all(&output_buf)?;
+                        stats.overlaps_found += 1;
+                    }
+                }
+
+                OutputMode::Fractions => {
+                    // --overlap-fractions: output A + B + overlap columns
+                    for b_rec in active_b.iter() {
+                        if b_rec.end() <= a_start || b_rec.start() >= a_end {
+                            continue;
+                        }
+
+                        if has_filters && !self.passes_filters(&a_rec, b_rec) {
+                            continue;
+                        }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
+
+                        output_buf.clear();
+                        self.write_both_records(&mut output_buf, &a_rec, b_rec, &mut itoa_buf);
+                        output_buf.pop();
+                        write_overlap_fractions(
+                            &mut output_buf,
+                            (a_start, a_end),
+                            (b_rec.start(), b_rec.end()),
+                        )?;
+                        output_buf.push(b'\n');
+                        writer.write_all(&output_buf)?;
+                        stats.overlaps_found += 1;
+                    }
+                }
+
+                OutputMode::Join { overlap_bp, outer } => {
+                    // -wo / -loj / -wao: output A + B (+ overlap bp)
+                    for b_rec in active_b.iter() {
+                        if b_rec.end() <= a_start || b_rec.start() >= a_end {
+                            continue;
+                        }
+
+                        if has_filters && !self.passes_filters(&a_rec, b_rec) {
+                            continue;
+                        }
+                        if reported == max_hits {
+                            stats.a_capped += 1;
+                            break;
+                        }
+                        reported += 1;
 
                         output_buf.clear();
                         self.write_both_records(&mut output_buf, &a_rec, b_rec, &mut itoa_buf);
+                        if overlap_bp {
+                            output_buf.pop();
+                            let bp = a_rec.interval.overlap_length(&b_rec.interval);
+                            output_buf.push(b'\t');
+                            output_buf.extend_from_slice(itoa_buf.format(bp).as_bytes());
+                            output_buf.push(b'\n');
+                        }
                         writer.write_all(&output_buf)?;
                         stats.overlaps_found += 1;
                     }
+                    if outer && reported == 0 {
+                        output_buf.clear();
+                        self.write_record(&mut output_buf, &a_rec, &mut itoa_buf);
+                        output_buf.pop();
+                        write_null_b(&mut output_buf, b_columns)?;
+                        if overlap_bp {
+                            output_buf.extend_from_slice(b"\t0");
+                        }
+                        output_buf.push(b'\n');
+                        writer.write_all(&output_buf)?;
+                    }
                 }
             }
         }
@@ -1093,7 +1529,7 @@ impl StreamingIntersectCommand {
         // Count remaining B intervals for stats
         while pending_b.is_some() {
             stats.b_intervals += 1;
-            pending_b = b_reader.read_record()?;
+            pending_b = self.next_b_record(&mut b_reader)?;
         }
 
         writer.flush().map_err(BedError::Io)?;
@@ -1117,6 +1553,12 @@ impl StreamingIntersectCommand {
             }
         }
 
+        if let Some(bp) = self.min_overlap_bp {
+            if a.interval.overlap_length(&b.interval) < bp {
+                return false;
+            }
+        }
+
         // Fraction filtering
         if let Some(frac) = self.fraction_a {
             if !a.interval.overlaps_by_fraction(&b.interval, frac) {
@@ -1253,7 +1695,11 @@ impl StreamingIntersectCommand {
             buf.extend_from_slice(name.as_bytes());
             if let Some(score) = rec.score {
                 buf.push(b'\t');
-                buf.extend_from_slice(itoa_buf.format(score as i64).as_bytes());
+                if score.fract() == 0.0 {
+                    buf.extend_from_slice(itoa_buf.format(score as i64).as_bytes());
+                } else {
+                    let _ = write!(buf, "{}", display_score(score));
+                }
                 if let Some(strand) = rec.strand {
                     buf.push(b'\t');
                     match strand {
@@ -1278,14 +1724,23 @@ pub struct StreamingStats {
     pub overlaps_found: usize,
     /// Maximum size of active B set (memory high-water mark)
     pub max_active_b: usize,
+    /// A intervals that reused the previous A's overlaps (identical coordinates)
+    pub a_reused: usize,
+    /// A intervals whose overlaps were cut off by `--max-hits`
+    pub a_capped: usize,
 }
 
 impl std::fmt::Display for StreamingStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "A intervals: {}, B intervals: {}, Overlaps: {}, Max active B: {}",
-            self.a_intervals, self.b_intervals, self.overlaps_found, self.max_active_b
+            "A intervals: {}, B intervals: {}, Overlaps: {}, Max active B: {}, Reused A: {}, Capped A: {}",
+            self.a_intervals,
+            self.b_intervals,
+            self.overlaps_found,
+            self.max_active_b,
+            self.a_reused,
+            self.a_capped
         )
     }
 }
@@ -1293,6 +1748,7 @@ impl std::fmt::Display for StreamingStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::NamedTempFile;
 
     fn make_bed_content(intervals: &[(&str, u64, u64)]) -> String {
         intervals
@@ -1658,8 +2114,150 @@ mod tests {
         assert_eq!(stats.b_intervals, 2);
     }
 
+    #[test]
+    fn test_max_hits() {
+        // The second A repeats the first and takes the cached (capped) hits
+        let a = "chr1\t100\t200\ta1\t0\t+\nchr1\t100\t200\ta2\t0\t+\n\
+                 chr1\t300\t400\ta3\t0\t+\n";
+        let b = "chr1\t110\t120\tb1\t0\t+\nchr1\t130\t140\tb2\t0\t+\n\
+                 chr1\t150\t160\tb3\t0\t+\nchr1\t350\t360\tb4\t0\t+\n";
+        let mut a_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a.as_bytes()).unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        b_file.write_all(b.as_bytes()).unwrap();
+
+        // Optimized path, then the strand-aware record path
+        for same_strand in [false, true] {
+            let mut cmd = StreamingIntersectCommand::new();
+            cmd.write_b = true;
+            cmd.max_hits = Some(2);
+            cmd.same_strand = same_strand;
+            let mut output = Vec::new();
+            let stats = cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+            let names: Vec<String> = String::from_utf8(output)
+                .unwrap()
+                .lines()
+                .map(|l| l.split('\t').nth(9).unwrap().to_string())
+                .collect();
+            assert_eq!(names, ["b1", "b2", "b1", "b2", "b4"]);
+            assert_eq!(stats.overlaps_found, 5);
+            assert_eq!(stats.a_capped, 2);
+        }
+
+        // Count ignores the cap
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.count = true;
+        cmd.max_hits = Some(1);
+        let mut output = Vec::new();
+        let stats = cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("chr1\t100\t200\ta1\t0\t+\t3\n"));
+        assert_eq!(stats.a_capped, 0);
+    }
+
+    #[test]
+    fn test_overlap_fractions() {
+        let a = "chr1\t100\t200\ta1\t0\t+\n";
+        let b = "chr1\t150\t350\tb1\t0\t+\n";
+        let mut a_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a.as_bytes()).unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        b_file.write_all(b.as_bytes()).unwrap();
+
+        // Optimized path, then the strand-aware record path
+        for same_strand in [false, true] {
+            let mut cmd = StreamingIntersectCommand::new();
+            cmd.overlap_fractions = true;
+            cmd.same_strand = same_strand;
+            let mut output = Vec::new();
+            cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "chr1\t100\t200\ta1\t0\t+\tchr1\t150\t350\tb1\t0\t+\t50\t0.5000000\t0.2500000\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_join_modes() {
+        let a = "chr1\t100\t200\ta1\t0\t+\nchr1\t300\t400\ta2\t0\t+\nchr2\t0\t10\ta3\t0\t+\n";
+        let b = "chr1\t150\t250\tb1\t0\t+\n";
+        let mut a_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a.as_bytes()).unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        b_file.write_all(b.as_bytes()).unwrap();
+
+        let hit = "chr1\t100\t200\ta1\t0\t+\tchr1\t150\t250\tb1\t0\t+";
+        let null = "\t.\t-1\t-1\t.\t.\t.";
+        // Optimized path, then the strand-aware record path
+        for same_strand in [false, true] {
+            let run = |write_overlap: bool, left_outer: bool| {
+                let mut cmd = StreamingIntersectCommand::new();
+                cmd.write_overlap = write_overlap;
+                cmd.left_outer = left_outer;
+                cmd.same_strand = same_strand;
+                let mut output = Vec::new();
+                cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+                String::from_utf8(output).unwrap()
+            };
+
+            assert_eq!(run(true, false), format!("{}\t50\n", hit));
+            assert_eq!(
+                run(false, true),
+                format!(
+                    "{}\nchr1\t300\t400\ta2\t0\t+{}\nchr2\t0\t10\ta3\t0\t+{}\n",
+                    hit, null, null
+                )
+            );
+            assert_eq!(
+                run(true, true),
+                format!(
+                    "{}\t50\nchr1\t300\t400\ta2\t0\t+{}\t0\nchr2\t0\t10\ta3\t0\t+{}\t0\n",
+                    hit, null, null
+                )
+            );
+        }
+    }
+
     // ==================== Zero-Clone Verification ====================
 
+    #[test]
+    fn test_duplicate_a_reuses_hits() {
+        // Runs of identical A coordinates (with differing names), then a
+        // distinct A that must not see the cached hits.
+        let a = "chr1\t100\t200\ttx1\nchr1\t100\t200\ttx2\nchr1\t100\t200\ttx3\n\
+                 chr1\t100\t300\ttx4\nchr2\t100\t200\ttx5\nchr2\t100\t200\ttx6\n";
+        let b = "chr1\t150\t160\tb1\nchr1\t190\t250\tb2\nchr2\t0\t50\tb3\n";
+        let mut a_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a.as_bytes()).unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        b_file.write_all(b.as_bytes()).unwrap();
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.count = true;
+        cmd.fraction_b = Some(0.5);
+        let mut output = Vec::new();
+        let stats = cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t200\ttx1\t1\nchr1\t100\t200\ttx2\t1\nchr1\t100\t200\ttx3\t1\n\
+             chr1\t100\t300\ttx4\t2\nchr2\t100\t200\ttx5\t0\nchr2\t100\t200\ttx6\t0\n"
+        );
+        assert_eq!(stats.a_reused, 3);
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.write_a = true;
+        cmd.write_b = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result.matches("\tb2\n").count(), 4);
+        assert!(result.contains("chr1\t100\t200\ttx3\tchr1\t150\t160\tb1\n"));
+    }
+
     #[test]
     fn test_output_mode_computation() {
         let mut cmd = StreamingIntersectCommand::new();
@@ -1686,4 +2284,87 @@ mod tests {
         cmd.no_overlap = true;
         assert_eq!(cmd.compute_output_mode(), OutputMode::NoOverlap);
     }
+
+    #[test]
+    fn test_b_score_filter_both_paths() {
+        use std::io::Write as IoWrite;
+
+        let a_content = "chr1\t100\t200\tA\n";
+        let b_content =
+            "chr1\t120\t130\tlow\t3\nchr1\t140\t150\thigh\t30\nchr1\t160\t170\tnone\t.\n";
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.count = true;
+        cmd.b_score = ScoreFilter::new(Some(10.0), None);
+
+        // Record path (BedReader)
+        let mut output = Vec::new();
+        cmd.run_streaming(
+            BedReader::new(a_content.as_bytes()),
+            BedReader::new(b_content.as_bytes()),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "chr1\t100\t200\tA\t1\n");
+
+        // Raw-line path
+        let mut a_file = NamedTempFile::new().unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a_content.as_bytes()).unwrap();
+        b_file.write_all(b_content.as_bytes()).unwrap();
+        cmd.b_score = ScoreFilter::new(None, Some(10.0));
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "chr1\t100\t200\tA\t1\n");
+    }
+
+    // ==================== Invert-B Tests ====================
+
+    fn run_invert_b(cmd: &StreamingIntersectCommand, a: &str, b: &str) -> String {
+        let mut a_file = NamedTempFile::new().unwrap();
+        a_file.write_all(a.as_bytes()).unwrap();
+        let mut b_file = NamedTempFile::new().unwrap();
+        b_file.write_all(b.as_bytes()).unwrap();
+
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        genome.insert("chr2".to_string(), 500);
+
+        let mut output = Vec::new();
+        cmd.run_invert_b(a_file.path(), b_file.path(), &genome, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_invert_b_reports_uncovered_regions() {
+        let a = "chr1\t100\t500\tgeneA\nchr2\t0\t100\tgeneB\n";
+        let b = "chr1\t150\t200\nchr1\t300\t600\n";
+
+        let cmd = StreamingIntersectCommand::new();
+        let result = run_invert_b(&cmd, a, b);
+
+        assert_eq!(
+            result,
+            "chr1\t100\t150\tgeneA\nchr1\t200\t300\tgeneA\nchr2\t0\t100\tgeneB\n"
+        );
+    }
+
+    #[test]
+    fn test_invert_b_no_overlap_finds_fully_covered() {
+        let a = "chr1\t100\t200\nchr1\t250\t350\n";
+        let b = "chr1\t0\t300\n";
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.no_overlap = true;
+        assert_eq!(run_invert_b(&cmd, a, b), "chr1\t100\t200\n");
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.write_a = true;
+        cmd.write_b = true;
+        assert_eq!(
+            run_invert_b(&cmd, a, b),
+            "chr1\t250\t350\tchr1\t300\t1000\n"
+        );
+    }
 }
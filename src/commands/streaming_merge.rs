@@ -20,8 +20,13 @@
 //! Input file MUST be sorted by chromosome, then by start position.
 
 use crate::bed::{BedError, BedReader};
+use crate::commands::merge::{
+    allowed_gap, within_reach, write_merge_labels, MergeId, RelativeDistance, Representative,
+    ScoreOp, ScoreSummary,
+};
+use crate::interchange::stdin_reader;
 use crate::interval::Strand;
-use std::fs::File;
+use crate::streaming::read_ahead::open_input;
 use std::io::{self, BufReader, BufWriter, Write};
 use std::path::Path;
 
@@ -34,6 +39,21 @@ pub struct StreamingMergeCommand {
     pub strand_specific: bool,
     /// Report count of merged intervals
     pub count: bool,
+    /// Only output merged intervals formed from at least this many records
+    pub min_count: usize,
+    /// Merge distance relative to interval length; overrides `distance`
+    pub relative_distance: Option<RelativeDistance>,
+    /// Merge intervals exactly `distance` apart (book-ended at distance 0)
+    pub book_ended: bool,
+    /// Append an identifier column to each merged interval
+    pub id: Option<MergeId>,
+    /// Append the comma-joined names of the merged records
+    pub report_names: bool,
+    /// Report the coordinates of the record with the highest value in this
+    /// 1-based column, followed by the merged span
+    pub best_by: Option<usize>,
+    /// Append the scores of the merged records combined with this operation
+    pub scores: Option<ScoreOp>,
 }
 
 impl Default for StreamingMergeCommand {
@@ -48,6 +68,13 @@ impl StreamingMergeCommand {
             distance: 0,
             strand_specific: false,
             count: false,
+            min_count: 1,
+            relative_distance: None,
+            book_ended: true,
+            id: None,
+            report_names: false,
+            best_by: None,
+            scores: None,
         }
     }
 
@@ -63,6 +90,56 @@ impl StreamingMergeCommand {
         self
     }
 
+    /// Drop merged intervals formed from fewer than `n` records.
+    pub fn with_min_count(mut self, n: usize) -> Self {
+        self.min_count = n;
+        self
+    }
+
+    /// Measure the merge distance relative to interval length.
+    pub fn with_relative_distance(mut self, relative: Option<RelativeDistance>) -> Self {
+        self.relative_distance = relative;
+        self
+    }
+
+    /// Set whether intervals exactly the merge distance apart are merged.
+    pub fn with_book_ended(mut self, book_ended: bool) -> Self {
+        self.book_ended = book_ended;
+        self
+    }
+
+    /// Append an identifier column to each merged interval.
+    pub fn with_id(mut self, id: Option<MergeId>) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Append the names of the records making up each merged interval.
+    ///
+    /// Names of the current span are held until it is written, so memory
+    /// grows with the largest merged cluster rather than staying constant.
+    pub fn with_names(mut self, report_names: bool) -> Self {
+        self.report_names = report_names;
+        self
+    }
+
+    /// Represent each merged interval by its highest-scoring record.
+    ///
+    /// The record with the largest value in 1-based column `col` (5 for the
+    /// score) gives the first three columns; the merged span follows as two
+    /// extra columns after the other optional columns.
+    pub fn with_best_by(mut self, col: Option<usize>) -> Self {
+        self.best_by = col;
+        self
+    }
+
+    /// Append the scores of each merged interval's records, combined with
+    /// `op`, after the count column.
+    pub fn with_scores(mut self, op: Option<ScoreOp>) -> Self {
+        self.scores = op;
+        self
+    }
+
     /// Execute streaming merge on a sorted BED file.
     ///
     /// Memory usage: O(1) - only tracks current merge span
@@ -71,15 +148,14 @@ impl StreamingMergeCommand {
         input_path: P,
         output: &mut W,
     ) -> Result<StreamingMergeStats, BedError> {
-        let file = File::open(input_path.as_ref())?;
+        let file = open_input(input_path.as_ref())?;
         let reader = BedReader::new(BufReader::with_capacity(64 * 1024, file));
         self.run_streaming(reader, output)
     }
 
     /// Execute streaming merge from stdin.
     pub fn run_stdin<W: Write>(&self, output: &mut W) -> Result<StreamingMergeStats, BedError> {
-        let stdin = io::stdin();
-        let reader = BedReader::new(stdin.lock());
+        let reader = BedReader::new(stdin_reader());
         self.run_streaming(reader, output)
     }
 
@@ -100,6 +176,9 @@ impl StreamingMergeCommand {
         let mut current_end: u64 = 0;
         let mut current_strand: Option<Strand> = None;
         let mut current_count: usize = 0;
+        let mut current_names: Vec<String> = Vec::new();
+        let mut best = Representative::default();
+        let mut scores = ScoreSummary::default();
 
         for result in reader.records() {
             let rec = result?;
@@ -114,7 +193,15 @@ impl StreamingMergeCommand {
             let should_merge = if let Some(ref chrom) = current_chrom {
                 let same_chrom = chrom == rec_chrom;
                 let same_strand = !self.strand_specific || current_strand == rec_strand;
-                let overlaps = rec_start <= current_end + self.distance;
+                let gap = allowed_gap(
+                    self.distance,
+                    self.relative_distance,
+                    current_start,
+                    current_end,
+                    rec_start,
+                    rec_end,
+                );
+                let overlaps = within_reach(rec_start, current_end, gap, self.book_ended);
                 same_chrom && same_strand && overlaps
             } else {
                 false
@@ -127,15 +214,20 @@ impl StreamingMergeCommand {
             } else {
                 // Output current span if exists
                 if let Some(ref chrom) = current_chrom {
-                    self.write_span(
-                        &mut writer,
-                        chrom,
-                        current_start,
-                        current_end,
-                        current_strand,
-                        current_count,
-                    )?;
-                    stats.intervals_written += 1;
+                    if current_count >= self.min_count {
+                        self.write_span(
+                            &mut writer,
+                            chrom,
+                            current_start,
+                            current_end,
+                            current_strand,
+                            current_count,
+                            &current_names,
+                            best.coords(),
+                            &scores,
+                        )?;
+                        stats.intervals_written += 1;
+                    }
                 }
 
                 // Start new span
@@ -144,26 +236,45 @@ impl StreamingMergeCommand {
                 current_end = rec_end;
                 current_strand = rec_strand;
                 current_count = 1;
+                current_names.clear();
+                best.clear();
+                scores.clear();
+            }
+            if let Some(col) = self.best_by {
+                best.offer(rec_start, rec_end, rec.numeric_column(col));
+            }
+            if self.scores.is_some() {
+                scores.offer(&rec);
+            }
+            if self.report_names {
+                current_names.extend(rec.name);
             }
         }
 
         // Output final span
         if let Some(ref chrom) = current_chrom {
-            self.write_span(
-                &mut writer,
-                chrom,
-                current_start,
-                current_end,
-                current_strand,
-                current_count,
-            )?;
-            stats.intervals_written += 1;
+            if current_count >= self.min_count {
+                self.write_span(
+                    &mut writer,
+                    chrom,
+                    current_start,
+                    current_end,
+                    current_strand,
+                    current_count,
+                    &current_names,
+                    best.coords(),
+                    &scores,
+                )?;
+                stats.intervals_written += 1;
+            }
         }
 
         writer.flush().map_err(BedError::Io)?;
         Ok(stats)
     }
 
+    /// Write a merged span; with `representative`, its coordinates replace
+    /// the span's, which follows in two extra columns.
     #[inline]
     fn write_span<W: Write>(
         &self,
@@ -173,39 +284,30 @@ impl StreamingMergeCommand {
         end: u64,
         strand: Option<Strand>,
         count: usize,
+        names: &[String],
+        representative: Option<(u64, u64)>,
+        scores: &ScoreSummary,
     ) -> Result<(), BedError> {
-        if self.strand_specific {
-            if self.count {
-                writeln!(
-                    writer,
-                    "{}\t{}\t{}\t{}\t{}",
-                    chrom,
-                    start,
-                    end,
-                    strand
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| ".".to_string()),
-                    count
-                )
-                .map_err(BedError::Io)?;
-            } else {
-                writeln!(
-                    writer,
-                    "{}\t{}\t{}\t{}",
-                    chrom,
-                    start,
-                    end,
-                    strand
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| ".".to_string())
-                )
-                .map_err(BedError::Io)?;
-            }
-        } else if self.count {
-            writeln!(writer, "{}\t{}\t{}\t{}", chrom, start, end, count).map_err(BedError::Io)?;
-        } else {
-            writeln!(writer, "{}\t{}\t{}", chrom, start, end).map_err(BedError::Io)?;
+        let (rep_start, rep_end) = representative.unwrap_or((start, end));
+        write!(writer, "{}\t{}\t{}", chrom, rep_start, rep_end)?;
+        let strand = self
+            .strand_specific
+            .then(|| strand.unwrap_or(Strand::Unknown));
+        if let Some(strand) = strand {
+            write!(writer, "\t{}", strand)?;
+        }
+        if self.count {
+            write!(writer, "\t{}", count)?;
+        }
+        if let Some(op) = self.scores {
+            scores.write(writer, op)?;
+        }
+        let names = self.report_names.then_some(names);
+        write_merge_labels(writer, self.id, names, chrom, start, end, strand)?;
+        if representative.is_some() {
+            write!(writer, "\t{}\t{}", start, end)?;
         }
+        writer.write_all(b"\n")?;
         Ok(())
     }
 }
@@ -295,6 +397,25 @@ mod tests {
         assert_eq!(lines[0], "chr1\t100\t350");
     }
 
+    #[test]
+    fn test_streaming_merge_book_ended() {
+        let content = make_bed_content(&[("chr1", 100, 200), ("chr1", 200, 300)]);
+        let run = |cmd: StreamingMergeCommand| {
+            let mut output = Vec::new();
+            cmd.run_streaming(BedReader::new(content.as_bytes()), &mut output)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(run(StreamingMergeCommand::new()), "chr1\t100\t300\n");
+        assert_eq!(
+            run(StreamingMergeCommand::new()
+                .with_strand(true)
+                .with_book_ended(false)),
+            "chr1\t100\t200\t.\nchr1\t200\t300\t.\n"
+        );
+    }
+
     #[test]
     fn test_streaming_merge_multiple_chroms() {
         let content = make_bed_content(&[
@@ -338,6 +459,45 @@ mod tests {
         assert!(lines[0].ends_with("\t3")); // 3 intervals merged
     }
 
+    #[test]
+    fn test_streaming_merge_min_count() {
+        let content = make_bed_content(&[
+            ("chr1", 100, 200),
+            ("chr1", 150, 250),
+            ("chr1", 400, 500),
+            ("chr2", 100, 200),
+            ("chr2", 120, 180),
+        ]);
+
+        let mut cmd = StreamingMergeCommand::new().with_min_count(2);
+        cmd.count = true;
+
+        let reader = BedReader::new(content.as_bytes());
+        let mut output = Vec::new();
+        let stats = cmd.run_streaming(reader, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "chr1\t100\t250\t2\nchr2\t100\t200\t2\n");
+        assert_eq!(stats.intervals_written, 2);
+    }
+
+    #[test]
+    fn test_streaming_merge_relative_distance() {
+        let content =
+            make_bed_content(&[("chr1", 100, 200), ("chr1", 205, 305), ("chr1", 320, 420)]);
+
+        let cmd = StreamingMergeCommand::new()
+            .with_relative_distance(Some(RelativeDistance::new(10.0, Default::default())));
+
+        let reader = BedReader::new(content.as_bytes());
+        let mut output = Vec::new();
+        cmd.run_streaming(reader, &mut output).unwrap();
+
+        // Gap 5 <= 10% of 100; gap 15 > 10% of 100
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "chr1\t100\t305\nchr1\t320\t420\n");
+    }
+
     #[test]
     fn test_streaming_merge_touching() {
         let content = make_bed_content(&[
@@ -494,4 +654,38 @@ mod tests {
         let result = cmd.run_streaming(reader, &mut output);
         assert!(result.is_ok(), "Should handle missing strand column");
     }
+
+    #[test]
+    fn test_streaming_merge_id_and_names() {
+        let content =
+            "chr1\t100\t200\tg1\t0\t+\nchr1\t150\t250\tg2\t0\t+\nchr1\t300\t400\tg3\t0\t-\n";
+        let cmd = StreamingMergeCommand::new()
+            .with_strand(true)
+            .with_id(Some(MergeId::Coords))
+            .with_names(true);
+        let mut output = Vec::new();
+        cmd.run_streaming(BedReader::new(content.as_bytes()), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t250\t+\tchr1_100_250_+\tg1,g2\nchr1\t300\t400\t-\tchr1_300_400_-\tg3\n"
+        );
+    }
+
+    #[test]
+    fn test_streaming_merge_best_by() {
+        let content = "chr1\t100\t200\ta\t5\t+\nchr1\t150\t300\tb\t9\t+\n\
+                       chr1\t250\t400\tc\t9\t+\nchr1\t500\t600\td\t1\t-\n";
+        let cmd = StreamingMergeCommand::new()
+            .with_strand(true)
+            .with_best_by(Some(5));
+        let mut output = Vec::new();
+        cmd.run_streaming(BedReader::new(content.as_bytes()), &mut output)
+            .unwrap();
+        // Ties keep the first record
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t150\t300\t+\t100\t400\nchr1\t500\t600\t-\t500\t600\n"
+        );
+    }
 }
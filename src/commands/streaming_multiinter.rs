@@ -3,25 +3,38 @@
 //! Memory complexity: O(k) where k = max overlapping intervals across all files
 //! at any position.
 //!
-//! Uses k-way merge with min-heap to stream through all sorted input files
-//! simultaneously without loading them entirely into memory.
+//! Uses a k-way merge over a loser tree to stream through all sorted input
+//! files simultaneously without loading them entirely into memory.
+//!
+//! When there are more inputs than can be open at once, they are merged in
+//! rounds: each batch is merged into a temporary run file whose lines carry
+//! the original file index, and the runs are merged until few enough remain
+//! to open together. Run files live in a [`ScratchDir`], so they honour
+//! `--temp-dir`.
 //!
 //! REQUIREMENT: All input files must be sorted by (chrom, start) for streaming mode.
 //! Use `--assume-sorted` flag or pre-sort with `grit sort`.
 
 #![allow(clippy::ptr_arg)]
 
+use super::multiinter::write_sparse_header;
 use crate::bed::BedError;
-use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
+use crate::scratch::{ScratchDir, ScratchFile};
+use crate::streaming::buffers::{input_buffer_size, DEFAULT_OUTPUT_BUFFER};
+use crate::streaming::loser_tree::LoserTree;
+use crate::streaming::parsing::{parse_bed3_bytes_with_rest, parse_u64_fast, should_skip_line};
+use crate::streaming::read_ahead::{open_input, InputFile};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Inputs above this count are read with the low-memory buffer size.
+const WIDE_MERGE: usize = 256;
 
 /// An interval from a specific file with its source index.
-#[derive(Debug, Clone)]
+///
+/// Ordered by (chrom, start, end, file index), the order of the merge.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct TaggedInterval {
     chrom: Vec<u8>,
     start: u64,
@@ -29,33 +42,6 @@ struct TaggedInterval {
     file_idx: usize,
 }
 
-/// Wrapper for min-heap (BinaryHeap is max-heap by default).
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct HeapEntry {
-    chrom: Vec<u8>,
-    start: u64,
-    end: u64,
-    file_idx: usize,
-}
-
-impl Ord for HeapEntry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Reverse ordering for min-heap
-        other
-            .chrom
-            .cmp(&self.chrom)
-            .then(other.start.cmp(&self.start))
-            .then(other.end.cmp(&self.end))
-            .then(other.file_idx.cmp(&self.file_idx))
-    }
-}
-
-impl PartialOrd for HeapEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 /// An event in the sweep-line algorithm.
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct Event {
@@ -78,16 +64,42 @@ impl PartialOrd for Event {
     }
 }
 
+/// An input of one merge: an original file or a run from an earlier round.
+#[derive(Debug, Clone)]
+enum Source {
+    Input {
+        path: PathBuf,
+        file_idx: usize,
+    },
+    /// Lines are `chrom start end file_idx`.
+    Run(PathBuf),
+}
+
+impl Source {
+    fn open(&self, buffer_size: usize) -> Result<FileReader<BufReader<InputFile>>, BedError> {
+        let (path, file_idx) = match self {
+            Source::Input { path, file_idx } => (path, Some(*file_idx)),
+            Source::Run(path) => (path, None),
+        };
+        let file = open_input(path)?;
+        Ok(FileReader::new(
+            BufReader::with_capacity(buffer_size, file),
+            file_idx,
+        ))
+    }
+}
+
 /// Reader state for a single file.
 struct FileReader<R: BufRead> {
     reader: R,
     line_buf: String,
-    file_idx: usize,
+    /// Index reported for every interval; `None` reads it from column 4.
+    file_idx: Option<usize>,
     exhausted: bool,
 }
 
 impl<R: BufRead> FileReader<R> {
-    fn new(reader: R, file_idx: usize) -> Self {
+    fn new(reader: R, file_idx: Option<usize>) -> Self {
         Self {
             reader,
             line_buf: String::with_capacity(1024),
@@ -115,18 +127,42 @@ impl<R: BufRead> FileReader<R> {
                 continue;
             }
 
-            if let Some((chrom, start, end)) = parse_bed3_bytes(line_bytes) {
+            if let Some((chrom, start, end, rest)) = parse_bed3_bytes_with_rest(line_bytes) {
+                let file_idx = match self.file_idx {
+                    Some(idx) => idx,
+                    None => line_bytes
+                        .get(rest + 1..)
+                        .and_then(parse_u64_fast)
+                        .ok_or_else(|| {
+                            BedError::InvalidFormat(
+                                "corrupt multiinter run file: missing file index".to_string(),
+                            )
+                        })? as usize,
+                };
                 return Ok(Some(TaggedInterval {
                     chrom: chrom.to_vec(),
                     start,
                     end,
-                    file_idx: self.file_idx,
+                    file_idx,
                 }));
             }
         }
     }
 }
 
+/// Default cap on files open at once: the soft open-file limit less some
+/// headroom for stdio, the output and temporary files.
+pub fn default_max_open_files() -> usize {
+    open_file_limit().map_or(1000, |limit| limit.saturating_sub(64).max(16))
+}
+
+/// Soft limit on open files, where the platform reports one.
+fn open_file_limit() -> Option<usize> {
+    let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+    let line = limits.lines().find(|l| l.starts_with("Max open files"))?;
+    line.split_whitespace().nth(3)?.parse().ok()
+}
+
 /// Streaming multiinter command configuration.
 #[derive(Debug, Clone)]
 pub struct StreamingMultiinterCommand {
@@ -134,6 +170,12 @@ pub struct StreamingMultiinterCommand {
     pub cluster: bool,
     /// Skip sorted validation (faster for pre-sorted input)
     pub assume_sorted: bool,
+    /// Labels reported in the file list column instead of 1-based indices
+    pub names: Option<Vec<String>>,
+    /// Most input files open at once; more inputs are merged in rounds
+    pub max_open_files: usize,
+    /// Write the sparse format: file indices instead of presence flags
+    pub sparse: bool,
 }
 
 impl Default for StreamingMultiinterCommand {
@@ -147,6 +189,9 @@ impl StreamingMultiinterCommand {
         Self {
             cluster: false,
             assume_sorted: false,
+            names: None,
+            max_open_files: default_max_open_files(),
+            sparse: false,
         }
     }
 
@@ -162,6 +207,24 @@ impl StreamingMultiinterCommand {
         self
     }
 
+    /// Report `names` instead of file indices (builder pattern).
+    pub fn with_names(mut self, names: Vec<String>) -> Self {
+        self.names = Some(names);
+        self
+    }
+
+    /// Write the sparse format (builder pattern).
+    pub fn with_sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Cap the number of files open at once (builder pattern).
+    pub fn with_max_open_files(mut self, max_open_files: usize) -> Self {
+        self.max_open_files = max_open_files;
+        self
+    }
+
     /// Execute streaming multiinter.
     ///
     /// Memory: O(k) where k = max overlapping intervals across all files.
@@ -177,26 +240,54 @@ impl StreamingMultiinterCommand {
             return Ok(());
         }
 
-        // Open all files
-        let mut readers = Vec::with_capacity(inputs.len());
-        for (idx, path) in inputs.iter().enumerate() {
-            let file = File::open(path)?;
-            let reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, file);
-            readers.push(FileReader::new(reader, idx));
+        if let Some(ref names) = self.names {
+            if names.len() != inputs.len() {
+                return Err(BedError::InvalidFormat(format!(
+                    "{} names given for {} input files",
+                    names.len(),
+                    inputs.len()
+                )));
+            }
+        }
+
+        let mut sources: Vec<Source> = inputs
+            .iter()
+            .enumerate()
+            .map(|(file_idx, path)| Source::Input {
+                path: path.as_ref().to_path_buf(),
+                file_idx,
+            })
+            .collect();
+
+        // One descriptor of each batch goes to the run being written
+        let limit = self.max_open_files.max(3);
+        let run_dir = if sources.len() > limit {
+            Some(ScratchDir::new()?)
+        } else {
+            None
+        };
+        if let Some(dir) = &run_dir {
+            sources = merge_rounds(sources, limit, dir)?;
         }
 
+        let buffer_size = input_buffer_size(sources.len() > WIDE_MERGE);
+        let readers = sources
+            .iter()
+            .map(|source| source.open(buffer_size))
+            .collect::<Result<Vec<_>, _>>()?;
+
         self.multiinter_streaming(readers, inputs.len(), output)
     }
 
     /// Streaming multiinter implementation using k-way merge.
     ///
     /// Algorithm:
-    /// 1. Initialize min-heap with first interval from each file
+    /// 1. Build a loser tree over the first interval from each reader
     /// 2. Process intervals in sorted order:
     ///    - When entering a new chromosome, process the previous one
     ///    - Accumulate events (start/end) for current chromosome
-    ///    - Pull next interval from the file that provided the current one
-    /// 3. When chromosome changes or all files exhausted, run sweep-line
+    ///    - Pull next interval from the reader that provided the current one
+    /// 3. When chromosome changes or all readers exhausted, run sweep-line
     fn multiinter_streaming<R: BufRead, W: Write>(
         &self,
         mut readers: Vec<FileReader<R>>,
@@ -205,21 +296,16 @@ impl StreamingMultiinterCommand {
     ) -> Result<(), BedError> {
         // Large output buffer (8MB)
         let mut buf_output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
-
-        // Initialize min-heap with first interval from each file
-        let mut heap: BinaryHeap<HeapEntry> = BinaryHeap::with_capacity(n_files);
-
-        for reader in &mut readers {
-            if let Some(interval) = reader.next_interval()? {
-                heap.push(HeapEntry {
-                    chrom: interval.chrom,
-                    start: interval.start,
-                    end: interval.end,
-                    file_idx: interval.file_idx,
-                });
-            }
+        if self.sparse {
+            write_sparse_header(&mut buf_output, self.names.as_deref(), n_files)?;
         }
 
+        let heads = readers
+            .iter_mut()
+            .map(|reader| reader.next_interval())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut tree = LoserTree::new(heads);
+
         // Current chromosome being processed
         let mut current_chrom: Option<Vec<u8>> = None;
         // Events for current chromosome
@@ -228,7 +314,14 @@ impl StreamingMultiinterCommand {
         // itoa buffer for fast integer formatting
         let mut itoa_buf = itoa::Buffer::new();
 
-        while let Some(entry) = heap.pop() {
+        while tree.peek().is_some() {
+            // Pull next interval from the same reader
+            let reader = tree.winner();
+            let next = readers[reader].next_interval()?;
+            let Some(entry) = tree.replace_winner(next) else {
+                break;
+            };
+
             // Check if chromosome changed
             let chrom_changed = match &current_chrom {
                 Some(c) => c != &entry.chrom,
@@ -249,8 +342,6 @@ impl StreamingMultiinterCommand {
                 events.clear();
             }
 
-            current_chrom = Some(entry.chrom.clone());
-
             // Add events for this interval
             events.push(Event {
                 pos: entry.start,
@@ -263,15 +354,7 @@ impl StreamingMultiinterCommand {
                 file_idx: entry.file_idx,
             });
 
-            // Pull next interval from the same file
-            if let Some(next) = readers[entry.file_idx].next_interval()? {
-                heap.push(HeapEntry {
-                    chrom: next.chrom,
-                    start: next.start,
-                    end: next.end,
-                    file_idx: next.file_idx,
-                });
-            }
+            current_chrom = Some(entry.chrom);
         }
 
         // Process last chromosome
@@ -305,26 +388,27 @@ impl StreamingMultiinterCommand {
         // Sort events: by position, then ends before starts
         events.sort_unstable();
 
-        // Track depth per file
+        // Track depth per file, and how many files are covered
         let mut file_depths: Vec<u32> = vec![0; n_files];
+        let mut covered = 0usize;
         let mut prev_pos: u64 = events[0].pos;
-        let mut has_coverage = false;
 
         for event in events.iter() {
             // Output region if there was coverage
-            if event.pos > prev_pos && has_coverage {
+            if event.pos > prev_pos && covered > 0 {
                 self.output_region(chrom, prev_pos, event.pos, &file_depths, output, itoa_buf)?;
             }
 
             // Update depth
+            let depth = &mut file_depths[event.file_idx];
             if event.is_start {
-                file_depths[event.file_idx] += 1;
-            } else {
-                file_depths[event.file_idx] = file_depths[event.file_idx].saturating_sub(1);
+                covered += (*depth == 0) as usize;
+                *depth += 1;
+            } else if *depth > 0 {
+                *depth -= 1;
+                covered -= (*depth == 0) as usize;
             }
 
-            // Check if any file has coverage
-            has_coverage = file_depths.iter().any(|&d| d > 0);
             prev_pos = event.pos;
         }
 
@@ -353,12 +437,16 @@ impl StreamingMultiinterCommand {
             return Ok(());
         }
 
-        // Build list of file indices (1-based)
+        // Build list of file labels (1-based indices unless names are set or
+        // the output is sparse)
         let file_list: Vec<String> = file_depths
             .iter()
             .enumerate()
             .filter(|(_, &d)| d > 0)
-            .map(|(i, _)| (i + 1).to_string())
+            .map(|(i, _)| match &self.names {
+                Some(names) if !self.sparse => names[i].clone(),
+                _ => (i + 1).to_string(),
+            })
             .collect();
 
         // Write output: chrom, start, end, count, file_list, flags...
@@ -381,6 +469,10 @@ impl StreamingMultiinterCommand {
             .map_err(BedError::Io)?;
 
         // Write presence flags
+        if self.sparse {
+            output.write_all(b"\n").map_err(BedError::Io)?;
+            return Ok(());
+        }
         for &depth in file_depths {
             output.write_all(b"\t").map_err(BedError::Io)?;
             output
@@ -394,6 +486,59 @@ impl StreamingMultiinterCommand {
     }
 }
 
+/// Merge `sources` in batches of `limit - 1` into run files under `dir`,
+/// repeating until at most `limit` remain.
+fn merge_rounds(
+    mut sources: Vec<Source>,
+    limit: usize,
+    dir: &ScratchDir,
+) -> Result<Vec<Source>, BedError> {
+    let mut run_count = 0;
+    while sources.len() > limit {
+        let mut runs = Vec::with_capacity(sources.len() / (limit - 1) + 1);
+        for batch in sources.chunks(limit - 1) {
+            let run = dir.create(&format!("run{}.txt", run_count))?;
+            run_count += 1;
+            runs.push(Source::Run(run.path().to_path_buf()));
+            merge_to_run(batch, run)?;
+        }
+        sources = runs;
+    }
+    Ok(sources)
+}
+
+/// Merge `sources` into a run file of `chrom start end file_idx` lines.
+fn merge_to_run(sources: &[Source], run: ScratchFile) -> Result<(), BedError> {
+    let buffer_size = input_buffer_size(sources.len() > WIDE_MERGE);
+    let mut readers = sources
+        .iter()
+        .map(|source| source.open(buffer_size))
+        .collect::<Result<Vec<_>, _>>()?;
+    let heads = readers
+        .iter_mut()
+        .map(|reader| reader.next_interval())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut tree = LoserTree::new(heads);
+
+    let mut out = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, run);
+    let mut itoa_buf = itoa::Buffer::new();
+    while tree.peek().is_some() {
+        let reader = tree.winner();
+        let next = readers[reader].next_interval()?;
+        let Some(entry) = tree.replace_winner(next) else {
+            break;
+        };
+        out.write_all(&entry.chrom)?;
+        for value in [entry.start, entry.end, entry.file_idx as u64] {
+            out.write_all(b"\t")?;
+            out.write_all(itoa_buf.format(value).as_bytes())?;
+        }
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -402,7 +547,7 @@ mod tests {
     fn make_reader(data: &str, idx: usize) -> FileReader<BufReader<Cursor<Vec<u8>>>> {
         let cursor = Cursor::new(data.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
-        FileReader::new(reader, idx)
+        FileReader::new(reader, Some(idx))
     }
 
     #[test]
@@ -551,4 +696,41 @@ mod tests {
         let parts: Vec<&str> = lines[0].split('\t').collect();
         assert_eq!(parts[3], "1"); // count == 1
     }
+
+    #[test]
+    fn test_streaming_multiinter_merge_rounds() {
+        let data = [
+            "chr1\t100\t200\nchr2\t0\t50\n",
+            "chr1\t150\t250\n",
+            "",
+            "#header\nchr1\t180\t300\n",
+            "chr2\t10\t20\n",
+            "chr1\t100\t200\n",
+            "chr1\t0\t1000\nchr2\t40\t60\n",
+        ];
+        let files: Vec<tempfile::NamedTempFile> = data
+            .iter()
+            .map(|content| {
+                let mut file = tempfile::NamedTempFile::new().unwrap();
+                file.write_all(content.as_bytes()).unwrap();
+                file
+            })
+            .collect();
+        let paths: Vec<&Path> = files.iter().map(|f| f.path()).collect();
+
+        let run = |max_open_files: usize| {
+            let cmd = StreamingMultiinterCommand::new()
+                .with_assume_sorted(true)
+                .with_max_open_files(max_open_files);
+            let mut output = Vec::new();
+            cmd.run(&paths, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // 7 inputs through batches of 2 take two rounds of run files
+        let direct = run(100);
+        assert!(direct.contains("chr1\t180\t200\t5\t1,2,4,6,7\t1\t1\t0\t1\t0\t1\t1\n"));
+        assert_eq!(run(3), direct);
+        assert_eq!(run(4), direct);
+    }
 }
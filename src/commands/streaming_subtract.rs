@@ -21,10 +21,9 @@
 use crate::bed::BedError;
 use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
 use crate::streaming::parsing::{parse_bed3_bytes, parse_bed3_bytes_with_rest, should_skip_line};
-use crate::streaming::ActiveInterval;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use crate::streaming::read_ahead::{open_input, InputFile};
+use crate::streaming::{ActiveInterval, ChromTable};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
 /// Pending B interval - coordinates only.
@@ -43,6 +42,8 @@ pub struct StreamingSubtractCommand {
     pub fraction: Option<f64>,
     /// Require reciprocal fraction overlap
     pub reciprocal: bool,
+    /// Minimum overlap in base pairs required to subtract
+    pub min_overlap_bp: Option<u64>,
     /// Require same strand
     pub same_strand: bool,
 }
@@ -88,6 +89,7 @@ impl StreamingSubtractCommand {
             remove_entire: false,
             fraction: None,
             reciprocal: false,
+            min_overlap_bp: None,
             same_strand: false,
         }
     }
@@ -100,16 +102,32 @@ impl StreamingSubtractCommand {
         a_path: P,
         b_path: P,
         output: &mut W,
+    ) -> Result<StreamingSubtractStats, BedError> {
+        self.run_with_suppressed(a_path, b_path, output, None::<&mut io::Sink>)
+    }
+
+    /// Execute streaming subtract, also writing fully suppressed A records.
+    ///
+    /// An A record is suppressed when nothing of it survives: any qualifying
+    /// overlap with `-A`, or complete coverage by B otherwise. Suppressed
+    /// records are written unchanged to `suppressed` in input order.
+    pub fn run_with_suppressed<P: AsRef<Path>, W: Write, S: Write>(
+        &self,
+        a_path: P,
+        b_path: P,
+        output: &mut W,
+        suppressed: Option<&mut S>,
     ) -> Result<StreamingSubtractStats, BedError> {
         // Output buffer (2MB default, reduced from 8MB for memory efficiency)
         let mut output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
+        let mut suppressed = suppressed.map(|s| BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, s));
 
         // Stream A file
-        let a_file = File::open(a_path.as_ref())?;
+        let a_file = open_input(a_path.as_ref())?;
         let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
 
         // Stream B file
-        let b_file = File::open(b_path.as_ref())?;
+        let b_file = open_input(b_path.as_ref())?;
         let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
 
         // Reusable line buffers
@@ -125,9 +143,9 @@ impl StreamingSubtractCommand {
         let mut b_exhausted = pending_b.is_none();
 
         // Track seen B chromosomes to handle any sort order
-        let mut seen_b_chroms: HashSet<Vec<u8>> = HashSet::new();
+        let mut seen_b_chroms = ChromTable::new();
         if !b_exhausted {
-            seen_b_chroms.insert(b_chrom.clone());
+            seen_b_chroms.intern(&b_chrom);
         }
 
         // Active set: Vec with head index (no VecDeque overhead)
@@ -189,7 +207,7 @@ impl StreamingSubtractCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -222,7 +240,7 @@ impl StreamingSubtractCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                         continue;
                     } else {
                         // B is on the same chromosome as A
@@ -243,7 +261,7 @@ impl StreamingSubtractCommand {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -274,11 +292,22 @@ impl StreamingSubtractCommand {
             } else if self.remove_entire {
                 // -A flag: remove entire A if any overlap
                 stats.intervals_removed += 1;
+                if let Some(ref mut s) = suppressed {
+                    Self::write_line(s, line_bytes)?;
+                }
             } else {
                 // Subtract and emit fragments
                 let fragments =
                     self.subtract_intervals_reuse(a_start, a_end, &overlap_buf, &mut merged_buf);
 
+                if fragments.is_empty() {
+                    // A is completely covered by B
+                    stats.intervals_removed += 1;
+                    if let Some(ref mut s) = suppressed {
+                        Self::write_line(s, line_bytes)?;
+                    }
+                }
+
                 for &(frag_start, frag_end) in fragments {
                     Self::write_fragment(
                         &mut output,
@@ -301,6 +330,9 @@ impl StreamingSubtractCommand {
         }
 
         output.flush().map_err(BedError::Io)?;
+        if let Some(ref mut s) = suppressed {
+            s.flush().map_err(BedError::Io)?;
+        }
         Ok(stats)
     }
 
@@ -308,7 +340,7 @@ impl StreamingSubtractCommand {
     /// Returns Err on IO error, Ok(None) on EOF, Ok(Some) on success.
     #[inline]
     fn read_next_b(
-        reader: &mut BufReader<File>,
+        reader: &mut BufReader<InputFile>,
         line_buf: &mut String,
         chrom_buf: &mut Vec<u8>,
     ) -> Result<Option<PendingB>, BedError> {
@@ -343,9 +375,14 @@ impl StreamingSubtractCommand {
         }
     }
 
-    /// Check fraction filter without allocation.
+    /// Check overlap size filters without allocation.
     #[inline(always)]
     fn passes_fraction_filter(&self, a_start: u64, a_end: u64, b_start: u64, b_end: u64) -> bool {
+        if let Some(bp) = self.min_overlap_bp {
+            if a_end.min(b_end).saturating_sub(a_start.max(b_start)) < bp {
+                return false;
+            }
+        }
         if let Some(frac) = self.fraction {
             let overlap_start = a_start.max(b_start);
             let overlap_end = a_end.min(b_end);
@@ -600,6 +637,35 @@ mod tests {
         assert!(lines[1].starts_with("chr1\t200\t300"));
     }
 
+    #[test]
+    fn test_streaming_subtract_keep_suppressed() {
+        let a_file =
+            create_temp_bed("chr1\t100\t200\ta1\nchr1\t300\t500\ta2\nchr1\t600\t700\ta3\n");
+        let b_file = create_temp_bed("chr1\t50\t250\nchr1\t400\t450\n");
+
+        let cmd = StreamingSubtractCommand::new();
+        let mut output = Vec::new();
+        let mut suppressed = Vec::new();
+        let stats = cmd
+            .run_with_suppressed(
+                a_file.path(),
+                b_file.path(),
+                &mut output,
+                Some(&mut suppressed),
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t300\t400\ta2\nchr1\t450\t500\ta2\nchr1\t600\t700\ta3\n"
+        );
+        assert_eq!(
+            String::from_utf8(suppressed).unwrap(),
+            "chr1\t100\t200\ta1\n"
+        );
+        assert_eq!(stats.intervals_removed, 1);
+    }
+
     #[test]
     fn test_active_interval_size() {
         assert_eq!(std::mem::size_of::<ActiveInterval>(), 8);
@@ -1,6 +1,8 @@
 //! Streaming window implementation with TRUE O(k) memory complexity.
 //!
 //! Finds intervals in B that are within a window distance of intervals in A.
+//! With `from_b`, the window is laid around each B instead, and A intervals
+//! are reported when they fall inside it.
 //!
 //! ZERO ALLOCATION in hot path:
 //! - No per-record String allocation (raw byte parsing with memchr)
@@ -14,12 +16,19 @@
 //! # Requirements
 //!
 //! Both input files MUST be sorted by chromosome (lexicographic), then by start position.
+//! [`StreamingWindowCommand::run_indexed`] instead looks up each A record, in
+//! any order, in an [`IndexedBed`].
 
 use crate::bed::BedError;
+use crate::commands::stab::IndexedBed;
+use crate::config::display_score;
 use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
-use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
-use std::collections::HashSet;
-use std::fs::File;
+use crate::streaming::parsing::{
+    column_bytes, parse_bed3_bytes, parse_bed3_bytes_with_rest, parse_score_bytes, should_skip_line,
+};
+use crate::streaming::read_ahead::{open_input, InputFile};
+use crate::streaming::ChromTable;
+use std::cmp::Reverse;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
@@ -28,10 +37,83 @@ use std::path::Path;
 struct ActiveB {
     start: u32,
     end: u32,
+    /// Score column, parsed only for per-A summaries
+    score: Option<f64>,
     /// Original line bytes (stored for output)
     line: Vec<u8>,
 }
 
+impl ActiveB {
+    /// `rest` is the offset of the columns after `end`, as returned by
+    /// [`parse_bed3_bytes_with_rest`].
+    fn new(line: &str, start: u64, end: u64, rest: usize, want_score: bool) -> Self {
+        let score = if want_score {
+            parse_score_bytes(&line.as_bytes()[rest..])
+        } else {
+            None
+        };
+        Self {
+            start: start as u32,
+            end: end as u32,
+            score,
+            line: line.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Summary of the B hits inside one A window.
+#[derive(Debug, Default)]
+struct WindowSummary {
+    count: usize,
+    nearest: Option<u64>,
+    max_score: Option<f64>,
+    score_sum: f64,
+    scored: usize,
+}
+
+impl WindowSummary {
+    fn add(&mut self, a_start: u64, a_end: u64, b: &ActiveB) {
+        self.count += 1;
+        let (b_start, b_end) = (b.start as u64, b.end as u64);
+        // Gap between A and B; 0 when they overlap
+        let distance = if b_end <= a_start {
+            a_start - b_end
+        } else {
+            b_start.saturating_sub(a_end)
+        };
+        self.nearest = Some(self.nearest.map_or(distance, |d| d.min(distance)));
+        if let Some(score) = b.score {
+            self.max_score = Some(self.max_score.map_or(score, |m| m.max(score)));
+            self.score_sum += score;
+            self.scored += 1;
+        }
+    }
+}
+
+/// Representative B hit for one value of the dedup column.
+#[derive(Debug)]
+struct NamedHit<'a> {
+    /// Position in the active window, to write hits in B order.
+    index: usize,
+    /// Dedup column value; B lines without one (or with `.`) are never merged.
+    key: Option<&'a [u8]>,
+    /// Overlap with A, then proximity to A: larger is better.
+    rank: (u64, Reverse<u64>),
+}
+
+/// Keep the best-ranked hit per key.
+fn add_named_hit<'a>(hits: &mut Vec<NamedHit<'a>>, hit: NamedHit<'a>) {
+    if hit.key.is_some() {
+        if let Some(best) = hits.iter_mut().find(|h| h.key == hit.key) {
+            if hit.rank > best.rank {
+                *best = hit;
+            }
+            return;
+        }
+    }
+    hits.push(hit);
+}
+
 /// Streaming window command configuration.
 #[derive(Debug, Clone)]
 pub struct StreamingWindowCommand {
@@ -45,6 +127,15 @@ pub struct StreamingWindowCommand {
     pub no_overlap: bool,
     /// Report count of overlaps
     pub count: bool,
+    /// Report one row per A with count, nearest distance, max and mean B score
+    pub per_a_summary: bool,
+    /// Minimum overlap in base pairs between B and the expanded window
+    pub min_overlap_bp: Option<u64>,
+    /// Report one B per value of this 1-based B column (e.g. 4 for the name),
+    /// preferring the largest overlap with A, then the nearest
+    pub dedup_b_col: Option<usize>,
+    /// Apply the window around B features instead of A
+    pub from_b: bool,
 }
 
 impl Default for StreamingWindowCommand {
@@ -61,6 +152,10 @@ impl StreamingWindowCommand {
             right: None,
             no_overlap: false,
             count: false,
+            per_a_summary: false,
+            min_overlap_bp: None,
+            dedup_b_col: None,
+            from_b: false,
         }
     }
 
@@ -76,6 +171,17 @@ impl StreamingWindowCommand {
         self.right.unwrap_or(self.window)
     }
 
+    /// Overlap in bases between a window and the interval tested against it,
+    /// or `None` if they do not overlap.
+    #[inline(always)]
+    fn window_overlap(&self, a: (u64, u64), b: (u64, u64)) -> Option<u64> {
+        let (around, other) = if self.from_b { (b, a) } else { (a, b) };
+        let win_start = around.0.saturating_sub(self.left_window());
+        let win_end = around.1.saturating_add(self.right_window());
+        (other.0 < win_end && other.1 > win_start)
+            .then(|| other.1.min(win_end) - other.0.max(win_start))
+    }
+
     /// Execute streaming window on two sorted BED files.
     ///
     /// Memory usage: O(k) where k = max B intervals within window at any point
@@ -89,10 +195,10 @@ impl StreamingWindowCommand {
         let mut output = BufWriter::with_capacity(DEFAULT_OUTPUT_BUFFER, output);
 
         // Stream files
-        let a_file = File::open(a_path.as_ref())?;
+        let a_file = open_input(a_path.as_ref())?;
         let mut a_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, a_file);
 
-        let b_file = File::open(b_path.as_ref())?;
+        let b_file = open_input(b_path.as_ref())?;
         let mut b_reader = BufReader::with_capacity(DEFAULT_INPUT_BUFFER, b_file);
 
         // Reusable line buffers
@@ -104,22 +210,28 @@ impl StreamingWindowCommand {
 
         // B state
         let mut b_chrom: Vec<u8> = Vec::with_capacity(64);
-        let mut pending_b = Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+        let want_score = self.per_a_summary;
+        let mut pending_b =
+            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom, want_score)?;
         let mut b_exhausted = pending_b.is_none();
 
         // Track seen B chromosomes to handle any sort order
-        let mut seen_b_chroms: HashSet<Vec<u8>> = HashSet::new();
+        let mut seen_b_chroms = ChromTable::new();
         if !b_exhausted {
-            seen_b_chroms.insert(b_chrom.clone());
+            seen_b_chroms.intern(&b_chrom);
         }
 
         // Active set: B intervals that might be within window of current or future A
         let mut active: Vec<ActiveB> = Vec::with_capacity(1024);
         let mut head_idx: usize = 0;
 
-        // Window sizes
-        let left_win = self.left_window();
-        let right_win = self.right_window();
+        // Reach of the sweep around A. A window of (left, right) around B
+        // reaches B from A's side as (right, left)
+        let (left_win, right_win) = if self.from_b {
+            (self.right_window(), self.left_window())
+        } else {
+            (self.left_window(), self.right_window())
+        };
 
         // Stats
         let mut stats = StreamingWindowStats::default();
@@ -162,13 +274,17 @@ impl StreamingWindowCommand {
                 // Skip B to current chromosome (or B has already passed it)
                 if !b_exhausted && !seen_b_chroms.contains(chrom) {
                     while b_chrom.as_slice() != chrom {
-                        pending_b =
-                            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        pending_b = Self::read_next_b(
+                            &mut b_reader,
+                            &mut b_line_buf,
+                            &mut b_chrom,
+                            want_score,
+                        )?;
                         if pending_b.is_none() {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -200,13 +316,17 @@ impl StreamingWindowCommand {
                             break;
                         }
                         // B hasn't reached A's chromosome yet, read next B
-                        pending_b =
-                            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        pending_b = Self::read_next_b(
+                            &mut b_reader,
+                            &mut b_line_buf,
+                            &mut b_chrom,
+                            want_score,
+                        )?;
                         if pending_b.is_none() {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                         continue;
                     } else {
                         // B is on the same chromosome as A
@@ -217,13 +337,17 @@ impl StreamingWindowCommand {
                         }
                         // Add to active (might overlap current or future windows)
                         active.push(b);
-                        pending_b =
-                            Self::read_next_b(&mut b_reader, &mut b_line_buf, &mut b_chrom)?;
+                        pending_b = Self::read_next_b(
+                            &mut b_reader,
+                            &mut b_line_buf,
+                            &mut b_chrom,
+                            want_score,
+                        )?;
                         if pending_b.is_none() {
                             b_exhausted = true;
                             break;
                         }
-                        seen_b_chroms.insert(b_chrom.clone());
+                        seen_b_chroms.intern(&b_chrom);
                     }
                 }
             }
@@ -232,49 +356,177 @@ impl StreamingWindowCommand {
                 .max_active_b
                 .max(active.len().saturating_sub(head_idx));
 
-            // Find overlaps with expanded window
-            let active_slice = &active[head_idx..];
-            let mut match_count = 0;
+            self.report_a(
+                &mut output,
+                line_bytes,
+                (a_start, a_end),
+                &active[head_idx..],
+                &mut stats,
+            )?;
+        }
+
+        output.flush().map_err(BedError::Io)?;
+        Ok(stats)
+    }
 
-            for b in active_slice {
-                let b_start = b.start as u64;
-                let b_end = b.end as u64;
+    /// Look up each A record in an indexed B instead of streaming B.
+    ///
+    /// Meant for a few A records against a large sorted B with a `.gri`
+    /// index: only the B records near each window are read, so the cost
+    /// does not grow with the size of B. A need not be sorted; its records
+    /// are reported in input order, with the same output as [`run`] gives
+    /// for sorted A.
+    ///
+    /// [`run`]: StreamingWindowCommand::run
+    pub fn run_indexed<P: AsRef<Path>, W: Write>(
+        &self,
+        a_path: P,
+        b: &IndexedBed,
+        output: &mut W,
+    ) -> Result<StreamingWindowStats, BedError> {
+        let mut output = BufWriter::new(output);
+        let a_reader = BufReader::new(open_input(a_path.as_ref())?);
 
-                // Check if B overlaps the expanded window [win_start, win_end)
-                if b_start < win_end && b_end > win_start {
-                    match_count += 1;
+        let (left_win, right_win) = if self.from_b {
+            (self.right_window(), self.left_window())
+        } else {
+            (self.left_window(), self.right_window())
+        };
 
-                    if !self.no_overlap && !self.count {
-                        // Output match: A_line \t B_line
-                        Self::write_pair(&mut output, line_bytes, &b.line)?;
-                        stats.output_pairs += 1;
-                    }
-                }
+        let mut stats = StreamingWindowStats::default();
+        let mut candidates: Vec<ActiveB> = Vec::new();
+        for line in a_reader.lines() {
+            let line = line?;
+            let line = line.trim_end();
+            let line_bytes = line.as_bytes();
+            if should_skip_line(line_bytes) {
+                continue;
             }
+            let Some((chrom, a_start, a_end)) = parse_bed3_bytes(line_bytes) else {
+                continue;
+            };
+            stats.a_intervals += 1;
 
-            if self.count {
-                // Output A with count
-                Self::write_count(&mut output, line_bytes, match_count)?;
-                stats.output_pairs += 1;
-            } else if self.no_overlap && match_count == 0 {
-                // Output A intervals with no matches
-                output.write_all(line_bytes).map_err(BedError::Io)?;
-                output.write_all(b"\n").map_err(BedError::Io)?;
-                stats.output_pairs += 1;
+            let chrom = String::from_utf8_lossy(chrom);
+            let win_start = a_start.saturating_sub(left_win);
+            let win_end = a_end.saturating_add(right_win);
+            candidates.clear();
+            for (b_line, b_start, b_end) in b.query(&chrom, win_start, win_end) {
+                let b_line = String::from_utf8_lossy(b_line);
+                let b_line = b_line.trim_end();
+                let rest = if self.per_a_summary {
+                    parse_bed3_bytes_with_rest(b_line.as_bytes()).map_or(b_line.len(), |f| f.3)
+                } else {
+                    b_line.len()
+                };
+                candidates.push(ActiveB::new(
+                    b_line,
+                    b_start,
+                    b_end,
+                    rest,
+                    self.per_a_summary,
+                ));
             }
+            stats.max_active_b = stats.max_active_b.max(candidates.len());
+
+            self.report_a(
+                &mut output,
+                line_bytes,
+                (a_start, a_end),
+                &candidates,
+                &mut stats,
+            )?;
         }
 
         output.flush().map_err(BedError::Io)?;
         Ok(stats)
     }
 
+    /// Write the output for one A record given the B records that may fall
+    /// in its window, in B order.
+    fn report_a<W: Write>(
+        &self,
+        output: &mut W,
+        line_bytes: &[u8],
+        (a_start, a_end): (u64, u64),
+        candidates: &[ActiveB],
+        stats: &mut StreamingWindowStats,
+    ) -> Result<(), BedError> {
+        let mut match_count = 0;
+        let mut summary = WindowSummary::default();
+        let mut named_hits: Vec<NamedHit> = Vec::new();
+
+        for (index, b) in candidates.iter().enumerate() {
+            let b_start = b.start as u64;
+            let b_end = b.end as u64;
+
+            // Check if B overlaps A's window (or A overlaps B's window)
+            if self
+                .window_overlap((a_start, a_end), (b_start, b_end))
+                .is_some_and(|overlap| overlap >= self.min_overlap_bp.unwrap_or(0))
+            {
+                match_count += 1;
+
+                if self.per_a_summary {
+                    summary.add(a_start, a_end, b);
+                } else if let Some(col) = self.dedup_b_col {
+                    let overlap = b_end.min(a_end).saturating_sub(b_start.max(a_start));
+                    let distance = if b_end <= a_start {
+                        a_start - b_end
+                    } else {
+                        b_start.saturating_sub(a_end)
+                    };
+                    add_named_hit(
+                        &mut named_hits,
+                        NamedHit {
+                            index,
+                            key: column_bytes(&b.line, col).filter(|k| *k != b"."),
+                            rank: (overlap, Reverse(distance)),
+                        },
+                    );
+                } else if !self.no_overlap && !self.count {
+                    // Output match: A_line \t B_line
+                    Self::write_pair(output, line_bytes, &b.line)?;
+                    stats.output_pairs += 1;
+                }
+            }
+        }
+
+        if self.dedup_b_col.is_some() {
+            match_count = named_hits.len();
+            if !self.no_overlap && !self.count {
+                named_hits.sort_unstable_by_key(|h| h.index);
+                for hit in &named_hits {
+                    Self::write_pair(output, line_bytes, &candidates[hit.index].line)?;
+                    stats.output_pairs += 1;
+                }
+            }
+        }
+
+        if self.per_a_summary {
+            Self::write_summary(output, line_bytes, &summary)?;
+            stats.output_pairs += 1;
+        } else if self.count {
+            // Output A with count
+            Self::write_count(output, line_bytes, match_count)?;
+            stats.output_pairs += 1;
+        } else if self.no_overlap && match_count == 0 {
+            // Output A intervals with no matches
+            output.write_all(line_bytes).map_err(BedError::Io)?;
+            output.write_all(b"\n").map_err(BedError::Io)?;
+            stats.output_pairs += 1;
+        }
+        Ok(())
+    }
+
     /// Read next B interval.
     /// Returns Err on IO error, Ok(None) on EOF, Ok(Some) on success.
     #[inline]
     fn read_next_b(
-        reader: &mut BufReader<File>,
+        reader: &mut BufReader<InputFile>,
         line_buf: &mut String,
         chrom_buf: &mut Vec<u8>,
+        want_score: bool,
     ) -> Result<Option<ActiveB>, BedError> {
         loop {
             line_buf.clear();
@@ -291,7 +543,7 @@ impl StreamingWindowCommand {
             }
 
             // Parse BED3 - skip malformed lines
-            let (chrom, start, end) = match parse_bed3_bytes(line_bytes) {
+            let (chrom, start, end, rest) = match parse_bed3_bytes_with_rest(line_bytes) {
                 Some(v) => v,
                 None => continue,
             };
@@ -299,11 +551,7 @@ impl StreamingWindowCommand {
             chrom_buf.clear();
             chrom_buf.extend_from_slice(chrom);
 
-            return Ok(Some(ActiveB {
-                start: start as u32,
-                end: end as u32,
-                line: line_bytes.to_vec(),
-            }));
+            return Ok(Some(ActiveB::new(line, start, end, rest, want_score)));
         }
     }
 
@@ -327,6 +575,34 @@ impl StreamingWindowCommand {
         output.write_all(b"\n").map_err(BedError::Io)?;
         Ok(())
     }
+
+    /// Write A followed by count, nearest distance, max score and mean score.
+    /// Missing values are written as `.`.
+    fn write_summary<W: Write>(
+        output: &mut W,
+        a_line: &[u8],
+        summary: &WindowSummary,
+    ) -> Result<(), BedError> {
+        output.write_all(a_line).map_err(BedError::Io)?;
+        write!(output, "\t{}", summary.count).map_err(BedError::Io)?;
+        match summary.nearest {
+            Some(d) => write!(output, "\t{}", d),
+            None => output.write_all(b"\t."),
+        }
+        .map_err(BedError::Io)?;
+        match summary.max_score {
+            Some(max) => write!(
+                output,
+                "\t{}\t{}",
+                display_score(max),
+                display_score(summary.score_sum / summary.scored as f64)
+            ),
+            None => output.write_all(b"\t.\t."),
+        }
+        .map_err(BedError::Io)?;
+        output.write_all(b"\n").map_err(BedError::Io)?;
+        Ok(())
+    }
 }
 
 /// Statistics from streaming window operation.
@@ -379,6 +655,44 @@ mod tests {
         assert!(result.contains("chr1\t500\t600\tchr1\t650\t700"));
     }
 
+    #[test]
+    fn test_streaming_window_from_b() {
+        let a_file = create_temp_bed("chr1\t100\t150\tup\nchr1\t400\t450\tdown\n");
+        let b_file = create_temp_bed("chr1\t300\t350\tgene\n");
+
+        // 200 bp upstream of B reaches `up`; 10 bp downstream misses `down`
+        let mut cmd = StreamingWindowCommand::new();
+        cmd.left = Some(200);
+        cmd.right = Some(10);
+        cmd.from_b = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t150\tup\tchr1\t300\t350\tgene\n"
+        );
+
+        // The same sizes around A find the opposite side
+        cmd.from_b = false;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t400\t450\tdown\tchr1\t300\t350\tgene\n"
+        );
+
+        // Minimum overlap is measured between A and B's window
+        cmd.from_b = true;
+        cmd.min_overlap_bp = Some(51);
+        cmd.count = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t100\t150\tup\t0\nchr1\t400\t450\tdown\t0\n"
+        );
+    }
+
     #[test]
     fn test_streaming_window_no_overlap() {
         let a_file = create_temp_bed("chr1\t500\t600\nchr1\t2000\t2100\n");
@@ -435,6 +749,27 @@ mod tests {
         assert!(result.contains("-"));
     }
 
+    #[test]
+    fn test_streaming_window_per_a_summary() {
+        let a_file = create_temp_bed("chr1\t500\t600\nchr1\t5000\t5100\n");
+        let b_file = create_temp_bed(
+            "chr1\t450\t520\tb1\t4\t+\nchr1\t640\t660\tb2\t7.5\t-\nchr1\t680\t690\n",
+        );
+
+        let mut cmd = StreamingWindowCommand::new();
+        cmd.window = 100;
+        cmd.per_a_summary = true;
+
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(
+            result,
+            "chr1\t500\t600\t3\t0\t7.5\t5.75\nchr1\t5000\t5100\t0\t.\t.\t.\n"
+        );
+    }
+
     #[test]
     fn test_streaming_window_left_right() {
         let a_file = create_temp_bed("chr1\t500\t600\n");
@@ -453,4 +788,58 @@ mod tests {
         assert!(result.contains("chr1\t350\t400"));
         assert!(!result.contains("chr1\t750\t800"));
     }
+
+    #[test]
+    fn test_streaming_window_dedup_b_name() {
+        // Two transcripts of g1 (the second overlaps A more), two of g2 near
+        // A, and unnamed B records that are never merged
+        let a_file = create_temp_bed("chr1\t1000\t2000\tpeak\n");
+        let b_file = create_temp_bed(
+            "chr1\t500\t1100\tg1\nchr1\t900\t1900\tg1\nchr1\t2150\t2300\tg2\n\
+             chr1\t2100\t2200\tg2\nchr1\t2500\t2600\t.\nchr1\t2600\t2700\t.\n",
+        );
+
+        let mut cmd = StreamingWindowCommand::new();
+        cmd.window = 1000;
+        cmd.dedup_b_col = Some(4);
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+
+        let b_starts: Vec<&str> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|l| l.split('\t').nth(5).unwrap())
+            .collect();
+        assert_eq!(b_starts, ["900", "2100", "2500", "2600"]);
+
+        cmd.count = true;
+        let mut output = Vec::new();
+        cmd.run(a_file.path(), b_file.path(), &mut output).unwrap();
+        assert_eq!(output, b"chr1\t1000\t2000\tpeak\t4\n");
+    }
+
+    #[test]
+    fn test_streaming_window_indexed_matches_streaming() {
+        let a_file = create_temp_bed("chr1\t500\t600\nchr1\t5000\t5100\nchr2\t100\t200\n");
+        let b_file = create_temp_bed(
+            "chr1\t0\t3000\tlong\t1\nchr1\t450\t520\tb1\t4\nchr1\t680\t690\tb2\t2\n\
+             chr1\t5150\t5160\tb3\t9\nchr3\t100\t200\tb4\t5\n",
+        );
+        let b = IndexedBed::open(b_file.path(), false).unwrap();
+
+        for (per_a_summary, from_b) in [(false, false), (true, false), (false, true)] {
+            let mut cmd = StreamingWindowCommand::new();
+            cmd.window = 100;
+            cmd.per_a_summary = per_a_summary;
+            cmd.from_b = from_b;
+
+            let mut streamed = Vec::new();
+            cmd.run(a_file.path(), b_file.path(), &mut streamed)
+                .unwrap();
+            let mut indexed = Vec::new();
+            let stats = cmd.run_indexed(a_file.path(), &b, &mut indexed).unwrap();
+            assert_eq!(indexed, streamed);
+            assert_eq!(stats.a_intervals, 3);
+        }
+    }
 }
@@ -3,12 +3,13 @@
 //! Uses O(n + m) sweep-line algorithm per chromosome for optimal performance.
 
 use crate::bed::{read_records, BedError};
+use crate::config::display_score;
 use crate::index::IntervalIndex;
 use crate::interval::{BedRecord, Interval};
-use crate::parallel::{group_by_chromosome, PARALLEL_THRESHOLD};
+use crate::parallel::{group_by_chromosome, PoolHandle, PARALLEL_THRESHOLD};
 use rayon::prelude::*;
 use std::collections::HashMap;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
 
 /// Subtract command configuration.
@@ -20,10 +21,14 @@ pub struct SubtractCommand {
     pub fraction: Option<f64>,
     /// Require reciprocal fraction overlap
     pub reciprocal: bool,
+    /// Minimum overlap in base pairs required to subtract
+    pub min_overlap_bp: Option<u64>,
     /// Require same strand
     pub same_strand: bool,
     /// Process in parallel by chromosome
     pub parallel: bool,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
 }
 
 impl Default for SubtractCommand {
@@ -38,11 +43,19 @@ impl SubtractCommand {
             remove_entire: false,
             fraction: None,
             reciprocal: false,
+            min_overlap_bp: None,
             same_strand: false,
             parallel: true,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Subtract B intervals from A intervals.
     pub fn subtract(&self, a_intervals: &[Interval], b_index: &IntervalIndex) -> Vec<Interval> {
         let mut results = Vec::new();
@@ -96,19 +109,26 @@ impl SubtractCommand {
         let b_index = IntervalIndex::from_intervals(b_intervals);
         let a_groups = group_by_chromosome(a_intervals);
 
-        let results: Vec<Vec<Interval>> = a_groups
-            .into_par_iter()
-            .map(|(_, intervals)| self.subtract(&intervals, &b_index))
-            .collect();
+        let results: Vec<Vec<Interval>> = self.pool.install(|| {
+            a_groups
+                .into_par_iter()
+                .map(|(_, intervals)| self.subtract(&intervals, &b_index))
+                .collect()
+        });
 
         let mut final_results: Vec<Interval> = results.into_iter().flatten().collect();
         final_results.sort();
         final_results
     }
 
-    /// Check if an overlap passes the fraction filter.
+    /// Check if an overlap passes the overlap size filters.
     #[inline]
     fn passes_filters(&self, a: &Interval, b: &Interval) -> bool {
+        if let Some(bp) = self.min_overlap_bp {
+            if a.overlap_length(b) < bp {
+                return false;
+            }
+        }
         if let Some(frac) = self.fraction {
             if self.reciprocal {
                 if !a.overlaps_reciprocal(b, frac) {
@@ -134,6 +154,21 @@ impl SubtractCommand {
         b_path: P,
         output: &mut W,
     ) -> Result<(), BedError> {
+        self.run_with_suppressed(a_path, b_path, output, None::<&mut io::Sink>)
+    }
+
+    /// Execute subtract, also writing A records that were removed entirely.
+    ///
+    /// With `-A` these are all A records with a qualifying overlap; otherwise
+    /// they are the A records left with no fragment after subtraction.
+    pub fn run_with_suppressed<P: AsRef<Path>, W: Write, S: Write>(
+        &self,
+        a_path: P,
+        b_path: P,
+        output: &mut W,
+        mut suppressed: Option<&mut S>,
+    ) -> Result<(), BedError> {
+        let keep_suppressed = suppressed.is_some();
         let a_records = read_records(a_path)?;
         let b_records = read_records(b_path)?;
 
@@ -142,8 +177,12 @@ impl SubtractCommand {
         }
 
         // Group by chromosome
-        let a_by_chrom = Self::group_records_by_chrom_owned(a_records);
-        let b_by_chrom = Self::group_records_by_chrom_owned(b_records);
+        let a_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(a_records));
+        let b_by_chrom = self
+            .pool
+            .install(|| Self::group_records_by_chrom_owned(b_records));
 
         // Get sorted chromosome list for deterministic output
         let mut chroms: Vec<_> = a_by_chrom.keys().cloned().collect();
@@ -157,29 +196,49 @@ impl SubtractCommand {
             // Sequential processing for small datasets
             for chrom in &chroms {
                 let mut buf = Vec::with_capacity(64 * 1024);
+                let mut removed = Vec::new();
                 if let Some(a_list) = a_by_chrom.get(chrom) {
                     let b_list = b_by_chrom.get(chrom);
-                    self.subtract_chromosome_sweepline(a_list, b_list, &mut buf);
+                    self.subtract_chromosome_sweepline(
+                        a_list,
+                        b_list,
+                        &mut buf,
+                        keep_suppressed.then_some(&mut removed),
+                    );
                 }
                 output.write_all(&buf).map_err(BedError::Io)?;
+                if let Some(ref mut s) = suppressed {
+                    s.write_all(&removed).map_err(BedError::Io)?;
+                }
             }
         } else {
             // Parallel processing for large datasets
-            let results: Vec<Vec<u8>> = chroms
-                .par_iter()
-                .map(|chrom| {
-                    let mut buf = Vec::with_capacity(64 * 1024);
-                    if let Some(a_list) = a_by_chrom.get(chrom) {
-                        let b_list = b_by_chrom.get(chrom);
-                        self.subtract_chromosome_sweepline(a_list, b_list, &mut buf);
-                    }
-                    buf
-                })
-                .collect();
+            let results: Vec<(Vec<u8>, Vec<u8>)> = self.pool.install(|| {
+                chroms
+                    .par_iter()
+                    .map(|chrom| {
+                        let mut buf = Vec::with_capacity(64 * 1024);
+                        let mut removed = Vec::new();
+                        if let Some(a_list) = a_by_chrom.get(chrom) {
+                            let b_list = b_by_chrom.get(chrom);
+                            self.subtract_chromosome_sweepline(
+                                a_list,
+                                b_list,
+                                &mut buf,
+                                keep_suppressed.then_some(&mut removed),
+                            );
+                        }
+                        (buf, removed)
+                    })
+                    .collect()
+            });
 
             // Write results in chromosome order
-            for buf in results {
+            for (buf, removed) in results {
                 output.write_all(&buf).map_err(BedError::Io)?;
+                if let Some(ref mut s) = suppressed {
+                    s.write_all(&removed).map_err(BedError::Io)?;
+                }
             }
         }
 
@@ -195,11 +254,14 @@ impl SubtractCommand {
     ///    a. Advance j while B[j].end <= A.start (skip non-overlapping)
     ///    b. Collect overlapping B intervals (B.start < A.end)
     ///    c. Subtract and emit fragments directly
+    ///
+    /// A records that produce no fragment are written to `suppressed` if given.
     fn subtract_chromosome_sweepline(
         &self,
         a_sorted: &[BedRecord],
         b_sorted: Option<&Vec<BedRecord>>,
         output: &mut Vec<u8>,
+        mut suppressed: Option<&mut Vec<u8>>,
     ) {
         let b_sorted = match b_sorted {
             Some(b) if !b.is_empty() => b,
@@ -257,26 +319,29 @@ impl SubtractCommand {
                 continue;
             }
 
-            if self.remove_entire {
-                // -A flag: remove entire feature if any overlap
-                continue;
-            }
+            // -A flag: remove entire feature if any overlap
+            let emitted = !self.remove_entire
+                && self.subtract_and_emit(output, a_rec, &b_sorted[overlap_start..overlap_end]);
 
-            // Subtract overlapping B intervals from A
-            // Use in-place subtraction to avoid allocations
-            self.subtract_and_emit(output, a_rec, &b_sorted[overlap_start..overlap_end]);
+            if !emitted {
+                if let Some(ref mut removed) = suppressed {
+                    self.write_record_to_buf(removed, a_rec);
+                }
+            }
         }
     }
 
     /// Subtract B intervals from A and emit results directly to buffer.
     /// Avoids intermediate Vec allocations.
+    ///
+    /// Returns false if B covers A completely and nothing was emitted.
     #[inline]
     fn subtract_and_emit(
         &self,
         output: &mut Vec<u8>,
         a_rec: &BedRecord,
         b_intervals: &[BedRecord],
-    ) {
+    ) -> bool {
         // Sort B intervals by start for correct subtraction order
         // (They should already be sorted, but ensure correctness)
         let mut b_sorted: Vec<&BedRecord> = b_intervals.iter().collect();
@@ -287,6 +352,7 @@ impl SubtractCommand {
 
         // Current position in A that we're processing
         let mut current_pos = a_start;
+        let mut emitted = false;
 
         for b_rec in b_sorted {
             let b_start = b_rec.start();
@@ -305,6 +371,7 @@ impl SubtractCommand {
                 let frag_end = b_start.min(a_end);
                 if frag_end > current_pos {
                     self.write_fragment_to_buf(output, a_rec, current_pos, frag_end);
+                    emitted = true;
                 }
             }
 
@@ -315,7 +382,10 @@ impl SubtractCommand {
         // Emit remaining fragment after all B intervals
         if current_pos < a_end {
             self.write_fragment_to_buf(output, a_rec, current_pos, a_end);
+            emitted = true;
         }
+
+        emitted
     }
 
     /// Write a full record to buffer.
@@ -326,7 +396,7 @@ impl SubtractCommand {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -343,7 +413,7 @@ impl SubtractCommand {
         if let Some(ref name) = rec.name {
             let _ = write!(buf, "\t{}", name);
             if let Some(score) = rec.score {
-                let _ = write!(buf, "\t{}", score as i64);
+                let _ = write!(buf, "\t{}", display_score(score));
                 if let Some(strand) = rec.strand {
                     let _ = write!(buf, "\t{}", strand);
                 }
@@ -465,6 +535,66 @@ mod tests {
         assert_eq!(results[0].end, 200);
     }
 
+    #[test]
+    fn test_subtract_min_overlap_bp() {
+        let mut cmd = SubtractCommand::new();
+        cmd.min_overlap_bp = Some(20);
+
+        let a = vec![Interval::new("chr1", 100, 200)];
+        let b = vec![
+            Interval::new("chr1", 90, 110),  // 10bp, ignored
+            Interval::new("chr1", 150, 175), // 25bp, removed
+        ];
+        let b_index = IntervalIndex::from_intervals(b);
+
+        let results = cmd.subtract(&a, &b_index);
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].start, results[0].end), (100, 150));
+        assert_eq!((results[1].start, results[1].end), (175, 200));
+    }
+
+    #[test]
+    fn test_run_keep_suppressed() {
+        use std::io::Write as IoWrite;
+
+        let mut a_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(a_file, "chr1\t100\t200\nchr1\t300\t500\nchr2\t0\t50").unwrap();
+        let mut b_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(b_file, "chr1\t100\t200\nchr1\t400\t450").unwrap();
+
+        let mut cmd = SubtractCommand::new();
+        let mut output = Vec::new();
+        let mut suppressed = Vec::new();
+        cmd.run_with_suppressed(
+            a_file.path(),
+            b_file.path(),
+            &mut output,
+            Some(&mut suppressed),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "chr1\t300\t400\nchr1\t450\t500\nchr2\t0\t50\n"
+        );
+        assert_eq!(String::from_utf8(suppressed).unwrap(), "chr1\t100\t200\n");
+
+        cmd.remove_entire = true;
+        let mut output = Vec::new();
+        let mut suppressed = Vec::new();
+        cmd.run_with_suppressed(
+            a_file.path(),
+            b_file.path(),
+            &mut output,
+            Some(&mut suppressed),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "chr2\t0\t50\n");
+        assert_eq!(
+            String::from_utf8(suppressed).unwrap(),
+            "chr1\t100\t200\nchr1\t300\t500\n"
+        );
+    }
+
     #[test]
     fn test_parallel_subtract() {
         let cmd = SubtractCommand::new();
@@ -3,7 +3,7 @@
 use crate::bed::{read_records, BedError};
 use crate::index::IntervalIndex;
 use crate::interval::Interval;
-use crate::parallel::group_by_chromosome;
+use crate::parallel::{group_by_chromosome, PoolHandle};
 use rayon::prelude::*;
 use std::io::Write;
 use std::path::Path;
@@ -29,8 +29,12 @@ pub struct WindowCommand {
     pub count: bool,
     /// Only report A intervals with no matches
     pub no_overlap: bool,
+    /// Minimum overlap in base pairs between B and the expanded window
+    pub min_overlap_bp: Option<u64>,
     /// Process in parallel by chromosome
     pub parallel: bool,
+    /// Thread pool for parallel processing
+    pub pool: PoolHandle,
 }
 
 impl Default for WindowCommand {
@@ -51,10 +55,18 @@ impl WindowCommand {
             write_b: true,
             count: false,
             no_overlap: false,
+            min_overlap_bp: None,
             parallel: true,
+            pool: PoolHandle::global(),
         }
     }
 
+    /// Run parallel work on `pool` instead of the global Rayon pool.
+    pub fn with_pool(mut self, pool: PoolHandle) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Get the left window size.
     fn left_window(&self) -> u64 {
         self.left.unwrap_or(self.window)
@@ -90,6 +102,10 @@ impl WindowCommand {
             let matches: Vec<Interval> = b_index
                 .find_overlaps(&expanded)
                 .into_iter()
+                .filter(|b| {
+                    self.min_overlap_bp
+                        .is_none_or(|bp| expanded.overlap_length(b) >= bp)
+                })
                 .cloned()
                 .collect();
 
@@ -120,10 +136,12 @@ impl WindowCommand {
         let b_index = IntervalIndex::from_intervals(b_intervals);
         let a_groups = group_by_chromosome(a_intervals);
 
-        let results: Vec<Vec<WindowResult>> = a_groups
-            .into_par_iter()
-            .map(|(_, intervals)| self.find_window_matches(&intervals, &b_index))
-            .collect();
+        let results: Vec<Vec<WindowResult>> = self.pool.install(|| {
+            a_groups
+                .into_par_iter()
+                .map(|(_, intervals)| self.find_window_matches(&intervals, &b_index))
+                .collect()
+        });
 
         results.into_iter().flatten().collect()
     }
@@ -274,6 +292,28 @@ mod tests {
         assert_eq!(results[0].b_intervals.len(), 1);
     }
 
+    #[test]
+    fn test_window_min_overlap_bp() {
+        let mut cmd = WindowCommand::new();
+        cmd.window = 100;
+        cmd.min_overlap_bp = Some(50);
+
+        // A: 500-600 -> window 400-700
+        let a = vec![Interval::new("chr1", 500, 600)];
+        let b = vec![
+            Interval::new("chr1", 350, 450), // 50bp inside the window
+            Interval::new("chr1", 680, 750), // 20bp inside the window
+        ];
+        let b_index = IntervalIndex::from_intervals(b);
+
+        let results = cmd.find_window_matches(&a, &b_index);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].b_intervals,
+            vec![Interval::new("chr1", 350, 450)]
+        );
+    }
+
     #[test]
     fn test_expand_interval() {
         let mut cmd = WindowCommand::new();
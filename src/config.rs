@@ -3,7 +3,14 @@
 //! This module provides thread-safe global configuration that affects
 //! parsing and interval semantics without adding overhead to hot loops.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::bed::BedError;
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::RwLock;
 
 /// Global flag for bedtools-compatible zero-length interval handling.
 ///
@@ -60,6 +67,452 @@ pub fn normalize_end(start: u64, end: u64) -> u64 {
     }
 }
 
+/// Column delimiter used when parsing BED lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delimiter {
+    /// Tabs, falling back to runs of whitespace for lines without a tab
+    #[default]
+    Auto,
+    /// Tabs only (strict BED)
+    Tab,
+    /// Runs of spaces and/or tabs
+    Space,
+}
+
+impl Delimiter {
+    /// Parse from a CLI value (`auto`, `tab`, `space`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Self::Auto),
+            "tab" => Some(Self::Tab),
+            "space" => Some(Self::Space),
+            _ => None,
+        }
+    }
+}
+
+static DELIMITER: AtomicU8 = AtomicU8::new(0);
+
+/// Set the column delimiter for BED parsing.
+#[inline]
+pub fn set_delimiter(delim: Delimiter) {
+    DELIMITER.store(delim as u8, Ordering::Release);
+}
+
+/// Get the column delimiter for BED parsing.
+#[inline]
+pub fn delimiter() -> Delimiter {
+    match DELIMITER.load(Ordering::Acquire) {
+        1 => Delimiter::Tab,
+        2 => Delimiter::Space,
+        _ => Delimiter::Auto,
+    }
+}
+
+/// How score columns are written when records are re-serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreFormat {
+    /// Integral scores as integers, fractional scores (e.g. narrowPeak
+    /// signal values) with full precision
+    #[default]
+    Auto,
+    /// Truncate every score to an integer
+    Integer,
+}
+
+impl ScoreFormat {
+    /// Parse from a CLI value (`auto`, `int`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Self::Auto),
+            "int" => Some(Self::Integer),
+            _ => None,
+        }
+    }
+}
+
+static SCORE_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Set the score output policy.
+#[inline]
+pub fn set_score_format(format: ScoreFormat) {
+    SCORE_FORMAT.store(format as u8, Ordering::Release);
+}
+
+/// Get the score output policy.
+#[inline]
+pub fn score_format() -> ScoreFormat {
+    match SCORE_FORMAT.load(Ordering::Acquire) {
+        1 => ScoreFormat::Integer,
+        _ => ScoreFormat::Auto,
+    }
+}
+
+/// Score formatted according to the current [`ScoreFormat`].
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreDisplay(f64);
+
+impl fmt::Display for ScoreDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let score = self.0;
+        if score_format() == ScoreFormat::Integer || (score.fract() == 0.0 && score.abs() < 1e15) {
+            write!(f, "{}", score as i64)
+        } else {
+            // Shortest representation that round-trips to the same f64
+            write!(f, "{}", score)
+        }
+    }
+}
+
+/// Wrap a score for writing with `{}`.
+#[inline]
+pub fn display_score(score: f64) -> ScoreDisplay {
+    ScoreDisplay(score)
+}
+
+/// Chromosome whitelist/blacklist applied while parsing.
+///
+/// Patterns are comma-separated. `*` matches any run of characters and `?`
+/// a single character; a pattern ending in a numeric range such as `chr1-22`
+/// matches `chr1` through `chr22`. A chromosome is kept when it matches a
+/// keep pattern (or none are given) and no drop pattern.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChromFilter {
+    keep: Vec<ChromPattern>,
+    drop: Vec<ChromPattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChromPattern {
+    Glob(Vec<u8>),
+    Range {
+        prefix: Vec<u8>,
+        low: u64,
+        high: u64,
+    },
+}
+
+impl ChromFilter {
+    /// Build a filter from `--keep-chroms` and `--drop-chroms` lists.
+    pub fn new(keep: Option<&str>, drop: Option<&str>) -> Result<Self, BedError> {
+        Ok(Self {
+            keep: keep.map(parse_patterns).transpose()?.unwrap_or_default(),
+            drop: drop.map(parse_patterns).transpose()?.unwrap_or_default(),
+        })
+    }
+
+    /// True if the filter keeps every chromosome.
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_empty() && self.drop.is_empty()
+    }
+
+    /// Whether records on `chrom` are kept.
+    pub fn allows(&self, chrom: &[u8]) -> bool {
+        (self.keep.is_empty() || self.keep.iter().any(|p| p.matches(chrom)))
+            && !self.drop.iter().any(|p| p.matches(chrom))
+    }
+}
+
+/// Name of the class that takes chromosomes no pattern matches.
+pub const OTHER_CLASS: &str = "other";
+
+/// Named groups of chromosomes, such as autosomes, sex chromosomes,
+/// mitochondria and unplaced scaffolds.
+///
+/// Read from a file with one `class<TAB>patterns` line per class, where
+/// patterns are written as for [`ChromFilter`]. A chromosome belongs to the
+/// first class with a matching pattern; chromosomes no pattern matches go to
+/// [`OTHER_CLASS`], listed last unless the file names it. Blank lines and
+/// lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChromClasses {
+    classes: Vec<(String, Vec<ChromPattern>)>,
+    other: usize,
+}
+
+impl ChromClasses {
+    /// Read classes from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            BedError::InvalidFormat(format!(
+                "Failed to open chromosome classes '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Read classes from a reader.
+    ///
+    /// A class given on several lines takes the patterns of all of them.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, BedError> {
+        let mut classes: Vec<(String, Vec<ChromPattern>)> = Vec::new();
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, patterns)) = line.split_once(char::is_whitespace) else {
+                return Err(BedError::Parse {
+                    line: line_num + 1,
+                    message: format!("expected 'class<TAB>patterns', found '{}'", line),
+                });
+            };
+            let patterns = parse_patterns(patterns.trim()).map_err(|e| BedError::Parse {
+                line: line_num + 1,
+                message: e.to_string(),
+            })?;
+            match classes.iter_mut().find(|(n, _)| n == name) {
+                Some((_, existing)) => existing.extend(patterns),
+                None => classes.push((name.to_string(), patterns)),
+            }
+        }
+        if classes.is_empty() {
+            return Err(BedError::InvalidFormat(
+                "no chromosome classes defined".to_string(),
+            ));
+        }
+
+        let other = match classes.iter().position(|(n, _)| n == OTHER_CLASS) {
+            Some(i) => i,
+            None => {
+                classes.push((OTHER_CLASS.to_string(), Vec::new()));
+                classes.len() - 1
+            }
+        };
+        Ok(Self { classes, other })
+    }
+
+    /// Class names in file order, [`OTHER_CLASS`] included.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.classes.iter().map(|(n, _)| n.as_str())
+    }
+
+    /// Index into [`names`](Self::names) of the class `chrom` belongs to.
+    pub fn class_of(&self, chrom: &[u8]) -> usize {
+        self.classes
+            .iter()
+            .position(|(_, patterns)| patterns.iter().any(|p| p.matches(chrom)))
+            .unwrap_or(self.other)
+    }
+}
+
+fn parse_patterns(list: &str) -> Result<Vec<ChromPattern>, BedError> {
+    list.split(',')
+        .map(|p| ChromPattern::parse(p.trim()))
+        .collect()
+}
+
+impl ChromPattern {
+    fn parse(pattern: &str) -> Result<Self, BedError> {
+        if pattern.is_empty() {
+            return Err(BedError::InvalidFormat(
+                "empty chromosome pattern".to_string(),
+            ));
+        }
+        let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if let Some((head, high)) = pattern.rsplit_once('-') {
+            let prefix = head.trim_end_matches(|c: char| c.is_ascii_digit());
+            let low = &head[prefix.len()..];
+            if !pattern.contains(['*', '?']) && is_number(low) && is_number(high) {
+                return match (low.parse::<u64>(), high.parse::<u64>()) {
+                    (Ok(low), Ok(high)) if low <= high => Ok(Self::Range {
+                        prefix: prefix.as_bytes().to_vec(),
+                        low,
+                        high,
+                    }),
+                    _ => Err(BedError::InvalidFormat(format!(
+                        "invalid chromosome range '{}'",
+                        pattern
+                    ))),
+                };
+            }
+        }
+        Ok(Self::Glob(pattern.as_bytes().to_vec()))
+    }
+
+    fn matches(&self, chrom: &[u8]) -> bool {
+        match self {
+            Self::Glob(pattern) => glob_match(pattern, chrom),
+            Self::Range { prefix, low, high } => {
+                let Some(number) = chrom.strip_prefix(prefix.as_slice()) else {
+                    return false;
+                };
+                // No leading zeros, so `chr1-22` does not match `chr01`
+                if number.is_empty() || (number[0] == b'0' && number.len() > 1) {
+                    return false;
+                }
+                std::str::from_utf8(number)
+                    .ok()
+                    .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .is_some_and(|n| (*low..=*high).contains(&n))
+            }
+        }
+    }
+}
+
+/// Match `text` against a pattern with `*` and `?` wildcards.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it is matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last `*` absorb one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+static CHROM_FILTER: RwLock<Option<ChromFilter>> = RwLock::new(None);
+static CHROM_FILTER_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Bumped on every change so per-thread verdict caches can tell they are stale.
+static CHROM_FILTER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Last (generation, chromosome, verdict); input is usually grouped by
+    /// chromosome, so most lookups hit.
+    static LAST_CHROM: RefCell<(u64, Vec<u8>, bool)> = const { RefCell::new((0, Vec::new(), true)) };
+}
+
+/// Set the chromosome filter used by the parsers; `None` keeps everything.
+pub fn set_chrom_filter(filter: Option<ChromFilter>) {
+    let filter = filter.filter(|f| !f.is_empty());
+    let active = filter.is_some();
+    *CHROM_FILTER.write().unwrap_or_else(|e| e.into_inner()) = filter;
+    CHROM_FILTER_GENERATION.fetch_add(1, Ordering::AcqRel);
+    CHROM_FILTER_ACTIVE.store(active, Ordering::Release);
+}
+
+/// True if a chromosome filter is set.
+#[inline]
+pub fn chrom_filter_active() -> bool {
+    CHROM_FILTER_ACTIVE.load(Ordering::Acquire)
+}
+
+/// Whether the parsers keep records on `chrom`.
+#[inline]
+pub fn chrom_allowed(chrom: &[u8]) -> bool {
+    !chrom_filter_active() || filtered_chrom_allowed(chrom)
+}
+
+#[inline(never)]
+fn filtered_chrom_allowed(chrom: &[u8]) -> bool {
+    let generation = CHROM_FILTER_GENERATION.load(Ordering::Acquire);
+    LAST_CHROM.with(|last| {
+        let mut last = last.borrow_mut();
+        if last.0 != generation || last.1 != chrom {
+            let allowed = CHROM_FILTER
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_ref()
+                .is_none_or(|f| f.allows(chrom));
+            last.0 = generation;
+            last.1.clear();
+            last.1.extend_from_slice(chrom);
+            last.2 = allowed;
+        }
+        last.2
+    })
+}
+
+// Counters for input quirks the parsers accept. Only touched when a quirk is
+// seen, so well-formed input pays nothing.
+static CRLF_LINES: AtomicU64 = AtomicU64::new(0);
+static WHITESPACE_LINES: AtomicU64 = AtomicU64::new(0);
+static MID_FILE_HEADERS: AtomicU64 = AtomicU64::new(0);
+
+/// Kind of tolerated input irregularity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Irregularity {
+    /// Line ended in `\r\n`
+    Crlf,
+    /// Columns separated by spaces instead of tabs
+    WhitespaceDelimited,
+    /// `track`/`browser` line after the first record
+    MidFileHeader,
+}
+
+/// Count one tolerated irregularity.
+#[inline]
+pub fn record_irregularity(kind: Irregularity) {
+    let counter = match kind {
+        Irregularity::Crlf => &CRLF_LINES,
+        Irregularity::WhitespaceDelimited => &WHITESPACE_LINES,
+        Irregularity::MidFileHeader => &MID_FILE_HEADERS,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Snapshot of tolerated input irregularities seen so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseIrregularities {
+    pub crlf_lines: u64,
+    pub whitespace_delimited_lines: u64,
+    pub mid_file_headers: u64,
+}
+
+impl ParseIrregularities {
+    /// True if no irregularities were seen.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for ParseIrregularities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CRLF lines: {}, Whitespace-delimited lines: {}, Mid-file track/browser lines: {}",
+            self.crlf_lines, self.whitespace_delimited_lines, self.mid_file_headers
+        )
+    }
+}
+
+/// Read the irregularity counters.
+pub fn parse_irregularities() -> ParseIrregularities {
+    ParseIrregularities {
+        crlf_lines: CRLF_LINES.load(Ordering::Relaxed),
+        whitespace_delimited_lines: WHITESPACE_LINES.load(Ordering::Relaxed),
+        mid_file_headers: MID_FILE_HEADERS.load(Ordering::Relaxed),
+    }
+}
+
+/// Restores the irregularity counters when dropped.
+///
+/// Held across pre-passes such as sort validation, which read lines that the
+/// command itself reads again.
+pub(crate) struct IrregularityGuard(ParseIrregularities);
+
+impl IrregularityGuard {
+    pub(crate) fn new() -> Self {
+        Self(parse_irregularities())
+    }
+}
+
+impl Drop for IrregularityGuard {
+    fn drop(&mut self) {
+        CRLF_LINES.store(self.0.crlf_lines, Ordering::Relaxed);
+        WHITESPACE_LINES.store(self.0.whitespace_delimited_lines, Ordering::Relaxed);
+        MID_FILE_HEADERS.store(self.0.mid_file_headers, Ordering::Relaxed);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +533,72 @@ mod tests {
         assert_eq!(normalize_end(100, 200), 200); // Non-zero-length unchanged
         set_bedtools_compatible(false); // Reset
     }
+
+    #[test]
+    fn test_delimiter_parse() {
+        assert_eq!(Delimiter::parse("auto"), Some(Delimiter::Auto));
+        assert_eq!(Delimiter::parse("tab"), Some(Delimiter::Tab));
+        assert_eq!(Delimiter::parse("space"), Some(Delimiter::Space));
+        assert_eq!(Delimiter::parse("comma"), None);
+        assert_eq!(delimiter(), Delimiter::Auto);
+    }
+
+    #[test]
+    fn test_chrom_filter() {
+        let filter = ChromFilter::new(Some("chr1-22,chrX"), Some("chrUn_*,*_random,chrM")).unwrap();
+        assert!(filter.allows(b"chr1"));
+        assert!(filter.allows(b"chr22"));
+        assert!(filter.allows(b"chrX"));
+        assert!(!filter.allows(b"chr23"));
+        assert!(!filter.allows(b"chr01"));
+        assert!(!filter.allows(b"chrY"));
+        assert!(!filter.allows(b"chr1_KI270706v1_random"));
+
+        let drop_only = ChromFilter::new(None, Some("chrUn_*,*_random,chr?")).unwrap();
+        assert!(drop_only.allows(b"chr10"));
+        assert!(!drop_only.allows(b"chrM"));
+        assert!(!drop_only.allows(b"chrUn_GL000220v1"));
+        assert!(!drop_only.allows(b"chr1_KI270706v1_random"));
+
+        assert!(ChromFilter::new(None, None).unwrap().is_empty());
+        assert!(ChromFilter::new(Some("chr1,"), None).is_err());
+        assert!(ChromFilter::new(Some("chr22-1"), None).is_err());
+        // Not a numeric range, so a literal name
+        assert!(ChromFilter::new(Some("HLA-A"), None)
+            .unwrap()
+            .allows(b"HLA-A"));
+    }
+
+    #[test]
+    fn test_chrom_classes() {
+        let text =
+            "# class\tpatterns\nautosomes\tchr1-22\nsex\tchrX,chrY\n\nmito\tchrM\nsex\tchrXY*\n";
+        let classes = ChromClasses::from_reader(text.as_bytes()).unwrap();
+        let names: Vec<&str> = classes.names().collect();
+        assert_eq!(names, ["autosomes", "sex", "mito", "other"]);
+        assert_eq!(classes.class_of(b"chr7"), 0);
+        assert_eq!(classes.class_of(b"chrY"), 1);
+        assert_eq!(classes.class_of(b"chrXY_alt"), 1);
+        assert_eq!(classes.class_of(b"chrM"), 2);
+        assert_eq!(classes.class_of(b"chrUn_GL000220v1"), 3);
+
+        // A named `other` class keeps its place and takes unmatched names
+        let classes = ChromClasses::from_reader("other\tchrUn_*\nmain\tchr*\n".as_bytes()).unwrap();
+        assert_eq!(classes.names().collect::<Vec<_>>(), ["other", "main"]);
+        assert_eq!(classes.class_of(b"scaffold_12"), 0);
+        assert_eq!(classes.class_of(b"chr2"), 1);
+
+        assert!(ChromClasses::from_reader("# nothing\n".as_bytes()).is_err());
+        assert!(ChromClasses::from_reader("autosomes\n".as_bytes()).is_err());
+        assert!(ChromClasses::from_reader("bad\tchr9-2\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_display_score() {
+        assert_eq!(ScoreFormat::parse("int"), Some(ScoreFormat::Integer));
+        assert_eq!(display_score(500.0).to_string(), "500");
+        assert_eq!(display_score(-3.0).to_string(), "-3");
+        assert_eq!(display_score(12.345678).to_string(), "12.345678");
+        assert_eq!(display_score(0.1).to_string(), "0.1");
+    }
 }
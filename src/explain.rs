@@ -0,0 +1,349 @@
+//! Execution plans for `--explain`.
+//!
+//! A [`Plan`] records how a command would run: the implementation its flags
+//! select, how sort order is established, the I/O buffers it allocates and a
+//! memory estimate. Input sizes come from file metadata and record counts
+//! are extrapolated from a short sample of each file, so building a plan
+//! never reads a whole input.
+
+use crate::bed::BedError;
+use crate::interval::BedRecord;
+use crate::streaming::buffers::{DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Bytes read from the start of each input to estimate its record count.
+const SAMPLE_BYTES: usize = 64 * 1024;
+
+/// How a command processes its inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// One pass over sorted inputs; memory is bounded by the intervals
+    /// overlapping the current position.
+    Streaming,
+    /// Inputs are loaded and processed in parallel.
+    InMemory,
+    /// Each A record is looked up in an index of B; only the B records near
+    /// it are read.
+    Indexed,
+}
+
+/// How input sort order is established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// A separate read of each input checks the order before processing.
+    PrePass,
+    /// Input is trusted to be sorted and never checked.
+    Assumed,
+    /// Records are sorted after loading.
+    SortedInMemory,
+    /// The command does not depend on input order.
+    NotRequired,
+}
+
+/// How an input is held while the command runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Residency {
+    /// Read through a fixed-size buffer.
+    Streamed,
+    /// Every record is held in memory.
+    Loaded,
+    /// Memory-mapped and read only where an index points.
+    Mapped,
+}
+
+/// One input of a plan.
+#[derive(Debug, Clone)]
+pub struct PlanInput {
+    pub label: String,
+    pub path: PathBuf,
+    /// File size in bytes; `None` for stdin.
+    pub bytes: Option<u64>,
+    /// Record count extrapolated from a sample; `None` for stdin.
+    pub records: Option<u64>,
+    pub residency: Residency,
+}
+
+impl PlanInput {
+    /// Describe an input, sampling the file to estimate its record count.
+    ///
+    /// A path of `-` is stdin, whose size is unknown.
+    pub fn new<P: AsRef<Path>>(
+        label: impl Into<String>,
+        path: P,
+        residency: Residency,
+    ) -> Result<Self, BedError> {
+        let path = path.as_ref();
+        let (bytes, records) = if path.to_string_lossy() == "-" {
+            (None, None)
+        } else {
+            let mut file = File::open(path).map_err(|e| {
+                BedError::InvalidFormat(format!("Failed to open '{}': {}", path.display(), e))
+            })?;
+            let bytes = file.metadata()?.len();
+            let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+            file.by_ref()
+                .take(SAMPLE_BYTES as u64)
+                .read_to_end(&mut sample)?;
+            (Some(bytes), Some(estimate_records(&sample, bytes)))
+        };
+
+        Ok(Self {
+            label: label.into(),
+            path: path.to_path_buf(),
+            bytes,
+            records,
+            residency,
+        })
+    }
+
+    /// Estimated memory for holding this input, if it is loaded.
+    ///
+    /// Each record costs a `BedRecord` plus roughly its line length in heap
+    /// strings.
+    pub fn resident_bytes(&self) -> Option<u64> {
+        match self.residency {
+            Residency::Streamed | Residency::Mapped => Some(0),
+            Residency::Loaded => {
+                let per_record = std::mem::size_of::<BedRecord>() as u64;
+                Some(self.bytes? + self.records? * per_record)
+            }
+        }
+    }
+}
+
+/// Record count of a file of `total` bytes whose first bytes are `sample`.
+fn estimate_records(sample: &[u8], total: u64) -> u64 {
+    let lines = memchr::memchr_iter(b'\n', sample).count() as u64;
+    if sample.len() as u64 >= total {
+        // Whole file sampled: count a final line without a newline too
+        let unterminated = !sample.is_empty() && !sample.ends_with(b"\n");
+        return lines + unterminated as u64;
+    }
+    if lines == 0 {
+        return 1;
+    }
+    (total as f64 * lines as f64 / sample.len() as f64).round() as u64
+}
+
+/// How a command would run, as reported by `--explain`.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub command: String,
+    pub strategy: Strategy,
+    pub validation: Validation,
+    pub inputs: Vec<PlanInput>,
+    /// Worker threads used by the chosen implementation.
+    pub threads: usize,
+    pub notes: Vec<String>,
+}
+
+impl Plan {
+    /// Start a plan; in-memory plans use the current Rayon pool size.
+    pub fn new(command: impl Into<String>, strategy: Strategy, validation: Validation) -> Self {
+        let threads = match strategy {
+            Strategy::Streaming | Strategy::Indexed => 1,
+            Strategy::InMemory => rayon::current_num_threads(),
+        };
+        Self {
+            command: command.into(),
+            strategy,
+            validation,
+            inputs: Vec::new(),
+            threads,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Add an input.
+    pub fn with_input<P: AsRef<Path>>(
+        mut self,
+        label: &str,
+        path: P,
+        residency: Residency,
+    ) -> Result<Self, BedError> {
+        self.inputs.push(PlanInput::new(label, path, residency)?);
+        Ok(self)
+    }
+
+    /// Add a free-form note.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Read and write buffer bytes allocated up front.
+    pub fn buffer_bytes(&self) -> u64 {
+        let streamed = self
+            .inputs
+            .iter()
+            .filter(|i| i.residency == Residency::Streamed)
+            .count();
+        (streamed * DEFAULT_INPUT_BUFFER + DEFAULT_OUTPUT_BUFFER) as u64
+    }
+
+    /// Estimated peak memory, or `None` when a loaded input has unknown size.
+    ///
+    /// Streaming plans exclude the active set, which depends on how many
+    /// intervals overlap any one position rather than on file size.
+    pub fn estimated_memory(&self) -> Option<u64> {
+        self.inputs
+            .iter()
+            .try_fold(self.buffer_bytes(), |total, input| {
+                Some(total + input.resident_bytes()?)
+            })
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "command:     {}", self.command)?;
+        let strategy = match self.strategy {
+            Strategy::Streaming => "streaming (one pass over sorted input)",
+            Strategy::InMemory => "in-memory (load, index and process in parallel)",
+            Strategy::Indexed => "indexed (look up each A record in B's index)",
+        };
+        writeln!(f, "strategy:    {}", strategy)?;
+        let validation = match self.validation {
+            Validation::PrePass => "sort check of each input before processing (one extra read)",
+            Validation::Assumed => "none, input assumed sorted",
+            Validation::SortedInMemory => "none, records are sorted after loading",
+            Validation::NotRequired => "none, input order does not matter",
+        };
+        writeln!(f, "validation:  {}", validation)?;
+        writeln!(f, "threads:     {}", self.threads)?;
+
+        for input in &self.inputs {
+            let residency = match input.residency {
+                Residency::Streamed => "streamed",
+                Residency::Loaded => "loaded",
+                Residency::Mapped => "memory-mapped",
+            };
+            match (input.bytes, input.records) {
+                (Some(bytes), Some(records)) => writeln!(
+                    f,
+                    "input {}:{:pad$}{} ({}, ~{} records, {})",
+                    input.label,
+                    "",
+                    input.path.display(),
+                    human_bytes(bytes),
+                    human_count(records),
+                    residency,
+                    pad = 6usize.saturating_sub(input.label.len()),
+                )?,
+                _ => writeln!(
+                    f,
+                    "input {}:{:pad$}stdin (size unknown, {})",
+                    input.label,
+                    "",
+                    residency,
+                    pad = 6usize.saturating_sub(input.label.len()),
+                )?,
+            }
+        }
+
+        writeln!(f, "buffers:     {}", human_bytes(self.buffer_bytes()))?;
+        match (self.estimated_memory(), self.strategy) {
+            (Some(bytes), Strategy::Streaming) => writeln!(
+                f,
+                "memory:      ~{} plus intervals overlapping the sweep position",
+                human_bytes(bytes)
+            )?,
+            (Some(bytes), Strategy::InMemory) => {
+                writeln!(f, "memory:      ~{}", human_bytes(bytes))?
+            }
+            (Some(bytes), Strategy::Indexed) => writeln!(
+                f,
+                "memory:      ~{} plus the B records near each A record",
+                human_bytes(bytes)
+            )?,
+            (None, _) => writeln!(f, "memory:      unknown (input size unknown)")?,
+        }
+        for note in &self.notes {
+            writeln!(f, "note:        {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. `1.5 GiB`.
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Format a count with a K/M/G suffix, e.g. `24.1M`.
+fn human_count(n: u64) -> String {
+    match n {
+        0..=9_999 => n.to_string(),
+        10_000..=999_999 => format!("{:.1}K", n as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1}M", n as f64 / 1e6),
+        _ => format!("{:.1}G", n as f64 / 1e9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_estimate_records() {
+        assert_eq!(estimate_records(b"", 0), 0);
+        assert_eq!(estimate_records(b"a\nb\nc", 5), 3);
+        // Two lines in a 4-byte sample of a 4000-byte file
+        assert_eq!(estimate_records(b"a\nb\n", 4000), 2000);
+    }
+
+    #[test]
+    fn test_human_units() {
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+        assert_eq!(human_count(950), "950");
+        assert_eq!(human_count(24_100_000), "24.1M");
+    }
+
+    #[test]
+    fn test_plan_memory() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"chr1\t0\t10\nchr1\t5\t20\n").unwrap();
+        file.flush().unwrap();
+
+        let streaming = Plan::new("merge", Strategy::Streaming, Validation::PrePass)
+            .with_input("A", file.path(), Residency::Streamed)
+            .unwrap();
+        assert_eq!(streaming.inputs[0].records, Some(2));
+        assert_eq!(
+            streaming.estimated_memory(),
+            Some((DEFAULT_INPUT_BUFFER + DEFAULT_OUTPUT_BUFFER) as u64)
+        );
+
+        let loaded = Plan::new("sort", Strategy::InMemory, Validation::NotRequired)
+            .with_input("A", file.path(), Residency::Loaded)
+            .unwrap();
+        let per_record = std::mem::size_of::<BedRecord>() as u64;
+        assert_eq!(
+            loaded.estimated_memory(),
+            Some(DEFAULT_OUTPUT_BUFFER as u64 + 20 + 2 * per_record)
+        );
+
+        let stdin = Plan::new("sort", Strategy::InMemory, Validation::NotRequired)
+            .with_input("A", "-", Residency::Loaded)
+            .unwrap();
+        assert_eq!(stdin.estimated_memory(), None);
+        assert!(stdin.to_string().contains("stdin (size unknown, loaded)"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Minimal FASTA reading.
+//!
+//! Sequences are read one record at a time, so only a single chromosome is
+//! held in memory. Record names are the first whitespace-separated token of
+//! the header line. Line breaks inside a sequence are dropped; bases are kept
+//! as written (case is preserved).
+
+use crate::bed::BedError;
+use crate::streaming::parsing::trim_newline;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// One FASTA record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastaRecord {
+    pub name: String,
+    pub seq: Vec<u8>,
+}
+
+/// Streaming reader over FASTA records.
+pub struct FastaReader<R: Read> {
+    reader: BufReader<R>,
+    line: Vec<u8>,
+    /// Header of the next record, already consumed from the input.
+    next_name: Option<String>,
+    line_num: usize,
+}
+
+impl FastaReader<File> {
+    /// Open a FASTA file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            BedError::InvalidFormat(format!("Failed to open FASTA '{}': {}", path.display(), e))
+        })?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<R: Read> FastaReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::with_capacity(256 * 1024, reader),
+            line: Vec::with_capacity(256),
+            next_name: None,
+            line_num: 0,
+        }
+    }
+
+    /// Read the next record, or `None` at end of input.
+    pub fn next_record(&mut self) -> Result<Option<FastaRecord>, BedError> {
+        let name = match self.next_name.take() {
+            Some(name) => name,
+            None => match self.read_header()? {
+                Some(name) => name,
+                None => return Ok(None),
+            },
+        };
+
+        let mut seq = Vec::new();
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                break;
+            }
+            self.line_num += 1;
+            let line = trim_newline(&self.line);
+            if let Some(header) = line.strip_prefix(b">") {
+                self.next_name = Some(parse_name(header));
+                break;
+            }
+            seq.extend_from_slice(line);
+        }
+
+        Ok(Some(FastaRecord { name, seq }))
+    }
+
+    /// Skip to the first header line.
+    fn read_header(&mut self) -> Result<Option<String>, BedError> {
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_num += 1;
+            let line = trim_newline(&self.line);
+            if line.is_empty() {
+                continue;
+            }
+            return match line.strip_prefix(b">") {
+                Some(header) => Ok(Some(parse_name(header))),
+                None => Err(BedError::Parse {
+                    line: self.line_num,
+                    message: "expected FASTA header starting with '>'".to_string(),
+                }),
+            };
+        }
+    }
+}
+
+impl<R: Read> Iterator for FastaReader<R> {
+    type Item = Result<FastaRecord, BedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+fn parse_name(header: &[u8]) -> String {
+    let name = header
+        .split(|b| b.is_ascii_whitespace())
+        .find(|t| !t.is_empty())
+        .unwrap_or_default();
+    String::from_utf8_lossy(name).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_records() {
+        let content = "\n>chr1 assembled\r\nACGT\r\nNNac\r\n>chr2\nGG\n>empty\n";
+        let records: Vec<_> = FastaReader::new(content.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].name, "chr1");
+        assert_eq!(records[0].seq, b"ACGTNNac");
+        assert_eq!(records[1].seq, b"GG");
+        assert!(records[2].seq.is_empty());
+    }
+
+    #[test]
+    fn test_missing_header() {
+        let mut reader = FastaReader::new("ACGT\n".as_bytes());
+        assert!(matches!(
+            reader.next_record(),
+            Err(BedError::Parse { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,482 @@
+//! Sidecar index for random access into sorted BED files.
+//!
+//! The index records, for each chromosome, the byte range its records occupy
+//! and the length of its longest record. With that, every record overlapping
+//! a region lies in a short stretch of the range that a binary search on
+//! start positions finds directly, so point lookups never read the whole
+//! file. The index is small enough to keep as text next to the BED file:
+//!
+//! ```text
+//! #grit-index  2
+//! #source  1048576  1700000000  250000000
+//! chr1  0  524288  15000  12000
+//! chr2  524288  1048576  9000  11800
+//! ```
+//!
+//! The `#source` line holds the size, modification time (seconds) and the
+//! sub-second part of the modification time (nanoseconds) of the indexed
+//! file; an index whose source has changed since is reported as stale rather
+//! than silently used. Chromosome lines are
+//! `chrom, offset, end, max_len, records`, tab-separated.
+//!
+//! # Compatibility
+//!
+//! The first line carries the format version. Readers accept every version
+//! from [`MIN_FILE_INDEX_VERSION`] up to [`FILE_INDEX_VERSION`], so indexes
+//! built by older releases keep working after an upgrade, and reject newer
+//! versions with an error rather than misreading them. Unknown `#` lines and
+//! extra columns are ignored, so additions older readers can skip do not
+//! need a new version. [`upgrade_sidecar`] rewrites an older index in the
+//! current format without re-reading the BED file.
+//!
+//! Version history:
+//!
+//! - 1: `#source` holds whole seconds only, so a file rewritten with the
+//!   same size within the same second is not detected as changed.
+//! - 2: `#source` adds the nanoseconds of the modification time.
+
+use crate::bed::BedError;
+use crate::streaming::parsing::parse_bed3_bytes;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Index format version written on the first line.
+pub const FILE_INDEX_VERSION: u32 = 2;
+
+/// Oldest index format version that can still be read.
+pub const MIN_FILE_INDEX_VERSION: u32 = 1;
+
+/// Extension appended to the BED path to name its index.
+pub const FILE_INDEX_EXTENSION: &str = "gri";
+
+/// Records of one chromosome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChromBlock {
+    pub chrom: String,
+    /// Byte offset of the first record.
+    pub offset: u64,
+    /// Byte offset just past the last record.
+    pub end: u64,
+    /// Length of the longest record.
+    pub max_len: u64,
+    pub records: u64,
+}
+
+/// Size and modification time of an indexed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceStamp {
+    pub len: u64,
+    pub mtime: u64,
+    /// Sub-second part of the modification time, in nanoseconds; `None` in
+    /// version 1 indexes, which record whole seconds.
+    pub nanos: Option<u32>,
+}
+
+impl SourceStamp {
+    /// Stamp of the file at `path` as it is now.
+    pub fn of<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
+        let meta = fs::metadata(path)?;
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            len: meta.len(),
+            mtime: mtime.as_secs(),
+            nanos: Some(mtime.subsec_nanos()),
+        })
+    }
+
+    /// Whether a file stamped `current` is unchanged since this stamp was
+    /// taken, as far as this stamp's precision can tell.
+    pub fn matches(&self, current: &SourceStamp) -> bool {
+        self.len == current.len
+            && self.mtime == current.mtime
+            && (self.nanos.is_none() || self.nanos == current.nanos)
+    }
+}
+
+/// Per-chromosome byte ranges of a sorted BED file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIndex {
+    /// Format version the index was read as; [`FILE_INDEX_VERSION`] for
+    /// built indexes.
+    pub version: u32,
+    pub source: SourceStamp,
+    /// Chromosomes in file order.
+    pub blocks: Vec<ChromBlock>,
+}
+
+impl FileIndex {
+    /// Index the contents of a BED file.
+    ///
+    /// Records of each chromosome must be contiguous and sorted by start;
+    /// the chromosome order itself does not matter. Header, comment and
+    /// blank lines are skipped.
+    pub fn build(data: &[u8], source: SourceStamp) -> Result<Self, BedError> {
+        let mut blocks: Vec<ChromBlock> = Vec::new();
+        let mut last_start = 0;
+        let mut pos = 0;
+        let mut line_num = 0;
+
+        while pos < data.len() {
+            let line_end = memchr::memchr(b'\n', &data[pos..]).map_or(data.len(), |i| pos + i);
+            let next = (line_end + 1).min(data.len());
+            line_num += 1;
+            let line = &data[pos..line_end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if is_header(line) {
+                pos = next;
+                continue;
+            }
+
+            let (chrom, start, end) = parse_bed3_bytes(line).ok_or_else(|| BedError::Parse {
+                line: line_num,
+                message: "expected chrom, start and end columns".to_string(),
+            })?;
+            match blocks.last_mut() {
+                Some(block) if block.chrom.as_bytes() == chrom => {
+                    if start < last_start {
+                        return Err(BedError::Parse {
+                            line: line_num,
+                            message: format!(
+                                "input is not sorted: start {} follows {} on {}",
+                                start, last_start, block.chrom
+                            ),
+                        });
+                    }
+                    block.end = line_end as u64;
+                    block.max_len = block.max_len.max(end.saturating_sub(start));
+                    block.records += 1;
+                }
+                _ => {
+                    let chrom = String::from_utf8_lossy(chrom).into_owned();
+                    if blocks.iter().any(|b| b.chrom == chrom) {
+                        return Err(BedError::Parse {
+                            line: line_num,
+                            message: format!(
+                                "input is not sorted: records of {} are not contiguous",
+                                chrom
+                            ),
+                        });
+                    }
+                    blocks.push(ChromBlock {
+                        chrom,
+                        offset: pos as u64,
+                        end: line_end as u64,
+                        max_len: end.saturating_sub(start),
+                        records: 1,
+                    });
+                }
+            }
+            last_start = start;
+            pos = next;
+        }
+
+        Ok(Self {
+            version: FILE_INDEX_VERSION,
+            source,
+            blocks,
+        })
+    }
+
+    /// Block of a chromosome.
+    pub fn block(&self, chrom: &str) -> Option<&ChromBlock> {
+        self.blocks.iter().find(|b| b.chrom == chrom)
+    }
+
+    /// Path of the index for a BED file: the BED path plus `.gri`.
+    pub fn sidecar_path<P: AsRef<Path>>(bed: P) -> PathBuf {
+        let mut path = bed.as_ref().as_os_str().to_owned();
+        path.push(".");
+        path.push(FILE_INDEX_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// Write the index in the current format.
+    pub fn write<W: Write>(&self, output: W) -> Result<(), BedError> {
+        let mut out = BufWriter::new(output);
+        writeln!(out, "#grit-index\t{}", FILE_INDEX_VERSION)?;
+        write!(out, "#source\t{}\t{}", self.source.len, self.source.mtime)?;
+        if let Some(nanos) = self.source.nanos {
+            write!(out, "\t{}", nanos)?;
+        }
+        writeln!(out)?;
+        for b in &self.blocks {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}",
+                b.chrom, b.offset, b.end, b.max_len, b.records
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Write the index next to the BED file it describes.
+    pub fn write_sidecar<P: AsRef<Path>>(&self, bed: P) -> Result<PathBuf, BedError> {
+        let path = Self::sidecar_path(bed);
+        self.write(File::create(&path)?)?;
+        Ok(path)
+    }
+
+    /// Read an index of any supported version.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, BedError> {
+        let mut lines = reader.lines();
+        let invalid = |line: usize, message: String| BedError::Parse { line, message };
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let version = header
+            .strip_prefix("#grit-index\t")
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or_else(|| invalid(1, "not a grit index".to_string()))?;
+        if version > FILE_INDEX_VERSION {
+            return Err(invalid(
+                1,
+                format!(
+                    "index version {} was written by a newer grit (this one reads versions {} to {}); \
+                     upgrade grit or rebuild the index with --build-index",
+                    version, MIN_FILE_INDEX_VERSION, FILE_INDEX_VERSION
+                ),
+            ));
+        }
+        if version < MIN_FILE_INDEX_VERSION {
+            return Err(invalid(1, format!("unsupported index version {}", version)));
+        }
+
+        let stamp = lines.next().transpose()?.unwrap_or_default();
+        let fields: Vec<&str> = stamp.split('\t').collect();
+        let source = match fields.as_slice() {
+            ["#source", len, mtime, rest @ ..] => SourceStamp {
+                len: len
+                    .parse()
+                    .map_err(|_| invalid(2, "invalid source size".into()))?,
+                mtime: mtime
+                    .parse()
+                    .map_err(|_| invalid(2, "invalid source time".into()))?,
+                nanos: match rest.first() {
+                    Some(nanos) if version >= 2 => Some(
+                        nanos
+                            .parse()
+                            .map_err(|_| invalid(2, "invalid source time".into()))?,
+                    ),
+                    _ => None,
+                },
+            },
+            _ => return Err(invalid(2, "missing #source line".to_string())),
+        };
+
+        let mut blocks = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            // Header lines added by later versions
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let f: Vec<&str> = line.split('\t').collect();
+            let num = |j: usize| -> Result<u64, BedError> {
+                f.get(j)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| invalid(i + 3, format!("invalid index line: {}", line)))
+            };
+            blocks.push(ChromBlock {
+                chrom: f[0].to_string(),
+                offset: num(1)?,
+                end: num(2)?,
+                max_len: num(3)?,
+                records: num(4)?,
+            });
+        }
+
+        Ok(Self {
+            version,
+            source,
+            blocks,
+        })
+    }
+
+    /// Load the sidecar index of a BED file, if there is one.
+    ///
+    /// An index whose recorded size or modification time no longer matches
+    /// the BED file is an error, since offsets into a changed file would
+    /// return wrong records.
+    pub fn load_sidecar<P: AsRef<Path>>(bed: P) -> Result<Option<Self>, BedError> {
+        let path = Self::sidecar_path(&bed);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let index = Self::read(BufReader::new(file))
+            .map_err(|e| BedError::InvalidFormat(format!("index '{}': {}", path.display(), e)))?;
+        if !index.source.matches(&SourceStamp::of(&bed)?) {
+            return Err(BedError::InvalidFormat(format!(
+                "index '{}' is stale: '{}' changed after it was built; rebuild it with --build-index",
+                path.display(),
+                bed.as_ref().display()
+            )));
+        }
+        Ok(Some(index))
+    }
+}
+
+/// Outcome of [`upgrade_sidecar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexUpgrade {
+    /// The index already has the current version.
+    Current,
+    /// The index had (or, in a dry run, has) an older version.
+    Upgraded { from: u32 },
+}
+
+/// Rewrite the sidecar index of a BED file in the current format.
+///
+/// Only the BED file's size and modification time are read: an index that
+/// still matches them is restamped at full precision and written back with
+/// its blocks unchanged. A stale index is an error, since it has to be
+/// rebuilt from the data. With `dry_run` nothing is written.
+///
+/// The new index replaces the old one by rename, so readers on shared
+/// storage see either the old or the new file, never a partial one.
+pub fn upgrade_sidecar<P: AsRef<Path>>(bed: P, dry_run: bool) -> Result<IndexUpgrade, BedError> {
+    let bed = bed.as_ref();
+    let path = FileIndex::sidecar_path(bed);
+    let file = File::open(&path).map_err(|e| {
+        BedError::InvalidFormat(format!("cannot open index '{}': {}", path.display(), e))
+    })?;
+    let mut index = FileIndex::read(BufReader::new(file))
+        .map_err(|e| BedError::InvalidFormat(format!("index '{}': {}", path.display(), e)))?;
+    if index.version == FILE_INDEX_VERSION {
+        return Ok(IndexUpgrade::Current);
+    }
+
+    let current = SourceStamp::of(bed)?;
+    if !index.source.matches(&current) {
+        return Err(BedError::InvalidFormat(format!(
+            "index '{}' is stale: '{}' changed after it was built; rebuild it with --build-index",
+            path.display(),
+            bed.display()
+        )));
+    }
+    let from = index.version;
+    if !dry_run {
+        index.source = current;
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        index.write(File::create(&tmp)?)?;
+        fs::rename(&tmp, &path)?;
+    }
+    Ok(IndexUpgrade::Upgraded { from })
+}
+
+/// Header, comment and blank lines. Unlike
+/// [`should_skip_line`](crate::streaming::parsing::should_skip_line) this
+/// ignores chromosome filters, so an index covers the whole file.
+#[inline]
+pub(crate) fn is_header(line: &[u8]) -> bool {
+    line.is_empty() || line[0] == b'#' || line.starts_with(b"track") || line.starts_with(b"browser")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAMP: SourceStamp = SourceStamp {
+        len: 0,
+        mtime: 0,
+        nanos: None,
+    };
+
+    #[test]
+    fn test_build_blocks() {
+        let data = b"track name=x\nchr2\t0\t10\nchr2\t5\t100\nchr1\t3\t4\n";
+        let index = FileIndex::build(data, STAMP).unwrap();
+        assert_eq!(index.blocks.len(), 2);
+        let chr2 = index.block("chr2").unwrap();
+        assert_eq!((chr2.offset, chr2.max_len, chr2.records), (13, 95, 2));
+        assert_eq!(
+            &data[chr2.offset as usize..chr2.end as usize],
+            b"chr2\t0\t10\nchr2\t5\t100"
+        );
+        assert_eq!(index.block("chr1").unwrap().end, data.len() as u64 - 1);
+    }
+
+    #[test]
+    fn test_build_rejects_unsorted() {
+        let err = FileIndex::build(b"chr1\t5\t6\nchr1\t1\t2\n", STAMP);
+        assert!(matches!(err, Err(BedError::Parse { line: 2, .. })));
+        let err = FileIndex::build(b"chr1\t5\t6\nchr2\t1\t2\nchr1\t9\t10\n", STAMP);
+        assert!(matches!(err, Err(BedError::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let index = FileIndex::build(b"chr1\t0\t10\nchrX\t7\t9\n", STAMP).unwrap();
+        let mut text = Vec::new();
+        index.write(&mut text).unwrap();
+        assert!(text.starts_with(b"#grit-index\t2\n#source\t0\t0\n"));
+        assert_eq!(FileIndex::read(&text[..]).unwrap(), index);
+
+        let err = FileIndex::read(&b"#grit-index\t99\n"[..]).unwrap_err();
+        assert!(matches!(err, BedError::Parse { line: 1, .. }));
+        assert!(err.to_string().contains("newer grit"));
+    }
+
+    #[test]
+    fn test_read_older_versions() {
+        let v1 = b"#grit-index\t1\n#source\t10\t1700000000\nchr1\t0\t9\t5\t1\n";
+        let index = FileIndex::read(&v1[..]).unwrap();
+        assert_eq!(index.version, 1);
+        assert_eq!(index.source.nanos, None);
+        let now = SourceStamp {
+            len: 10,
+            mtime: 1_700_000_000,
+            nanos: Some(5),
+        };
+        assert!(index.source.matches(&now));
+
+        // Unknown header lines and extra columns are skipped
+        let v2 = b"#grit-index\t2\n#source\t10\t1700000000\t7\n#future\tx\nchr1\t0\t9\t5\t1\ty\n";
+        let index = FileIndex::read(&v2[..]).unwrap();
+        assert_eq!(index.source.nanos, Some(7));
+        assert!(!index.source.matches(&now));
+        assert_eq!(index.blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_upgrade_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let bed = dir.path().join("a.bed");
+        fs::write(&bed, "chr1\t0\t10\n").unwrap();
+        let stamp = SourceStamp::of(&bed).unwrap();
+        let sidecar = FileIndex::sidecar_path(&bed);
+        let v1 = format!(
+            "#grit-index\t1\n#source\t{}\t{}\nchr1\t0\t9\t10\t1\n",
+            stamp.len, stamp.mtime
+        );
+        fs::write(&sidecar, &v1).unwrap();
+
+        assert_eq!(
+            upgrade_sidecar(&bed, true).unwrap(),
+            IndexUpgrade::Upgraded { from: 1 }
+        );
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), v1);
+        assert_eq!(
+            upgrade_sidecar(&bed, false).unwrap(),
+            IndexUpgrade::Upgraded { from: 1 }
+        );
+        let index = FileIndex::load_sidecar(&bed).unwrap().unwrap();
+        assert_eq!(index.version, FILE_INDEX_VERSION);
+        assert_eq!(index.source, stamp);
+        assert_eq!(index.block("chr1").unwrap().end, 9);
+        assert_eq!(upgrade_sidecar(&bed, false).unwrap(), IndexUpgrade::Current);
+
+        // A stale index must be rebuilt, not restamped
+        fs::write(&sidecar, v1.replace("#source\t10", "#source\t11")).unwrap();
+        let err = upgrade_sidecar(&bed, false).unwrap_err();
+        assert!(err.to_string().contains("is stale"));
+    }
+}
@@ -1,13 +1,86 @@
 //! Genome file parser for chromosome sizes.
 //!
-//! Parses .genome files (tab-delimited: chrom\tsize)
+//! Parses .genome files (tab-delimited: chrom\tsize). Columns after the size
+//! may carry per-chromosome metadata used by genome-wide statistics:
+//!
+//! ```text
+//! chr1    248956422   effective=230000000
+//! chrX    156040895   ploidy=1
+//! chrY    57227415    ploidy=1    effective=23636355
+//! chrUn_KI270302v1    2274    exclude
+//! ```
+//!
+//! `ploidy=N` sets the copy number (default 2), `effective=N` the number of
+//! usable (e.g. mappable) bases, and `exclude` drops the chromosome from
+//! genome-wide totals. Other columns without `=` are ignored, so UCSC
+//! `chromInfo` dumps with a file-name column still load.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
-use crate::bed::BedError;
+use crate::bed::{read_intervals, BedError};
+use crate::config::chrom_allowed;
+use crate::interval::Interval;
+use crate::streaming::parsing::{parse_bed3_bytes_with_rest, should_skip_line};
+
+/// Copy number assumed for chromosomes without a `ploidy=` column.
+pub const DEFAULT_PLOIDY: u32 = 2;
+
+/// Per-chromosome metadata from the optional genome file columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChromInfo {
+    /// Copies of the chromosome; 0 marks a chromosome absent from the sample.
+    pub ploidy: u32,
+    /// Excluded from genome-wide totals and uniform sampling.
+    pub excluded: bool,
+    /// Usable bases, if fewer than the chromosome size.
+    pub effective_size: Option<u64>,
+}
+
+impl Default for ChromInfo {
+    fn default() -> Self {
+        Self {
+            ploidy: DEFAULT_PLOIDY,
+            excluded: false,
+            effective_size: None,
+        }
+    }
+}
+
+impl ChromInfo {
+    /// Parse the metadata columns following chrom and size.
+    fn parse(fields: &[&str], size: u64) -> Result<Self, String> {
+        let mut info = Self::default();
+        for field in fields {
+            let field = field.trim();
+            match field.split_once('=') {
+                Some(("ploidy", v)) => {
+                    info.ploidy = v.parse().map_err(|_| format!("Invalid ploidy: {}", v))?;
+                }
+                Some(("effective", v)) => {
+                    let effective: u64 = v
+                        .parse()
+                        .map_err(|_| format!("Invalid effective size: {}", v))?;
+                    if effective > size {
+                        return Err(format!(
+                            "Effective size {} exceeds chromosome size {}",
+                            effective, size
+                        ));
+                    }
+                    info.effective_size = Some(effective);
+                }
+                Some((key, _)) => return Err(format!("Unknown genome column: {}", key)),
+                None if field == "exclude" => info.excluded = true,
+                None if field == "include" => info.excluded = false,
+                None => {}
+            }
+        }
+        Ok(info)
+    }
+}
 
 /// Genome information containing chromosome sizes.
 /// Preserves chromosome order from input file.
@@ -17,6 +90,8 @@ pub struct Genome {
     sizes: HashMap<String, u64>,
     /// Chromosome order (preserves input file order)
     order: Vec<String>,
+    /// Metadata for chromosomes that differ from [`ChromInfo::default`]
+    info: HashMap<String, ChromInfo>,
 }
 
 impl Genome {
@@ -25,16 +100,18 @@ impl Genome {
         Self {
             sizes: HashMap::new(),
             order: Vec::new(),
+            info: HashMap::new(),
         }
     }
 
     /// Load genome from a file.
-    /// Format: tab-delimited with chrom\tsize per line
+    /// Format: tab-delimited with chrom\tsize per line, optionally followed
+    /// by metadata columns (see the module docs). Chromosomes excluded by
+    /// [`crate::config::set_chrom_filter`] are left out.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        let mut sizes = HashMap::new();
-        let mut order = Vec::new();
+        let mut genome = Self::new();
 
         for (line_num, line_result) in reader.lines().enumerate() {
             let line = line_result?;
@@ -58,14 +135,21 @@ impl Genome {
                 line: line_num + 1,
                 message: format!("Invalid chromosome size: {}", fields[1]),
             })?;
+            let info = ChromInfo::parse(&fields[2..], size).map_err(|message| BedError::Parse {
+                line: line_num + 1,
+                message,
+            })?;
 
-            if !sizes.contains_key(&chrom) {
-                order.push(chrom.clone());
+            // Chromosomes excluded by --keep-chroms/--drop-chroms
+            if !chrom_allowed(chrom.as_bytes()) {
+                continue;
             }
-            sizes.insert(chrom, size);
+
+            genome.set_chrom_info(&chrom, info);
+            genome.insert(chrom, size);
         }
 
-        Ok(Self { sizes, order })
+        Ok(genome)
     }
 
     /// Get the size of a chromosome.
@@ -95,6 +179,11 @@ impl Genome {
         self.sizes.is_empty()
     }
 
+    /// Total length of all chromosomes.
+    pub fn total_size(&self) -> u64 {
+        self.sizes.values().sum()
+    }
+
     /// Insert a chromosome size (appends to order if new).
     pub fn insert(&mut self, chrom: String, size: u64) {
         if !self.sizes.contains_key(&chrom) {
@@ -102,12 +191,345 @@ impl Genome {
         }
         self.sizes.insert(chrom, size);
     }
+
+    /// Metadata for a chromosome, or the defaults if none was given.
+    pub fn chrom_info(&self, chrom: &str) -> ChromInfo {
+        self.info.get(chrom).copied().unwrap_or_default()
+    }
+
+    /// Set a chromosome's metadata.
+    pub fn set_chrom_info(&mut self, chrom: &str, info: ChromInfo) {
+        if info == ChromInfo::default() {
+            self.info.remove(chrom);
+        } else {
+            self.info.insert(chrom.to_string(), info);
+        }
+    }
+
+    /// True if any chromosome carries metadata.
+    pub fn has_metadata(&self) -> bool {
+        !self.info.is_empty()
+    }
+
+    /// Copy number of a chromosome.
+    #[inline]
+    pub fn ploidy(&self, chrom: &str) -> u32 {
+        self.chrom_info(chrom).ploidy
+    }
+
+    /// Usable bases of a chromosome: its effective size if one was given,
+    /// otherwise its full size.
+    pub fn effective_size(&self, chrom: &str) -> Option<u64> {
+        let size = self.chrom_size(chrom)?;
+        Some(self.chrom_info(chrom).effective_size.unwrap_or(size))
+    }
+
+    /// Whether a chromosome counts toward genome-wide totals: it must be
+    /// known, not excluded and present in at least one copy.
+    pub fn in_totals(&self, chrom: &str) -> bool {
+        let info = self.chrom_info(chrom);
+        self.has_chrom(chrom) && !info.excluded && info.ploidy > 0
+    }
+
+    /// Effective genome size: the usable bases of every chromosome that
+    /// counts toward genome-wide totals. Equals [`total_size`](Self::total_size)
+    /// for a plain two-column genome file.
+    pub fn effective_total_size(&self) -> u64 {
+        self.order
+            .iter()
+            .filter(|c| self.in_totals(c))
+            .filter_map(|c| self.effective_size(c))
+            .sum()
+    }
+
+    /// Bases a chromosome's depth histogram contributes to the genome-wide
+    /// histogram at `depth`.
+    ///
+    /// Chromosomes outside the totals contribute nothing. Bases beyond the
+    /// effective size are taken from the depth-0 bin, since unusable bases
+    /// are not expected to be covered.
+    pub fn genome_wide_bases(&self, chrom: &str, depth: u32, bases: u64) -> u64 {
+        if !self.in_totals(chrom) {
+            return 0;
+        }
+        if depth > 0 {
+            return bases;
+        }
+        let size = self.chrom_size(chrom).unwrap_or(0);
+        let unusable = size - self.effective_size(chrom).unwrap_or(size);
+        bases.saturating_sub(unusable)
+    }
+
+    /// Copy BED lines from `reader` to `writer`, clipping records that
+    /// extend past the end of their chromosome.
+    ///
+    /// A record's end is moved back to the chromosome end; a record starting
+    /// at or past it is left out. Headers, lines that do not parse and
+    /// records on chromosomes missing from the genome are copied unchanged.
+    pub fn clip_bed<R: BufRead, W: Write>(
+        &self,
+        mut reader: R,
+        writer: &mut W,
+    ) -> Result<ClipStats, BedError> {
+        let mut stats = ClipStats::default();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            let parsed = if should_skip_line(content) {
+                None
+            } else {
+                parse_bed3_bytes_with_rest(content)
+            };
+            let Some((chrom, start, end, rest)) = parsed else {
+                writer.write_all(&line)?;
+                continue;
+            };
+            stats.records += 1;
+            let size = std::str::from_utf8(chrom)
+                .ok()
+                .and_then(|c| self.chrom_size(c));
+            match size {
+                Some(size) if end > size && start >= size => stats.dropped += 1,
+                Some(size) if end > size => {
+                    stats.clipped += 1;
+                    writer.write_all(chrom)?;
+                    write!(writer, "\t{}\t{}", start, size)?;
+                    writer.write_all(&content[rest..])?;
+                    writer.write_all(&line[content.len()..])?;
+                }
+                _ => writer.write_all(&line)?,
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// Records changed by [`Genome::clip_bed`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClipStats {
+    /// Records read
+    pub records: u64,
+    /// Records whose end was moved back to the chromosome end
+    pub clipped: u64,
+    /// Records starting at or past the chromosome end, left out
+    pub dropped: u64,
+}
+
+impl fmt::Display for ClipStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} clipped, {} dropped of {} records",
+            self.clipped, self.dropped, self.records
+        )
+    }
+}
+
+/// A set of allowed genomic regions.
+///
+/// Regions are kept per chromosome as sorted, disjoint, non-adjacent
+/// half-open ranges, so membership and clipping are binary searches.
+/// Commands that take include/exclude BED files build one of these instead
+/// of re-implementing the filtering themselves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenomeMask {
+    regions: HashMap<String, Vec<(u64, u64)>>,
+}
+
+impl GenomeMask {
+    /// Create an empty mask (nothing allowed).
+    pub fn new() -> Self {
+        Self {
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Mask allowing every base of every chromosome in the genome.
+    pub fn from_genome(genome: &Genome) -> Self {
+        let regions = genome
+            .chromosomes()
+            .filter_map(|c| {
+                let size = genome.chrom_size(c)?;
+                (size > 0).then(|| (c.clone(), vec![(0, size)]))
+            })
+            .collect();
+        Self { regions }
+    }
+
+    /// Mask allowing the union of the given intervals.
+    pub fn from_intervals(intervals: &[Interval]) -> Self {
+        let mut by_chrom: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+        for interval in intervals.iter().filter(|i| i.end > i.start) {
+            by_chrom
+                .entry(interval.chrom.clone())
+                .or_default()
+                .push((interval.start, interval.end));
+        }
+
+        for ranges in by_chrom.values_mut() {
+            ranges.sort_unstable();
+            let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+            for &(start, end) in ranges.iter() {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            *ranges = merged;
+        }
+
+        Self { regions: by_chrom }
+    }
+
+    /// Build a mask from a genome, restricted to an include BED file and
+    /// with an exclude BED file removed. Either file may be omitted.
+    pub fn from_files<P: AsRef<Path>>(
+        genome: &Genome,
+        include: Option<P>,
+        exclude: Option<P>,
+    ) -> Result<Self, BedError> {
+        let mut mask = Self::from_genome(genome);
+        if let Some(path) = include {
+            mask = mask.intersect(&Self::from_intervals(&read_intervals(path)?));
+        }
+        if let Some(path) = exclude {
+            mask = mask.subtract(&Self::from_intervals(&read_intervals(path)?));
+        }
+        Ok(mask)
+    }
+
+    /// Regions allowed by both masks.
+    pub fn intersect(&self, other: &GenomeMask) -> Self {
+        let regions = self
+            .regions
+            .iter()
+            .filter_map(|(chrom, a)| {
+                let b = other.regions.get(chrom)?;
+                let mut out = Vec::new();
+                let (mut i, mut j) = (0, 0);
+                while i < a.len() && j < b.len() {
+                    let start = a[i].0.max(b[j].0);
+                    let end = a[i].1.min(b[j].1);
+                    if start < end {
+                        out.push((start, end));
+                    }
+                    if a[i].1 < b[j].1 {
+                        i += 1;
+                    } else {
+                        j += 1;
+                    }
+                }
+                (!out.is_empty()).then(|| (chrom.clone(), out))
+            })
+            .collect();
+        Self { regions }
+    }
+
+    /// Regions allowed by this mask but not by `other`.
+    pub fn subtract(&self, other: &GenomeMask) -> Self {
+        let regions = self
+            .regions
+            .iter()
+            .filter_map(|(chrom, a)| {
+                let Some(b) = other.regions.get(chrom) else {
+                    return Some((chrom.clone(), a.clone()));
+                };
+                let mut out = Vec::new();
+                let mut j = 0;
+                for &(start, end) in a {
+                    let mut pos = start;
+                    while j < b.len() && b[j].1 <= pos {
+                        j += 1;
+                    }
+                    let mut k = j;
+                    while k < b.len() && b[k].0 < end {
+                        if b[k].0 > pos {
+                            out.push((pos, b[k].0));
+                        }
+                        pos = pos.max(b[k].1);
+                        k += 1;
+                    }
+                    if pos < end {
+                        out.push((pos, end));
+                    }
+                }
+                (!out.is_empty()).then(|| (chrom.clone(), out))
+            })
+            .collect();
+        Self { regions }
+    }
+
+    /// Regions allowed by either mask.
+    pub fn union(&self, other: &GenomeMask) -> Self {
+        let intervals: Vec<Interval> = self
+            .iter()
+            .chain(other.iter())
+            .map(|(chrom, start, end)| Interval::new(chrom, start, end))
+            .collect();
+        Self::from_intervals(&intervals)
+    }
+
+    /// Check whether `[start, end)` lies entirely inside one allowed region.
+    pub fn contains(&self, chrom: &str, start: u64, end: u64) -> bool {
+        let ranges = self.regions(chrom);
+        let idx = ranges.partition_point(|&(_, e)| e <= start);
+        ranges
+            .get(idx)
+            .is_some_and(|&(s, e)| s <= start && end <= e)
+    }
+
+    /// Check whether `[start, end)` touches any allowed region.
+    pub fn overlaps(&self, chrom: &str, start: u64, end: u64) -> bool {
+        self.clip(chrom, start, end).next().is_some()
+    }
+
+    /// Parts of `[start, end)` that fall inside allowed regions, in order.
+    pub fn clip<'a>(
+        &'a self,
+        chrom: &str,
+        start: u64,
+        end: u64,
+    ) -> impl Iterator<Item = (u64, u64)> + 'a {
+        let ranges = self.regions(chrom);
+        let idx = ranges.partition_point(|&(_, e)| e <= start);
+        ranges[idx..]
+            .iter()
+            .take_while(move |&&(s, _)| s < end)
+            .map(move |&(s, e)| (s.max(start), e.min(end)))
+            .filter(|(s, e)| s < e)
+    }
+
+    /// Allowed regions on a chromosome, sorted and disjoint.
+    pub fn regions(&self, chrom: &str) -> &[(u64, u64)] {
+        self.regions.get(chrom).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Iterate over all allowed regions as `(chrom, start, end)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64, u64)> {
+        self.regions
+            .iter()
+            .flat_map(|(c, r)| r.iter().map(move |&(s, e)| (c.as_str(), s, e)))
+    }
+
+    /// Total number of allowed bases.
+    pub fn total_len(&self) -> u64 {
+        self.iter().map(|(_, s, e)| e - s).sum()
+    }
+
+    /// Check if nothing is allowed.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -127,6 +549,43 @@ mod tests {
         assert_eq!(genome.len(), 3);
     }
 
+    #[test]
+    fn test_genome_metadata_columns() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "chr1\t1000\teffective=800").unwrap();
+        writeln!(file, "chrX\t500\tploidy=1").unwrap();
+        writeln!(file, "chrY\t300\tploidy=0").unwrap();
+        writeln!(file, "chrUn\t100\texclude").unwrap();
+        writeln!(file, "chrM\t20\t/gbdb/hg38/hg38.2bit").unwrap();
+
+        let genome = Genome::from_file(file.path()).unwrap();
+        assert_eq!(genome.total_size(), 1920);
+        assert_eq!(genome.effective_size("chr1"), Some(800));
+        assert_eq!(genome.ploidy("chrX"), 1);
+        assert_eq!(genome.ploidy("chrM"), DEFAULT_PLOIDY);
+        assert!(!genome.in_totals("chrY"));
+        assert!(!genome.in_totals("chrUn"));
+        assert_eq!(genome.effective_total_size(), 800 + 500 + 20);
+
+        // Unusable bases come out of the depth-0 bin
+        assert_eq!(genome.genome_wide_bases("chr1", 0, 900), 700);
+        assert_eq!(genome.genome_wide_bases("chr1", 3, 100), 100);
+        assert_eq!(genome.genome_wide_bases("chrUn", 1, 100), 0);
+
+        for bad in [
+            "chr1\t10\tploidy=x",
+            "chr1\t10\teffective=11",
+            "chr1\t10\tcn=2",
+        ] {
+            let mut file = NamedTempFile::new().unwrap();
+            writeln!(file, "{}", bad).unwrap();
+            assert!(matches!(
+                Genome::from_file(file.path()),
+                Err(BedError::Parse { line: 1, .. })
+            ));
+        }
+    }
+
     #[test]
     fn test_genome_bounds() {
         let mut genome = Genome::new();
@@ -135,4 +594,98 @@ mod tests {
         assert!(genome.has_chrom("chr1"));
         assert!(!genome.has_chrom("chr2"));
     }
+
+    fn mask_genome() -> Genome {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        genome.insert("chr2".to_string(), 500);
+        genome
+    }
+
+    #[test]
+    fn test_mask_from_intervals_merges() {
+        let mask = GenomeMask::from_intervals(&[
+            Interval::new("chr1", 300, 400),
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 150, 300),
+        ]);
+
+        assert_eq!(mask.regions("chr1"), &[(100, 400)]);
+        assert_eq!(mask.total_len(), 300);
+    }
+
+    #[test]
+    fn test_mask_algebra() {
+        let genome = mask_genome();
+        let include = GenomeMask::from_intervals(&[
+            Interval::new("chr1", 100, 600),
+            Interval::new("chr3", 0, 10),
+        ]);
+        let exclude = GenomeMask::from_intervals(&[
+            Interval::new("chr1", 200, 300),
+            Interval::new("chr1", 550, 700),
+        ]);
+
+        let mask = GenomeMask::from_genome(&genome)
+            .intersect(&include)
+            .subtract(&exclude);
+
+        assert_eq!(mask.regions("chr1"), &[(100, 200), (300, 550)]);
+        assert!(mask.regions("chr2").is_empty());
+        assert!(mask.regions("chr3").is_empty());
+
+        let joined = mask.union(&GenomeMask::from_intervals(&[Interval::new(
+            "chr1", 200, 300,
+        )]));
+        assert_eq!(joined.regions("chr1"), &[(100, 550)]);
+    }
+
+    #[test]
+    fn test_mask_contains_and_clip() {
+        let mask = GenomeMask::from_intervals(&[
+            Interval::new("chr1", 100, 200),
+            Interval::new("chr1", 300, 400),
+        ]);
+
+        assert!(mask.contains("chr1", 120, 180));
+        assert!(!mask.contains("chr1", 150, 350));
+        assert!(!mask.contains("chr2", 120, 180));
+        assert!(mask.overlaps("chr1", 150, 350));
+        assert!(!mask.overlaps("chr1", 200, 300));
+
+        let pieces: Vec<_> = mask.clip("chr1", 150, 350).collect();
+        assert_eq!(pieces, vec![(150, 200), (300, 350)]);
+    }
+
+    #[test]
+    fn test_clip_bed() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        let input = "track name=x\n\
+                     chr1\t100\t200\ta\n\
+                     chr1\t900\t1200\tb\t5\t+\r\n\
+                     chr1\t1000\t1100\tc\n\
+                     chr2\t0\t5000\td\n\
+                     chr1\t950\t1001";
+        let mut out = Vec::new();
+        let stats = genome.clip_bed(input.as_bytes(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "track name=x\n\
+             chr1\t100\t200\ta\n\
+             chr1\t900\t1000\tb\t5\t+\r\n\
+             chr2\t0\t5000\td\n\
+             chr1\t950\t1000"
+        );
+        assert_eq!(
+            stats,
+            ClipStats {
+                records: 5,
+                clipped: 2,
+                dropped: 1
+            }
+        );
+        assert_eq!(stats.to_string(), "2 clipped, 1 dropped of 5 records");
+    }
 }
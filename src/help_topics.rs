@@ -0,0 +1,388 @@
+//! Long-form help topics for `grit help <topic>`.
+//!
+//! Each [`Topic`] explains a concept that cuts across subcommands (sort
+//! order, the streaming memory model, bedtools compatibility, genome files)
+//! and carries runnable examples. An example is a `$ grit ...` line followed
+//! by the exact output it prints, ending at a blank line. The examples run
+//! on the small files in [`EXAMPLE_FILES`], which `grit help <topic>` prints
+//! after the text so every example can be reproduced as shown; the test
+//! suite runs them all and compares the output.
+
+use std::fmt;
+
+/// Files the topic examples read, as `(name, contents)`.
+pub const EXAMPLE_FILES: &[(&str, &str)] = &[
+    (
+        "a.bed",
+        "chr1\t100\t200\ta1\n\
+         chr1\t150\t300\ta2\n\
+         chr1\t500\t600\ta3\n\
+         chr2\t100\t200\ta4\n",
+    ),
+    (
+        "b.bed",
+        "chr1\t180\t250\tb1\n\
+         chr1\t550\t700\tb2\n\
+         chr2\t300\t400\tb3\n",
+    ),
+    (
+        "unsorted.bed",
+        "chr2\t100\t200\n\
+         chr10\t5\t10\n\
+         chr1\t500\t600\n\
+         chr1\t100\t200\n",
+    ),
+    (
+        "genome.txt",
+        "chr1\t1000\n\
+         chr2\t800\n\
+         chr10\t500\texclude\n",
+    ),
+];
+
+/// A help topic.
+#[derive(Debug, Clone, Copy)]
+pub struct Topic {
+    pub name: &'static str,
+    /// One line for the topic list.
+    pub summary: &'static str,
+    pub body: &'static str,
+    /// Names of the [`EXAMPLE_FILES`] the examples read.
+    pub files: &'static [&'static str],
+}
+
+/// An example taken from a topic body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// Arguments after `grit`, split on whitespace.
+    pub args: Vec<String>,
+    /// Expected stdout, one `\n`-terminated line per output line.
+    pub stdout: String,
+}
+
+impl Topic {
+    /// The examples in the body, in order.
+    pub fn examples(&self) -> Vec<Example> {
+        let mut examples: Vec<Example> = Vec::new();
+        let mut current: Option<Example> = None;
+        for line in self.body.lines() {
+            let line = line.strip_prefix("    ").unwrap_or(line);
+            if let Some(command) = line.strip_prefix("$ grit ") {
+                examples.extend(current.take());
+                current = Some(Example {
+                    args: command.split_whitespace().map(String::from).collect(),
+                    stdout: String::new(),
+                });
+            } else if line.trim().is_empty() {
+                examples.extend(current.take());
+            } else if let Some(example) = current.as_mut() {
+                example.stdout.push_str(line);
+                example.stdout.push('\n');
+            }
+        }
+        examples.extend(current);
+        examples
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.body)?;
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        writeln!(f, "\nEXAMPLE FILES")?;
+        for (name, contents) in EXAMPLE_FILES {
+            if !self.files.contains(name) {
+                continue;
+            }
+            writeln!(f, "\n  {}:", name)?;
+            for line in contents.lines() {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All topics, in the order `grit help` lists them.
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        name: "sorting",
+        summary: "Sort order grit expects, and how to get it",
+        files: &["a.bed", "b.bed", "unsorted.bed", "genome.txt"],
+        body: "\
+SORTING
+
+Streaming commands sweep both inputs once, from the first base of each
+chromosome to the last, so they need records grouped by chromosome and
+ordered by start. By default chromosomes are compared as plain strings
+(chr1 < chr10 < chr2), which is what `grit sort` and `sort -k1,1 -k2,2n`
+produce:
+
+    $ grit sort -i unsorted.bed
+    chr1\t100\t200
+    chr1\t500\t600
+    chr10\t5\t10
+    chr2\t100\t200
+
+With -g/--genome, chromosomes follow the order of the genome file instead,
+and commands check the input against that order:
+
+    $ grit sort -i unsorted.bed -g genome.txt
+    chr1\t100\t200
+    chr1\t500\t600
+    chr2\t100\t200
+    chr10\t5\t10
+
+Unsorted input is an error rather than a wrong answer: merge, intersect
+and the other sweeping commands read each input once up front to check
+its order, and name the first record out of place. Three flags change
+this:
+
+  --assume-sorted   Skip the check. Use it for files grit sorted, or in
+                    pipelines where the check would read stdin twice.
+  --allow-unsorted  Load and sort the input in memory first.
+  -g/--genome       Check (and sort) by genome-file chromosome order.
+
+    $ grit merge -i a.bed --assume-sorted
+    chr1\t100\t300
+    chr1\t500\t600
+    chr2\t100\t200
+
+    $ grit intersect -a unsorted.bed -b b.bed --allow-unsorted
+    chr1\t180\t200
+    chr1\t550\t600
+
+See also: grit sort --help, grit help streaming
+",
+    },
+    Topic {
+        name: "streaming",
+        summary: "Memory model of --streaming and the sweep-line commands",
+        files: &["a.bed", "b.bed"],
+        body: "\
+STREAMING
+
+grit has two ways to run most two-file commands:
+
+  in memory (default)  Load both inputs, index B and process A in
+                       parallel. Memory grows with the input: O(n + m).
+  --streaming          Sweep both sorted inputs once, keeping only the
+                       B records that overlap the current A record.
+                       Memory is O(k), where k is the largest number of
+                       records overlapping one position (usually < 100),
+                       whatever the file sizes.
+
+Both produce the same records. Streaming needs sorted input (see
+`grit help sorting`) and reads stdin as it arrives, so it is the mode for
+pipelines and for files larger than RAM:
+
+    $ grit intersect -a a.bed -b b.bed
+    chr1\t180\t200\ta1
+    chr1\t180\t250\ta2
+    chr1\t550\t600\ta3
+
+    $ grit intersect -a a.bed -b b.bed --streaming --assume-sorted
+    chr1\t180\t200\ta1
+    chr1\t180\t250\ta2
+    chr1\t550\t600\ta3
+
+intersect, subtract, closest, genomecov and multiinter take --streaming.
+merge, coverage, window and complement sweep sorted input by default.
+All of them take --assume-sorted to skip the up-front order check.
+The global --explain flag prints the strategy a command line would use,
+its buffers and a memory estimate, without running it.
+
+Only the current A record and the active B records are held, so a
+single very long B interval (a whole-chromosome mask, say) stays active
+until the sweep passes its end. That is the case where k, and memory,
+grow.
+
+See also: docs/STREAMING_MODEL.md, grit help sorting
+",
+    },
+    Topic {
+        name: "bedtools",
+        summary: "bedtools compatibility: command names, flags and differences",
+        files: &["a.bed", "b.bed", "genome.txt"],
+        body: "\
+BEDTOOLS COMPATIBILITY
+
+grit commands match their bedtools counterparts record for record on
+sorted input. The legacy tool names are accepted as aliases:
+
+  bedtools           grit          alias
+  sort               sort          sortBed
+  merge              merge         mergeBed
+  intersect          intersect     intersectBed
+  subtract           subtract      subtractBed
+  closest            closest       closestBed
+  window             window        windowBed
+  coverage           coverage      coverageBed
+  shuffle            shuffle       shuffleBed
+  slop               slop          slopBed
+  complement         complement    complementBed
+  genomecov          genomecov     genomeCoverageBed
+  multiinter         multiinter    multiIntersectBed
+  jaccard            jaccard
+
+    $ grit intersectBed -a a.bed -b b.bed -v
+    chr2\t100\t200\ta4
+
+Prefixing a bedtools command line with `grit` runs it unchanged:
+`grit bedtools <cmd>` rewrites bedtools' single-dash options (-sorted,
+-wa, -wo, -loj, -bg, -hist, ...) to grit's. Plain `grit <cmd>` accepts the
+same spellings and prints a warning listing the translations.
+
+    $ grit bedtools intersect -a a.bed -b b.bed -wa -sorted
+    chr1\t100\t200\ta1
+    chr1\t150\t300\ta2
+    chr1\t500\t600\ta3
+
+    $ grit bedtools genomecov -i a.bed -g genome.txt -bg
+    chr1\t100\t150\t1
+    chr1\t150\t200\t2
+    chr1\t200\t300\t1
+    chr1\t500\t600\t1
+    chr2\t100\t200\t1
+
+Differences from bedtools:
+
+  - Options grit does not implement (-split, -mdb) are rejected by name
+    instead of ignored, with exit status 2.
+  - Unsorted input to a sweeping command is an error, not silently wrong
+    output. -sorted becomes --assume-sorted.
+  - Zero-length intervals (start == end) hold no bases. The global
+    --bedtools-compatible flag treats them as 1bp, as bedtools does.
+
+See also: docs/migration.md
+",
+    },
+    Topic {
+        name: "genome",
+        summary: "Genome files: format, metadata columns and chromosome order",
+        files: &["a.bed", "genome.txt"],
+        body: "\
+GENOME FILES
+
+A genome file lists chromosome sizes, one `chrom<TAB>size` line per
+chromosome, like `samtools faidx` .fai files (whose extra columns are
+ignored) or UCSC chromInfo dumps. It is required by commands that need
+chromosome ends (slop, complement, genomecov, shuffle) and optional for
+the others, where -g/--genome sets the chromosome order (see
+`grit help sorting`).
+
+Columns after the size hold per-chromosome metadata for genome-wide
+statistics:
+
+  ploidy=N      Copy number of the chromosome (default 2; 0 = absent).
+  effective=N   Usable (e.g. mappable) bases, if fewer than the size.
+  exclude       Leave the chromosome out of genome-wide totals and
+                uniform sampling.
+
+The genome summary of genomecov counts chr1 and chr2 only, because
+chr10 is excluded:
+
+    $ grit genomecov -i a.bed -g genome.txt
+    chr1\t0\t700\t1000\t0.7
+    chr1\t1\t250\t1000\t0.25
+    chr1\t2\t50\t1000\t0.05
+    chr2\t0\t700\t800\t0.875
+    chr2\t1\t100\t800\t0.125
+    chr10\t0\t500\t500\t1
+    genome\t0\t1400\t1800\t0.777778
+    genome\t1\t350\t1800\t0.194444
+    genome\t2\t50\t1800\t0.0277778
+
+Chromosome ends clip the output of commands that grow or invert
+intervals:
+
+    $ grit complement -i a.bed -g genome.txt
+    chr1\t0\t100
+    chr1\t300\t500
+    chr1\t600\t1000
+    chr2\t0\t100
+    chr2\t200\t800
+    chr10\t0\t500
+
+    $ grit slop -i a.bed -g genome.txt -b 450
+    chr1\t0\t650\ta1
+    chr1\t0\t750\ta2
+    chr1\t50\t1000\ta3
+    chr2\t0\t650\ta4
+
+See also: grit slop --help, grit genomecov --help
+",
+    },
+    Topic {
+        name: "reproducibility",
+        summary: "Seeds and the stability of random output across versions",
+        files: &["a.bed", "genome.txt"],
+        body: "\
+REPRODUCIBILITY
+
+Commands that sample (shuffle, generate, enrich --shuffles) take --seed N,
+default 42, and draw every random number from one generator seeded with
+it. The generator and the way its numbers become positions are part of
+grit, not of a dependency, so a run can be repeated exactly from the
+command line alone:
+
+  - The same seed, input and options give byte-identical output on every
+    platform, with any thread count, from any build of the same release.
+  - Output for a given seed changes only in releases whose CHANGELOG says
+    so. Record the grit version (`grit --version`, or the provenance
+    header written by --provenance) next to the seed in a methods section.
+
+These controls are the same on every machine:
+
+    $ grit shuffle -i a.bed -g genome.txt --seed 7
+    chr1\t155\t255\ta1
+    chr2\t278\t428\ta2
+    chr2\t326\t426\ta3
+    chr2\t231\t331\ta4
+
+Options that change what is drawn also change the output, even with the
+same seed. --chrom keeps each record on its own chromosome:
+
+    $ grit shuffle -i a.bed -g genome.txt --seed 7 --chrom
+    chr1\t49\t149\ta1
+    chr1\t146\t296\ta2
+    chr1\t646\t746\ta3
+    chr2\t299\t399\ta4
+
+To draw several independent replicates, use a different seed for each
+rather than one run with more records.
+
+See also: grit shuffle --help, grit generate --help, grit enrich --help
+",
+    },
+];
+
+/// The topic called `name`.
+pub fn find(name: &str) -> Option<&'static Topic> {
+    TOPICS.iter().find(|topic| topic.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_examples() {
+        let sorting = find("sorting").unwrap();
+        let examples = sorting.examples();
+        assert_eq!(examples.len(), 4);
+        assert_eq!(examples[0].args, ["sort", "-i", "unsorted.bed"]);
+        assert_eq!(examples[0].stdout.lines().count(), 4);
+        assert!(examples[2].stdout.starts_with("chr1\t100\t300\n"));
+        assert!(find("missing").is_none());
+
+        // Every file a topic names exists
+        for topic in TOPICS {
+            for file in topic.files {
+                assert!(EXAMPLE_FILES.iter().any(|(name, _)| name == file));
+            }
+        }
+    }
+}
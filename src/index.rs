@@ -1,38 +1,60 @@
 //! Interval indexing for fast overlap queries.
 
 use crate::interval::{BedRecord, Interval};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// An indexed collection of intervals organized by chromosome.
 /// Uses a sorted list with binary search for efficient queries.
-pub struct IntervalIndex {
-    intervals_by_chrom: HashMap<String, Vec<(Interval, usize)>>,
-    intervals: Vec<Interval>,
+///
+/// The intervals may be borrowed, in which case the index only adds the
+/// per-chromosome position lists on top of the caller's data.
+pub struct IntervalIndex<'a> {
+    intervals_by_chrom: HashMap<String, ChromPositions>,
+    intervals: Cow<'a, [Interval]>,
 }
 
-impl IntervalIndex {
+/// Intervals of one chromosome.
+#[derive(Default)]
+struct ChromPositions {
+    /// Positions into `intervals`, sorted by start then end.
+    positions: Vec<usize>,
+    /// Length of the longest interval. Ends are not sorted when intervals
+    /// nest, so queries bound the search by start minus this instead.
+    max_len: u64,
+}
+
+impl<'a> IntervalIndex<'a> {
     /// Create a new empty index.
     pub fn new() -> Self {
         Self {
             intervals_by_chrom: HashMap::new(),
-            intervals: Vec::new(),
+            intervals: Cow::Owned(Vec::new()),
         }
     }
 
     /// Build an index from a collection of intervals.
-    pub fn from_intervals(intervals: Vec<Interval>) -> Self {
-        let mut by_chrom: HashMap<String, Vec<(Interval, usize)>> = HashMap::new();
+    ///
+    /// Accepts an owned `Vec` or a borrowed slice; borrowed input is not copied.
+    pub fn from_intervals(intervals: impl Into<Cow<'a, [Interval]>>) -> Self {
+        let intervals = intervals.into();
+        let mut by_chrom: HashMap<String, ChromPositions> = HashMap::new();
 
         for (idx, interval) in intervals.iter().enumerate() {
-            by_chrom
-                .entry(interval.chrom.clone())
-                .or_default()
-                .push((interval.clone(), idx));
+            let chrom = match by_chrom.get_mut(interval.chrom.as_str()) {
+                Some(chrom) => chrom,
+                None => by_chrom.entry(interval.chrom.clone()).or_default(),
+            };
+            chrom.positions.push(idx);
+            chrom.max_len = chrom.max_len.max(interval.len());
         }
 
         // Sort each chromosome's intervals by start position
-        for chrom_intervals in by_chrom.values_mut() {
-            chrom_intervals.sort_by(|a, b| a.0.start.cmp(&b.0.start).then(a.0.end.cmp(&b.0.end)));
+        for ChromPositions { positions, .. } in by_chrom.values_mut() {
+            positions.sort_by(|&a, &b| {
+                let (a, b) = (&intervals[a], &intervals[b]);
+                a.start.cmp(&b.start).then(a.end.cmp(&b.end))
+            });
         }
 
         Self {
@@ -47,92 +69,47 @@ impl IntervalIndex {
         Self::from_intervals(intervals)
     }
 
+    /// Positions of intervals overlapping the query, in start order.
+    fn overlapping<'q>(&'q self, query: &'q Interval) -> impl Iterator<Item = usize> + 'q {
+        let (positions, max_len) = self
+            .intervals_by_chrom
+            .get(&query.chrom)
+            .map_or((&[][..], 0), |c| (c.positions.as_slice(), c.max_len));
+
+        // Binary search to find starting point: nothing starting before
+        // this can reach the query
+        let start_idx = positions.partition_point(|&idx| {
+            self.intervals[idx].start.saturating_add(max_len) <= query.start
+        });
+
+        // Scan forward to find all overlaps
+        positions[start_idx..]
+            .iter()
+            .copied()
+            .take_while(move |&idx| self.intervals[idx].start < query.end)
+            .filter(move |&idx| query.overlaps(&self.intervals[idx]))
+    }
+
     /// Find all intervals overlapping a query interval.
     pub fn find_overlaps(&self, query: &Interval) -> Vec<&Interval> {
-        let mut results = Vec::new();
-
-        if let Some(chrom_intervals) = self.intervals_by_chrom.get(&query.chrom) {
-            // Binary search to find starting point
-            let start_idx = chrom_intervals
-                .binary_search_by(|(i, _)| {
-                    if i.end <= query.start {
-                        std::cmp::Ordering::Less
-                    } else {
-                        std::cmp::Ordering::Greater
-                    }
-                })
-                .unwrap_or_else(|i| i);
-
-            // Scan forward to find all overlaps
-            for (interval, idx) in chrom_intervals.iter().skip(start_idx) {
-                if interval.start >= query.end {
-                    break;
-                }
-                if query.overlaps(interval) {
-                    results.push(&self.intervals[*idx]);
-                }
-            }
-        }
-
-        results
+        self.overlapping(query)
+            .map(|idx| &self.intervals[idx])
+            .collect()
     }
 
     /// Find all intervals overlapping a query, returning their indices.
     pub fn find_overlap_indices(&self, query: &Interval) -> Vec<usize> {
-        let mut results = Vec::new();
-
-        if let Some(chrom_intervals) = self.intervals_by_chrom.get(&query.chrom) {
-            let start_idx = chrom_intervals
-                .binary_search_by(|(i, _)| {
-                    if i.end <= query.start {
-                        std::cmp::Ordering::Less
-                    } else {
-                        std::cmp::Ordering::Greater
-                    }
-                })
-                .unwrap_or_else(|i| i);
-
-            for (interval, idx) in chrom_intervals.iter().skip(start_idx) {
-                if interval.start >= query.end {
-                    break;
-                }
-                if query.overlaps(interval) {
-                    results.push(*idx);
-                }
-            }
-        }
-
-        results
+        self.overlapping(query).collect()
     }
 
     /// Count overlapping intervals.
     pub fn count_overlaps(&self, query: &Interval) -> usize {
-        self.find_overlap_indices(query).len()
+        self.overlapping(query).count()
     }
 
     /// Check if any interval overlaps the query.
     pub fn has_overlap(&self, query: &Interval) -> bool {
-        if let Some(chrom_intervals) = self.intervals_by_chrom.get(&query.chrom) {
-            let start_idx = chrom_intervals
-                .binary_search_by(|(i, _)| {
-                    if i.end <= query.start {
-                        std::cmp::Ordering::Less
-                    } else {
-                        std::cmp::Ordering::Greater
-                    }
-                })
-                .unwrap_or_else(|i| i);
-
-            for (interval, _) in chrom_intervals.iter().skip(start_idx) {
-                if interval.start >= query.end {
-                    break;
-                }
-                if query.overlaps(interval) {
-                    return true;
-                }
-            }
-        }
-        false
+        self.overlapping(query).next().is_some()
     }
 
     /// Get all chromosomes in the index.
@@ -161,7 +138,7 @@ impl IntervalIndex {
     }
 }
 
-impl Default for IntervalIndex {
+impl Default for IntervalIndex<'_> {
     fn default() -> Self {
         Self::new()
     }
@@ -266,6 +243,23 @@ mod tests {
         assert_eq!(overlaps.len(), 2);
     }
 
+    #[test]
+    fn test_find_overlaps_nested() {
+        let intervals = vec![
+            Interval::new("chr1", 0, 1000),
+            Interval::new("chr1", 10, 20),
+            Interval::new("chr1", 30, 40),
+        ];
+        let index = IntervalIndex::from_intervals(intervals);
+
+        let query = Interval::new("chr1", 500, 501);
+        assert_eq!(
+            index.find_overlaps(&query),
+            vec![&Interval::new("chr1", 0, 1000)]
+        );
+        assert_eq!(index.count_overlaps(&Interval::new("chr1", 15, 35)), 3);
+    }
+
     #[test]
     fn test_count_overlaps() {
         let intervals = sample_intervals();
@@ -294,6 +288,16 @@ mod tests {
         assert_eq!(index.count_overlaps(&query), 0);
     }
 
+    #[test]
+    fn test_borrowed_index() {
+        let intervals = sample_intervals();
+        let index = IntervalIndex::from_intervals(intervals.as_slice());
+
+        let query = Interval::new("chr1", 175, 225);
+        assert_eq!(index.find_overlap_indices(&query), vec![0, 1]);
+        assert_eq!(index.intervals().as_ptr(), intervals.as_ptr());
+    }
+
     #[test]
     fn test_simple_index() {
         let intervals = sample_intervals();
@@ -0,0 +1,507 @@
+//! Binary interchange of interval streams between grit processes.
+//!
+//! With `--of binary` a command writes its output as length-prefixed frames
+//! instead of text lines, and with `--if binary` it reads such frames from
+//! stdin, so the coordinates passed along a shell pipeline are never turned
+//! into decimal text and back. `grit cat` converts a stream to BED.
+//!
+//! # Format
+//!
+//! ```text
+//! stream := MAGIC frame*
+//! MAGIC  := "GRITBIN" version:u8
+//! frame  := kind:u8 length:u32 payload[length]
+//! ```
+//!
+//! Integers are little-endian. Frame kinds:
+//!
+//! - `1` chromosome: `id:u32` followed by the name. Later records refer to
+//!   the chromosome by id; a new definition of an id replaces the old one.
+//! - `2` record: `chrom:u32 start:u64 end:u64` followed by the columns after
+//!   `end`, tab-separated, without a newline.
+//! - `3` line: a line that is not a record (comments, headers), verbatim.
+//!
+//! Unknown frame kinds are skipped. `MAGIC` may appear again between frames,
+//! so binary streams can be concatenated.
+//!
+//! # Compatibility
+//!
+//! The current format version is [`BINARY_FORMAT_VERSION`]. Additions that
+//! older readers can skip come as new frame kinds and keep the version;
+//! only a change to existing frames bumps it. Readers decode every version
+//! up to their own and reject streams from a newer grit with an error
+//! naming both versions.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, StdinLock, StdoutLock, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Format version written in the last byte of [`MAGIC`].
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+
+/// First bytes of every binary stream.
+pub const MAGIC: &[u8; 8] = b"GRITBIN\x01";
+
+/// [`MAGIC`] without its version byte.
+const MAGIC_PREFIX: &[u8; 7] = b"GRITBIN";
+
+const FRAME_CHROM: u8 = 1;
+const FRAME_RECORD: u8 = 2;
+const FRAME_LINE: u8 = 3;
+
+/// Frames are collected up to this size before being written.
+const OUTPUT_BUFFER: usize = 256 * 1024;
+
+static BINARY_INPUT: AtomicBool = AtomicBool::new(false);
+static BINARY_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Encoding of a stream between processes, selected by `--if`/`--of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamEncoding {
+    /// Text lines.
+    #[default]
+    Bed,
+    /// Length-prefixed frames.
+    Binary,
+}
+
+impl StreamEncoding {
+    /// Parse an encoding name (`bed`, `binary`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bed" => Some(Self::Bed),
+            "binary" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Set how stdin is decoded.
+pub fn set_input_encoding(encoding: StreamEncoding) {
+    BINARY_INPUT.store(encoding == StreamEncoding::Binary, Ordering::Relaxed);
+}
+
+/// Set how stdout is encoded.
+pub fn set_output_encoding(encoding: StreamEncoding) {
+    BINARY_OUTPUT.store(encoding == StreamEncoding::Binary, Ordering::Relaxed);
+}
+
+/// Whether `data` starts a binary stream, of any version.
+pub fn is_binary(data: &[u8]) -> bool {
+    data.starts_with(MAGIC_PREFIX)
+}
+
+/// Stdout in the configured output encoding.
+pub fn encoded_stdout() -> FrameWriter<StdoutLock<'static>> {
+    FrameWriter::new(io::stdout().lock(), BINARY_OUTPUT.load(Ordering::Relaxed))
+}
+
+/// Stdin as text lines, decoded from frames with `--if binary`.
+pub fn stdin_reader() -> InputReader<StdinLock<'static>> {
+    InputReader::new(io::stdin().lock(), BINARY_INPUT.load(Ordering::Relaxed))
+}
+
+/// Writer that encodes the text lines written to it as frames.
+///
+/// When not encoding, writes go straight to the inner writer.
+pub struct FrameWriter<W: Write> {
+    inner: W,
+    binary: bool,
+    /// Unfinished line
+    partial: Vec<u8>,
+    /// Encoded frames not yet written
+    out: Vec<u8>,
+    chroms: HashMap<Vec<u8>, u32>,
+    started: bool,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W, binary: bool) -> Self {
+        Self {
+            inner,
+            binary,
+            partial: Vec::new(),
+            out: Vec::new(),
+            chroms: HashMap::new(),
+            started: false,
+        }
+    }
+
+    fn frame_header(&mut self, kind: u8, len: usize) {
+        if !self.started {
+            self.out.extend_from_slice(MAGIC);
+            self.started = true;
+        }
+        self.out.push(kind);
+        self.out.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+
+    /// Encode one line, without its newline.
+    fn encode_line(&mut self, line: &[u8]) {
+        match parse_record(line) {
+            Some((chrom, start, end, rest)) => {
+                let id = match self.chroms.get(chrom) {
+                    Some(&id) => id,
+                    None => {
+                        let id = self.chroms.len() as u32;
+                        self.chroms.insert(chrom.to_vec(), id);
+                        self.frame_header(FRAME_CHROM, 4 + chrom.len());
+                        self.out.extend_from_slice(&id.to_le_bytes());
+                        self.out.extend_from_slice(chrom);
+                        id
+                    }
+                };
+                self.frame_header(FRAME_RECORD, 20 + rest.len());
+                self.out.extend_from_slice(&id.to_le_bytes());
+                self.out.extend_from_slice(&start.to_le_bytes());
+                self.out.extend_from_slice(&end.to_le_bytes());
+                self.out.extend_from_slice(rest);
+            }
+            None => {
+                self.frame_header(FRAME_LINE, line.len());
+                self.out.extend_from_slice(line);
+            }
+        }
+    }
+
+    fn write_out(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.out)?;
+        self.out.clear();
+        Ok(())
+    }
+}
+
+/// Split a line into chromosome, start, end and the columns after `end`.
+///
+/// Lines whose coordinates would not be written back byte for byte (leading
+/// zeros, a `+` sign, trailing `\r`) are not records, so decoding reproduces
+/// the input exactly.
+fn parse_record(line: &[u8]) -> Option<(&[u8], u64, u64, &[u8])> {
+    let mut fields = line.splitn(4, |&b| b == b'\t');
+    let chrom = fields.next()?;
+    let start = parse_coordinate(fields.next()?)?;
+    let end = parse_coordinate(fields.next()?)?;
+    let rest = fields.next();
+    if chrom.is_empty() || chrom[0] == b'#' || rest == Some(b"") {
+        return None;
+    }
+    Some((chrom, start, end, rest.unwrap_or_default()))
+}
+
+fn parse_coordinate(field: &[u8]) -> Option<u64> {
+    let canonical = !field.is_empty()
+        && field.iter().all(u8::is_ascii_digit)
+        && (field.len() == 1 || field[0] != b'0');
+    if !canonical {
+        return None;
+    }
+    std::str::from_utf8(field).ok()?.parse().ok()
+}
+
+impl<W: Write> Write for FrameWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.binary {
+            return self.inner.write(buf);
+        }
+
+        let mut rest = buf;
+        while let Some(i) = memchr::memchr(b'\n', rest) {
+            if self.partial.is_empty() {
+                self.encode_line(&rest[..i]);
+            } else {
+                let mut line = std::mem::take(&mut self.partial);
+                line.extend_from_slice(&rest[..i]);
+                self.encode_line(&line);
+            }
+            rest = &rest[i + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+        if self.out.len() >= OUTPUT_BUFFER {
+            self.write_out()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.encode_line(&line);
+        }
+        self.write_out()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for FrameWriter<W> {
+    fn drop(&mut self) {
+        if self.binary {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// Reader that decodes frames back into text lines.
+pub struct FrameReader<R: BufRead> {
+    inner: R,
+    /// Decoded lines ready to be read, from `pos`
+    out: Vec<u8>,
+    pos: usize,
+    payload: Vec<u8>,
+    chroms: HashMap<u32, Vec<u8>>,
+    started: bool,
+}
+
+impl<R: BufRead> FrameReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            out: Vec::new(),
+            pos: 0,
+            payload: Vec::new(),
+            chroms: HashMap::new(),
+            started: false,
+        }
+    }
+
+    /// Decode frames until a line is ready or the input ends.
+    fn refill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        while self.out.is_empty() {
+            let Some(kind) = self.next_kind()? else {
+                return Ok(());
+            };
+            let mut len = [0u8; 4];
+            self.inner.read_exact(&mut len).map_err(truncated)?;
+            self.payload.resize(u32::from_le_bytes(len) as usize, 0);
+            self.inner
+                .read_exact(&mut self.payload)
+                .map_err(truncated)?;
+            self.decode(kind)?;
+        }
+        Ok(())
+    }
+
+    /// Kind byte of the next frame, skipping stream headers.
+    fn next_kind(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            let data = self.inner.fill_buf()?;
+            if data.is_empty() {
+                return Ok(None);
+            }
+            if data[0] == MAGIC[0] {
+                let mut magic = [0u8; 8];
+                self.inner.read_exact(&mut magic).map_err(truncated)?;
+                if !magic.starts_with(MAGIC_PREFIX) || magic[7] == 0 {
+                    return Err(invalid_data("corrupt binary stream header"));
+                }
+                if magic[7] > BINARY_FORMAT_VERSION {
+                    return Err(invalid_data(&format!(
+                        "binary stream version {} was written by a newer grit (this one reads \
+                         versions up to {}); upgrade grit or pass the stream as BED",
+                        magic[7], BINARY_FORMAT_VERSION
+                    )));
+                }
+                self.started = true;
+                continue;
+            }
+            if !self.started {
+                return Err(invalid_data(
+                    "input is not a grit binary stream (expected the output of --of binary)",
+                ));
+            }
+            let kind = data[0];
+            self.inner.consume(1);
+            return Ok(Some(kind));
+        }
+    }
+
+    fn decode(&mut self, kind: u8) -> io::Result<()> {
+        let payload = &self.payload;
+        match kind {
+            FRAME_CHROM if payload.len() >= 4 => {
+                let id = u32::from_le_bytes(payload[..4].try_into().unwrap());
+                self.chroms.insert(id, payload[4..].to_vec());
+            }
+            FRAME_RECORD if payload.len() >= 20 => {
+                let id = u32::from_le_bytes(payload[..4].try_into().unwrap());
+                let start = u64::from_le_bytes(payload[4..12].try_into().unwrap());
+                let end = u64::from_le_bytes(payload[12..20].try_into().unwrap());
+                let chrom = self.chroms.get(&id).ok_or_else(|| {
+                    invalid_data(&format!("record refers to undefined chromosome id {}", id))
+                })?;
+                let mut itoa_buf = itoa::Buffer::new();
+                self.out.extend_from_slice(chrom);
+                self.out.push(b'\t');
+                self.out
+                    .extend_from_slice(itoa_buf.format(start).as_bytes());
+                self.out.push(b'\t');
+                self.out.extend_from_slice(itoa_buf.format(end).as_bytes());
+                if payload.len() > 20 {
+                    self.out.push(b'\t');
+                    self.out.extend_from_slice(&payload[20..]);
+                }
+                self.out.push(b'\n');
+            }
+            FRAME_LINE => {
+                self.out.extend_from_slice(payload);
+                self.out.push(b'\n');
+            }
+            FRAME_CHROM | FRAME_RECORD => {
+                return Err(invalid_data("truncated binary frame"));
+            }
+            // Frames from a newer writer
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Report input ending inside a frame as a truncated stream.
+fn truncated(e: io::Error) -> io::Error {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        invalid_data("truncated binary frame")
+    } else {
+        e
+    }
+}
+
+impl<R: BufRead> Read for FrameReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for FrameReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.out.len() {
+            self.refill()?;
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.out.len());
+    }
+}
+
+/// Text input that may have arrived as frames.
+pub enum InputReader<R: BufRead> {
+    Text(R),
+    Binary(FrameReader<R>),
+}
+
+impl<R: BufRead> InputReader<R> {
+    pub fn new(inner: R, binary: bool) -> Self {
+        if binary {
+            Self::Binary(FrameReader::new(inner))
+        } else {
+            Self::Text(inner)
+        }
+    }
+
+    /// Decode frames only if the input starts with [`MAGIC`].
+    pub fn detect(mut inner: R) -> io::Result<Self> {
+        let binary = is_binary(inner.fill_buf()?);
+        Ok(Self::new(inner, binary))
+    }
+}
+
+impl<R: BufRead> Read for InputReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Text(r) => r.read(buf),
+            Self::Binary(r) => r.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for InputReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Text(r) => r.fill_buf(),
+            Self::Binary(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Text(r) => r.consume(amt),
+            Self::Binary(r) => r.consume(amt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(text: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut writer = FrameWriter::new(&mut out, true);
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        out
+    }
+
+    fn decode(data: &[u8]) -> io::Result<String> {
+        let mut text = String::new();
+        FrameReader::new(data).read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "#comment\n\
+                    chr1\t100\t200\n\
+                    chr1\t150\t250\tname\t5\t+\n\
+                    chr2\t007\t10\n\
+                    chr2\t0\t10\r\n\
+                    chr3\t1\t2\t\n\
+                    chr10\t5\t6\tx";
+        let data = encode(text);
+        assert!(is_binary(&data));
+        assert_eq!(decode(&data).unwrap(), format!("{}\n", text));
+
+        // Chromosome names are written once per stream
+        let data = encode("chr1\t1\t2\nchr1\t3\t4\n");
+        assert_eq!(data.windows(4).filter(|w| w == b"chr1").count(), 1);
+
+        // Concatenated streams decode in sequence
+        let mut both = encode("chrA\t1\t2\n");
+        both.extend(encode("chrB\t3\t4\n"));
+        assert_eq!(decode(&both).unwrap(), "chrA\t1\t2\nchrB\t3\t4\n");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(decode(b"chr1\t1\t2\n").is_err());
+        let data = encode("chr1\t1\t2\n");
+        let err = decode(&data[..data.len() - 3]).unwrap_err();
+        assert!(err.to_string().contains("truncated binary frame"));
+        assert_eq!(decode(b"").unwrap(), "");
+
+        // Streams from a newer grit are detected but not misread
+        let mut newer = data.clone();
+        newer[7] = BINARY_FORMAT_VERSION + 1;
+        assert!(is_binary(&newer));
+        let err = decode(&newer).unwrap_err();
+        assert!(err.to_string().contains("newer grit"));
+
+        let mut text = String::new();
+        InputReader::detect(&b"chr1\t1\t2\n"[..])
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "chr1\t1\t2\n");
+    }
+}
@@ -1,6 +1,9 @@
 //! Core interval types for genomic region representation.
 
+use crate::config::display_score;
+use crate::genome::Genome;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
 
 /// A genomic interval with chromosome, start, and end positions.
@@ -132,6 +135,166 @@ impl Interval {
 
         result
     }
+
+    /// Midpoint of the interval, rounding down for odd lengths.
+    #[inline]
+    pub fn midpoint(&self) -> u64 {
+        self.start + self.len() / 2
+    }
+
+    /// Window of `width` bases centered on the midpoint.
+    ///
+    /// For odd widths the extra base goes to the right. The window is clamped
+    /// at position 0 but keeps its width; clipping to the chromosome end is
+    /// left to the caller, which knows the chromosome size.
+    pub fn centered(&self, width: u64) -> Interval {
+        let start = self.midpoint().saturating_sub(width / 2);
+        Interval {
+            chrom: self.chrom.clone(),
+            start,
+            end: start + width,
+        }
+    }
+
+    /// The single 5' base of the interval.
+    ///
+    /// This is the last base for minus-strand features and the first base
+    /// otherwise, so unstranded input anchors at `start`.
+    pub fn anchor_5prime(&self, strand: Option<Strand>) -> Interval {
+        match strand {
+            Some(Strand::Minus) => self.last_base(),
+            _ => self.first_base(),
+        }
+    }
+
+    /// The single 3' base of the interval.
+    pub fn anchor_3prime(&self, strand: Option<Strand>) -> Interval {
+        match strand {
+            Some(Strand::Minus) => self.first_base(),
+            _ => self.last_base(),
+        }
+    }
+
+    #[inline]
+    fn first_base(&self) -> Interval {
+        Interval::new(self.chrom.clone(), self.start, self.start + 1)
+    }
+
+    #[inline]
+    fn last_base(&self) -> Interval {
+        let end = self.end.max(self.start + 1);
+        Interval::new(self.chrom.clone(), end - 1, end)
+    }
+
+    /// Start and end in genome-wide linear coordinates.
+    ///
+    /// `None` if the chromosome is not in `coords` or the interval runs past
+    /// its end.
+    pub fn to_global(&self, coords: &GlobalCoords) -> Option<(u64, u64)> {
+        Some((
+            coords.to_global(&self.chrom, self.start)?,
+            coords.to_global(&self.chrom, self.end)?,
+        ))
+    }
+
+    /// Interval at genome-wide linear coordinates `start..end`.
+    ///
+    /// `None` if `start` is past the genome or the range crosses into the
+    /// next chromosome.
+    pub fn from_global(start: u64, end: u64, coords: &GlobalCoords) -> Option<Interval> {
+        let (index, local) = coords.locate(start)?;
+        let len = end.checked_sub(start)?;
+        if local + len > coords.size(index) {
+            return None;
+        }
+        Some(Interval::new(
+            coords.chroms[index].clone(),
+            local,
+            local + len,
+        ))
+    }
+}
+
+/// Genome-wide linear coordinates: chromosomes laid end to end in genome
+/// file order, each starting at the cumulative size of those before it.
+///
+/// A single `u64` position orders the whole genome, which suits plotting,
+/// genome-wide binned matrices and sorting on one key.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalCoords {
+    /// Chromosome names in genome order
+    chroms: Vec<String>,
+    /// Offset of each chromosome, plus the genome length as a last entry
+    offsets: Vec<u64>,
+    /// Chromosome name to position in `chroms`
+    index: HashMap<String, usize>,
+}
+
+impl GlobalCoords {
+    /// Offsets for the chromosomes of `genome`, in its order.
+    pub fn from_genome(genome: &Genome) -> Self {
+        let mut coords = Self {
+            offsets: vec![0],
+            ..Self::default()
+        };
+        for chrom in genome.chromosomes() {
+            let size = genome.chrom_size(chrom).unwrap_or(0);
+            let offset = coords.total_len();
+            coords.index.insert(chrom.clone(), coords.chroms.len());
+            coords.chroms.push(chrom.clone());
+            coords.offsets.push(offset + size);
+        }
+        coords
+    }
+
+    /// Length of the whole genome.
+    pub fn total_len(&self) -> u64 {
+        self.offsets.last().copied().unwrap_or(0)
+    }
+
+    /// Chromosomes in order.
+    pub fn chroms(&self) -> &[String] {
+        &self.chroms
+    }
+
+    /// Start offsets of the chromosomes, followed by the genome length.
+    pub fn offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+
+    /// Offset of a chromosome's first base.
+    pub fn offset(&self, chrom: &str) -> Option<u64> {
+        self.index.get(chrom).map(|&i| self.offsets[i])
+    }
+
+    /// Linear position of `chrom:pos`; `pos` may equal the chromosome size,
+    /// as an interval end does.
+    pub fn to_global(&self, chrom: &str, pos: u64) -> Option<u64> {
+        let i = *self.index.get(chrom)?;
+        (pos <= self.size(i)).then(|| self.offsets[i] + pos)
+    }
+
+    /// Chromosome and position of a linear position inside the genome.
+    pub fn to_local(&self, global: u64) -> Option<(&str, u64)> {
+        let (i, pos) = self.locate(global)?;
+        Some((&self.chroms[i], pos))
+    }
+
+    /// Index of the chromosome holding `global`, and the position on it.
+    fn locate(&self, global: u64) -> Option<(usize, u64)> {
+        if global >= self.total_len() {
+            return None;
+        }
+        // Last chromosome starting at or before `global`; empty chromosomes
+        // share their offset with the next one and are skipped
+        let i = self.offsets.partition_point(|&o| o <= global) - 1;
+        Some((i, global - self.offsets[i]))
+    }
+
+    #[inline]
+    fn size(&self, index: usize) -> u64 {
+        self.offsets[index + 1] - self.offsets[index]
+    }
 }
 
 impl fmt::Display for Interval {
@@ -219,6 +382,53 @@ impl BedRecord {
     pub fn is_empty(&self) -> bool {
         self.interval.is_empty()
     }
+
+    /// Midpoint of the record's interval.
+    #[inline]
+    pub fn midpoint(&self) -> u64 {
+        self.interval.midpoint()
+    }
+
+    /// Copy of the record resized to `width` bases around its midpoint.
+    pub fn centered(&self, width: u64) -> BedRecord {
+        self.with_interval(self.interval.centered(width))
+    }
+
+    /// Copy of the record reduced to its 5' base, using the record's strand.
+    pub fn anchor_5prime(&self) -> BedRecord {
+        self.with_interval(self.interval.anchor_5prime(self.strand))
+    }
+
+    /// Copy of the record reduced to its 3' base, using the record's strand.
+    pub fn anchor_3prime(&self) -> BedRecord {
+        self.with_interval(self.interval.anchor_3prime(self.strand))
+    }
+
+    /// Value of a 1-based column as a number, if the column holds one.
+    ///
+    /// Only the columns the BED parser keeps as numbers or text can be read:
+    /// 2-5, 7-8, 10 and 13 onwards.
+    pub fn numeric_column(&self, col: usize) -> Option<f64> {
+        match col {
+            2 => Some(self.start() as f64),
+            3 => Some(self.end() as f64),
+            4 => self.name.as_deref()?.parse().ok(),
+            5 => self.score,
+            7 => self.thick_start.map(|v| v as f64),
+            8 => self.thick_end.map(|v| v as f64),
+            10 => self.block_count.map(f64::from),
+            col if col > 12 => self.extra_fields.get(col - 13)?.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Copy of the record with new coordinates and all other fields kept.
+    fn with_interval(&self, interval: Interval) -> BedRecord {
+        BedRecord {
+            interval,
+            ..self.clone()
+        }
+    }
 }
 
 impl fmt::Display for BedRecord {
@@ -227,7 +437,7 @@ impl fmt::Display for BedRecord {
         if let Some(ref name) = self.name {
             write!(f, "\t{}", name)?;
             if let Some(score) = self.score {
-                write!(f, "\t{}", score)?;
+                write!(f, "\t{}", display_score(score))?;
                 if let Some(strand) = self.strand {
                     write!(f, "\t{}", strand)?;
                 }
@@ -291,6 +501,64 @@ mod tests {
         assert!(!a.overlaps_by_fraction(&b, 0.6));
     }
 
+    #[test]
+    fn test_global_coords() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 100);
+        genome.insert("chrEmpty".to_string(), 0);
+        genome.insert("chr2".to_string(), 50);
+        let coords = GlobalCoords::from_genome(&genome);
+
+        assert_eq!(coords.total_len(), 150);
+        assert_eq!(coords.offset("chr2"), Some(100));
+        assert_eq!(coords.to_global("chr2", 10), Some(110));
+        assert_eq!(coords.to_global("chr1", 100), Some(100));
+        assert_eq!(coords.to_global("chr1", 101), None);
+        assert_eq!(coords.to_global("chrX", 0), None);
+        assert_eq!(coords.to_local(100), Some(("chr2", 0)));
+        assert_eq!(coords.to_local(99), Some(("chr1", 99)));
+        assert_eq!(coords.to_local(150), None);
+
+        let iv = Interval::new("chr2", 10, 50);
+        assert_eq!(iv.to_global(&coords), Some((110, 150)));
+        assert_eq!(Interval::from_global(110, 150, &coords), Some(iv));
+        // Crosses from chr1 into chr2
+        assert_eq!(Interval::from_global(90, 110, &coords), None);
+        assert_eq!(Interval::new("chr1", 90, 110).to_global(&coords), None);
+    }
+
+    #[test]
+    fn test_interval_midpoint_and_centered() {
+        let a = Interval::new("chr1", 100, 201);
+        assert_eq!(a.midpoint(), 150);
+        assert_eq!(a.centered(10), Interval::new("chr1", 145, 155));
+        assert_eq!(a.centered(11), Interval::new("chr1", 145, 156));
+
+        // Clamped at zero without losing width
+        let b = Interval::new("chr1", 0, 4);
+        assert_eq!(b.centered(10), Interval::new("chr1", 0, 10));
+    }
+
+    #[test]
+    fn test_interval_anchors() {
+        let a = Interval::new("chr1", 100, 200);
+        assert_eq!(a.anchor_5prime(None), Interval::new("chr1", 100, 101));
+        assert_eq!(a.anchor_3prime(None), Interval::new("chr1", 199, 200));
+        assert_eq!(
+            a.anchor_5prime(Some(Strand::Minus)),
+            Interval::new("chr1", 199, 200)
+        );
+        assert_eq!(
+            a.anchor_3prime(Some(Strand::Minus)),
+            Interval::new("chr1", 100, 101)
+        );
+        // An anchor is a 1 bp interval, so centering on it centers on that base
+        assert_eq!(
+            a.anchor_5prime(None).centered(20),
+            Interval::new("chr1", 90, 110)
+        );
+    }
+
     #[test]
     fn test_interval_distance() {
         let a = Interval::new("chr1", 100, 200);
@@ -28,18 +28,31 @@
 //! ```
 
 pub mod bed;
+pub mod build_info;
 pub mod commands;
 pub mod config;
+pub mod explain;
+pub mod fasta;
+pub mod file_index;
 pub mod genome;
+pub mod help_topics;
 pub mod index;
+pub mod interchange;
 pub mod interval;
+pub mod manifest;
+pub mod output_format;
+pub mod output_limit;
 pub mod parallel;
+pub mod progress;
+pub mod provenance;
+pub mod rng;
+pub mod scratch;
 pub mod streaming;
 
 // Re-export commonly used types
 pub use bed::{read_intervals, read_records, BedReader};
 pub use index::IntervalIndex;
-pub use interval::{BedRecord, Interval, Strand};
+pub use interval::{BedRecord, GlobalCoords, Interval, Strand};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -52,7 +65,8 @@ pub mod prelude {
         SubtractCommand, WindowCommand,
     };
     pub use crate::index::IntervalIndex;
-    pub use crate::interval::{BedRecord, Interval, Strand};
+    pub use crate::interval::{BedRecord, GlobalCoords, Interval, Strand};
+    pub use crate::parallel::PoolHandle;
 }
 
 #[cfg(test)]
@@ -5,32 +5,69 @@
 //!
 //! Usage: `grit <COMMAND> [OPTIONS]`
 
-use clap::{Parser, Subcommand};
-use std::io;
-use std::path::PathBuf;
+use clap::{CommandFactory, Parser, Subcommand};
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::LazyLock;
 
-use grit_genomics::bed::{BedError, BedReader};
+use grit_genomics::bed::{is_bedgraph, BedError, BedReader};
 use grit_genomics::commands::{
-    verify_sorted, verify_sorted_reader, verify_sorted_with_genome, ClosestCommand,
-    ComplementCommand, FastMergeCommand, FastSortCommand, GenomecovCommand, GenomecovOutputMode,
-    IntersectCommand, JaccardCommand, MergeCommand, MultiinterCommand, SlopCommand, SortCommand,
+    read_stab_regions, verify_sorted, verify_sorted_bytes, verify_sorted_with_genome, Anchor,
+    ClosestCommand, ComplementCommand, ConvertCommand, ConvertFormat, CovariatesCommand, CutColumn,
+    CutCommand, CutFormat, DeltaCommand, DepthClasses, DepthNormalization, DistanceBasis,
+    EnrichCommand, EnrichMetric, FastMergeCommand, FastSortCommand, FixStrandCommand,
+    GenomecovCommand, GenomecovOutputMode, IndexedBed, IndexedCoverageCommand, IntersectCommand,
+    JaccardCommand, JaccardPair, JoinCommand, JoinType, MergeCommand, MergeId, ModifyCommand,
+    MultiinterCommand, ReciprocalSummary, RelativeDistance, ScoreOp, ShuffleBackground,
+    ShuffleCommand, SlopCommand, SortCommand, SparseMultiinterReader, StabCommand, StabQuery,
     StreamingClosestCommand, StreamingCoverageCommand, StreamingGenomecovCommand,
     StreamingGenomecovMode, StreamingIntersectCommand, StreamingMultiinterCommand,
     StreamingSubtractCommand, StreamingWindowCommand, SubtractCommand,
 };
-use grit_genomics::genome::Genome;
+use grit_genomics::config::ChromClasses;
+use grit_genomics::explain::{Plan, Residency, Strategy, Validation};
+use grit_genomics::file_index::{
+    upgrade_sidecar, FileIndex, IndexUpgrade, FILE_INDEX_EXTENSION, FILE_INDEX_VERSION,
+};
+use grit_genomics::genome::{Genome, GenomeMask};
+use grit_genomics::help_topics;
+use grit_genomics::interchange::{
+    encoded_stdout, set_input_encoding, set_output_encoding, stdin_reader, InputReader,
+    StreamEncoding,
+};
+use grit_genomics::manifest::Manifest;
+use grit_genomics::output_format::{FormattedWriter, Layout, OutputFormat};
+use grit_genomics::output_limit::{
+    limited_stdout, output_limit_exceeded, parse_byte_size, set_output_limits,
+};
+#[cfg(all(feature = "numa", target_os = "linux"))]
+use grit_genomics::parallel::ThreadPlacement;
+use grit_genomics::scratch::ScratchDir;
+use grit_genomics::streaming::read_ahead::{set_io_threads, DEFAULT_IO_THREADS};
+use grit_genomics::streaming::ScoreFilter;
+
+/// `grit --version` report; `-V` prints the short version only.
+static LONG_VERSION: LazyLock<String> = LazyLock::new(grit_genomics::build_info::long_version);
 
 #[derive(Parser)]
 #[command(name = "grit")]
 #[command(author = "Manish Kumar Bobbili")]
-#[command(version)]
+#[command(version, long_version = LONG_VERSION.as_str())]
 #[command(about = "GRIT: Genomic Range Interval Toolkit - high-performance genomic interval operations", long_about = None)]
+#[command(disable_help_subcommand = true)]
 struct Cli {
     /// Number of threads to use (default: number of CPUs)
     #[arg(long, short = 't', global = true)]
     threads: Option<usize>,
 
+    /// Pin worker threads: cores (one CPU each, filling a NUMA node before
+    /// the next) or nodes (round-robin over NUMA nodes)
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    #[arg(long, global = true, value_name = "MODE", value_parser = ["cores", "nodes"])]
+    pin_threads: Option<String>,
+
     /// Normalize zero-length intervals (start == end) to 1bp intervals
     /// to match bedtools behavior. By default, GRIT uses strict half-open
     /// interval semantics where zero-length intervals do not overlap with
@@ -38,6 +75,69 @@ struct Cli {
     #[arg(long, global = true)]
     bedtools_compatible: bool,
 
+    /// Column delimiter for BED input: tab, space (runs of whitespace), or
+    /// auto (tabs, falling back to whitespace for lines without a tab)
+    #[arg(long, global = true, default_value = "auto", value_parser = ["auto", "tab", "space"])]
+    delim: String,
+
+    /// How score columns are written: auto (integers stay integers,
+    /// fractional scores keep full precision) or int (truncate to integer)
+    #[arg(long, global = true, default_value = "auto", value_parser = ["auto", "int"])]
+    score_format: String,
+
+    /// Print how the command would run (strategy, sort validation, buffers,
+    /// estimated memory) without running it
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Only read records on these chromosomes: comma-separated names, `*`/`?`
+    /// globs or numeric ranges (e.g. chr1-22,chrX)
+    #[arg(long, global = true, value_name = "LIST")]
+    keep_chroms: Option<String>,
+
+    /// Skip records on these chromosomes, e.g. chrUn_*,*_random,chrM (same
+    /// syntax as --keep-chroms)
+    #[arg(long, global = true, value_name = "LIST")]
+    drop_chroms: Option<String>,
+
+    /// Start the output with a `##grit` comment line recording the version,
+    /// command line, timestamp and input checksums (and carry over the
+    /// `##grit` lines of the inputs)
+    #[arg(long, global = true)]
+    provenance: bool,
+
+    /// Stop writing after N output lines; the output is truncated on a line
+    /// boundary and grit exits with status 3
+    #[arg(long, global = true, value_name = "N")]
+    max_output_lines: Option<u64>,
+
+    /// Stop writing before the output exceeds SIZE bytes (K, M, G and T
+    /// suffixes allowed); the output is truncated on a line boundary and grit
+    /// exits with status 3
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    max_output_bytes: Option<u64>,
+
+    /// Encoding of stdin: bed (text) or binary (the frames written by
+    /// `--of binary` from another grit process)
+    #[arg(long = "if", global = true, value_name = "ENCODING", default_value = "bed", value_parser = ["bed", "binary"])]
+    input_encoding: String,
+
+    /// Encoding of stdout: bed (text) or binary frames for a downstream grit
+    /// command reading with `--if binary` (`grit cat` converts back to BED)
+    #[arg(long = "of", global = true, value_name = "ENCODING", default_value = "bed", value_parser = ["bed", "binary"])]
+    output_encoding: String,
+
+    /// Directory for intermediate files (default: $TMPDIR); they are removed
+    /// when the command finishes, fails or panics
+    #[arg(long, global = true, value_name = "DIR")]
+    temp_dir: Option<PathBuf>,
+
+    /// How many input files of streaming commands may be read ahead on
+    /// background threads, so I/O overlaps with processing; 0 reads every
+    /// input inline
+    #[arg(long, global = true, value_name = "N", default_value_t = DEFAULT_IO_THREADS)]
+    io_threads: usize,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -45,6 +145,7 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Sort a BED file by chromosome and position
+    #[command(alias = "sortBed")]
     Sort {
         /// Input BED file (use - for stdin)
         #[arg(short, long)]
@@ -80,6 +181,7 @@ enum Commands {
     },
 
     /// Merge overlapping intervals
+    #[command(alias = "mergeBed")]
     Merge {
         /// Input BED file (use - for stdin)
         #[arg(short, long)]
@@ -101,6 +203,45 @@ enum Commands {
         #[arg(short = 'c', long)]
         count: bool,
 
+        /// Only output merged intervals formed from at least N input records
+        #[arg(long, value_name = "N", default_value = "1")]
+        min_count: usize,
+
+        /// Merge distance as a percentage of interval length instead of bp
+        #[arg(long, value_name = "PCT", conflicts_with = "distance")]
+        distance_pct: Option<f64>,
+
+        /// Interval length that --distance-pct is measured against: the
+        /// shorter or longer of the current merged span and the next interval
+        #[arg(long, default_value = "shorter", value_parser = ["shorter", "longer"], requires = "distance_pct")]
+        distance_basis: String,
+
+        /// Do not merge book-ended intervals (end == next start); with -d,
+        /// only merge intervals less than the distance apart
+        #[arg(long)]
+        no_book_ended: bool,
+
+        /// Append an ID column to each merged interval: chrom_start_end
+        /// (coords) or a 16-digit hex hash of it (hash); strand is included
+        /// with -s
+        #[arg(long, value_name = "KIND", value_parser = ["coords", "hash"])]
+        id: Option<String>,
+
+        /// Append the comma-separated names (column 4) of the merged records
+        #[arg(long)]
+        names: bool,
+
+        /// Report the coordinates of the record with the highest value in
+        /// this column (default 5, the score), with the merged span appended
+        /// as the last two columns
+        #[arg(long, value_name = "COL", num_args = 0..=1, default_missing_value = "5")]
+        best_by: Option<usize>,
+
+        /// Append the scores (column 5, or the BedGraph value column) of the
+        /// merged records combined with sum, mean or max
+        #[arg(long, value_name = "OP", value_parser = ["sum", "mean", "max"])]
+        scores: Option<String>,
+
         /// Print streaming statistics to stderr
         #[arg(long)]
         stats: bool,
@@ -115,6 +256,7 @@ enum Commands {
     },
 
     /// Find overlapping intervals between two BED files
+    #[command(alias = "intersectBed")]
     Intersect {
         /// Input BED file A
         #[arg(short = 'a', long)]
@@ -148,10 +290,37 @@ enum Commands {
         #[arg(short = 'r', long)]
         reciprocal: bool,
 
+        /// Minimum overlap in base pairs (in addition to -f)
+        #[arg(long, value_name = "N")]
+        min_overlap_bp: Option<u64>,
+
         /// Report the number of overlaps
         #[arg(short = 'c', long)]
         count: bool,
 
+        /// Stop each A interval after reporting N overlaps
+        #[arg(long, value_name = "N", conflicts_with_all = ["unique", "no_overlap", "count"])]
+        max_hits: Option<usize>,
+
+        /// Write A and B followed by the overlap in bp and the fractions of
+        /// A and of B it covers (like -wo with two float columns)
+        #[arg(long, conflicts_with_all = ["unique", "no_overlap", "count"])]
+        overlap_fractions: bool,
+
+        /// Write A and B followed by the overlap in bp (-wo in bedtools)
+        #[arg(long = "wo", conflicts_with_all = ["unique", "no_overlap", "count", "overlap_fractions"])]
+        write_overlap: bool,
+
+        /// Like --wo, but also write each A without overlaps against a null B
+        /// record (`.` and -1 coordinates) and an overlap of 0 (-wao in bedtools)
+        #[arg(long = "wao", conflicts_with_all = ["unique", "no_overlap", "count", "overlap_fractions"])]
+        write_all_overlap: bool,
+
+        /// Left outer join: write A and B, and each A without overlaps against
+        /// a null B record (-loj in bedtools)
+        #[arg(long = "loj", conflicts_with_all = ["unique", "no_overlap", "count", "overlap_fractions"])]
+        left_outer_join: bool,
+
         /// Use streaming mode (constant memory, requires sorted input)
         #[arg(long)]
         streaming: bool,
@@ -171,9 +340,50 @@ enum Commands {
         /// Genome file for chromosome order validation (streaming mode)
         #[arg(short = 'g', long)]
         genome: Option<PathBuf>,
+
+        /// Intersect A with the complement of B over the genome (requires -g)
+        #[arg(long)]
+        invert_b: bool,
+
+        /// Clip records extending past their chromosome end (from -g) before
+        /// intersecting, dropping records that start past it; counts go to stderr
+        #[arg(long, requires = "genome", conflicts_with = "indexed")]
+        clip_to_genome: bool,
+
+        /// Ignore B records with a score below this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "min-b-value", value_name = "X")]
+        min_b_score: Option<f64>,
+
+        /// Ignore B records with a score above this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "max-b-value", value_name = "X")]
+        max_b_score: Option<f64>,
+
+        /// Look up each A record in B's .gri index (see `grit stab
+        /// --build-index`) instead of reading all of B; for a few A records
+        /// against a large sorted B. A may be unsorted and is reported in
+        /// input order
+        #[arg(long, conflicts_with_all = ["streaming", "stats", "invert_b"])]
+        indexed: bool,
+
+        /// Collapse exact duplicate B lines before intersecting (streaming mode)
+        #[arg(long, requires = "streaming", conflicts_with = "invert_b")]
+        dedup_b: bool,
+
+        /// With --dedup-b, append how many times each B line occurred as a
+        /// last B column
+        #[arg(long, requires = "dedup_b")]
+        dedup_b_count: bool,
+
+        /// Output format: bed, tsv+header (named columns under a header row)
+        /// or jsonl (one JSON object per result)
+        #[arg(long, default_value = "bed", value_parser = ["bed", "tsv+header", "jsonl"])]
+        output_format: String,
     },
 
     /// Remove intervals in A that overlap with B
+    #[command(alias = "subtractBed")]
     Subtract {
         /// Input BED file A
         #[arg(short = 'a', long)]
@@ -195,6 +405,10 @@ enum Commands {
         #[arg(short = 'r', long)]
         reciprocal: bool,
 
+        /// Minimum overlap in base pairs (in addition to -f)
+        #[arg(long, value_name = "N")]
+        min_overlap_bp: Option<u64>,
+
         /// Use streaming mode (O(k) memory, requires sorted input)
         #[arg(long)]
         streaming: bool,
@@ -214,9 +428,19 @@ enum Commands {
         /// Genome file for chromosome order validation (streaming mode)
         #[arg(short = 'g', long)]
         genome: Option<PathBuf>,
+
+        /// Write A records removed entirely by the subtraction to this file
+        #[arg(long, value_name = "FILE")]
+        keep_suppressed: Option<PathBuf>,
+
+        /// Clip records extending past their chromosome end (from -g) before
+        /// subtracting, dropping records that start past it; counts go to stderr
+        #[arg(long, requires = "genome")]
+        clip_to_genome: bool,
     },
 
     /// Find the closest interval in B for each interval in A
+    #[command(alias = "closestBed")]
     Closest {
         /// Input BED file A
         #[arg(short = 'a', long)]
@@ -265,9 +489,37 @@ enum Commands {
         /// Genome file for chromosome order validation (streaming mode)
         #[arg(short = 'g', long)]
         genome: Option<PathBuf>,
+
+        /// Report a histogram of nearest-B distances instead of pairs
+        #[arg(long)]
+        histogram: bool,
+
+        /// Bin width in bp for --histogram
+        #[arg(long, default_value = "1000", requires = "histogram")]
+        bin_size: u64,
+
+        /// Report one tied B per distinct value of a B column (default: 4,
+        /// the name), e.g. one line per gene rather than per transcript
+        #[arg(long, value_name = "COL", num_args = 0..=1, default_missing_value = "4", conflicts_with = "histogram")]
+        dedup_b_name: Option<usize>,
+
+        /// Collapse exact duplicate B lines before searching (streaming mode)
+        #[arg(long, requires = "streaming")]
+        dedup_b: bool,
+
+        /// With --dedup-b, append how many times each B line occurred as a
+        /// last B column
+        #[arg(long, requires = "dedup_b")]
+        dedup_b_count: bool,
+
+        /// Output format: bed, tsv+header (named columns under a header row)
+        /// or jsonl (one JSON object per result)
+        #[arg(long, default_value = "bed", value_parser = ["bed", "tsv+header", "jsonl"])]
+        output_format: String,
     },
 
     /// Find intervals in B that are within a window of A
+    #[command(alias = "windowBed")]
     Window {
         /// Input BED file A
         #[arg(short = 'a', long)]
@@ -297,6 +549,32 @@ enum Commands {
         #[arg(short = 'v', long)]
         no_overlap: bool,
 
+        /// Minimum overlap in base pairs between B and the window
+        #[arg(long, value_name = "N")]
+        min_overlap_bp: Option<u64>,
+
+        /// Report one row per A: count, nearest distance, max score and
+        /// mean score of B hits in the window
+        #[arg(long, conflicts_with_all = ["count", "no_overlap"])]
+        per_a_summary: bool,
+
+        /// Report one B per distinct value of a B column (default: 4, the
+        /// name), keeping the largest overlap with A, then the nearest
+        #[arg(long, value_name = "COL", num_args = 0..=1, default_missing_value = "4", conflicts_with = "per_a_summary")]
+        dedup_b_name: Option<usize>,
+
+        /// Apply the window (-w/-l/-r) around B features and report the A
+        /// features inside it
+        #[arg(long)]
+        from_b: bool,
+
+        /// Look up each A record in B's .gri index (see `grit stab
+        /// --build-index`) instead of reading all of B; for a few A records
+        /// against a large sorted B. A may be unsorted and is reported in
+        /// input order
+        #[arg(long)]
+        indexed: bool,
+
         /// Skip sorted validation (faster for pre-sorted input)
         #[arg(long)]
         assume_sorted: bool,
@@ -307,10 +585,12 @@ enum Commands {
     },
 
     /// Calculate coverage of A intervals by B intervals
+    #[command(alias = "coverageBed")]
     Coverage {
-        /// Input BED file A (regions)
-        #[arg(short = 'a', long)]
-        file_a: PathBuf,
+        /// Input BED file A (regions); several A files share one pass over B
+        /// and need --output-dir
+        #[arg(short = 'a', long, required = true, num_args = 1..)]
+        file_a: Vec<PathBuf>,
 
         /// Input BED file B (reads/features)
         #[arg(short = 'b', long)]
@@ -328,6 +608,97 @@ enum Commands {
         #[arg(long)]
         mean: bool,
 
+        /// With --mean, also report the standard deviation of per-base depth
+        #[arg(long, requires = "mean")]
+        stddev: bool,
+
+        /// With --mean, also report the median per-base depth
+        #[arg(long, requires = "mean")]
+        median: bool,
+
+        /// With --mean, also report these quantiles of per-base depth (e.g. 0.1,0.9)
+        #[arg(long, requires = "mean", value_delimiter = ',', value_name = "Q,...")]
+        quantiles: Vec<f64>,
+
+        /// Report how many B classes (values of a B column, default: 4, the
+        /// name) cover each A and their Shannon diversity and evenness,
+        /// weighted by overlapping bases
+        #[arg(
+            long,
+            value_name = "COL",
+            num_args = 0..=1,
+            default_missing_value = "4",
+            conflicts_with_all = ["histogram", "per_base", "mean", "index_a"]
+        )]
+        diversity: Option<usize>,
+
+        /// Count B records sharing a name (column 4) once toward depth, so
+        /// paired-end mates and split alignments count as one fragment
+        #[arg(long, conflicts_with_all = ["diversity", "index_a"])]
+        collapse_by_name: bool,
+
+        /// Skip sorted validation (faster for pre-sorted input)
+        #[arg(long)]
+        assume_sorted: bool,
+
+        /// Genome file for chromosome order validation
+        #[arg(short = 'g', long)]
+        genome: Option<PathBuf>,
+
+        /// Ignore B records with a score below this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "min-b-value", value_name = "X")]
+        min_b_score: Option<f64>,
+
+        /// Ignore B records with a score above this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "max-b-value", value_name = "X")]
+        max_b_score: Option<f64>,
+
+        /// Also write the fraction of B records and bases that fall within A
+        /// to this file
+        #[arg(long, value_name = "FILE")]
+        reciprocal_summary: Option<PathBuf>,
+
+        /// Load A into an index and stream B once (for a small A and a huge,
+        /// possibly unsorted B); A is reported in input order
+        #[arg(
+            long,
+            conflicts_with_all = ["histogram", "per_base", "stddev", "median", "quantiles", "reciprocal_summary"]
+        )]
+        index_a: bool,
+
+        /// Write the results for each A file to DIR/<A file name> instead of
+        /// stdout
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+
+        /// Output format: bed, tsv+header (named columns under a header row)
+        /// or jsonl (one JSON object per result)
+        #[arg(long, default_value = "bed", value_parser = ["bed", "tsv+header", "jsonl"])]
+        output_format: String,
+    },
+
+    /// Split A intervals where B coverage depth changes class
+    Segment {
+        /// Input BED file A (regions to split)
+        #[arg(short = 'a', long)]
+        file_a: PathBuf,
+
+        /// Input BED file B (reads/features)
+        #[arg(short = 'b', long)]
+        file_b: PathBuf,
+
+        /// Depths at which a new class starts; the default 1,2 gives the
+        /// classes 0, 1 and 2+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_value = "1,2",
+            value_name = "D,..."
+        )]
+        breaks: Vec<u32>,
+
         /// Skip sorted validation (faster for pre-sorted input)
         #[arg(long)]
         assume_sorted: bool,
@@ -335,9 +706,137 @@ enum Commands {
         /// Genome file for chromosome order validation
         #[arg(short = 'g', long)]
         genome: Option<PathBuf>,
+
+        /// Ignore B records with a score below this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "min-b-value", value_name = "X")]
+        min_b_score: Option<f64>,
+
+        /// Ignore B records with a score above this value (or no numeric
+        /// score); for BedGraph B the value column is used
+        #[arg(long, visible_alias = "max-b-value", value_name = "X")]
+        max_b_score: Option<f64>,
+    },
+
+    /// Call regions where two BedGraph tracks differ (treatment vs control)
+    Delta {
+        /// Treatment BedGraph
+        #[arg(short = 'a', long)]
+        file_a: PathBuf,
+
+        /// Control BedGraph
+        #[arg(short = 'b', long)]
+        file_b: PathBuf,
+
+        /// Minimum absolute difference between the tracks
+        #[arg(long, value_name = "X")]
+        min_diff: Option<f64>,
+
+        /// Minimum fold change in either direction (e.g. 2 for twofold)
+        #[arg(long, value_name = "X")]
+        min_fold: Option<f64>,
+
+        /// Added to both values before computing fold changes
+        #[arg(long, default_value = "1")]
+        pseudocount: f64,
+
+        /// Drop runs shorter than this many bases before merging
+        #[arg(long, default_value = "1")]
+        min_length: u64,
+
+        /// Merge same-direction runs at most this many bases apart
+        #[arg(short = 'd', long, default_value = "0")]
+        distance: u64,
+
+        /// Skip sorted validation (faster for pre-sorted input)
+        #[arg(long)]
+        assume_sorted: bool,
+
+        /// Genome file for chromosome order
+        #[arg(short = 'g', long)]
+        genome: Option<PathBuf>,
+    },
+
+    /// Append length, GC fraction and mappability covariates to each interval
+    Covariates {
+        /// Input BED file (e.g. fixed-size bins)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Reference FASTA; adds a GC fraction column
+        #[arg(long)]
+        fasta: Option<PathBuf>,
+
+        /// Mappability BedGraph; adds a mean mappability column
+        #[arg(long, value_name = "BEDGRAPH")]
+        mappability: Option<PathBuf>,
+    },
+
+    /// Join a BED file with a tab-delimited table on a key column
+    Join {
+        /// Left BED file (coordinates and order are kept)
+        #[arg(short = 'a', long)]
+        file_a: PathBuf,
+
+        /// Right tab-delimited table
+        #[arg(short = 'b', long)]
+        file_b: PathBuf,
+
+        /// Key column in A (1-based; default: BED name)
+        #[arg(long, value_name = "COL", default_value = "4")]
+        key_a: usize,
+
+        /// Key column in B (1-based)
+        #[arg(long, value_name = "COL", default_value = "1")]
+        key_b: usize,
+
+        /// Join type: inner (matched A records only) or left (all A records)
+        #[arg(long = "type", default_value = "inner", value_parser = ["inner", "left"])]
+        join_type: String,
+
+        /// Value for each B column when an A record has no match (left join)
+        #[arg(long, default_value = ".")]
+        fill: String,
+    },
+
+    /// Move intervals to random locations, keeping their lengths
+    #[command(alias = "shuffleBed")]
+    Shuffle {
+        /// Input BED file (use - for stdin)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Genome file (chrom sizes)
+        #[arg(short, long)]
+        genome: PathBuf,
+
+        /// Background: uniform (size-weighted chromosomes) or matched (each record's own chromosome)
+        #[arg(long, default_value = "uniform", value_parser = ["uniform", "matched"])]
+        background: String,
+
+        /// Keep each record on its chromosome (same as --background matched)
+        #[arg(long)]
+        chrom: bool,
+
+        /// Number of controls per input record
+        #[arg(long, value_name = "N", default_value = "1")]
+        per_record: usize,
+
+        /// Random seed; equal seeds give equal output (see `grit help reproducibility`)
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Only place intervals inside regions from this BED file
+        #[arg(long)]
+        include: Option<PathBuf>,
+
+        /// Never place intervals overlapping regions from this BED file
+        #[arg(long)]
+        exclude: Option<PathBuf>,
     },
 
     /// Extend intervals by a given number of bases
+    #[command(alias = "slopBed")]
     Slop {
         /// Input BED file
         #[arg(short, long)]
@@ -366,9 +865,106 @@ enum Commands {
         /// Interpret values as fraction of interval size
         #[arg(long)]
         pct: bool,
+
+        /// Read the extension of each record from this 1-based column
+        /// (e.g. 5 for a fragment length in the score); -b/-l/-r then
+        /// multiply it and default to -b 1
+        #[arg(long, value_name = "COL", conflicts_with = "pct")]
+        amount_col: Option<usize>,
+
+        /// Write records clamped at chromosome bounds or on chromosomes
+        /// missing from the genome to this file, with a reason column
+        #[arg(long, value_name = "FILE")]
+        rejects: Option<PathBuf>,
+    },
+
+    /// Recenter intervals on an anchor base (midpoint, 5' or 3' end)
+    Modify {
+        /// Input BED file (use - for stdin)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Genome file; clips output to chromosome ends and drops unknown chromosomes
+        #[arg(short, long)]
+        genome: Option<PathBuf>,
+
+        /// Emit a window of this many bases centered on the anchor
+        #[arg(long, value_name = "N")]
+        center: Option<u64>,
+
+        /// Anchor base: mid (midpoint), 5p or 3p (strand-aware)
+        #[arg(long, default_value = "mid", value_parser = ["mid", "5p", "3p"])]
+        anchor: String,
+    },
+
+    /// Select and reorder columns, keeping the output valid BED
+    Cut {
+        /// Input BED file (use - for stdin)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Columns to keep, in order: 1-based numbers, ranges (5-7, 7-) or
+        /// column names (name, strand, thickStart, value, signalValue, ...)
+        #[arg(short, long, value_name = "LIST")]
+        fields: String,
+
+        /// Format for column names: bed, bedgraph or narrowpeak (default:
+        /// detected from the file)
+        #[arg(long, value_parser = ["bed", "bedgraph", "narrowpeak"])]
+        format: Option<String>,
+
+        /// Sort the output by chromosome, start and end (for when the
+        /// coordinate columns change)
+        #[arg(long)]
+        sort: bool,
+
+        /// Genome file giving the chromosome order for --sort
+        #[arg(short, long, requires = "sort")]
+        genome: Option<PathBuf>,
+    },
+
+    /// Convert between BED, GFF3, SAF (featureCounts) and IGV locus lists
+    Convert {
+        /// Input file (use - for stdin)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output format: bed, gff, saf or igv
+        #[arg(long, value_parser = ["bed", "gff", "gff3", "saf", "igv"])]
+        to: String,
+
+        /// Input format (default: detected from the file extension, else bed)
+        #[arg(long, value_parser = ["bed", "gff", "gff3", "saf", "igv"])]
+        from: Option<String>,
+
+        /// GFF source column of written features
+        #[arg(long, default_value = "grit")]
+        source: String,
+
+        /// GFF type column of written features (default: region); when
+        /// reading GFF, only features of this type are converted
+        #[arg(long, value_name = "TYPE")]
+        feature_type: Option<String>,
+    },
+
+    /// Normalize the strand column and fill unknown strands from a gene model
+    FixStrand {
+        /// Input BED file (use - for stdin)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Infer unknown strands from the genes (BED6) each record overlaps;
+        /// records overlapping genes on both strands stay '.'
+        #[arg(long, value_name = "FILE")]
+        genes: Option<PathBuf>,
+
+        /// Fail on unrecognized strand values instead of writing '.'
+        #[arg(long)]
+        strict: bool,
     },
 
     /// Return intervals NOT covered by the input BED file
+    #[command(alias = "complementBed")]
     Complement {
         /// Input BED file
         #[arg(short, long)]
@@ -381,9 +977,18 @@ enum Commands {
         /// Assume input is sorted in genome order (enables O(1) memory streaming)
         #[arg(long)]
         assume_sorted: bool,
+
+        /// Only report complement regions inside intervals from this BED file
+        #[arg(long)]
+        include: Option<PathBuf>,
+
+        /// Never report complement regions inside intervals from this BED file
+        #[arg(long)]
+        exclude: Option<PathBuf>,
     },
 
     /// Compute genome-wide coverage
+    #[command(alias = "genomeCoverageBed")]
     Genomecov {
         /// Input BED file
         #[arg(short, long)]
@@ -409,6 +1014,50 @@ enum Commands {
         #[arg(long, default_value = "1.0")]
         scale: f64,
 
+        /// Round scaled depths to integers (for bedGraphToBigWig)
+        #[arg(long = "int")]
+        round_scaled: bool,
+
+        /// Report BedGraph values as the genome-wide percentile or z-score of
+        /// each depth instead of the depth (implies --bg unless --bga is given)
+        #[arg(long, value_parser = ["percentile", "zscore"], conflicts_with_all = ["per_base", "scale", "round_scaled"])]
+        normalize: Option<String>,
+
+        /// Count records sharing a name (column 4) once toward depth, so
+        /// paired-end mates and split alignments count as one fragment
+        #[arg(long)]
+        collapse_by_name: bool,
+
+        /// Report only bases inside the regions of this BED file (e.g.
+        /// capture targets); histogram rows count target bases
+        #[arg(long, value_name = "BED")]
+        regions: Option<PathBuf>,
+
+        /// Write per-chromosome and total target depth (mean and percentage
+        /// of bases at each --thresholds depth) to this file
+        #[arg(long, value_name = "FILE", requires = "regions")]
+        regions_summary: Option<PathBuf>,
+
+        /// Depths reported by --regions-summary
+        #[arg(
+            long,
+            requires = "regions_summary",
+            value_delimiter = ',',
+            default_value = "1,10,20,30",
+            value_name = "D,..."
+        )]
+        thresholds: Vec<u32>,
+
+        /// Report histogram rows per chromosome class instead of per
+        /// chromosome, from a file of `class<TAB>patterns` lines (e.g.
+        /// `autosomes  chr1-22`); unmatched chromosomes form the class `other`
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["per_base", "bedgraph", "bedgraph_all", "normalize"]
+        )]
+        chrom_classes: Option<PathBuf>,
+
         /// Use streaming mode (O(k) memory, requires sorted input)
         #[arg(long)]
         streaming: bool,
@@ -416,25 +1065,112 @@ enum Commands {
         /// Skip sorted validation (faster for pre-sorted input)
         #[arg(long)]
         assume_sorted: bool,
+
+        /// Report percent complete by genome position, with an ETA, on stderr
+        #[arg(long)]
+        progress: bool,
+
+        /// Report progress and each chromosome as it finishes
+        #[arg(long)]
+        verbose: bool,
     },
 
     /// Calculate Jaccard similarity between two BED files
     Jaccard {
         /// Input BED file A
+        #[arg(short = 'a', long, required_unless_present = "manifest")]
+        file_a: Option<PathBuf>,
+
+        /// Input BED file B
+        #[arg(short = 'b', long, required_unless_present = "manifest")]
+        file_b: Option<PathBuf>,
+
+        /// Also report bases in A and B, overlap coefficient and Dice coefficient
+        #[arg(short = 'e', long)]
+        extended: bool,
+
+        /// Genome file; adds fraction-of-genome columns (implies --extended)
+        #[arg(short = 'g', long)]
+        genome: Option<PathBuf>,
+
+        /// Compare inputs from a TSV of `label<TAB>path` lines in parallel,
+        /// writing a long-format table; every pair unless --pairs or --against
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["file_a", "file_b"])]
+        manifest: Option<PathBuf>,
+
+        /// TSV of `label_a<TAB>label_b` lines naming the manifest pairs to compare
+        #[arg(
+            long,
+            value_name = "FILE",
+            requires = "manifest",
+            conflicts_with = "against"
+        )]
+        pairs: Option<PathBuf>,
+
+        /// Compare every other manifest input against the one with this label
+        #[arg(long, value_name = "LABEL", requires = "manifest")]
+        against: Option<String>,
+    },
+
+    /// Observed vs expected overlap of A with B: fold enrichment, z-score and p-value
+    Enrich {
+        /// Input BED file A (the features tested for enrichment)
         #[arg(short = 'a', long)]
         file_a: PathBuf,
 
-        /// Input BED file B
+        /// Input BED file B (the annotation)
         #[arg(short = 'b', long)]
         file_b: PathBuf,
+
+        /// Genome file (chrom sizes)
+        #[arg(short, long)]
+        genome: PathBuf,
+
+        /// Overlap measure: records (A records touching B) or bases (A bases covered by B)
+        #[arg(long, default_value = "records", value_parser = ["records", "bases"])]
+        metric: String,
+
+        /// Expected overlap from N shuffles of A instead of genome coverage fractions
+        #[arg(long, value_name = "N", default_value = "0")]
+        shuffles: usize,
+
+        /// Shuffle background: uniform (size-weighted chromosomes) or matched (each record's own chromosome)
+        #[arg(long, default_value = "uniform", value_parser = ["uniform", "matched"])]
+        background: String,
+
+        /// Random seed for the shuffles (see `grit help reproducibility`)
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Only place shuffled intervals inside regions from this BED file
+        #[arg(long, requires = "shuffles")]
+        include: Option<PathBuf>,
+
+        /// Never place shuffled intervals overlapping regions from this BED file
+        #[arg(long, requires = "shuffles")]
+        exclude: Option<PathBuf>,
     },
 
     /// Identify common intervals across multiple BED files
+    #[command(alias = "multiIntersectBed")]
     Multiinter {
         /// Input BED files
-        #[arg(short = 'i', long = "input", num_args = 1..)]
+        #[arg(
+            short = 'i',
+            long = "input",
+            num_args = 1..,
+            required_unless_present_any = ["manifest", "expand"]
+        )]
         inputs: Vec<PathBuf>,
 
+        /// TSV of `label<TAB>path` lines listing the input files
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["inputs", "names"])]
+        manifest: Option<PathBuf>,
+
+        /// Labels for the input files, reported instead of file numbers
+        #[arg(long, num_args = 1..)]
+        names: Option<Vec<String>>,
+
         /// Only output intervals found in all files
         #[arg(long)]
         cluster: bool,
@@ -446,6 +1182,57 @@ enum Commands {
         /// Skip sorted validation (faster for pre-sorted input)
         #[arg(long)]
         assume_sorted: bool,
+
+        /// Most input files open at once in streaming mode; more inputs are
+        /// merged in rounds through temporary files (default: from the
+        /// open-file limit)
+        #[arg(long, value_name = "N")]
+        max_open_files: Option<usize>,
+
+        /// Sparse output: list the indices of the files present instead of
+        /// one presence column per file, under a #grit-sparse header
+        #[arg(long)]
+        sparse: bool,
+
+        /// Expand a --sparse output file back to the dense layout
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["inputs", "manifest", "names", "cluster", "sparse"]
+        )]
+        expand: Option<PathBuf>,
+    },
+
+    /// Report intervals covering positions or regions of a sorted BED file
+    Stab {
+        /// Sorted BED file (a .gri index next to it is used if present)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Queries: chrom:pos (1-based) or chrom:start-end (1-based, inclusive)
+        #[arg(value_name = "QUERY", required_unless_present_any = ["regions", "build_index"])]
+        queries: Vec<String>,
+
+        /// BED file of query regions
+        #[arg(short, long, value_name = "FILE")]
+        regions: Option<PathBuf>,
+
+        /// Write a .gri index next to the input for faster repeated queries
+        #[arg(long)]
+        build_index: bool,
+    },
+
+    /// Maintain .gri indexes written by stab --build-index
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+
+    /// Convert binary interval streams (from --of binary) back to BED
+    Cat {
+        /// Binary or BED files (default: stdin)
+        #[arg(value_name = "FILE")]
+        inputs: Vec<PathBuf>,
     },
 
     /// Generate synthetic BED datasets for benchmarking
@@ -459,7 +1246,7 @@ enum Commands {
         #[arg(long, default_value = "1M,5M,10M,25M,50M")]
         sizes: String,
 
-        /// Random seed for reproducibility
+        /// Random seed; equal seeds give equal output (see `grit help reproducibility`)
         #[arg(long, default_value = "42")]
         seed: u64,
 
@@ -503,44 +1290,443 @@ enum Commands {
         #[arg(long)]
         force: bool,
     },
-}
 
-/// Preprocess CLI arguments to support bedtools-style flags.
-/// Converts -wa to --wa and -wb to --wb for compatibility.
-fn preprocess_args() -> Vec<String> {
-    std::env::args()
-        .map(|arg| match arg.as_str() {
-            "-wa" => "--wa".to_string(),
-            "-wb" => "--wb".to_string(),
-            _ => arg,
-        })
-        .collect()
+    /// Print help for a topic (sorting, streaming, bedtools, genome) or a command
+    Help {
+        /// Topic or command name (default: list commands and topics)
+        topic: Option<String>,
+    },
 }
 
-fn main() {
-    let cli = Cli::parse_from(preprocess_args());
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Rewrite indexes from older grit releases in the current format,
+    /// without re-reading the BED files
+    Upgrade {
+        /// Indexed BED files or their .gri indexes
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Report which indexes need upgrading without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
 
-    // Configure bedtools-compatible mode if requested
-    // This must be set before any parsing occurs
-    if cli.bedtools_compatible {
-        grit_genomics::config::set_bedtools_compatible(true);
+/// Print counts of tolerated input quirks (CRLF, whitespace columns, ...) to stderr.
+fn report_parse_irregularities() {
+    let irregularities = grit_genomics::config::parse_irregularities();
+    if !irregularities.is_empty() {
+        eprintln!("Input irregularities: {}", irregularities);
     }
+}
 
-    // Configure thread pool if --threads specified
-    if let Some(n) = cli.threads {
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(n)
-            .build_global()
-            .expect("Failed to initialize thread pool");
+/// Reject a 1-based column number of 0.
+fn check_column(flag: &str, col: Option<usize>) -> Result<(), BedError> {
+    if col == Some(0) {
+        return Err(BedError::InvalidFormat(format!(
+            "{} takes a 1-based column number",
+            flag
+        )));
     }
+    Ok(())
+}
 
-    let result = match cli.command {
-        Commands::Sort {
-            input,
+/// Files a command reads, in command-line order, for `--provenance`.
+///
+/// Output files (`--rejects`, `--keep-suppressed`, ...) are not included; a
+/// missing optional input means stdin for sort and merge.
+fn command_inputs(command: &Commands) -> Result<Vec<PathBuf>, BedError> {
+    let stdin = || PathBuf::from("-");
+    let mut inputs = Vec::new();
+    match command {
+        Commands::Sort { input, genome, .. } | Commands::Merge { input, genome, .. } => {
+            inputs.push(input.clone().unwrap_or_else(stdin));
+            inputs.extend(genome.clone());
+        }
+        Commands::Intersect {
+            file_a,
+            file_b,
             genome,
-            size_asc,
-            size_desc,
-            reverse,
+            ..
+        }
+        | Commands::Subtract {
+            file_a,
+            file_b,
+            genome,
+            ..
+        }
+        | Commands::Closest {
+            file_a,
+            file_b,
+            genome,
+            ..
+        }
+        | Commands::Window {
+            file_a,
+            file_b,
+            genome,
+            ..
+        }
+        | Commands::Segment {
+            file_a,
+            file_b,
+            genome,
+            ..
+        }
+        | Commands::Delta {
+            file_a,
+            file_b,
+            genome,
+            ..
+        } => {
+            inputs.extend([file_a.clone(), file_b.clone()]);
+            inputs.extend(genome.clone());
+        }
+        Commands::Jaccard {
+            file_a,
+            file_b,
+            genome,
+            manifest,
+            pairs,
+            ..
+        } => {
+            inputs.extend(file_a.clone());
+            inputs.extend(file_b.clone());
+            inputs.extend(genome.clone());
+            if let Some(manifest) = manifest {
+                inputs.push(manifest.clone());
+                inputs.extend(pairs.clone());
+                inputs.extend(Manifest::from_file(manifest)?.paths());
+            }
+        }
+        Commands::Enrich {
+            file_a,
+            file_b,
+            genome,
+            include,
+            exclude,
+            ..
+        } => {
+            inputs.extend([file_a.clone(), file_b.clone(), genome.clone()]);
+            inputs.extend(include.clone());
+            inputs.extend(exclude.clone());
+        }
+        Commands::Coverage {
+            file_a,
+            file_b,
+            genome,
+            ..
+        } => {
+            inputs.extend(file_a.iter().cloned());
+            inputs.push(file_b.clone());
+            inputs.extend(genome.clone());
+        }
+        Commands::Join { file_a, file_b, .. } => {
+            inputs.extend([file_a.clone(), file_b.clone()]);
+        }
+        Commands::Covariates {
+            input,
+            fasta,
+            mappability,
+            ..
+        } => {
+            inputs.push(input.clone());
+            inputs.extend(fasta.clone());
+            inputs.extend(mappability.clone());
+        }
+        Commands::Shuffle {
+            input,
+            genome,
+            include,
+            exclude,
+            ..
+        }
+        | Commands::Complement {
+            input,
+            genome,
+            include,
+            exclude,
+            ..
+        } => {
+            inputs.extend([input.clone(), genome.clone()]);
+            inputs.extend(include.clone());
+            inputs.extend(exclude.clone());
+        }
+        Commands::Slop { input, genome, .. } => {
+            inputs.extend([input.clone(), genome.clone()]);
+        }
+        Commands::Genomecov {
+            input,
+            genome,
+            regions,
+            chrom_classes,
+            ..
+        } => {
+            inputs.extend([input.clone(), genome.clone()]);
+            inputs.extend(regions.clone());
+            inputs.extend(chrom_classes.clone());
+        }
+        Commands::Modify { input, genome, .. } | Commands::Cut { input, genome, .. } => {
+            inputs.push(input.clone());
+            inputs.extend(genome.clone());
+        }
+        Commands::Convert { input, .. } => inputs.push(input.clone()),
+        Commands::FixStrand { input, genes, .. } => {
+            inputs.push(input.clone());
+            inputs.extend(genes.clone());
+        }
+        Commands::Multiinter {
+            inputs: files,
+            manifest,
+            expand,
+            ..
+        } => {
+            if let Some(manifest) = manifest {
+                inputs.push(manifest.clone());
+                inputs.extend(Manifest::from_file(manifest)?.paths());
+            }
+            inputs.extend(files.iter().cloned());
+            inputs.extend(expand.clone());
+        }
+        Commands::Stab { input, regions, .. } => {
+            inputs.push(input.clone());
+            inputs.extend(regions.clone());
+        }
+        Commands::Cat { inputs: files } => {
+            if files.is_empty() {
+                inputs.push(stdin());
+            }
+            inputs.extend(files.iter().cloned());
+        }
+        Commands::Index { .. } | Commands::Generate { .. } | Commands::Help { .. } => {}
+    }
+    Ok(inputs)
+}
+
+/// Write the `--provenance` header for `command` to stdout.
+fn write_provenance(command: &Commands) -> Result<(), BedError> {
+    let mut provenance = grit_genomics::provenance::Provenance::new(std::env::args());
+    for path in command_inputs(command)? {
+        provenance.add_input(&path)?;
+    }
+    let mut handle = encoded_stdout();
+    provenance.write_header(&mut handle)?;
+    handle.flush()?;
+    Ok(())
+}
+
+/// Global options that take a separate value, used to find the subcommand.
+/// Read from the CLI definition so new global options are picked up.
+static GLOBAL_VALUE_OPTIONS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    Cli::command()
+        .get_arguments()
+        .filter(|arg| arg.is_global_set() && arg.get_action().takes_values())
+        .flat_map(|arg| {
+            let short = arg.get_short().map(|s| format!("-{}", s));
+            let long = arg.get_long().map(|l| format!("--{}", l));
+            short.into_iter().chain(long)
+        })
+        .collect()
+});
+
+/// Single-dash bedtools spellings and their grit flags, as (subcommand,
+/// bedtools flag, grit flag). `*` applies to every subcommand.
+const BEDTOOLS_FLAGS: &[(&str, &str, &str)] = &[
+    ("*", "-sorted", "--assume-sorted"),
+    ("sort", "-sizeA", "--sizeA"),
+    ("sort", "-sizeD", "--sizeD"),
+    ("sort", "-chrThenSizeA", "--chrThenSizeA"),
+    ("intersect", "-wo", "--wo"),
+    ("intersect", "-wao", "--wao"),
+    ("intersect", "-loj", "--loj"),
+    ("closest", "-io", "--io"),
+    ("closest", "-iu", "--iu"),
+    ("closest", "-id", "--id"),
+    ("coverage", "-hist", "--hist"),
+    ("coverage", "-mean", "--mean"),
+    ("slop", "-pct", "--pct"),
+    ("genomecov", "-bg", "--bg"),
+    ("genomecov", "-bga", "--bga"),
+    ("genomecov", "-scale", "--scale"),
+    ("multiinter", "-names", "--names"),
+    ("shuffle", "-chrom", "--chrom"),
+    ("shuffle", "-seed", "--seed"),
+    ("shuffle", "-incl", "--include"),
+    ("shuffle", "-excl", "--exclude"),
+];
+
+/// bedtools options with no grit equivalent, as (subcommand, bedtools flag).
+/// They are rejected by name rather than being read as a bundle of short
+/// flags (`-split` as `-s -p ...`).
+const BEDTOOLS_UNSUPPORTED: &[(&str, &str)] = &[
+    ("intersect", "-split"),
+    ("coverage", "-split"),
+    ("genomecov", "-split"),
+    ("intersect", "-mdb"),
+    ("closest", "-mdb"),
+];
+
+/// grit spelling of a bedtools flag for `subcommand`, if it differs.
+fn translate_bedtools_flag(subcommand: &str, flag: &str) -> Option<&'static str> {
+    BEDTOOLS_FLAGS
+        .iter()
+        .find(|(cmd, from, _)| (*cmd == "*" || *cmd == subcommand) && *from == flag)
+        .map(|(_, _, to)| *to)
+}
+
+/// Preprocess CLI arguments to support bedtools-style flags.
+///
+/// `-wa`/`-wb` become `--wa`/`--wb` everywhere, and the spellings in
+/// [`BEDTOOLS_FLAGS`] are translated for the subcommand they belong to, with
+/// a warning listing the translations. `grit bedtools <cmd> ...` is rewritten
+/// to `grit <cmd> ...` and translates silently, so legacy command lines only
+/// need `bedtools` replaced by `grit bedtools`.
+fn preprocess_args() -> Vec<String> {
+    let mut args: Vec<String> = std::env::args()
+        .map(|arg| match arg.as_str() {
+            "-wa" => "--wa".to_string(),
+            "-wb" => "--wb".to_string(),
+            _ => arg,
+        })
+        .collect();
+
+    // Locate the subcommand, skipping global options and their values
+    let mut pos = 1;
+    while pos < args.len() && args[pos].starts_with('-') {
+        pos += if GLOBAL_VALUE_OPTIONS.contains(&args[pos]) {
+            2
+        } else {
+            1
+        };
+    }
+
+    let shim = args.get(pos).map(String::as_str) == Some("bedtools");
+    if shim {
+        args.remove(pos);
+    }
+
+    // Resolve legacy names such as `genomeCoverageBed` to the subcommand
+    let subcommand = args.get(pos).and_then(|name| {
+        Cli::command()
+            .find_subcommand(name)
+            .map(|cmd| cmd.get_name().to_string())
+    });
+    let Some(subcommand) = subcommand else {
+        return args;
+    };
+
+    let mut translated: Vec<String> = Vec::new();
+    for arg in &mut args[pos + 1..] {
+        if arg == "--" {
+            break;
+        }
+        if BEDTOOLS_UNSUPPORTED.contains(&(subcommand.as_str(), arg.as_str())) {
+            eprintln!(
+                "Error: bedtools option '{}' is not supported by grit {}",
+                arg, subcommand
+            );
+            process::exit(2);
+        }
+        if let Some(flag) = translate_bedtools_flag(&subcommand, arg) {
+            let note = format!("{} -> {}", arg, flag);
+            if !translated.contains(&note) {
+                translated.push(note);
+            }
+            *arg = flag.to_string();
+        }
+    }
+    if !shim && !translated.is_empty() {
+        eprintln!(
+            "Warning: translated bedtools options: {}",
+            translated.join(", ")
+        );
+    }
+
+    args
+}
+
+fn main() {
+    let cli = Cli::parse_from(preprocess_args());
+
+    // Configure bedtools-compatible mode if requested
+    // This must be set before any parsing occurs
+    if cli.bedtools_compatible {
+        grit_genomics::config::set_bedtools_compatible(true);
+    }
+    if let Some(delim) = grit_genomics::config::Delimiter::parse(&cli.delim) {
+        grit_genomics::config::set_delimiter(delim);
+    }
+    if let Some(format) = grit_genomics::config::ScoreFormat::parse(&cli.score_format) {
+        grit_genomics::config::set_score_format(format);
+    }
+    match grit_genomics::config::ChromFilter::new(
+        cli.keep_chroms.as_deref(),
+        cli.drop_chroms.as_deref(),
+    ) {
+        Ok(filter) => grit_genomics::config::set_chrom_filter(Some(filter)),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+    set_output_limits(cli.max_output_lines, cli.max_output_bytes);
+    set_input_encoding(StreamEncoding::parse(&cli.input_encoding).unwrap_or_default());
+    set_output_encoding(StreamEncoding::parse(&cli.output_encoding).unwrap_or_default());
+    grit_genomics::scratch::set_temp_dir(cli.temp_dir.clone());
+    set_io_threads(cli.io_threads);
+
+    // Configure thread pool if --threads (or --pin-threads) specified
+    let pool = cli
+        .threads
+        .map(|n| rayon::ThreadPoolBuilder::new().num_threads(n));
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    let pool = match cli.pin_threads.as_deref().and_then(ThreadPlacement::parse) {
+        Some(placement) => match placement.apply(pool.unwrap_or_default()) {
+            Ok(builder) => Some(builder),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        },
+        None => pool,
+    };
+    if let Some(builder) = pool {
+        builder
+            .build_global()
+            .expect("Failed to initialize thread pool");
+    }
+
+    if cli.explain {
+        match explain_command(&cli.command) {
+            Ok(plan) => print!("{}", plan),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if cli.provenance
+        && !matches!(
+            cli.command,
+            Commands::Generate { .. } | Commands::Help { .. }
+        )
+    {
+        if let Err(e) = write_provenance(&cli.command) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+
+    let result = match cli.command {
+        Commands::Sort {
+            input,
+            genome,
+            size_asc,
+            size_desc,
+            reverse,
             chrom_only,
             fast,
             stats,
@@ -554,6 +1740,14 @@ fn main() {
             strand,
             in_memory,
             count,
+            min_count,
+            distance_pct,
+            distance_basis,
+            no_book_ended,
+            id,
+            names,
+            best_by,
+            scores,
             stats,
             assume_sorted,
             genome,
@@ -563,6 +1757,14 @@ fn main() {
             strand,
             in_memory,
             count,
+            min_count,
+            distance_pct,
+            distance_basis,
+            !no_book_ended,
+            id,
+            names,
+            best_by,
+            scores,
             stats,
             assume_sorted,
             genome,
@@ -577,12 +1779,26 @@ fn main() {
             no_overlap,
             fraction,
             reciprocal,
+            min_overlap_bp,
             count,
+            max_hits,
+            overlap_fractions,
+            write_overlap,
+            write_all_overlap,
+            left_outer_join,
             streaming,
             stats,
             assume_sorted,
             allow_unsorted,
             genome,
+            invert_b,
+            clip_to_genome,
+            min_b_score,
+            max_b_score,
+            indexed,
+            dedup_b,
+            dedup_b_count,
+            output_format,
         } => run_intersect(
             file_a,
             file_b,
@@ -592,12 +1808,24 @@ fn main() {
             no_overlap,
             fraction,
             reciprocal,
+            min_overlap_bp,
             count,
+            max_hits,
+            overlap_fractions,
+            write_overlap || write_all_overlap,
+            left_outer_join || write_all_overlap,
             streaming,
             stats,
             assume_sorted,
             allow_unsorted,
             genome,
+            invert_b,
+            clip_to_genome,
+            ScoreFilter::new(min_b_score, max_b_score),
+            indexed,
+            dedup_b,
+            dedup_b_count,
+            OutputFormat::parse(&output_format).unwrap_or_default(),
         ),
 
         Commands::Subtract {
@@ -606,22 +1834,28 @@ fn main() {
             remove_entire,
             fraction,
             reciprocal,
+            min_overlap_bp,
             streaming,
             stats,
             assume_sorted,
             allow_unsorted,
             genome,
+            keep_suppressed,
+            clip_to_genome,
         } => run_subtract(
             file_a,
             file_b,
             remove_entire,
             fraction,
             reciprocal,
+            min_overlap_bp,
             streaming,
             stats,
             assume_sorted,
             allow_unsorted,
             genome,
+            keep_suppressed,
+            clip_to_genome,
         ),
 
         Commands::Closest {
@@ -637,6 +1871,12 @@ fn main() {
             assume_sorted,
             allow_unsorted,
             genome,
+            histogram,
+            bin_size,
+            output_format,
+            dedup_b_name,
+            dedup_b,
+            dedup_b_count,
         } => run_closest(
             file_a,
             file_b,
@@ -650,6 +1890,11 @@ fn main() {
             assume_sorted,
             allow_unsorted,
             genome,
+            histogram.then_some(bin_size),
+            OutputFormat::parse(&output_format).unwrap_or_default(),
+            dedup_b_name,
+            dedup_b,
+            dedup_b_count,
         ),
 
         Commands::Window {
@@ -660,6 +1905,11 @@ fn main() {
             right,
             count,
             no_overlap,
+            min_overlap_bp,
+            per_a_summary,
+            dedup_b_name,
+            from_b,
+            indexed,
             assume_sorted,
             genome,
         } => run_window(
@@ -670,6 +1920,11 @@ fn main() {
             right,
             count,
             no_overlap,
+            min_overlap_bp,
+            per_a_summary,
+            dedup_b_name,
+            from_b,
+            indexed,
             assume_sorted,
             genome,
         ),
@@ -680,16 +1935,104 @@ fn main() {
             histogram,
             per_base,
             mean,
+            stddev,
+            median,
+            quantiles,
+            diversity,
+            collapse_by_name,
             assume_sorted,
             genome,
+            min_b_score,
+            max_b_score,
+            reciprocal_summary,
+            index_a,
+            output_dir,
+            output_format,
         } => run_coverage(
             file_a,
             file_b,
             histogram,
             per_base,
             mean,
+            stddev,
+            median,
+            quantiles,
+            diversity,
+            collapse_by_name,
             assume_sorted,
             genome,
+            ScoreFilter::new(min_b_score, max_b_score),
+            reciprocal_summary,
+            index_a,
+            output_dir,
+            OutputFormat::parse(&output_format).unwrap_or_default(),
+        ),
+
+        Commands::Segment {
+            file_a,
+            file_b,
+            breaks,
+            assume_sorted,
+            genome,
+            min_b_score,
+            max_b_score,
+        } => run_segment(
+            file_a,
+            file_b,
+            breaks,
+            assume_sorted,
+            genome,
+            ScoreFilter::new(min_b_score, max_b_score),
+        ),
+
+        Commands::Delta {
+            file_a,
+            file_b,
+            min_diff,
+            min_fold,
+            pseudocount,
+            min_length,
+            distance,
+            assume_sorted,
+            genome,
+        } => run_delta(
+            file_a,
+            file_b,
+            min_diff,
+            min_fold,
+            pseudocount,
+            min_length,
+            distance,
+            assume_sorted,
+            genome,
+        ),
+
+        Commands::Covariates {
+            input,
+            fasta,
+            mappability,
+        } => run_covariates(input, fasta, mappability),
+
+        Commands::Join {
+            file_a,
+            file_b,
+            key_a,
+            key_b,
+            join_type,
+            fill,
+        } => run_join(file_a, file_b, key_a, key_b, join_type, fill),
+
+        Commands::Shuffle {
+            input,
+            genome,
+            background,
+            chrom,
+            per_record,
+            seed,
+            include,
+            exclude,
+        } => run_shuffle(
+            input, genome, background, chrom, per_record, seed, include, exclude,
         ),
 
         Commands::Slop {
@@ -700,13 +2043,48 @@ fn main() {
             right,
             strand,
             pct,
-        } => run_slop(input, genome, both, left, right, strand, pct),
+            amount_col,
+            rejects,
+        } => run_slop(
+            input, genome, both, left, right, strand, pct, amount_col, rejects,
+        ),
+
+        Commands::Modify {
+            input,
+            genome,
+            center,
+            anchor,
+        } => run_modify(input, genome, center, anchor),
+
+        Commands::Cut {
+            input,
+            fields,
+            format,
+            sort,
+            genome,
+        } => run_cut(input, fields, format, sort, genome),
+
+        Commands::Convert {
+            input,
+            to,
+            from,
+            source,
+            feature_type,
+        } => run_convert(input, to, from, source, feature_type),
+
+        Commands::FixStrand {
+            input,
+            genes,
+            strict,
+        } => run_fix_strand(input, genes, strict),
 
         Commands::Complement {
             input,
             genome,
             assume_sorted,
-        } => run_complement(input, genome, assume_sorted),
+            include,
+            exclude,
+        } => run_complement(input, genome, assume_sorted, include, exclude),
 
         Commands::Genomecov {
             input,
@@ -715,8 +2093,17 @@ fn main() {
             bedgraph,
             bedgraph_all,
             scale,
+            round_scaled,
+            normalize,
+            collapse_by_name,
+            regions,
+            regions_summary,
+            thresholds,
+            chrom_classes,
             streaming,
             assume_sorted,
+            progress,
+            verbose,
         } => run_genomecov(
             input,
             genome,
@@ -724,18 +2111,77 @@ fn main() {
             bedgraph,
             bedgraph_all,
             scale,
+            round_scaled,
+            normalize,
+            collapse_by_name,
+            regions,
+            regions_summary,
+            thresholds,
+            chrom_classes,
             streaming,
             assume_sorted,
+            progress || verbose,
+            verbose,
         ),
 
-        Commands::Jaccard { file_a, file_b } => run_jaccard(file_a, file_b),
+        Commands::Jaccard {
+            file_a,
+            file_b,
+            extended,
+            genome,
+            manifest,
+            pairs,
+            against,
+        } => run_jaccard(file_a, file_b, extended, genome, manifest, pairs, against),
+
+        Commands::Enrich {
+            file_a,
+            file_b,
+            genome,
+            metric,
+            shuffles,
+            background,
+            seed,
+            include,
+            exclude,
+        } => run_enrich(
+            file_a, file_b, genome, metric, shuffles, background, seed, include, exclude,
+        ),
 
         Commands::Multiinter {
             inputs,
+            manifest,
+            names,
             cluster,
             streaming,
             assume_sorted,
-        } => run_multiinter(inputs, cluster, streaming, assume_sorted),
+            max_open_files,
+            sparse,
+            expand,
+        } => run_multiinter(
+            inputs,
+            manifest,
+            names,
+            cluster,
+            streaming,
+            assume_sorted,
+            max_open_files,
+            sparse,
+            expand,
+        ),
+
+        Commands::Stab {
+            input,
+            queries,
+            regions,
+            build_index,
+        } => run_stab(input, queries, regions, build_index),
+
+        Commands::Index {
+            action: IndexAction::Upgrade { files, dry_run },
+        } => run_index_upgrade(files, dry_run),
+
+        Commands::Cat { inputs } => run_cat(inputs),
 
         Commands::Generate {
             output,
@@ -766,14 +2212,322 @@ fn main() {
             len_max,
             force,
         ),
+
+        Commands::Help { topic } => run_help(topic),
     };
 
+    if let Some(limit) = output_limit_exceeded() {
+        eprintln!("Warning: {}", limit);
+        process::exit(3);
+    }
     if let Err(e) = result {
         eprintln!("Error: {}", e);
         process::exit(1);
     }
 }
 
+/// Build the `--explain` plan for a command, mirroring the choices made by
+/// its `run_*` function.
+fn explain_command(command: &Commands) -> Result<Plan, BedError> {
+    let check = |assume_sorted: bool| {
+        if assume_sorted {
+            Validation::Assumed
+        } else {
+            Validation::PrePass
+        }
+    };
+
+    match command {
+        Commands::Sort {
+            input,
+            size_asc,
+            size_desc,
+            chrom_only,
+            ..
+        } => {
+            let input = input.clone().unwrap_or_else(|| PathBuf::from("-"));
+            let plan = Plan::new("sort", Strategy::InMemory, Validation::NotRequired).with_input(
+                "A",
+                &input,
+                Residency::Loaded,
+            )?;
+            Ok(if *size_asc || *size_desc || *chrom_only {
+                plan.with_note("size and chromosome-only orders use the comparison sort")
+            } else {
+                plan.with_note("radix sort over a memory-mapped input")
+            })
+        }
+
+        Commands::Merge {
+            input,
+            strand,
+            in_memory,
+            assume_sorted,
+            id,
+            names,
+            best_by,
+            scores,
+            ..
+        } => {
+            let input = input.clone().unwrap_or_else(|| PathBuf::from("-"));
+            if *in_memory {
+                return Plan::new("merge", Strategy::InMemory, Validation::SortedInMemory)
+                    .with_input("A", &input, Residency::Loaded);
+            }
+            let stdin = input.to_string_lossy() == "-";
+            // Unsorted stdin is buffered whole so it can be checked first
+            let residency = if stdin && !assume_sorted {
+                Residency::Loaded
+            } else {
+                Residency::Streamed
+            };
+            let mut plan = Plan::new("merge", Strategy::Streaming, check(*assume_sorted))
+                .with_input("A", &input, residency)?;
+            if stdin && !assume_sorted {
+                plan = plan.with_note("stdin is buffered in memory for the sort check");
+            }
+            if *strand {
+                plan = plan.with_note("strand-aware merge uses the general streaming path");
+            } else if id.is_some() || *names || best_by.is_some() || scores.is_some() {
+                plan = plan
+                    .with_note("--id/--names/--best-by/--scores use the general streaming path");
+            }
+            Ok(plan)
+        }
+
+        Commands::Intersect {
+            file_a,
+            file_b,
+            streaming,
+            assume_sorted,
+            allow_unsorted,
+            invert_b,
+            indexed,
+            ..
+        } => {
+            if *indexed {
+                return explain_indexed("intersect", file_a, file_b, Residency::Loaded);
+            }
+            if *invert_b {
+                return Ok(
+                    Plan::new("intersect", Strategy::Streaming, check(*assume_sorted))
+                        .with_input("A", file_a, Residency::Streamed)?
+                        .with_input("B", file_b, Residency::Streamed)?
+                        .with_note("the complement of B is generated from the genome on the fly"),
+                );
+            }
+            explain_pair(
+                "intersect",
+                file_a,
+                file_b,
+                *streaming,
+                *assume_sorted,
+                *allow_unsorted,
+            )
+            .map(with_streaming_hint)
+        }
+
+        Commands::Subtract {
+            file_a,
+            file_b,
+            streaming,
+            assume_sorted,
+            allow_unsorted,
+            ..
+        } => explain_pair(
+            "subtract",
+            file_a,
+            file_b,
+            *streaming,
+            *assume_sorted,
+            *allow_unsorted,
+        )
+        .map(with_streaming_hint),
+
+        Commands::Closest {
+            file_a,
+            file_b,
+            max_distance,
+            streaming,
+            assume_sorted,
+            allow_unsorted,
+            histogram,
+            ..
+        } => {
+            let streams = *streaming && !histogram && max_distance.is_none();
+            let plan = explain_pair(
+                "closest",
+                file_a,
+                file_b,
+                streams,
+                *assume_sorted,
+                *allow_unsorted,
+            )?;
+            Ok(if *streaming && !streams {
+                plan.with_note("--histogram and -D always run in memory, even with --streaming")
+            } else {
+                with_streaming_hint(plan)
+            })
+        }
+
+        Commands::Window {
+            file_a,
+            file_b,
+            assume_sorted,
+            indexed,
+            ..
+        } => {
+            if *indexed {
+                return explain_indexed("window", file_a, file_b, Residency::Streamed);
+            }
+            Plan::new("window", Strategy::Streaming, check(*assume_sorted))
+                .with_input("A", file_a, Residency::Streamed)?
+                .with_input("B", file_b, Residency::Streamed)
+        }
+
+        Commands::Segment {
+            file_a,
+            file_b,
+            assume_sorted,
+            ..
+        } => Plan::new("segment", Strategy::Streaming, check(*assume_sorted))
+            .with_input("A", file_a, Residency::Streamed)?
+            .with_input("B", file_b, Residency::Streamed),
+
+        Commands::Delta {
+            file_a,
+            file_b,
+            assume_sorted,
+            ..
+        } => Plan::new("delta", Strategy::Streaming, check(*assume_sorted))
+            .with_input("A", file_a, Residency::Streamed)?
+            .with_input("B", file_b, Residency::Streamed),
+
+        Commands::Coverage {
+            file_a,
+            file_b,
+            assume_sorted,
+            index_a,
+            ..
+        } => {
+            if *index_a {
+                return Ok(
+                    Plan::new("coverage", Strategy::Streaming, Validation::NotRequired)
+                        .with_input("A", &file_a[0], Residency::Loaded)?
+                        .with_input("B", file_b, Residency::Streamed)?
+                        .with_note("A is indexed in memory; B is read once in any order"),
+                );
+            }
+            let mut plan = Plan::new("coverage", Strategy::Streaming, check(*assume_sorted));
+            for (i, a) in file_a.iter().enumerate() {
+                let label = if file_a.len() == 1 {
+                    "A".to_string()
+                } else {
+                    format!("A{}", i + 1)
+                };
+                plan = plan.with_input(&label, a, Residency::Streamed)?;
+            }
+            let plan = plan.with_input("B", file_b, Residency::Streamed)?;
+            if file_a.len() > 1 {
+                return Ok(plan.with_note("A files are merged and share one pass over B"));
+            }
+            Ok(plan)
+        }
+
+        Commands::Genomecov {
+            input,
+            streaming,
+            assume_sorted,
+            ..
+        } => {
+            if *streaming || *assume_sorted {
+                Ok(
+                    Plan::new("genomecov", Strategy::Streaming, Validation::Assumed)
+                        .with_input("A", input, Residency::Streamed)?
+                        .with_note("input order is not checked; sort it with 'grit sort' first"),
+                )
+            } else {
+                Plan::new("genomecov", Strategy::InMemory, Validation::SortedInMemory).with_input(
+                    "A",
+                    input,
+                    Residency::Loaded,
+                )
+            }
+        }
+
+        _ => Err(BedError::InvalidFormat(
+            "--explain supports sort, merge, intersect, subtract, closest, window, coverage, \
+             segment, delta and genomecov"
+                .to_string(),
+        )),
+    }
+}
+
+/// Point in-memory plans at `--streaming`.
+fn with_streaming_hint(plan: Plan) -> Plan {
+    if plan.strategy == Strategy::InMemory {
+        plan.with_note("add --streaming to bound memory for sorted input")
+    } else {
+        plan
+    }
+}
+
+/// Plan for a two-file command with a streaming and an in-memory path.
+fn explain_pair(
+    command: &str,
+    file_a: &PathBuf,
+    file_b: &PathBuf,
+    streaming: bool,
+    assume_sorted: bool,
+    allow_unsorted: bool,
+) -> Result<Plan, BedError> {
+    if streaming {
+        let validation = if assume_sorted {
+            Validation::Assumed
+        } else {
+            Validation::PrePass
+        };
+        Plan::new(command, Strategy::Streaming, validation)
+            .with_input("A", file_a, Residency::Streamed)?
+            .with_input("B", file_b, Residency::Streamed)
+    } else {
+        let validation = if allow_unsorted {
+            Validation::SortedInMemory
+        } else {
+            Validation::PrePass
+        };
+        Plan::new(command, Strategy::InMemory, validation)
+            .with_input("A", file_a, Residency::Loaded)?
+            .with_input("B", file_b, Residency::Loaded)
+    }
+}
+
+/// Plan for `--indexed`: A is read in any order and looked up in B.
+fn explain_indexed(
+    command: &str,
+    file_a: &PathBuf,
+    file_b: &PathBuf,
+    a_residency: Residency,
+) -> Result<Plan, BedError> {
+    let sidecar = FileIndex::sidecar_path(file_b);
+    let note = if sidecar.exists() {
+        format!("B is looked up through {}", sidecar.display())
+    } else {
+        format!(
+            "{} does not exist; B is scanned once to index it in memory \
+             (write it with 'grit stab -i {} --build-index')",
+            sidecar.display(),
+            file_b.display()
+        )
+    };
+    Ok(
+        Plan::new(command, Strategy::Indexed, Validation::NotRequired)
+            .with_input("A", file_a, a_residency)?
+            .with_input("B", file_b, Residency::Mapped)?
+            .with_note(note),
+    )
+}
+
 fn run_sort(
     input: Option<PathBuf>,
     genome: Option<PathBuf>,
@@ -784,8 +2538,7 @@ fn run_sort(
     _fast: bool, // Legacy flag, fast mode is now default
     stats: bool,
 ) -> Result<(), BedError> {
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
     // Load genome file if provided
     let genome = genome.map(|p| Genome::from_file(&p)).transpose()?;
@@ -816,6 +2569,7 @@ fn run_sort(
 
         if stats {
             eprintln!("Fast sort stats: {}", result);
+            report_parse_irregularities();
         }
 
         Ok(())
@@ -853,16 +2607,65 @@ fn validate_sorted(path: &PathBuf, genome: Option<&Genome>) -> Result<(), BedErr
     }
 }
 
+/// Buffer stdin and check that it is sorted. Input that cannot be decoded
+/// with `--if binary` is reported as such rather than as unsorted.
+fn verify_sorted_stdin() -> Result<Vec<u8>, BedError> {
+    let mut reader = stdin_reader();
+    let binary = matches!(reader, InputReader::Binary(_));
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer).map_err(|e| {
+        if binary {
+            BedError::InvalidFormat(format!("invalid binary stream on stdin: {}", e))
+        } else {
+            BedError::Io(e)
+        }
+    })?;
+    verify_sorted_bytes(&buffer).map_err(|e| {
+        BedError::InvalidFormat(format!(
+            "stdin is not sorted: {}\n\n\
+             Fix: Pre-sort your input before piping.\n\
+             Or use '--assume-sorted' if you know the input is sorted.",
+            e
+        ))
+    })?;
+    Ok(buffer)
+}
+
 fn run_merge(
     input: Option<PathBuf>,
     distance: u64,
     strand: bool,
     in_memory: bool,
     count: bool,
+    min_count: usize,
+    distance_pct: Option<f64>,
+    distance_basis: String,
+    book_ended: bool,
+    id: Option<String>,
+    names: bool,
+    best_by: Option<usize>,
+    scores: Option<String>,
     stats: bool,
     assume_sorted: bool,
     genome_path: Option<PathBuf>,
 ) -> Result<(), BedError> {
+    check_column("--best-by", best_by)?;
+    let id = id.as_deref().and_then(MergeId::parse);
+    let scores = scores.as_deref().and_then(ScoreOp::parse);
+    let relative_distance = match distance_pct {
+        Some(pct) if !(pct >= 0.0 && pct.is_finite()) => {
+            return Err(BedError::InvalidFormat(format!(
+                "--distance-pct must be a non-negative number, got {}",
+                pct
+            )));
+        }
+        Some(pct) => Some(RelativeDistance::new(
+            pct,
+            DistanceBasis::parse(&distance_basis).unwrap_or_default(),
+        )),
+        None => None,
+    };
+
     // Load genome file if provided
     let genome =
         if let Some(ref gp) = genome_path {
@@ -872,49 +2675,54 @@ fn run_merge(
         } else {
             None
         };
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
     if in_memory {
         // Use in-memory mode - loads all records, can handle unsorted input
         let cmd = MergeCommand::new()
             .with_distance(distance)
-            .with_strand(strand);
+            .with_strand(strand)
+            .with_count(count)
+            .with_min_count(min_count)
+            .with_relative_distance(relative_distance)
+            .with_book_ended(book_ended)
+            .with_id(id)
+            .with_names(names)
+            .with_best_by(best_by)
+            .with_scores(scores);
 
         if let Some(path) = input {
             if path.to_string_lossy() == "-" {
-                let stdin = io::stdin();
-                let reader = BedReader::new(stdin.lock());
+                let reader = BedReader::new(stdin_reader());
                 cmd.merge_streaming(reader, &mut handle)
             } else {
                 cmd.run(path, &mut handle)
             }
         } else {
-            let stdin = io::stdin();
-            let reader = BedReader::new(stdin.lock());
+            let reader = BedReader::new(stdin_reader());
             cmd.merge_streaming(reader, &mut handle)
         }
-    } else if strand {
-        // Strand-specific merge not yet implemented in fast path, use standard streaming
+    } else if strand || id.is_some() || names || best_by.is_some() || scores.is_some() {
+        // Strand-specific merge, label and score columns and representatives
+        // are not implemented in the BED3-only fast path, use standard streaming
         use grit_genomics::commands::StreamingMergeCommand;
         let mut cmd = StreamingMergeCommand::new()
             .with_distance(distance)
-            .with_strand(strand);
+            .with_strand(strand)
+            .with_min_count(min_count)
+            .with_relative_distance(relative_distance)
+            .with_book_ended(book_ended)
+            .with_id(id)
+            .with_names(names)
+            .with_best_by(best_by)
+            .with_scores(scores);
         cmd.count = count;
 
         let result = if let Some(path) = input {
             if path.to_string_lossy() == "-" {
                 // Stdin: validate by buffering, then process
                 if !assume_sorted {
-                    let stdin = io::stdin();
-                    let buffer = verify_sorted_reader(stdin.lock()).map_err(|e| {
-                        BedError::InvalidFormat(format!(
-                            "stdin is not sorted: {}\n\n\
-                             Fix: Pre-sort your input before piping.\n\
-                             Or use '--assume-sorted' if you know the input is sorted.",
-                            e
-                        ))
-                    })?;
+                    let buffer = verify_sorted_stdin()?;
                     let cursor = std::io::Cursor::new(buffer);
                     let reader = BedReader::new(cursor);
                     cmd.run_streaming(reader, &mut handle)?
@@ -944,15 +2752,7 @@ fn run_merge(
         } else {
             // No path specified: read from stdin
             if !assume_sorted {
-                let stdin = io::stdin();
-                let buffer = verify_sorted_reader(stdin.lock()).map_err(|e| {
-                    BedError::InvalidFormat(format!(
-                        "stdin is not sorted: {}\n\n\
-                         Fix: Pre-sort your input before piping.\n\
-                         Or use '--assume-sorted' if you know the input is sorted.",
-                        e
-                    ))
-                })?;
+                let buffer = verify_sorted_stdin()?;
                 let cursor = std::io::Cursor::new(buffer);
                 let reader = BedReader::new(cursor);
                 cmd.run_streaming(reader, &mut handle)?
@@ -963,27 +2763,24 @@ fn run_merge(
 
         if stats {
             eprintln!("Streaming merge stats: {}", result);
+            report_parse_irregularities();
         }
 
         Ok(())
     } else {
         // Use fast streaming mode (default) - O(1) memory, zero-allocation parsing
-        let mut cmd = FastMergeCommand::new().with_distance(distance);
+        let mut cmd = FastMergeCommand::new()
+            .with_distance(distance)
+            .with_min_count(min_count)
+            .with_relative_distance(relative_distance)
+            .with_book_ended(book_ended);
         cmd.count = count;
 
         let result = if let Some(path) = input {
             if path.to_string_lossy() == "-" {
                 // Stdin: validate by buffering, then process
                 if !assume_sorted {
-                    let stdin = io::stdin();
-                    let buffer = verify_sorted_reader(stdin.lock()).map_err(|e| {
-                        BedError::InvalidFormat(format!(
-                            "stdin is not sorted: {}\n\n\
-                             Fix: Pre-sort your input before piping.\n\
-                             Or use '--assume-sorted' if you know the input is sorted.",
-                            e
-                        ))
-                    })?;
+                    let buffer = verify_sorted_stdin()?;
                     let cursor = std::io::Cursor::new(buffer);
                     cmd.run_reader(cursor, &mut handle)?
                 } else {
@@ -1012,15 +2809,7 @@ fn run_merge(
         } else {
             // No path specified: read from stdin
             if !assume_sorted {
-                let stdin = io::stdin();
-                let buffer = verify_sorted_reader(stdin.lock()).map_err(|e| {
-                    BedError::InvalidFormat(format!(
-                        "stdin is not sorted: {}\n\n\
-                         Fix: Pre-sort your input before piping.\n\
-                         Or use '--assume-sorted' if you know the input is sorted.",
-                        e
-                    ))
-                })?;
+                let buffer = verify_sorted_stdin()?;
                 let cursor = std::io::Cursor::new(buffer);
                 cmd.run_reader(cursor, &mut handle)?
             } else {
@@ -1030,12 +2819,42 @@ fn run_merge(
 
         if stats {
             eprintln!("Fast merge stats: {}", result);
+            report_parse_irregularities();
         }
 
         Ok(())
     }
 }
 
+/// Write copies of `inputs` clipped to the chromosome sizes of `genome`,
+/// reporting the records changed in each on stderr.
+///
+/// The copies keep their file names so BedGraph inputs are still detected;
+/// an input of `-` is read from stdin.
+fn clip_inputs(
+    genome: &Genome,
+    inputs: [&PathBuf; 2],
+) -> Result<(ScratchDir, [PathBuf; 2]), BedError> {
+    let scratch = ScratchDir::new()?;
+    let mut clipped = [PathBuf::new(), PathBuf::new()];
+    for ((label, path), copy) in ["A", "B"].into_iter().zip(inputs).zip(&mut clipped) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut out = std::io::BufWriter::new(scratch.create(&format!("{}-{}", label, name))?);
+        let stats = if path.as_os_str() == "-" {
+            genome.clip_bed(std::io::stdin().lock(), &mut out)?
+        } else {
+            let file = File::open(path).map_err(|e| {
+                BedError::InvalidFormat(format!("Failed to open '{}': {}", path.display(), e))
+            })?;
+            genome.clip_bed(BufReader::new(file), &mut out)?
+        };
+        out.flush()?;
+        eprintln!("Clipped {} to genome: {}", label, stats);
+        *copy = out.get_ref().path().to_path_buf();
+    }
+    Ok((scratch, clipped))
+}
+
 fn run_intersect(
     file_a: PathBuf,
     file_b: PathBuf,
@@ -1045,12 +2864,24 @@ fn run_intersect(
     no_overlap: bool,
     fraction: Option<f64>,
     reciprocal: bool,
+    min_overlap_bp: Option<u64>,
     count: bool,
+    max_hits: Option<usize>,
+    overlap_fractions: bool,
+    write_overlap: bool,
+    left_outer: bool,
     streaming: bool,
     stats: bool,
     assume_sorted: bool,
     allow_unsorted: bool,
     genome_path: Option<PathBuf>,
+    invert_b: bool,
+    clip_to_genome: bool,
+    b_score: ScoreFilter,
+    indexed: bool,
+    dedup_b: bool,
+    dedup_b_count: bool,
+    output_format: OutputFormat,
 ) -> Result<(), BedError> {
     // Load genome file if provided
     let genome =
@@ -1062,19 +2893,128 @@ fn run_intersect(
             None
         };
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    // Inputs are read from clipped copies in scratch space, which live until
+    // the run ends; messages keep naming the user's files
+    let (input_a, input_b, _clipped) = match genome.as_ref().filter(|_| clip_to_genome) {
+        Some(genome) => {
+            let (scratch, [input_a, input_b]) = clip_inputs(genome, [&file_a, &file_b])?;
+            (input_a, input_b, Some(scratch))
+        }
+        None => (file_a.clone(), file_b.clone(), None),
+    };
+
+    // BedGraph B filters on its value column and names it in structured output
+    let b_bedgraph = is_bedgraph(&input_b);
+    let b_score = b_score.with_bedgraph(b_bedgraph);
+
+    // Same precedence as the output modes of the intersect commands
+    let layout = if no_overlap {
+        Layout::new()
+    } else if count {
+        Layout::new().with_fields(["count"])
+    } else if !unique && overlap_fractions {
+        Layout::new()
+            .with_b()
+            .with_fields(["overlap_bp", "a_fraction", "b_fraction"])
+    } else if write_overlap {
+        Layout::new().with_b().with_fields(["overlap_bp"])
+    } else if left_outer {
+        Layout::new().with_b()
+    } else if !unique && write_b {
+        Layout::new().with_b().with_overlap_bp()
+    } else {
+        Layout::new()
+    }
+    .with_bedgraph(is_bedgraph(&input_a), b_bedgraph);
+    let mut handle = FormattedWriter::new(limited_stdout(), output_format, layout);
     let genome_flag = if genome.is_some() {
         " -g <genome.txt>"
     } else {
         ""
     };
 
+    if invert_b {
+        // Complement of B is computed on the fly, which needs chromosome sizes
+        let genome = genome.as_ref().ok_or_else(|| {
+            BedError::InvalidFormat(
+                "--invert-b requires a genome file (-g) to define the complement of B".to_string(),
+            )
+        })?;
+
+        if !assume_sorted {
+            validate_sorted(&input_a, Some(genome)).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "File A is not sorted: {}\n\n\
+                     Fix: Run 'grit sort -i {} -g <genome.txt>' > sorted_a.bed first.",
+                    e,
+                    file_a.display()
+                ))
+            })?;
+            validate_sorted(&input_b, Some(genome)).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "File B is not sorted: {}\n\n\
+                     Fix: Run 'grit sort -i {} -g <genome.txt>' > sorted_b.bed first.",
+                    e,
+                    file_b.display()
+                ))
+            })?;
+        }
+
+        let mut cmd = StreamingIntersectCommand::new();
+        cmd.write_a = write_a;
+        cmd.write_b = write_b;
+        cmd.unique = unique;
+        cmd.no_overlap = no_overlap;
+        cmd.fraction_a = fraction;
+        cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
+        cmd.count = count;
+        cmd.max_hits = max_hits;
+        cmd.overlap_fractions = overlap_fractions;
+        cmd.write_overlap = write_overlap;
+        cmd.left_outer = left_outer;
+        cmd.b_score = b_score;
+        cmd.assume_sorted = true;
+
+        let result = cmd.run_invert_b(&input_a, &input_b, genome, &mut handle)?;
+        handle.finish()?;
+
+        if stats {
+            eprintln!("Streaming intersect stats: {}", result);
+            report_parse_irregularities();
+        }
+
+        return Ok(());
+    }
+
+    if indexed {
+        // B's index stands in for sort validation, and A is read in any order
+        let b = open_indexed_b(&input_b)?;
+        let mut cmd = IntersectCommand::new();
+        cmd.write_a = write_a;
+        cmd.write_b = write_b;
+        cmd.unique = unique;
+        cmd.no_overlap = no_overlap;
+        cmd.fraction_a = fraction;
+        cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
+        cmd.count = count;
+        cmd.max_hits = max_hits;
+        cmd.overlap_fractions = overlap_fractions;
+        cmd.write_overlap = write_overlap;
+        cmd.left_outer = left_outer;
+        cmd.b_score = b_score;
+
+        cmd.run_indexed(&input_a, &b, &mut handle)?;
+        handle.finish()?;
+        return Ok(());
+    }
+
     if streaming {
         // Use streaming mode - constant memory, requires sorted input
         // Only validate sorted order if --assume-sorted is not set
         if !assume_sorted {
-            validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_a, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File A is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_a.bed first.\n\
@@ -1084,7 +3024,7 @@ fn run_intersect(
                     genome_flag
                 ))
             })?;
-            validate_sorted(&file_b, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_b, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File B is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_b.bed first.\n\
@@ -1103,21 +3043,31 @@ fn run_intersect(
         cmd.no_overlap = no_overlap;
         cmd.fraction_a = fraction;
         cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
         cmd.count = count;
+        cmd.max_hits = max_hits;
+        cmd.overlap_fractions = overlap_fractions;
+        cmd.write_overlap = write_overlap;
+        cmd.left_outer = left_outer;
+        cmd.b_score = b_score;
+        cmd.dedup_b = dedup_b;
+        cmd.dedup_b_counts = dedup_b_count;
         // Always skip inline validation in streaming mode - we either validated above or user assumes sorted
         cmd.assume_sorted = true;
 
-        let result = cmd.run(&file_a, &file_b, &mut handle)?;
+        let result = cmd.run(&input_a, &input_b, &mut handle)?;
+        handle.finish()?;
 
         if stats {
             eprintln!("Streaming intersect stats: {}", result);
+            report_parse_irregularities();
         }
 
         Ok(())
     } else {
         // Non-streaming mode: validate sorted input unless --allow-unsorted
         if !allow_unsorted {
-            validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_a, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File A is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_a.bed first.\n\
@@ -1128,7 +3078,7 @@ fn run_intersect(
                     genome_flag
                 ))
             })?;
-            validate_sorted(&file_b, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_b, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File B is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_b.bed first.\n\
@@ -1149,9 +3099,17 @@ fn run_intersect(
         cmd.no_overlap = no_overlap;
         cmd.fraction_a = fraction;
         cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
         cmd.count = count;
-
-        cmd.run(file_a, file_b, &mut handle)
+        cmd.max_hits = max_hits;
+        cmd.overlap_fractions = overlap_fractions;
+        cmd.write_overlap = write_overlap;
+        cmd.left_outer = left_outer;
+        cmd.b_score = b_score;
+
+        cmd.run(&input_a, &input_b, &mut handle)?;
+        handle.finish()?;
+        Ok(())
     }
 }
 
@@ -1161,11 +3119,14 @@ fn run_subtract(
     remove_entire: bool,
     fraction: Option<f64>,
     reciprocal: bool,
+    min_overlap_bp: Option<u64>,
     streaming: bool,
     stats: bool,
     assume_sorted: bool,
     allow_unsorted: bool,
     genome_path: Option<PathBuf>,
+    keep_suppressed: Option<PathBuf>,
+    clip_to_genome: bool,
 ) -> Result<(), BedError> {
     // Load genome file if provided
     let genome =
@@ -1177,19 +3138,36 @@ fn run_subtract(
             None
         };
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    // Inputs are read from clipped copies in scratch space, which live until
+    // the run ends; messages keep naming the user's files
+    let (input_a, input_b, _clipped) = match genome.as_ref().filter(|_| clip_to_genome) {
+        Some(genome) => {
+            let (scratch, [input_a, input_b]) = clip_inputs(genome, [&file_a, &file_b])?;
+            (input_a, input_b, Some(scratch))
+        }
+        None => (file_a.clone(), file_b.clone(), None),
+    };
+
+    let mut handle = limited_stdout();
     let genome_flag = if genome.is_some() {
         " -g <genome.txt>"
     } else {
         ""
     };
 
+    // Suppressed A records go to a side file in the same pass
+    let mut suppressed = match keep_suppressed {
+        Some(ref path) => Some(std::fs::File::create(path).map_err(|e| {
+            BedError::InvalidFormat(format!("Failed to create '{}': {}", path.display(), e))
+        })?),
+        None => None,
+    };
+
     if streaming {
         // Use streaming mode - O(k) memory, requires sorted input
         // Validate that both input files are sorted (unless --assume-sorted)
         if !assume_sorted {
-            validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_a, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File A is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_a.bed first.\n\
@@ -1199,7 +3177,7 @@ fn run_subtract(
                     genome_flag
                 ))
             })?;
-            validate_sorted(&file_b, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_b, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File B is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_b.bed first.\n\
@@ -1215,18 +3193,21 @@ fn run_subtract(
         cmd.remove_entire = remove_entire;
         cmd.fraction = fraction;
         cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
 
-        let result = cmd.run(&file_a, &file_b, &mut handle)?;
+        let result =
+            cmd.run_with_suppressed(&input_a, &input_b, &mut handle, suppressed.as_mut())?;
 
         if stats {
             eprintln!("Streaming subtract stats: {}", result);
+            report_parse_irregularities();
         }
 
         Ok(())
     } else {
         // Non-streaming mode: validate sorted input unless --allow-unsorted
         if !allow_unsorted {
-            validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_a, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File A is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_a.bed first.\n\
@@ -1237,7 +3218,7 @@ fn run_subtract(
                     genome_flag
                 ))
             })?;
-            validate_sorted(&file_b, genome.as_ref()).map_err(|e| {
+            validate_sorted(&input_b, genome.as_ref()).map_err(|e| {
                 BedError::InvalidFormat(format!(
                     "File B is not sorted: {}\n\n\
                      Fix: Run 'grit sort -i {}{}' > sorted_b.bed first.\n\
@@ -1255,25 +3236,38 @@ fn run_subtract(
         cmd.remove_entire = remove_entire;
         cmd.fraction = fraction;
         cmd.reciprocal = reciprocal;
+        cmd.min_overlap_bp = min_overlap_bp;
 
-        cmd.run(file_a, file_b, &mut handle)
+        cmd.run_with_suppressed(&input_a, &input_b, &mut handle, suppressed.as_mut())
     }
 }
 
 fn run_closest(
     file_a: PathBuf,
     file_b: PathBuf,
-    _distance: bool,
+    distance: bool,
     tie: Option<String>,
     ignore_overlaps: bool,
     ignore_upstream: bool,
     ignore_downstream: bool,
-    _max_distance: Option<u64>,
+    max_distance: Option<u64>,
     streaming: bool,
     assume_sorted: bool,
     allow_unsorted: bool,
     genome_path: Option<PathBuf>,
+    histogram_bin_size: Option<u64>,
+    output_format: OutputFormat,
+    dedup_b_col: Option<usize>,
+    dedup_b: bool,
+    dedup_b_count: bool,
 ) -> Result<(), BedError> {
+    check_column("--dedup-b-name", dedup_b_col)?;
+    if histogram_bin_size.is_some() && output_format != OutputFormat::Bed {
+        return Err(BedError::InvalidFormat(
+            "--output-format cannot be used with --histogram".to_string(),
+        ));
+    }
+
     // Load genome file if provided
     let genome =
         if let Some(ref gp) = genome_path {
@@ -1284,14 +3278,34 @@ fn run_closest(
             None
         };
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let layout = if distance {
+        Layout::new().with_b().with_fields(["distance"])
+    } else {
+        Layout::new().with_b().with_distance()
+    };
+    let mut handle = FormattedWriter::new(limited_stdout(), output_format, layout);
     let genome_flag = if genome.is_some() {
         " -g <genome.txt>"
     } else {
         ""
     };
 
+    // The histogram keeps every distance for its quantiles and -D needs the
+    // full candidate set, so both always use the in-memory implementation
+    let streaming = streaming && histogram_bin_size.is_none() && max_distance.is_none();
+
+    // In-memory records keep BED6 columns only
+    if !streaming && dedup_b {
+        return Err(BedError::InvalidFormat(
+            "--dedup-b requires --streaming (without -D or --histogram)".to_string(),
+        ));
+    }
+    if !streaming && dedup_b_col.is_some_and(|col| col > 6) {
+        return Err(BedError::InvalidFormat(
+            "--dedup-b-name on a column past 6 requires --streaming (without -D)".to_string(),
+        ));
+    }
+
     if streaming {
         // Validate that both input files are sorted (unless --assume-sorted)
         if !assume_sorted {
@@ -1323,8 +3337,13 @@ fn run_closest(
         cmd.ignore_upstream = ignore_upstream;
         cmd.ignore_downstream = ignore_downstream;
         cmd.report_all_ties = tie.as_ref().is_none_or(|t| t == "all");
+        cmd.report_distance = distance;
+        cmd.dedup_b_col = dedup_b_col;
+        cmd.dedup_b = dedup_b;
+        cmd.dedup_b_counts = dedup_b_count;
 
         cmd.run(file_a, file_b, &mut handle)?;
+        handle.finish()?;
         Ok(())
     } else {
         // Non-streaming mode: validate sorted input unless --allow-unsorted
@@ -1356,11 +3375,12 @@ fn run_closest(
         use grit_genomics::commands::closest::TieHandling;
 
         let mut cmd = ClosestCommand::new();
-        cmd.report_distance = _distance;
+        cmd.report_distance = distance;
+        cmd.dedup_b_col = dedup_b_col;
         cmd.ignore_overlaps = ignore_overlaps;
         cmd.ignore_upstream = ignore_upstream;
         cmd.ignore_downstream = ignore_downstream;
-        cmd.max_distance = _max_distance;
+        cmd.max_distance = max_distance;
 
         if let Some(t) = tie {
             cmd.tie_handling = match t.as_str() {
@@ -1371,7 +3391,14 @@ fn run_closest(
             };
         }
 
-        cmd.run(file_a, file_b, &mut handle)
+        if let Some(bin_size) = histogram_bin_size {
+            let mut hist = cmd.distance_histogram(file_a, file_b, bin_size)?;
+            return hist.write(&mut handle);
+        }
+
+        cmd.run(file_a, file_b, &mut handle)?;
+        handle.finish()?;
+        Ok(())
     }
 }
 
@@ -1383,9 +3410,16 @@ fn run_window(
     right: Option<u64>,
     count: bool,
     no_overlap: bool,
+    min_overlap_bp: Option<u64>,
+    per_a_summary: bool,
+    dedup_b_col: Option<usize>,
+    from_b: bool,
+    indexed: bool,
     assume_sorted: bool,
     genome_path: Option<PathBuf>,
 ) -> Result<(), BedError> {
+    check_column("--dedup-b-name", dedup_b_col)?;
+
     // Load genome file if provided
     let genome =
         if let Some(ref gp) = genome_path {
@@ -1402,8 +3436,9 @@ fn run_window(
     };
 
     // Use streaming implementation for better performance
-    // Validate that both input files are sorted (unless --assume-sorted)
-    if !assume_sorted {
+    // Validate that both input files are sorted (unless --assume-sorted, or
+    // --indexed, where B's index stands in for the check)
+    if !assume_sorted && !indexed {
         validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
             BedError::InvalidFormat(format!(
                 "File A is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
@@ -1428,23 +3463,76 @@ fn run_window(
     cmd.right = right;
     cmd.count = count;
     cmd.no_overlap = no_overlap;
+    cmd.min_overlap_bp = min_overlap_bp;
+    cmd.per_a_summary = per_a_summary;
+    cmd.dedup_b_col = dedup_b_col;
+    cmd.from_b = from_b;
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
-    cmd.run(file_a, file_b, &mut handle)?;
+    if indexed {
+        let b = open_indexed_b(&file_b)?;
+        cmd.run_indexed(file_a, &b, &mut handle)?;
+    } else {
+        cmd.run(file_a, file_b, &mut handle)?;
+    }
     Ok(())
 }
 
+/// Open B for `--indexed` lookups. Without a `.gri` index B is indexed in
+/// memory, which reads it once and checks its order.
+fn open_indexed_b(file_b: &Path) -> Result<IndexedBed, BedError> {
+    if file_b.as_os_str() == "-" {
+        return Err(BedError::InvalidFormat(
+            "--indexed needs B as a seekable file; it cannot read from stdin".to_string(),
+        ));
+    }
+    IndexedBed::open(file_b, false)
+}
+
 fn run_coverage(
-    file_a: PathBuf,
+    files_a: Vec<PathBuf>,
     file_b: PathBuf,
     histogram: bool,
     per_base: bool,
     mean: bool,
+    stddev: bool,
+    median: bool,
+    quantiles: Vec<f64>,
+    diversity: Option<usize>,
+    collapse_by_name: bool,
     assume_sorted: bool,
     genome_path: Option<PathBuf>,
+    b_score: ScoreFilter,
+    reciprocal_summary: Option<PathBuf>,
+    index_a: bool,
+    output_dir: Option<PathBuf>,
+    output_format: OutputFormat,
 ) -> Result<(), BedError> {
+    if files_a.len() > 1 {
+        if output_dir.is_none() {
+            return Err(BedError::InvalidFormat(
+                "several -a files need --output-dir for their results".to_string(),
+            ));
+        }
+        if index_a || reciprocal_summary.is_some() {
+            return Err(BedError::InvalidFormat(
+                "--index-a and --reciprocal-summary take a single -a file".to_string(),
+            ));
+        }
+    }
+    if let Some(q) = quantiles.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+        return Err(BedError::InvalidFormat(format!(
+            "--quantiles values must be between 0 and 1, got {}",
+            q
+        )));
+    }
+    if diversity == Some(0) {
+        return Err(BedError::InvalidFormat(
+            "--diversity column is 1-based".to_string(),
+        ));
+    }
+
     // Load genome file if provided
     let genome =
         if let Some(ref gp) = genome_path {
@@ -1460,16 +3548,19 @@ fn run_coverage(
         ""
     };
 
-    // Validate that both input files are sorted (unless --assume-sorted)
-    if !assume_sorted {
-        validate_sorted(&file_a, genome.as_ref()).map_err(|e| {
-            BedError::InvalidFormat(format!(
-                "File A is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
-                e,
-                file_a.display(),
-                genome_flag
-            ))
-        })?;
+    // Validate that both input files are sorted (unless --assume-sorted);
+    // the indexed mode does not depend on order
+    if !assume_sorted && !index_a {
+        for file_a in &files_a {
+            validate_sorted(file_a, genome.as_ref()).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "File A is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
+                    e,
+                    file_a.display(),
+                    genome_flag
+                ))
+            })?;
+        }
         validate_sorted(&file_b, genome.as_ref()).map_err(|e| {
             BedError::InvalidFormat(format!(
                 "File B is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
@@ -1486,13 +3577,382 @@ fn run_coverage(
     cmd.histogram = histogram;
     cmd.per_base = per_base;
     cmd.mean = mean;
+    cmd.stddev = stddev;
+    cmd.quantiles = median.then_some(0.5).into_iter().chain(quantiles).collect();
+    let b_score = b_score.with_bedgraph(is_bedgraph(&file_b));
+    cmd.b_score = b_score;
+    cmd.diversity = diversity;
+    cmd.collapse_by_name = collapse_by_name;
+
+    let fields: Vec<String> = if diversity.is_some() {
+        vec!["classes".into(), "shannon".into(), "evenness".into()]
+    } else if per_base {
+        vec!["position".into(), "depth".into()]
+    } else if histogram {
+        vec![
+            "depth".into(),
+            "bases".into(),
+            "length".into(),
+            "fraction".into(),
+        ]
+    } else if mean {
+        let stats = stddev.then(|| "stddev".to_string());
+        let quantiles = cmd.quantiles.iter().map(|q| format!("q{}", q * 100.0));
+        std::iter::once("mean".to_string())
+            .chain(stats)
+            .chain(quantiles)
+            .collect()
+    } else {
+        vec![
+            "count".into(),
+            "covered_bp".into(),
+            "length".into(),
+            "fraction".into(),
+        ]
+    };
+    let sinks: Vec<Box<dyn Write>> = match &output_dir {
+        Some(dir) => coverage_output_files(dir, &files_a)?,
+        None => vec![Box::new(limited_stdout())],
+    };
+    let mut handles: Vec<_> = files_a
+        .iter()
+        .zip(sinks)
+        .map(|(file_a, sink)| {
+            let layout = Layout::new()
+                .with_fields(fields.clone())
+                .with_bedgraph(is_bedgraph(file_a), false);
+            FormattedWriter::new(sink, output_format, layout)
+        })
+        .collect();
+
+    if files_a.len() > 1 {
+        cmd.run_multi(&files_a, &file_b, &mut handles, genome.as_ref())?;
+        for handle in handles {
+            handle.finish()?;
+        }
+        return Ok(());
+    }
+
+    let file_a = &files_a[0];
+    let mut handle = handles.pop().expect("one output per A file");
+
+    if index_a {
+        let mut cmd = IndexedCoverageCommand::new().with_mean(mean);
+        cmd.b_score = b_score;
+        cmd.run(file_a, &file_b, &mut handle)?;
+        handle.finish()?;
+        return Ok(());
+    }
+
+    let Some(summary_path) = reciprocal_summary else {
+        cmd.run(file_a, &file_b, &mut handle)?;
+        handle.finish()?;
+        return Ok(());
+    };
+
+    let summary = cmd.run_reciprocal(file_a, &file_b, &mut handle)?;
+    handle.finish()?;
+
+    let mut out = std::fs::File::create(&summary_path).map_err(|e| {
+        BedError::InvalidFormat(format!(
+            "Failed to create '{}': {}",
+            summary_path.display(),
+            e
+        ))
+    })?;
+    ReciprocalSummary::write_header(&mut out)?;
+    summary.write_row(&mut out, &file_b.display().to_string())?;
+    Ok(())
+}
+
+/// Create `dir/<A file name>` for each A file.
+fn coverage_output_files(dir: &Path, files_a: &[PathBuf]) -> Result<Vec<Box<dyn Write>>, BedError> {
+    std::fs::create_dir_all(dir).map_err(|e| {
+        BedError::InvalidFormat(format!("Failed to create '{}': {}", dir.display(), e))
+    })?;
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(files_a.len());
+    for file_a in files_a {
+        let name = file_a.file_name().ok_or_else(|| {
+            BedError::InvalidFormat(format!("'{}' is not a file name", file_a.display()))
+        })?;
+        let path = dir.join(name);
+        if paths.contains(&path) {
+            return Err(BedError::InvalidFormat(format!(
+                "two -a files would both be written to '{}'",
+                path.display()
+            )));
+        }
+        let existing = std::fs::canonicalize(&path).ok();
+        if existing.is_some() && existing == std::fs::canonicalize(file_a).ok() {
+            return Err(BedError::InvalidFormat(format!(
+                "--output-dir would overwrite the input '{}'",
+                file_a.display()
+            )));
+        }
+        paths.push(path);
+    }
+    paths
+        .iter()
+        .map(|path| {
+            let file = File::create(path).map_err(|e| {
+                BedError::InvalidFormat(format!("Failed to create '{}': {}", path.display(), e))
+            })?;
+            Ok(Box::new(file) as Box<dyn Write>)
+        })
+        .collect()
+}
+
+fn run_segment(
+    file_a: PathBuf,
+    file_b: PathBuf,
+    breaks: Vec<u32>,
+    assume_sorted: bool,
+    genome_path: Option<PathBuf>,
+    b_score: ScoreFilter,
+) -> Result<(), BedError> {
+    let classes = DepthClasses::new(breaks)?;
+    let genome = genome_path.as_ref().map(Genome::from_file).transpose()?;
+    let genome_flag = if genome.is_some() {
+        " -g <genome.txt>"
+    } else {
+        ""
+    };
+
+    if !assume_sorted {
+        for (label, path) in [("A", &file_a), ("B", &file_b)] {
+            validate_sorted(path, genome.as_ref()).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "File {} is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
+                    label,
+                    e,
+                    path.display(),
+                    genome_flag
+                ))
+            })?;
+        }
+    }
+
+    let mut cmd = StreamingCoverageCommand::new();
+    cmd.b_score = b_score.with_bedgraph(is_bedgraph(&file_b));
+    cmd.segment = Some(classes);
+
+    let mut handle = limited_stdout();
+    cmd.run(file_a, file_b, &mut handle)
+}
+
+fn run_delta(
+    treatment: PathBuf,
+    control: PathBuf,
+    min_diff: Option<f64>,
+    min_fold: Option<f64>,
+    pseudocount: f64,
+    min_length: u64,
+    distance: u64,
+    assume_sorted: bool,
+    genome_path: Option<PathBuf>,
+) -> Result<(), BedError> {
+    let genome = genome_path.as_ref().map(Genome::from_file).transpose()?;
+    let genome_flag = if genome.is_some() {
+        " -g <genome.txt>"
+    } else {
+        ""
+    };
+
+    if !assume_sorted {
+        for (label, path) in [("Treatment", &treatment), ("Control", &control)] {
+            validate_sorted(path, genome.as_ref()).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "{} file is not sorted: {}\n\nFix: Run 'grit sort -i {}{}' first.",
+                    label,
+                    e,
+                    path.display(),
+                    genome_flag
+                ))
+            })?;
+        }
+    }
+
+    let mut cmd = DeltaCommand::new()
+        .with_pseudocount(pseudocount)
+        .with_min_length(min_length)
+        .with_distance(distance);
+    cmd.min_diff = min_diff;
+    cmd.min_fold = min_fold;
+    if let Some(g) = &genome {
+        cmd = cmd.with_genome(g);
+    }
+
+    let mut handle = limited_stdout();
+    cmd.run(treatment, control, &mut handle)
+}
+
+fn run_covariates(
+    input: PathBuf,
+    fasta: Option<PathBuf>,
+    mappability: Option<PathBuf>,
+) -> Result<(), BedError> {
+    let cmd = CovariatesCommand::new()
+        .with_fasta(fasta)
+        .with_mappability(mappability);
+
+    let mut handle = limited_stdout();
+
+    cmd.run(input, &mut handle)
+}
+
+fn run_join(
+    file_a: PathBuf,
+    file_b: PathBuf,
+    key_a: usize,
+    key_b: usize,
+    join_type: String,
+    fill: String,
+) -> Result<(), BedError> {
+    let cmd = JoinCommand::new()
+        .with_keys(key_a, key_b)
+        .with_join_type(JoinType::parse(&join_type).unwrap_or_default())
+        .with_fill(fill);
+
+    if file_a.to_string_lossy() == "-" {
+        return cmd.run_stdio(file_b);
+    }
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
     cmd.run(file_a, file_b, &mut handle)
 }
 
+/// `grit index upgrade`: one `index<TAB>status` line per file on stdout.
+/// Failures are reported and skipped, and fail the command at the end.
+fn run_index_upgrade(files: Vec<PathBuf>, dry_run: bool) -> Result<(), BedError> {
+    let mut failed = 0;
+    for file in files {
+        // Accept the index itself as well as the BED file it belongs to
+        let bed = if file.extension().is_some_and(|e| e == FILE_INDEX_EXTENSION) {
+            file.with_extension("")
+        } else {
+            file
+        };
+        let sidecar = FileIndex::sidecar_path(&bed);
+        let status = match upgrade_sidecar(&bed, dry_run) {
+            Ok(IndexUpgrade::Current) => format!("current (version {})", FILE_INDEX_VERSION),
+            Ok(IndexUpgrade::Upgraded { from }) => format!(
+                "{} version {} to {}",
+                if dry_run { "would upgrade" } else { "upgraded" },
+                from,
+                FILE_INDEX_VERSION
+            ),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                failed += 1;
+                continue;
+            }
+        };
+        println!("{}\t{}", sidecar.display(), status);
+    }
+    if failed > 0 {
+        return Err(BedError::InvalidFormat(format!(
+            "{} index(es) could not be upgraded",
+            failed
+        )));
+    }
+    Ok(())
+}
+
+fn run_cat(inputs: Vec<PathBuf>) -> Result<(), BedError> {
+    let mut handle = limited_stdout();
+    let inputs = if inputs.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        inputs
+    };
+
+    // Each input is checked for the binary header, so BED passes through
+    for path in &inputs {
+        if path.to_string_lossy() == "-" {
+            let mut reader = InputReader::detect(std::io::stdin().lock())?;
+            std::io::copy(&mut reader, &mut handle)?;
+        } else {
+            let file = BufReader::new(File::open(path)?);
+            std::io::copy(&mut InputReader::detect(file)?, &mut handle)?;
+        }
+    }
+    handle.flush()?;
+    Ok(())
+}
+
+/// `grit help [TOPIC]`: a help topic, a command's `--help`, or (without a
+/// name) the command list followed by the topics.
+fn run_help(topic: Option<String>) -> Result<(), BedError> {
+    let mut cli = Cli::command();
+    // Rendered in full and written at once, so `grit help | head` exits cleanly
+    let text = match topic {
+        None => {
+            let mut text = format!("{}\nHelp topics (grit help <TOPIC>):\n", cli.render_help());
+            for topic in help_topics::TOPICS {
+                text.push_str(&format!("  {:<11} {}\n", topic.name, topic.summary));
+            }
+            text
+        }
+        Some(name) => {
+            if let Some(topic) = help_topics::find(&name) {
+                topic.to_string()
+            } else if let Some(command) = cli.find_subcommand_mut(&name) {
+                let name = format!("grit {}", command.get_name());
+                command
+                    .clone()
+                    .bin_name(name)
+                    .render_long_help()
+                    .to_string()
+            } else {
+                let topics: Vec<&str> = help_topics::TOPICS.iter().map(|t| t.name).collect();
+                return Err(BedError::InvalidFormat(format!(
+                    "no help topic or command named '{}' (topics: {})",
+                    name,
+                    topics.join(", ")
+                )));
+            }
+        }
+    };
+    std::io::stdout().lock().write_all(text.as_bytes())?;
+    Ok(())
+}
+
+fn run_shuffle(
+    input: PathBuf,
+    genome_file: PathBuf,
+    background: String,
+    chrom: bool,
+    per_record: usize,
+    seed: u64,
+    include: Option<PathBuf>,
+    exclude: Option<PathBuf>,
+) -> Result<(), BedError> {
+    let genome = Genome::from_file(&genome_file)
+        .map_err(|e| BedError::InvalidFormat(format!("Failed to load genome file: {}", e)))?;
+
+    let background = if chrom {
+        ShuffleBackground::Matched
+    } else {
+        ShuffleBackground::parse(&background).unwrap_or_default()
+    };
+    let mut cmd = ShuffleCommand::new()
+        .with_background(background)
+        .with_per_record(per_record)
+        .with_seed(seed);
+    if include.is_some() || exclude.is_some() {
+        cmd = cmd.with_mask(GenomeMask::from_files(&genome, include, exclude)?);
+    }
+
+    if input.to_string_lossy() == "-" {
+        return cmd.run_stdio(&genome);
+    }
+
+    let mut handle = limited_stdout();
+
+    cmd.run(input, &genome, &mut handle)
+}
+
 fn run_slop(
     input: PathBuf,
     genome_file: PathBuf,
@@ -1501,32 +3961,156 @@ fn run_slop(
     right: Option<f64>,
     strand: bool,
     pct: bool,
+    amount_col: Option<usize>,
+    rejects: Option<PathBuf>,
 ) -> Result<(), BedError> {
+    check_column("--amount-col", amount_col)?;
     let genome = Genome::from_file(&genome_file)?;
 
+    let mut rejects = match rejects {
+        Some(ref path) => Some(std::fs::File::create(path).map_err(|e| {
+            BedError::InvalidFormat(format!("Failed to create '{}': {}", path.display(), e))
+        })?),
+        None => None,
+    };
+
     let mut cmd = SlopCommand::new();
-    cmd.both = both.unwrap_or(0.0);
+    // With --amount-col and no -b/-l/-r, extend both sides by the column value
+    let default_both = if amount_col.is_some() && left.is_none() && right.is_none() {
+        1.0
+    } else {
+        0.0
+    };
+    cmd.both = both.unwrap_or(default_both);
     cmd.left = left;
     cmd.right = right;
     cmd.strand = strand;
     cmd.pct = pct;
+    cmd.amount_col = amount_col;
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
-    cmd.run(input, &genome, &mut handle)
+    cmd.run_with_rejects(input, &genome, &mut handle, rejects.as_mut())
+}
+
+fn run_modify(
+    input: PathBuf,
+    genome_path: Option<PathBuf>,
+    center: Option<u64>,
+    anchor: String,
+) -> Result<(), BedError> {
+    let genome =
+        match genome_path {
+            Some(ref gp) => Some(Genome::from_file(gp).map_err(|e| {
+                BedError::InvalidFormat(format!("Failed to load genome file: {}", e))
+            })?),
+            None => None,
+        };
+
+    let cmd = ModifyCommand::new()
+        .with_anchor(Anchor::parse(&anchor).unwrap_or_default())
+        .with_center(center);
+
+    if input.to_string_lossy() == "-" {
+        return cmd.run_stdio(genome.as_ref());
+    }
+
+    let mut handle = limited_stdout();
+
+    cmd.run(input, genome.as_ref(), &mut handle)
+}
+
+fn run_cut(
+    input: PathBuf,
+    fields: String,
+    format: Option<String>,
+    sort: bool,
+    genome_path: Option<PathBuf>,
+) -> Result<(), BedError> {
+    let genome = genome_path
+        .map(|gp| {
+            Genome::from_file(&gp)
+                .map_err(|e| BedError::InvalidFormat(format!("Failed to load genome file: {}", e)))
+        })
+        .transpose()?;
+    let stdin = input.to_string_lossy() == "-";
+    let format = match format {
+        Some(f) => CutFormat::parse(&f).unwrap_or_default(),
+        None if stdin => CutFormat::Bed,
+        None => CutFormat::detect(&input),
+    };
+
+    let cmd = CutCommand::new(CutColumn::parse_list(&fields, format)?).with_sort(sort);
+    if !sort && !cmd.keeps_coordinates() {
+        eprintln!(
+            "Warning: cut changes the coordinate columns; the output may not be sorted (use --sort)"
+        );
+    }
+
+    if stdin {
+        return cmd.run_stdio(genome.as_ref());
+    }
+    let mut handle = limited_stdout();
+    cmd.run(input, genome.as_ref(), &mut handle)
+}
+
+fn run_convert(
+    input: PathBuf,
+    to: String,
+    from: Option<String>,
+    source: String,
+    feature_type: Option<String>,
+) -> Result<(), BedError> {
+    let stdin = input.to_string_lossy() == "-";
+    let from = match from {
+        Some(f) => ConvertFormat::parse(&f).unwrap_or_default(),
+        None if stdin => ConvertFormat::Bed,
+        None => ConvertFormat::detect(&input),
+    };
+    let to = ConvertFormat::parse(&to).unwrap_or_default();
+
+    let cmd = ConvertCommand::new(from, to)
+        .with_source(source)
+        .with_feature_type(feature_type);
+    if stdin {
+        cmd.run_stdio()?;
+    } else {
+        let mut handle = limited_stdout();
+        cmd.run(input, &mut handle)?;
+    }
+    Ok(())
+}
+
+fn run_fix_strand(input: PathBuf, genes: Option<PathBuf>, strict: bool) -> Result<(), BedError> {
+    let mut cmd = FixStrandCommand::new().with_strict(strict);
+    if let Some(genes) = genes {
+        cmd = cmd.with_genes(genes)?;
+    }
+
+    let stats = if input.to_string_lossy() == "-" {
+        cmd.run_stdio()?
+    } else {
+        let mut handle = limited_stdout();
+        cmd.run(input, &mut handle)?
+    };
+    eprintln!("fix-strand: {}", stats);
+    Ok(())
 }
 
 fn run_complement(
     input: PathBuf,
     genome_file: PathBuf,
     assume_sorted: bool,
+    include: Option<PathBuf>,
+    exclude: Option<PathBuf>,
 ) -> Result<(), BedError> {
     let genome = Genome::from_file(&genome_file)?;
-    let cmd = ComplementCommand::new().with_assume_sorted(assume_sorted);
+    let mut cmd = ComplementCommand::new().with_assume_sorted(assume_sorted);
+    if include.is_some() || exclude.is_some() {
+        cmd = cmd.with_mask(GenomeMask::from_files(&genome, include, exclude)?);
+    }
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
     cmd.run(input, &genome, &mut handle)
 }
@@ -1538,15 +4122,30 @@ fn run_genomecov(
     bedgraph: bool,
     bedgraph_all: bool,
     scale: f64,
+    round_scaled: bool,
+    normalize: Option<String>,
+    collapse_by_name: bool,
+    regions: Option<PathBuf>,
+    regions_summary: Option<PathBuf>,
+    thresholds: Vec<u32>,
+    chrom_classes: Option<PathBuf>,
     streaming: bool,
     assume_sorted: bool,
+    progress: bool,
+    verbose: bool,
 ) -> Result<(), BedError> {
     let genome = Genome::from_file(&genome_file)?;
+    let chrom_classes = chrom_classes.map(ChromClasses::from_file).transpose()?;
+    let normalize = normalize.as_deref().and_then(DepthNormalization::parse);
+    let bedgraph = bedgraph || (normalize.is_some() && !bedgraph_all);
+    let targets = match regions {
+        Some(path) => Some(GenomeMask::from_files(&genome, Some(path), None)?),
+        None => None,
+    };
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
-    if streaming || assume_sorted {
+    let summary = if streaming || assume_sorted {
         // Use streaming implementation with O(k) memory
         let mode = if per_base {
             StreamingGenomecovMode::PerBase
@@ -1561,13 +4160,25 @@ fn run_genomecov(
         let cmd = StreamingGenomecovCommand::new()
             .with_mode(mode)
             .with_scale(scale)
-            .with_assume_sorted(assume_sorted);
-
-        cmd.run(input, &genome, &mut handle)
+            .with_round_scaled(round_scaled)
+            .with_normalize(normalize)
+            .with_collapse_by_name(collapse_by_name)
+            .with_targets(targets)
+            .with_chrom_classes(chrom_classes)
+            .with_assume_sorted(assume_sorted)
+            .with_progress(progress, verbose);
+
+        cmd.run_with_summary(input, &genome, &mut handle)?
     } else {
         // Use original implementation (loads all intervals into memory)
-        let mut cmd = GenomecovCommand::new();
+        let mut cmd = GenomecovCommand::new()
+            .with_normalize(normalize)
+            .with_collapse_by_name(collapse_by_name)
+            .with_targets(targets)
+            .with_chrom_classes(chrom_classes)
+            .with_progress(progress, verbose);
         cmd.scale = scale;
+        cmd.round_scaled = round_scaled;
 
         if per_base {
             cmd.mode = GenomecovOutputMode::PerBase;
@@ -1578,39 +4189,161 @@ fn run_genomecov(
         }
         // else default to Histogram
 
-        cmd.run(input, &genome, &mut handle)
+        cmd.run_with_summary(input, &genome, &mut handle)?
+    };
+
+    let Some(summary_path) = regions_summary else {
+        return Ok(());
+    };
+    let mut out = std::fs::File::create(&summary_path).map_err(|e| {
+        BedError::InvalidFormat(format!(
+            "Failed to create '{}': {}",
+            summary_path.display(),
+            e
+        ))
+    })?;
+    summary.write(&mut out, &thresholds)
+}
+
+fn run_jaccard(
+    file_a: Option<PathBuf>,
+    file_b: Option<PathBuf>,
+    extended: bool,
+    genome_path: Option<PathBuf>,
+    manifest: Option<PathBuf>,
+    pairs: Option<PathBuf>,
+    against: Option<String>,
+) -> Result<(), BedError> {
+    let mut cmd = JaccardCommand::new().with_extended(extended);
+    if let Some(ref gp) = genome_path {
+        let genome = Genome::from_file(gp)
+            .map_err(|e| BedError::InvalidFormat(format!("Failed to load genome file: {}", e)))?;
+        cmd = cmd.with_genome(&genome);
+    }
+
+    let mut handle = limited_stdout();
+
+    let Some(manifest) = manifest else {
+        // clap requires both files without a manifest
+        return cmd.run(
+            file_a.unwrap_or_default(),
+            file_b.unwrap_or_default(),
+            &mut handle,
+        );
+    };
+    let manifest = Manifest::from_file(&manifest)?;
+    let pairs = match (pairs, against) {
+        (Some(path), _) => {
+            let file = File::open(&path).map_err(|e| {
+                BedError::InvalidFormat(format!(
+                    "Failed to open pairs file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            JaccardPair::from_reader(BufReader::new(file), &manifest)?
+        }
+        (None, Some(label)) => JaccardPair::against(&manifest, &label)?,
+        (None, None) => JaccardPair::all_pairs(&manifest),
+    };
+    cmd.run_batch(&pairs, &mut handle)
+}
+
+fn run_enrich(
+    file_a: PathBuf,
+    file_b: PathBuf,
+    genome_file: PathBuf,
+    metric: String,
+    shuffles: usize,
+    background: String,
+    seed: u64,
+    include: Option<PathBuf>,
+    exclude: Option<PathBuf>,
+) -> Result<(), BedError> {
+    let genome = Genome::from_file(&genome_file)
+        .map_err(|e| BedError::InvalidFormat(format!("Failed to load genome file: {}", e)))?;
+
+    let mut shuffler = ShuffleCommand::new()
+        .with_background(ShuffleBackground::parse(&background).unwrap_or_default())
+        .with_seed(seed);
+    if include.is_some() || exclude.is_some() {
+        shuffler = shuffler.with_mask(GenomeMask::from_files(&genome, include, exclude)?);
     }
+    let cmd = EnrichCommand::new()
+        .with_metric(EnrichMetric::parse(&metric).unwrap_or_default())
+        .with_shuffles(shuffles)
+        .with_shuffler(shuffler);
+
+    let mut handle = limited_stdout();
+
+    cmd.run(file_a, file_b, &genome, &mut handle)
 }
 
-fn run_jaccard(file_a: PathBuf, file_b: PathBuf) -> Result<(), BedError> {
-    let cmd = JaccardCommand::new();
+fn run_stab(
+    input: PathBuf,
+    queries: Vec<String>,
+    regions: Option<PathBuf>,
+    build_index: bool,
+) -> Result<(), BedError> {
+    let mut parsed = queries
+        .iter()
+        .map(|q| StabQuery::parse(q))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(path) = regions {
+        parsed.extend(read_stab_regions(path)?);
+    }
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
 
-    cmd.run(file_a, file_b, &mut handle)
+    StabCommand::new()
+        .with_build_index(build_index)
+        .run(input, &parsed, &mut handle)?;
+    Ok(())
 }
 
 fn run_multiinter(
     inputs: Vec<PathBuf>,
+    manifest: Option<PathBuf>,
+    names: Option<Vec<String>>,
     cluster: bool,
     streaming: bool,
     assume_sorted: bool,
+    max_open_files: Option<usize>,
+    sparse: bool,
+    expand: Option<PathBuf>,
 ) -> Result<(), BedError> {
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+    let mut handle = limited_stdout();
+
+    if let Some(path) = expand {
+        return SparseMultiinterReader::from_path(path)?.write_dense(&mut handle);
+    }
+
+    // A manifest supplies both the inputs and their labels
+    let (inputs, names) = match manifest {
+        Some(path) => {
+            let manifest = Manifest::from_file(&path)?;
+            (manifest.paths(), Some(manifest.labels()))
+        }
+        None => (inputs, names),
+    };
 
     if streaming || assume_sorted {
         // Use streaming implementation with O(k) memory and k-way merge
-        let cmd = StreamingMultiinterCommand::new()
+        let mut cmd = StreamingMultiinterCommand::new()
             .with_cluster(cluster)
-            .with_assume_sorted(assume_sorted);
+            .with_assume_sorted(assume_sorted)
+            .with_sparse(sparse);
+        cmd.names = names;
+        if let Some(n) = max_open_files {
+            cmd.max_open_files = n;
+        }
 
         cmd.run(&inputs, &mut handle)
     } else {
         // Use original implementation (loads all intervals into memory)
-        let mut cmd = MultiinterCommand::new();
+        let mut cmd = MultiinterCommand::new().with_sparse(sparse);
         cmd.cluster = cluster;
+        cmd.names = names;
 
         cmd.run(&inputs, &mut handle)
     }
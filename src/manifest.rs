@@ -0,0 +1,156 @@
+//! Input manifests for multi-file commands.
+//!
+//! A manifest is a tab-separated file with one input per line:
+//!
+//! ```text
+//! # label    path
+//! rep1       peaks/rep1.bed
+//! rep2       /data/peaks/rep2.bed
+//! ```
+//!
+//! Relative paths are resolved against the manifest's directory, so a
+//! manifest can be moved together with its inputs. A line with a single
+//! column uses the path itself as the label. Blank lines and lines starting
+//! with `#` are ignored.
+
+use crate::bed::BedError;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// One labelled input from a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub label: String,
+    pub path: PathBuf,
+}
+
+/// Labelled inputs in manifest order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Read a manifest file, resolving relative paths against its directory.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BedError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            BedError::InvalidFormat(format!(
+                "Failed to open manifest '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let base_dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+        Self::from_reader(BufReader::new(file), base_dir)
+    }
+
+    /// Parse a manifest from a reader.
+    ///
+    /// Relative paths are joined onto `base_dir` when it is given.
+    pub fn from_reader<R: BufRead>(reader: R, base_dir: Option<&Path>) -> Result<Self, BedError> {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let (label, raw_path) = match fields.as_slice() {
+                [path] => (path.trim(), path.trim()),
+                [label, path] => (label.trim(), path.trim()),
+                _ => {
+                    return Err(BedError::Parse {
+                        line: line_num + 1,
+                        message: format!(
+                            "expected 'label<TAB>path' in manifest, found {} columns",
+                            fields.len()
+                        ),
+                    })
+                }
+            };
+
+            if label.is_empty() || raw_path.is_empty() {
+                return Err(BedError::Parse {
+                    line: line_num + 1,
+                    message: "empty label or path in manifest".to_string(),
+                });
+            }
+            if !seen.insert(label.to_string()) {
+                return Err(BedError::Parse {
+                    line: line_num + 1,
+                    message: format!("duplicate manifest label '{}'", label),
+                });
+            }
+
+            let path = Path::new(raw_path);
+            let path = match base_dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path.to_path_buf(),
+            };
+
+            entries.push(ManifestEntry {
+                label: label.to_string(),
+                path,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Number of inputs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the manifest lists no inputs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Input paths in manifest order.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries.iter().map(|e| e.path.clone()).collect()
+    }
+
+    /// Labels in manifest order.
+    pub fn labels(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.label.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let content = "# label\tpath\nrep1\tpeaks/rep1.bed\n\nrep2\t/abs/rep2.bed\r\nsolo.bed\n";
+        let manifest = Manifest::from_reader(content.as_bytes(), Some(Path::new("/data"))).unwrap();
+
+        assert_eq!(manifest.labels(), vec!["rep1", "rep2", "solo.bed"]);
+        assert_eq!(
+            manifest.paths(),
+            vec![
+                PathBuf::from("/data/peaks/rep1.bed"),
+                PathBuf::from("/abs/rep2.bed"),
+                PathBuf::from("/data/solo.bed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_errors() {
+        let dup = Manifest::from_reader("a\tx.bed\na\ty.bed\n".as_bytes(), None);
+        assert!(matches!(dup, Err(BedError::Parse { line: 2, .. })));
+
+        let extra = Manifest::from_reader("a\tx.bed\textra\n".as_bytes(), None);
+        assert!(matches!(extra, Err(BedError::Parse { line: 1, .. })));
+    }
+}
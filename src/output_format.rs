@@ -0,0 +1,514 @@
+//! Structured output formats for overlap results.
+//!
+//! Commands write BED-style lines; [`FormattedWriter`] sits between a command
+//! and its output and rewrites each line as a TSV row under a header or as a
+//! JSON object, according to a [`Layout`] naming the parts of a line: the A
+//! record, an optional B record, and trailing columns such as `count`.
+//!
+//! A record's width is not fixed, so the B record is located per line: it
+//! starts at the first column after A's coordinates that repeats A's
+//! chromosome (or is `.` for "no match") and is followed by two integers.
+//!
+//! A BedGraph record's fourth column is named `value` rather than `name`,
+//! and is written to JSON as a number.
+
+use crate::interval::Interval;
+use std::io::{self, Write};
+
+/// Output format selected by `--output-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Plain BED-style lines, as written by the command.
+    #[default]
+    Bed,
+    /// Tab-separated with a header row of column names.
+    TsvHeader,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl OutputFormat {
+    /// Parse a format name (`bed`, `tsv+header`, `jsonl`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bed" => Some(Self::Bed),
+            "tsv+header" => Some(Self::TsvHeader),
+            "jsonl" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// Standard BED column names; later columns are named `col13`, `col14`, ...
+pub(crate) const BED_COLUMNS: [&str; 12] = [
+    "chrom",
+    "start",
+    "end",
+    "name",
+    "score",
+    "strand",
+    "thickStart",
+    "thickEnd",
+    "itemRgb",
+    "blockCount",
+    "blockSizes",
+    "blockStarts",
+];
+
+/// Parts of each output line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// A B record follows the A record.
+    pub has_b: bool,
+    /// Names of the columns after the records.
+    pub fields: Vec<String>,
+    /// Add `overlap_bp` computed from the A and B coordinates, and
+    /// `overlap_fraction`, the share of A it covers.
+    pub overlap_bp: bool,
+    /// Add `distance` computed from the A and B coordinates, unless it is
+    /// already one of `fields`. Matches `closest -d`: 0 for overlapping
+    /// records, otherwise the gap plus one.
+    pub distance: bool,
+    /// The A record is BedGraph.
+    pub a_bedgraph: bool,
+    /// The B record is BedGraph.
+    pub b_bedgraph: bool,
+}
+
+impl Layout {
+    /// Lines holding only an A record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lines holding an A record followed by a B record.
+    pub fn with_b(mut self) -> Self {
+        self.has_b = true;
+        self
+    }
+
+    /// Name the trailing columns.
+    pub fn with_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Report the A/B overlap length and the fraction of A it covers.
+    pub fn with_overlap_bp(mut self) -> Self {
+        self.overlap_bp = true;
+        self
+    }
+
+    /// Report the A/B distance.
+    pub fn with_distance(mut self) -> Self {
+        self.distance = true;
+        self
+    }
+
+    /// Name the fourth column of BedGraph records `value`.
+    pub fn with_bedgraph(mut self, a: bool, b: bool) -> Self {
+        self.a_bedgraph = a;
+        self.b_bedgraph = b;
+        self
+    }
+
+    fn derives_distance(&self) -> bool {
+        self.distance && !self.fields.iter().any(|f| f == "distance")
+    }
+}
+
+/// One output line split into its parts.
+struct Row<'a> {
+    a: Vec<&'a [u8]>,
+    /// `None` when the layout has no B record or B is the `.` placeholder.
+    b: Option<Vec<&'a [u8]>>,
+    /// Width of the B placeholder columns when `b` is `None`.
+    b_placeholder: usize,
+    fields: Vec<&'a [u8]>,
+}
+
+impl<'a> Row<'a> {
+    fn split(line: &'a [u8], layout: &Layout) -> Self {
+        let columns: Vec<&[u8]> = line.split(|&b| b == b'\t').collect();
+        let trailing = layout.fields.len().min(columns.len());
+        let (records, fields) = columns.split_at(columns.len() - trailing);
+
+        let b_start = if layout.has_b {
+            find_b_start(records)
+        } else {
+            None
+        };
+        let (a, b) = match b_start {
+            Some(start) => records.split_at(start),
+            None => (records, &[][..]),
+        };
+
+        let b_is_record = b.first().is_some_and(|&c| c != b".");
+        Self {
+            a: a.to_vec(),
+            b: b_is_record.then(|| b.to_vec()),
+            b_placeholder: if b_is_record { 0 } else { b.len() },
+            fields: fields.to_vec(),
+        }
+    }
+
+    fn a_interval(&self) -> Option<Interval> {
+        interval(&self.a)
+    }
+
+    fn b_interval(&self) -> Option<Interval> {
+        interval(self.b.as_deref()?)
+    }
+}
+
+/// Index of the first B column among `records`.
+fn find_b_start(records: &[&[u8]]) -> Option<usize> {
+    let chrom = *records.first()?;
+    (3..records.len().saturating_sub(2)).find(|&j| {
+        (records[j] == chrom || records[j] == b".")
+            && parse_int(records[j + 1]).is_some()
+            && parse_int(records[j + 2]).is_some()
+    })
+}
+
+fn interval(columns: &[&[u8]]) -> Option<Interval> {
+    let chrom = std::str::from_utf8(columns.first()?).ok()?;
+    let start = parse_int(columns.get(1)?)?;
+    let end = parse_int(columns.get(2)?)?;
+    Some(Interval::new(
+        chrom,
+        start.try_into().ok()?,
+        end.try_into().ok()?,
+    ))
+}
+
+fn parse_int(text: &[u8]) -> Option<i64> {
+    std::str::from_utf8(text).ok()?.parse().ok()
+}
+
+/// Writer that reformats a command's BED-style lines.
+///
+/// With [`OutputFormat::Bed`] bytes pass through unchanged. Otherwise
+/// partial lines are buffered until their newline arrives, and
+/// [`FormattedWriter::finish`] must be called after the command returns.
+pub struct FormattedWriter<W: Write> {
+    inner: W,
+    format: OutputFormat,
+    layout: Layout,
+    pending: Vec<u8>,
+    header_written: bool,
+}
+
+impl<W: Write> FormattedWriter<W> {
+    pub fn new(inner: W, format: OutputFormat, layout: Layout) -> Self {
+        Self {
+            inner,
+            format,
+            layout,
+            pending: Vec::new(),
+            header_written: false,
+        }
+    }
+
+    /// Write any final unterminated line, and the TSV header if no rows were
+    /// written, then flush.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.write_line(&line)?;
+        }
+        if self.format == OutputFormat::TsvHeader && !self.header_written {
+            let empty = Row {
+                a: vec![&b""[..]; 3],
+                b: self.layout.has_b.then(|| vec![&b""[..]; 3]),
+                b_placeholder: 0,
+                fields: Vec::new(),
+            };
+            self.write_header(&empty)?;
+        }
+        self.inner.flush()
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let row = Row::split(line, &self.layout);
+        match self.format {
+            OutputFormat::Bed => {
+                self.inner.write_all(line)?;
+                self.inner.write_all(b"\n")
+            }
+            OutputFormat::TsvHeader => self.write_tsv(&row),
+            OutputFormat::Jsonl => self.write_json(&row),
+        }
+    }
+
+    /// Derived columns as formatted numbers; `None` when B is missing.
+    fn derived(&self, row: &Row) -> Vec<(&'static str, Option<String>)> {
+        let a = row.a_interval();
+        let b = row.b_interval();
+        let mut derived = Vec::new();
+        if self.layout.overlap_bp {
+            let bp = a.as_ref().zip(b.as_ref()).map(|(a, b)| a.overlap_length(b));
+            derived.push(("overlap_bp", bp.map(|bp| bp.to_string())));
+            let fraction = a.as_ref().zip(bp).and_then(|(a, bp)| {
+                (!a.is_empty()).then(|| format!("{:.7}", bp as f64 / a.len() as f64))
+            });
+            derived.push(("overlap_fraction", fraction));
+        }
+        if self.layout.derives_distance() {
+            let distance = a.as_ref().zip(b.as_ref()).and_then(|(a, b)| {
+                let gap = a.distance_to(b)?;
+                Some(if a.overlaps(b) { 0 } else { gap + 1 })
+            });
+            derived.push(("distance", distance.map(|d| d.to_string())));
+        }
+        derived
+    }
+
+    fn write_header(&mut self, row: &Row) -> io::Result<()> {
+        let mut names: Vec<String> = (0..row.a.len())
+            .map(|i| format!("a_{}", column_name(i, self.layout.a_bedgraph)))
+            .collect();
+        if self.layout.has_b {
+            let width = row.b.as_ref().map_or(row.b_placeholder.max(3), Vec::len);
+            names.extend(
+                (0..width).map(|i| format!("b_{}", column_name(i, self.layout.b_bedgraph))),
+            );
+        }
+        names.extend(self.layout.fields.iter().cloned());
+        names.extend(
+            self.derived(row)
+                .into_iter()
+                .map(|(name, _)| name.to_string()),
+        );
+        self.header_written = true;
+        writeln!(self.inner, "{}", names.join("\t"))
+    }
+
+    fn write_tsv(&mut self, row: &Row) -> io::Result<()> {
+        if !self.header_written {
+            self.write_header(row)?;
+        }
+        let mut columns: Vec<&[u8]> = row.a.clone();
+        match &row.b {
+            Some(b) => columns.extend(b),
+            None if self.layout.has_b => {
+                columns.extend(std::iter::repeat_n(&b"."[..], row.b_placeholder.max(3)))
+            }
+            None => {}
+        }
+        columns.extend(&row.fields);
+        let derived: Vec<String> = self
+            .derived(row)
+            .into_iter()
+            .map(|(_, v)| v.unwrap_or_else(|| ".".to_string()))
+            .collect();
+        columns.extend(derived.iter().map(|s| s.as_bytes()));
+        self.inner.write_all(&columns.join(&b'\t'))?;
+        self.inner.write_all(b"\n")
+    }
+
+    fn write_json(&mut self, row: &Row) -> io::Result<()> {
+        let out = &mut self.inner;
+        out.write_all(b"{\"a\":")?;
+        write_json_record(out, &row.a, self.layout.a_bedgraph)?;
+        if self.layout.has_b {
+            out.write_all(b",\"b\":")?;
+            match &row.b {
+                Some(b) => write_json_record(out, b, self.layout.b_bedgraph)?,
+                None => out.write_all(b"null")?,
+            }
+        }
+        for (name, value) in self.layout.fields.iter().zip(&row.fields) {
+            write!(out, ",{}:", JsonStr(name.as_bytes()))?;
+            // A missing B leaves placeholder values such as -1 behind
+            if self.layout.has_b && row.b.is_none() && name == "distance" {
+                out.write_all(b"null")?;
+            } else {
+                write_json_value(out, value)?;
+            }
+        }
+        let derived = self.derived(row);
+        let out = &mut self.inner;
+        for (name, value) in derived {
+            write!(out, ",\"{}\":", name)?;
+            match value {
+                Some(v) => write_json_value(out, v.as_bytes())?,
+                None => out.write_all(b"null")?,
+            }
+        }
+        out.write_all(b"}\n")
+    }
+}
+
+impl<W: Write> Write for FormattedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.format == OutputFormat::Bed {
+            return self.inner.write(buf);
+        }
+        self.pending.extend_from_slice(buf);
+        if let Some(last) = memchr::memrchr(b'\n', &self.pending) {
+            let complete: Vec<u8> = self.pending.drain(..=last).collect();
+            for line in complete[..last].split(|&b| b == b'\n') {
+                self.write_line(line)?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn column_name(index: usize, bedgraph: bool) -> String {
+    if bedgraph && index == 3 {
+        return "value".to_string();
+    }
+    BED_COLUMNS
+        .get(index)
+        .map_or_else(|| format!("col{}", index + 1), |name| name.to_string())
+}
+
+/// Write a record as an object keyed by BED column name.
+fn write_json_record<W: Write>(out: &mut W, columns: &[&[u8]], bedgraph: bool) -> io::Result<()> {
+    out.write_all(b"{")?;
+    for (i, value) in columns.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write!(out, "\"{}\":", column_name(i, bedgraph))?;
+        // Names, strands and colors stay strings even when they look numeric
+        match i {
+            3 if bedgraph => write_json_value(out, value)?,
+            0 | 3 | 5 | 8 | 10 | 11 => write!(out, "{}", JsonStr(value))?,
+            _ => write_json_value(out, value)?,
+        }
+    }
+    out.write_all(b"}")
+}
+
+/// Write a number if `text` parses as one, else a string.
+fn write_json_value<W: Write>(out: &mut W, text: &[u8]) -> io::Result<()> {
+    if let Some(n) = parse_int(text) {
+        return write!(out, "{}", n);
+    }
+    let number = std::str::from_utf8(text)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|n| n.is_finite());
+    match number {
+        Some(n) => write!(out, "{}", n),
+        None => write!(out, "{}", JsonStr(text)),
+    }
+}
+
+/// JSON string literal.
+struct JsonStr<'a>(&'a [u8]);
+
+impl std::fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write as _;
+        f.write_char('"')?;
+        for c in String::from_utf8_lossy(self.0).chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(format: OutputFormat, layout: Layout, input: &str) -> String {
+        let mut output = Vec::new();
+        let mut writer = FormattedWriter::new(&mut output, format, layout);
+        // Split writes mid-line, as buffered command output does
+        let (head, tail) = input.split_at(input.len() / 2);
+        writer.write_all(head.as_bytes()).unwrap();
+        writer.write_all(tail.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_jsonl_pairs() {
+        let layout = Layout::new().with_b().with_overlap_bp();
+        let out = format(
+            OutputFormat::Jsonl,
+            layout,
+            "chr1\t100\t200\tg\"1\t5\t+\tchr1\t150\t400\tp1\nchr2\t0\t10\t.\t.\t-1\t-1\n",
+        );
+        assert_eq!(
+            out,
+            "{\"a\":{\"chrom\":\"chr1\",\"start\":100,\"end\":200,\"name\":\"g\\\"1\",\"score\":5,\"strand\":\"+\"},\
+             \"b\":{\"chrom\":\"chr1\",\"start\":150,\"end\":400,\"name\":\"p1\"},\"overlap_bp\":50,\"overlap_fraction\":0.5}\n\
+             {\"a\":{\"chrom\":\"chr2\",\"start\":0,\"end\":10,\"name\":\".\"},\"b\":null,\
+             \"overlap_bp\":null,\"overlap_fraction\":null}\n"
+        );
+    }
+
+    #[test]
+    fn test_jsonl_fields_and_distance() {
+        let layout = Layout::new().with_fields(["count", "fraction"]);
+        let out = format(OutputFormat::Jsonl, layout, "chr1\t0\t10\t3\t0.5000000\n");
+        assert_eq!(
+            out,
+            "{\"a\":{\"chrom\":\"chr1\",\"start\":0,\"end\":10},\"count\":3,\"fraction\":0.5}\n"
+        );
+
+        let layout = Layout::new().with_b().with_distance();
+        let out = format(OutputFormat::Jsonl, layout, "chr1\t0\t10\tchr1\t25\t30\n");
+        assert!(out.ends_with(",\"distance\":16}\n"));
+    }
+
+    #[test]
+    fn test_tsv_header() {
+        let layout = Layout::new().with_b().with_fields(["distance"]);
+        let out = format(
+            OutputFormat::TsvHeader,
+            layout,
+            "chr1\t0\t10\tx\tchr1\t25\t30\t15\nchr2\t0\t10\tx\t.\t-1\t-1\t-1\n",
+        );
+        assert_eq!(
+            out,
+            "a_chrom\ta_start\ta_end\ta_name\tb_chrom\tb_start\tb_end\tdistance\n\
+             chr1\t0\t10\tx\tchr1\t25\t30\t15\n\
+             chr2\t0\t10\tx\t.\t.\t.\t-1\n"
+        );
+
+        let empty = format(
+            OutputFormat::TsvHeader,
+            Layout::new().with_fields(["count"]),
+            "",
+        );
+        assert_eq!(empty, "a_chrom\ta_start\ta_end\tcount\n");
+        assert_eq!(
+            OutputFormat::parse("tsv+header"),
+            Some(OutputFormat::TsvHeader)
+        );
+    }
+
+    #[test]
+    fn test_bedgraph_value_column() {
+        let layout = Layout::new()
+            .with_b()
+            .with_overlap_bp()
+            .with_bedgraph(false, true);
+        let line = "chr1\t100\t200\t7\tchr1\t150\t400\t2.5e-1\n";
+        assert_eq!(
+            format(OutputFormat::Jsonl, layout.clone(), line),
+            "{\"a\":{\"chrom\":\"chr1\",\"start\":100,\"end\":200,\"name\":\"7\"},\
+             \"b\":{\"chrom\":\"chr1\",\"start\":150,\"end\":400,\"value\":0.25},\
+             \"overlap_bp\":50,\"overlap_fraction\":0.5}\n"
+        );
+        assert!(format(OutputFormat::TsvHeader, layout, line)
+            .starts_with("a_chrom\ta_start\ta_end\ta_name\tb_chrom\tb_start\tb_end\tb_value\t"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Opt-in caps on the size of a command's output.
+//!
+//! `--max-output-lines` and `--max-output-bytes` guard shared filesystems
+//! against runaway results, such as the near-cartesian product of
+//! intersecting two dense repeat tracks. Commands write to stdout through
+//! [`limited_stdout`]; once the next line would cross a limit nothing more is
+//! written, the write fails with [`OutputLimitExceeded`], and the output ends
+//! on the last whole line that fit.
+//!
+//! The counters are process-wide, so a command that opens stdout more than
+//! once still gets one budget.
+
+use crate::interchange::{encoded_stdout, FrameWriter};
+use std::fmt;
+use std::io::{self, StdoutLock, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+
+static MAX_LINES: AtomicU64 = AtomicU64::new(u64::MAX);
+static MAX_BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+static LIMITED: AtomicBool = AtomicBool::new(false);
+static LINES: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+/// 0 while within the limits, then 1 (lines) or 2 (bytes).
+static EXCEEDED: AtomicU8 = AtomicU8::new(0);
+
+/// Set the output caps; `None` leaves that dimension unlimited.
+pub fn set_output_limits(max_lines: Option<u64>, max_bytes: Option<u64>) {
+    MAX_LINES.store(max_lines.unwrap_or(u64::MAX), Ordering::Relaxed);
+    MAX_BYTES.store(max_bytes.unwrap_or(u64::MAX), Ordering::Relaxed);
+    LIMITED.store(
+        max_lines.is_some() || max_bytes.is_some(),
+        Ordering::Relaxed,
+    );
+}
+
+/// The limit that stopped the output, if any.
+pub fn output_limit_exceeded() -> Option<OutputLimitExceeded> {
+    match EXCEEDED.load(Ordering::Relaxed) {
+        1 => Some(OutputLimitExceeded::Lines(
+            MAX_LINES.load(Ordering::Relaxed),
+        )),
+        2 => Some(OutputLimitExceeded::Bytes(
+            MAX_BYTES.load(Ordering::Relaxed),
+        )),
+        _ => None,
+    }
+}
+
+/// Parse a byte count with an optional binary suffix: `500`, `64K`, `10M`,
+/// `2G` or `1T` (case-insensitive, an optional trailing `B` is allowed).
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let upper = s.trim().to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (digits, shift) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 10),
+        Some('M') => (&digits[..digits.len() - 1], 20),
+        Some('G') => (&digits[..digits.len() - 1], 30),
+        Some('T') => (&digits[..digits.len() - 1], 40),
+        _ => (digits, 0),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size '{}': expected e.g. 500, 64K, 10M or 2G", s))
+}
+
+/// Which limit was reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputLimitExceeded {
+    Lines(u64),
+    Bytes(u64),
+}
+
+impl fmt::Display for OutputLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lines(n) => write!(f, "output truncated at --max-output-lines {}", n),
+            Self::Bytes(n) => write!(f, "output truncated at --max-output-bytes {}", n),
+        }
+    }
+}
+
+impl std::error::Error for OutputLimitExceeded {}
+
+/// Stdout, subject to the configured output limits.
+///
+/// The limits count text lines, before any `--of binary` encoding.
+pub fn limited_stdout() -> LimitedWriter<FrameWriter<StdoutLock<'static>>> {
+    LimitedWriter::new(encoded_stdout())
+}
+
+/// Writer that passes whole lines through while they fit the limits.
+///
+/// The unfinished tail of a line is held back until its newline arrives (or
+/// the writer is flushed), so a line is either written in full or not at all.
+pub struct LimitedWriter<W: Write> {
+    inner: W,
+    partial: Vec<u8>,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Check that `len` more bytes (completing a line if `ends_line`) fit,
+    /// recording the first limit crossed.
+    fn check(&mut self, len: usize, ends_line: bool) -> io::Result<()> {
+        if EXCEEDED.load(Ordering::Relaxed) == 0 {
+            let lines = LINES.load(Ordering::Relaxed) + u64::from(ends_line);
+            let bytes = BYTES.load(Ordering::Relaxed) + len as u64;
+            if lines > MAX_LINES.load(Ordering::Relaxed) {
+                EXCEEDED.store(1, Ordering::Relaxed);
+            } else if bytes > MAX_BYTES.load(Ordering::Relaxed) {
+                EXCEEDED.store(2, Ordering::Relaxed);
+            }
+        }
+        match output_limit_exceeded() {
+            Some(limit) => {
+                self.partial.clear();
+                Err(io::Error::other(limit))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !LIMITED.load(Ordering::Relaxed) {
+            return self.inner.write(buf);
+        }
+
+        let mut rest = buf;
+        while let Some(i) = memchr::memchr(b'\n', rest) {
+            let line_len = self.partial.len() + i + 1;
+            self.check(line_len, true)?;
+            self.inner.write_all(&self.partial)?;
+            self.inner.write_all(&rest[..=i])?;
+            self.partial.clear();
+            LINES.fetch_add(1, Ordering::Relaxed);
+            BYTES.fetch_add(line_len as u64, Ordering::Relaxed);
+            rest = &rest[i + 1..];
+        }
+        self.check(self.partial.len() + rest.len(), false)?;
+        self.partial.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // An unterminated final line is written when the command finishes
+        if !self.partial.is_empty() && EXCEEDED.load(Ordering::Relaxed) == 0 {
+            self.inner.write_all(&self.partial)?;
+            BYTES.fetch_add(self.partial.len() as u64, Ordering::Relaxed);
+            self.partial.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("500"), Ok(500));
+        assert_eq!(parse_byte_size("64k"), Ok(64 << 10));
+        assert_eq!(parse_byte_size("10MB"), Ok(10 << 20));
+        assert_eq!(parse_byte_size("2G"), Ok(2 << 30));
+        assert!(parse_byte_size("ten").is_err());
+        assert!(parse_byte_size("").is_err());
+    }
+
+    // The limits are process-wide, so one test covers both dimensions.
+    #[test]
+    fn test_limited_writer() {
+        let reset = || {
+            LINES.store(0, Ordering::Relaxed);
+            BYTES.store(0, Ordering::Relaxed);
+            EXCEEDED.store(0, Ordering::Relaxed);
+        };
+
+        set_output_limits(Some(2), None);
+        reset();
+        let mut out = Vec::new();
+        let mut writer = LimitedWriter::new(&mut out);
+        writer.write_all(b"a\tb\nc").unwrap();
+        writer.write_all(b"\td\ne").unwrap();
+        let err = writer.write_all(b"\tf\n").unwrap_err();
+        assert_eq!(err.to_string(), "output truncated at --max-output-lines 2");
+        assert!(writer.write_all(b"g\n").is_err());
+        drop(writer);
+        assert_eq!(out, b"a\tb\nc\td\n");
+        assert_eq!(output_limit_exceeded(), Some(OutputLimitExceeded::Lines(2)));
+
+        set_output_limits(None, Some(11));
+        reset();
+        let mut out = Vec::new();
+        let mut writer = LimitedWriter::new(&mut out);
+        writer.write_all(b"12345\n1234\n").unwrap();
+        assert!(writer.write_all(b"x\n").is_err());
+        drop(writer);
+        assert_eq!(out, b"12345\n1234\n");
+        assert_eq!(
+            output_limit_exceeded(),
+            Some(OutputLimitExceeded::Bytes(11))
+        );
+
+        set_output_limits(None, None);
+        reset();
+    }
+}
@@ -1,17 +1,221 @@
 //! Parallel processing utilities using Rayon.
+//!
+//! Commands run their parallel sections on a [`PoolHandle`], which defaults to
+//! the global Rayon pool. Embedders can give each command its own pool so
+//! grit's parallelism is isolated from theirs and concurrent operations keep
+//! separate thread budgets.
+//!
+//! With the `numa` feature on Linux, pools can pin their workers to CPUs or
+//! NUMA nodes ([`ThreadPlacement`]) and large scratch buffers are allocated by
+//! the workers that use them ([`first_touch_vec`]), so their pages stay on the
+//! local node of a multi-socket machine.
 
-use crate::bed::Result as BedResult;
+use crate::bed::{BedError, Result as BedResult};
 
 /// Minimum number of intervals before enabling parallelization.
 /// Below this threshold, sequential processing is faster due to
 /// thread spawn overhead.
 pub const PARALLEL_THRESHOLD: usize = 10_000;
 use crate::interval::{BedRecord, Interval};
+use crate::streaming::parsing::should_skip_line;
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::sync::Arc;
+
+/// Thread pool that a command runs its parallel work on.
+///
+/// The default handle uses whichever pool is current when the command runs:
+/// the global pool, or the enclosing pool when called from inside
+/// `ThreadPool::install`. Cloning a handle shares the same pool.
+#[derive(Debug, Clone, Default)]
+pub struct PoolHandle {
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl PoolHandle {
+    /// Use the current (by default, global) Rayon pool.
+    pub fn global() -> Self {
+        Self::default()
+    }
+
+    /// Build a dedicated pool with `num_threads` threads.
+    pub fn with_threads(num_threads: usize) -> BedResult<Self> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| BedError::InvalidFormat(format!("Failed to build thread pool: {}", e)))?;
+        Ok(Self::from_pool(Arc::new(pool)))
+    }
+
+    /// Run on an existing pool.
+    pub fn from_pool(pool: Arc<ThreadPool>) -> Self {
+        Self { pool: Some(pool) }
+    }
+
+    /// Run `op` with this pool as the current Rayon pool.
+    ///
+    /// Parallel iterators and `rayon::current_num_threads` inside `op` use the
+    /// pool; with the default handle `op` simply runs on the calling thread.
+    #[inline]
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    /// Number of threads parallel work will use.
+    pub fn current_num_threads(&self) -> usize {
+        match &self.pool {
+            Some(pool) => pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        }
+    }
+}
+
+/// Where pool workers run (`numa` feature, Linux only).
+#[cfg(all(feature = "numa", target_os = "linux"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPlacement {
+    /// Pin each worker to a single CPU, filling one NUMA node before the next.
+    Cores,
+    /// Pin workers round-robin to the CPU set of a NUMA node, leaving the
+    /// scheduler free to move them within the node.
+    Nodes,
+}
+
+#[cfg(all(feature = "numa", target_os = "linux"))]
+static PLACEMENT_ACTIVE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(all(feature = "numa", target_os = "linux"))]
+impl ThreadPlacement {
+    /// Parse `cores` or `nodes`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cores" => Some(Self::Cores),
+            "nodes" => Some(Self::Nodes),
+            _ => None,
+        }
+    }
+
+    /// Make the pools built by `builder` pin their workers.
+    pub fn apply(self, builder: ThreadPoolBuilder) -> BedResult<ThreadPoolBuilder> {
+        let nodes = numa_nodes();
+        let cpusets: Vec<Vec<usize>> = match self {
+            Self::Cores => nodes.concat().into_iter().map(|cpu| vec![cpu]).collect(),
+            Self::Nodes => nodes,
+        };
+        if cpusets.is_empty() {
+            return Err(BedError::InvalidFormat(
+                "No CPUs available for thread pinning".to_string(),
+            ));
+        }
+        PLACEMENT_ACTIVE.store(true, std::sync::atomic::Ordering::Relaxed);
+        Ok(builder.start_handler(move |index| {
+            // Pinning is an optimisation: a worker that cannot be pinned
+            // still runs, wherever the scheduler puts it
+            let _ = pin_current_thread(&cpusets[index % cpusets.len()]);
+        }))
+    }
+}
+
+#[cfg(all(feature = "numa", target_os = "linux"))]
+impl PoolHandle {
+    /// Build a dedicated pool with `num_threads` pinned threads.
+    pub fn with_placement(num_threads: usize, placement: ThreadPlacement) -> BedResult<Self> {
+        let pool = placement
+            .apply(ThreadPoolBuilder::new().num_threads(num_threads))?
+            .build()
+            .map_err(|e| BedError::InvalidFormat(format!("Failed to build thread pool: {}", e)))?;
+        Ok(Self::from_pool(Arc::new(pool)))
+    }
+}
+
+/// CPUs of each NUMA node, from sysfs; one node holding every CPU when the
+/// kernel exposes no topology.
+#[cfg(all(feature = "numa", target_os = "linux"))]
+pub fn numa_nodes() -> Vec<Vec<usize>> {
+    let mut nodes: Vec<(usize, Vec<usize>)> = std::fs::read_dir("/sys/devices/system/node")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let id = name.to_str()?.strip_prefix("node")?.parse().ok()?;
+            let cpus = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((id, parse_cpulist(&cpus)?))
+        })
+        .filter(|(_, cpus)| !cpus.is_empty())
+        .collect();
+    nodes.sort_unstable();
+    if nodes.is_empty() {
+        let n = std::thread::available_parallelism().map_or(1, |n| n.get());
+        return vec![(0..n).collect()];
+    }
+    nodes.into_iter().map(|(_, cpus)| cpus).collect()
+}
+
+/// Parse a kernel CPU list such as `0-3,8-11,16`.
+#[cfg(all(feature = "numa", target_os = "linux"))]
+fn parse_cpulist(s: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in s.trim().split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((lo, hi)) => cpus.extend(lo.parse::<usize>().ok()?..=hi.parse().ok()?),
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+/// Restrict the calling thread to `cpus`.
+#[cfg(all(feature = "numa", target_os = "linux"))]
+fn pin_current_thread(cpus: &[usize]) -> std::io::Result<()> {
+    // SAFETY: cpu_set_t is plain data; CPU_SET is only given indices below
+    // CPU_SETSIZE, and sched_setaffinity reads exactly size_of::<cpu_set_t>().
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// A `len`-element vector of `value`, written by the current pool's workers.
+///
+/// Linux places a page on the NUMA node of the thread that first writes it,
+/// so when the workers are pinned each chunk lands next to the worker that
+/// fills it. Without pinning this is `vec![value; len]`.
+pub fn first_touch_vec<T: Copy + Send + Sync>(len: usize, value: T) -> Vec<T> {
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    if PLACEMENT_ACTIVE.load(std::sync::atomic::Ordering::Relaxed) && len >= PARALLEL_THRESHOLD {
+        let mut buf = Vec::with_capacity(len);
+        let chunk = len.div_ceil(rayon::current_num_threads());
+        buf.spare_capacity_mut()
+            .par_chunks_mut(chunk)
+            .for_each(|part| {
+                for slot in part {
+                    slot.write(value);
+                }
+            });
+        // SAFETY: every element of the spare capacity was initialised above.
+        unsafe { buf.set_len(len) };
+        return buf;
+    }
+    vec![value; len]
+}
 
 /// Group intervals by chromosome for parallel processing.
 pub fn group_by_chromosome(intervals: Vec<Interval>) -> HashMap<String, Vec<Interval>> {
@@ -27,6 +231,20 @@ pub fn group_by_chromosome(intervals: Vec<Interval>) -> HashMap<String, Vec<Inte
     groups
 }
 
+/// Group borrowed intervals by chromosome without copying them.
+pub fn group_refs_by_chromosome(intervals: &[Interval]) -> HashMap<&str, Vec<&Interval>> {
+    let mut groups: HashMap<&str, Vec<&Interval>> = HashMap::new();
+
+    for interval in intervals {
+        groups
+            .entry(interval.chrom.as_str())
+            .or_default()
+            .push(interval);
+    }
+
+    groups
+}
+
 /// Group BED records by chromosome.
 pub fn group_records_by_chromosome(records: Vec<BedRecord>) -> HashMap<String, Vec<BedRecord>> {
     let mut groups: HashMap<String, Vec<BedRecord>> = HashMap::new();
@@ -89,13 +307,7 @@ pub fn parallel_read_bed<P: AsRef<Path>>(path: P) -> BedResult<Vec<Interval>> {
     let lines: Vec<String> = reader
         .lines()
         .filter_map(|l| l.ok())
-        .filter(|l| {
-            let trimmed = l.trim();
-            !trimmed.is_empty()
-                && !trimmed.starts_with('#')
-                && !trimmed.starts_with("track")
-                && !trimmed.starts_with("browser")
-        })
+        .filter(|l| !should_skip_line(l.trim().as_bytes()))
         .collect();
 
     // Parse in parallel
@@ -131,7 +343,7 @@ impl ParallelStats {
             .iter()
             .map(|(chrom, intervals)| (chrom.clone(), intervals.len()))
             .collect();
-        intervals_per_chrom.sort_by(|a, b| b.1.cmp(&a.1));
+        intervals_per_chrom.sort_by_key(|b| std::cmp::Reverse(b.1));
 
         Self {
             total_intervals: groups.values().map(|v| v.len()).sum(),
@@ -187,6 +399,54 @@ mod tests {
         assert_eq!(groups.get("chr2").unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_pool_handle_threads() {
+        let pool = PoolHandle::with_threads(2).unwrap();
+        assert_eq!(pool.current_num_threads(), 2);
+        assert_eq!(pool.install(rayon::current_num_threads), 2);
+
+        // Clones share the pool
+        let shared = pool.clone();
+        assert_eq!(shared.install(rayon::current_num_threads), 2);
+
+        let global = PoolHandle::global();
+        assert_eq!(
+            global.current_num_threads(),
+            global.install(rayon::current_num_threads)
+        );
+    }
+
+    #[test]
+    fn test_first_touch_vec() {
+        assert_eq!(first_touch_vec(3, 7u8), vec![7, 7, 7]);
+        let big = first_touch_vec(PARALLEL_THRESHOLD * 3 + 1, 1u32);
+        assert_eq!(big.len(), PARALLEL_THRESHOLD * 3 + 1);
+        assert!(big.iter().all(|&v| v == 1));
+    }
+
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    #[test]
+    fn test_thread_placement() {
+        assert_eq!(
+            parse_cpulist("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpulist("x"), None);
+        assert!(!numa_nodes().is_empty());
+
+        for placement in [ThreadPlacement::Cores, ThreadPlacement::Nodes] {
+            let pool = PoolHandle::with_placement(2, placement).unwrap();
+            assert_eq!(pool.install(rayon::current_num_threads), 2);
+            let v = pool.install(|| first_touch_vec(PARALLEL_THRESHOLD * 2, 5u16));
+            assert!(v.iter().all(|&x| x == 5));
+        }
+        assert_eq!(
+            ThreadPlacement::parse("nodes"),
+            Some(ThreadPlacement::Nodes)
+        );
+        assert_eq!(ThreadPlacement::parse("numa"), None);
+    }
+
     #[test]
     fn test_parallel_sort() {
         let intervals = vec![
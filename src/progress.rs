@@ -0,0 +1,250 @@
+//! Progress reports for commands that walk a whole genome.
+//!
+//! Input size is a poor measure of progress when coverage is uneven, but a
+//! command that visits the chromosomes of a genome file in order knows how
+//! far along the genome it is. [`GenomeProgress`] turns the current position
+//! into a percentage of the genome's length and an ETA, and can note each
+//! chromosome as it finishes, since chromosome sizes differ by two orders of
+//! magnitude.
+//!
+//! Reports go to stderr: rewritten in place on a terminal, otherwise one line
+//! every few seconds so logs stay short.
+
+use crate::genome::Genome;
+use std::io::{self, IsTerminal, Stderr, Write};
+use std::time::{Duration, Instant};
+
+/// Positions between clock reads.
+const CHECK_EVERY: u32 = 4096;
+
+/// Tracks the position reached in a genome and reports it.
+pub struct GenomeProgress<W: Write> {
+    out: W,
+    label: String,
+    names: Vec<String>,
+    /// Genome offset of each chromosome's first base
+    offsets: Vec<u64>,
+    total: u64,
+    verbose: bool,
+    in_place: bool,
+    interval: Duration,
+    started: Instant,
+    last_report: Instant,
+    chrom_started: Instant,
+    /// Chromosome being visited
+    current: usize,
+    done: u64,
+    ticks: u32,
+    /// A report without a newline is on the terminal
+    pending: bool,
+}
+
+impl GenomeProgress<Stderr> {
+    /// Progress for `label` on stderr.
+    pub fn stderr(label: &str, genome: &Genome, verbose: bool) -> Self {
+        let in_place = io::stderr().is_terminal();
+        let interval = if in_place {
+            Duration::from_millis(500)
+        } else {
+            Duration::from_secs(10)
+        };
+        Self::new(label, genome, io::stderr())
+            .with_verbose(verbose)
+            .with_in_place(in_place)
+            .with_interval(interval)
+    }
+}
+
+impl<W: Write> GenomeProgress<W> {
+    pub fn new(label: &str, genome: &Genome, out: W) -> Self {
+        let mut names = Vec::with_capacity(genome.len());
+        let mut offsets = Vec::with_capacity(genome.len() + 1);
+        let mut total = 0u64;
+        for chrom in genome.chromosomes() {
+            names.push(chrom.clone());
+            offsets.push(total);
+            total += genome.chrom_size(chrom).unwrap_or(0);
+        }
+        offsets.push(total);
+
+        let now = Instant::now();
+        Self {
+            out,
+            label: label.to_string(),
+            names,
+            offsets,
+            total,
+            verbose: false,
+            in_place: false,
+            interval: Duration::from_secs(10),
+            started: now,
+            last_report: now,
+            chrom_started: now,
+            current: 0,
+            done: 0,
+            ticks: 0,
+            pending: false,
+        }
+    }
+
+    /// Also report each chromosome as it is finished.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Rewrite one status line with `\r` instead of printing new lines.
+    pub fn with_in_place(mut self, in_place: bool) -> Self {
+        self.in_place = in_place;
+        self
+    }
+
+    /// Minimum time between reports.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Record that position `pos` of chromosome `chrom_idx` (genome order)
+    /// has been reached. Earlier chromosomes count as finished.
+    pub fn update(&mut self, chrom_idx: usize, pos: u64) {
+        let moved = chrom_idx > self.current;
+        if moved {
+            self.finish_chroms(chrom_idx);
+        }
+        if chrom_idx < self.names.len() {
+            let size = self.offsets[chrom_idx + 1] - self.offsets[chrom_idx];
+            self.done = self.done.max(self.offsets[chrom_idx] + pos.min(size));
+        }
+
+        let check = moved || self.ticks % CHECK_EVERY == 0;
+        self.ticks = self.ticks.wrapping_add(1);
+        if check && self.last_report.elapsed() >= self.interval {
+            self.report();
+        }
+    }
+
+    /// Mark the whole genome as done and write the final report.
+    pub fn finish(&mut self) {
+        self.finish_chroms(self.names.len());
+        self.done = self.total;
+        self.clear_line();
+        let _ = writeln!(
+            self.out,
+            "{}: 100.0% of {} bp in {}",
+            self.label,
+            self.total,
+            format_duration(self.started.elapsed())
+        );
+    }
+
+    /// Finish the chromosomes before `chrom_idx`.
+    fn finish_chroms(&mut self, chrom_idx: usize) {
+        let end = chrom_idx.min(self.names.len());
+        while self.current < end {
+            let i = self.current;
+            if self.verbose {
+                self.clear_line();
+                let _ = writeln!(
+                    self.out,
+                    "{}: {} done ({} bp, {})",
+                    self.label,
+                    self.names[i],
+                    self.offsets[i + 1] - self.offsets[i],
+                    format_duration(self.chrom_started.elapsed())
+                );
+            }
+            self.done = self.done.max(self.offsets[i + 1]);
+            self.current += 1;
+            self.chrom_started = Instant::now();
+        }
+    }
+
+    fn report(&mut self) {
+        let elapsed = self.started.elapsed();
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        };
+        let eta = if fraction > 0.0 {
+            format_duration(elapsed.mul_f64((1.0 - fraction) / fraction))
+        } else {
+            "?".to_string()
+        };
+        let chrom = self.names.get(self.current).map_or("-", String::as_str);
+        let line = format!(
+            "{}: {:.1}% ({}), {} elapsed, ETA {}",
+            self.label,
+            fraction * 100.0,
+            chrom,
+            format_duration(elapsed),
+            eta
+        );
+        let _ = if self.in_place {
+            write!(self.out, "\r{}\x1b[K", line)
+        } else {
+            writeln!(self.out, "{}", line)
+        };
+        let _ = self.out.flush();
+        self.pending = self.in_place;
+        self.last_report = Instant::now();
+    }
+
+    /// End an in-place status line before writing a regular one.
+    fn clear_line(&mut self) {
+        if self.pending {
+            let _ = write!(self.out, "\r\x1b[K");
+            self.pending = false;
+        }
+    }
+}
+
+/// Format a duration as `4.2s`, `3m12s` or `1h02m`.
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", d.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genome_progress() {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 300);
+        genome.insert("chr2".to_string(), 100);
+
+        let mut out = Vec::new();
+        let mut progress = GenomeProgress::new("genomecov", &genome, &mut out)
+            .with_verbose(true)
+            .with_interval(Duration::ZERO);
+        progress.update(0, 100);
+        progress.update(1, 50);
+        progress.finish();
+        drop(progress);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("genomecov: 25.0% (chr1), "));
+        assert!(lines[1].starts_with("genomecov: chr1 done (300 bp, "));
+        assert!(lines[2].starts_with("genomecov: 87.5% (chr2), "));
+        assert!(lines[3].starts_with("genomecov: chr2 done (100 bp, "));
+        assert!(lines[4].starts_with("genomecov: 100.0% of 400 bp in "));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(4200)), "4.2s");
+        assert_eq!(format_duration(Duration::from_secs(192)), "3m12s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m");
+    }
+}
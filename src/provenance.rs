@@ -0,0 +1,234 @@
+//! Provenance header lines for reproducible outputs.
+//!
+//! With `--provenance` every command starts its output with a comment line
+//! recording the grit version, the command line, a timestamp and the SHA-256
+//! of each input file:
+//!
+//! ```text
+//! ##grit version=0.1.1 date=2026-01-05T09:30:00Z cmd=grit intersect -a a.bed -b b.bed input=a.bed;sha256=… input=b.bed;sha256=…
+//! ```
+//!
+//! Fields are separated by tabs (shown as spaces above). Provenance lines
+//! found at the top of an input are copied ahead of the command's own line,
+//! so the output of a pipeline of grit commands carries the history of every
+//! step. BED readers skip them like any other `#` comment.
+
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Start of every provenance line.
+pub const PROVENANCE_PREFIX: &str = "##grit\t";
+
+/// Provenance of one grit invocation.
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    command_line: String,
+    timestamp: i64,
+    /// (path, hex SHA-256); stdin has no checksum.
+    inputs: Vec<(String, Option<String>)>,
+    /// Provenance lines of the inputs, in order, without duplicates.
+    upstream: Vec<String>,
+}
+
+impl Provenance {
+    /// Start a record for `args` (program name first), timestamped now.
+    ///
+    /// `SOURCE_DATE_EPOCH` overrides the clock, as for `grit --version`.
+    pub fn new<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0)
+            });
+        let command_line = args
+            .into_iter()
+            .map(|arg| quote_arg(arg.as_ref()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            command_line,
+            timestamp,
+            inputs: Vec::new(),
+            upstream: Vec::new(),
+        }
+    }
+
+    /// Record an input file: its checksum and any provenance lines at its top.
+    ///
+    /// `-` (stdin) is listed without a checksum since it can only be read once.
+    pub fn add_input(&mut self, path: &Path) -> io::Result<()> {
+        let name = path.display().to_string();
+        if name == "-" {
+            self.inputs.push((name, None));
+            return Ok(());
+        }
+
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && line.starts_with('#') {
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            if trimmed.starts_with(PROVENANCE_PREFIX) && !self.upstream.iter().any(|l| l == trimmed)
+            {
+                self.upstream.push(trimmed.to_string());
+            }
+            line.clear();
+        }
+
+        self.inputs.push((name, Some(sha256_file(path)?)));
+        Ok(())
+    }
+
+    /// This invocation's provenance line, without a newline.
+    pub fn line(&self) -> String {
+        let mut line = format!(
+            "{}version={}\tdate={}\tcmd={}",
+            PROVENANCE_PREFIX,
+            crate::VERSION,
+            format_timestamp(self.timestamp),
+            self.command_line
+        );
+        for (path, digest) in &self.inputs {
+            match digest {
+                Some(hex) => line.push_str(&format!("\tinput={};sha256={}", path, hex)),
+                None => line.push_str(&format!("\tinput={}", path)),
+            }
+        }
+        line
+    }
+
+    /// Write the inputs' provenance lines followed by this invocation's.
+    pub fn write_header<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for line in &self.upstream {
+            writeln!(out, "{}", line)?;
+        }
+        writeln!(out, "{}", self.line())
+    }
+}
+
+/// Hex SHA-256 of a file's contents.
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Quote an argument that would not survive being split on whitespace.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '\'') {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// UTC ISO 8601 timestamp (`YYYY-MM-DDTHH:MM:SSZ`) of seconds since the epoch.
+fn format_timestamp(secs: i64) -> String {
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let tod = secs.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        tod / 3600,
+        tod % 3600 / 60,
+        tod % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_827_696), "2000-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"abc").unwrap();
+        file.flush().unwrap();
+        assert_eq!(
+            sha256_file(file.path()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("a.bed"), "a.bed");
+        assert_eq!(quote_arg("my file.bed"), "'my file.bed'");
+        assert_eq!(quote_arg("it's"), "'it'\\''s'");
+        assert_eq!(quote_arg(""), "''");
+    }
+
+    #[test]
+    fn test_header_carries_upstream() {
+        let mut input = NamedTempFile::new().unwrap();
+        write!(
+            input,
+            "##grit\tversion=0.0.1\tcmd=grit sort -i raw.bed\n#other comment\nchr1\t1\t2\n"
+        )
+        .unwrap();
+        input.flush().unwrap();
+
+        let mut prov = Provenance::new(["grit", "merge", "-i", "x y.bed"]);
+        prov.add_input(input.path()).unwrap();
+        prov.add_input(input.path()).unwrap();
+        prov.add_input(Path::new("-")).unwrap();
+
+        let mut out = Vec::new();
+        prov.write_header(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "##grit\tversion=0.0.1\tcmd=grit sort -i raw.bed");
+
+        let fields: Vec<&str> = lines[1].split('\t').collect();
+        assert_eq!(fields[0], "##grit");
+        assert_eq!(fields[1], format!("version={}", crate::VERSION));
+        assert_eq!(fields[3], "cmd=grit merge -i 'x y.bed'");
+        let digest = sha256_file(input.path()).unwrap();
+        assert_eq!(
+            fields[4],
+            format!("input={};sha256={}", input.path().display(), digest)
+        );
+        assert_eq!(fields[6], "input=-");
+    }
+}
@@ -0,0 +1,171 @@
+//! Seeded random numbers for commands that sample.
+//!
+//! Every command that draws random numbers (`generate`, `shuffle`) takes a
+//! `--seed` and gets its numbers from a [`SeededRng`]. The generator and the
+//! way draws are turned into ranges are implemented here rather than taken
+//! from a dependency, so the numbers a seed produces are fixed by grit
+//! alone:
+//!
+//! - The same seed, input and options give byte-identical output on every
+//!   platform, thread count and build.
+//! - Output for a given seed changes only in a release whose CHANGELOG says
+//!   so, e.g. when a command starts drawing its numbers in another order.
+//!
+//! The generator is xoshiro256++ seeded through SplitMix64. Ranges are
+//! drawn with Lemire's multiply-and-reject method, which is unbiased. A
+//! command that needs several independent sequences from one seed (such as
+//! the A and B files of `generate`) takes them from [`SeededRng::stream`]
+//! instead of inventing nearby seeds.
+//!
+//! ```
+//! use grit_genomics::rng::SeededRng;
+//!
+//! let mut a = SeededRng::new(42);
+//! let mut b = SeededRng::new(42);
+//! assert_eq!(a.range(0..1000), b.range(0..1000));
+//! ```
+
+use std::ops::{Range, RangeInclusive};
+
+/// Seed used when a command is not given one.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// Deterministic random number generator with a stable output sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    state: [u64; 4],
+}
+
+impl SeededRng {
+    /// Generator for `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self::stream(seed, 0)
+    }
+
+    /// Independent sequence number `stream` of `seed`.
+    ///
+    /// Stream 0 is the sequence of [`SeededRng::new`].
+    pub fn stream(seed: u64, stream: u64) -> Self {
+        let mut sm = seed ^ stream.wrapping_mul(0xD1B5_4A32_D192_ED03);
+        let mut state = [0u64; 4];
+        for word in &mut state {
+            *word = splitmix64(&mut sm);
+        }
+        Self { state }
+    }
+
+    /// Next 64 random bits.
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform value in `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is 0.
+    #[inline]
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "cannot draw from an empty range");
+        let mut m = self.next_u64() as u128 * n as u128;
+        if (m as u64) < n {
+            let threshold = n.wrapping_neg() % n;
+            while (m as u64) < threshold {
+                m = self.next_u64() as u128 * n as u128;
+            }
+        }
+        (m >> 64) as u64
+    }
+
+    /// Uniform value in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range is empty.
+    #[inline]
+    pub fn range(&mut self, range: Range<u64>) -> u64 {
+        assert!(range.start < range.end, "cannot draw from an empty range");
+        range.start + self.below(range.end - range.start)
+    }
+
+    /// Uniform value in `range`, both ends included.
+    ///
+    /// # Panics
+    ///
+    /// If the range is empty.
+    #[inline]
+    pub fn range_inclusive(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "cannot draw from an empty range");
+        match (end - start).checked_add(1) {
+            Some(n) => start + self.below(n),
+            None => self.next_u64(),
+        }
+    }
+
+    /// Uniform value in `[0, 1)` with 53 bits of precision.
+    #[inline]
+    pub fn unit_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+impl Default for SeededRng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+#[inline]
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_is_pinned() {
+        // These values are part of the reproducibility guarantee: a change
+        // here changes the output of every seeded command
+        let mut rng = SeededRng::new(42);
+        let draws: Vec<u64> = (0..4).map(|_| rng.range(0..1_000_000)).collect();
+        assert_eq!(draws, [814305, 318821, 983894, 701135]);
+        assert_ne!(SeededRng::stream(42, 1), SeededRng::new(42));
+        assert_ne!(SeededRng::new(43), SeededRng::new(42));
+    }
+
+    #[test]
+    fn test_ranges_stay_in_bounds() {
+        let mut rng = SeededRng::new(7);
+        for _ in 0..10_000 {
+            assert!((10..20).contains(&rng.range(10..20)));
+            assert!((5..=6).contains(&rng.range_inclusive(5..=6)));
+            assert!((0.0..1.0).contains(&rng.unit_f64()));
+        }
+        assert_eq!(rng.range_inclusive(3..=3), 3);
+        rng.range_inclusive(0..=u64::MAX);
+
+        // Every value of a small range is reachable
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            seen[rng.below(6) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
@@ -0,0 +1,214 @@
+//! Scratch space for intermediate files.
+//!
+//! Commands that spill to disk (the external sort of `generate`, the merge
+//! rounds of `multiinter`) create a [`ScratchDir`] and write their files
+//! into it. The directory is placed under `--temp-dir`, or `TMPDIR` when that is not given, and is
+//! removed when the guard is dropped, so an early return on error leaves
+//! nothing behind.
+//!
+//! Release builds abort on panic without running destructors, so the live
+//! scratch directories are also registered with a panic hook that removes
+//! them before the process dies. A killed process (`SIGKILL`, OOM killer)
+//! still cannot clean up; scratch directories are named `grit-*` to make
+//! leftovers easy to find.
+//!
+//! Bytes written through [`ScratchFile`]s are counted per directory and
+//! process-wide, with the peak kept for reporting.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use tempfile::TempDir;
+
+static TEMP_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Directories to remove if the process panics.
+static LIVE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static PANIC_HOOK: Once = Once::new();
+static CURRENT_BYTES: AtomicU64 = AtomicU64::new(0);
+static PEAK_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Set the parent directory for scratch space; `None` uses `TMPDIR`.
+pub fn set_temp_dir(dir: Option<PathBuf>) {
+    *TEMP_DIR.lock().unwrap() = dir;
+}
+
+/// Parent directory for scratch space.
+pub fn temp_dir() -> PathBuf {
+    TEMP_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Scratch bytes currently on disk and the most at any time, process-wide.
+pub fn scratch_usage() -> (u64, u64) {
+    (
+        CURRENT_BYTES.load(Ordering::Relaxed),
+        PEAK_BYTES.load(Ordering::Relaxed),
+    )
+}
+
+/// Remove every live scratch directory (run from the panic hook).
+fn remove_live_dirs() {
+    // The panic may have happened while the registry was locked
+    let live = match LIVE.try_lock() {
+        Ok(live) => live.clone(),
+        Err(_) => return,
+    };
+    for dir in live {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            remove_live_dirs();
+            previous(info);
+        }));
+    });
+}
+
+/// A directory of scratch files, removed with everything in it on drop.
+pub struct ScratchDir {
+    dir: TempDir,
+    bytes: Arc<AtomicU64>,
+}
+
+impl ScratchDir {
+    /// Create a scratch directory under [`temp_dir`].
+    pub fn new() -> io::Result<Self> {
+        Self::in_dir(temp_dir())
+    }
+
+    /// Create a scratch directory under `parent`.
+    pub fn in_dir<P: AsRef<Path>>(parent: P) -> io::Result<Self> {
+        let parent = parent.as_ref();
+        install_panic_hook();
+        let dir = tempfile::Builder::new()
+            .prefix("grit-")
+            .tempdir_in(parent)
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "cannot create scratch directory in {}: {} (set --temp-dir or TMPDIR)",
+                        parent.display(),
+                        e
+                    ),
+                )
+            })?;
+        LIVE.lock().unwrap().push(dir.path().to_path_buf());
+        Ok(Self {
+            dir,
+            bytes: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Create (or truncate) the scratch file `name`.
+    pub fn create(&self, name: &str) -> io::Result<ScratchFile> {
+        let path = self.dir.path().join(name);
+        Ok(ScratchFile {
+            file: File::create(&path)?,
+            path,
+            bytes: Arc::clone(&self.bytes),
+        })
+    }
+
+    /// Bytes written to this directory's files.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Ok(mut live) = LIVE.lock() {
+            live.retain(|dir| dir != self.dir.path());
+        }
+        CURRENT_BYTES.fetch_sub(self.bytes_written(), Ordering::Relaxed);
+    }
+}
+
+/// A file in a [`ScratchDir`] that counts the bytes written to it.
+pub struct ScratchFile {
+    file: File,
+    path: PathBuf,
+    bytes: Arc<AtomicU64>,
+}
+
+impl ScratchFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Write for ScratchFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.file.write(buf)?;
+        self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        let current = CURRENT_BYTES.fetch_add(n as u64, Ordering::Relaxed) + n as u64;
+        PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The registry of live directories is process-wide, so one test covers
+    // both ways of cleaning up.
+    #[test]
+    fn test_scratch_dir_cleanup_and_accounting() {
+        let parent = tempfile::tempdir().unwrap();
+        let scratch = ScratchDir::in_dir(parent.path()).unwrap();
+        let path = scratch.path().to_path_buf();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("grit-"));
+
+        let mut file = scratch.create("chunk_0.bed").unwrap();
+        file.write_all(b"chr1\t1\t2\n").unwrap();
+        file.write_all(b"chr1\t3\t4\n").unwrap();
+        assert_eq!(scratch.bytes_written(), 18);
+        assert!(scratch_usage().1 >= 18);
+        assert!(LIVE.lock().unwrap().contains(&path));
+
+        drop(file);
+        drop(scratch);
+        assert!(!path.exists());
+        assert!(!LIVE.lock().unwrap().contains(&path));
+
+        // What the panic hook does when destructors will not run
+        let scratch = ScratchDir::in_dir(parent.path()).unwrap();
+        scratch.create("x").unwrap().write_all(b"x").unwrap();
+        let path = scratch.path().to_path_buf();
+        remove_live_dirs();
+        assert!(!path.exists());
+        drop(scratch);
+    }
+
+    #[test]
+    fn test_unwritable_temp_dir() {
+        let err = ScratchDir::in_dir("/nonexistent/grit-scratch")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("--temp-dir"));
+    }
+}
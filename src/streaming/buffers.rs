@@ -12,6 +12,11 @@ pub const DEFAULT_OUTPUT_BUFFER: usize = 2 * 1024 * 1024;
 /// Use this when memory is extremely constrained.
 pub const LOW_MEMORY_OUTPUT_BUFFER: usize = 256 * 1024;
 
+/// Output batch size (1 MB) for [`BatchWriter`](super::BatchWriter).
+/// Records are assembled in memory and handed to the sink in one write
+/// once this many bytes are pending.
+pub const OUTPUT_BATCH_SIZE: usize = 1024 * 1024;
+
 /// Default input buffer size (256 KB).
 /// Good balance for reading sorted BED files.
 pub const DEFAULT_INPUT_BUFFER: usize = 256 * 1024;
@@ -0,0 +1,127 @@
+//! Interned chromosome names for streaming commands.
+//!
+//! Streaming commands remember the chromosomes they have passed, to reject
+//! unsorted input and to skip B records on chromosomes A never visits.
+//! [`ChromTable`] stores each name once and hands out a dense [`ChromId`] in
+//! first-seen order, so a chromosome change allocates only the first time a
+//! name appears and later checks compare integers instead of byte strings.
+//!
+//! Built from a genome file, the ids follow the genome order and comparing
+//! two ids compares the positions of their chromosomes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Dense id of an interned chromosome name.
+pub type ChromId = u32;
+
+/// Chromosome names interned to dense ids.
+#[derive(Debug, Clone, Default)]
+pub struct ChromTable {
+    ids: HashMap<Arc<[u8]>, ChromId>,
+    names: Vec<Arc<[u8]>>,
+}
+
+impl ChromTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Table holding the genome's chromosomes, with ids in genome order.
+    pub fn from_genome(genome: &crate::genome::Genome) -> Self {
+        let mut table = Self::new();
+        for chrom in genome.chromosomes() {
+            table.intern(chrom.as_bytes());
+        }
+        table
+    }
+
+    /// Id of `name`, adding it to the table if it is new.
+    #[inline]
+    pub fn intern(&mut self, name: &[u8]) -> ChromId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as ChromId;
+        let name: Arc<[u8]> = Arc::from(name);
+        self.ids.insert(Arc::clone(&name), id);
+        self.names.push(name);
+        id
+    }
+
+    /// Id of `name` if it has been interned.
+    #[inline]
+    pub fn get(&self, name: &[u8]) -> Option<ChromId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Whether `name` has been interned.
+    #[inline]
+    pub fn contains(&self, name: &[u8]) -> bool {
+        self.ids.contains_key(name)
+    }
+
+    /// Name of an interned id.
+    ///
+    /// # Panics
+    ///
+    /// If `id` was not handed out by this table.
+    #[inline]
+    pub fn name(&self, id: ChromId) -> &[u8] {
+        &self.names[id as usize]
+    }
+
+    /// Name of an interned id as text, for error messages.
+    pub fn name_lossy(&self, id: ChromId) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(self.name(id))
+    }
+
+    /// Number of interned names.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Forget all names; ids are handed out from 0 again.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.names.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Genome;
+
+    #[test]
+    fn test_intern_is_dense_and_stable() {
+        let mut table = ChromTable::new();
+        assert_eq!(table.intern(b"chr2"), 0);
+        assert_eq!(table.intern(b"chr1"), 1);
+        assert_eq!(table.intern(b"chr2"), 0);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(b"chr1"), Some(1));
+        assert_eq!(table.get(b"chrX"), None);
+        assert!(table.contains(b"chr2"));
+        assert_eq!(table.name(1), b"chr1");
+
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.intern(b"chr1"), 0);
+    }
+
+    #[test]
+    fn test_from_genome_follows_genome_order() {
+        let mut genome = Genome::new();
+        genome.insert("chr10".to_string(), 100);
+        genome.insert("chr2".to_string(), 200);
+        genome.insert("chr1".to_string(), 300);
+        let table = ChromTable::from_genome(&genome);
+        assert!(table.get(b"chr10") < table.get(b"chr2"));
+        assert!(table.get(b"chr2") < table.get(b"chr1"));
+    }
+}
@@ -0,0 +1,203 @@
+//! On-the-fly complement of a sorted BED stream.
+//!
+//! `ComplementStream` yields the gaps between intervals of a BED reader,
+//! chromosome by chromosome in genome-file order, without materializing the
+//! complement. Memory is O(1) in the number of input intervals.
+
+use crate::bed::BedError;
+use crate::genome::Genome;
+use crate::streaming::parsing::{parse_bed3_bytes, should_skip_line};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// A region not covered by any input interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// Index of the chromosome in genome-file order.
+    pub chrom_idx: usize,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Streaming complement of a BED reader sorted in genome order.
+///
+/// Input intervals on chromosomes missing from the genome are ignored,
+/// matching `grit complement`. Chromosomes with no input intervals yield a
+/// single gap spanning the whole chromosome.
+pub struct ComplementStream<'g, R: BufRead> {
+    reader: R,
+    chroms: Vec<(&'g str, u64)>,
+    chrom_order: HashMap<&'g str, usize>,
+    line_buf: String,
+    /// Chromosome currently being emitted.
+    chrom_idx: usize,
+    /// End of input coverage so far on the current chromosome.
+    covered_to: u64,
+    /// Next input interval: (chrom index, start, end).
+    pending: Option<(usize, u64, u64)>,
+    exhausted: bool,
+}
+
+impl<'g, R: BufRead> ComplementStream<'g, R> {
+    /// Create a complement stream over `reader` using chromosome sizes from `genome`.
+    pub fn new(reader: R, genome: &'g Genome) -> Self {
+        let chroms: Vec<(&str, u64)> = genome
+            .chromosomes()
+            .map(|c| (c.as_str(), genome.chrom_size(c).unwrap_or(0)))
+            .collect();
+        let chrom_order = chroms
+            .iter()
+            .enumerate()
+            .map(|(i, (c, _))| (*c, i))
+            .collect();
+
+        Self {
+            reader,
+            chroms,
+            chrom_order,
+            line_buf: String::with_capacity(1024),
+            chrom_idx: 0,
+            covered_to: 0,
+            pending: None,
+            exhausted: false,
+        }
+    }
+
+    /// Look up the genome-order index of a chromosome.
+    #[inline]
+    pub fn chrom_index(&self, chrom: &[u8]) -> Option<usize> {
+        std::str::from_utf8(chrom)
+            .ok()
+            .and_then(|c| self.chrom_order.get(c).copied())
+    }
+
+    /// Name of the chromosome at a genome-order index.
+    #[inline]
+    pub fn chrom_name(&self, chrom_idx: usize) -> &'g str {
+        self.chroms[chrom_idx].0
+    }
+
+    /// Return the next gap, or `None` once every genome chromosome is done.
+    pub fn next_gap(&mut self) -> Result<Option<Gap>, BedError> {
+        while self.chrom_idx < self.chroms.len() {
+            let size = self.chroms[self.chrom_idx].1;
+
+            if self.pending.is_none() && !self.exhausted {
+                self.pending = self.read_next()?;
+                self.exhausted = self.pending.is_none();
+            }
+
+            match self.pending {
+                Some((idx, start, end)) if idx == self.chrom_idx => {
+                    self.pending = None;
+                    let start = start.min(size);
+                    let gap_start = self.covered_to;
+                    self.covered_to = self.covered_to.max(end.min(size));
+                    if start > gap_start {
+                        return Ok(Some(Gap {
+                            chrom_idx: idx,
+                            start: gap_start,
+                            end: start,
+                        }));
+                    }
+                }
+                Some((idx, _, _)) if idx < self.chrom_idx => {
+                    return Err(BedError::InvalidFormat(format!(
+                        "File not sorted by genome order: chromosome '{}' appears after '{}'",
+                        self.chroms[idx].0, self.chroms[self.chrom_idx].0
+                    )));
+                }
+                _ => {
+                    // No more input on this chromosome: emit the trailing gap
+                    let gap = Gap {
+                        chrom_idx: self.chrom_idx,
+                        start: self.covered_to,
+                        end: size,
+                    };
+                    self.chrom_idx += 1;
+                    self.covered_to = 0;
+                    if gap.start < gap.end {
+                        return Ok(Some(gap));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Read the next input interval on a genome chromosome.
+    fn read_next(&mut self) -> Result<Option<(usize, u64, u64)>, BedError> {
+        loop {
+            self.line_buf.clear();
+            if self.reader.read_line(&mut self.line_buf)? == 0 {
+                return Ok(None);
+            }
+
+            let line = self.line_buf.trim_end().as_bytes();
+            if should_skip_line(line) {
+                continue;
+            }
+
+            let (chrom, start, end) = match parse_bed3_bytes(line) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if let Some(idx) = self.chrom_index(chrom) {
+                return Ok(Some((idx, start, end)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_genome() -> Genome {
+        let mut genome = Genome::new();
+        genome.insert("chr1".to_string(), 1000);
+        genome.insert("chr2".to_string(), 500);
+        genome.insert("chr3".to_string(), 300);
+        genome
+    }
+
+    fn collect(content: &str, genome: &Genome) -> Vec<(usize, u64, u64)> {
+        let mut stream = ComplementStream::new(content.as_bytes(), genome);
+        let mut gaps = Vec::new();
+        while let Some(gap) = stream.next_gap().unwrap() {
+            gaps.push((gap.chrom_idx, gap.start, gap.end));
+        }
+        gaps
+    }
+
+    #[test]
+    fn test_complement_stream_gaps() {
+        let genome = make_genome();
+        let gaps = collect("chr1\t100\t200\nchr1\t150\t300\nchr1\t500\t1000\n", &genome);
+
+        assert_eq!(
+            gaps,
+            vec![(0, 0, 100), (0, 300, 500), (1, 0, 500), (2, 0, 300)]
+        );
+    }
+
+    #[test]
+    fn test_complement_stream_skips_unknown_and_covered() {
+        let genome = make_genome();
+        let gaps = collect("chr1\t0\t1000\nchrUn\t0\t10\nchr3\t0\t100\n", &genome);
+
+        assert_eq!(gaps, vec![(1, 0, 500), (2, 100, 300)]);
+    }
+
+    #[test]
+    fn test_complement_stream_wrong_order() {
+        let genome = make_genome();
+        let mut stream = ComplementStream::new("chr2\t0\t10\nchr1\t0\t10\n".as_bytes(), &genome);
+
+        // chr1 has no input before chr2 starts, so it is emitted whole
+        assert!(stream.next_gap().unwrap().is_some());
+        assert!(stream.next_gap().is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Collapsing exact duplicate records of a sorted stream.
+//!
+//! Annotation files often repeat a line verbatim, which multiplies `-wa`/`-wb`
+//! rows and inflates counts. In a stream sorted by (chrom, start), identical
+//! lines share a start position, so [`DedupReader`] only holds the records of
+//! the current position in memory while it drops repeats. Records keep the
+//! order of their first occurrence; comment and header lines pass through.
+
+use super::parsing::parse_bed3_bytes;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+
+/// Reader that drops exact duplicate lines of a sorted BED stream.
+///
+/// With [`with_counts`](Self::with_counts) each kept record gains a last
+/// column holding how many times it occurred.
+pub struct DedupReader<R: BufRead> {
+    inner: R,
+    counts: bool,
+    /// Bytes ready to be read, from `pos`
+    out: Vec<u8>,
+    pos: usize,
+    /// Line read past the end of the current group
+    carry: Vec<u8>,
+    /// (chrom, start) of the current group
+    key: Option<(Vec<u8>, u64)>,
+    /// Distinct lines of the current group (without line ending) and counts
+    group: Vec<(Vec<u8>, u64)>,
+    index: HashMap<Vec<u8>, usize>,
+    eof: bool,
+    duplicates: u64,
+}
+
+impl<R: BufRead> DedupReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            counts: false,
+            out: Vec::new(),
+            pos: 0,
+            carry: Vec::new(),
+            key: None,
+            group: Vec::new(),
+            index: HashMap::new(),
+            eof: false,
+            duplicates: 0,
+        }
+    }
+
+    /// Append each record's multiplicity as a last column.
+    pub fn with_counts(mut self, counts: bool) -> Self {
+        self.counts = counts;
+        self
+    }
+
+    /// Number of lines dropped so far.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Move the current group to the output buffer.
+    fn flush_group(&mut self) {
+        for (line, count) in self.group.drain(..) {
+            self.out.extend_from_slice(&line);
+            if self.counts {
+                self.out.push(b'\t');
+                self.out
+                    .extend_from_slice(itoa::Buffer::new().format(count).as_bytes());
+            }
+            self.out.push(b'\n');
+        }
+        self.index.clear();
+        self.key = None;
+    }
+
+    /// Fill the output buffer with the next group (or pass-through line).
+    fn refill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        while self.out.is_empty() && !self.eof {
+            let mut line = std::mem::take(&mut self.carry);
+            if line.is_empty() && self.inner.read_until(b'\n', &mut line)? == 0 {
+                self.eof = true;
+                self.flush_group();
+                break;
+            }
+
+            let body_len = line
+                .iter()
+                .rposition(|b| !b.is_ascii_whitespace())
+                .map_or(0, |i| i + 1);
+            let body = &line[..body_len];
+            let Some((chrom, start, _)) = parse_bed3_bytes(body) else {
+                // Comments, headers and malformed lines are not deduplicated
+                self.flush_group();
+                self.out.extend_from_slice(&line);
+                break;
+            };
+
+            if let Some((key_chrom, key_start)) = &self.key {
+                if key_chrom.as_slice() != chrom || *key_start != start {
+                    self.carry = line;
+                    self.flush_group();
+                    break;
+                }
+            } else {
+                self.key = Some((chrom.to_vec(), start));
+            }
+
+            match self.index.get(body) {
+                Some(&i) => {
+                    self.group[i].1 += 1;
+                    self.duplicates += 1;
+                }
+                None => {
+                    self.index.insert(body.to_vec(), self.group.len());
+                    self.group.push((body.to_vec(), 1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for DedupReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for DedupReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.out.len() {
+            self.refill()?;
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.out.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dedup(input: &str, counts: bool) -> String {
+        let mut reader = DedupReader::new(input.as_bytes()).with_counts(counts);
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_dedup_reader() {
+        let input = "#header\n\
+                     chr1\t10\t20\tx\n\
+                     chr1\t10\t30\ty\n\
+                     chr1\t10\t20\tx\r\n\
+                     chr1\t15\t20\tz\n\
+                     chr1\t15\t20\tz\n\
+                     chr2\t10\t20\tx";
+        assert_eq!(
+            dedup(input, false),
+            "#header\nchr1\t10\t20\tx\nchr1\t10\t30\ty\nchr1\t15\t20\tz\nchr2\t10\t20\tx\n"
+        );
+        assert_eq!(
+            dedup(input, true),
+            "#header\nchr1\t10\t20\tx\t2\nchr1\t10\t30\ty\t1\nchr1\t15\t20\tz\t2\nchr2\t10\t20\tx\t1\n"
+        );
+
+        let mut reader = DedupReader::new(input.as_bytes());
+        let lines: Vec<String> = reader.by_ref().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(reader.duplicates(), 2);
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-record callbacks on the output of streaming commands.
+//!
+//! Every streaming command writes its results to a caller-supplied
+//! `W: Write`. Wrapping that writer in a [`HookedWriter`] runs a
+//! [`RecordHook`] on each emitted record before it reaches the sink, so an
+//! embedder can count results, drop records, or copy them elsewhere (a
+//! database, a channel) without reimplementing the command's output.
+//!
+//! A record is one output line, passed without its line ending. Header and
+//! comment lines (`#`, `track`, `browser`) go straight through.
+//!
+//! ```rust,no_run
+//! use grit_genomics::commands::StreamingIntersectCommand;
+//! use grit_genomics::streaming::{HookedWriter, RecordAction};
+//! use std::io;
+//!
+//! let mut hits = 0u64;
+//! let stdout = io::stdout();
+//! let mut out = HookedWriter::new(stdout.lock(), |record: &[u8]| {
+//!     hits += 1;
+//!     // Keep only chr1
+//!     Ok(if record.starts_with(b"chr1\t") {
+//!         RecordAction::Emit
+//!     } else {
+//!         RecordAction::Skip
+//!     })
+//! });
+//! StreamingIntersectCommand::new()
+//!     .run("a.bed", "b.bed", &mut out)
+//!     .unwrap();
+//! ```
+
+use std::io::{self, Write};
+
+/// What to do with a record after its hook has seen it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordAction {
+    /// Write the record to the output.
+    Emit,
+    /// Leave the record out of the output.
+    Skip,
+}
+
+/// Callback run on each record a streaming command emits.
+///
+/// Closures `FnMut(&[u8]) -> io::Result<RecordAction>` implement this trait.
+/// An error from the hook stops the command and is returned as its error.
+pub trait RecordHook {
+    /// Inspect one record (without its line ending).
+    fn on_record(&mut self, record: &[u8]) -> io::Result<RecordAction>;
+
+    /// Called when the output is flushed, e.g. to commit a batch of
+    /// database writes. May be called more than once.
+    fn on_flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F> RecordHook for F
+where
+    F: FnMut(&[u8]) -> io::Result<RecordAction>,
+{
+    fn on_record(&mut self, record: &[u8]) -> io::Result<RecordAction> {
+        self(record)
+    }
+}
+
+/// Writer that runs a [`RecordHook`] on each line before passing it on.
+///
+/// The unfinished tail of a line is held back until its newline arrives (or
+/// the writer is flushed), so the hook always sees whole records.
+pub struct HookedWriter<W: Write, H: RecordHook> {
+    inner: W,
+    hook: H,
+    partial: Vec<u8>,
+}
+
+impl<W: Write, H: RecordHook> HookedWriter<W, H> {
+    pub fn new(inner: W, hook: H) -> Self {
+        Self {
+            inner,
+            hook,
+            partial: Vec::new(),
+        }
+    }
+
+    /// The hook, e.g. to read counters it has collected.
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    /// Flush and return the sink and the hook.
+    pub fn into_parts(mut self) -> io::Result<(W, H)> {
+        self.flush()?;
+        Ok((self.inner, self.hook))
+    }
+
+    /// Run the hook on `line` (including its line ending, if any) and write
+    /// it if kept.
+    fn dispatch(inner: &mut W, hook: &mut H, line: &[u8]) -> io::Result<()> {
+        let record = line.strip_suffix(b"\n").unwrap_or(line);
+        let record = record.strip_suffix(b"\r").unwrap_or(record);
+        if is_header(record) || hook.on_record(record)? == RecordAction::Emit {
+            inner.write_all(line)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_header(line: &[u8]) -> bool {
+    line.first() == Some(&b'#') || line.starts_with(b"track") || line.starts_with(b"browser")
+}
+
+impl<W: Write, H: RecordHook> Write for HookedWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(i) = memchr::memchr(b'\n', rest) {
+            if self.partial.is_empty() {
+                Self::dispatch(&mut self.inner, &mut self.hook, &rest[..=i])?;
+            } else {
+                self.partial.extend_from_slice(&rest[..=i]);
+                let line = std::mem::take(&mut self.partial);
+                Self::dispatch(&mut self.inner, &mut self.hook, &line)?;
+                self.partial = line;
+                self.partial.clear();
+            }
+            rest = &rest[i + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // An unterminated final line is a record too
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            Self::dispatch(&mut self.inner, &mut self.hook, &line)?;
+        }
+        self.hook.on_flush()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::StreamingMergeCommand;
+
+    #[test]
+    fn test_hook_sees_whole_records_and_filters() {
+        let mut seen = Vec::new();
+        let mut out = Vec::new();
+        let mut writer = HookedWriter::new(&mut out, |record: &[u8]| {
+            seen.push(record.to_vec());
+            Ok(if record.ends_with(b"drop") {
+                RecordAction::Skip
+            } else {
+                RecordAction::Emit
+            })
+        });
+        writer.write_all(b"#header\nchr1\t1").unwrap();
+        writer.write_all(b"\t2\tkeep\nchr1\t3\t4\tdrop\n").unwrap();
+        writer.write_all(b"chr2\t5\t6").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        assert_eq!(out, b"#header\nchr1\t1\t2\tkeep\nchr2\t5\t6");
+        assert_eq!(
+            seen,
+            vec![
+                b"chr1\t1\t2\tkeep".to_vec(),
+                b"chr1\t3\t4\tdrop".to_vec(),
+                b"chr2\t5\t6".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hook_on_streaming_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.bed");
+        std::fs::write(&path, "chr1\t1\t5\nchr1\t3\t8\nchr2\t1\t2\n").unwrap();
+
+        let mut records = 0;
+        let mut writer = HookedWriter::new(Vec::new(), |_: &[u8]| {
+            records += 1;
+            Ok(RecordAction::Emit)
+        });
+        StreamingMergeCommand::new()
+            .run(&path, &mut writer)
+            .unwrap();
+        let (out, _) = writer.into_parts().unwrap();
+        assert_eq!(out, b"chr1\t1\t8\nchr2\t1\t2\n");
+        assert_eq!(records, 2);
+
+        let mut writer = HookedWriter::new(Vec::new(), |_: &[u8]| {
+            Err(io::Error::other("database unavailable"))
+        });
+        assert!(StreamingMergeCommand::new()
+            .run(&path, &mut writer)
+            .is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Loser tree for k-way merges.
+//!
+//! A tournament tree over the head item of each input. Each internal node
+//! keeps the loser of the match played there, so replacing the winner only
+//! replays the matches on the path from its leaf to the root: about log2(k)
+//! comparisons per item, against roughly twice that for a binary heap's
+//! sift-down.
+
+/// Tournament tree holding the current item of each of `k` sources.
+///
+/// Exhausted sources hold `None`, which loses to every item. Equal items are
+/// won by the lower source index, so merges are stable.
+#[derive(Debug)]
+pub struct LoserTree<T: Ord> {
+    /// `nodes[0]` is the overall winner; `nodes[1..k]` hold the loser of the
+    /// match at each internal node. Leaf `i` sits at position `i + k`.
+    nodes: Vec<usize>,
+    items: Vec<Option<T>>,
+}
+
+impl<T: Ord> LoserTree<T> {
+    /// Build a tree from the first item of each source.
+    pub fn new(items: Vec<Option<T>>) -> Self {
+        let k = items.len();
+        let mut nodes = vec![0; k.max(1)];
+        // Winner of the subtree rooted at each internal node
+        let mut winners = vec![0; k.max(1)];
+        for node in (1..k).rev() {
+            let winner_of = |child: usize| {
+                if child >= k {
+                    child - k
+                } else {
+                    winners[child]
+                }
+            };
+            let (left, right) = (winner_of(2 * node), winner_of(2 * node + 1));
+            let (winner, loser) = if beats(&items, right, left) {
+                (right, left)
+            } else {
+                (left, right)
+            };
+            nodes[node] = loser;
+            winners[node] = winner;
+        }
+        if k > 1 {
+            nodes[0] = winners[1];
+        }
+        Self { nodes, items }
+    }
+
+    /// Number of sources.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// True if the tree has no sources.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Source index of the smallest item.
+    #[inline]
+    pub fn winner(&self) -> usize {
+        self.nodes[0]
+    }
+
+    /// The smallest item, or `None` once every source is exhausted.
+    #[inline]
+    pub fn peek(&self) -> Option<&T> {
+        self.items.get(self.winner())?.as_ref()
+    }
+
+    /// Replace the winning item with the next item from the same source and
+    /// return the old winner.
+    pub fn replace_winner(&mut self, next: Option<T>) -> Option<T> {
+        let k = self.items.len();
+        let mut winner = self.winner();
+        let old = std::mem::replace(self.items.get_mut(winner)?, next);
+        let mut node = (winner + k) / 2;
+        while node >= 1 {
+            if beats(&self.items, self.nodes[node], winner) {
+                std::mem::swap(&mut self.nodes[node], &mut winner);
+            }
+            node /= 2;
+        }
+        self.nodes[0] = winner;
+        old
+    }
+}
+
+/// Whether source `a`'s item comes before source `b`'s.
+#[inline]
+fn beats<T: Ord>(items: &[Option<T>], a: usize, b: usize) -> bool {
+    match (&items[a], &items[b]) {
+        (Some(x), Some(y)) => x < y || (x == y && a < b),
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => a < b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(sources: Vec<Vec<u32>>) -> Vec<(u32, usize)> {
+        let mut iters: Vec<_> = sources.into_iter().map(|s| s.into_iter()).collect();
+        let mut tree = LoserTree::new(iters.iter_mut().map(|it| it.next()).collect());
+        let mut out = Vec::new();
+        while tree.peek().is_some() {
+            let source = tree.winner();
+            let next = iters[source].next();
+            out.push((tree.replace_winner(next).unwrap(), source));
+        }
+        out
+    }
+
+    #[test]
+    fn test_merge_sorted_sources() {
+        let sources = vec![
+            vec![1, 4, 9],
+            vec![],
+            vec![2, 3, 10, 11],
+            vec![5],
+            vec![0, 9],
+        ];
+        let values: Vec<u32> = merge(sources).into_iter().map(|(v, _)| v).collect();
+        assert_eq!(values, vec![0, 1, 2, 3, 4, 5, 9, 9, 10, 11]);
+    }
+
+    #[test]
+    fn test_ties_prefer_lower_source() {
+        let out = merge(vec![vec![7, 7], vec![7], vec![7]]);
+        assert_eq!(out, vec![(7, 0), (7, 0), (7, 1), (7, 2)]);
+    }
+
+    #[test]
+    fn test_single_and_empty() {
+        assert_eq!(merge(vec![vec![3, 5]]), vec![(3, 0), (5, 0)]);
+        let tree: LoserTree<u32> = LoserTree::new(Vec::new());
+        assert!(tree.is_empty());
+        assert_eq!(tree.peek(), None);
+    }
+}
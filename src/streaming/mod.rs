@@ -6,23 +6,50 @@
 //! - Efficient output formatting
 //! - Active set management with automatic compaction
 //! - Buffer size configuration for memory efficiency
+//! - On-the-fly complement of sorted inputs
+//! - Score-threshold filtering of B records as they are read
+//! - Loser-tree k-way merging of many sorted inputs
+//! - Dropping exact duplicate records of sorted inputs
+//! - Per-record hooks on command output
+//! - Grouping of same-name records (mates, split alignments)
+//! - Interned chromosome names for sort validation and B skipping
+//! - Background read-ahead of input files
 //!
 //! All streaming commands maintain O(k) memory where k = max overlapping intervals.
 
 pub mod active_set;
 pub mod buffers;
+pub mod chrom_table;
+pub mod complement;
+pub mod dedup;
+pub mod hooks;
+pub mod loser_tree;
+pub mod names;
 pub mod output;
 pub mod parsing;
+pub mod read_ahead;
+pub mod score_filter;
 pub mod validation;
 
 pub use active_set::{ActiveInterval, ActiveSet};
 pub use buffers::{
     input_buffer_size, output_buffer_size, DEFAULT_INPUT_BUFFER, DEFAULT_OUTPUT_BUFFER,
-    LOW_MEMORY_INPUT_BUFFER, LOW_MEMORY_OUTPUT_BUFFER,
+    LOW_MEMORY_INPUT_BUFFER, LOW_MEMORY_OUTPUT_BUFFER, OUTPUT_BATCH_SIZE,
 };
-pub use output::BedWriter;
-pub use parsing::{parse_bed3_bytes, parse_bed3_bytes_with_rest, parse_u64_fast, should_skip_line};
+pub use chrom_table::{ChromId, ChromTable};
+pub use complement::{ComplementStream, Gap};
+pub use dedup::DedupReader;
+pub use hooks::{HookedWriter, RecordAction, RecordHook};
+pub use loser_tree::LoserTree;
+pub use names::{union_by_name, NameSeen, NameWindow};
+pub use output::{BatchWriter, BedWriter};
+pub use parsing::{
+    column_bytes, parse_bed3_bytes, parse_bed3_bytes_with_rest, parse_score_bytes, parse_u64_fast,
+    should_skip_line,
+};
+pub use read_ahead::{open_input, InputFile, ReadAhead};
+pub use score_filter::ScoreFilter;
 pub use validation::{
-    verify_sorted, verify_sorted_reader, verify_sorted_with_genome, GenomeOrderValidator,
-    SortValidator,
+    verify_sorted, verify_sorted_bytes, verify_sorted_reader, verify_sorted_with_genome,
+    GenomeOrderValidator, SortValidator,
 };
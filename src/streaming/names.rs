@@ -0,0 +1,146 @@
+//! Grouping of same-name records during a sweep.
+//!
+//! Paired-end mates and the pieces of a split alignment share a name, and
+//! depth options like `--collapse-by-name` count each name once. Names only
+//! matter while their records can still overlap what the sweep reports, so
+//! [`NameWindow`] keeps a name from its first record until the sweep passes
+//! its last base, and memory stays proportional to the names in flight.
+//!
+//! Records without a name (or named `.`) are never grouped.
+
+use std::collections::HashMap;
+
+/// Minimum number of tracked names before pruning is attempted.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// A record's name as seen by [`NameWindow::add`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameSeen {
+    /// Id shared by all records of the name while it is in the window.
+    pub id: u64,
+    /// End of the bases already covered by earlier records of the name
+    /// (0 for its first record).
+    pub covered_to: u64,
+}
+
+/// Names of recent records of one chromosome, each with an id and the
+/// furthest end reached by its records.
+#[derive(Debug, Default)]
+pub struct NameWindow {
+    names: HashMap<Vec<u8>, NameSeen>,
+    next_id: u64,
+    /// Size of the map after the last prune, to prune at geometric intervals
+    kept: usize,
+}
+
+impl NameWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a record ending at `end`.
+    pub fn add(&mut self, name: &[u8], end: u64) -> NameSeen {
+        let id = self.next_id;
+        self.next_id += 1;
+        if name.is_empty() || name == b"." {
+            return NameSeen { id, covered_to: 0 };
+        }
+        if let Some(seen) = self.names.get_mut(name) {
+            let previous = *seen;
+            seen.covered_to = seen.covered_to.max(end);
+            return previous;
+        }
+        self.names.insert(
+            name.to_vec(),
+            NameSeen {
+                id,
+                covered_to: end,
+            },
+        );
+        NameSeen { id, covered_to: 0 }
+    }
+
+    /// Forget names whose records all end at or before `pos`.
+    ///
+    /// Call with a position the sweep has passed for good. A forgotten name
+    /// gets a new id if it appears again. The scan only runs once the window
+    /// has doubled since the last one, so the cost is amortized.
+    pub fn prune(&mut self, pos: u64) {
+        if self.names.len() < PRUNE_THRESHOLD.max(2 * self.kept) {
+            return;
+        }
+        self.names.retain(|_, seen| seen.covered_to > pos);
+        self.kept = self.names.len();
+    }
+
+    /// Forget all names, e.g. at a chromosome change.
+    pub fn clear(&mut self) {
+        self.names.clear();
+        self.kept = 0;
+    }
+}
+
+/// Merge the intervals of each name, in place.
+///
+/// `pieces` holds `(name id, start, end)`. Overlapping or adjacent intervals
+/// of one id become a single interval, so each base is covered at most once
+/// per name. The result is sorted by start; the number of distinct ids is
+/// returned.
+pub fn union_by_name(pieces: &mut Vec<(u64, u64, u64)>) -> usize {
+    pieces.sort_unstable();
+    let mut names = 0;
+    let mut kept = 0;
+    for i in 0..pieces.len() {
+        let (id, start, end) = pieces[i];
+        if kept > 0 && pieces[kept - 1].0 == id {
+            let last = &mut pieces[kept - 1];
+            if start <= last.2 {
+                last.2 = last.2.max(end);
+                continue;
+            }
+        } else {
+            names += 1;
+        }
+        pieces[kept] = (id, start, end);
+        kept += 1;
+    }
+    pieces.truncate(kept);
+    pieces.sort_unstable_by_key(|&(_, start, end)| (start, end));
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_window() {
+        let mut window = NameWindow::new();
+        let first = window.add(b"frag1", 150);
+        assert_eq!(first.covered_to, 0);
+        let mate = window.add(b"frag1", 350);
+        assert_eq!(
+            mate,
+            NameSeen {
+                id: first.id,
+                covered_to: 150
+            }
+        );
+        assert_ne!(window.add(b".", 10).id, window.add(b".", 10).id);
+
+        for i in 0..PRUNE_THRESHOLD {
+            window.add(format!("r{}", i).as_bytes(), 100);
+        }
+        window.prune(200);
+        // frag1 still reaches 350
+        assert_eq!(window.names.len(), 1);
+        assert_eq!(window.add(b"frag1", 400).id, first.id);
+    }
+
+    #[test]
+    fn test_union_by_name() {
+        let mut pieces = vec![(1, 100, 150), (2, 120, 130), (1, 140, 200), (1, 300, 350)];
+        assert_eq!(union_by_name(&mut pieces), 2);
+        assert_eq!(pieces, vec![(1, 100, 200), (2, 120, 130), (1, 300, 350)]);
+    }
+}
@@ -4,11 +4,131 @@
 //! to avoid allocation in the hot path.
 
 use crate::bed::BedError;
-use std::io::{BufWriter, Write};
+use crate::streaming::buffers::OUTPUT_BATCH_SIZE;
+use std::cell::RefCell;
+use std::io::{self, BufWriter, Write};
 
 /// Buffer size for BedWriter (8MB default).
 const DEFAULT_BUFFER_SIZE: usize = 8 * 1024 * 1024;
 
+/// Batches larger than this are not kept for reuse after a writer is dropped.
+const MAX_RETAINED_BATCH: usize = 4 * OUTPUT_BATCH_SIZE;
+
+thread_local! {
+    /// Scratch buffer shared by successive `BatchWriter`s on this thread.
+    static BATCH_SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Output writer that coalesces small writes into one large write.
+///
+/// Hot loops emit a record as several short `write_all` calls (fields, tabs,
+/// newline). Going through `BufWriter` costs a capacity check and a call per
+/// piece; here each piece is a plain `Vec` append, and the sink only sees a
+/// single `write_all` per [`OUTPUT_BATCH_SIZE`] bytes. The batch buffer is
+/// borrowed from a thread-local scratch so repeated runs do not reallocate.
+///
+/// Like `BufWriter`, pending output is flushed on drop with errors ignored;
+/// call [`Write::flush`] to observe them.
+pub struct BatchWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> BatchWriter<W> {
+    /// Wrap `inner`, borrowing this thread's scratch buffer.
+    pub fn new(inner: W) -> Self {
+        let mut buf = BATCH_SCRATCH
+            .try_with(|s| std::mem::take(&mut *s.borrow_mut()))
+            .unwrap_or_default();
+        buf.clear();
+        buf.reserve(OUTPUT_BATCH_SIZE + OUTPUT_BATCH_SIZE / 8);
+        Self { inner, buf }
+    }
+
+    /// Append an `A<TAB>B<NEWLINE>` record in one reservation.
+    #[inline]
+    pub fn write_pair(&mut self, a: &[u8], b: &[u8]) -> io::Result<()> {
+        self.buf.reserve(a.len() + b.len() + 2);
+        self.buf.extend_from_slice(a);
+        self.buf.push(b'\t');
+        self.buf.extend_from_slice(b);
+        self.buf.push(b'\n');
+        self.flush_if_full()
+    }
+
+    /// Queue `a<TAB>b<TAB>value<LF>`, e.g. a pair followed by its distance.
+    pub fn write_pair_with_int<I: itoa::Integer>(
+        &mut self,
+        a: &[u8],
+        b: &[u8],
+        value: I,
+    ) -> io::Result<()> {
+        let mut num = itoa::Buffer::new();
+        let value = num.format(value).as_bytes();
+        self.buf.reserve(a.len() + b.len() + value.len() + 3);
+        self.buf.extend_from_slice(a);
+        self.buf.push(b'\t');
+        self.buf.extend_from_slice(b);
+        self.buf.push(b'\t');
+        self.buf.extend_from_slice(value);
+        self.buf.push(b'\n');
+        self.flush_if_full()
+    }
+
+    /// Number of bytes waiting to be written.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.buf.len()
+    }
+
+    #[inline]
+    fn flush_if_full(&mut self) -> io::Result<()> {
+        if self.buf.len() >= OUTPUT_BATCH_SIZE {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.inner.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BatchWriter<W> {
+    #[inline]
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        self.flush_if_full()?;
+        Ok(data.len())
+    }
+
+    #[inline]
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(data);
+        self.flush_if_full()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_batch()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for BatchWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_batch();
+        let mut buf = std::mem::take(&mut self.buf);
+        if buf.capacity() <= MAX_RETAINED_BATCH {
+            buf.clear();
+            let _ = BATCH_SCRATCH.try_with(|s| *s.borrow_mut() = buf);
+        }
+    }
+}
+
 /// High-performance BED output writer.
 ///
 /// Uses large buffering and zero-allocation formatting with itoa/ryu
@@ -198,6 +318,56 @@ mod tests {
         assert_eq!(output, b"chr1\t100\t200\tchr1\t150\t250\n");
     }
 
+    #[test]
+    fn test_batch_writer_coalesces() {
+        struct CountingSink {
+            data: Vec<u8>,
+            writes: usize,
+        }
+        impl Write for CountingSink {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.writes += 1;
+                self.data.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut sink = CountingSink {
+            data: Vec::new(),
+            writes: 0,
+        };
+        let line = b"chr1\t100\t200";
+        let records = OUTPUT_BATCH_SIZE / 20;
+        {
+            let mut writer = BatchWriter::new(&mut sink);
+            for _ in 0..records {
+                writer.write_pair(line, line).unwrap();
+                writer.write_all(b"x\n").unwrap();
+            }
+            writer.flush().unwrap();
+            assert_eq!(writer.pending(), 0);
+        }
+
+        assert_eq!(sink.data.len(), records * (2 * line.len() + 4));
+        assert!(sink
+            .data
+            .starts_with(b"chr1\t100\t200\tchr1\t100\t200\nx\n"));
+        assert!(sink.writes <= 3, "{} writes", sink.writes);
+    }
+
+    #[test]
+    fn test_batch_writer_flushes_on_drop() {
+        let mut output = Vec::new();
+        {
+            let mut writer = BatchWriter::new(&mut output);
+            writer.write_all(b"chr1\t1\t2\n").unwrap();
+        }
+        assert_eq!(output, b"chr1\t1\t2\n");
+    }
+
     #[test]
     fn test_write_float_7dp() {
         let mut output = Vec::new();
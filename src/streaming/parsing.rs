@@ -2,8 +2,15 @@
 //!
 //! These functions provide high-performance parsing of BED records
 //! without any heap allocation in the hot path.
+//!
+//! Tab-delimited LF input takes the fast path. CRLF endings and, depending on
+//! [`crate::config::delimiter`], whitespace-delimited columns are accepted too
+//! and counted via [`crate::config::record_irregularity`].
 
-use crate::config::normalize_end;
+use crate::config::{
+    chrom_allowed, chrom_filter_active, delimiter, normalize_end, record_irregularity, Delimiter,
+    Irregularity,
+};
 use memchr::memchr;
 
 /// Fast u64 parsing - no allocation, no error formatting.
@@ -47,22 +54,7 @@ pub fn parse_u64_fast(bytes: &[u8]) -> Option<u64> {
 /// (start == end) are normalized to 1bp intervals (end = start + 1).
 #[inline(always)]
 pub fn parse_bed3_bytes(line: &[u8]) -> Option<(&[u8], u64, u64)> {
-    let tab1 = memchr(b'\t', line)?;
-    let chrom = &line[..tab1];
-
-    let rest1 = &line[tab1 + 1..];
-    let tab2 = memchr(b'\t', rest1)?;
-    let start = parse_u64_fast(&rest1[..tab2])?;
-
-    let rest2 = &rest1[tab2 + 1..];
-    let end_len = memchr(b'\t', rest2).unwrap_or(rest2.len());
-    let end_len_trimmed = memchr(b'\n', &rest2[..end_len]).unwrap_or(end_len);
-    let end = parse_u64_fast(&rest2[..end_len_trimmed])?;
-
-    // Normalize zero-length intervals if bedtools-compatible mode is enabled
-    let end = normalize_end(start, end);
-
-    Some((chrom, start, end))
+    parse_bed3_bytes_with_rest(line).map(|(chrom, start, end, _)| (chrom, start, end))
 }
 
 /// Parse BED3 fields and return the rest of line index.
@@ -79,6 +71,48 @@ pub fn parse_bed3_bytes(line: &[u8]) -> Option<(&[u8], u64, u64)> {
 /// (start == end) are normalized to 1bp intervals (end = start + 1).
 #[inline(always)]
 pub fn parse_bed3_bytes_with_rest(line: &[u8]) -> Option<(&[u8], u64, u64, usize)> {
+    let line = trim_line_ending(line);
+    match delimiter() {
+        Delimiter::Tab => parse_bed3_tab(line),
+        Delimiter::Space => parse_bed3_whitespace(line),
+        Delimiter::Auto => match parse_bed3_tab(line) {
+            Some(fields) => Some(fields),
+            None if memchr(b'\t', line).is_none() => {
+                let fields = parse_bed3_whitespace(line)?;
+                record_irregularity(Irregularity::WhitespaceDelimited);
+                Some(fields)
+            }
+            None => None,
+        },
+    }
+}
+
+/// Strip trailing `\n` and `\r` bytes without recording irregularities.
+#[inline]
+pub(crate) fn trim_newline(line: &[u8]) -> &[u8] {
+    let mut end = line.len();
+    while end > 0 && matches!(line[end - 1], b'\n' | b'\r') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Strip a trailing `\n` and/or `\r`, counting CRLF endings.
+#[inline(always)]
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    match line.strip_suffix(b"\r") {
+        Some(stripped) => {
+            record_irregularity(Irregularity::Crlf);
+            stripped
+        }
+        None => line,
+    }
+}
+
+/// Tab-delimited BED3 fast path.
+#[inline(always)]
+fn parse_bed3_tab(line: &[u8]) -> Option<(&[u8], u64, u64, usize)> {
     let tab1 = memchr(b'\t', line)?;
     let chrom = &line[..tab1];
 
@@ -100,16 +134,138 @@ pub fn parse_bed3_bytes_with_rest(line: &[u8]) -> Option<(&[u8], u64, u64, usize
     Some((chrom, start, end, rest_start))
 }
 
-/// Check if a line should be skipped (empty, comment, or header).
+/// Parse BED3 fields separated by runs of spaces and/or tabs.
+///
+/// Leading whitespace is ignored; `rest_start` points just past the end field.
+pub fn parse_bed3_whitespace(line: &[u8]) -> Option<(&[u8], u64, u64, usize)> {
+    let is_ws = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut fields = [(0usize, 0usize); 3];
+    let mut pos = 0;
+
+    for field in fields.iter_mut() {
+        while pos < line.len() && is_ws(&line[pos]) {
+            pos += 1;
+        }
+        let field_start = pos;
+        while pos < line.len() && !is_ws(&line[pos]) {
+            pos += 1;
+        }
+        if pos == field_start {
+            return None;
+        }
+        *field = (field_start, pos);
+    }
+
+    let chrom = &line[fields[0].0..fields[0].1];
+    let start = parse_u64_fast(&line[fields[1].0..fields[1].1])?;
+    let end = parse_u64_fast(&line[fields[2].0..fields[2].1])?;
+
+    // Normalize zero-length intervals if bedtools-compatible mode is enabled
+    let end = normalize_end(start, end);
+
+    Some((chrom, start, end, pos))
+}
+
+/// Check if a line should be skipped (empty, comment, header, or on a
+/// chromosome excluded by [`crate::config::set_chrom_filter`]).
 #[inline(always)]
 pub fn should_skip_line(line: &[u8]) -> bool {
-    line.is_empty() || line[0] == b'#' || line.starts_with(b"track") || line.starts_with(b"browser")
+    line.is_empty()
+        || is_header_line(line)
+        || (chrom_filter_active() && !chrom_allowed(first_field(line)))
+}
+
+/// Check if a line is a comment, `track` or `browser` header.
+#[inline(always)]
+pub fn is_header_line(line: &[u8]) -> bool {
+    line.first() == Some(&b'#') || line.starts_with(b"track") || line.starts_with(b"browser")
+}
+
+/// Bytes up to the first tab or space.
+#[inline]
+fn first_field(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .position(|b| b.is_ascii_whitespace())
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Parse the score (BED column 5) from the columns after `end`.
+///
+/// `rest` is the tail of the line starting at the offset returned by
+/// [`parse_bed3_bytes_with_rest`]. Returns `None` when the column is missing
+/// or not numeric (e.g. `.`).
+#[inline]
+pub fn parse_score_bytes(rest: &[u8]) -> Option<f64> {
+    let field = if rest.first() == Some(&b'\t') {
+        rest.split(|&b| b == b'\t').nth(2)?
+    } else {
+        rest.split(|b| b.is_ascii_whitespace())
+            .filter(|f| !f.is_empty())
+            .nth(1)?
+    };
+    std::str::from_utf8(field).ok()?.trim().parse().ok()
+}
+
+/// Bytes of the 1-based column `col` of a BED line.
+///
+/// Lines with a tab are split on tabs, others on runs of whitespace (the
+/// `--delim auto` fallback). Returns `None` when the line is shorter.
+#[inline]
+pub fn column_bytes(line: &[u8], col: usize) -> Option<&[u8]> {
+    let idx = col.checked_sub(1)?;
+    if memchr::memchr(b'\t', line).is_some() {
+        line.split(|&b| b == b'\t').nth(idx)
+    } else {
+        line.split(|b| b.is_ascii_whitespace())
+            .filter(|f| !f.is_empty())
+            .nth(idx)
+    }
+}
+
+/// Number of columns in a BED line, split as in [`column_bytes`].
+#[inline]
+pub fn column_count(line: &[u8]) -> usize {
+    if memchr::memchr(b'\t', line).is_some() {
+        line.split(|&b| b == b'\t').count()
+    } else {
+        line.split(|b| b.is_ascii_whitespace())
+            .filter(|f| !f.is_empty())
+            .count()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_score_bytes() {
+        let line = b"chr1\t10\t20\tpeak\t7.5\t+";
+        let (_, _, _, rest) = parse_bed3_bytes_with_rest(line).unwrap();
+        assert_eq!(parse_score_bytes(&line[rest..]), Some(7.5));
+
+        assert_eq!(parse_score_bytes(b"\tpeak\t.\t+"), None);
+        assert_eq!(parse_score_bytes(b"\tpeak"), None);
+        assert_eq!(parse_score_bytes(b""), None);
+        assert_eq!(parse_score_bytes(b"  peak   12\r"), Some(12.0));
+    }
+
+    #[test]
+    fn test_column_bytes() {
+        let line = b"chr1\t10\t20\tgeneA\t0\t+";
+        assert_eq!(column_bytes(line, 1), Some(&b"chr1"[..]));
+        assert_eq!(column_bytes(line, 4), Some(&b"geneA"[..]));
+        assert_eq!(column_bytes(line, 7), None);
+        assert_eq!(column_bytes(line, 0), None);
+        assert_eq!(column_bytes(b"chr1  10 20   geneB", 4), Some(&b"geneB"[..]));
+
+        assert_eq!(column_count(line), 6);
+        assert_eq!(column_count(b"chr1  10 20   geneB"), 4);
+        assert_eq!(column_count(b"chr1\t10\t20\t\t"), 5);
+    }
+
     #[test]
     fn test_parse_u64_fast() {
         assert_eq!(parse_u64_fast(b"12345"), Some(12345));
@@ -149,6 +305,35 @@ mod tests {
         assert_eq!(rest_start, 12); // Position after "200"
     }
 
+    #[test]
+    fn test_parse_bed3_crlf() {
+        assert_eq!(
+            parse_bed3_bytes(b"chr1\t100\t200\r\n"),
+            Some((&b"chr1"[..], 100, 200))
+        );
+        assert_eq!(
+            parse_bed3_bytes(b"chr1\t100\t200\r"),
+            Some((&b"chr1"[..], 100, 200))
+        );
+    }
+
+    #[test]
+    fn test_parse_bed3_whitespace() {
+        // Auto mode falls back to whitespace when a line has no tab
+        assert_eq!(
+            parse_bed3_bytes(b"chr1 100  200"),
+            Some((&b"chr1"[..], 100, 200))
+        );
+
+        let line = b"  chr2 \t5 10 name 0 +";
+        let (chrom, start, end, rest_start) = parse_bed3_whitespace(line).unwrap();
+        assert_eq!((chrom, start, end), (&b"chr2"[..], 5, 10));
+        assert_eq!(&line[rest_start..], b" name 0 +");
+
+        assert_eq!(parse_bed3_whitespace(b"chr1 100"), None);
+        assert_eq!(parse_bed3_whitespace(b"chr1 x 200"), None);
+    }
+
     #[test]
     fn test_should_skip_line() {
         assert!(should_skip_line(b""));
@@ -156,5 +341,10 @@ mod tests {
         assert!(should_skip_line(b"track name=foo"));
         assert!(should_skip_line(b"browser position chr1:1-100"));
         assert!(!should_skip_line(b"chr1\t100\t200"));
+
+        assert!(is_header_line(b"#comment"));
+        assert!(is_header_line(b"track name=foo"));
+        assert!(!is_header_line(b""));
+        assert!(!is_header_line(b"chr1\t100\t200"));
     }
 }
@@ -0,0 +1,277 @@
+//! Background read-ahead for streaming inputs.
+//!
+//! On network filesystems a streaming sweep can spend much of its time
+//! waiting for reads. [`open_input`] hands large input files to a
+//! [`ReadAhead`] reader, whose background thread fills the next chunk while
+//! the command parses the current one (double buffering), so I/O overlaps
+//! with compute. Output is the same whether or not an input is read ahead.
+//!
+//! At most [`io_threads`] read-ahead threads run at once, set with the global
+//! `--io-threads`. Files opened beyond that, small files and pipes are read
+//! inline.
+
+use super::buffers::DEFAULT_INPUT_BUFFER;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Bytes read by the background thread at a time.
+pub const READ_AHEAD_CHUNK: usize = DEFAULT_INPUT_BUFFER;
+
+/// Read-ahead threads allowed by default: one for each input of a
+/// two-input command.
+pub const DEFAULT_IO_THREADS: usize = 2;
+
+/// Files smaller than this are read inline; there is little I/O to hide.
+const MIN_READ_AHEAD_BYTES: u64 = 4 * READ_AHEAD_CHUNK as u64;
+
+static IO_THREADS: AtomicUsize = AtomicUsize::new(DEFAULT_IO_THREADS);
+static ACTIVE_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set how many inputs may be read ahead at once; 0 reads every input inline.
+pub fn set_io_threads(threads: usize) {
+    IO_THREADS.store(threads, Ordering::Release);
+}
+
+/// Number of inputs that may be read ahead at once.
+pub fn io_threads() -> usize {
+    IO_THREADS.load(Ordering::Acquire)
+}
+
+/// Open an input file, reading it ahead on a background thread when it is
+/// a regular file large enough to benefit and a thread is free.
+pub fn open_input<P: AsRef<Path>>(path: P) -> io::Result<InputFile> {
+    let file = File::open(path)?;
+    let large = file
+        .metadata()
+        .is_ok_and(|m| m.is_file() && m.len() >= MIN_READ_AHEAD_BYTES);
+    if !large {
+        return Ok(InputFile(Source::Inline(file)));
+    }
+    match ThreadSlot::acquire() {
+        Some(slot) => Ok(InputFile(Source::ReadAhead(
+            ReadAhead::new(file, READ_AHEAD_CHUNK)?,
+            slot,
+        ))),
+        None => Ok(InputFile(Source::Inline(file))),
+    }
+}
+
+/// Input file opened by [`open_input`].
+pub struct InputFile(Source);
+
+enum Source {
+    Inline(File),
+    ReadAhead(ReadAhead, ThreadSlot),
+}
+
+impl InputFile {
+    /// True if a background thread reads this file.
+    pub fn is_read_ahead(&self) -> bool {
+        matches!(self.0, Source::ReadAhead(..))
+    }
+}
+
+impl Read for InputFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Source::Inline(file) => file.read(buf),
+            Source::ReadAhead(reader, _) => reader.read(buf),
+        }
+    }
+}
+
+/// One of the [`io_threads`] read-ahead threads, released on drop.
+struct ThreadSlot;
+
+impl ThreadSlot {
+    fn acquire() -> Option<Self> {
+        ACTIVE_THREADS
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < io_threads()).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+impl Drop for ThreadSlot {
+    fn drop(&mut self) {
+        ACTIVE_THREADS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Reader whose source is read on a background thread.
+///
+/// Two buffers of `chunk_size` bytes circulate: the one being read from and
+/// the one the thread is filling. Dropping the reader stops the thread
+/// after its current read.
+pub struct ReadAhead {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    spare: Sender<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl ReadAhead {
+    /// Start reading `inner` in chunks of `chunk_size` bytes.
+    pub fn new<R: Read + Send + 'static>(mut inner: R, chunk_size: usize) -> io::Result<Self> {
+        let chunk_size = chunk_size.max(1);
+        let (chunk_tx, chunks) = bounded(1);
+        let (spare, spare_rx) = bounded(2);
+        for _ in 0..2 {
+            let _ = spare.send(Vec::with_capacity(chunk_size));
+        }
+
+        thread::Builder::new()
+            .name("grit-read-ahead".to_string())
+            .spawn(move || {
+                while let Ok(mut buf) = spare_rx.recv() {
+                    buf.resize(chunk_size, 0);
+                    let (filled, error) = fill(&mut inner, &mut buf);
+                    buf.truncate(filled);
+                    // A short chunk means the end of the input or an error
+                    let last = filled < chunk_size;
+                    if filled > 0 && chunk_tx.send(Ok(buf)).is_err() {
+                        return;
+                    }
+                    if let Some(e) = error {
+                        let _ = chunk_tx.send(Err(e));
+                    }
+                    if last {
+                        return;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            chunks,
+            spare,
+            current: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+}
+
+/// Read into `buf` until it is full, the input ends or a read fails.
+fn fill<R: Read>(inner: &mut R, buf: &mut [u8]) -> (usize, Option<io::Error>) {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match inner.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return (filled, Some(e)),
+        }
+    }
+    (filled, None)
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            if self.done {
+                return Ok(0);
+            }
+            let spent = std::mem::take(&mut self.current);
+            if spent.capacity() > 0 {
+                let _ = self.spare.try_send(spent);
+            }
+            self.pos = 0;
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) => self.current = chunk,
+                Ok(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+                // The thread has sent its last chunk
+                Err(_) => self.done = true,
+            }
+        }
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Cursor};
+
+    #[test]
+    fn test_read_ahead_matches_input() {
+        let data: Vec<u8> = (0..10_000u32)
+            .flat_map(|i| format!("chr1\t{}\t{}\n", i, i + 10).into_bytes())
+            .collect();
+        for chunk_size in [1, 7, 4096, data.len(), data.len() + 1] {
+            let mut out = Vec::new();
+            ReadAhead::new(Cursor::new(data.clone()), chunk_size)
+                .unwrap()
+                .read_to_end(&mut out)
+                .unwrap();
+            assert_eq!(out, data, "chunk size {}", chunk_size);
+        }
+
+        let lines = BufReader::new(ReadAhead::new(Cursor::new(data), 100).unwrap())
+            .lines()
+            .count();
+        assert_eq!(lines, 10_000);
+
+        let mut empty = Vec::new();
+        ReadAhead::new(io::empty(), 16)
+            .unwrap()
+            .read_to_end(&mut empty)
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_read_ahead_reports_errors() {
+        struct Failing(usize);
+        impl Read for Failing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::Error::other("disk gone"));
+                }
+                let n = buf.len().min(self.0);
+                buf[..n].fill(b'x');
+                self.0 -= n;
+                Ok(n)
+            }
+        }
+
+        let mut reader = ReadAhead::new(Failing(10), 4).unwrap();
+        let mut out = Vec::new();
+        let err = reader.read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.to_string(), "disk gone");
+        // Everything read before the failure is delivered first
+        assert_eq!(out, b"xxxxxxxxxx");
+    }
+
+    #[test]
+    fn test_open_input() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"chr1\t0\t10\n").unwrap();
+        let mut input = open_input(file.path()).unwrap();
+        assert!(!input.is_read_ahead());
+        let mut out = String::new();
+        input.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "chr1\t0\t10\n");
+
+        let data = "chr1\t100\t200\n".repeat(100_000);
+        std::fs::write(file.path(), &data).unwrap();
+        let mut input = open_input(file.path()).unwrap();
+        assert!(input.is_read_ahead());
+        let mut out = String::new();
+        input.read_to_string(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+}
@@ -0,0 +1,114 @@
+//! Score-threshold filter for B records.
+//!
+//! Streaming commands check each B line as it is read, so records outside
+//! the range never enter the active set and a large annotation file can be
+//! thresholded without a separate filtering pass. For BedGraph input the
+//! bounds apply to the value column instead of the score.
+
+use super::parsing::parse_score_bytes;
+use crate::interval::BedRecord;
+
+/// Inclusive bounds on the BED score column.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScoreFilter {
+    /// Lowest accepted score.
+    pub min: Option<f64>,
+    /// Highest accepted score.
+    pub max: Option<f64>,
+    /// Read the BedGraph value (column 4) instead of the score (column 5).
+    pub bedgraph: bool,
+}
+
+impl ScoreFilter {
+    pub fn new(min: Option<f64>, max: Option<f64>) -> Self {
+        Self {
+            min,
+            max,
+            bedgraph: false,
+        }
+    }
+
+    /// Filter on the BedGraph value column (builder pattern).
+    pub fn with_bedgraph(mut self, bedgraph: bool) -> Self {
+        self.bedgraph = bedgraph;
+        self
+    }
+
+    /// Whether any bound is set.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.min.is_some() || self.max.is_some()
+    }
+
+    /// Check a parsed score.
+    ///
+    /// Records without a numeric score fail as soon as any bound is set.
+    #[inline]
+    pub fn accepts(&self, score: Option<f64>) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let Some(score) = score else {
+            return false;
+        };
+        self.min.is_none_or(|min| score >= min) && self.max.is_none_or(|max| score <= max)
+    }
+
+    /// Check the columns after `end` of a raw BED line.
+    #[inline]
+    pub fn accepts_rest(&self, rest: &[u8]) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let score = if self.bedgraph {
+            rest.split(|b| *b == b'\t' || *b == b' ')
+                .find(|f| !f.is_empty())
+                .and_then(|f| std::str::from_utf8(f).ok()?.trim().parse().ok())
+        } else {
+            parse_score_bytes(rest)
+        };
+        self.accepts(score)
+    }
+
+    /// Check a parsed record; a BedGraph value is read as the record's name.
+    #[inline]
+    pub fn accepts_record(&self, record: &BedRecord) -> bool {
+        if self.bedgraph {
+            self.accepts(record.name.as_deref().and_then(|v| v.parse().ok()))
+        } else {
+            self.accepts(record.score)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_filter_bounds() {
+        let filter = ScoreFilter::new(Some(10.0), Some(20.0));
+        assert!(filter.accepts(Some(10.0)));
+        assert!(filter.accepts(Some(20.0)));
+        assert!(!filter.accepts(Some(9.9)));
+        assert!(!filter.accepts(Some(20.5)));
+        assert!(!filter.accepts(None));
+        assert!(!filter.accepts_rest(b"\tpeak\t."));
+        assert!(filter.accepts_rest(b"\tpeak\t15\t+"));
+
+        let value = ScoreFilter::new(Some(0.5), None).with_bedgraph(true);
+        assert!(value.accepts_rest(b"\t0.75"));
+        assert!(value.accepts_rest(b" 1e3"));
+        assert!(!value.accepts_rest(b"\t0.25"));
+        assert!(!value.accepts_rest(b""));
+        let mut record = BedRecord::new("chr1", 0, 10);
+        record.name = Some("0.6".to_string());
+        assert!(value.accepts_record(&record));
+        record.score = Some(0.1);
+        assert!(!filter.accepts_record(&record));
+
+        let open = ScoreFilter::default();
+        assert!(open.accepts(None));
+        assert!(open.accepts_rest(b""));
+    }
+}
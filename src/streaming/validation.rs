@@ -11,8 +11,9 @@
 //! genome order (chr1, chr2, chr3...) - any consistent ordering works.
 
 use crate::bed::{BedError, BedReader};
-use std::collections::HashSet;
-use std::fs::File;
+use crate::config::IrregularityGuard;
+use crate::streaming::chrom_table::{ChromId, ChromTable};
+use crate::streaming::read_ahead::open_input;
 use std::io::BufReader;
 use std::path::Path;
 
@@ -28,12 +29,13 @@ use std::path::Path;
 /// verify_sorted("input.bed").expect("File must be sorted");
 /// ```
 pub fn verify_sorted<P: AsRef<Path>>(path: P) -> Result<(), BedError> {
-    let file = File::open(path.as_ref())?;
+    let _guard = IrregularityGuard::new();
+    let file = open_input(path.as_ref())?;
     let reader = BedReader::new(BufReader::new(file));
 
-    let mut prev_chrom: Option<String> = None;
+    let mut chroms = ChromTable::new();
+    let mut prev_chrom: Option<ChromId> = None;
     let mut prev_start: u64 = 0;
-    let mut seen_chroms: HashSet<String> = HashSet::new();
     let mut line_num = 0;
 
     for result in reader.records() {
@@ -43,25 +45,27 @@ pub fn verify_sorted<P: AsRef<Path>>(path: P) -> Result<(), BedError> {
         let chrom = rec.chrom();
         let start = rec.start();
 
-        if let Some(ref pc) = prev_chrom {
-            if chrom != pc {
+        match prev_chrom {
+            Some(pc) if chroms.name(pc) == chrom.as_bytes() => {
+                if start < prev_start {
+                    return Err(BedError::InvalidFormat(format!(
+                        "File not sorted: position {} at line {} comes after {} on {}",
+                        start, line_num, prev_start, chrom
+                    )));
+                }
+            }
+            _ => {
                 // Switching chromosomes - check we haven't seen this one before
-                if seen_chroms.contains(chrom) {
+                if chroms.contains(chrom.as_bytes()) {
                     return Err(BedError::InvalidFormat(format!(
                         "File not sorted: chromosome '{}' at line {} was seen earlier (chromosomes must be contiguous)",
                         chrom, line_num
                     )));
                 }
-                seen_chroms.insert(pc.clone());
-            } else if start < prev_start {
-                return Err(BedError::InvalidFormat(format!(
-                    "File not sorted: position {} at line {} comes after {} on {}",
-                    start, line_num, prev_start, chrom
-                )));
+                prev_chrom = Some(chroms.intern(chrom.as_bytes()));
             }
         }
 
-        prev_chrom = Some(chrom.to_string());
         prev_start = start;
     }
 
@@ -78,9 +82,9 @@ pub fn verify_sorted<P: AsRef<Path>>(path: P) -> Result<(), BedError> {
 /// 2. Within a chromosome, positions are non-decreasing
 #[derive(Debug, Default)]
 pub struct SortValidator {
-    prev_chrom: Option<String>,
+    prev_chrom: Option<ChromId>,
     prev_start: u64,
-    seen_chroms: HashSet<String>,
+    chroms: ChromTable,
     record_count: usize,
 }
 
@@ -97,25 +101,27 @@ impl SortValidator {
     pub fn validate(&mut self, chrom: &str, start: u64) -> Result<(), BedError> {
         self.record_count += 1;
 
-        if let Some(ref pc) = self.prev_chrom {
-            if chrom != pc {
+        match self.prev_chrom {
+            Some(pc) if self.chroms.name(pc) == chrom.as_bytes() => {
+                if start < self.prev_start {
+                    return Err(BedError::InvalidFormat(format!(
+                        "File not sorted: position {} at record {} comes after {} on {}",
+                        start, self.record_count, self.prev_start, chrom
+                    )));
+                }
+            }
+            _ => {
                 // Switching chromosomes - check we haven't seen this one before
-                if self.seen_chroms.contains(chrom) {
+                if self.chroms.contains(chrom.as_bytes()) {
                     return Err(BedError::InvalidFormat(format!(
                         "File not sorted: chromosome '{}' at record {} was seen earlier (chromosomes must be contiguous)",
                         chrom, self.record_count
                     )));
                 }
-                self.seen_chroms.insert(pc.clone());
-            } else if start < self.prev_start {
-                return Err(BedError::InvalidFormat(format!(
-                    "File not sorted: position {} at record {} comes after {} on {}",
-                    start, self.record_count, self.prev_start, chrom
-                )));
+                self.prev_chrom = Some(self.chroms.intern(chrom.as_bytes()));
             }
         }
 
-        self.prev_chrom = Some(chrom.to_string());
         self.prev_start = start;
 
         Ok(())
@@ -133,25 +139,27 @@ impl SortValidator {
     ) -> Result<(), BedError> {
         self.record_count += 1;
 
-        if let Some(ref pc) = self.prev_chrom {
-            if chrom != pc {
+        match self.prev_chrom {
+            Some(pc) if self.chroms.name(pc) == chrom.as_bytes() => {
+                if start < self.prev_start {
+                    return Err(BedError::InvalidFormat(format!(
+                        "File {} not sorted: position {} at record {} comes after {} on {}",
+                        file_id, start, self.record_count, self.prev_start, chrom
+                    )));
+                }
+            }
+            _ => {
                 // Switching chromosomes - check we haven't seen this one before
-                if self.seen_chroms.contains(chrom) {
+                if self.chroms.contains(chrom.as_bytes()) {
                     return Err(BedError::InvalidFormat(format!(
                         "File {} not sorted: chromosome '{}' at record {} was seen earlier (chromosomes must be contiguous)",
                         file_id, chrom, self.record_count
                     )));
                 }
-                self.seen_chroms.insert(pc.clone());
-            } else if start < self.prev_start {
-                return Err(BedError::InvalidFormat(format!(
-                    "File {} not sorted: position {} at record {} comes after {} on {}",
-                    file_id, start, self.record_count, self.prev_start, chrom
-                )));
+                self.prev_chrom = Some(self.chroms.intern(chrom.as_bytes()));
             }
         }
 
-        self.prev_chrom = Some(chrom.to_string());
         self.prev_start = start;
 
         Ok(())
@@ -161,7 +169,7 @@ impl SortValidator {
     pub fn reset(&mut self) {
         self.prev_chrom = None;
         self.prev_start = 0;
-        self.seen_chroms.clear();
+        self.chroms.clear();
         self.record_count = 0;
     }
 
@@ -183,18 +191,13 @@ pub fn verify_sorted_with_genome<P: AsRef<Path>>(
     path: P,
     genome: &crate::genome::Genome,
 ) -> Result<(), BedError> {
-    let file = File::open(path.as_ref())?;
+    let _guard = IrregularityGuard::new();
+    let file = open_input(path.as_ref())?;
     let reader = BedReader::new(BufReader::new(file));
 
-    // Build a map of chromosome -> position in genome order
-    let chrom_order: std::collections::HashMap<&str, usize> = genome
-        .chromosomes()
-        .enumerate()
-        .map(|(i, c)| (c.as_str(), i))
-        .collect();
-
-    let mut prev_chrom: Option<String> = None;
-    let mut prev_chrom_order: Option<usize> = None;
+    // Ids follow genome order, so comparing ids compares positions
+    let order = ChromTable::from_genome(genome);
+    let mut prev_chrom: Option<ChromId> = None;
     let mut prev_start: u64 = 0;
     let mut line_num = 0;
 
@@ -206,8 +209,8 @@ pub fn verify_sorted_with_genome<P: AsRef<Path>>(
         let start = rec.start();
 
         // Check if chromosome exists in genome file
-        let current_order = match chrom_order.get(chrom) {
-            Some(&order) => order,
+        let current = match order.get(chrom.as_bytes()) {
+            Some(id) => id,
             None => {
                 return Err(BedError::InvalidFormat(format!(
                     "Chromosome '{}' at line {} not found in genome file",
@@ -216,17 +219,15 @@ pub fn verify_sorted_with_genome<P: AsRef<Path>>(
             }
         };
 
-        if let Some(ref pc) = prev_chrom {
-            if chrom != pc {
-                // Switching chromosomes - check it comes after previous in genome order
-                if current_order < prev_chrom_order.unwrap() {
-                    return Err(BedError::InvalidFormat(format!(
-                        "File not sorted by genome order: chromosome '{}' at line {} should come before '{}'\n\n\
-                         Fix: Run 'grit sort -i {} -g <genome.txt>' to sort by genome order.",
-                        chrom, line_num, pc, path.as_ref().display()
-                    )));
-                }
-            } else if start < prev_start {
+        if let Some(pc) = prev_chrom {
+            if current < pc {
+                // Switching back to a chromosome earlier in genome order
+                return Err(BedError::InvalidFormat(format!(
+                    "File not sorted by genome order: chromosome '{}' at line {} should come before '{}'\n\n\
+                     Fix: Run 'grit sort -i {} -g <genome.txt>' to sort by genome order.",
+                    chrom, line_num, order.name_lossy(pc), path.as_ref().display()
+                )));
+            } else if current == pc && start < prev_start {
                 return Err(BedError::InvalidFormat(format!(
                     "File not sorted: position {} at line {} comes after {} on {}",
                     start, line_num, prev_start, chrom
@@ -234,8 +235,7 @@ pub fn verify_sorted_with_genome<P: AsRef<Path>>(
             }
         }
 
-        prev_chrom = Some(chrom.to_string());
-        prev_chrom_order = Some(current_order);
+        prev_chrom = Some(current);
         prev_start = start;
     }
 
@@ -251,9 +251,8 @@ pub fn verify_sorted_with_genome<P: AsRef<Path>>(
 #[derive(Debug)]
 pub struct GenomeOrderValidator<'a> {
     genome: &'a crate::genome::Genome,
-    chrom_order: std::collections::HashMap<String, usize>,
-    prev_chrom: Option<String>,
-    prev_chrom_order: Option<usize>,
+    order: ChromTable,
+    prev_chrom: Option<ChromId>,
     prev_start: u64,
     record_count: usize,
 }
@@ -261,17 +260,10 @@ pub struct GenomeOrderValidator<'a> {
 impl<'a> GenomeOrderValidator<'a> {
     /// Create a new genome-order validator.
     pub fn new(genome: &'a crate::genome::Genome) -> Self {
-        let chrom_order: std::collections::HashMap<String, usize> = genome
-            .chromosomes()
-            .enumerate()
-            .map(|(i, c)| (c.clone(), i))
-            .collect();
-
         Self {
             genome,
-            chrom_order,
+            order: ChromTable::from_genome(genome),
             prev_chrom: None,
-            prev_chrom_order: None,
             prev_start: 0,
             record_count: 0,
         }
@@ -285,8 +277,8 @@ impl<'a> GenomeOrderValidator<'a> {
         self.record_count += 1;
 
         // Check if chromosome exists in genome file
-        let current_order = match self.chrom_order.get(chrom) {
-            Some(&order) => order,
+        let current = match self.order.get(chrom.as_bytes()) {
+            Some(id) => id,
             None => {
                 return Err(BedError::InvalidFormat(format!(
                     "Chromosome '{}' at record {} not found in genome file",
@@ -295,16 +287,14 @@ impl<'a> GenomeOrderValidator<'a> {
             }
         };
 
-        if let Some(ref pc) = self.prev_chrom {
-            if chrom != pc {
-                // Switching chromosomes - check it comes after previous in genome order
-                if current_order < self.prev_chrom_order.unwrap() {
-                    return Err(BedError::InvalidFormat(format!(
-                        "File not sorted by genome order: chromosome '{}' at record {} should come before '{}'",
-                        chrom, self.record_count, pc
-                    )));
-                }
-            } else if start < self.prev_start {
+        if let Some(pc) = self.prev_chrom {
+            if current < pc {
+                // Switching back to a chromosome earlier in genome order
+                return Err(BedError::InvalidFormat(format!(
+                    "File not sorted by genome order: chromosome '{}' at record {} should come before '{}'",
+                    chrom, self.record_count, self.order.name_lossy(pc)
+                )));
+            } else if current == pc && start < self.prev_start {
                 return Err(BedError::InvalidFormat(format!(
                     "File not sorted: position {} at record {} comes after {} on {}",
                     start, self.record_count, self.prev_start, chrom
@@ -312,8 +302,7 @@ impl<'a> GenomeOrderValidator<'a> {
             }
         }
 
-        self.prev_chrom = Some(chrom.to_string());
-        self.prev_chrom_order = Some(current_order);
+        self.prev_chrom = Some(current);
         self.prev_start = start;
 
         Ok(())
@@ -339,13 +328,19 @@ impl<'a> GenomeOrderValidator<'a> {
 pub fn verify_sorted_reader<R: std::io::Read>(mut reader: R) -> Result<Vec<u8>, BedError> {
     let mut buffer = Vec::new();
     reader.read_to_end(&mut buffer)?;
+    verify_sorted_bytes(&buffer)?;
+    Ok(buffer)
+}
 
-    let cursor = std::io::Cursor::new(&buffer);
+/// Verify that buffered BED data is sorted, as [`verify_sorted_reader`] does.
+pub fn verify_sorted_bytes(buffer: &[u8]) -> Result<(), BedError> {
+    let _guard = IrregularityGuard::new();
+    let cursor = std::io::Cursor::new(buffer);
     let bed_reader = BedReader::new(std::io::BufReader::new(cursor));
 
-    let mut prev_chrom: Option<String> = None;
+    let mut chroms = ChromTable::new();
+    let mut prev_chrom: Option<ChromId> = None;
     let mut prev_start: u64 = 0;
-    let mut seen_chroms: HashSet<String> = HashSet::new();
     let mut line_num = 0;
 
     for result in bed_reader.records() {
@@ -355,29 +350,31 @@ pub fn verify_sorted_reader<R: std::io::Read>(mut reader: R) -> Result<Vec<u8>,
         let chrom = rec.chrom();
         let start = rec.start();
 
-        if let Some(ref pc) = prev_chrom {
-            if chrom != pc {
+        match prev_chrom {
+            Some(pc) if chroms.name(pc) == chrom.as_bytes() => {
+                if start < prev_start {
+                    return Err(BedError::InvalidFormat(format!(
+                        "stdin not sorted: position {} at line {} comes after {} on {}",
+                        start, line_num, prev_start, chrom
+                    )));
+                }
+            }
+            _ => {
                 // Switching chromosomes - check we haven't seen this one before
-                if seen_chroms.contains(chrom) {
+                if chroms.contains(chrom.as_bytes()) {
                     return Err(BedError::InvalidFormat(format!(
                         "stdin not sorted: chromosome '{}' at line {} was seen earlier (chromosomes must be contiguous)",
                         chrom, line_num
                     )));
                 }
-                seen_chroms.insert(pc.clone());
-            } else if start < prev_start {
-                return Err(BedError::InvalidFormat(format!(
-                    "stdin not sorted: position {} at line {} comes after {} on {}",
-                    start, line_num, prev_start, chrom
-                )));
+                prev_chrom = Some(chroms.intern(chrom.as_bytes()));
             }
         }
 
-        prev_chrom = Some(chrom.to_string());
         prev_start = start;
     }
 
-    Ok(buffer)
+    Ok(())
 }
 
 #[cfg(test)]
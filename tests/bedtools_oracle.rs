@@ -0,0 +1,227 @@
+//! Differential tests against an installed bedtools.
+//!
+//! Each test generates A/B datasets with `GenerateCommand` (uniform and
+//! clustered placement, plus A against itself), runs the same operation
+//! through bedtools and grit, and requires the outputs to hold the same
+//! lines. This codifies the parity checked by `tests/validation/validate.sh`
+//! on generated data instead of the small fixtures.
+//!
+//! Lines are compared as sorted multisets, as `validate.sh` does, since
+//! bedtools and grit may order records with equal keys differently.
+//!
+//! The tests pass without running anything when `bedtools` is not on PATH.
+
+use grit_genomics::commands::{GenerateCommand, GenerateConfig, GenerateMode, SortMode};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A/B files from one generated dataset, with a genome file covering them.
+struct Dataset {
+    label: &'static str,
+    a: PathBuf,
+    b: PathBuf,
+    genome: PathBuf,
+}
+
+fn bedtools_available() -> bool {
+    Command::new("bedtools")
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Generate the datasets under `dir`.
+fn datasets(dir: &Path) -> Vec<Dataset> {
+    let mut sets = Vec::new();
+    for (label, mode) in [
+        ("uniform", GenerateMode::Balanced),
+        ("clustered", GenerateMode::Clustered),
+    ] {
+        let out = dir.join(label);
+        let config = GenerateConfig {
+            output_dir: out.clone(),
+            seed: 42,
+            mode,
+            sorted: SortMode::Yes,
+            custom_a: Some(2_000),
+            custom_b: Some(8_000),
+            force: true,
+            ..GenerateConfig::default()
+        };
+        GenerateCommand::new(config)
+            .run()
+            .expect("generate datasets");
+
+        // Custom sizes are written to a single `custom_A..._B...` directory
+        let pair = fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.is_dir())
+            .expect("generated directory");
+        let (a, b) = (pair.join("A.bed"), pair.join("B.bed"));
+        let genome = pair.join("genome.txt");
+        write_genome(&genome, &[&a, &b]);
+        sets.push(Dataset {
+            label,
+            a: a.clone(),
+            b: b.clone(),
+            genome: genome.clone(),
+        });
+        if label == "uniform" {
+            sets.push(Dataset {
+                label: "self",
+                a: a.clone(),
+                b: a,
+                genome,
+            });
+        }
+    }
+    sets
+}
+
+/// Write a genome file, in lexicographic order like the generated data,
+/// whose chromosomes end past every interval.
+fn write_genome(path: &Path, beds: &[&Path]) {
+    let mut sizes: BTreeMap<String, u64> = BTreeMap::new();
+    for bed in beds {
+        for line in fs::read_to_string(bed).unwrap().lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let end: u64 = fields[2].parse().unwrap();
+            let size = sizes.entry(fields[0].to_string()).or_default();
+            *size = (*size).max(end + 1_000);
+        }
+    }
+    let text: String = sizes
+        .iter()
+        .map(|(chrom, size)| format!("{}\t{}\n", chrom, size))
+        .collect();
+    fs::write(path, text).unwrap();
+}
+
+fn run(program: &str, args: &[&str]) -> Vec<String> {
+    let mut command = if program == "grit" {
+        let mut c = Command::new("cargo");
+        c.args(["run", "--release", "--quiet", "--"]);
+        c
+    } else {
+        Command::new(program)
+    };
+    let output = command.args(args).output().expect("run command");
+    assert!(
+        output.status.success(),
+        "{} {:?} failed: {}",
+        program,
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines.sort();
+    lines
+}
+
+/// Run each case on every dataset and compare bedtools with grit.
+///
+/// A case is the bedtools arguments and the grit arguments, with `{a}`,
+/// `{b}` and `{g}` standing for the dataset's files.
+fn check(cases: &[(&str, &str)]) {
+    if !bedtools_available() {
+        eprintln!("bedtools not found on PATH; skipping");
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    for set in datasets(dir.path()) {
+        let fill = |args: &str| -> Vec<String> {
+            args.split_whitespace()
+                .map(|arg| match arg {
+                    "{a}" => set.a.display().to_string(),
+                    "{b}" => set.b.display().to_string(),
+                    "{g}" => set.genome.display().to_string(),
+                    _ => arg.to_string(),
+                })
+                .collect()
+        };
+        for (bedtools_args, grit_args) in cases {
+            let bt_args = fill(bedtools_args);
+            let gr_args = fill(grit_args);
+            let expected = run(
+                "bedtools",
+                &bt_args.iter().map(String::as_str).collect::<Vec<_>>(),
+            );
+            let actual = run(
+                "grit",
+                &gr_args.iter().map(String::as_str).collect::<Vec<_>>(),
+            );
+            assert!(
+                !expected.is_empty(),
+                "{}: bedtools wrote nothing",
+                set.label
+            );
+
+            let first_diff = expected.iter().zip(&actual).position(|(e, a)| e != a);
+            assert!(
+                expected.len() == actual.len() && first_diff.is_none(),
+                "{} on {} data: {} lines from bedtools, {} from grit; first difference:\n  bedtools: {:?}\n  grit:     {:?}",
+                grit_args,
+                set.label,
+                expected.len(),
+                actual.len(),
+                first_diff.map(|i| &expected[i]),
+                first_diff.map(|i| &actual[i]),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_genomecov_matches_bedtools() {
+    check(&[
+        ("genomecov -i {a} -g {g}", "genomecov -i {a} -g {g}"),
+        (
+            "genomecov -i {a} -g {g} -bg",
+            "genomecov -i {a} -g {g} --bg",
+        ),
+        (
+            "genomecov -i {a} -g {g} -bga",
+            "genomecov -i {a} -g {g} --bga",
+        ),
+    ]);
+}
+
+#[test]
+fn test_coverage_matches_bedtools() {
+    check(&[
+        ("coverage -a {a} -b {b}", "coverage -a {a} -b {b}"),
+        (
+            "coverage -a {a} -b {b} -hist",
+            "coverage -a {a} -b {b} --hist",
+        ),
+        (
+            "coverage -a {a} -b {b} -mean",
+            "coverage -a {a} -b {b} --mean",
+        ),
+    ]);
+}
+
+#[test]
+fn test_subtract_matches_bedtools() {
+    check(&[
+        ("subtract -a {a} -b {b}", "subtract -a {a} -b {b}"),
+        ("subtract -a {a} -b {b} -A", "subtract -a {a} -b {b} -A"),
+    ]);
+}
+
+#[test]
+fn test_closest_matches_bedtools() {
+    check(&[
+        ("closest -a {a} -b {b}", "closest -a {a} -b {b}"),
+        ("closest -a {a} -b {b} -d", "closest -a {a} -b {b} -d"),
+        ("closest -a {a} -b {b} -io", "closest -a {a} -b {b} --io"),
+    ]);
+}
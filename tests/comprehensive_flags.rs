@@ -39,7 +39,7 @@ fn run_grit(args: &[&str]) -> Output {
         .expect("Failed to run grit")
 }
 
-fn run_grit_with_stdin(args: &[&str], stdin_content: &str) -> Output {
+fn run_grit_with_stdin(args: &[&str], stdin_content: impl AsRef<[u8]>) -> Output {
     let mut child = Command::new("cargo")
         .args(["run", "--release", "--"])
         .args(args)
@@ -50,7 +50,7 @@ fn run_grit_with_stdin(args: &[&str], stdin_content: &str) -> Output {
         .expect("Failed to spawn grit");
 
     if let Some(mut stdin) = child.stdin.take() {
-        stdin.write_all(stdin_content.as_bytes()).unwrap();
+        stdin.write_all(stdin_content.as_ref()).unwrap();
     }
 
     child.wait_with_output().expect("Failed to wait for grit")
@@ -385,44 +385,2190 @@ fn test_closest_report_distance() {
     );
 }
 
+#[test]
+fn test_closest_histogram() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t1000\t1100\nchr1\t5000\t5100\nchr2\t0\t10\n");
+    let b = create_bed_file("chr1\t150\t160\nchr1\t1600\t1700\n");
+
+    let output = run_grit(&[
+        "closest",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "--histogram",
+        "--bin-size",
+        "1000",
+    ]);
+
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let result = stdout(&output);
+    let lines: Vec<&str> = result.lines().collect();
+    assert_eq!(lines[0], "bin_start\tbin_end\tcount\tfraction");
+    assert!(
+        lines.iter().any(|l| l.starts_with("0\t1000\t2\t")),
+        "{}",
+        result
+    );
+    assert!(
+        lines.iter().any(|l| l.starts_with("3000\t4000\t1\t")),
+        "{}",
+        result
+    );
+    assert!(
+        lines.last().unwrap().starts_with("# n=3 no_closest=1"),
+        "{}",
+        result
+    );
+}
+
 // =============================================================================
 // MERGE: Strand (-s) flag tests
 // =============================================================================
 
-/// Test -s (strand) flag - same strand merges
+/// Test -s (strand) flag - same strand merges
+#[test]
+fn test_merge_strand_same() {
+    let bed = create_bed_file("chr1\t100\t200\t.\t.\t+\nchr1\t150\t250\t.\t.\t+\n");
+
+    let output = run_grit(&["merge", "-i", bed.path().to_str().unwrap(), "-s"]);
+
+    assert!(is_success(&output));
+    let result = stdout(&output);
+    let lines: Vec<_> = result.lines().collect();
+    assert_eq!(
+        lines.len(),
+        1,
+        "Same strand intervals should merge: {}",
+        result
+    );
+}
+
+/// Test -s (strand) flag - different strands don't merge
+#[test]
+fn test_merge_strand_different() {
+    let bed = create_bed_file("chr1\t100\t200\t.\t.\t+\nchr1\t150\t250\t.\t.\t-\n");
+
+    let output = run_grit(&["merge", "-i", bed.path().to_str().unwrap(), "-s"]);
+
+    assert!(is_success(&output));
+    let result = stdout(&output);
+    let lines: Vec<_> = result.lines().collect();
+    assert_eq!(
+        lines.len(),
+        2,
+        "Different strand intervals should not merge: {}",
+        result
+    );
+}
+
+/// Test --min-count across the fast, strand-aware and in-memory paths
+#[test]
+fn test_merge_min_count() {
+    let bed = create_bed_file(
+        "chr1\t100\t200\t.\t.\t+\nchr1\t150\t250\t.\t.\t+\nchr1\t400\t500\t.\t.\t+\n",
+    );
+    let path = bed.path().to_str().unwrap();
+
+    for extra in [&[][..], &["-s"][..], &["--in-memory"][..]] {
+        let mut args = vec!["merge", "-i", path, "--min-count", "2", "-c"];
+        args.extend_from_slice(extra);
+        let output = run_grit(&args);
+
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        let result = stdout(&output);
+        let lines: Vec<_> = result.lines().collect();
+        assert_eq!(lines.len(), 1, "{:?}: {}", extra, result);
+        assert!(lines[0].starts_with("chr1\t100\t250\t"), "{}", result);
+        assert!(lines[0].ends_with("\t2"), "{}", result);
+    }
+}
+
+/// Test merge and sort on CRLF, space-delimited input with a mid-file track line
+#[test]
+fn test_lenient_bed_input() {
+    let bed = create_bed_file("chr1 100 200\r\ntrack name=x\r\nchr1 150 250\r\nchr1 400 500\r\n");
+    let path = bed.path().to_str().unwrap();
+
+    let output = run_grit(&["merge", "-i", path, "--stats"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t250\nchr1\t400\t500\n");
+    assert!(
+        stderr(&output).contains("CRLF lines: 3"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = run_grit(&["sort", "-i", path]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output).lines().count(), 3);
+
+    let output = run_grit(&["--delim", "tab", "merge", "-i", path]);
+    assert!(!is_success(&output));
+}
+
+/// Test that fractional scores survive re-serialization unless --score-format int
+#[test]
+fn test_float_score_preserved() {
+    let a = create_bed_file("chr1\t100\t200\tp1\t12.75\t+\nchr1\t300\t400\tp2\t8\t-\n");
+    let b = create_bed_file("chr1\t150\t350\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["intersect", "-a", a, "-b", b]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t150\t200\tp1\t12.75\t+\nchr1\t300\t350\tp2\t8\t-\n"
+    );
+
+    let output = run_grit(&["--score-format", "int", "subtract", "-a", a, "-b", b]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).starts_with("chr1\t100\t150\tp1\t12\t+\n"));
+}
+
+/// Test window --per-a-summary emits one row per A, including A without hits
+#[test]
+fn test_window_per_a_summary() {
+    let a = create_bed_file("chr1\t500\t600\nchr1\t5000\t5100\n");
+    let b = create_bed_file("chr1\t700\t750\tb1\t3\t+\nchr1\t800\t850\tb2\t5\t+\n");
+
+    let output = run_grit(&[
+        "window",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "-w",
+        "500",
+        "--per-a-summary",
+    ]);
+
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t500\t600\t2\t100\t5\t4\nchr1\t5000\t5100\t0\t.\t.\t.\n"
+    );
+}
+
+/// Test window --per-a-summary reads scores from space-delimited B
+#[test]
+fn test_window_per_a_summary_space_delimited() {
+    let a = create_bed_file("chr1\t500\t600\n");
+    let b = create_bed_file("chr1 700 750 b1 3 +\nchr1 800 850 b2 5 +\n");
+
+    for delim in ["auto", "space"] {
+        let output = run_grit(&[
+            "--delim",
+            delim,
+            "window",
+            "-a",
+            a.path().to_str().unwrap(),
+            "-b",
+            b.path().to_str().unwrap(),
+            "-w",
+            "500",
+            "--per-a-summary",
+        ]);
+
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t500\t600\t2\t100\t5\t4\n",
+            "--delim {}",
+            delim
+        );
+    }
+}
+
+/// Test --version build report and short -V output
+#[test]
+fn test_version_build_info() {
+    let output = run_grit(&["--version"]);
+    assert!(is_success(&output));
+    let long = stdout(&output);
+    for key in [
+        "commit:",
+        "build date:",
+        "target:",
+        "features:",
+        "cpu features:",
+    ] {
+        assert!(long.contains(key), "missing {}: {}", key, long);
+    }
+
+    let output = run_grit(&["-V"]);
+    assert!(is_success(&output));
+    assert_eq!(stdout(&output).lines().count(), 1);
+}
+
+/// Test merge --distance-pct across the fast, strand and in-memory paths
+#[test]
+fn test_merge_distance_pct() {
+    let bed = create_bed_file(
+        "chr1\t100\t200\t.\t.\t+\nchr1\t205\t305\t.\t.\t+\nchr1\t320\t420\t.\t.\t+\n",
+    );
+    let path = bed.path().to_str().unwrap();
+
+    for extra in [&[][..], &["-s"][..], &["--in-memory"][..]] {
+        let mut args = vec!["merge", "-i", path, "--distance-pct", "10"];
+        args.extend_from_slice(extra);
+        let output = run_grit(&args);
+
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        let result = stdout(&output);
+        let lines: Vec<_> = result.lines().collect();
+        assert_eq!(lines.len(), 2, "{:?}: {}", extra, result);
+        assert!(lines[0].starts_with("chr1\t100\t305"), "{}", result);
+    }
+
+    let output = run_grit(&["merge", "-i", path, "--distance-pct", "10", "-d", "5"]);
+    assert!(!is_success(&output));
+}
+
+/// Test modify --center on midpoints and strand-aware 5' anchors
+#[test]
+fn test_modify_center() {
+    let bed = create_bed_file("chr1\t1000\t1201\tp1\t5\t-\nchr1\t10\t20\tp2\t5\t+\n");
+    let path = bed.path().to_str().unwrap();
+
+    let output = run_grit(&["modify", "-i", path, "--center", "100"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let result = stdout(&output);
+    let lines: Vec<_> = result.lines().collect();
+    assert_eq!(lines[0], "chr1\t1050\t1150\tp1\t5\t-");
+    assert_eq!(lines[1], "chr1\t0\t100\tp2\t5\t+");
+
+    let output = run_grit(&["modify", "-i", path, "--anchor", "5p", "--center", "10"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let result = stdout(&output);
+    let lines: Vec<_> = result.lines().collect();
+    assert_eq!(lines[0], "chr1\t1195\t1205\tp1\t5\t-");
+    assert_eq!(lines[1], "chr1\t5\t15\tp2\t5\t+");
+}
+
+/// Test coverage --mean with depth stddev and quantiles
+#[test]
+fn test_coverage_mean_depth_stats() {
+    let a = create_bed_file("chr1\t100\t200\n");
+    let b = create_bed_file("chr1\t100\t150\nchr1\t100\t150\nchr1\t100\t150\nchr1\t100\t150\n");
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "--mean",
+        "--stddev",
+        "--median",
+        "--quantiles",
+        "0.25,0.75",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\t2.0000000\t2.0000000\t0\t0\t4\n"
+    );
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "--stddev",
+    ]);
+    assert!(!is_success(&output));
+}
+
+/// Test covariates with FASTA and mappability BedGraph
+#[test]
+fn test_covariates() {
+    let bed = create_bed_file("chr1\t0\t4\tbin1\nchr1\t4\t8\tbin2\n");
+    let fasta = create_bed_file(">chr1\nACGG\nNNAT\n");
+    let track = create_bed_file("chr1\t0\t3\t1\n");
+
+    let output = run_grit(&[
+        "covariates",
+        "-i",
+        bed.path().to_str().unwrap(),
+        "--fasta",
+        fasta.path().to_str().unwrap(),
+        "--mappability",
+        track.path().to_str().unwrap(),
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t4\tbin1\t4\t0.750000\t0.750000\nchr1\t4\t8\tbin2\t4\t0.000000\t0.000000\n"
+    );
+}
+
+/// Test bedtools tool-name aliases and the `grit bedtools` shim
+#[test]
+fn test_bedtools_aliases_and_shim() {
+    let bed = create_bed_file("chr1\t100\t200\nchr1\t150\t300\n");
+    let genome = create_genome_file("chr1\t1000\n");
+    let bed_path = bed.path().to_str().unwrap();
+    let genome_path = genome.path().to_str().unwrap();
+
+    let output = run_grit(&["mergeBed", "-i", bed_path]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t300\n");
+
+    let expected = "chr1\t100\t150\t0.5\nchr1\t150\t200\t1\nchr1\t200\t300\t0.5\n";
+    for name in ["genomecov", "genomeCoverageBed"] {
+        let output = run_grit(&[
+            "-t",
+            "1",
+            "bedtools",
+            name,
+            "-i",
+            bed_path,
+            "-g",
+            genome_path,
+            "-bg",
+            "-scale",
+            "0.5",
+            "-sorted",
+        ]);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(stdout(&output), expected);
+    }
+
+    // Unsupported bedtools options are still rejected
+    let output = run_grit(&["bedtools", "merge", "-i", bed_path, "-o", "sum"]);
+    assert!(!is_success(&output));
+}
+
+/// Test bedtools single-dash flags outside the shim
+#[test]
+fn test_bedtools_single_dash_flags() {
+    let a = create_bed_file("chr1\t100\t200\ta\n");
+    let b = create_bed_file("chr1\t150\t160\tb\nchr1\t300\t400\tc\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["closest", "-a", a, "-b", b, "-io", "-sorted"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t200\ta\tchr1\t300\t400\tc\n");
+    assert!(stderr(&output)
+        .contains("Warning: translated bedtools options: -io -> --io, -sorted -> --assume-sorted"));
+
+    // The shim translates without a warning
+    let output = run_grit(&["bedtools", "closest", "-a", a, "-b", b, "-io"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(!stderr(&output).contains("Warning"));
+
+    let hit = "chr1\t100\t200\ta\tchr1\t150\t160\tb";
+    for (flag, expected) in [
+        ("-wo", format!("{}\t10\n", hit)),
+        ("-loj", format!("{}\n", hit)),
+    ] {
+        for mode in [&["--streaming"][..], &[][..], &["--indexed"]] {
+            let mut args = vec!["intersect", "-a", a, "-b", b, flag];
+            args.extend_from_slice(mode);
+            let output = run_grit(&args);
+            assert!(is_success(&output), "stderr: {}", stderr(&output));
+            assert_eq!(stdout(&output), expected, "{} {:?}", flag, mode);
+        }
+    }
+
+    // A without overlaps is paired with a null B record
+    let far = create_bed_file("chr1\t100\t200\ta\nchr2\t5\t10\tz\n");
+    let far = far.path().to_str().unwrap();
+    for mode in [&["--streaming"][..], &[][..]] {
+        let mut args = vec!["bedtools", "intersect", "-a", far, "-b", b, "-wao"];
+        args.extend_from_slice(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            format!("{}\t10\nchr2\t5\t10\tz\t.\t-1\t-1\t.\t0\n", hit)
+        );
+    }
+
+    for (cmd, flag) in [("intersect", "-split"), ("closest", "-mdb")] {
+        let output = run_grit(&[cmd, "-a", a, "-b", b, flag]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(stderr(&output).contains(&format!("bedtools option '{}' is not supported", flag)));
+    }
+
+    // Only rejected on the subcommands bedtools defines them for
+    let output = run_grit(&["merge", "-i", a, "-split"]);
+    assert!(!stderr(&output).contains("bedtools option"));
+}
+
+/// Test that global options taking a value are skipped to find the subcommand
+#[test]
+fn test_bedtools_flags_after_global_value_options() {
+    let a = create_bed_file("chr1\t100\t200\ta\n");
+    let b = create_bed_file("chr1\t150\t160\tb\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    for global in [
+        &["--max-output-lines", "5"][..],
+        &["--max-output-bytes", "1K"][..],
+        &["--io-threads", "1"][..],
+        &["--temp-dir", "."][..],
+    ] {
+        let mut args = global.to_vec();
+        args.extend_from_slice(&["bedtools", "intersect", "-a", a, "-b", b, "-sorted"]);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "{:?}: {}", global, stderr(&output));
+        assert_eq!(stdout(&output), "chr1\t150\t160\ta\n");
+
+        let mut args = global.to_vec();
+        args.extend_from_slice(&["intersect", "-a", a, "-b", b, "-sorted"]);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "{:?}: {}", global, stderr(&output));
+        assert!(stderr(&output).contains("-sorted -> --assume-sorted"));
+    }
+}
+
+/// Test join on the BED name column, inner and left
+#[test]
+fn test_join_inner_and_left() {
+    let genes = create_bed_file("chr1\t100\t200\tGENE1\nchr1\t300\t400\tGENE2\n");
+    let table = create_bed_file("GENE1\t12.5\n");
+
+    let args = [
+        "join",
+        "-a",
+        genes.path().to_str().unwrap(),
+        "-b",
+        table.path().to_str().unwrap(),
+    ];
+    let output = run_grit(&args);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t200\tGENE1\t12.5\n");
+
+    let mut left = args.to_vec();
+    left.extend_from_slice(&["--type", "left", "--fill", "0"]);
+    let output = run_grit(&left);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\tGENE1\t12.5\nchr1\t300\t400\tGENE2\t0\n"
+    );
+}
+
+/// Test empty and header-only B files, and A without a trailing newline
+#[test]
+fn test_empty_and_header_only_b() {
+    let a = create_bed_file("chr1\t100\t200\tr1\nchr2\t50\t80\tr2");
+    let empty = create_bed_file("");
+    let header_only = create_bed_file("#chrom\tstart\tend\ntrack name=none\n");
+    let a_path = a.path().to_str().unwrap();
+
+    for b in [&empty, &header_only] {
+        let b_path = b.path().to_str().unwrap();
+        let cases: [(&[&str], &str); 6] = [
+            (
+                &["intersect", "-v"],
+                "chr1\t100\t200\tr1\nchr2\t50\t80\tr2\n",
+            ),
+            (
+                &["intersect", "-c", "--streaming"],
+                "chr1\t100\t200\tr1\t0\nchr2\t50\t80\tr2\t0\n",
+            ),
+            (&["subtract"], "chr1\t100\t200\tr1\nchr2\t50\t80\tr2\n"),
+            (&["window", "-v"], "chr1\t100\t200\tr1\nchr2\t50\t80\tr2\n"),
+            (
+                &["closest", "-d"],
+                "chr1\t100\t200\tr1\t.\t-1\t-1\t-1\nchr2\t50\t80\tr2\t.\t-1\t-1\t-1\n",
+            ),
+            (
+                &["closest", "-d", "--streaming"],
+                "chr1\t100\t200\tr1\t.\t-1\t-1\t-1\nchr2\t50\t80\tr2\t.\t-1\t-1\t-1\n",
+            ),
+        ];
+        for (cmd, expected) in cases {
+            let mut args = vec![cmd[0], "-a", a_path, "-b", b_path];
+            args.extend_from_slice(&cmd[1..]);
+            let output = run_grit(&args);
+            assert!(is_success(&output), "{:?}: {}", cmd, stderr(&output));
+            assert_eq!(stdout(&output), expected, "{:?}", cmd);
+        }
+    }
+
+    // An empty A produces no output rather than an error
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        empty.path().to_str().unwrap(),
+        "-b",
+        a_path,
+        "-v",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
+
+/// Test that streaming closest reports -d distances like the default mode
+#[test]
+fn test_closest_streaming_distance_matches() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t500\t600\nchr3\t5\t6\n");
+    let b = create_bed_file("chr1\t150\t160\nchr1\t300\t350\nchr1\t650\t700");
+    let args = [
+        "closest",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "-d",
+        "--io",
+    ];
+
+    let default = run_grit(&args);
+    assert!(is_success(&default), "stderr: {}", stderr(&default));
+    let mut streaming_args = args.to_vec();
+    streaming_args.push("--streaming");
+    let streaming = run_grit(&streaming_args);
+    assert!(is_success(&streaming), "stderr: {}", stderr(&streaming));
+
+    assert_eq!(stdout(&streaming), stdout(&default));
+    assert_eq!(
+        stdout(&streaming),
+        "chr1\t100\t200\tchr1\t300\t350\t101\n\
+         chr1\t500\t600\tchr1\t650\t700\t51\n\
+         chr3\t5\t6\t.\t-1\t-1\t-1\n"
+    );
+}
+
+/// Test shuffle keeps lengths, honours --background matched and is seeded
+#[test]
+fn test_shuffle_matched_background() {
+    let input = create_bed_file("chr1\t100\t300\ta\nchr2\t0\t50\tb\n");
+    let genome = create_genome_file("chr1\t100000\nchr2\t1000\n");
+    let exclude = create_bed_file("chr2\t0\t500\n");
+
+    let args = [
+        "shuffle",
+        "-i",
+        input.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "--background",
+        "matched",
+        "--per-record",
+        "5",
+        "--exclude",
+        exclude.path().to_str().unwrap(),
+    ];
+    let output = run_grit(&args);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    let lines: Vec<Vec<&str>> = out.lines().map(|l| l.split('\t').collect()).collect();
+    assert_eq!(lines.len(), 10);
+    for (i, fields) in lines.iter().enumerate() {
+        let (chrom, name, len) = if i < 5 {
+            ("chr1", "a", 200)
+        } else {
+            ("chr2", "b", 50)
+        };
+        let start: u64 = fields[1].parse().unwrap();
+        let end: u64 = fields[2].parse().unwrap();
+        assert_eq!((fields[0], fields[3], end - start), (chrom, name, len));
+        if chrom == "chr2" {
+            assert!(start >= 500, "placed in excluded region: {}", out);
+        }
+    }
+
+    // Same seed, same output; bedtools spelling runs the same command
+    let again = run_grit(&args);
+    assert_eq!(stdout(&again), out);
+    let bedtools = run_grit(&[
+        "bedtools",
+        "shuffle",
+        "-i",
+        input.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "-chrom",
+        "-seed",
+        "42",
+        "-excl",
+        exclude.path().to_str().unwrap(),
+    ]);
+    assert!(is_success(&bedtools), "stderr: {}", stderr(&bedtools));
+    assert_eq!(stdout(&bedtools).lines().count(), 2);
+}
+
+/// Test --explain prints the plan without running the command
+#[test]
+fn test_explain_plan() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t300\t400\n");
+    let b = create_bed_file("chr1\t150\t250\n");
+    let a_path = a.path().to_str().unwrap();
+    let b_path = b.path().to_str().unwrap();
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a_path,
+        "-b",
+        b_path,
+        "--streaming",
+        "--assume-sorted",
+        "--explain",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let plan = stdout(&output);
+    assert!(plan.contains("strategy:    streaming"), "{}", plan);
+    assert!(
+        plan.contains("validation:  none, input assumed sorted"),
+        "{}",
+        plan
+    );
+    assert!(plan.contains("~2 records, streamed"), "{}", plan);
+    assert!(!plan.contains("chr1\t"), "command ran: {}", plan);
+
+    // -D forces the in-memory path even with --streaming
+    let output = run_grit(&[
+        "--explain",
+        "closest",
+        "-a",
+        a_path,
+        "-b",
+        b_path,
+        "--streaming",
+        "-D",
+        "10",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).contains("strategy:    in-memory"));
+
+    let output = run_grit(&["jaccard", "-a", a_path, "-b", b_path, "--explain"]);
+    assert!(!is_success(&output));
+}
+
+/// Test B score thresholds for intersect (both modes) and coverage
+#[test]
+fn test_b_score_thresholds() {
+    let a = create_bed_file("chr1\t100\t200\tg1\nchr1\t500\t600\tg2\n");
+    let b =
+        create_bed_file("chr1\t150\t160\tp1\t5\nchr1\t170\t180\tp2\t50\nchr1\t550\t560\tp3\t.\n");
+    let a_path = a.path().to_str().unwrap();
+    let b_path = b.path().to_str().unwrap();
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "intersect",
+            "-a",
+            a_path,
+            "-b",
+            b_path,
+            "-c",
+            "--min-b-score",
+            "10",
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t200\tg1\t1\nchr1\t500\t600\tg2\t0\n",
+            "{:?}",
+            mode
+        );
+    }
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a_path,
+        "-b",
+        b_path,
+        "--max-b-score",
+        "10",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\tg1\t1\t10\t100\t0.1000000\n\
+         chr1\t500\t600\tg2\t0\t0\t100\t0.0000000\n"
+    );
+}
+
+/// Test chromosome keep/drop filters across parsers
+#[test]
+fn test_keep_drop_chroms() {
+    let bed = create_bed_file(
+        "chr1\t100\t200\n\
+         chr1_KI270706v1_random\t5\t10\n\
+         chr2\t50\t80\n\
+         chrM\t0\t30\n\
+         chrUn_GL000220v1\t1\t9\n\
+         chrX\t5\t9\n",
+    );
+    let path = bed.path().to_str().unwrap();
+
+    let output = run_grit(&["sort", "-i", path, "--drop-chroms", "chrUn_*,*_random,chrM"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\nchr2\t50\t80\nchrX\t5\t9\n"
+    );
+
+    // Streaming intersect reads through the byte-level parser
+    let output = run_grit(&[
+        "--keep-chroms",
+        "chr1-22",
+        "intersect",
+        "-a",
+        path,
+        "-b",
+        path,
+        "-u",
+        "--streaming",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t200\nchr2\t50\t80\n");
+
+    let output = run_grit(&["merge", "-i", path, "--keep-chroms", "chr5-1"]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("invalid chromosome range"));
+
+    // Dropped chromosomes are left out of the genome file too
+    let genome = create_genome_file("chr1\t1000\nchrM\t100\nchr2\t1000\n");
+    let genome = genome.path().to_str().unwrap();
+    let input = create_bed_file("chr1\t0\t500\n");
+    let input = input.path().to_str().unwrap();
+
+    let output = run_grit(&[
+        "--drop-chroms",
+        "chrM",
+        "genomecov",
+        "-i",
+        input,
+        "-g",
+        genome,
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    assert!(!out.contains("chrM"), "{}", out);
+    assert!(out.contains("genome\t0\t1500\t2000\t"), "{}", out);
+
+    let output = run_grit(&[
+        "--drop-chroms",
+        "chrM",
+        "complement",
+        "-i",
+        input,
+        "-g",
+        genome,
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t500\t1000\nchr2\t0\t1000\n");
+
+    let output = run_grit(&[
+        "--drop-chroms",
+        "chrM",
+        "shuffle",
+        "-i",
+        input,
+        "-g",
+        genome,
+        "--per-record",
+        "50",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    assert_eq!(out.lines().count(), 50);
+    assert!(!out.contains("chrM"), "{}", out);
+}
+
+/// Test --output-format jsonl and tsv+header
+#[test]
+fn test_output_format() {
+    let a = create_bed_file("chr1\t100\t200\tg1\nchr2\t10\t20\tg2\n");
+    let b = create_bed_file("chr1\t150\t160\nchr1\t300\t400\n");
+    let a_path = a.path().to_str().unwrap();
+    let b_path = b.path().to_str().unwrap();
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "intersect",
+            "-a",
+            a_path,
+            "-b",
+            b_path,
+            "--wa",
+            "--wb",
+            "--output-format",
+            "jsonl",
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "{\"a\":{\"chrom\":\"chr1\",\"start\":100,\"end\":200,\"name\":\"g1\"},\
+             \"b\":{\"chrom\":\"chr1\",\"start\":150,\"end\":160},\"overlap_bp\":10,\
+             \"overlap_fraction\":0.1}\n",
+            "{:?}",
+            mode
+        );
+    }
+
+    let output = run_grit(&[
+        "closest",
+        "-a",
+        a_path,
+        "-b",
+        b_path,
+        "-d",
+        "--output-format",
+        "tsv+header",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "a_chrom\ta_start\ta_end\ta_name\tb_chrom\tb_start\tb_end\tdistance\n\
+         chr1\t100\t200\tg1\tchr1\t150\t160\t0\n\
+         chr2\t10\t20\tg2\t.\t.\t.\t-1\n"
+    );
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a_path,
+        "-b",
+        b_path,
+        "--output-format",
+        "jsonl",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).starts_with(
+        "{\"a\":{\"chrom\":\"chr1\",\"start\":100,\"end\":200,\"name\":\"g1\"},\
+         \"count\":1,\"covered_bp\":10,\"length\":100,\"fraction\":0.1}\n"
+    ));
+}
+
+/// Test genome file metadata columns in genomecov totals and shuffle draws
+#[test]
+fn test_genome_file_metadata() {
+    let input = create_bed_file("chr1\t0\t100\nchrUn\t0\t100\n");
+    let genome = create_genome_file("chr1\t1000\teffective=800\nchrUn\t100\texclude\n");
+
+    for streaming in [false, true] {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            input.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+        ];
+        if streaming {
+            args.push("--streaming");
+        }
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t0\t900\t1000\t0.9\n\
+             chr1\t1\t100\t1000\t0.1\n\
+             chrUn\t1\t100\t100\t1\n\
+             genome\t0\t700\t800\t0.875\n\
+             genome\t1\t100\t800\t0.125\n"
+        );
+    }
+
+    let output = run_grit(&[
+        "shuffle",
+        "-i",
+        input.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "--per-record",
+        "50",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).lines().all(|l| l.starts_with("chr1\t")));
+
+    let bad = create_genome_file("chr1\t1000\tploidy=two\n");
+    let output = run_grit(&[
+        "genomecov",
+        "-i",
+        input.path().to_str().unwrap(),
+        "-g",
+        bad.path().to_str().unwrap(),
+    ]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("Invalid ploidy"));
+}
+
+/// Test merge --id and --names label columns
+#[test]
+fn test_merge_id_and_names() {
+    let input = create_bed_file(
+        "chr1\t100\t200\tg1\t0\t+\nchr1\t150\t250\tg2\t0\t+\nchr1\t300\t400\tg3\t0\t-\n",
+    );
+    let path = input.path().to_str().unwrap();
+
+    let output = run_grit(&["merge", "-i", path, "-c", "--id", "coords", "--names"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t250\t2\tchr1_100_250\tg1,g2\nchr1\t300\t400\t1\tchr1_300_400\tg3\n"
+    );
+
+    let output = run_grit(&["merge", "-i", path, "--id", "hash"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let text = stdout(&output);
+    let ids: Vec<&str> = text
+        .lines()
+        .map(|l| l.split('\t').nth(3).unwrap())
+        .collect();
+    assert!(ids.iter().all(|id| id.len() == 16) && ids[0] != ids[1]);
+}
+
+/// Test merge --best-by summit representatives
+#[test]
+fn test_merge_best_by() {
+    let input = create_bed_file(
+        "chr1\t100\t200\tp1\t5\nchr1\t150\t300\tp2\t9\nchr1\t250\t400\tp3\t2\nchr1\t900\t950\tp4\t.\n",
+    );
+    let path = input.path().to_str().unwrap();
+
+    for mode in [None, Some("--in-memory")] {
+        let mut args = vec!["merge", "-i", path, "--best-by", "--id", "coords"];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t150\t300\tchr1_100_400\t100\t400\nchr1\t900\t950\tchr1_900_950\t900\t950\n",
+            "mode {:?}",
+            mode
+        );
+    }
+}
+
+/// Test merge --scores on BedGraph and BED input
+#[test]
+fn test_merge_scores() {
+    let bedgraph = create_bed_file(
+        "track type=bedGraph\nchr1\t0\t10\t2\nchr1\t10\t20\t4\nchr1\t30\t40\t1.5\n",
+    );
+    let path = bedgraph.path().to_str().unwrap();
+
+    for (op, first) in [("sum", "6"), ("mean", "3"), ("max", "4")] {
+        for mode in [None, Some("--in-memory")] {
+            let mut args = vec!["merge", "-i", path, "--scores", op];
+            args.extend(mode);
+            let output = run_grit(&args);
+            assert!(is_success(&output), "stderr: {}", stderr(&output));
+            assert_eq!(
+                stdout(&output),
+                format!("chr1\t0\t20\t{}\nchr1\t30\t40\t1.5\n", first),
+                "{} {:?}",
+                op,
+                mode
+            );
+        }
+    }
+
+    // BED scores come from column 5; unscored records are skipped
+    let output = run_grit_with_stdin(
+        &["merge", "-i", "-", "-c", "--scores", "sum"],
+        "chr1\t0\t10\ta\t5\t+\nchr1\t5\t20\tb\t.\t+\nchr1\t30\t40\tc\t.\t-\n",
+    );
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t0\t20\t2\t5\nchr1\t30\t40\t1\t.\n");
+}
+
+/// Test jaccard --manifest batch comparisons
+#[test]
+fn test_jaccard_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("r1.bed"),
+        "chr1\t100\t200\nchr1\t300\t400\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("r2.bed"), "chr1\t150\t350\n").unwrap();
+    std::fs::write(dir.path().join("r3.bed"), "chr1\t0\t1000\n").unwrap();
+    let manifest = dir.path().join("m.tsv");
+    std::fs::write(&manifest, "rep1\tr1.bed\nrep2\tr2.bed\nrep3\tr3.bed\n").unwrap();
+    let manifest = manifest.to_str().unwrap();
+
+    let output = run_grit(&["jaccard", "--manifest", manifest]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    assert_eq!(out.lines().count(), 1 + 3 * 4);
+    assert!(out.contains("rep1\trep2\tjaccard\t0.333333\n"));
+    assert!(out.contains("rep2\trep3\tjaccard\t0.2\n"));
+
+    let output = run_grit(&["jaccard", "--manifest", manifest, "--against", "rep3", "-e"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    assert_eq!(out.lines().count(), 1 + 2 * 8);
+    assert!(out.contains("rep1\trep3\toverlap_coef\t1\n"));
+
+    let output = run_grit(&["jaccard", "--manifest", manifest, "-a", manifest]);
+    assert!(!is_success(&output));
+}
+
+/// Test --min-overlap-bp on intersect, subtract and window
+#[test]
+fn test_min_overlap_bp() {
+    let a = create_bed_file("chr1\t100\t200\tA1\nchr1\t300\t400\tA2\n");
+    let b = create_bed_file("chr1\t150\t350\tlong\nchr1\t190\t210\tshort\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "intersect",
+            "-a",
+            a,
+            "-b",
+            b,
+            "-c",
+            "--min-overlap-bp",
+            "20",
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t200\tA1\t1\nchr1\t300\t400\tA2\t1\n"
+        );
+
+        let mut args = vec!["subtract", "-a", a, "-b", b, "--min-overlap-bp", "20"];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(stdout(&output), "chr1\t100\t150\tA1\nchr1\t350\t400\tA2\n");
+    }
+
+    // The short B overlaps A1 by 10bp, but A1 padded by 10bp on each side by 20bp
+    let output = run_grit(&[
+        "window",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-w",
+        "10",
+        "-c",
+        "--min-overlap-bp",
+        "20",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\tA1\t2\nchr1\t300\t400\tA2\t1\n"
+    );
+}
+
+/// Test coverage --reciprocal-summary
+#[test]
+fn test_coverage_reciprocal_summary() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t150\t300\n");
+    let b = create_bed_file("chr1\t0\t50\nchr1\t90\t260\nchr1\t280\t400\nchr3\t0\t30\n");
+    let dir = tempfile::tempdir().unwrap();
+    let summary = dir.path().join("summary.tsv");
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "--reciprocal-summary",
+        summary.to_str().unwrap(),
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output).lines().count(), 2);
+
+    let text = std::fs::read_to_string(&summary).unwrap();
+    let row: Vec<&str> = text.lines().nth(1).unwrap().split('\t').skip(1).collect();
+    assert!(text.starts_with("#b_file\trecords"));
+    assert_eq!(row, ["4", "2", "0.500000", "370", "180", "0.486486"]);
+}
+
+/// Test coverage --index-a against unsorted B
+#[test]
+fn test_coverage_index_a() {
+    let a = create_bed_file("chr1\t100\t200\tg1\nchr1\t120\t130\tg2\nchr2\t0\t100\tg3\n");
+    let b_sorted = create_bed_file("chr1\t90\t125\nchr1\t125\t160\nchr2\t90\t95\n");
+    let b_unsorted = create_bed_file("chr2\t90\t95\nchr1\t125\t160\nchr1\t90\t125\n");
+    let a = a.path().to_str().unwrap();
+
+    let sweep = run_grit(&["coverage", "-a", a, "-b", b_sorted.path().to_str().unwrap()]);
+    assert!(is_success(&sweep), "stderr: {}", stderr(&sweep));
+    let indexed = run_grit(&[
+        "coverage",
+        "-a",
+        a,
+        "-b",
+        b_unsorted.path().to_str().unwrap(),
+        "--index-a",
+    ]);
+    assert!(is_success(&indexed), "stderr: {}", stderr(&indexed));
+    assert_eq!(stdout(&indexed), stdout(&sweep));
+}
+
+/// Test intersect --max-hits caps the pairs per A and is reported in --stats
+#[test]
+fn test_intersect_max_hits() {
+    let a = create_bed_file("chr1\t100\t200\tA1\nchr1\t300\t400\tA2\n");
+    let b = create_bed_file(
+        "chr1\t110\t120\tb1\nchr1\t130\t140\tb2\nchr1\t150\t160\tb3\nchr1\t350\t360\tb4\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec!["intersect", "-a", a, "-b", b, "--wb", "--max-hits", "2"];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        let out = stdout(&output);
+        let names: Vec<&str> = out
+            .lines()
+            .map(|l| l.rsplit('\t').next().unwrap())
+            .collect();
+        assert_eq!(names, ["b1", "b2", "b4"]);
+    }
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "--streaming",
+        "--stats",
+        "--max-hits",
+        "1",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output).lines().count(), 2);
+    assert!(stderr(&output).contains("Capped A: 1"));
+
+    // The cap only applies to modes that print one line per overlap
+    let output = run_grit(&["intersect", "-a", a, "-b", b, "-c", "--max-hits", "1"]);
+    assert!(!is_success(&output));
+}
+
+/// Test intersect --overlap-fractions appends overlap bp and both fractions
+#[test]
+fn test_intersect_overlap_fractions() {
+    let a = create_bed_file("chr1\t100\t200\tA1\n");
+    let b = create_bed_file("chr1\t150\t350\tb1\nchr1\t180\t190\tb2\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec!["intersect", "-a", a, "-b", b, "--overlap-fractions"];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t200\tA1\tchr1\t150\t350\tb1\t50\t0.5000000\t0.2500000\n\
+             chr1\t100\t200\tA1\tchr1\t180\t190\tb2\t10\t0.1000000\t1.0000000\n"
+        );
+    }
+
+    let output = run_grit(&["intersect", "-a", a, "-b", b, "--overlap-fractions", "-u"]);
+    assert!(!is_success(&output));
+}
+
+/// Test intersect/window --indexed against the streaming path, with and
+/// without a .gri index for B
+#[test]
+fn test_indexed_lookups() {
+    let dir = tempfile::tempdir().unwrap();
+    let b = dir.path().join("b.bed");
+    std::fs::write(
+        &b,
+        "chr1\t0\t5000\tlong\nchr1\t150\t350\tb1\nchr1\t900\t950\tb2\nchr2\t100\t200\tb3\n",
+    )
+    .unwrap();
+    let b = b.to_str().unwrap();
+    let a = create_bed_file("chr1\t100\t200\tA1\nchr1\t6000\t6100\tA2\nchr2\t150\t160\tA3\n");
+    let a = a.path().to_str().unwrap();
+    // Unsorted A is reported in input order
+    let a_unsorted = create_bed_file("chr2\t150\t160\tA3\nchr1\t100\t200\tA1\n");
+    let a_unsorted = a_unsorted.path().to_str().unwrap();
+
+    for build_index in [false, true] {
+        if build_index {
+            assert!(is_success(&run_grit(&["stab", "-i", b, "--build-index"])));
+        }
+        for flags in [&["--wa", "--wb"][..], &["-c"], &["-v"], &["-f", "0.5"]] {
+            let mut args = vec!["intersect", "-a", a, "-b", b];
+            args.extend(flags);
+            let streamed = run_grit(&[&args[..], &["--streaming"]].concat());
+            let indexed = run_grit(&[&args[..], &["--indexed"]].concat());
+            assert!(is_success(&indexed), "stderr: {}", stderr(&indexed));
+            assert_eq!(stdout(&indexed), stdout(&streamed), "flags: {:?}", flags);
+        }
+        for flags in [&["-w", "500"][..], &["-c"], &["--per-a-summary"]] {
+            let mut args = vec!["window", "-a", a, "-b", b];
+            args.extend(flags);
+            let streamed = run_grit(&args);
+            let indexed = run_grit(&[&args[..], &["--indexed"]].concat());
+            assert!(is_success(&indexed), "stderr: {}", stderr(&indexed));
+            assert_eq!(stdout(&indexed), stdout(&streamed), "flags: {:?}", flags);
+        }
+
+        let output = run_grit(&["intersect", "-a", a_unsorted, "-b", b, "-u", "--indexed"]);
+        assert_eq!(stdout(&output), "chr2\t150\t160\tA3\nchr1\t100\t200\tA1\n");
+    }
+
+    let output = run_grit(&["intersect", "-a", a, "-b", b, "--indexed", "--streaming"]);
+    assert!(!is_success(&output));
+}
+
+/// Test --provenance headers and their propagation through a second command
+#[test]
+fn test_provenance_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let raw = create_bed_file("chr1\t50\t60\nchr1\t10\t20\nchr1\t15\t30\n");
+    let raw = raw.path().to_str().unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--release", "--", "--provenance", "sort", "-i", raw])
+        .env("SOURCE_DATE_EPOCH", "86400")
+        .output()
+        .unwrap();
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let sorted = stdout(&output);
+    let header = sorted.lines().next().unwrap();
+    assert!(header.starts_with("##grit\tversion="));
+    assert!(header.contains("\tdate=1970-01-02T00:00:00Z\t"));
+    assert!(header.contains(&format!("\tinput={};sha256=", raw)));
+
+    let sorted_path = dir.path().join("sorted.bed");
+    std::fs::write(&sorted_path, &sorted).unwrap();
+    let output = run_grit(&["merge", "-i", sorted_path.to_str().unwrap(), "--provenance"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let merged = stdout(&output);
+    let lines: Vec<&str> = merged.lines().collect();
+    assert_eq!(lines[0], header);
+    assert!(lines[1].contains("merge"));
+    assert_eq!(&lines[2..], ["chr1\t10\t30", "chr1\t50\t60"]);
+
+    // Without the flag nothing is added
+    let output = run_grit(&["merge", "-i", sorted_path.to_str().unwrap()]);
+    assert_eq!(stdout(&output), "chr1\t10\t30\nchr1\t50\t60\n");
+}
+
+/// Test window/closest --dedup-b-name report one hit per B name
+#[test]
+fn test_dedup_b_name() {
+    let a = create_bed_file("chr1\t1000\t2000\tpeak\n");
+    let b = create_bed_file(
+        "chr1\t500\t1100\ttx1\t0\t+\tGENE1\n\
+         chr1\t900\t1900\ttx2\t0\t+\tGENE1\n\
+         chr1\t2100\t2200\ttx3\t0\t-\tGENE2\n\
+         chr1\t2100\t2200\ttx4\t0\t-\tGENE2\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&[
+        "window",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-w",
+        "500",
+        "--dedup-b-name",
+        "7",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    let names: Vec<&str> = out.lines().map(|l| l.split('\t').nth(7).unwrap()).collect();
+    assert_eq!(names, ["tx2", "tx3"]);
+
+    // Names are distinct per transcript, so nothing is merged on column 4
+    let output = run_grit(&[
+        "window",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-w",
+        "500",
+        "--dedup-b-name",
+        "-c",
+    ]);
+    assert_eq!(stdout(&output), "chr1\t1000\t2000\tpeak\t4\n");
+
+    // The two GENE2 transcripts tie 51bp downstream of this A
+    let a2 = create_bed_file("chr1\t2000\t2050\tq\n");
+    let a2 = a2.path().to_str().unwrap();
+    let output = run_grit(&[
+        "closest",
+        "-a",
+        a2,
+        "-b",
+        b,
+        "--streaming",
+        "--dedup-b-name",
+        "7",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t2000\t2050\tq\tchr1\t2100\t2200\ttx3\t0\t-\tGENE2\n"
+    );
+
+    // Column 7 is not kept by the in-memory path
+    let output = run_grit(&["closest", "-a", a2, "-b", b, "--dedup-b-name", "7"]);
+    assert!(!is_success(&output));
+
+    let output = run_grit(&["closest", "-a", a, "-b", b, "--dedup-b-name", "0"]);
+    assert!(!is_success(&output));
+}
+
+/// Test segment splits A at depth class changes
+#[test]
+fn test_segment() {
+    let a = create_bed_file("chr1\t0\t100\tt1\nchr2\t0\t50\tt2\n");
+    let b = create_bed_file(
+        "chr1\t10\t30\n\
+         chr1\t20\t40\n\
+         chr1\t20\t25\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["segment", "-a", a, "-b", b]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t10\tt1\t0\n\
+         chr1\t10\t20\tt1\t1\n\
+         chr1\t20\t30\tt1\t2+\n\
+         chr1\t30\t40\tt1\t1\n\
+         chr1\t40\t100\tt1\t0\n\
+         chr2\t0\t50\tt2\t0\n"
+    );
+
+    let output = run_grit(&["segment", "-a", a, "-b", b, "--breaks", "3"]);
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t20\tt1\t0-2\n\
+         chr1\t20\t25\tt1\t3+\n\
+         chr1\t25\t100\tt1\t0-2\n\
+         chr2\t0\t50\tt2\t0-2\n"
+    );
+
+    let output = run_grit(&["segment", "-a", a, "-b", b, "--breaks", "2,1"]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("strictly increasing"));
+}
+
+/// Test BedGraph B: value filtering and the value column in structured output
+#[test]
+fn test_intersect_bedgraph_b() {
+    let a = create_bed_file("chr1\t100\t200\tp1\nchr1\t300\t400\tp2\n");
+    let b = create_bed_file(
+        "track type=bedGraph\n\
+         chr1\t0\t150\t1.5\n\
+         chr1\t150\t350\t0.25\n\
+         chr1\t350\t500\t-1\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    for mode in [&[][..], &["--streaming"][..]] {
+        let mut args = vec![
+            "intersect",
+            "-a",
+            a,
+            "-b",
+            b,
+            "-wa",
+            "-wb",
+            "--min-b-value",
+            "0",
+        ];
+        args.extend_from_slice(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t200\tp1\tchr1\t0\t150\t1.5\n\
+             chr1\t100\t200\tp1\tchr1\t150\t350\t0.25\n\
+             chr1\t300\t400\tp2\tchr1\t150\t350\t0.25\n",
+            "mode {:?}",
+            mode
+        );
+    }
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-wa",
+        "-wb",
+        "--max-b-score=-0.5",
+        "--output-format",
+        "jsonl",
+    ]);
+    assert_eq!(
+        stdout(&output),
+        "{\"a\":{\"chrom\":\"chr1\",\"start\":300,\"end\":400,\"name\":\"p2\"},\
+         \"b\":{\"chrom\":\"chr1\",\"start\":350,\"end\":500,\"value\":-1},\
+         \"overlap_bp\":50,\"overlap_fraction\":0.5}\n"
+    );
+}
+
+/// Test multiinter --sparse output and --expand back to the dense layout
+#[test]
+fn test_multiinter_sparse() {
+    let a = create_bed_file("chr1\t100\t200\n");
+    let b = create_bed_file("chr1\t150\t250\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["multiinter", "-i", a, b, "--names", "x", "y", "--sparse"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let sparse = stdout(&output);
+    assert_eq!(
+        sparse,
+        "#grit-sparse\tx\ty\n\
+         chr1\t100\t150\t1\t1\n\
+         chr1\t150\t200\t2\t1,2\n\
+         chr1\t200\t250\t1\t2\n"
+    );
+
+    let sparse_file = create_bed_file(&sparse);
+    let output = run_grit(&[
+        "multiinter",
+        "--expand",
+        sparse_file.path().to_str().unwrap(),
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let dense = run_grit(&["multiinter", "-i", a, b, "--names", "x", "y"]);
+    assert_eq!(stdout(&output), stdout(&dense));
+}
+
+/// Test --max-output-lines / --max-output-bytes truncation
+#[test]
+fn test_max_output_limits() {
+    let a = create_bed_file("chr1\t0\t100\nchr1\t200\t300\nchr1\t400\t500\n");
+    let b = create_bed_file("chr1\t0\t1000\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["intersect", "-a", a, "-b", b, "--max-output-lines", "2"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "chr1\t0\t100\nchr1\t200\t300\n");
+    assert!(stderr(&output).contains("--max-output-lines 2"));
+
+    // 13 bytes per line: the second line would cross the limit
+    let output = run_grit(&["sort", "-i", a, "--max-output-bytes", "20"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "chr1\t0\t100\n");
+
+    let output = run_grit(&["merge", "-i", a, "--max-output-lines", "3"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output).lines().count(), 3);
+}
+
+/// Test that --io-threads read-ahead does not change streaming output
+#[test]
+fn test_io_threads_read_ahead() {
+    // Large enough (over 1 MB) to be read ahead
+    let a: String = (0..60_000)
+        .map(|i| format!("chr1\t{}\t{}\n", i * 10, i * 10 + 15))
+        .collect();
+    let a = create_bed_file(&a);
+    let b = create_bed_file("chr1\t100\t5000\nchr1\t300000\t300100\n");
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let inline = run_grit(&[
+        "--io-threads",
+        "0",
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "--streaming",
+    ]);
+    assert!(is_success(&inline), "stderr: {}", stderr(&inline));
+    assert_eq!(stdout(&inline).lines().count(), 502);
+    for threads in ["1", "2"] {
+        let output = run_grit(&[
+            "intersect",
+            "-a",
+            a,
+            "-b",
+            b,
+            "--streaming",
+            "--io-threads",
+            threads,
+        ]);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(stdout(&output), stdout(&inline));
+    }
+}
+
+/// Test delta between two BedGraph tracks
+#[test]
+fn test_delta() {
+    let t = create_bed_file("chr1\t0\t100\t10\nchr1\t100\t150\t2\nchr1\t150\t200\t10\n");
+    let c = create_bed_file("chr1\t0\t200\t2\nchr2\t0\t80\t9\n");
+    let (t, c) = (t.path().to_str().unwrap(), c.path().to_str().unwrap());
+
+    let output = run_grit(&["delta", "-a", t, "-b", c, "--min-diff", "5"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t100\tup\t10.000000\t2.000000\t1.874469\n\
+         chr1\t150\t200\tup\t10.000000\t2.000000\t1.874469\n\
+         chr2\t0\t80\tdown\t0.000000\t9.000000\t-3.321928\n"
+    );
+
+    let output = run_grit(&[
+        "delta",
+        "-a",
+        t,
+        "-b",
+        c,
+        "--min-fold",
+        "3",
+        "-d",
+        "50",
+        "--min-length",
+        "100",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t100\tup\t10.000000\t2.000000\t1.874469\n"
+    );
+}
+
+/// Test coverage --diversity over annotation classes
+#[test]
+fn test_coverage_diversity() {
+    let a = create_bed_file("chr1\t100\t200\tpeak1\nchr1\t300\t400\tpeak2\n");
+    let b = create_bed_file(
+        "chr1\t100\t150\tx\tpromoter\n\
+         chr1\t150\t200\ty\tenhancer\n\
+         chr1\t300\t400\tz\tenhancer\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&["coverage", "-a", a, "-b", b, "--diversity", "5"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\tpeak1\t2\t0.693147\t1.000000\n\
+         chr1\t300\t400\tpeak2\t1\t0.000000\t.\n"
+    );
+
+    // By name every B record is its own class
+    let output = run_grit(&["coverage", "-a", a, "-b", b, "--diversity"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(stdout(&output).starts_with("chr1\t100\t200\tpeak1\t2\t"));
+}
+
+/// Test --dedup-b collapsing repeated B lines
+#[test]
+fn test_dedup_b() {
+    let a = create_bed_file("chr1\t100\t200\ta1\n");
+    let b = create_bed_file(
+        "chr1\t150\t160\tg\n\
+         chr1\t150\t170\th\n\
+         chr1\t150\t160\tg\n",
+    );
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "--streaming",
+        "-wa",
+        "-wb",
+        "--dedup-b",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\ta1\tchr1\t150\t160\tg\n\
+         chr1\t100\t200\ta1\tchr1\t150\t170\th\n"
+    );
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "--streaming",
+        "-c",
+        "--dedup-b",
+    ]);
+    assert_eq!(stdout(&output), "chr1\t100\t200\ta1\t2\n");
+
+    let output = run_grit(&[
+        "closest",
+        "-a",
+        a,
+        "-b",
+        b,
+        "--streaming",
+        "--dedup-b",
+        "--dedup-b-count",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\ta1\tchr1\t150\t160\tg\t2\n\
+         chr1\t100\t200\ta1\tchr1\t150\t170\th\t1\n"
+    );
+
+    // Deduplication happens in the streaming B reader
+    let output = run_grit(&["intersect", "-a", a, "-b", b, "--dedup-b"]);
+    assert!(!is_success(&output));
+}
+
+/// Test --of/--if binary pipes and grit cat
 #[test]
-fn test_merge_strand_same() {
-    let bed = create_bed_file("chr1\t100\t200\t.\t.\t+\nchr1\t150\t250\t.\t.\t+\n");
+fn test_binary_interchange() {
+    let input = create_bed_file("chr2\t5\t10\tb\nchr1\t100\t200\ta\t1\t+\nchr1\t150\t300\n");
+    let path = input.path().to_str().unwrap();
+
+    let output = run_grit(&["--of", "binary", "sort", "-i", path]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert!(output.stdout.starts_with(b"GRITBIN\x01"));
+    let binary = create_bed_file("");
+    let binary_path = binary.path().to_str().unwrap();
+    std::fs::write(binary_path, &output.stdout).unwrap();
+
+    let output = run_grit(&["cat", binary_path, path]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t200\ta\t1\t+\nchr1\t150\t300\nchr2\t5\t10\tb\n\
+         chr2\t5\t10\tb\nchr1\t100\t200\ta\t1\t+\nchr1\t150\t300\n"
+    );
 
-    let output = run_grit(&["merge", "-i", bed.path().to_str().unwrap(), "-s"]);
+    let binary_bytes = std::fs::read(binary_path).unwrap();
+    let output = run_grit_with_stdin(&["--if", "binary", "merge"], &binary_bytes);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t300\nchr2\t5\t10\n");
 
-    assert!(is_success(&output));
-    let result = stdout(&output);
-    let lines: Vec<_> = result.lines().collect();
+    // Text on stdin is rejected when binary is expected
+    let output = run_grit_with_stdin(&["--if", "binary", "merge"], "chr1\t1\t2\n");
+    assert!(!is_success(&output));
+    let err = stderr(&output);
+    assert!(
+        err.contains("invalid binary stream on stdin"),
+        "stderr: {}",
+        err
+    );
+    assert!(err.contains("not a grit binary stream"));
+    assert!(!err.contains("not sorted"), "stderr: {}", err);
+
+    // A stream cut off mid-frame is reported as truncated, not unsorted
+    let output = run_grit_with_stdin(
+        &["--if", "binary", "merge"],
+        &binary_bytes[..binary_bytes.len() - 3],
+    );
+    assert!(!is_success(&output));
+    let err = stderr(&output);
+    assert!(err.contains("truncated binary frame"), "stderr: {}", err);
+    assert!(!err.contains("Pre-sort"), "stderr: {}", err);
+}
+
+/// Test coverage with several A files sharing one pass over B
+#[test]
+fn test_coverage_multiple_a_files() {
+    let a1 = create_bed_file("chr1\t100\t200\tp1\nchr2\t0\t50\tp2\n");
+    let a2 = create_bed_file("chr1\t50\t150\te1\nchr3\t0\t10\te3\n");
+    let b = create_bed_file("chr1\t90\t160\nchr1\t120\t130\nchr2\t10\t20\n");
+    let (a1, a2, b) = (
+        a1.path().to_str().unwrap(),
+        a2.path().to_str().unwrap(),
+        b.path().to_str().unwrap(),
+    );
+    let out_dir = tempfile::tempdir().unwrap();
+    let dir = out_dir.path().to_str().unwrap();
+
+    let output = run_grit(&["coverage", "-a", a1, a2, "-b", b, "--output-dir", dir]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    for a in [a1, a2] {
+        let name = std::path::Path::new(a).file_name().unwrap();
+        let written = std::fs::read_to_string(out_dir.path().join(name)).unwrap();
+        let single = run_grit(&["coverage", "-a", a, "-b", b]);
+        assert_eq!(written, stdout(&single));
+    }
+
+    // Results cannot all go to stdout
+    let output = run_grit(&["coverage", "-a", a1, "-a", a2, "-b", b]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("--output-dir"));
+}
+
+/// Test cut column selection and BED validation
+#[test]
+fn test_cut() {
+    let input =
+        create_bed_file("chr2\t100\t900\tB\t0\t-\t150\t800\nchr1\t500\t1500\tA\t0\t+\t700\t1200\n");
+    let path = input.path().to_str().unwrap();
+
+    let output = run_grit(&["cut", "-i", path, "-f", "1-3,strand,name"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
     assert_eq!(
-        lines.len(),
-        1,
-        "Same strand intervals should merge: {}",
-        result
+        stdout(&output),
+        "chr2\t100\t900\t-\tB\nchr1\t500\t1500\t+\tA\n"
+    );
+
+    let output = run_grit(&[
+        "cut",
+        "-i",
+        path,
+        "-f",
+        "chrom,thickStart,thickEnd",
+        "--sort",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t700\t1200\nchr2\t150\t800\n");
+
+    let output = run_grit_with_stdin(&["cut", "-i", "-", "-f", "1,4,3"], "chr1\t1\t2\tx\n");
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("not a coordinate"));
+
+    // Headers pass through; records on dropped chromosomes do not
+    let output = run_grit_with_stdin(
+        &["--drop-chroms", "chrM", "cut", "-i", "-", "-f", "1,2,3"],
+        "track name=x\nchr1\t1\t2\tx\nchrM\t5\t9\ty\n",
     );
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "track name=x\nchr1\t1\t2\n");
 }
 
-/// Test -s (strand) flag - different strands don't merge
+/// Test fix-strand normalization and inference from genes
 #[test]
-fn test_merge_strand_different() {
-    let bed = create_bed_file("chr1\t100\t200\t.\t.\t+\nchr1\t150\t250\t.\t.\t-\n");
+fn test_fix_strand() {
+    let input =
+        create_bed_file("chr1\t10\t20\ta\t0\t-1\nchr1\t30\t40\nchr1\t50\t60\tb\t0\tsense\n");
+    let genes = create_bed_file("chr1\t0\t100\tg\t0\t+\n");
+    let path = input.path().to_str().unwrap();
 
-    let output = run_grit(&["merge", "-i", bed.path().to_str().unwrap(), "-s"]);
+    let output = run_grit(&[
+        "fix-strand",
+        "-i",
+        path,
+        "--genes",
+        genes.path().to_str().unwrap(),
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t10\t20\ta\t0\t-\nchr1\t30\t40\t.\t0\t+\nchr1\t50\t60\tb\t0\t+\n"
+    );
+    assert!(stderr(&output).contains("Repaired: 3"));
 
-    assert!(is_success(&output));
-    let result = stdout(&output);
-    let lines: Vec<_> = result.lines().collect();
+    let output = run_grit(&["fix-strand", "-i", path, "--strict"]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("unrecognized strand 'sense'"));
+
+    // Records on dropped chromosomes are neither written nor counted
+    let output = run_grit_with_stdin(
+        &["--drop-chroms", "chrM", "fix-strand", "-i", "-"],
+        "#header\nchr1\t10\t20\ta\t0\t+\nchrM\t0\t5\tm\t0\t-1\nchr2\t1\t2\n",
+    );
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
     assert_eq!(
-        lines.len(),
-        2,
-        "Different strand intervals should not merge: {}",
-        result
+        stdout(&output),
+        "#header\nchr1\t10\t20\ta\t0\t+\nchr2\t1\t2\n"
+    );
+    assert!(stderr(&output).contains("Records: 2"));
+}
+
+/// Test convert between BED, GFF3, SAF and IGV loci
+#[test]
+fn test_convert_formats() {
+    let input = create_bed_file("chr1\t99\t200\tpeak1\t7.5\t+\nchr2\t0\t50\n");
+    let path = input.path().to_str().unwrap();
+
+    let output = run_grit(&["convert", "-i", path, "--to", "saf"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "GeneID\tChr\tStart\tEnd\tStrand\npeak1\tchr1\t100\t200\t+\nchr2:1-50\tchr2\t1\t50\t.\n"
+    );
+
+    let output = run_grit(&["convert", "-i", path, "--to", "igv"]);
+    assert_eq!(stdout(&output), "chr1:100-200\nchr2:1-50\n");
+
+    // GFF3 round trip keeps name, score and strand
+    let gff = run_grit(&["convert", "-i", path, "--to", "gff"]);
+    assert!(is_success(&gff), "stderr: {}", stderr(&gff));
+    let output = run_grit_with_stdin(
+        &["convert", "-i", "-", "--from", "gff", "--to", "bed"],
+        &gff.stdout,
+    );
+    assert_eq!(
+        stdout(&output),
+        "chr1\t99\t200\tpeak1\t7.5\t+\nchr2\t0\t50\n"
+    );
+
+    let empty = create_bed_file("chr1\t10\t10\n");
+    let output = run_grit(&[
+        "convert",
+        "-i",
+        empty.path().to_str().unwrap(),
+        "--to",
+        "saf",
+    ]);
+    assert!(!is_success(&output));
+}
+
+/// Test enrich against coverage fractions and shuffles
+#[test]
+fn test_enrich() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t1000\t1100\nchr2\t300\t400\n");
+    let b = create_bed_file("chr1\t0\t2000\n");
+    let genome = create_genome_file("chr1\t10000\nchr2\t10000\n");
+    let (a, b, genome) = (
+        a.path().to_str().unwrap(),
+        b.path().to_str().unwrap(),
+        genome.path().to_str().unwrap(),
+    );
+
+    let output = run_grit(&["enrich", "-a", a, "-b", b, "-g", genome]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let out = stdout(&output);
+    let row: Vec<&str> = out.lines().nth(1).unwrap().split('\t').collect();
+    assert_eq!(row[0], "records");
+    assert_eq!(row[1], "2");
+    // Each 100 bp record touches [0, 2000) from 2000 of 20000 starts
+    assert_eq!(row[2], "0.3");
+    assert_eq!(row[5], "NA");
+
+    let args = [
+        "enrich",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-g",
+        genome,
+        "--shuffles",
+        "200",
+        "--seed",
+        "3",
+    ];
+    let first = run_grit(&args);
+    assert!(is_success(&first), "stderr: {}", stderr(&first));
+    assert_eq!(stdout(&first), stdout(&run_grit(&args)));
+    let out = stdout(&first);
+    let row: Vec<&str> = out.lines().nth(1).unwrap().split('\t').collect();
+    assert!(row[5].parse::<f64>().unwrap() < 0.2);
+    assert_eq!(row[6], "200");
+
+    let output = run_grit(&["enrich", "-a", a, "-b", b, "-g", genome, "--exclude", b]);
+    assert!(!is_success(&output));
+}
+
+/// Test genomecov --progress and --verbose reports on stderr
+#[test]
+fn test_genomecov_progress() {
+    let input = create_bed_file("chr1\t10\t500\nchr2\t5\t50\n");
+    let genome = create_genome_file("chr1\t1000\nchr2\t100\n");
+    let (input, genome) = (
+        input.path().to_str().unwrap(),
+        genome.path().to_str().unwrap(),
+    );
+
+    for streaming in [false, true] {
+        let mut args = vec!["genomecov", "-i", input, "-g", genome, "--bg", "--verbose"];
+        if streaming {
+            args.push("--streaming");
+        }
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(stdout(&output), "chr1\t10\t500\t1\nchr2\t5\t50\t1\n");
+        let report = stderr(&output);
+        assert!(
+            report.contains("genomecov: chr1 done (1000 bp, "),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains("genomecov: chr2 done (100 bp, "),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains("genomecov: 100.0% of 1100 bp in "),
+            "{}",
+            report
+        );
+    }
+}
+
+/// Test genomecov --normalize percentile/zscore BedGraph output
+#[test]
+fn test_genomecov_normalize() {
+    let input = create_bed_file("chr1\t10\t20\nchr1\t20\t30\nchr1\t25\t30\n");
+    let genome = create_genome_file("chr1\t100\n");
+    let args = |extra: &[&'static str]| {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            input.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra);
+        args
+    };
+
+    let output = run_grit(&args(&["--normalize", "percentile"]));
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t10\t25\t95\nchr1\t25\t30\t100\n");
+
+    let output = run_grit(&args(&["--normalize", "zscore", "--bga", "--streaming"]));
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let values: Vec<f64> = stdout(&output)
+        .lines()
+        .map(|l| l.rsplit('\t').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(values.len(), 4);
+    assert!(values[0] < 0.0 && values[2] > values[1] && values[1] > 0.0);
+
+    let output = run_grit(&args(&["--normalize", "zscore", "--scale", "2"]));
+    assert!(!is_success(&output));
+}
+
+/// Test --collapse-by-name for coverage and genomecov
+#[test]
+fn test_collapse_by_name() {
+    let reads =
+        create_bed_file("chr1\t100\t150\tpair1\nchr1\t120\t180\tpair1\nchr1\t130\t140\tsingle\n");
+    let a = create_bed_file("chr1\t100\t200\n");
+    let genome = create_genome_file("chr1\t1000\n");
+
+    let output = run_grit(&[
+        "coverage",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        reads.path().to_str().unwrap(),
+        "--collapse-by-name",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t200\t2\t80\t100\t0.8000000\n");
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            reads.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+            "--bg",
+            "--collapse-by-name",
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t130\t1\nchr1\t130\t140\t2\nchr1\t140\t180\t1\n"
+        );
+    }
+}
+
+/// Test genomecov --regions restricts output to targets and writes a summary
+#[test]
+fn test_genomecov_regions() {
+    let reads = create_bed_file("chr1\t100\t200\nchr1\t150\t250\nchr2\t0\t50\n");
+    let targets = create_bed_file("chr1\t180\t300\nchr1\t400\t420\nchr3\t10\t20\n");
+    let genome = create_genome_file("chr1\t1000\nchr2\t500\nchr3\t300\n");
+    let dir = tempfile::tempdir().unwrap();
+    let summary = dir.path().join("summary.tsv");
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            reads.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+            "--bga",
+            "--regions",
+            targets.path().to_str().unwrap(),
+            "--regions-summary",
+            summary.to_str().unwrap(),
+            "--thresholds",
+            "1,2",
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t180\t200\t2\nchr1\t200\t250\t1\nchr1\t250\t300\t0\n\
+             chr1\t400\t420\t0\nchr3\t10\t20\t0\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&summary).unwrap(),
+            "#chrom\ttarget_bases\tmean_depth\tpct_ge_1x\tpct_ge_2x\n\
+             chr1\t140\t0.64\t50.00\t14.29\n\
+             chr3\t10\t0.00\t0.00\t0.00\n\
+             total\t150\t0.60\t46.67\t13.33\n"
+        );
+    }
+
+    // The summary needs --regions
+    let output = run_grit(&[
+        "genomecov",
+        "-i",
+        reads.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "--regions-summary",
+        summary.to_str().unwrap(),
+    ]);
+    assert!(!is_success(&output));
+}
+
+/// Test genomecov histogram rows rolled up into chromosome classes
+#[test]
+fn test_genomecov_chrom_classes() {
+    let reads = create_bed_file("chr1\t0\t100\nchr1\t50\t200\nchrM\t0\t100\nchrUn_1\t0\t10\n");
+    let genome = create_genome_file("chr1\t1000\nchr2\t500\nchrM\t100\nchrUn_1\t50\nchrUn_2\t50\n");
+    let classes = create_bed_file("# class\tpatterns\nautosomes\tchr1-22\nmito\tchrM\n");
+
+    for mode in [None, Some("--streaming")] {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            reads.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+            "--chrom-classes",
+            classes.path().to_str().unwrap(),
+        ];
+        args.extend(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "autosomes\t0\t1300\t1500\t0.866667\nautosomes\t1\t150\t1500\t0.1\n\
+             autosomes\t2\t50\t1500\t0.0333333\nmito\t1\t100\t100\t1\n\
+             other\t0\t90\t100\t0.9\nother\t1\t10\t100\t0.1\n\
+             genome\t0\t1390\t1700\t0.817647\ngenome\t1\t260\t1700\t0.152941\n\
+             genome\t2\t50\t1700\t0.0294118\n"
+        );
+    }
+
+    // Classes only apply to histogram output
+    let output = run_grit(&[
+        "genomecov",
+        "-i",
+        reads.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "--bg",
+        "--chrom-classes",
+        classes.path().to_str().unwrap(),
+    ]);
+    assert!(!is_success(&output));
+}
+
+/// Test stab point and region queries, with and without a sidecar index
+#[test]
+fn test_stab_queries() {
+    let input = create_bed_file(
+        "chr1\t0\t5000\tlong\nchr1\t100\t200\ta\nchr1\t150\t160\tb\nchr1\t900\t950\tc\nchr2\t10\t20\td\n",
+    );
+    let path = input.path().to_str().unwrap();
+
+    let output = run_grit(&["stab", "-i", path, "chr1:151", "chr2:1-10", "chr3:5"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t0\t5000\tlong\nchr1\t100\t200\ta\nchr1\t150\t160\tb\n"
+    );
+
+    let output = run_grit(&["stab", "-i", path, "--build-index"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    let index = format!("{}.gri", path);
+    assert!(std::fs::read_to_string(&index)
+        .unwrap()
+        .starts_with("#grit-index\t2\n"));
+
+    let output = run_grit(&["stab", "-i", path, "chr1:901-5,000"]);
+    assert_eq!(stdout(&output), "chr1\t0\t5000\tlong\nchr1\t900\t950\tc\n");
+
+    let output = run_grit(&["stab", "-i", path, "chr1:0"]);
+    assert!(!is_success(&output));
+    std::fs::remove_file(index).unwrap();
+}
+
+/// Test that version 1 indexes are still used and upgrade in place
+#[test]
+fn test_index_upgrade() {
+    let input = create_bed_file("chr1\t100\t200\ta\nchr2\t10\t20\tb\n");
+    let path = input.path().to_str().unwrap();
+    let meta = std::fs::metadata(path).unwrap();
+    let mtime = meta
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let index = format!("{}.gri", path);
+    std::fs::write(
+        &index,
+        format!(
+            "#grit-index\t1\n#source\t{}\t{}\nchr1\t0\t14\t100\t1\nchr2\t15\t28\t10\t1\n",
+            meta.len(),
+            mtime
+        ),
+    )
+    .unwrap();
+
+    let output = run_grit(&["stab", "-i", path, "chr2:15"]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr2\t10\t20\tb\n");
+
+    let output = run_grit(&["index", "upgrade", "--dry-run", &index]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\twould upgrade version 1 to 2\n", index)
+    );
+
+    let output = run_grit(&["index", "upgrade", path]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\tupgraded version 1 to 2\n", index)
     );
+    let text = std::fs::read_to_string(&index).unwrap();
+    assert!(text.starts_with("#grit-index\t2\n"));
+    assert!(text.ends_with("chr1\t0\t14\t100\t1\nchr2\t15\t28\t10\t1\n"));
+
+    let output = run_grit(&["index", "upgrade", path, "missing.bed"]);
+    assert!(!is_success(&output));
+    assert_eq!(stdout(&output), format!("{}\tcurrent (version 2)\n", index));
+    assert!(stderr(&output).contains("missing.bed.gri"));
+
+    // Indexes from a newer grit are refused rather than misread
+    std::fs::write(&index, text.replace("#grit-index\t2", "#grit-index\t9")).unwrap();
+    let output = run_grit(&["stab", "-i", path, "chr2:15"]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("written by a newer grit"));
+    std::fs::remove_file(index).unwrap();
 }
 
 /// Test -s with -d (strand + distance)
@@ -646,6 +2792,43 @@ fn test_intersect_wa_wb() {
     );
 }
 
+/// Test --invert-b intersects A with the complement of B
+#[test]
+fn test_intersect_invert_b() {
+    let a = create_bed_file("chr1\t100\t500\tgeneA\nchr2\t0\t100\tgeneB\n");
+    let b = create_bed_file("chr1\t150\t200\nchr1\t300\t600\n");
+    let genome = create_genome_file("chr1\t1000\nchr2\t500\n");
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "--invert-b",
+    ]);
+
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "chr1\t100\t150\tgeneA\nchr1\t200\t300\tgeneA\nchr2\t0\t100\tgeneB\n"
+    );
+
+    // Without a genome there is no complement to compute
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "--invert-b",
+    ]);
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("requires a genome file"));
+}
+
 // =============================================================================
 // SUBTRACT: -f (fraction) and -r (reciprocal) tests
 // =============================================================================
@@ -706,6 +2889,92 @@ fn test_subtract_fraction() {
     assert!(!result.trim().is_empty(), "Should output A: {}", result);
 }
 
+/// Test subtract --keep-suppressed in both modes
+#[test]
+fn test_subtract_keep_suppressed() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t300\t400\nchr1\t500\t600\n");
+    let b = create_bed_file("chr1\t90\t210\nchr1\t350\t360\n");
+
+    for mode in [&["--streaming"][..], &[][..]] {
+        let removed = NamedTempFile::new().unwrap();
+        let mut args = vec![
+            "subtract",
+            "-a",
+            a.path().to_str().unwrap(),
+            "-b",
+            b.path().to_str().unwrap(),
+            "--keep-suppressed",
+            removed.path().to_str().unwrap(),
+        ];
+        args.extend_from_slice(mode);
+        let output = run_grit(&args);
+
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert!(!stdout(&output).contains("100\t200"));
+        let suppressed = std::fs::read_to_string(removed.path()).unwrap();
+        assert_eq!(suppressed, "chr1\t100\t200\n", "mode {:?}", mode);
+    }
+}
+
+/// Test intersect and subtract --clip-to-genome
+#[test]
+fn test_clip_to_genome() {
+    let a = create_bed_file("chr1\t900\t1200\ta1\nchr1\t1100\t1300\ta2\nchr2\t10\t50\ta3\n");
+    let b = create_bed_file("chr1\t950\t1000\nchr2\t0\t20\n");
+    let genome = create_genome_file("chr1\t1000\nchr2\t500\n");
+    let (a, b, g) = (
+        a.path().to_str().unwrap(),
+        b.path().to_str().unwrap(),
+        genome.path().to_str().unwrap(),
+    );
+
+    let output = run_grit(&[
+        "intersect",
+        "-a",
+        a,
+        "-b",
+        b,
+        "-g",
+        g,
+        "-u",
+        "--clip-to-genome",
+    ]);
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t900\t1000\ta1\nchr2\t10\t50\ta3\n");
+    assert!(stderr(&output).contains("Clipped A to genome: 1 clipped, 1 dropped of 3 records"));
+
+    for mode in [&["--streaming"][..], &[][..]] {
+        let mut args = vec!["subtract", "-a", a, "-b", b, "-g", g, "--clip-to-genome"];
+        args.extend_from_slice(mode);
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t900\t950\ta1\nchr2\t20\t50\ta3\n",
+            "mode {:?}",
+            mode
+        );
+    }
+
+    // The chromosome sizes come from -g
+    let output = run_grit(&["subtract", "-a", a, "-b", b, "--clip-to-genome"]);
+    assert!(!is_success(&output));
+
+    // Errors name the user's file, not its clipped copy
+    let unsorted = create_bed_file("chr1\t500\t600\nchr1\t100\t2000\n");
+    let unsorted = unsorted.path().to_str().unwrap();
+    for cmd in ["intersect", "subtract"] {
+        let output = run_grit(&[cmd, "-a", unsorted, "-b", b, "-g", g, "--clip-to-genome"]);
+        assert!(!is_success(&output));
+        assert!(
+            stderr(&output).contains(&format!("grit sort -i {} -g", unsorted)),
+            "{}: {}",
+            cmd,
+            stderr(&output)
+        );
+    }
+}
+
 // =============================================================================
 // WINDOW: genome validation and window size tests
 // =============================================================================
@@ -770,6 +3039,30 @@ fn test_window_asymmetric() {
     );
 }
 
+/// Test --from-b applies -l/-r around B
+#[test]
+fn test_window_from_b() {
+    let a = create_bed_file("chr1\t100\t200\nchr1\t800\t900\n");
+    let b = create_bed_file("chr1\t500\t600\n");
+
+    // 500 bp upstream of B reaches the first A only
+    let output = run_grit(&[
+        "window",
+        "-a",
+        a.path().to_str().unwrap(),
+        "-b",
+        b.path().to_str().unwrap(),
+        "-l",
+        "500",
+        "-r",
+        "0",
+        "--from-b",
+    ]);
+
+    assert!(is_success(&output));
+    assert_eq!(stdout(&output), "chr1\t100\t200\tchr1\t500\t600\n");
+}
+
 /// Test -g (genome) validation for window
 #[test]
 fn test_window_genome_validation() {
@@ -953,6 +3246,33 @@ fn test_slop_strand_aware() {
     );
 }
 
+/// Test --rejects reports clamped and dropped records
+#[test]
+fn test_slop_rejects() {
+    let bed = create_bed_file("chr1\t20\t100\nchr1\t400\t500\nchrUn\t0\t10\n");
+    let genome = create_genome_file("chr1\t1000\n");
+    let rejects = NamedTempFile::new().unwrap();
+
+    let output = run_grit(&[
+        "slop",
+        "-i",
+        bed.path().to_str().unwrap(),
+        "-g",
+        genome.path().to_str().unwrap(),
+        "-b",
+        "50",
+        "--rejects",
+        rejects.path().to_str().unwrap(),
+    ]);
+
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), "chr1\t0\t150\nchr1\t350\t550\n");
+    assert_eq!(
+        std::fs::read_to_string(rejects.path()).unwrap(),
+        "chr1\t20\t100\tclamped_start\nchrUn\t0\t10\tunknown_chrom\n"
+    );
+}
+
 // =============================================================================
 // STDIN input tests
 // =============================================================================
@@ -1166,6 +3486,39 @@ fn test_genomecov_bedgraph_all() {
     );
 }
 
+/// Test --scale with --int (rounded, coalesced bedgraph) in both modes
+#[test]
+fn test_genomecov_scale_int() {
+    let bed = create_bed_file("chr1\t100\t200\nchr1\t200\t300\nchr1\t250\t300\n");
+    let genome = create_genome_file("chr1\t1000\n");
+
+    for extra in [&[][..], &["--streaming"][..]] {
+        let mut args = vec![
+            "genomecov",
+            "-i",
+            bed.path().to_str().unwrap(),
+            "-g",
+            genome.path().to_str().unwrap(),
+            "--bg",
+            "--scale",
+            "0.6",
+        ];
+        args.extend_from_slice(extra);
+
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t250\t0.6\nchr1\t250\t300\t1.2\n"
+        );
+
+        args.push("--int");
+        let output = run_grit(&args);
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(stdout(&output), "chr1\t100\t300\t1\n");
+    }
+}
+
 // =============================================================================
 // JACCARD: tests
 // =============================================================================
@@ -1271,3 +3624,60 @@ fn test_multiinter_cluster() {
         result
     );
 }
+
+/// Test that multiinter merge rounds write their run files under --temp-dir
+#[test]
+fn test_multiinter_merge_rounds_temp_dir() {
+    let files: Vec<_> = (0..5)
+        .map(|i| create_bed_file(&format!("chr1\t{}\t{}\n", i * 10, i * 10 + 25)))
+        .collect();
+    let paths: Vec<&str> = files.iter().map(|f| f.path().to_str().unwrap()).collect();
+    let run = |temp_dir: Option<&str>| {
+        let mut args = match temp_dir {
+            Some(dir) => vec!["--temp-dir", dir, "multiinter", "--max-open-files", "3"],
+            None => vec!["multiinter"],
+        };
+        args.extend_from_slice(&["--streaming", "-i"]);
+        args.extend_from_slice(&paths);
+        run_grit(&args)
+    };
+
+    let expected = run(None);
+    assert!(is_success(&expected), "stderr: {}", stderr(&expected));
+
+    let temp = tempfile::tempdir().unwrap();
+    let output = run(Some(temp.path().to_str().unwrap()));
+    assert!(is_success(&output), "stderr: {}", stderr(&output));
+    assert_eq!(stdout(&output), stdout(&expected));
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+
+    // The rounds fail when the scratch directory cannot be created there
+    let missing = temp.path().join("missing");
+    let output = run(Some(missing.to_str().unwrap()));
+    assert!(!is_success(&output));
+    assert!(stderr(&output).contains("cannot create scratch directory"));
+}
+
+/// Test multiinter --manifest resolves paths and reports labels
+#[test]
+fn test_multiinter_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.bed"), "chr1\t100\t200\n").unwrap();
+    std::fs::write(dir.path().join("b.bed"), "chr1\t150\t250\n").unwrap();
+    let manifest = dir.path().join("inputs.tsv");
+    std::fs::write(&manifest, "rep1\ta.bed\nrep2\tb.bed\n").unwrap();
+
+    for mode in [&["--streaming"][..], &[][..]] {
+        let mut args = vec!["multiinter", "--manifest", manifest.to_str().unwrap()];
+        args.extend_from_slice(mode);
+        let output = run_grit(&args);
+
+        assert!(is_success(&output), "stderr: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            "chr1\t100\t150\t1\trep1\t1\t0\n\
+             chr1\t150\t200\t2\trep1,rep2\t1\t1\n\
+             chr1\t200\t250\t1\trep2\t0\t1\n"
+        );
+    }
+}
@@ -1052,3 +1052,70 @@ fn test_matches_gnu_sort_large() {
     std::fs::remove_file(gnu_output).ok();
     std::fs::remove_file(grit_output).ok();
 }
+
+/// Stress test with 1M contigs (pan-genome / metagenome scale), more than a
+/// 16-bit chromosome index can hold, with long contig names.
+#[test]
+fn test_sort_1m_contigs() {
+    let input_path = "/tmp/grit_test_sort_1m_contigs.bed";
+    let genome_path = "/tmp/grit_test_sort_1m_contigs.genome";
+    let gnu_output = "/tmp/grit_test_sort_1m_contigs_gnu.bed";
+    let grit_output = "/tmp/grit_test_sort_1m_contigs_grit.bed";
+    let n: u64 = 1_000_000;
+    let name = |i: u64| format!("metagenome_bin{:03}_scaffold_contig_{:07}", i % 997, i);
+
+    // Visit contigs in a scrambled order (multiplier coprime to n)
+    let mut file = std::io::BufWriter::new(File::create(input_path).unwrap());
+    let mut genome = std::io::BufWriter::new(File::create(genome_path).unwrap());
+    for k in 0..n {
+        let i = (k * 7_919) % n;
+        writeln!(file, "{}\t{}\t{}", name(i), k % 5_000, k % 5_000 + 100).unwrap();
+        writeln!(genome, "{}\t1000000", name((k * 104_729) % n)).unwrap();
+    }
+    drop(file);
+    drop(genome);
+
+    let gnu_status = Command::new("sort")
+        .env("LC_ALL", "C")
+        .args(["-k1,1", "-k2,2n", "-k3,3n", input_path])
+        .stdout(File::create(gnu_output).unwrap())
+        .status()
+        .expect("Failed to run GNU sort");
+    assert!(gnu_status.success(), "GNU sort failed");
+
+    let grit_status = Command::new("./target/release/grit")
+        .args(["sort", "-i", input_path])
+        .stdout(File::create(grit_output).unwrap())
+        .status()
+        .expect("Failed to run grit");
+    assert!(grit_status.success(), "grit sort failed");
+    assert!(
+        read_lines(gnu_output) == read_lines(grit_output),
+        "grit sort differs from GNU sort with 1M contigs"
+    );
+
+    // Genome order: contigs come out in the order of the genome file
+    let grit_status = Command::new("./target/release/grit")
+        .args(["sort", "-i", input_path, "-g", genome_path])
+        .stdout(File::create(grit_output).unwrap())
+        .status()
+        .expect("Failed to run grit");
+    assert!(grit_status.success(), "grit sort -g failed");
+    let chroms: Vec<String> = read_lines(grit_output)
+        .iter()
+        .map(|l| l.split('\t').next().unwrap().to_string())
+        .collect();
+    let expected: Vec<String> = read_lines(genome_path)
+        .iter()
+        .map(|l| l.split('\t').next().unwrap().to_string())
+        .collect();
+    assert!(
+        chroms == expected,
+        "grit sort -g ignores genome order with 1M contigs"
+    );
+
+    std::fs::remove_file(input_path).ok();
+    std::fs::remove_file(genome_path).ok();
+    std::fs::remove_file(gnu_output).ok();
+    std::fs::remove_file(grit_output).ok();
+}
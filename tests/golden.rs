@@ -0,0 +1,585 @@
+//! Golden output tests across the whole CLI.
+//!
+//! Small canonical datasets are generated into a scratch directory, each
+//! command is run over every combination of a set of flag alternatives, and
+//! the combined output is compared byte for byte with the snapshots in
+//! `tests/golden/<command>.snap`. Refactors that must not change output, such
+//! as moving commands onto a shared sweep engine, can be checked against the
+//! full flag surface in one run.
+//!
+//! After an intended output change, regenerate the snapshots and review the
+//! diff:
+//!
+//! ```text
+//! GRIT_UPDATE_GOLDEN=1 cargo test --test golden
+//! ```
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Chromosomes of the generated genome, in sorted order.
+const CHROMS: [(&str, u64); 3] = [("chr1", 20_000), ("chr2", 15_000), ("chrX", 5_000)];
+
+// =============================================================================
+// Dataset generation
+// =============================================================================
+
+/// xorshift64* generator, so datasets never change with a dependency bump.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Random sorted BED6 records (BED3 if `bed6` is false) plus `extra` lines.
+fn random_bed(rng: &mut Rng, prefix: &str, n: usize, bed6: bool, extra: &[&str]) -> String {
+    let mut records: Vec<(usize, u64, u64, String)> = Vec::new();
+    for i in 0..n {
+        let chrom = rng.below(CHROMS.len() as u64) as usize;
+        let size = CHROMS[chrom].1;
+        let len = 1 + rng.below(600);
+        let start = rng.below(size - len);
+        let rest = if bed6 {
+            let strand = if rng.below(2) == 0 { '+' } else { '-' };
+            format!("\t{}{}\t{}\t{}", prefix, i, rng.below(1000), strand)
+        } else {
+            String::new()
+        };
+        records.push((chrom, start, start + len, rest));
+    }
+    for line in extra {
+        let f: Vec<&str> = line.splitn(4, '\t').collect();
+        let chrom = CHROMS.iter().position(|c| c.0 == f[0]).unwrap();
+        let rest = f.get(3).map_or(String::new(), |r| format!("\t{}", r));
+        records.push((chrom, f[1].parse().unwrap(), f[2].parse().unwrap(), rest));
+    }
+    records.sort_by_key(|r| (r.0, r.1, r.2));
+
+    let mut out = String::new();
+    for (chrom, start, end, rest) in records {
+        writeln!(out, "{}\t{}\t{}{}", CHROMS[chrom].0, start, end, rest).unwrap();
+    }
+    out
+}
+
+/// Write the datasets every case reads, by relative path, into `dir`.
+fn write_datasets(dir: &Path) {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let write = |name: &str, content: &str| fs::write(dir.join(name), content).unwrap();
+
+    let mut genome = String::new();
+    for (chrom, size) in CHROMS {
+        writeln!(genome, "{}\t{}", chrom, size).unwrap();
+    }
+    write("genome.txt", &genome);
+
+    // Zero-length, touching, nested and duplicate records
+    let a_edges = [
+        "chr1\t1000\t1000\tzero\t0\t+",
+        "chr1\t1000\t1100\ttouch1\t5\t+",
+        "chr1\t1100\t1200\ttouch2\t5\t-",
+        "chr1\t3000\t3500\touter\t10\t+",
+        "chr1\t3200\t3300\tinner\t20\t-",
+        "chr2\t500\t600\tdup\t1\t+",
+        "chr2\t500\t600\tdup\t1\t+",
+    ];
+    let a = random_bed(&mut rng, "a", 40, true, &a_edges);
+    write("a.bed", &a);
+    let b_edges = [
+        "chr1\t1100\t1150\tedge\t3\t+",
+        "chr1\t3250\t3260\tcore\t7\t-",
+        "chrX\t0\t5000\twhole\t999\t+",
+    ];
+    write("b.bed", &random_bed(&mut rng, "b", 60, true, &b_edges));
+    write("c.bed", &random_bed(&mut rng, "c", 30, false, &[]));
+
+    let mut lines: Vec<&str> = a.lines().collect();
+    for i in (1..lines.len()).rev() {
+        lines.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+    write("unsorted.bed", &(lines.join("\n") + "\n"));
+
+    let mut table = String::new();
+    for i in (0..40).step_by(3) {
+        writeln!(table, "a{}\tgene{}\t{}", i, i, rng.below(100)).unwrap();
+    }
+    write("table.tsv", &table);
+
+    let mut fasta = String::new();
+    for (chrom, size) in CHROMS {
+        writeln!(fasta, ">{}", chrom).unwrap();
+        let seq: Vec<u8> = (0..size)
+            .map(|pos| match (pos / 1000 % 7, rng.below(4)) {
+                // A stretch of Ns in every seventh kilobase
+                (3, _) => b'N',
+                (_, base) => b"ACGT"[base as usize],
+            })
+            .collect();
+        for line in seq.chunks(60) {
+            fasta.push_str(std::str::from_utf8(line).unwrap());
+            fasta.push('\n');
+        }
+    }
+    write("genome.fa", &fasta);
+
+    // Per-base outputs stay readable on a tiny genome
+    write("tiny.bed", "chr1\t10\t50\nchr1\t30\t80\nchr1\t120\t121\n");
+    write("tiny_genome.txt", "chr1\t150\n");
+}
+
+// =============================================================================
+// Snapshot harness
+// =============================================================================
+
+/// Alternatives for one flag, e.g. `&[NONE, &["-s"]]`.
+type Axis = &'static [&'static [&'static str]];
+
+/// Base arguments of an invocation and the flag axes combined with them.
+type Run = (&'static [&'static str], &'static [Axis]);
+
+/// Every combination of one alternative from each axis, in order.
+fn combinations(axes: &[Axis]) -> Vec<Vec<&'static str>> {
+    axes.iter().fold(vec![Vec::new()], |acc, axis| {
+        acc.iter()
+            .flat_map(|prefix| {
+                axis.iter().map(move |alt| {
+                    let mut args = prefix.clone();
+                    args.extend_from_slice(alt);
+                    args
+                })
+            })
+            .collect()
+    })
+}
+
+/// Run one invocation and render it as a snapshot entry.
+///
+/// Like the other CLI tests this runs the release binary, so snapshots
+/// record what users get.
+fn render(dir: &Path, args: &[&str]) -> String {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let output = Command::new("cargo")
+        .args(["run", "--release", "-q", "--manifest-path", manifest, "--"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run grit");
+
+    let code = output
+        .status
+        .code()
+        .map_or_else(|| "signal".to_string(), |c| c.to_string());
+    let mut entry = format!("$ grit {}\nexit: {}\n", args.join(" "), code);
+    entry.push_str("--- stdout\n");
+    entry.push_str(&String::from_utf8_lossy(&output.stdout));
+    if !output.stderr.is_empty() {
+        entry.push_str("--- stderr\n");
+        entry.push_str(&String::from_utf8_lossy(&output.stderr));
+    }
+    entry.push('\n');
+    entry
+}
+
+/// Run `command` with `base` arguments over all flag combinations of each
+/// axis set and compare with `tests/golden/<name>.snap`.
+fn check(name: &str, runs: &[Run]) {
+    let dir = TempDir::new().unwrap();
+    write_datasets(dir.path());
+
+    let mut actual = String::new();
+    for (base, axes) in runs {
+        for flags in combinations(axes) {
+            let args: Vec<&str> = base.iter().chain(&flags).copied().collect();
+            actual.push_str(&render(dir.path(), &args));
+        }
+    }
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.snap", name));
+    if std::env::var_os("GRIT_UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}; run with GRIT_UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+    if actual != expected {
+        let (line, (want, got)) = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .find(|(_, (want, got))| want != got)
+            .unwrap_or((
+                expected.lines().count().min(actual.lines().count()),
+                ("<end of snapshot>", "<end of output>"),
+            ));
+        // The invocation the first difference belongs to
+        let case = actual
+            .lines()
+            .take(line + 1)
+            .filter(|l| l.starts_with("$ grit "))
+            .last()
+            .unwrap_or("");
+        panic!(
+            "{} differs from {} at line {}\n  in: {}\n  expected: {}\n  actual:   {}\n\
+             If the change is intended, rerun with GRIT_UPDATE_GOLDEN=1 and review the diff.",
+            name,
+            path.display(),
+            line + 1,
+            case,
+            want,
+            got
+        );
+    }
+}
+
+const NONE: &[&str] = &[];
+
+// =============================================================================
+// Commands
+// =============================================================================
+
+#[test]
+fn golden_sort() {
+    check(
+        "sort",
+        &[(
+            &["sort", "-i", "unsorted.bed"],
+            &[&[
+                NONE,
+                &["--sizeA"],
+                &["--sizeD"],
+                &["-r"],
+                &["--chrThenSizeA"],
+                &["-g", "genome.txt"],
+            ]],
+        )],
+    );
+}
+
+#[test]
+fn golden_merge() {
+    check(
+        "merge",
+        &[
+            (
+                &["merge", "-i", "a.bed"],
+                &[
+                    &[NONE, &["-d", "50"]],
+                    &[NONE, &["-s"]],
+                    &[NONE, &["-c"]],
+                    &[NONE, &["--in-memory"]],
+                    &[NONE, &["--no-book-ended"]],
+                ],
+            ),
+            (
+                &["merge", "-i", "a.bed"],
+                &[
+                    &[NONE, &["--id", "coords"], &["--id", "hash"]],
+                    &[NONE, &["--names"]],
+                    &[NONE, &["-s"]],
+                    &[NONE, &["--in-memory"]],
+                ],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn golden_intersect() {
+    check(
+        "intersect",
+        &[
+            (
+                &["intersect", "-a", "a.bed", "-b", "b.bed"],
+                &[
+                    &[
+                        NONE,
+                        &["--wa"],
+                        &["--wb"],
+                        &["--wa", "--wb"],
+                        &["-u"],
+                        &["-v"],
+                        &["-c"],
+                    ],
+                    &[
+                        NONE,
+                        &["-f", "0.5"],
+                        &["-f", "0.5", "-r"],
+                        &["--min-overlap-bp", "50"],
+                    ],
+                    &[NONE, &["--streaming"]],
+                ],
+            ),
+            (
+                &["intersect", "-a", "a.bed", "-b", "b.bed", "--max-hits", "1"],
+                &[&[NONE, &["--wa", "--wb"]], &[NONE, &["--streaming"]]],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn golden_subtract() {
+    check(
+        "subtract",
+        &[(
+            &["subtract", "-a", "a.bed", "-b", "b.bed"],
+            &[
+                &[NONE, &["-A"]],
+                &[
+                    NONE,
+                    &["-f", "0.5"],
+                    &["-f", "0.5", "-r"],
+                    &["--min-overlap-bp", "50"],
+                ],
+                &[NONE, &["--streaming"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_closest() {
+    check(
+        "closest",
+        &[(
+            &["closest", "-a", "a.bed", "-b", "b.bed"],
+            &[
+                &[NONE, &["-d"]],
+                &[NONE, &["--io"], &["--iu"], &["--id"]],
+                &[NONE, &["-t", "first"], &["-t", "last"]],
+                &[NONE, &["--streaming"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_window() {
+    check(
+        "window",
+        &[(
+            &["window", "-a", "a.bed", "-b", "b.bed"],
+            &[
+                &[NONE, &["-w", "200"], &["-l", "100", "-r", "300"]],
+                &[NONE, &["-c"], &["-v"], &["--per-a-summary"]],
+                &[NONE, &["--min-overlap-bp", "50"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_coverage() {
+    check(
+        "coverage",
+        &[
+            (
+                &["coverage", "-a", "a.bed", "-b", "b.bed"],
+                &[&[
+                    NONE,
+                    &["--hist"],
+                    &["--mean"],
+                    &["--mean", "--stddev", "--median"],
+                ]],
+            ),
+            (&["coverage", "-a", "tiny.bed", "-b", "tiny.bed", "-d"], &[]),
+            (
+                &["coverage", "-a", "a.bed", "-b", "b.bed", "--index-a"],
+                &[&[NONE, &["--mean"]]],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn golden_segment() {
+    check(
+        "segment",
+        &[(
+            &["segment", "-a", "a.bed", "-b", "b.bed"],
+            &[&[NONE, &["--breaks", "1,3,5"]]],
+        )],
+    );
+}
+
+#[test]
+fn golden_slop() {
+    check(
+        "slop",
+        &[(
+            &["slop", "-i", "a.bed", "-g", "genome.txt"],
+            &[
+                &[
+                    &["-b", "100"],
+                    &["-l", "50", "-r", "200"],
+                    &["-b", "0.5", "--pct"],
+                ],
+                &[NONE, &["-s"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_modify() {
+    check(
+        "modify",
+        &[(
+            &["modify", "-i", "a.bed"],
+            &[
+                &[NONE, &["--anchor", "5p"], &["--anchor", "3p"]],
+                &[NONE, &["--center", "100"]],
+                &[NONE, &["-g", "genome.txt"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_complement() {
+    check(
+        "complement",
+        &[(
+            &["complement", "-i", "a.bed", "-g", "genome.txt"],
+            &[&[NONE, &["--assume-sorted"], &["--exclude", "c.bed"]]],
+        )],
+    );
+}
+
+#[test]
+fn golden_genomecov() {
+    check(
+        "genomecov",
+        &[
+            (
+                &["genomecov", "-i", "a.bed", "-g", "genome.txt"],
+                &[
+                    &[NONE, &["--bg"], &["--bga"]],
+                    &[NONE, &["--scale", "0.5"]],
+                    &[NONE, &["--streaming"]],
+                ],
+            ),
+            (
+                &["genomecov", "-i", "a.bed", "-g", "genome.txt"],
+                &[
+                    &[&["--normalize", "percentile"], &["--normalize", "zscore"]],
+                    &[NONE, &["--bga"]],
+                    &[NONE, &["--streaming"]],
+                ],
+            ),
+            (
+                &["genomecov", "-i", "tiny.bed", "-g", "tiny_genome.txt", "-d"],
+                &[&[NONE, &["--streaming"]]],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn golden_jaccard() {
+    check(
+        "jaccard",
+        &[(
+            &["jaccard", "-a", "a.bed", "-b", "b.bed"],
+            &[&[NONE, &["-e"], &["-g", "genome.txt"]]],
+        )],
+    );
+}
+
+#[test]
+fn golden_stab() {
+    check(
+        "stab",
+        &[
+            (
+                &[
+                    "stab",
+                    "-i",
+                    "a.bed",
+                    "chr1:5,000",
+                    "chr2:7000-9000",
+                    "chrX:100",
+                    "chr9:1",
+                ],
+                &[&[NONE, &["--build-index"]]],
+            ),
+            (&["stab", "-i", "a.bed", "-r", "c.bed"], &[]),
+        ],
+    );
+}
+
+#[test]
+fn golden_multiinter() {
+    check(
+        "multiinter",
+        &[
+            (
+                &["multiinter", "-i", "a.bed", "b.bed", "c.bed"],
+                &[
+                    &[NONE, &["--cluster"]],
+                    &[NONE, &["--streaming"]],
+                    &[NONE, &["--names", "A", "B", "C"]],
+                ],
+            ),
+            (
+                &["multiinter", "-i", "a.bed", "b.bed", "c.bed", "--sparse"],
+                &[&[NONE, &["--streaming"]]],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn golden_shuffle() {
+    check(
+        "shuffle",
+        &[(
+            &["shuffle", "-i", "a.bed", "-g", "genome.txt"],
+            &[
+                &[NONE, &["--chrom"]],
+                &[NONE, &["--seed", "7"]],
+                &[NONE, &["--exclude", "c.bed"]],
+            ],
+        )],
+    );
+}
+
+#[test]
+fn golden_join() {
+    check(
+        "join",
+        &[(
+            &["join", "-a", "a.bed", "-b", "table.tsv"],
+            &[&[NONE, &["--type", "left"]]],
+        )],
+    );
+}
+
+#[test]
+fn golden_covariates() {
+    check(
+        "covariates",
+        &[(&["covariates", "-i", "a.bed", "--fasta", "genome.fa"], &[])],
+    );
+}
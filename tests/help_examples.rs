@@ -0,0 +1,79 @@
+//! Runs the examples embedded in the `grit help <topic>` pages.
+//!
+//! Every `$ grit ...` line of every topic is run in a scratch directory
+//! holding the topic example files, and its stdout must match the output
+//! printed under it, so the help text cannot drift from what grit does.
+
+use grit_genomics::help_topics::{EXAMPLE_FILES, TOPICS};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_grit(dir: &Path, args: &[&str]) -> Output {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    Command::new("cargo")
+        .args(["run", "--release", "-q", "--manifest-path", manifest, "--"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run grit")
+}
+
+#[test]
+fn test_help_topic_examples() {
+    let dir = TempDir::new().unwrap();
+    for (name, contents) in EXAMPLE_FILES {
+        fs::write(dir.path().join(name), contents).unwrap();
+    }
+
+    let mut failures = Vec::new();
+    for topic in TOPICS {
+        let examples = topic.examples();
+        assert!(!examples.is_empty(), "topic {} has no examples", topic.name);
+        for example in examples {
+            let args: Vec<&str> = example.args.iter().map(String::as_str).collect();
+            let output = run_grit(dir.path(), &args);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if !output.status.success() || stdout != example.stdout {
+                failures.push(format!(
+                    "[{}] $ grit {}\n--- expected\n{}--- got\n{}{}",
+                    topic.name,
+                    args.join(" "),
+                    example.stdout,
+                    stdout,
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_help_command() {
+    let dir = TempDir::new().unwrap();
+
+    let output = run_grit(dir.path(), &["help"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Usage: grit"));
+    for topic in TOPICS {
+        assert!(stdout.contains(topic.summary));
+    }
+
+    let output = run_grit(dir.path(), &["help", "streaming"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("STREAMING\n"));
+
+    // Command names and their aliases show the command's --help
+    let output = run_grit(dir.path(), &["help", "mergeBed"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Usage: grit merge"));
+
+    let output = run_grit(dir.path(), &["help", "sorted"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no help topic or command named 'sorted'"));
+    assert!(stderr.contains("sorting, streaming, bedtools, genome"));
+}